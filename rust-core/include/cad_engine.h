@@ -0,0 +1,72 @@
+#ifndef CAD_ENGINE_H
+#define CAD_ENGINE_H
+
+/* Generated by cbindgen from src/ffi.rs; do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum CadStatus {
+  CAD_STATUS_OK = 0,
+  CAD_STATUS_NULL_POINTER = 1,
+  CAD_STATUS_INVALID_HANDLE = 2,
+  CAD_STATUS_INVALID_INPUT = 3,
+  CAD_STATUS_UNKNOWN_SHAPE = 4,
+  CAD_STATUS_INVALID_GEOMETRY = 5,
+  CAD_STATUS_BUFFER_TOO_SMALL = 6,
+  CAD_STATUS_SERIALIZATION = 7,
+  CAD_STATUS_PANIC = 8,
+  CAD_STATUS_FAILED = 9,
+} CadStatus;
+
+typedef struct CadBuffer {
+  uint8_t *data;
+  size_t len;
+} CadBuffer;
+
+typedef uint64_t CadDocumentHandle;
+
+typedef uint32_t ShapeId;
+
+const char *cad_status_name(enum CadStatus status);
+
+const char *cad_last_error_message(void);
+
+enum CadStatus cad_document_new(CadDocumentHandle *out);
+
+enum CadStatus cad_document_free(CadDocumentHandle handle);
+
+enum CadStatus cad_document_shape_count(CadDocumentHandle handle, size_t *out);
+
+enum CadStatus cad_document_add_polygon(CadDocumentHandle handle,
+                                        const double *coords,
+                                        size_t len,
+                                        ShapeId *out_id);
+
+enum CadStatus cad_shape_area(CadDocumentHandle handle, ShapeId id, double *out);
+
+enum CadStatus cad_shape_bbox(CadDocumentHandle handle, ShapeId id, double *out);
+
+enum CadStatus cad_shape_set_transform(CadDocumentHandle handle, ShapeId id, const double *matrix);
+
+enum CadStatus cad_shapes_boolean(CadDocumentHandle handle,
+                                  ShapeId a,
+                                  ShapeId b,
+                                  uint32_t op,
+                                  ShapeId *out_ids,
+                                  size_t capacity,
+                                  size_t *out_count);
+
+enum CadStatus cad_document_export(CadDocumentHandle handle,
+                                   const char *format,
+                                   int32_t precision,
+                                   struct CadBuffer *out);
+
+enum CadStatus cad_document_to_bytes(CadDocumentHandle handle, struct CadBuffer *out);
+
+enum CadStatus cad_buffer_free(struct CadBuffer *buffer);
+
+#endif /* CAD_ENGINE_H */
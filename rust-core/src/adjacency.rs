@@ -0,0 +1,174 @@
+use wasm_bindgen::prelude::*;
+
+use crate::hittest::edges;
+use crate::{js_error, BoundingBox, CadError, Point, Polygon, QuadTree, Segment};
+
+// Which regions border which, for map-style colouring. Two regions border
+// each other when their outlines run together, within the tolerance, for
+// longer than the tolerance; meeting at a corner is not enough.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AdjacencyGraph {
+    // Per region, its neighbours in ascending order with the boundary length
+    // they share
+    neighbors: Vec<Vec<(usize, f64)>>,
+}
+
+// Length of `f` lying along `e`, counting both ends of `f` within
+// `tolerance` of `e`'s line as on it
+fn shared_length(e: &Segment, f: &Segment, tolerance: f64) -> f64 {
+    let len = e.length();
+    if len <= tolerance {
+        return 0.0;
+    }
+    let off_line = |p: &Point| ((e.b.x - e.a.x) * (p.y - e.a.y) - (e.b.y - e.a.y) * (p.x - e.a.x)).abs() / len;
+    if off_line(&f.a) > tolerance || off_line(&f.b) > tolerance {
+        return 0.0;
+    }
+    let (t0, t1) = (e.parameter(&f.a), e.parameter(&f.b));
+    (t0.max(t1).min(1.0) - t0.min(t1).max(0.0)).max(0.0) * len
+}
+
+fn padded(b: &BoundingBox, by: f64) -> BoundingBox {
+    BoundingBox::new(Point::new(b.min.x - by, b.min.y - by), Point::new(b.max.x + by, b.max.y + by))
+}
+
+pub fn adjacency_graph(polygons: &[Polygon], tolerance: f64) -> AdjacencyGraph {
+    let tolerance = tolerance.max(0.0);
+    let boxes: Vec<(u32, BoundingBox)> = polygons.iter().enumerate().map(|(i, p)| (i as u32, padded(&p.bounding_box(), tolerance))).collect();
+    let tree = QuadTree::bulk_build(&boxes);
+    let mut neighbors = vec![Vec::new(); polygons.len()];
+    for (i, a) in polygons.iter().enumerate() {
+        for j in tree.query(&boxes[i].1).into_iter().map(|j| j as usize).filter(|&j| j > i) {
+            let b = &polygons[j];
+            let shared: f64 = edges(&a.vertices).map(|e| edges(&b.vertices).map(|f| shared_length(&e, &f, tolerance)).sum::<f64>()).sum();
+            if shared > tolerance {
+                neighbors[i].push((j, shared));
+                neighbors[j].push((i, shared));
+            }
+        }
+    }
+    for list in &mut neighbors {
+        list.sort_by_key(|&(j, _)| j);
+    }
+    AdjacencyGraph { neighbors }
+}
+
+impl AdjacencyGraph {
+    pub fn len(&self) -> usize {
+        self.neighbors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.neighbors.is_empty()
+    }
+
+    // Empty for an index past the end
+    pub fn neighbors(&self, i: usize) -> Vec<usize> {
+        self.neighbors.get(i).map_or_else(Vec::new, |list| list.iter().map(|&(j, _)| j).collect())
+    }
+
+    // 0 for regions that don't border each other
+    pub fn shared_boundary_length(&self, i: usize, j: usize) -> f64 {
+        self.neighbors.get(i).and_then(|list| list.iter().find(|&&(k, _)| k == j)).map_or(0.0, |&(_, len)| len)
+    }
+
+    // A colour index below `max_colors` per region, with no two neighbours
+    // sharing one. Regions are coloured most-connected first, each taking
+    // the lowest colour its coloured neighbours leave free; greedy, so it
+    // can fail where a cleverer assignment would fit.
+    pub fn greedy_coloring(&self, max_colors: u8) -> Result<Vec<u8>, CadError> {
+        let mut order: Vec<usize> = (0..self.len()).collect();
+        order.sort_by_key(|&i| std::cmp::Reverse(self.neighbors[i].len()));
+        let mut colors: Vec<Option<u8>> = vec![None; self.len()];
+        for i in order {
+            let mut used = [false; 256];
+            for &(j, _) in &self.neighbors[i] {
+                if let Some(c) = colors[j] {
+                    used[c as usize] = true;
+                }
+            }
+            match (0..max_colors).find(|&c| !used[c as usize]) {
+                Some(c) => colors[i] = Some(c),
+                None => return Err(CadError::InvalidInput(format!("region {} needs more than {} colors", i, max_colors))),
+            }
+        }
+        Ok(colors.into_iter().flatten().collect())
+    }
+}
+
+#[wasm_bindgen]
+impl AdjacencyGraph {
+    #[wasm_bindgen(js_name = len)]
+    pub fn len_js(&self) -> usize {
+        self.len()
+    }
+
+    #[wasm_bindgen(js_name = neighbors)]
+    pub fn neighbors_js(&self, i: usize) -> Vec<usize> {
+        self.neighbors(i)
+    }
+
+    #[wasm_bindgen(js_name = shared_boundary_length)]
+    pub fn shared_boundary_length_js(&self, i: usize, j: usize) -> f64 {
+        self.shared_boundary_length(i, j)
+    }
+
+    // Palette index per region
+    #[wasm_bindgen(js_name = greedy_coloring)]
+    pub fn greedy_coloring_js(&self, max_colors: u8) -> Result<Vec<u8>, JsValue> {
+        self.greedy_coloring(max_colors).map_err(js_error)
+    }
+}
+
+// Array of polygons, each an array of {x, y}
+#[wasm_bindgen(js_name = adjacency_graph)]
+pub fn adjacency_graph_js(polygons: &JsValue, tolerance: f64) -> Result<AdjacencyGraph, JsValue> {
+    let polygons: Vec<Polygon> = crate::from_js(polygons)?;
+    Ok(adjacency_graph(&polygons, tolerance))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(x: f64, y: f64) -> Polygon {
+        Polygon::new(vec![Point::new(x, y), Point::new(x + 1.0, y), Point::new(x + 1.0, y + 1.0), Point::new(x, y + 1.0)])
+    }
+
+    #[test]
+    fn test_grid_is_rook_adjacent_and_colors_cleanly() {
+        // Row-major 3×3 grid, each cell nudged a little off the lattice
+        let grid: Vec<Polygon> = (0..9).map(|i| {
+            let wobble = (i % 3) as f64 * 1e-4;
+            square((i % 3) as f64 + wobble * 0.5, (i / 3) as f64 - wobble)
+        }).collect();
+        let graph = adjacency_graph(&grid, 1e-3);
+        for i in 0..9usize {
+            let (row, col) = (i / 3, i % 3);
+            let expected: Vec<usize> = (0..9usize).filter(|&j| (j / 3).abs_diff(row) + (j % 3).abs_diff(col) == 1).collect();
+            assert_eq!(graph.neighbors(i), expected, "cell {i}");
+        }
+        assert!((graph.shared_boundary_length(0, 1) - 1.0).abs() < 1e-3);
+        assert_eq!(graph.shared_boundary_length(0, 4), 0.0);
+
+        let colors = graph.greedy_coloring(4).unwrap();
+        assert_eq!(colors.len(), 9);
+        assert!(colors.iter().all(|&c| c < 4));
+        for i in 0..9 {
+            assert!(graph.neighbors(i).iter().all(|&j| colors[j] != colors[i]), "{colors:?}");
+        }
+        assert!(matches!(graph.greedy_coloring(1), Err(CadError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_corner_contact_is_not_adjacency() {
+        let graph = adjacency_graph(&[square(0.0, 0.0), square(1.0, 1.0), square(1.0, -0.5)], 1e-6);
+        assert!(graph.neighbors(1).iter().all(|&j| j != 0));
+        assert_eq!(graph.shared_boundary_length(0, 1), 0.0);
+        // Half an edge in common is enough
+        assert_eq!(graph.neighbors(2), vec![0]);
+        assert!((graph.shared_boundary_length(2, 0) - 0.5).abs() < 1e-9);
+        assert_eq!(graph.greedy_coloring(2).unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,145 @@
+use std::f64::consts::{PI, TAU};
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+// Angle held in radians, so that the unit is settled where the value is
+// made rather than wherever it's used. Serializes as its radians.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct Angle(f64);
+
+impl Angle {
+    pub fn from_radians(radians: f64) -> Angle {
+        Angle(radians)
+    }
+
+    // Divides before multiplying by pi, so whole fractions of a half turn
+    // come out exact: 90 degrees is exactly FRAC_PI_2
+    pub fn from_degrees(degrees: f64) -> Angle {
+        Angle(degrees / 180.0 * PI)
+    }
+
+    pub fn radians(&self) -> f64 {
+        self.0
+    }
+
+    pub fn to_degrees(&self) -> f64 {
+        self.0 / PI * 180.0
+    }
+
+    // The same direction in (-pi, pi]
+    pub fn normalized(&self) -> Angle {
+        let turned = (self.0 + PI).rem_euclid(TAU) - PI;
+        Angle(if turned <= -PI { PI } else { turned })
+    }
+
+    // Smallest turn, either way, taking this direction onto `other`
+    pub fn shortest_to(&self, other: Angle) -> Angle {
+        (other - *self).normalized()
+    }
+
+    // Size of that turn, in [0, pi]
+    pub fn difference(&self, other: Angle) -> Angle {
+        Angle(self.shortest_to(other).0.abs())
+    }
+
+    // Degrees, minutes and seconds, as 12°30′05.0″ with `precision` digits
+    // on the seconds. Rounding carries into minutes and degrees.
+    pub fn format_dms(&self, precision: usize) -> String {
+        let degrees = self.to_degrees();
+        let sign = if degrees < 0.0 { "-" } else { "" };
+        let scale = 10f64.powi(precision as i32);
+        let total = (degrees.abs() * 3600.0 * scale).round() / scale;
+        let (d, rest) = ((total / 3600.0).floor(), total % 3600.0);
+        let (m, s) = ((rest / 60.0).floor(), rest % 60.0);
+        let width = if precision > 0 { precision + 3 } else { 2 };
+        format!("{}{}°{:02}′{:0w$.p$}″", sign, d, m, s, w = width, p = precision)
+    }
+}
+
+impl Add for Angle {
+    type Output = Angle;
+
+    fn add(self, other: Angle) -> Angle {
+        Angle(self.0 + other.0)
+    }
+}
+
+impl Sub for Angle {
+    type Output = Angle;
+
+    fn sub(self, other: Angle) -> Angle {
+        Angle(self.0 - other.0)
+    }
+}
+
+impl Neg for Angle {
+    type Output = Angle;
+
+    fn neg(self) -> Angle {
+        Angle(-self.0)
+    }
+}
+
+impl Mul<f64> for Angle {
+    type Output = Angle;
+
+    fn mul(self, k: f64) -> Angle {
+        Angle(self.0 * k)
+    }
+}
+
+impl Div<f64> for Angle {
+    type Output = Angle;
+
+    fn div(self, k: f64) -> Angle {
+        Angle(self.0 / k)
+    }
+}
+
+#[wasm_bindgen]
+pub fn degrees_to_radians(degrees: f64) -> f64 {
+    Angle::from_degrees(degrees).radians()
+}
+
+#[wasm_bindgen]
+pub fn radians_to_degrees(radians: f64) -> f64 {
+    Angle::from_radians(radians).to_degrees()
+}
+
+#[wasm_bindgen]
+pub fn format_angle_dms(degrees: f64, precision: usize) -> String {
+    Angle::from_degrees(degrees).format_dms(precision)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalization() {
+        assert!((Angle::from_degrees(720.5).normalized().to_degrees() - 0.5).abs() < 1e-9);
+        assert!((Angle::from_degrees(-190.0).normalized().to_degrees() - 170.0).abs() < 1e-9);
+        assert_eq!(Angle::from_degrees(-180.0).normalized().radians(), PI);
+        assert_eq!(Angle::from_degrees(540.0).normalized().radians(), PI);
+        assert_eq!(Angle::from_degrees(90.0).radians(), std::f64::consts::FRAC_PI_2);
+
+        // Across the seam the short way round is 20 degrees, not 340
+        let (a, b) = (Angle::from_degrees(170.0), Angle::from_degrees(-170.0));
+        assert!((a.shortest_to(b).to_degrees() - 20.0).abs() < 1e-9);
+        assert!((b.shortest_to(a).to_degrees() + 20.0).abs() < 1e-9);
+        assert!((a.difference(b).to_degrees() - 20.0).abs() < 1e-9);
+        assert!(((a * 2.0) / 4.0 - Angle::from_degrees(85.0)).radians().abs() < 1e-12);
+        assert_eq!(-a + a, Angle::default());
+    }
+
+    #[test]
+    fn test_degrees_minutes_seconds() {
+        assert_eq!(Angle::from_degrees(12.5 + 5.0 / 3600.0).format_dms(1), "12°30′05.0″");
+        assert_eq!(Angle::from_degrees(90.0).format_dms(0), "90°00′00″");
+        // 59.99...″ rounds up into the next minute and degree
+        assert_eq!(Angle::from_degrees(29.0 + 59.0 / 60.0 + 59.996 / 3600.0).format_dms(2), "30°00′00.00″");
+        assert_eq!(Angle::from_degrees(-0.25).format_dms(0), "-0°15′00″");
+    }
+}
@@ -0,0 +1,348 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use std::f64::consts::{FRAC_PI_2, TAU};
+
+use crate::{Angle, BoundingBox, CadError, Point, Polyline, EPSILON};
+
+// Circular arc starting at `start_angle` (radians from +x) and turning by
+// `sweep`; positive sweeps run counter-clockwise
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Arc {
+    pub center: Point,
+    pub radius: f64,
+    pub start_angle: f64,
+    pub sweep: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ChordOrAngle {
+    Chord(f64),
+    Angle(f64),
+}
+
+fn check_radius(radius: f64) -> Result<(), CadError> {
+    if radius.is_finite() && radius > 0.0 {
+        Ok(())
+    } else {
+        Err(CadError::InvalidInput(format!("radius must be positive, got {}", radius)))
+    }
+}
+
+fn check_angle(angle: f64) -> Result<(), CadError> {
+    if (0.0..=std::f64::consts::TAU).contains(&angle) {
+        Ok(())
+    } else {
+        Err(CadError::InvalidInput(format!("angle must be within [0, 2π], got {}", angle)))
+    }
+}
+
+fn check_chord(radius: f64, chord: f64) -> Result<(), CadError> {
+    if chord >= 0.0 && chord <= 2.0 * radius + EPSILON {
+        Ok(())
+    } else {
+        Err(CadError::InvalidInput(format!("chord {} does not fit a circle of radius {}", chord, radius)))
+    }
+}
+
+pub fn chord_length(radius: f64, angle: f64) -> Result<f64, CadError> {
+    check_radius(radius)?;
+    check_angle(angle)?;
+    Ok(2.0 * radius * (angle / 2.0).sin())
+}
+
+pub fn circular_sector_area(radius: f64, angle: f64) -> Result<f64, CadError> {
+    check_radius(radius)?;
+    check_angle(angle)?;
+    Ok(radius * radius * angle / 2.0)
+}
+
+// Area between an arc and its chord. A chord always gives the minor segment;
+// pass the angle to get a major one.
+pub fn circular_segment_area(radius: f64, chord_or_angle: ChordOrAngle) -> Result<f64, CadError> {
+    check_radius(radius)?;
+    let angle = match chord_or_angle {
+        ChordOrAngle::Angle(angle) => angle,
+        ChordOrAngle::Chord(chord) => {
+            check_chord(radius, chord)?;
+            2.0 * (chord / (2.0 * radius)).min(1.0).asin()
+        }
+    };
+    check_angle(angle)?;
+    Ok(radius * radius * (angle - angle.sin()) / 2.0)
+}
+
+// Height of the minor arc over its chord
+pub fn sagitta(radius: f64, chord: f64) -> Result<f64, CadError> {
+    check_radius(radius)?;
+    check_chord(radius, chord)?;
+    Ok(radius - (radius * radius - chord * chord / 4.0).max(0.0).sqrt())
+}
+
+// Central angle of the arc with the given height; heights above the radius
+// give major arcs
+pub fn angle_from_sagitta(radius: f64, sagitta: f64) -> Result<f64, CadError> {
+    check_radius(radius)?;
+    if sagitta < 0.0 || sagitta > 2.0 * radius + EPSILON {
+        return Err(CadError::InvalidInput(format!("sagitta {} exceeds the diameter {}", sagitta, 2.0 * radius)));
+    }
+    Ok(2.0 * ((radius - sagitta) / radius).clamp(-1.0, 1.0).acos())
+}
+
+// Arc from `p1` to `p2` bulging `sagitta` away from the chord. Positive
+// sagittas give counter-clockwise arcs, matching the sign of a DXF bulge.
+pub fn arc_from_chord_and_sagitta(p1: &Point, p2: &Point, sagitta: f64) -> Result<Arc, CadError> {
+    let (dx, dy) = (p2.x - p1.x, p2.y - p1.y);
+    let chord = dx.hypot(dy);
+    if chord < EPSILON {
+        return Err(CadError::InvalidInput("arc endpoints coincide".to_string()));
+    }
+    if sagitta.abs() < EPSILON {
+        return Err(CadError::InvalidInput("zero sagitta describes a straight segment".to_string()));
+    }
+
+    let radius = (chord * chord / 4.0 + sagitta * sagitta) / (2.0 * sagitta.abs());
+    // Left normal of the chord direction
+    let (nx, ny) = (-dy / chord, dx / chord);
+    let shift = sagitta.signum() * radius - sagitta;
+    let center = Point::new((p1.x + p2.x) / 2.0 + nx * shift, (p1.y + p2.y) / 2.0 + ny * shift);
+
+    Ok(Arc {
+        center,
+        radius,
+        start_angle: (p1.y - center.y).atan2(p1.x - center.x),
+        sweep: 4.0 * (2.0 * sagitta / chord).atan(),
+    })
+}
+
+impl Arc {
+    // Arc from `start_angle` round to `end_angle`, counter-clockwise or
+    // clockwise, passing through zero if it has to. Equal angles make a full
+    // circle.
+    pub fn from_angles(center: Point, radius: f64, start_angle: f64, end_angle: f64, counter_clockwise: bool) -> Arc {
+        let turn = if counter_clockwise { end_angle - start_angle } else { start_angle - end_angle };
+        let mut sweep = turn.rem_euclid(TAU);
+        if sweep < EPSILON {
+            sweep = TAU;
+        }
+        Arc { center, radius, start_angle, sweep: if counter_clockwise { sweep } else { -sweep } }
+    }
+
+    // Arc with its start and sweep in degrees
+    pub fn from_degrees(center: Point, radius: f64, start_degrees: f64, sweep_degrees: f64) -> Arc {
+        Arc { center, radius, start_angle: Angle::from_degrees(start_degrees).radians(), sweep: Angle::from_degrees(sweep_degrees).radians() }
+    }
+
+    // DXF polyline bulge: tan of a quarter of the sweep
+    pub fn from_bulge(p1: &Point, p2: &Point, bulge: f64) -> Result<Arc, CadError> {
+        let chord = (p2.x - p1.x).hypot(p2.y - p1.y);
+        arc_from_chord_and_sagitta(p1, p2, bulge * chord / 2.0)
+    }
+
+    pub fn bulge(&self) -> f64 {
+        (self.sweep / 4.0).tan()
+    }
+
+    pub fn point_at(&self, angle: f64) -> Point {
+        Point::new(self.center.x + self.radius * angle.cos(), self.center.y + self.radius * angle.sin())
+    }
+
+    pub fn start_point(&self) -> Point {
+        self.point_at(self.start_angle)
+    }
+
+    pub fn end_point(&self) -> Point {
+        self.point_at(self.start_angle + self.sweep)
+    }
+
+    pub fn length(&self) -> f64 {
+        self.radius * self.sweep.abs()
+    }
+
+    // Open polyline through `segments` equal steps of the arc, ends included
+    pub fn to_polyline(&self, segments: usize) -> Polyline {
+        let segments = segments.max(1);
+        Polyline::new((0..=segments).map(|i| self.point_at(self.start_angle + self.sweep * i as f64 / segments as f64)).collect(), false)
+    }
+
+    // Tight box: the ends plus any of the circle's extreme points the arc
+    // passes
+    pub fn bounding_box(&self) -> BoundingBox {
+        let end = self.start_angle + self.sweep;
+        let (lo, hi) = (self.start_angle.min(end), self.start_angle.max(end));
+        let mut points = vec![self.start_point(), self.end_point()];
+        let mut k = (lo / FRAC_PI_2).ceil();
+        while k * FRAC_PI_2 <= hi && points.len() < 6 {
+            points.push(self.point_at(k * FRAC_PI_2));
+            k += 1.0;
+        }
+        BoundingBox::from_points(&points)
+    }
+}
+
+fn js(result: Result<f64, CadError>) -> Result<f64, JsValue> {
+    result.map_err(crate::js_error)
+}
+
+#[wasm_bindgen(js_name = chord_length)]
+pub fn chord_length_js(radius: f64, angle: f64) -> Result<f64, JsValue> {
+    js(chord_length(radius, angle))
+}
+
+#[wasm_bindgen(js_name = circular_sector_area)]
+pub fn circular_sector_area_js(radius: f64, angle: f64) -> Result<f64, JsValue> {
+    js(circular_sector_area(radius, angle))
+}
+
+#[wasm_bindgen]
+pub fn circular_segment_area_from_angle(radius: f64, angle: f64) -> Result<f64, JsValue> {
+    js(circular_segment_area(radius, ChordOrAngle::Angle(angle)))
+}
+
+#[wasm_bindgen]
+pub fn circular_segment_area_from_chord(radius: f64, chord: f64) -> Result<f64, JsValue> {
+    js(circular_segment_area(radius, ChordOrAngle::Chord(chord)))
+}
+
+#[wasm_bindgen(js_name = sagitta)]
+pub fn sagitta_js(radius: f64, chord: f64) -> Result<f64, JsValue> {
+    js(sagitta(radius, chord))
+}
+
+#[wasm_bindgen(js_name = angle_from_sagitta)]
+pub fn angle_from_sagitta_js(radius: f64, sagitta: f64) -> Result<f64, JsValue> {
+    js(angle_from_sagitta(radius, sagitta))
+}
+
+// { center, radius, start_angle, sweep } from angles in radians or in
+// degrees; the result is always in radians
+#[wasm_bindgen]
+pub fn arc_from_radians(cx: f64, cy: f64, radius: f64, start_radians: f64, sweep_radians: f64) -> Result<JsValue, JsValue> {
+    check_radius(radius).map_err(crate::js_error)?;
+    let arc = Arc { center: Point::new(cx, cy), radius, start_angle: start_radians, sweep: sweep_radians };
+    crate::to_js(&arc)
+}
+
+#[wasm_bindgen]
+pub fn arc_from_degrees(cx: f64, cy: f64, radius: f64, start_degrees: f64, sweep_degrees: f64) -> Result<JsValue, JsValue> {
+    check_radius(radius).map_err(crate::js_error)?;
+    crate::to_js(&Arc::from_degrees(Point::new(cx, cy), radius, start_degrees, sweep_degrees))
+}
+
+// Arc from start and end angles in radians; see `Arc::from_angles`
+#[wasm_bindgen]
+pub fn arc_from_angles(cx: f64, cy: f64, radius: f64, start_angle: f64, end_angle: f64, counter_clockwise: bool) -> Result<JsValue, JsValue> {
+    check_radius(radius).map_err(crate::js_error)?;
+    crate::to_js(&Arc::from_angles(Point::new(cx, cy), radius, start_angle, end_angle, counter_clockwise))
+}
+
+// Arcs from JS have their angles in the caller's convention
+fn arc_from(arc: &JsValue) -> Result<Arc, JsValue> {
+    Ok(crate::from_js::<Arc>(arc)?.user())
+}
+
+#[wasm_bindgen]
+pub fn arc_length(arc: &JsValue) -> Result<f64, JsValue> {
+    Ok(arc_from(arc)?.length())
+}
+
+#[wasm_bindgen]
+pub fn arc_to_polyline(arc: &JsValue, segments: usize) -> Result<JsValue, JsValue> {
+    crate::to_js(&arc_from(arc)?.to_polyline(segments))
+}
+
+#[wasm_bindgen]
+pub fn arc_bounding_box(arc: &JsValue) -> Result<JsValue, JsValue> {
+    crate::to_js(&arc_from(arc)?.bounding_box())
+}
+
+#[wasm_bindgen(js_name = arc_from_chord_and_sagitta)]
+pub fn arc_from_chord_and_sagitta_js(x1: f64, y1: f64, x2: f64, y2: f64, sagitta: f64) -> Result<JsValue, JsValue> {
+    let arc = arc_from_chord_and_sagitta(&Point::new(x1, y1), &Point::new(x2, y2), sagitta)
+        .map_err(crate::js_error)?;
+    crate::to_js(&arc.user())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::{FRAC_PI_2, PI, SQRT_2};
+
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-12
+    }
+
+    #[test]
+    fn test_half_and_quarter_circle_closed_forms() {
+        let r = 3.0;
+        assert!(close(chord_length(r, PI).unwrap(), 2.0 * r));
+        assert!(close(circular_sector_area(r, PI).unwrap(), PI * r * r / 2.0));
+        assert!(close(circular_segment_area(r, ChordOrAngle::Angle(PI)).unwrap(), PI * r * r / 2.0));
+        assert!(close(circular_segment_area(r, ChordOrAngle::Chord(2.0 * r)).unwrap(), PI * r * r / 2.0));
+        assert!(close(sagitta(r, 2.0 * r).unwrap(), r));
+        assert!(close(angle_from_sagitta(r, r).unwrap(), PI));
+
+        let chord = r * SQRT_2;
+        assert!(close(chord_length(r, FRAC_PI_2).unwrap(), chord));
+        assert!(close(circular_sector_area(r, FRAC_PI_2).unwrap(), PI * r * r / 4.0));
+        assert!(close(circular_segment_area(r, ChordOrAngle::Chord(chord)).unwrap(), r * r * (FRAC_PI_2 - 1.0) / 2.0));
+        assert!(close(sagitta(r, chord).unwrap(), r * (1.0 - SQRT_2 / 2.0)));
+        assert!(close(angle_from_sagitta(r, r * (1.0 - SQRT_2 / 2.0)).unwrap(), FRAC_PI_2));
+    }
+
+    #[test]
+    fn test_bulge_round_trip() {
+        let (p1, p2) = (Point::new(1.0, 2.0), Point::new(4.0, -1.0));
+        for bulge in [0.25, -0.5, 1.0, 2.5, -3.0] {
+            let arc = Arc::from_bulge(&p1, &p2, bulge).unwrap();
+            assert!((arc.bulge() - bulge).abs() < 1e-12);
+            let (s, e) = (arc.start_point(), arc.end_point());
+            assert!((s.x - p1.x).abs() < 1e-12 && (s.y - p1.y).abs() < 1e-12);
+            assert!((e.x - p2.x).abs() < 1e-12 && (e.y - p2.y).abs() < 1e-12);
+        }
+
+        // A unit bulge is a half circle running counter-clockwise
+        let half = arc_from_chord_and_sagitta(&Point::new(1.0, 0.0), &Point::new(-1.0, 0.0), 1.0).unwrap();
+        assert!(close(half.radius, 1.0) && close(half.sweep, PI));
+        assert!(close(half.point_at(half.start_angle + FRAC_PI_2).y, 1.0));
+    }
+
+    #[test]
+    fn test_arcs_from_angles_wrap_and_wind() {
+        let c = Point::new(1.0, 2.0);
+        // 350° round to 10° the short way through zero, and the long way back
+        let through_zero = Arc::from_angles(c, 2.0, Angle::from_degrees(350.0).radians(), Angle::from_degrees(10.0).radians(), true);
+        assert!(close(through_zero.sweep, Angle::from_degrees(20.0).radians()));
+        let long_way = Arc::from_angles(c, 2.0, Angle::from_degrees(350.0).radians(), Angle::from_degrees(10.0).radians(), false);
+        assert!(close(long_way.sweep, -Angle::from_degrees(340.0).radians()));
+        assert!(close(through_zero.length() + long_way.length(), 4.0 * PI));
+        assert!(close(Arc::from_angles(c, 2.0, 1.0, 1.0, true).sweep, 2.0 * PI));
+
+        // The short arc crosses +x, so its box reaches x = 3; the long one
+        // passes every other extreme
+        let b = through_zero.bounding_box();
+        assert!(close(b.max.x, 3.0) && close(b.max.y - b.min.y, 4.0 * Angle::from_degrees(10.0).radians().sin()));
+        let b = long_way.bounding_box();
+        assert!(close(b.min.x, -1.0) && close(b.min.y, 0.0) && close(b.max.y, 4.0));
+        assert!(b.max.x < 3.0);
+
+        let polyline = long_way.to_polyline(34);
+        assert_eq!(polyline.points.len(), 35);
+        assert!(!polyline.closed);
+        let (first, last) = (polyline.points[0], polyline.points[34]);
+        assert!(close(first.x, through_zero.start_point().x) && close(last.y, through_zero.end_point().y));
+        assert!(polyline.length() < long_way.length() && polyline.length() > 0.99 * long_way.length());
+    }
+
+    #[test]
+    fn test_domain_errors() {
+        assert!(sagitta(1.0, 2.5).is_err());
+        assert!(angle_from_sagitta(1.0, 2.5).is_err());
+        assert!(circular_segment_area(1.0, ChordOrAngle::Chord(3.0)).is_err());
+        assert!(chord_length(-1.0, 1.0).is_err());
+        assert!(circular_sector_area(1.0, 7.0).is_err());
+        assert!(arc_from_chord_and_sagitta(&Point::new(0.0, 0.0), &Point::new(1.0, 0.0), 0.0).is_err());
+        assert!(arc_from_chord_and_sagitta(&Point::new(1.0, 1.0), &Point::new(1.0, 1.0), 0.5).is_err());
+    }
+}
@@ -0,0 +1,516 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use wasm_bindgen::prelude::*;
+
+use crate::predicates::{orient2d, point_in_ring, ring_signed_area};
+use crate::{Point, Polygon, PolygonWithHoles, Segment, EPSILON};
+
+fn cross(ax: f64, ay: f64, bx: f64, by: f64) -> f64 {
+    ax * by - ay * bx
+}
+
+fn distance(a: &Point, b: &Point) -> f64 {
+    (b.x - a.x).hypot(b.y - a.y)
+}
+
+// Planar arrangement of a segment soup: segments are split wherever they
+// cross or touch, and coincident points are merged into one vertex
+pub(crate) struct Arrangement {
+    pub(crate) vertices: Vec<Point>,
+    // Undirected edges as (lower, higher) vertex index, without duplicates
+    pub(crate) edges: Vec<(usize, usize)>,
+}
+
+// Bounded or unbounded boundary cycle traced with its face on the left;
+// bounded faces come out counter-clockwise with positive area
+pub(crate) struct FaceCycle {
+    pub(crate) indices: Vec<usize>,
+    pub(crate) ring: Vec<Point>,
+    pub(crate) area: f64,
+    // Connected component of the arrangement the cycle belongs to
+    pub(crate) component: usize,
+}
+
+struct VertexGrid {
+    cell: f64,
+    eps: f64,
+    cells: HashMap<(i64, i64), Vec<usize>>,
+}
+
+impl VertexGrid {
+    fn key(&self, p: &Point) -> (i64, i64) {
+        ((p.x / self.cell).floor() as i64, (p.y / self.cell).floor() as i64)
+    }
+
+    fn find_or_insert(&mut self, vertices: &mut Vec<Point>, p: Point) -> usize {
+        let (kx, ky) = self.key(&p);
+        for cx in kx - 1..=kx + 1 {
+            for cy in ky - 1..=ky + 1 {
+                if let Some(ids) = self.cells.get(&(cx, cy)) {
+                    if let Some(&i) = ids.iter().find(|&&i| distance(&vertices[i], &p) <= self.eps) {
+                        return i;
+                    }
+                }
+            }
+        }
+        vertices.push(p);
+        self.cells.entry((kx, ky)).or_default().push(vertices.len() - 1);
+        vertices.len() - 1
+    }
+}
+
+// Points closer than this, as a multiple of the largest coordinate, may be
+// the same point rounded two ways
+const ROUNDING: f64 = 64.0 * f64::EPSILON;
+
+// Absolute merge distance for the given input: EPSILON of its bounding-box
+// extent, but never below what rounding its coordinates can move a point
+pub(crate) fn merge_epsilon(segments: &[Segment]) -> f64 {
+    let (mut min, mut max) = (Point::new(f64::INFINITY, f64::INFINITY), Point::new(f64::NEG_INFINITY, f64::NEG_INFINITY));
+    for p in segments.iter().flat_map(|s| [s.a, s.b]) {
+        min = Point::new(min.x.min(p.x), min.y.min(p.y));
+        max = Point::new(max.x.max(p.x), max.y.max(p.y));
+    }
+    if min.x > max.x {
+        return EPSILON;
+    }
+    let extent = (max.x - min.x).max(max.y - min.y);
+    let magnitude = [min.x, min.y, max.x, max.y].iter().fold(0.0, |m: f64, c| m.max(c.abs()));
+    (EPSILON * extent).max(ROUNDING * magnitude).max(f64::MIN_POSITIVE)
+}
+
+// Closes gaps up to `tolerance`: each endpoint that touches nothing else
+// gets a bridging segment to the nearest point of any other segment in range
+pub(crate) fn heal_gaps(segments: &[Segment], tolerance: f64, eps: f64) -> Vec<Segment> {
+    let mut healed = segments.to_vec();
+    if tolerance <= eps {
+        return healed;
+    }
+    for (i, s) in segments.iter().enumerate() {
+        for p in [s.a, s.b] {
+            let nearest = segments.iter().enumerate()
+                .filter(|(j, _)| *j != i)
+                .map(|(_, other)| other.closest_point(&p))
+                .min_by(|x, y| distance(x, &p).total_cmp(&distance(y, &p)));
+            if let Some(q) = nearest {
+                let d = distance(&q, &p);
+                if d > eps && d <= tolerance {
+                    healed.push(Segment::new(p, q));
+                }
+            }
+        }
+    }
+    healed
+}
+
+// A point strictly inside a ring, from its largest ear-clipped triangle
+pub(crate) fn interior_point(ring: &[Point]) -> Option<Point> {
+    let face = Polygon::new(ring.to_vec());
+    face.triangulate().into_iter().map(|[a, b, c]| (ring[a], ring[b], ring[c]))
+        .max_by(|x, y| orient2d(&x.0, &x.1, &x.2).abs().total_cmp(&orient2d(&y.0, &y.1, &y.2).abs()))
+        .map(|(a, b, c)| Point::new((a.x + b.x + c.x) / 3.0, (a.y + b.y + c.y) / 3.0))
+}
+
+// A point strictly inside the region the rings bound by even-odd rule: the
+// middle of the widest inside span on a horizontal line through the largest
+// gap between vertex heights
+pub(crate) fn scan_point(rings: &[&[Point]]) -> Option<Point> {
+    let mut ys: Vec<f64> = rings.iter().flat_map(|r| r.iter().map(|p| p.y)).collect();
+    ys.sort_by(f64::total_cmp);
+    let (lo, hi) = ys.windows(2).map(|w| (w[0], w[1])).max_by(|a, b| (a.1 - a.0).total_cmp(&(b.1 - b.0)))?;
+    if hi - lo <= 0.0 {
+        return None;
+    }
+    let y = (lo + hi) / 2.0;
+    let mut xs: Vec<f64> = Vec::new();
+    for ring in rings {
+        let n = ring.len();
+        for i in 0..n {
+            let (p, q) = (ring[i], ring[(i + 1) % n]);
+            if (p.y > y) != (q.y > y) {
+                xs.push(p.x + (y - p.y) * (q.x - p.x) / (q.y - p.y));
+            }
+        }
+    }
+    xs.sort_by(f64::total_cmp);
+    let (a, b) = xs.chunks_exact(2).map(|c| (c[0], c[1])).max_by(|a, b| (a.1 - a.0).total_cmp(&(b.1 - b.0)))?;
+    (b > a).then(|| Point::new((a + b) / 2.0, y))
+}
+
+pub(crate) fn drop_collinear(ring: Vec<Point>, eps: f64) -> Vec<Point> {
+    let n = ring.len();
+    (0..n).filter(|&i| {
+        let (a, b, c) = (ring[(i + n - 1) % n], ring[i], ring[(i + 1) % n]);
+        let span = (c.x - a.x).hypot(c.y - a.y).max(eps);
+        orient2d(&a, &b, &c).abs() / span > eps
+    }).map(|i| ring[i]).collect()
+}
+
+// Per face, the other components sitting inside it, which the face's cycle
+// alone doesn't show: each outer cycle of a component goes to the smallest
+// bounded face of another component around it
+pub(crate) fn islands(faces: &[FaceCycle]) -> Vec<Vec<&FaceCycle>> {
+    let mut islands: Vec<Vec<&FaceCycle>> = vec![Vec::new(); faces.len()];
+    for island in faces.iter().filter(|f| f.area < 0.0) {
+        let parent = (0..faces.len())
+            .filter(|&i| faces[i].area > 0.0 && faces[i].component != island.component && point_in_ring(&faces[i].ring, &island.ring[0]))
+            .min_by(|&i, &j| faces[i].area.total_cmp(&faces[j].area));
+        if let Some(i) = parent {
+            islands[i].push(island);
+        }
+    }
+    islands
+}
+
+impl Arrangement {
+    pub(crate) fn build(segments: &[Segment], eps: f64) -> Arrangement {
+        let mut segments: Vec<&Segment> = segments.iter().filter(|s| s.length() > eps).collect();
+        // Sorted by left end so each segment is only tested against those
+        // whose x range overlaps its own, then skipped unless y overlaps too
+        let min_x = |s: &Segment| s.a.x.min(s.b.x);
+        segments.sort_by(|s, t| min_x(s).total_cmp(&min_x(t)));
+        let mut cuts: Vec<Vec<f64>> = segments.iter().map(|_| vec![0.0, 1.0]).collect();
+        for i in 0..segments.len() {
+            let max_x = segments[i].a.x.max(segments[i].b.x) + eps;
+            for j in i + 1..segments.len() {
+                if min_x(segments[j]) > max_x {
+                    break;
+                }
+                let (s, t) = (segments[i], segments[j]);
+                if s.a.y.min(s.b.y) > t.a.y.max(t.b.y) + eps || t.a.y.min(t.b.y) > s.a.y.max(s.b.y) + eps {
+                    continue;
+                }
+                let (d1x, d1y) = (s.b.x - s.a.x, s.b.y - s.a.y);
+                let (d2x, d2y) = (t.b.x - t.a.x, t.b.y - t.a.y);
+                let denom = cross(d1x, d1y, d2x, d2y);
+                if denom.abs() > EPSILON * s.length() * t.length() {
+                    let (ex, ey) = (t.a.x - s.a.x, t.a.y - s.a.y);
+                    let u = cross(ex, ey, d2x, d2y) / denom;
+                    let v = cross(ex, ey, d1x, d1y) / denom;
+                    if (0.0..=1.0).contains(&u) && (0.0..=1.0).contains(&v) {
+                        cuts[i].push(u);
+                        cuts[j].push(v);
+                    }
+                }
+                // Endpoints resting on the other segment: T-junctions,
+                // near misses and collinear overlaps
+                for (on, other, k) in [(s, t, i), (t, s, j)] {
+                    for p in [other.a, other.b] {
+                        let q = on.closest_point(&p);
+                        if distance(&q, &p) <= eps {
+                            let len = on.length();
+                            cuts[k].push(distance(&on.a, &q) / len);
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut vertices = Vec::new();
+        let mut grid = VertexGrid { cell: 4.0 * eps, eps, cells: HashMap::new() };
+        let mut edges = Vec::new();
+        for (s, mut ts) in segments.iter().zip(cuts) {
+            ts.sort_by(f64::total_cmp);
+            let mut previous = None;
+            for t in ts {
+                let p = Point::new(s.a.x + (s.b.x - s.a.x) * t, s.a.y + (s.b.y - s.a.y) * t);
+                let v = grid.find_or_insert(&mut vertices, p);
+                if let Some(u) = previous {
+                    if u != v {
+                        edges.push((usize::min(u, v), usize::max(u, v)));
+                    }
+                }
+                previous = Some(v);
+            }
+        }
+        edges.sort_unstable();
+        edges.dedup();
+        Arrangement { vertices, edges }
+    }
+
+    // Drops edges that can't bound a face: repeatedly removes every edge
+    // ending at a vertex of degree one
+    pub(crate) fn prune_dangling(&mut self) {
+        loop {
+            let mut degree = vec![0usize; self.vertices.len()];
+            for &(u, v) in &self.edges {
+                degree[u] += 1;
+                degree[v] += 1;
+            }
+            let before = self.edges.len();
+            self.edges.retain(|&(u, v)| degree[u] > 1 && degree[v] > 1);
+            if self.edges.len() == before {
+                break;
+            }
+        }
+    }
+
+    fn components(&self) -> Vec<usize> {
+        let mut parent: Vec<usize> = (0..self.vertices.len()).collect();
+        fn root(parent: &mut [usize], mut i: usize) -> usize {
+            while parent[i] != i {
+                parent[i] = parent[parent[i]];
+                i = parent[i];
+            }
+            i
+        }
+        for &(u, v) in &self.edges {
+            let (ru, rv) = (root(&mut parent, u), root(&mut parent, v));
+            parent[ru] = rv;
+        }
+        (0..self.vertices.len()).map(|i| root(&mut parent, i)).collect()
+    }
+
+    pub(crate) fn faces(&self) -> Vec<FaceCycle> {
+        // Half-edge 2e runs u -> v along edge e = (u, v), 2e + 1 runs back
+        let origin = |h: usize| if h & 1 == 0 { self.edges[h / 2].0 } else { self.edges[h / 2].1 };
+        let target = |h: usize| origin(h ^ 1);
+        let angle = |h: usize| {
+            let (a, b) = (self.vertices[origin(h)], self.vertices[target(h)]);
+            (b.y - a.y).atan2(b.x - a.x)
+        };
+
+        let mut outgoing: Vec<Vec<usize>> = vec![Vec::new(); self.vertices.len()];
+        for h in 0..2 * self.edges.len() {
+            outgoing[origin(h)].push(h);
+        }
+        let mut slot = vec![0; 2 * self.edges.len()];
+        for list in &mut outgoing {
+            list.sort_by(|&a, &b| angle(a).total_cmp(&angle(b)));
+            for (i, &h) in list.iter().enumerate() {
+                slot[h] = i;
+            }
+        }
+        // At the far end, turn to the next edge clockwise from the way back
+        let next = |h: usize| {
+            let list = &outgoing[target(h)];
+            list[(slot[h ^ 1] + list.len() - 1) % list.len()]
+        };
+
+        let component = self.components();
+        let mut visited = vec![false; 2 * self.edges.len()];
+        let mut faces = Vec::new();
+        for start in 0..2 * self.edges.len() {
+            if visited[start] {
+                continue;
+            }
+            let mut indices = Vec::new();
+            let mut h = start;
+            while !visited[h] {
+                visited[h] = true;
+                indices.push(origin(h));
+                h = next(h);
+            }
+            let ring: Vec<Point> = indices.iter().map(|&i| self.vertices[i]).collect();
+            faces.push(FaceCycle { area: ring_signed_area(&ring), component: component[origin(start)], indices, ring });
+        }
+        faces
+    }
+
+    // Boundary of the union of the bounded faces that are `covered` at an
+    // interior point. Outer rings come out counter-clockwise, holes clockwise.
+    pub(crate) fn covered_boundary(&self, covered: impl Fn(&Point) -> bool) -> Vec<Vec<Point>> {
+        // Directed edges of covered faces; an edge shared by two covered faces
+        // appears once each way and cancels, leaving the union's boundary
+        let mut boundary: BTreeSet<(usize, usize)> = BTreeSet::new();
+        let faces = self.faces();
+        let islands = islands(&faces);
+        for (face, islands) in faces.iter().zip(&islands) {
+            let mut rings = vec![face.ring.as_slice()];
+            rings.extend(islands.iter().map(|f| f.ring.as_slice()));
+            if face.area <= 0.0 || !scan_point(&rings).is_some_and(|q| covered(&q)) {
+                continue;
+            }
+            // The islands' outlines bound this face too, running clockwise
+            for cycle in std::iter::once(face).chain(islands.iter().copied()) {
+                let k = cycle.indices.len();
+                for i in 0..k {
+                    let (a, b) = (cycle.indices[i], cycle.indices[(i + 1) % k]);
+                    if !boundary.remove(&(b, a)) {
+                        boundary.insert((a, b));
+                    }
+                }
+            }
+        }
+
+        let mut next: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for &(a, b) in &boundary {
+            next.entry(a).or_default().push(b);
+        }
+        let mut rings = Vec::new();
+        while let Some((&start, _)) = next.iter().next() {
+            let mut ring = Vec::new();
+            let mut at = start;
+            while let Some(outs) = next.get_mut(&at) {
+                let to = outs.pop().unwrap();
+                if outs.is_empty() {
+                    next.remove(&at);
+                }
+                ring.push(at);
+                at = to;
+                if at == start {
+                    break;
+                }
+            }
+            rings.extend(split_pinches(ring).into_iter().map(|r| r.iter().map(|&i| self.vertices[i]).collect::<Vec<Point>>()));
+        }
+        rings
+    }
+}
+
+// A traced ring through the same vertex more than once, where regions touch
+// at a point, as the simple loops meeting there
+fn split_pinches(ring: Vec<usize>) -> Vec<Vec<usize>> {
+    let mut loops = Vec::new();
+    let mut stack: Vec<usize> = Vec::new();
+    let mut at: HashMap<usize, usize> = HashMap::new();
+    for i in ring {
+        if let Some(&p) = at.get(&i) {
+            let closed = stack.split_off(p);
+            for j in &closed {
+                at.remove(j);
+            }
+            loops.push(closed);
+        }
+        at.insert(i, stack.len());
+        stack.push(i);
+    }
+    loops.push(stack);
+    loops
+}
+
+// Rings from `covered_boundary` as polygons: each clockwise ring becomes a
+// hole of the smallest counter-clockwise ring around it
+pub(crate) fn assemble(rings: Vec<Vec<Point>>, eps: f64) -> Vec<PolygonWithHoles> {
+    let rings: Vec<Vec<Point>> = rings.into_iter().map(|r| drop_collinear(r, eps))
+        .filter(|r| r.len() >= 3 && ring_signed_area(r).abs() > eps * eps)
+        .collect();
+    let (outers, holes): (Vec<&Vec<Point>>, Vec<&Vec<Point>>) = rings.iter().partition(|r| ring_signed_area(r) > 0.0);
+    let mut regions: Vec<PolygonWithHoles> = outers.iter().map(|r| PolygonWithHoles::new(Polygon::new(r.to_vec()), vec![])).collect();
+    for hole in holes {
+        let mut ccw = hole.clone();
+        ccw.reverse();
+        let Some(probe) = interior_point(&ccw) else {
+            continue;
+        };
+        let parent = (0..outers.len()).filter(|&i| point_in_ring(outers[i], &probe))
+            .min_by(|&i, &j| ring_signed_area(outers[i]).total_cmp(&ring_signed_area(outers[j])));
+        if let Some(i) = parent {
+            regions[i].holes.push(Polygon::new(hole.clone()));
+        }
+    }
+    regions
+}
+
+// Regions where `keep(inside a, inside b)` holds, `a` and `b` each covering
+// the union of their rings
+pub(crate) fn overlay(a: &[Vec<Point>], b: &[Vec<Point>], keep: impl Fn(bool, bool) -> bool) -> Vec<PolygonWithHoles> {
+    let segments: Vec<Segment> = a.iter().chain(b)
+        .flat_map(|r| (0..r.len()).map(move |i| Segment::new(r[i], r[(i + 1) % r.len()])))
+        .collect();
+    let eps = merge_epsilon(&segments);
+    let inside = |rings: &[Vec<Point>], q: &Point| rings.iter().any(|r| point_in_ring(r, q));
+    assemble(Arrangement::build(&segments, eps).covered_boundary(|q| keep(inside(a, q), inside(b, q))), eps)
+}
+
+// Face of the arrangement of `segments` containing `seed`, with islands
+// inside it as holes, or None when the seed is in the unbounded face. Gaps
+// up to `tolerance` are closed first.
+pub fn region_at_point(segments: &[Segment], seed: &Point, tolerance: f64) -> Option<PolygonWithHoles> {
+    let eps = merge_epsilon(segments);
+    let mut arrangement = Arrangement::build(&heal_gaps(segments, tolerance, eps), eps);
+    arrangement.prune_dangling();
+    let faces = arrangement.faces();
+
+    let bounded = |f: &&FaceCycle| f.area > 0.0;
+    let outer = faces.iter().filter(bounded).filter(|f| point_in_ring(&f.ring, seed)).min_by(|a, b| a.area.total_cmp(&b.area))?;
+
+    // An island is a hole when the smallest bounded face of another
+    // component around it is the seed's face
+    let holes = faces.iter().filter(|f| f.area < 0.0 && f.component != outer.component).filter(|island| {
+        let probe = island.ring[0];
+        let parent = faces.iter().filter(bounded)
+            .filter(|f| f.component != island.component && point_in_ring(&f.ring, &probe))
+            .min_by(|a, b| a.area.total_cmp(&b.area));
+        parent.is_some_and(|p| std::ptr::eq(p, outer))
+    }).map(|f| Polygon::new(f.ring.clone())).collect();
+
+    Some(PolygonWithHoles::new(Polygon::new(outer.ring.clone()), holes))
+}
+
+// `segments` is an array of { a: {x, y}, b: {x, y} }; null when the seed is outside
+#[wasm_bindgen(js_name = region_at_point)]
+pub fn region_at_point_js(segments: &JsValue, x: f64, y: f64, tolerance: f64) -> Result<JsValue, JsValue> {
+    let segments: Vec<Segment> = crate::from_js(segments)?;
+    crate::to_js(&region_at_point(&segments, &Point::new(x, y), tolerance))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seg(ax: f64, ay: f64, bx: f64, by: f64) -> Segment {
+        Segment::new(Point::new(ax, ay), Point::new(bx, by))
+    }
+
+    #[test]
+    fn test_tic_tac_toe_center_cell() {
+        let grid = [seg(1.0, 0.0, 1.0, 3.0), seg(2.0, 0.0, 2.0, 3.0), seg(0.0, 1.0, 3.0, 1.0), seg(0.0, 2.0, 3.0, 2.0)];
+        let region = region_at_point(&grid, &Point::new(1.5, 1.5), 0.0).unwrap();
+        assert!(region.holes.is_empty());
+        assert_eq!(region.outer.vertices.len(), 4);
+        assert!((region.area() - 1.0).abs() < 1e-12);
+        let bbox = region.outer.bounding_box();
+        assert_eq!((bbox.min, bbox.max), (Point::new(1.0, 1.0), Point::new(2.0, 2.0)));
+
+        assert!(region_at_point(&grid, &Point::new(0.5, 0.5), 0.0).is_none());
+        assert!(region_at_point(&grid, &Point::new(-1.0, 5.0), 0.0).is_none());
+    }
+
+    #[test]
+    fn test_gap_closed_only_within_tolerance() {
+        let square = [
+            seg(0.0, 0.0, 1.75, 0.0), seg(2.25, 0.0, 4.0, 0.0),
+            seg(4.0, 0.0, 4.0, 4.0), seg(4.0, 4.0, 0.0, 4.0), seg(0.0, 4.0, 0.0, 0.0),
+        ];
+        let seed = Point::new(2.0, 2.0);
+        let region = region_at_point(&square, &seed, 0.6).unwrap();
+        assert!((region.area() - 16.0).abs() < 1e-12);
+        assert!(region_at_point(&square, &seed, 0.1).is_none());
+    }
+
+    #[test]
+    fn test_island_becomes_hole() {
+        let mut segments = Vec::new();
+        for (lo, hi) in [(0.0, 10.0), (4.0, 6.0)] {
+            segments.extend([seg(lo, lo, hi, lo), seg(hi, lo, hi, hi), seg(hi, hi, lo, hi), seg(lo, hi, lo, lo)]);
+        }
+        // A wall splitting the outer square, overlapping one of its sides
+        segments.push(seg(0.0, 8.0, 10.0, 8.0));
+        segments.push(seg(10.0, 0.0, 10.0, 9.0));
+
+        let region = region_at_point(&segments, &Point::new(1.0, 1.0), 0.0).unwrap();
+        assert_eq!(region.holes.len(), 1);
+        assert!((region.area() - (80.0 - 4.0)).abs() < 1e-9);
+        let island = region_at_point(&segments, &Point::new(5.0, 5.0), 0.0).unwrap();
+        assert!((island.area() - 4.0).abs() < 1e-12 && island.holes.is_empty());
+        let top = region_at_point(&segments, &Point::new(5.0, 9.0), 0.0).unwrap();
+        assert!((top.area() - 20.0).abs() < 1e-9 && top.holes.is_empty());
+    }
+
+    #[test]
+    fn test_merge_distance_follows_extent_not_position() {
+        let square = |x: f64, y: f64, side: f64| [seg(x, y, x + side, y), seg(x + side, y, x + side, y + side), seg(x + side, y + side, x, y + side), seg(x, y + side, x, y)];
+        let near = merge_epsilon(&square(0.0, 0.0, 3.0));
+        assert!((near - 3.0 * EPSILON).abs() < 1e-24);
+        // Moved far out, only rounding widens it
+        let far = merge_epsilon(&square(1e7, -1e7, 3.0));
+        assert!((1e7 * f64::EPSILON..1e-6).contains(&far));
+        // A notch a thousandth deep out there is not merged away
+        let notch = [(1e7, -1e7), (1e7 + 3.0, -1e7), (1e7 + 3.0, -1e7 + 3.0), (1e7 + 1.5, -1e7 + 0.001), (1e7, -1e7 + 3.0)];
+        let ring: Vec<Point> = notch.iter().map(|&(x, y)| Point::new(x, y)).collect();
+        let regions = overlay(std::slice::from_ref(&ring), &[], |a, _| a);
+        assert_eq!(regions.len(), 1);
+        assert!((regions[0].area() - Polygon::new(ring).area()).abs() < 1e-6);
+    }
+}
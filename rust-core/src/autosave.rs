@@ -0,0 +1,333 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::binary::{from_bytes, to_bytes};
+use crate::hash::ContentHasher;
+use crate::{js_error, CadError, Document, Shape, ShapeId};
+
+// Every chunk is MAGIC, a kind byte, its sequence number, the checksum of
+// the snapshot it builds on (0 for a snapshot), the body's length and the
+// body, then the FNV-1a hash of all of that
+const MAGIC: &[u8; 4] = b"CADA";
+const SNAPSHOT: u8 = 0;
+const DELTA: u8 = 1;
+const HEADER: usize = 4 + 1 + 8 + 8 + 8;
+
+// What changed since the previous chunk: shapes added or edited, whole,
+// shapes removed, and everything else only when some of it changed
+#[derive(Serialize, Deserialize)]
+struct Delta {
+    next_id: ShapeId,
+    shapes: Vec<Shape>,
+    removed: Vec<ShapeId>,
+    settings: Option<Document>,
+}
+
+// Where a document's autosave chain is up to
+#[derive(Clone, Debug, Default)]
+pub(crate) struct AutosaveLog {
+    // Of the last chunk handed out; 0 until the first snapshot
+    sequence: u64,
+    // Checksum of the snapshot the chain builds on
+    base: u64,
+    // Shapes touched since the last chunk
+    pending: Vec<ShapeId>,
+    // Hash of `Document::settings` as last saved
+    settings: u64,
+}
+
+impl AutosaveLog {
+    pub(crate) fn touch(&mut self, id: ShapeId, shape_count: usize) {
+        if self.sequence == 0 {
+            return;
+        }
+        self.pending.push(id);
+        if self.pending.len() > 2 * shape_count + 16 {
+            self.pending.sort_unstable();
+            self.pending.dedup();
+        }
+    }
+}
+
+// One chunk for the caller to store, in sequence after the ones before
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct AutosavePayload {
+    pub sequence: u64,
+    // A full snapshot, which starts a new chain, rather than a delta
+    pub full: bool,
+    pub bytes: Vec<u8>,
+}
+
+// A document rebuilt from autosaves: the snapshot and the `applied` deltas
+// after it, stopping at the first delta that fails its checks
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct AutosaveRestore {
+    document: Document,
+    applied: usize,
+    error: Option<CadError>,
+}
+
+impl AutosaveRestore {
+    pub fn document(&self) -> &Document {
+        &self.document
+    }
+
+    pub fn into_document(self) -> Document {
+        self.document
+    }
+
+    pub fn applied(&self) -> usize {
+        self.applied
+    }
+
+    // A `CadError::CorruptDelta` naming the delta that stopped the replay
+    pub fn error(&self) -> Option<&CadError> {
+        self.error.as_ref()
+    }
+}
+
+fn checksum(bytes: &[u8]) -> u64 {
+    let mut h = ContentHasher::new();
+    h.bytes(bytes);
+    h.finish()
+}
+
+fn chunk(kind: u8, sequence: u64, base: u64, body: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(HEADER + body.len() + 8);
+    out.extend(MAGIC);
+    out.push(kind);
+    out.extend(sequence.to_le_bytes());
+    out.extend(base.to_le_bytes());
+    out.extend((body.len() as u64).to_le_bytes());
+    out.extend(body);
+    out.extend(checksum(&out).to_le_bytes());
+    out
+}
+
+struct Chunk<'a> {
+    kind: u8,
+    sequence: u64,
+    base: u64,
+    body: &'a [u8],
+}
+
+// Checks the framing and checksum, not what the body holds
+fn open(bytes: &[u8]) -> Result<Chunk<'_>, String> {
+    if bytes.len() < HEADER + 8 || &bytes[..4] != MAGIC {
+        return Err("not an autosave chunk".to_string());
+    }
+    let word = |at: usize| u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap());
+    let length = word(21);
+    if length != (bytes.len() - HEADER - 8) as u64 {
+        return Err(format!("body is {} bytes, expected {}", bytes.len() - HEADER - 8, length));
+    }
+    let end = bytes.len() - 8;
+    if checksum(&bytes[..end]) != word(end) {
+        return Err("checksum mismatch".to_string());
+    }
+    Ok(Chunk { kind: bytes[4], sequence: word(5), base: word(13), body: &bytes[HEADER..end] })
+}
+
+fn settings_hash(doc: &Document) -> Result<u64, CadError> {
+    Ok(checksum(&to_bytes(&doc.settings())?))
+}
+
+impl Document {
+    // Next chunk of the autosave chain: a full snapshot on the first call,
+    // and after `reset_autosave`, then deltas of what changed since the
+    // previous call, so their size follows the edits rather than the
+    // document. Replay them with `restore_from_autosave`.
+    pub fn autosave_state(&mut self) -> Result<AutosavePayload, CadError> {
+        let settings = settings_hash(self)?;
+        if self.autosave.sequence == 0 {
+            let bytes = chunk(SNAPSHOT, 1, 0, &to_bytes(self)?);
+            self.autosave = AutosaveLog { sequence: 1, base: checksum(&bytes), pending: Vec::new(), settings };
+            return Ok(AutosavePayload { sequence: 1, full: true, bytes });
+        }
+        let mut pending = std::mem::take(&mut self.autosave.pending);
+        pending.sort_unstable();
+        pending.dedup();
+        let (mut shapes, mut removed) = (Vec::new(), Vec::new());
+        for id in pending {
+            match self.shape(id) {
+                Ok(shape) => shapes.push(shape.clone()),
+                Err(_) => removed.push(id),
+            }
+        }
+        let changed = settings != self.autosave.settings;
+        let delta = Delta { next_id: self.next_id(), shapes, removed, settings: changed.then(|| self.settings()) };
+        let sequence = self.autosave.sequence + 1;
+        let bytes = chunk(DELTA, sequence, self.autosave.base, &to_bytes(&delta)?);
+        self.autosave.sequence = sequence;
+        self.autosave.settings = settings;
+        Ok(AutosavePayload { sequence, full: false, bytes })
+    }
+
+    // Makes the next `autosave_state` a full snapshot, e.g. once the deltas
+    // stored since the last one outweigh it
+    pub fn reset_autosave(&mut self) {
+        self.autosave = AutosaveLog::default();
+    }
+
+    // The document as of the last of `deltas` replayed onto `full`. Each
+    // delta must pass its checksum and follow on from the one before in
+    // the same chain; the first that doesn't ends the replay, and is
+    // reported with the document as it stood before it. Only a snapshot
+    // that fails its own checks is an error. The restored document carries
+    // on the chain, so its next autosave is the delta after the last
+    // applied.
+    pub fn restore_from_autosave(full: &[u8], deltas: &[&[u8]]) -> Result<AutosaveRestore, CadError> {
+        let snapshot = open(full).map_err(CadError::CorruptSnapshot)?;
+        if snapshot.kind != SNAPSHOT {
+            return Err(CadError::CorruptSnapshot("chunk is a delta".to_string()));
+        }
+        let mut document: Document = from_bytes(snapshot.body).map_err(|e| CadError::CorruptSnapshot(e.to_string()))?;
+        document.recount();
+        let base = checksum(full);
+        let mut sequence = snapshot.sequence;
+        let mut error = None;
+        let mut applied = 0;
+        for (index, bytes) in deltas.iter().enumerate() {
+            let delta = open(bytes).and_then(|c| {
+                if c.kind != DELTA {
+                    Err("chunk is a snapshot".to_string())
+                } else if c.base != base {
+                    Err("delta belongs to a different snapshot".to_string())
+                } else if c.sequence != sequence + 1 {
+                    Err(format!("expected sequence {}, found {}", sequence + 1, c.sequence))
+                } else {
+                    from_bytes::<Delta>(c.body).map_err(|e| e.to_string())
+                }
+            });
+            match delta {
+                Ok(delta) => {
+                    if let Some(settings) = delta.settings {
+                        document.restore_settings(settings);
+                    }
+                    for id in delta.removed {
+                        document.remove_shape(id).ok();
+                    }
+                    for shape in delta.shapes {
+                        document.put_shape(shape);
+                    }
+                    document.set_next_id(delta.next_id);
+                    document.recount();
+                    sequence += 1;
+                    applied += 1;
+                }
+                Err(message) => {
+                    error = Some(CadError::CorruptDelta { index, message });
+                    break;
+                }
+            }
+        }
+        let settings = settings_hash(&document)?;
+        document.autosave = AutosaveLog { sequence, base, pending: Vec::new(), settings };
+        Ok(AutosaveRestore { document, applied, error })
+    }
+}
+
+#[wasm_bindgen]
+impl Document {
+    // { sequence, full, bytes: Uint8Array }, for the caller to store
+    #[wasm_bindgen(js_name = autosave_state)]
+    pub fn autosave_state_js(&mut self) -> Result<AutosavePayload, JsValue> {
+        self.autosave_state().map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = reset_autosave)]
+    pub fn reset_autosave_js(&mut self) {
+        self.reset_autosave()
+    }
+
+    // `deltas` is an array of Uint8Array, in the order they were saved
+    #[wasm_bindgen(js_name = restore_from_autosave)]
+    pub fn restore_from_autosave_js(full: &[u8], deltas: &js_sys::Array) -> Result<AutosaveRestore, JsValue> {
+        let deltas: Vec<Vec<u8>> = deltas.iter().map(|d| js_sys::Uint8Array::new(&d).to_vec()).collect();
+        let deltas: Vec<&[u8]> = deltas.iter().map(|d| d.as_slice()).collect();
+        Document::restore_from_autosave(full, &deltas).map_err(js_error)
+    }
+}
+
+#[wasm_bindgen]
+impl AutosaveRestore {
+    #[wasm_bindgen(js_name = document)]
+    pub fn document_js(&self) -> Document {
+        self.document.clone()
+    }
+
+    #[wasm_bindgen(js_name = applied)]
+    pub fn applied_js(&self) -> usize {
+        self.applied
+    }
+
+    // The error the replay stopped at, as thrown elsewhere, or undefined
+    #[wasm_bindgen(js_name = error)]
+    pub fn error_js(&self) -> Option<JsValue> {
+        self.error.clone().map(js_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AttributeValue, Matrix, Point, Polygon};
+
+    fn square(x: f64) -> Polygon {
+        Polygon::new(vec![Point::new(x, 0.0), Point::new(x + 1.0, 0.0), Point::new(x + 1.0, 1.0), Point::new(x, 1.0)])
+    }
+
+    #[test]
+    fn test_deltas_replay_and_stop_at_corruption() {
+        let mut doc = Document::new();
+        let ids: Vec<ShapeId> = (0..50).map(|i| doc.add_shape(square(i as f64 * 2.0))).collect();
+        let full = doc.autosave_state().unwrap();
+        assert!(full.full && full.sequence == 1);
+
+        let mut deltas = Vec::new();
+        let mut hashes = vec![doc.content_hash_all()];
+        doc.set_transform(ids[3], Matrix::translate(5.0, 1.0)).unwrap();
+        doc.set_attribute(ids[7], "part", AttributeValue::Text("lid".to_string())).unwrap();
+        deltas.push(doc.autosave_state().unwrap());
+        hashes.push(doc.content_hash_all());
+        doc.remove_shape(ids[10]).unwrap();
+        let added = doc.add_shape(square(-4.0));
+        doc.set_layer_transform("A", Some(Matrix::translate(0.0, 3.0))).unwrap();
+        doc.set_layer(added, Some("A".to_string())).unwrap();
+        deltas.push(doc.autosave_state().unwrap());
+        hashes.push(doc.content_hash_all());
+        doc.rotate_shape(ids[0], 0.5).unwrap();
+        deltas.push(doc.autosave_state().unwrap());
+        hashes.push(doc.content_hash_all());
+
+        // A delta is sized by the edit, not by the document
+        assert!(deltas[2].bytes.len() * 10 < full.bytes.len());
+        assert_eq!(deltas.iter().map(|d| d.sequence).collect::<Vec<_>>(), vec![2, 3, 4]);
+
+        let all: Vec<&[u8]> = deltas.iter().map(|d| d.bytes.as_slice()).collect();
+        let mut restored = Document::restore_from_autosave(&full.bytes, &all).unwrap();
+        assert!(restored.error().is_none() && restored.applied() == 3);
+        assert_eq!(restored.document().content_hash_all(), hashes[3]);
+        assert_eq!(restored.document().layer_transform(Some("A")), Matrix::translate(0.0, 3.0));
+        // and carries on the chain where the original left off
+        doc.remove_shape(ids[1]).unwrap();
+        restored.document.remove_shape(ids[1]).unwrap();
+        let next = restored.document.autosave_state().unwrap();
+        assert_eq!(next, doc.autosave_state().unwrap());
+
+        // A flipped byte in the middle delta keeps the first edit only
+        let mut bad = deltas[1].bytes.clone();
+        bad[40] ^= 1;
+        let corrupt: Vec<&[u8]> = vec![all[0], &bad, all[2]];
+        let partial = Document::restore_from_autosave(&full.bytes, &corrupt).unwrap();
+        assert_eq!(partial.applied(), 1);
+        assert_eq!(partial.document().content_hash_all(), hashes[1]);
+        assert_eq!(partial.error(), Some(&CadError::CorruptDelta { index: 1, message: "checksum mismatch".to_string() }));
+        // Skipping a delta breaks the sequence
+        let skipped = Document::restore_from_autosave(&full.bytes, &[all[0], all[2]]).unwrap();
+        assert_eq!(skipped.error().map(|e| e.code()), Some("corrupt delta"));
+        assert!(Document::restore_from_autosave(&full.bytes[1..], &[]).is_err());
+    }
+}
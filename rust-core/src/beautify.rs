@@ -0,0 +1,447 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::{Point, Polygon, Polyline, EPSILON};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct BeautifyOptions {
+    // Turning angle in degrees, measured across the corner window, above
+    // which a point is a corner
+    pub corner_angle: f64,
+    // Arc length looked at on each side of a point, as a fraction of the
+    // stroke length
+    pub corner_window: f64,
+    // Largest deviation from a straight fit before a run becomes an arc, as a
+    // fraction of the stroke's bounding box diagonal
+    pub line_tolerance: f64,
+    // Line directions in degrees that fits snap to; their mirrors about the
+    // vertical (e.g. 135 for 45) are included automatically
+    pub snap_angles: Vec<f64>,
+    pub angle_tolerance: f64,
+    // Endpoint gap under which the stroke is closed, as a fraction of the
+    // bounding box diagonal; negative never closes
+    pub close_distance: f64,
+    pub arc_segments: usize,
+}
+
+impl Default for BeautifyOptions {
+    fn default() -> BeautifyOptions {
+        BeautifyOptions {
+            corner_angle: 35.0,
+            corner_window: 0.03,
+            line_tolerance: 0.02,
+            snap_angles: vec![0.0, 30.0, 45.0, 60.0, 90.0],
+            angle_tolerance: 5.0,
+            close_distance: 0.05,
+            arc_segments: 16,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Fit {
+    // Direction is a unit vector; `angle` is in degrees within [0, 180)
+    Line { origin: Point, dir: (f64, f64), angle: f64 },
+    // Circular arc through the run's end corners and this point
+    Arc { through: Point },
+}
+
+fn distance(a: &Point, b: &Point) -> f64 {
+    ((b.x - a.x).powi(2) + (b.y - a.y).powi(2)).sqrt()
+}
+
+// Unit direction for an angle in degrees, exact for multiples of 90
+fn unit(angle: f64) -> (f64, f64) {
+    if (angle / 90.0).fract() == 0.0 {
+        match ((angle / 90.0) as i64).rem_euclid(4) {
+            0 => (1.0, 0.0),
+            1 => (0.0, 1.0),
+            2 => (-1.0, 0.0),
+            _ => (0.0, -1.0),
+        }
+    } else {
+        let (s, c) = angle.to_radians().sin_cos();
+        (c, s)
+    }
+}
+
+// Keeps points on axis-aligned lines exactly on them
+fn exact(mut p: Point, origin: &Point, dir: (f64, f64)) -> Point {
+    if dir.0 == 0.0 {
+        p.x = origin.x;
+    }
+    if dir.1 == 0.0 {
+        p.y = origin.y;
+    }
+    p
+}
+
+fn project(p: &Point, fit: &Fit) -> Point {
+    match *fit {
+        Fit::Line { origin, dir, .. } => {
+            let t = (p.x - origin.x) * dir.0 + (p.y - origin.y) * dir.1;
+            exact(Point::new(origin.x + t * dir.0, origin.y + t * dir.1), &origin, dir)
+        }
+        Fit::Arc { .. } => *p,
+    }
+}
+
+// Shared corner of two consecutive runs
+fn join(a: &Fit, b: &Fit, raw: &Point) -> Point {
+    match (*a, *b) {
+        (Fit::Line { origin: o1, dir: d1, .. }, Fit::Line { origin: o2, dir: d2, .. }) => {
+            let cross = d1.0 * d2.1 - d1.1 * d2.0;
+            if cross.abs() < 1e-6 {
+                return project(raw, a);
+            }
+            let t = ((o2.x - o1.x) * d2.1 - (o2.y - o1.y) * d2.0) / cross;
+            let p = exact(Point::new(o1.x + t * d1.0, o1.y + t * d1.1), &o1, d1);
+            exact(p, &o2, d2)
+        }
+        (Fit::Line { .. }, _) => project(raw, a),
+        (_, Fit::Line { .. }) => project(raw, b),
+        _ => *raw,
+    }
+}
+
+// Index `window` of arc length away from `i`, or None when an open stroke
+// ends first
+fn reach(points: &[Point], i: usize, forward: bool, closed: bool, window: f64) -> Option<usize> {
+    let n = points.len();
+    let step = |j: usize| -> Option<usize> {
+        if closed {
+            Some(if forward { (j + 1) % n } else { (j + n - 1) % n })
+        } else if forward {
+            (j + 1 < n).then_some(j + 1)
+        } else {
+            j.checked_sub(1)
+        }
+    };
+
+    let mut j = i;
+    let mut travelled = 0.0;
+    while travelled < window {
+        let k = step(j)?;
+        travelled += distance(&points[j], &points[k]);
+        j = k;
+        if j == i {
+            return None;
+        }
+    }
+    (j != i).then_some(j)
+}
+
+fn turning_angles(points: &[Point], closed: bool, window: f64) -> Vec<f64> {
+    (0..points.len()).map(|i| {
+        match (reach(points, i, false, closed, window), reach(points, i, true, closed, window)) {
+            (Some(b), Some(f)) => {
+                let (p, pb, pf) = (points[i], points[b], points[f]);
+                let (ux, uy) = (p.x - pb.x, p.y - pb.y);
+                let (vx, vy) = (pf.x - p.x, pf.y - p.y);
+                (ux * vy - uy * vx).atan2(ux * vx + uy * vy).abs().to_degrees()
+            }
+            _ => 0.0,
+        }
+    }).collect()
+}
+
+// The sharpest point of every run of points turning more than `threshold`
+fn corner_peaks(angles: &[f64], threshold: f64, closed: bool) -> Vec<usize> {
+    let n = angles.len();
+    let above = |i: usize| angles[i] > threshold;
+    let start = if closed {
+        match (0..n).find(|&i| !above(i)) {
+            Some(s) => s,
+            None => return Vec::new(),
+        }
+    } else {
+        0
+    };
+
+    let mut peaks = Vec::new();
+    let mut best: Option<usize> = None;
+    for k in 0..n {
+        let i = (start + k) % n;
+        if above(i) {
+            if best.is_none_or(|b| angles[i] > angles[b]) {
+                best = Some(i);
+            }
+        } else if let Some(b) = best.take() {
+            peaks.push(b);
+        }
+    }
+    peaks.extend(best);
+    peaks.sort_unstable();
+    peaks
+}
+
+fn run(points: &[Point], from: usize, to: usize) -> Vec<Point> {
+    let n = points.len();
+    let count = (to + n - from) % n + 1;
+    (0..count).map(|k| points[(from + k) % n]).collect()
+}
+
+fn snap_angle(angle: f64, opts: &BeautifyOptions) -> f64 {
+    let a = angle.rem_euclid(180.0);
+    let gap = |c: f64| {
+        let d = (a - c).rem_euclid(180.0);
+        d.min(180.0 - d)
+    };
+    opts.snap_angles.iter()
+        .flat_map(|&s| [s, 180.0 - s])
+        .filter(|&c| gap(c) <= opts.angle_tolerance)
+        .min_by(|&x, &y| gap(x).total_cmp(&gap(y)))
+        .map(|c| c.rem_euclid(180.0))
+        .unwrap_or(a)
+}
+
+// Total least squares line through the run, ignoring the rounded-off ends
+// near its corners; falls back to an arc when the run bows too far
+fn fit_run(points: &[Point], trim: f64, line_tolerance: f64, opts: &BeautifyOptions) -> Fit {
+    let mut start = 0;
+    let mut travelled = 0.0;
+    while start + 1 < points.len() && travelled < trim {
+        travelled += distance(&points[start], &points[start + 1]);
+        start += 1;
+    }
+    let mut end = points.len();
+    travelled = 0.0;
+    while end > start + 1 && travelled < trim {
+        travelled += distance(&points[end - 2], &points[end - 1]);
+        end -= 1;
+    }
+    let core = if end - start >= 2 { &points[start..end] } else { points };
+
+    let count = core.len() as f64;
+    let cx = core.iter().map(|p| p.x).sum::<f64>() / count;
+    let cy = core.iter().map(|p| p.y).sum::<f64>() / count;
+    let (mut sxx, mut syy, mut sxy) = (0.0, 0.0, 0.0);
+    for p in core {
+        sxx += (p.x - cx).powi(2);
+        syy += (p.y - cy).powi(2);
+        sxy += (p.x - cx) * (p.y - cy);
+    }
+    let theta = 0.5 * (2.0 * sxy).atan2(sxx - syy);
+    let (s, c) = theta.sin_cos();
+    let deviation = core.iter().map(|p| (c * (p.y - cy) - s * (p.x - cx)).abs()).fold(0.0, f64::max);
+
+    if deviation > line_tolerance {
+        return Fit::Arc { through: points[points.len() / 2] };
+    }
+
+    let angle = snap_angle(theta.to_degrees(), opts);
+    Fit::Line { origin: Point::new(cx, cy), dir: unit(angle), angle }
+}
+
+// Interior points of the arc from `a` through `m` to `b`
+fn arc_points(a: &Point, m: &Point, b: &Point, segments: usize) -> Vec<Point> {
+    let d = 2.0 * (a.x * (m.y - b.y) + m.x * (b.y - a.y) + b.x * (a.y - m.y));
+    if d.abs() < EPSILON {
+        return Vec::new();
+    }
+
+    let (sa, sm, sb) = (a.x * a.x + a.y * a.y, m.x * m.x + m.y * m.y, b.x * b.x + b.y * b.y);
+    let center = Point::new(
+        (sa * (m.y - b.y) + sm * (b.y - a.y) + sb * (a.y - m.y)) / d,
+        (sa * (b.x - m.x) + sm * (a.x - b.x) + sb * (m.x - a.x)) / d,
+    );
+    let radius = distance(&center, a);
+    let angle_of = |p: &Point| (p.y - center.y).atan2(p.x - center.x);
+    let (a0, am, a1) = (angle_of(a), angle_of(m), angle_of(b));
+
+    let tau = std::f64::consts::TAU;
+    let ccw = (a1 - a0).rem_euclid(tau);
+    let sweep = if (am - a0).rem_euclid(tau) <= ccw { ccw } else { ccw - tau };
+    (1..segments).map(|k| {
+        let t = a0 + sweep * k as f64 / segments as f64;
+        Point::new(center.x + radius * t.cos(), center.y + radius * t.sin())
+    }).collect()
+}
+
+// Turns a freehand stroke into clean geometry: the stroke is cut at
+// curvature peaks, each run is fitted with a line (snapped to the configured
+// angles) or an arc, consecutive runs share their corner exactly, and a
+// stroke whose ends nearly meet comes back closed.
+pub fn beautify_stroke(points: &[Point], opts: BeautifyOptions) -> Polyline {
+    let mut points = points.to_vec();
+    points.dedup();
+    if points.len() < 3 {
+        return Polyline::new(points, false);
+    }
+
+    let bbox = Polygon::new(points.clone()).bounding_box();
+    let diagonal = bbox.width().hypot(bbox.height());
+    if diagonal < EPSILON {
+        return Polyline::new(vec![points[0]], false);
+    }
+
+    let n = points.len();
+    let closed = opts.close_distance >= 0.0 && distance(&points[0], &points[n - 1]) <= opts.close_distance * diagonal;
+    let window = opts.corner_window * Polyline::new(points.clone(), closed).length();
+    let line_tolerance = opts.line_tolerance * diagonal;
+
+    let peaks = corner_peaks(&turning_angles(&points, closed, window), opts.corner_angle, closed);
+    let mut corners: Vec<usize> = if closed {
+        let mut corners = peaks;
+        if corners.len() < 2 {
+            // Round closed strokes still need two runs to fit as arcs
+            let first = corners.first().copied().unwrap_or(0);
+            corners = vec![first, (first + n / 2) % n];
+            corners.sort_unstable();
+        }
+        corners
+    } else {
+        let mut corners = vec![0];
+        corners.extend(peaks.into_iter().filter(|&i| i != 0 && i != n - 1));
+        corners.push(n - 1);
+        corners
+    };
+
+    let fit_all = |corners: &[usize]| -> Vec<Fit> {
+        let runs = if closed { corners.len() } else { corners.len() - 1 };
+        (0..runs).map(|k| {
+            let pts = run(&points, corners[k], corners[(k + 1) % corners.len()]);
+            fit_run(&pts, window / 2.0, line_tolerance, &opts)
+        }).collect()
+    };
+
+    // Drop corners between runs that snapped to the same direction
+    let mut fits = fit_all(&corners);
+    loop {
+        let m = fits.len();
+        let first = if closed { 0 } else { 1 };
+        let merge = (first..m).find(|&k| {
+            let prev = if k == 0 { m - 1 } else { k - 1 };
+            match (fits[prev], fits[k]) {
+                (Fit::Line { angle: a, .. }, Fit::Line { angle: b, .. }) => {
+                    let d = (a - b).rem_euclid(180.0);
+                    d.min(180.0 - d) <= opts.angle_tolerance
+                }
+                _ => false,
+            }
+        });
+        match merge {
+            Some(k) if corners.len() > if closed { 3 } else { 2 } => {
+                corners.remove(k);
+                fits = fit_all(&corners);
+            }
+            _ => break,
+        }
+    }
+
+    let m = fits.len();
+    let mut vertices: Vec<Point> = Vec::with_capacity(corners.len());
+    if closed {
+        for k in 0..m {
+            vertices.push(join(&fits[(k + m - 1) % m], &fits[k], &points[corners[k]]));
+        }
+    } else {
+        vertices.push(project(&points[0], &fits[0]));
+        for k in 1..m {
+            vertices.push(join(&fits[k - 1], &fits[k], &points[corners[k]]));
+        }
+        vertices.push(project(&points[n - 1], &fits[m - 1]));
+    }
+
+    let mut result = Vec::new();
+    for k in 0..m {
+        let (a, b) = (vertices[k], vertices[(k + 1) % vertices.len()]);
+        result.push(a);
+        if let Fit::Arc { through } = fits[k] {
+            result.extend(arc_points(&a, &through, &b, opts.arc_segments.max(2)));
+        }
+    }
+    if !closed {
+        result.push(vertices[m]);
+    }
+
+    Polyline::new(result, closed)
+}
+
+#[wasm_bindgen(js_name = beautify_stroke)]
+pub fn beautify_stroke_js(points: &JsValue, opts: &JsValue) -> Result<JsValue, JsValue> {
+    let points: Vec<Point> = crate::from_js(points)?;
+    let opts: BeautifyOptions = crate::options_from(opts)?;
+    crate::to_js(&beautify_stroke(&points, opts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn noise(seed: &mut u64) -> f64 {
+        *seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        ((*seed >> 11) as f64 / (1u64 << 53) as f64) * 2.0 - 1.0
+    }
+
+    // Samples a path through `corners` every `step`, jittered by `amplitude`
+    fn wobbly(corners: &[Point], step: f64, amplitude: f64) -> Vec<Point> {
+        let mut seed = 7;
+        let mut points = Vec::new();
+        for w in corners.windows(2) {
+            let count = (distance(&w[0], &w[1]) / step) as usize;
+            for i in 0..count {
+                let t = i as f64 / count as f64;
+                let x = w[0].x + (w[1].x - w[0].x) * t + noise(&mut seed) * amplitude;
+                let y = w[0].y + (w[1].y - w[0].y) * t + noise(&mut seed) * amplitude;
+                points.push(Point::new(x, y));
+            }
+        }
+        points
+    }
+
+    #[test]
+    fn test_wobbly_rectangle_becomes_exact() {
+        // Starts mid-edge and stops just short of where it began
+        let path = [
+            Point::new(3.0, 0.0),
+            Point::new(10.0, 0.0),
+            Point::new(10.0, 6.0),
+            Point::new(0.0, 6.0),
+            Point::new(0.0, 0.0),
+            Point::new(2.8, 0.0),
+        ];
+        let result = beautify_stroke(&wobbly(&path, 0.1, 0.05), BeautifyOptions::default());
+
+        assert!(result.closed);
+        assert_eq!(result.points.len(), 4);
+        let expected = [Point::new(10.0, 0.0), Point::new(10.0, 6.0), Point::new(0.0, 6.0), Point::new(0.0, 0.0)];
+        for corner in expected {
+            assert!(result.points.iter().any(|p| distance(p, &corner) < 0.1));
+        }
+        for i in 0..4 {
+            let (a, b) = (result.points[i], result.points[(i + 1) % 4]);
+            assert!(a.x == b.x || a.y == b.y);
+        }
+    }
+
+    #[test]
+    fn test_slanted_stroke_snaps_to_45_degrees() {
+        let angle = 43.0f64.to_radians();
+        let points = wobbly(&[Point::new(0.0, 0.0), Point::new(10.0 * angle.cos(), 10.0 * angle.sin())], 0.1, 0.02);
+        let result = beautify_stroke(&points, BeautifyOptions::default());
+
+        assert!(!result.closed);
+        assert_eq!(result.points.len(), 2);
+        let (a, b) = (result.points[0], result.points[1]);
+        assert!(((b.y - a.y).atan2(b.x - a.x).to_degrees() - 45.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_round_stroke_fits_arcs() {
+        let mut seed = 3;
+        let points: Vec<Point> = (0..200).map(|i| {
+            let t = i as f64 / 200.0 * std::f64::consts::TAU;
+            let r = 5.0 + noise(&mut seed) * 0.02;
+            Point::new(r * t.cos(), r * t.sin())
+        }).collect();
+        let result = beautify_stroke(&points, BeautifyOptions::default());
+
+        assert!(result.closed);
+        assert!(result.points.len() > 8);
+        for p in &result.points {
+            assert!((p.x.hypot(p.y) - 5.0).abs() < 0.1);
+        }
+    }
+}
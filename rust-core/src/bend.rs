@@ -0,0 +1,220 @@
+use std::f64::consts::TAU;
+
+use wasm_bindgen::prelude::*;
+
+use crate::{CadError, Point, Polyline, Vector, EPSILON};
+
+// Segments used to draw a full turn of bend
+const ARC_SEGMENTS: usize = 64;
+
+// Sheet cross-section around a bend. The bend line runs across the sheet from
+// its inner surface in `u`; the sheet runs along `a`, to the right of `u`,
+// and the part beyond the line (positive `a`) is the flange. The flange
+// folds away from `u` around `center`, which sits `radius` behind the inner
+// surface.
+struct BendFrame {
+    origin: Point,
+    u: Vector,
+    a: Vector,
+    center: Point,
+    radius: f64,
+    angle: f64,
+    // Flat length consumed by the bend: the neutral fibre's arc length
+    allowance: f64,
+    thickness: f64,
+}
+
+fn add(p: &Point, v: &Vector, f: f64) -> Point {
+    Point::new(p.x + v.x * f, p.y + v.y * f)
+}
+
+fn dot(x: f64, y: f64, v: &Vector) -> f64 {
+    x * v.x + y * v.y
+}
+
+impl BendFrame {
+    fn new(bend_line: (Point, Vector), radius: f64, angle: f64, k_factor: f64, thickness: f64) -> Result<BendFrame, CadError> {
+        if !(radius.is_finite() && radius > 0.0) {
+            return Err(CadError::InvalidInput(format!("bend radius must be positive, got {}", radius)));
+        }
+        if !(angle > 0.0 && angle < TAU) {
+            return Err(CadError::InvalidInput(format!("bend angle must be within (0, 2π), got {}", angle)));
+        }
+        if !((0.0..=1.0).contains(&k_factor) && thickness >= 0.0 && thickness.is_finite()) {
+            return Err(CadError::InvalidInput(format!("invalid k-factor {} or thickness {}", k_factor, thickness)));
+        }
+        let u = bend_line.1.try_normalize().ok_or_else(|| CadError::Degenerate("bend line direction is zero".to_string()))?;
+        let origin = bend_line.0;
+        Ok(BendFrame {
+            origin,
+            u,
+            a: Vector::new(u.y, -u.x),
+            center: add(&origin, &u, -radius),
+            radius,
+            angle,
+            allowance: angle * (radius + k_factor * thickness),
+            thickness,
+        })
+    }
+
+    // Flat coordinates: distance past the bend line, and depth from the
+    // inner surface
+    fn flat_coordinates(&self, p: &Point) -> (f64, f64) {
+        let (x, y) = (p.x - self.origin.x, p.y - self.origin.y);
+        (dot(x, y, &self.a), dot(x, y, &self.u))
+    }
+
+    // Radial and sheet directions once the bend has turned by `phi`
+    fn turned(&self, phi: f64) -> (Vector, Vector) {
+        let (c, s) = (phi.cos(), phi.sin());
+        (
+            Vector::new(self.u.x * c + self.a.x * s, self.u.y * c + self.a.y * s),
+            Vector::new(self.a.x * c - self.u.x * s, self.a.y * c - self.u.y * s),
+        )
+    }
+
+    fn bend(&self, p: &Point) -> Point {
+        let (s, t) = self.flat_coordinates(p);
+        if s <= 0.0 {
+            return *p;
+        }
+        let phi = self.angle * (s / self.allowance).min(1.0);
+        let (radial, along) = self.turned(phi);
+        let on_arc = add(&self.center, &radial, self.radius + t);
+        add(&on_arc, &along, (s - self.allowance).max(0.0))
+    }
+
+    fn unbend(&self, q: &Point) -> Point {
+        let (x, y) = (q.x - self.center.x, q.y - self.center.y);
+        let r = x.hypot(y);
+        let tolerance = EPSILON * self.radius.max(1.0);
+        let depth_ok = |t: f64| t >= -tolerance && t <= self.thickness + tolerance;
+
+        // Inside the bend zone
+        let psi = dot(x, y, &self.a).atan2(dot(x, y, &self.u));
+        if psi >= -tolerance && psi <= self.angle + tolerance && depth_ok(r - self.radius) {
+            let s = self.allowance * (psi / self.angle).clamp(0.0, 1.0);
+            return add(&add(&self.origin, &self.a, s), &self.u, r - self.radius);
+        }
+        // On the flat side of the bend line, within the sheet. The depth
+        // check keeps flanges folded back past the bend line out of here.
+        let (s, t) = self.flat_coordinates(q);
+        if s <= 0.0 && depth_ok(t) {
+            return *q;
+        }
+        // On the flange
+        let (radial, along) = self.turned(self.angle);
+        let (s, t) = (dot(x, y, &along), dot(x, y, &radial) - self.radius);
+        add(&add(&self.origin, &self.a, self.allowance + s.max(0.0)), &self.u, t)
+    }
+}
+
+// Folds the part of a flat sheet section beyond the bend line onto the
+// rotated flange. The neutral fibre, at `k_factor` of the thickness from the
+// inner surface, keeps its length through the bend. Segments crossing the
+// bend zone are subdivided so the bend comes out as an arc.
+pub fn bend_outline(outline: &Polyline, bend_line: (Point, Vector), radius: f64, angle: f64, k_factor: f64, thickness: f64) -> Result<Polyline, CadError> {
+    let frame = BendFrame::new(bend_line, radius, angle, k_factor, thickness)?;
+    let steps = ((ARC_SEGMENTS as f64 * angle / TAU).ceil() as usize).max(1);
+    let step = frame.allowance / steps as f64;
+
+    let points = &outline.points;
+    let n = points.len();
+    let mut bent = Vec::new();
+    if let Some(first) = points.first() {
+        bent.push(frame.bend(first));
+    }
+    let segments = if outline.closed && n > 2 { n } else { n.saturating_sub(1) };
+    for i in 0..segments {
+        let (p, q) = (points[i], points[(i + 1) % n]);
+        let (sp, sq) = (frame.flat_coordinates(&p).0, frame.flat_coordinates(&q).0);
+        // Every step boundary of the bend zone strictly inside the segment,
+        // including both ends of the zone
+        let (lo, hi) = (sp.min(sq), sp.max(sq));
+        let mut cuts: Vec<f64> = (0..=steps).map(|k| k as f64 * step)
+            .filter(|s| *s > lo && *s < hi)
+            .map(|s| (s - sp) / (sq - sp))
+            .collect();
+        cuts.retain(|w| *w > EPSILON && *w < 1.0 - EPSILON);
+        cuts.sort_by(f64::total_cmp);
+        cuts.dedup_by(|a, b| (*a - *b).abs() < EPSILON);
+        for w in cuts {
+            bent.push(frame.bend(&Point::new(p.x + (q.x - p.x) * w, p.y + (q.y - p.y) * w)));
+        }
+        // The closing segment ends back at the first point
+        if i + 1 < n {
+            bent.push(frame.bend(&q));
+        }
+    }
+    Ok(Polyline::new(bent, outline.closed))
+}
+
+// Inverse of `bend_outline` with the same parameters: points in the bend zone
+// go back to the flat by arc position, flange points by distance along it.
+// Points are expected within the sheet's thickness; a flange folded flat
+// onto the sheet itself (a hem) can't be told apart from it.
+pub fn unbend_outline(outline: &Polyline, bend_line: (Point, Vector), radius: f64, angle: f64, k_factor: f64, thickness: f64) -> Result<Polyline, CadError> {
+    let frame = BendFrame::new(bend_line, radius, angle, k_factor, thickness)?;
+    Ok(Polyline::new(outline.points.iter().map(|q| frame.unbend(q)).collect(), outline.closed))
+}
+
+fn polyline_from(outline: &JsValue) -> Result<Polyline, JsValue> {
+    crate::from_js(outline)
+}
+
+#[wasm_bindgen(js_name = bend_outline)]
+#[allow(clippy::too_many_arguments)]
+pub fn bend_outline_js(outline: &JsValue, x: f64, y: f64, dx: f64, dy: f64, radius: f64, angle: f64, k_factor: f64, thickness: f64) -> Result<JsValue, JsValue> {
+    let bent = bend_outline(&polyline_from(outline)?, (Point::new(x, y), Vector::new(dx, dy)), radius, angle, k_factor, thickness)
+        .map_err(crate::js_error)?;
+    crate::to_js(&bent)
+}
+
+#[wasm_bindgen(js_name = unbend_outline)]
+#[allow(clippy::too_many_arguments)]
+pub fn unbend_outline_js(outline: &JsValue, x: f64, y: f64, dx: f64, dy: f64, radius: f64, angle: f64, k_factor: f64, thickness: f64) -> Result<JsValue, JsValue> {
+    let flat = unbend_outline(&polyline_from(outline)?, (Point::new(x, y), Vector::new(dx, dy)), radius, angle, k_factor, thickness)
+        .map_err(crate::js_error)?;
+    crate::to_js(&flat)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_2;
+
+    use super::*;
+
+    fn bend_line() -> (Point, Vector) {
+        (Point::new(50.0, 0.0), Vector::new(0.0, 1.0))
+    }
+
+    #[test]
+    fn test_right_angle_bend_round_trip() {
+        let line = Polyline::new(vec![Point::new(0.0, 0.0), Point::new(100.0, 0.0)], false);
+        let bent = bend_outline(&line, bend_line(), 5.0, FRAC_PI_2, 0.44, 1.0).unwrap();
+        assert!(bent.points.len() > 10);
+
+        // The inner surface runs round the inside radius, then straight down
+        let allowance = FRAC_PI_2 * (5.0 + 0.44);
+        let end = bent.points.last().unwrap();
+        assert!((end.x - 55.0).abs() < 1e-9 && (end.y - (-5.0 - 50.0 + allowance)).abs() < 1e-9);
+        assert!((bent.length() - (100.0 - allowance + FRAC_PI_2 * 5.0)).abs() < 0.01);
+
+        let flat = unbend_outline(&bent, bend_line(), 5.0, FRAC_PI_2, 0.44, 1.0).unwrap();
+        assert!((flat.length() - 100.0).abs() < 1e-6);
+        assert!(flat.points.iter().all(|p| p.y.abs() < 1e-9));
+    }
+
+    #[test]
+    fn test_section_outline_keeps_neutral_length() {
+        // 2-thick sheet section; the neutral fibre sits at 0.5 of the thickness
+        let section = Polyline::new(vec![Point::new(0.0, 0.0), Point::new(80.0, 0.0), Point::new(80.0, 2.0), Point::new(0.0, 2.0)], true);
+        let bent = bend_outline(&section, (Point::new(30.0, 0.0), Vector::new(0.0, 1.0)), 3.0, 2.0, 0.5, 2.0).unwrap();
+        assert!(bent.closed);
+        let flat = unbend_outline(&bent, (Point::new(30.0, 0.0), Vector::new(0.0, 1.0)), 3.0, 2.0, 0.5, 2.0).unwrap();
+        assert!((flat.length() - section.length()).abs() < 1e-6);
+
+        assert!(bend_outline(&section, bend_line(), 0.0, 1.0, 0.5, 1.0).is_err());
+        assert!(bend_outline(&section, bend_line(), 1.0, 7.0, 0.5, 1.0).is_err());
+    }
+}
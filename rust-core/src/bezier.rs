@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{BoundingBox, Point, Vector};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct CubicBezier {
+    pub p0: Point,
+    pub p1: Point,
+    pub p2: Point,
+    pub p3: Point,
+}
+
+fn lerp(a: &Point, b: &Point, t: f64) -> Point {
+    Point::new(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t)
+}
+
+impl CubicBezier {
+    pub fn new(p0: Point, p1: Point, p2: Point, p3: Point) -> CubicBezier {
+        CubicBezier { p0, p1, p2, p3 }
+    }
+
+    pub fn point_at(&self, t: f64) -> Point {
+        self.split(t).1.p0
+    }
+
+    pub fn derivative_at(&self, t: f64) -> Vector {
+        let s = 1.0 - t;
+        let (a, b, c) = (s * s * 3.0, s * t * 6.0, t * t * 3.0);
+        Vector::new(
+            a * (self.p1.x - self.p0.x) + b * (self.p2.x - self.p1.x) + c * (self.p3.x - self.p2.x),
+            a * (self.p1.y - self.p0.y) + b * (self.p2.y - self.p1.y) + c * (self.p3.y - self.p2.y),
+        )
+    }
+
+    // The curve before and after `t`, by de Casteljau
+    pub fn split(&self, t: f64) -> (CubicBezier, CubicBezier) {
+        let (a, b, c) = (lerp(&self.p0, &self.p1, t), lerp(&self.p1, &self.p2, t), lerp(&self.p2, &self.p3, t));
+        let (d, e) = (lerp(&a, &b, t), lerp(&b, &c, t));
+        let mid = lerp(&d, &e, t);
+        (CubicBezier::new(self.p0, a, d, mid), CubicBezier::new(mid, e, c, self.p3))
+    }
+
+    // The part of the curve from `t0` to `t1`
+    pub fn portion(&self, t0: f64, t1: f64) -> CubicBezier {
+        if t1 <= t0 {
+            let p = self.point_at(t0);
+            return CubicBezier::new(p, p, p, p);
+        }
+        let tail = self.split(t0).1;
+        if t0 >= 1.0 {
+            return tail;
+        }
+        tail.split((t1 - t0) / (1.0 - t0)).0
+    }
+
+    // Box of the control points, which holds the curve
+    pub fn control_box(&self) -> BoundingBox {
+        BoundingBox::from_points(&[self.p0, self.p1, self.p2, self.p3])
+    }
+}
@@ -0,0 +1,564 @@
+use std::fmt;
+
+use serde::de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, Visitor};
+use serde::ser::{self, Serialize};
+
+use crate::CadError;
+
+// Compact binary form of any serde type, for autosave chunks. Each value is
+// a tag byte naming its kind, then integers and floats little-endian at
+// their own width, strings, sequences and maps after a u64 length, and enum
+// variants by index. Structs and tuples are sequences with no field names,
+// so they only read back into the type that wrote them; the tags are enough
+// for untagged enums such as `AttributeValue`.
+pub(crate) fn to_bytes<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, CadError> {
+    let mut out = Writer(Vec::new());
+    value.serialize(&mut out).map_err(|e| CadError::Serialization(e.0))?;
+    Ok(out.0)
+}
+
+pub(crate) fn from_bytes<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, CadError> {
+    let mut reader = Reader(bytes);
+    let value = T::deserialize(&mut reader).map_err(|e| CadError::Serialization(e.0))?;
+    if !reader.0.is_empty() {
+        return Err(CadError::Serialization(format!("{} trailing bytes", reader.0.len())));
+    }
+    Ok(value)
+}
+
+#[derive(Debug)]
+pub(crate) struct Error(String);
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Error {
+        Error(msg.to_string())
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Error {
+        Error(msg.to_string())
+    }
+}
+
+const BOOL: u8 = 0;
+const I8: u8 = 1;
+const I16: u8 = 2;
+const I32: u8 = 3;
+const I64: u8 = 4;
+const U8: u8 = 5;
+const U16: u8 = 6;
+const U32: u8 = 7;
+const U64: u8 = 8;
+const F32: u8 = 9;
+const F64: u8 = 10;
+const CHAR: u8 = 11;
+const STR: u8 = 12;
+const BYTES: u8 = 13;
+const NONE: u8 = 14;
+const SOME: u8 = 15;
+const UNIT: u8 = 16;
+const SEQ: u8 = 17;
+const MAP: u8 = 18;
+const VARIANT: u8 = 19;
+
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn value(&mut self, tag: u8, bytes: &[u8]) -> Result<(), Error> {
+        self.0.push(tag);
+        self.0.extend(bytes);
+        Ok(())
+    }
+
+    fn len(&mut self, tag: u8, n: Option<usize>) -> Result<(), Error> {
+        let n = n.ok_or_else(|| Error("sequence of unknown length".to_string()))?;
+        self.value(tag, &(n as u64).to_le_bytes())
+    }
+
+    fn variant(&mut self, index: u32) -> Result<(), Error> {
+        self.value(VARIANT, &index.to_le_bytes())
+    }
+}
+
+impl ser::Serializer for &mut Writer {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+
+    fn serialize_bool(self, v: bool) -> Result<(), Error> {
+        self.value(BOOL, &[v as u8])
+    }
+    fn serialize_i8(self, v: i8) -> Result<(), Error> {
+        self.value(I8, &v.to_le_bytes())
+    }
+    fn serialize_i16(self, v: i16) -> Result<(), Error> {
+        self.value(I16, &v.to_le_bytes())
+    }
+    fn serialize_i32(self, v: i32) -> Result<(), Error> {
+        self.value(I32, &v.to_le_bytes())
+    }
+    fn serialize_i64(self, v: i64) -> Result<(), Error> {
+        self.value(I64, &v.to_le_bytes())
+    }
+    fn serialize_u8(self, v: u8) -> Result<(), Error> {
+        self.value(U8, &[v])
+    }
+    fn serialize_u16(self, v: u16) -> Result<(), Error> {
+        self.value(U16, &v.to_le_bytes())
+    }
+    fn serialize_u32(self, v: u32) -> Result<(), Error> {
+        self.value(U32, &v.to_le_bytes())
+    }
+    fn serialize_u64(self, v: u64) -> Result<(), Error> {
+        self.value(U64, &v.to_le_bytes())
+    }
+    fn serialize_f32(self, v: f32) -> Result<(), Error> {
+        self.value(F32, &v.to_le_bytes())
+    }
+    fn serialize_f64(self, v: f64) -> Result<(), Error> {
+        self.value(F64, &v.to_le_bytes())
+    }
+    fn serialize_char(self, v: char) -> Result<(), Error> {
+        self.value(CHAR, &(v as u32).to_le_bytes())
+    }
+    fn serialize_str(self, v: &str) -> Result<(), Error> {
+        self.len(STR, Some(v.len()))?;
+        self.0.extend(v.as_bytes());
+        Ok(())
+    }
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), Error> {
+        self.len(BYTES, Some(v.len()))?;
+        self.0.extend(v);
+        Ok(())
+    }
+    fn serialize_none(self) -> Result<(), Error> {
+        self.value(NONE, &[])
+    }
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), Error> {
+        self.value(SOME, &[])?;
+        value.serialize(self)
+    }
+    fn serialize_unit(self) -> Result<(), Error> {
+        self.value(UNIT, &[])
+    }
+    fn serialize_unit_struct(self, _: &'static str) -> Result<(), Error> {
+        self.value(UNIT, &[])
+    }
+    fn serialize_unit_variant(self, _: &'static str, index: u32, _: &'static str) -> Result<(), Error> {
+        self.variant(index)
+    }
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _: &'static str, value: &T) -> Result<(), Error> {
+        value.serialize(self)
+    }
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _: &'static str, index: u32, _: &'static str, value: &T) -> Result<(), Error> {
+        self.variant(index)?;
+        value.serialize(self)
+    }
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self, Error> {
+        self.len(SEQ, len)?;
+        Ok(self)
+    }
+    fn serialize_tuple(self, len: usize) -> Result<Self, Error> {
+        self.len(SEQ, Some(len))?;
+        Ok(self)
+    }
+    fn serialize_tuple_struct(self, _: &'static str, len: usize) -> Result<Self, Error> {
+        self.len(SEQ, Some(len))?;
+        Ok(self)
+    }
+    fn serialize_tuple_variant(self, _: &'static str, index: u32, _: &'static str, len: usize) -> Result<Self, Error> {
+        self.variant(index)?;
+        self.len(SEQ, Some(len))?;
+        Ok(self)
+    }
+    fn serialize_map(self, len: Option<usize>) -> Result<Self, Error> {
+        self.len(MAP, len)?;
+        Ok(self)
+    }
+    fn serialize_struct(self, _: &'static str, len: usize) -> Result<Self, Error> {
+        self.len(SEQ, Some(len))?;
+        Ok(self)
+    }
+    fn serialize_struct_variant(self, _: &'static str, index: u32, _: &'static str, len: usize) -> Result<Self, Error> {
+        self.variant(index)?;
+        self.len(SEQ, Some(len))?;
+        Ok(self)
+    }
+}
+
+impl ser::SerializeSeq for &mut Writer {
+    type Ok = ();
+    type Error = Error;
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(&mut **self)
+    }
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTuple for &mut Writer {
+    type Ok = ();
+    type Error = Error;
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(&mut **self)
+    }
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTupleStruct for &mut Writer {
+    type Ok = ();
+    type Error = Error;
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(&mut **self)
+    }
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTupleVariant for &mut Writer {
+    type Ok = ();
+    type Error = Error;
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(&mut **self)
+    }
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeMap for &mut Writer {
+    type Ok = ();
+    type Error = Error;
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        key.serialize(&mut **self)
+    }
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(&mut **self)
+    }
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeStruct for &mut Writer {
+    type Ok = ();
+    type Error = Error;
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, _: &'static str, value: &T) -> Result<(), Error> {
+        value.serialize(&mut **self)
+    }
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeStructVariant for &mut Writer {
+    type Ok = ();
+    type Error = Error;
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, _: &'static str, value: &T) -> Result<(), Error> {
+        value.serialize(&mut **self)
+    }
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+struct Reader<'de>(&'de [u8]);
+
+impl<'de> Reader<'de> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        if self.0.len() < N {
+            return Err(Error("unexpected end of data".to_string()));
+        }
+        let (head, rest) = self.0.split_at(N);
+        self.0 = rest;
+        Ok(head.try_into().unwrap())
+    }
+
+    fn peek(&self) -> Result<u8, Error> {
+        self.0.first().copied().ok_or_else(|| Error("unexpected end of data".to_string()))
+    }
+
+    fn expect(&mut self, tag: u8) -> Result<(), Error> {
+        match self.take::<1>()?[0] {
+            t if t == tag => Ok(()),
+            t => Err(Error(format!("expected tag {}, found {}", tag, t))),
+        }
+    }
+
+    fn len(&mut self, tag: u8) -> Result<usize, Error> {
+        self.expect(tag)?;
+        let n = u64::from_le_bytes(self.take()?);
+        // Every element takes at least its tag byte
+        if n > self.0.len() as u64 {
+            return Err(Error(format!("length {} runs past the end of the data", n)));
+        }
+        Ok(n as usize)
+    }
+
+    fn bytes(&mut self, tag: u8) -> Result<&'de [u8], Error> {
+        let n = self.len(tag)?;
+        let (head, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    // A sequence that must hold exactly `len` values, as a struct or tuple
+    fn fixed(&mut self, len: usize) -> Result<usize, Error> {
+        match self.len(SEQ)? {
+            n if n == len => Ok(n),
+            n => Err(Error(format!("expected {} fields, found {}", len, n))),
+        }
+    }
+}
+
+// Hands out a known number of values in turn, for sequences, maps, tuples
+// and struct fields alike
+struct Counted<'a, 'de> {
+    reader: &'a mut Reader<'de>,
+    left: usize,
+}
+
+impl<'de> de::SeqAccess<'de> for Counted<'_, 'de> {
+    type Error = Error;
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, Error> {
+        if self.left == 0 {
+            return Ok(None);
+        }
+        self.left -= 1;
+        seed.deserialize(&mut *self.reader).map(Some)
+    }
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.left)
+    }
+}
+
+impl<'de> de::MapAccess<'de> for Counted<'_, 'de> {
+    type Error = Error;
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, Error> {
+        if self.left == 0 {
+            return Ok(None);
+        }
+        self.left -= 1;
+        seed.deserialize(&mut *self.reader).map(Some)
+    }
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        seed.deserialize(&mut *self.reader)
+    }
+}
+
+impl<'de> de::EnumAccess<'de> for &mut Reader<'de> {
+    type Error = Error;
+    type Variant = Self;
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self), Error> {
+        self.expect(VARIANT)?;
+        let index = u32::from_le_bytes(self.take()?);
+        let value = seed.deserialize(index.into_deserializer())?;
+        Ok((value, self))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for &mut Reader<'de> {
+    type Error = Error;
+    fn unit_variant(self) -> Result<(), Error> {
+        Ok(())
+    }
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        seed.deserialize(self)
+    }
+    fn tuple_variant<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, Error> {
+        let left = self.fixed(len)?;
+        visitor.visit_seq(Counted { reader: self, left })
+    }
+    fn struct_variant<V: Visitor<'de>>(self, fields: &'static [&'static str], visitor: V) -> Result<V::Value, Error> {
+        let left = self.fixed(fields.len())?;
+        visitor.visit_seq(Counted { reader: self, left })
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &mut Reader<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.peek()? {
+            BOOL => self.deserialize_bool(visitor),
+            I8 => self.deserialize_i8(visitor),
+            I16 => self.deserialize_i16(visitor),
+            I32 => self.deserialize_i32(visitor),
+            I64 => self.deserialize_i64(visitor),
+            U8 => self.deserialize_u8(visitor),
+            U16 => self.deserialize_u16(visitor),
+            U32 => self.deserialize_u32(visitor),
+            U64 => self.deserialize_u64(visitor),
+            F32 => self.deserialize_f32(visitor),
+            F64 => self.deserialize_f64(visitor),
+            CHAR => self.deserialize_char(visitor),
+            STR => self.deserialize_str(visitor),
+            BYTES => self.deserialize_bytes(visitor),
+            NONE | SOME => self.deserialize_option(visitor),
+            UNIT => self.deserialize_unit(visitor),
+            SEQ => self.deserialize_seq(visitor),
+            MAP => self.deserialize_map(visitor),
+            VARIANT => visitor.visit_enum(self),
+            t => Err(Error(format!("unknown tag {}", t))),
+        }
+    }
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.expect(BOOL)?;
+        match self.take::<1>()?[0] {
+            0 => visitor.visit_bool(false),
+            1 => visitor.visit_bool(true),
+            b => Err(Error(format!("invalid bool {}", b))),
+        }
+    }
+    fn deserialize_i8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.expect(I8)?;
+        visitor.visit_i8(i8::from_le_bytes(self.take()?))
+    }
+    fn deserialize_i16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.expect(I16)?;
+        visitor.visit_i16(i16::from_le_bytes(self.take()?))
+    }
+    fn deserialize_i32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.expect(I32)?;
+        visitor.visit_i32(i32::from_le_bytes(self.take()?))
+    }
+    fn deserialize_i64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.expect(I64)?;
+        visitor.visit_i64(i64::from_le_bytes(self.take()?))
+    }
+    fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.expect(U8)?;
+        visitor.visit_u8(self.take::<1>()?[0])
+    }
+    fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.expect(U16)?;
+        visitor.visit_u16(u16::from_le_bytes(self.take()?))
+    }
+    fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.expect(U32)?;
+        visitor.visit_u32(u32::from_le_bytes(self.take()?))
+    }
+    fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.expect(U64)?;
+        visitor.visit_u64(u64::from_le_bytes(self.take()?))
+    }
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.expect(F32)?;
+        visitor.visit_f32(f32::from_le_bytes(self.take()?))
+    }
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.expect(F64)?;
+        visitor.visit_f64(f64::from_le_bytes(self.take()?))
+    }
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.expect(CHAR)?;
+        let code = u32::from_le_bytes(self.take()?);
+        visitor.visit_char(char::from_u32(code).ok_or_else(|| Error(format!("invalid char {}", code)))?)
+    }
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let text = std::str::from_utf8(self.bytes(STR)?).map_err(|e| Error(e.to_string()))?;
+        visitor.visit_borrowed_str(text)
+    }
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_str(visitor)
+    }
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_borrowed_bytes(self.bytes(BYTES)?)
+    }
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_bytes(visitor)
+    }
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.take::<1>()?[0] {
+            NONE => visitor.visit_none(),
+            SOME => visitor.visit_some(self),
+            t => Err(Error(format!("expected an option, found tag {}", t))),
+        }
+    }
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.expect(UNIT)?;
+        visitor.visit_unit()
+    }
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, _: &'static str, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_unit(visitor)
+    }
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _: &'static str, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let left = self.len(SEQ)?;
+        visitor.visit_seq(Counted { reader: self, left })
+    }
+    fn deserialize_tuple<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, Error> {
+        let left = self.fixed(len)?;
+        visitor.visit_seq(Counted { reader: self, left })
+    }
+    fn deserialize_tuple_struct<V: Visitor<'de>>(self, _: &'static str, len: usize, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_tuple(len, visitor)
+    }
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let left = self.len(MAP)?;
+        visitor.visit_map(Counted { reader: self, left })
+    }
+    fn deserialize_struct<V: Visitor<'de>>(self, _: &'static str, fields: &'static [&'static str], visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_tuple(fields.len(), visitor)
+    }
+    fn deserialize_enum<V: Visitor<'de>>(self, _: &'static str, _: &'static [&'static str], visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_enum(self)
+    }
+    fn deserialize_identifier<V: Visitor<'de>>(self, _: V) -> Result<V::Value, Error> {
+        Err(Error("field names are not stored".to_string()))
+    }
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_any(visitor)
+    }
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::{AttributeValue, Document, Point, Polygon};
+
+    #[test]
+    fn test_round_trip_and_truncation() {
+        let mut doc = Document::new();
+        let id = doc.add_shape(Polygon::new(vec![Point::new(0.0, 0.0), Point::new(1.5, 0.0), Point::new(0.0, -2.25)]));
+        doc.set_attribute(id, "part", AttributeValue::Text("bracket é".to_string())).unwrap();
+        doc.set_label(id, Some("A".to_string())).unwrap();
+        let bytes = to_bytes(&doc).unwrap();
+        let back: Document = from_bytes(&bytes).unwrap();
+        assert_eq!(back.content_hash_all(), doc.content_hash_all());
+
+        let map: BTreeMap<String, Option<(u8, f64)>> = [("a".to_string(), Some((3, -0.5))), ("b".to_string(), None)].into_iter().collect();
+        assert_eq!(from_bytes::<BTreeMap<String, Option<(u8, f64)>>>(&to_bytes(&map).unwrap()).unwrap(), map);
+        // Cut short, or with a length running off the end, fails cleanly
+        assert!(from_bytes::<Document>(&bytes[..bytes.len() - 3]).is_err());
+        let mut huge = bytes.clone();
+        huge[2..10].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(from_bytes::<Document>(&huge).is_err());
+    }
+}
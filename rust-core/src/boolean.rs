@@ -0,0 +1,216 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::arrangement::overlay;
+use crate::predicates::{orient2d, ring_signed_area};
+use crate::{CadError, MultiPolygon, Point, Polygon, PolygonWithHoles};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BooleanOp {
+    Union,
+    Intersection,
+    // The first polygon less the second
+    Difference,
+}
+
+impl BooleanOp {
+    pub fn parse(name: &str) -> Result<BooleanOp, CadError> {
+        match name {
+            "union" => Ok(BooleanOp::Union),
+            "intersection" => Ok(BooleanOp::Intersection),
+            "difference" => Ok(BooleanOp::Difference),
+            _ => Err(CadError::InvalidInput(format!("unknown boolean op '{}'", name))),
+        }
+    }
+
+    pub(crate) fn keeps(self, in_a: bool, in_b: bool) -> bool {
+        match self {
+            BooleanOp::Union => in_a || in_b,
+            BooleanOp::Intersection => in_a && in_b,
+            BooleanOp::Difference => in_a && !in_b,
+        }
+    }
+}
+
+fn ring(polygon: &Polygon) -> Vec<Vec<Point>> {
+    if polygon.vertices.len() >= 3 && ring_signed_area(&polygon.vertices).abs() > 0.0 {
+        vec![polygon.vertices.clone()]
+    } else {
+        vec![]
+    }
+}
+
+// Whether segments pq and ab cross at a point interior to both
+fn crosses(p: &Point, q: &Point, a: &Point, b: &Point) -> bool {
+    let (d1, d2) = (orient2d(p, q, a), orient2d(p, q, b));
+    let (d3, d4) = (orient2d(a, b, p), orient2d(a, b, q));
+    d1 * d2 < 0.0 && d3 * d4 < 0.0
+}
+
+// Region as one ring: each hole joined to the boundary by a cut there and
+// back along the same segment, so the ring runs round the hole the other
+// way and its area is the region's. Holes are taken rightmost first, each
+// cut from the hole's rightmost vertex to the nearest boundary vertex it
+// can see without crossing the boundary or a hole still to come.
+pub(crate) fn keyhole(region: PolygonWithHoles) -> Polygon {
+    let mut ring = region.outer.vertices;
+    let rightmost = |h: &[Point]| (0..h.len()).max_by(|&i, &j| h[i].x.total_cmp(&h[j].x)).unwrap();
+    let mut holes: Vec<Vec<Point>> = region.holes.into_iter().map(|h| h.vertices).filter(|h| h.len() >= 3).collect();
+    holes.sort_by(|a, b| b[rightmost(b)].x.total_cmp(&a[rightmost(a)].x));
+    for k in 0..holes.len() {
+        let hole = &holes[k];
+        let m = rightmost(hole);
+        let from = hole[m];
+        let blocked = |to: &Point| {
+            let edges = |r: &[Point]| (0..r.len()).map(move |i| (r[i], r[(i + 1) % r.len()])).collect::<Vec<_>>();
+            std::iter::once(&ring).chain(&holes[k..]).flat_map(|r| edges(r))
+                .any(|(a, b)| crosses(&from, to, &a, &b))
+        };
+        let distance2 = |p: &Point| (p.x - from.x).powi(2) + (p.y - from.y).powi(2);
+        let mut order: Vec<usize> = (0..ring.len()).collect();
+        order.sort_by(|&i, &j| distance2(&ring[i]).total_cmp(&distance2(&ring[j])));
+        let Some(&v) = order.iter().find(|&&i| !blocked(&ring[i])) else {
+            continue;
+        };
+        let mut spliced = ring[..=v].to_vec();
+        spliced.extend((0..=hole.len()).map(|i| hole[(m + i) % hole.len()]));
+        spliced.extend_from_slice(&ring[v..]);
+        ring = spliced;
+    }
+    Polygon::new(ring)
+}
+
+impl Polygon {
+    // Regions covered by `op` of the two polygons, holes kept apart. Inputs
+    // shouldn't cross themselves; shared edges and vertices are fine.
+    pub fn boolean_regions(&self, other: &Polygon, op: BooleanOp) -> Vec<PolygonWithHoles> {
+        overlay(&ring(self), &ring(other), |a, b| op.keeps(a, b))
+    }
+
+    // `op` of the two polygons as simple rings, any hole joined to its
+    // outline by a keyhole cut
+    pub fn boolean(&self, other: &Polygon, op: BooleanOp) -> MultiPolygon {
+        self.boolean_regions(other, op).into_iter().map(keyhole).collect()
+    }
+
+    pub fn union(&self, other: &Polygon) -> MultiPolygon {
+        self.boolean(other, BooleanOp::Union)
+    }
+
+    pub fn intersection(&self, other: &Polygon) -> MultiPolygon {
+        self.boolean(other, BooleanOp::Intersection)
+    }
+
+    pub fn difference(&self, other: &Polygon) -> MultiPolygon {
+        self.boolean(other, BooleanOp::Difference)
+    }
+}
+
+// `op` is "union", "intersection" or "difference"; returns an array of
+// vertex arrays
+#[wasm_bindgen]
+pub fn boolean_op(vertices_a: &JsValue, vertices_b: &JsValue, op: &str) -> Result<JsValue, JsValue> {
+    let a: Polygon = crate::polygon_from(vertices_a)?;
+    let b: Polygon = crate::polygon_from(vertices_b)?;
+    let op = BooleanOp::parse(op).map_err(crate::js_error)?;
+    let regions = crate::strict::checked_regions(a.boolean_regions(&b, op), "boolean").map_err(crate::js_error)?;
+    let rings: Vec<Vec<Point>> = regions.into_iter().map(|r| keyhole(r).vertices).collect();
+    crate::to_js(&rings)
+}
+
+// Union of any number of polygons: one region per separate part
+pub fn union_all_regions(polygons: &[Polygon]) -> Vec<PolygonWithHoles> {
+    let rings: Vec<Vec<Point>> = polygons.iter().filter(|p| p.vertices.len() >= 3).map(|p| p.vertices.clone()).collect();
+    overlay(&rings, &[], |a, _| a)
+}
+
+// `union_all_regions` as simple rings, holes keyholed as for `boolean`
+pub fn union_all(polygons: &[Polygon]) -> MultiPolygon {
+    union_all_regions(polygons).into_iter().map(keyhole).collect()
+}
+
+// `polygons` is an array of vertex arrays; returns an array of vertex arrays
+#[wasm_bindgen(js_name = union_all)]
+pub fn union_all_js(polygons: &JsValue) -> Result<JsValue, JsValue> {
+    let polygons: Vec<Polygon> = crate::from_js(polygons)?;
+    let regions = crate::strict::checked_regions(union_all_regions(&polygons), "union").map_err(crate::js_error)?;
+    let rings: Vec<Vec<Point>> = regions.into_iter().map(|r| keyhole(r).vertices).collect();
+    crate::to_js(&rings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::predicates::point_in_ring;
+
+    fn rect(x0: f64, y0: f64, x1: f64, y1: f64) -> Polygon {
+        Polygon::new(vec![Point::new(x0, y0), Point::new(x1, y0), Point::new(x1, y1), Point::new(x0, y1)])
+    }
+
+    fn area(pieces: &MultiPolygon) -> f64 {
+        pieces.iter().map(|p| p.area()).sum()
+    }
+
+    #[test]
+    fn test_overlapping_and_disjoint_rectangles() {
+        let (a, b) = (rect(0.0, 0.0, 4.0, 3.0), rect(2.0, 1.0, 6.0, 5.0));
+        assert_eq!(a.union(&b).len(), 1);
+        assert!((area(&a.union(&b)) - 24.0).abs() < 1e-9);
+        assert!((area(&a.intersection(&b)) - 4.0).abs() < 1e-9);
+        assert!((area(&a.difference(&b)) - 8.0).abs() < 1e-9);
+        assert!((area(&b.difference(&a)) - 12.0).abs() < 1e-9);
+
+        let far = rect(10.0, 10.0, 11.0, 11.0);
+        assert_eq!(a.union(&far).len(), 2);
+        assert!(a.intersection(&far).is_empty());
+        assert!((area(&a.difference(&far)) - 12.0).abs() < 1e-9);
+        assert!(a.union(&Polygon::new(vec![])).len() == 1 && a.intersection(&Polygon::new(vec![])).is_empty());
+    }
+
+    #[test]
+    fn test_containment_keyholes_the_hole() {
+        let (outer, inner) = (rect(0.0, 0.0, 10.0, 10.0), rect(3.0, 4.0, 5.0, 6.0));
+        assert!((area(&outer.union(&inner)) - 100.0).abs() < 1e-9);
+        assert!((area(&outer.intersection(&inner)) - 4.0).abs() < 1e-9);
+        assert!(inner.difference(&outer).is_empty());
+
+        let holed = outer.difference(&inner);
+        assert_eq!(holed.len(), 1);
+        assert!((holed[0].area() - 96.0).abs() < 1e-9);
+        assert_eq!(outer.boolean_regions(&inner, BooleanOp::Difference)[0].holes.len(), 1);
+        assert!(!point_in_ring(&holed[0].vertices, &Point::new(4.0, 5.0)));
+        assert!(point_in_ring(&holed[0].vertices, &Point::new(8.0, 5.0)));
+        // The region still triangulates as one piece
+        let triangles: f64 = holed[0].triangulate().iter()
+            .map(|t| Polygon::new(t.iter().map(|&i| holed[0].vertices[i]).collect()).area())
+            .sum();
+        assert!((triangles - 96.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_touching_edges_and_vertices() {
+        let a = rect(0.0, 0.0, 2.0, 2.0);
+        // Sharing the whole edge x = 2: one piece, nothing in common
+        let side = rect(2.0, 0.0, 4.0, 2.0);
+        let joined = a.union(&side);
+        assert_eq!(joined.len(), 1);
+        assert!((area(&joined) - 8.0).abs() < 1e-9);
+        assert_eq!(joined[0].vertices.len(), 4);
+        assert!(a.intersection(&side).is_empty());
+        assert!((area(&a.difference(&side)) - 4.0).abs() < 1e-9);
+
+        // Meeting at the corner (2, 2) only
+        let corner = rect(2.0, 2.0, 3.0, 3.0);
+        let both = a.union(&corner);
+        assert!((area(&both) - 5.0).abs() < 1e-9);
+        assert!(a.intersection(&corner).is_empty());
+        // Partly collinear edge, and identical inputs
+        let shifted = rect(2.0, 1.0, 3.0, 5.0);
+        assert!((area(&a.union(&shifted)) - 8.0).abs() < 1e-9);
+        assert!((area(&a.intersection(&a)) - 4.0).abs() < 1e-9);
+        assert!(a.difference(&a).is_empty());
+        for piece in a.union(&corner).iter().chain(&a.union(&shifted)) {
+            assert!(piece.vertices.iter().all(|p| p.x.is_finite() && p.y.is_finite()));
+        }
+    }
+}
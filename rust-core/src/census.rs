@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::predicates::ring_signed_area;
+use crate::{Document, Point, Polygon, ShapeId, EPSILON};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CensusEntry {
+    pub template: usize,
+    pub count: usize,
+    // Shapes congruent to the template by rotation and translation
+    pub shapes: Vec<ShapeId>,
+    // Shapes congruent only to the template's mirror image; filled in only
+    // when mirrored matches are requested
+    pub mirrored: Vec<ShapeId>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Census {
+    pub entries: Vec<CensusEntry>,
+    pub unmatched: Vec<ShapeId>,
+}
+
+// Vertices counter-clockwise, so that the vertex order of the input doesn't
+// matter, only its shape
+pub(crate) fn counter_clockwise(vertices: &[Point]) -> Vec<Point> {
+    let mut ccw = vertices.to_vec();
+    if ring_signed_area(&ccw) < 0.0 {
+        ccw.reverse();
+    }
+    ccw
+}
+
+fn mirror(vertices: &[Point]) -> Vec<Point> {
+    counter_clockwise(&vertices.iter().map(|p| Point::new(-p.x, p.y)).collect::<Vec<_>>())
+}
+
+pub(crate) fn perimeter(vertices: &[Point]) -> f64 {
+    let n = vertices.len();
+    (0..n).map(|i| distance(&vertices[i], &vertices[(i + 1) % n])).sum()
+}
+
+fn distance(a: &Point, b: &Point) -> f64 {
+    (b.x - a.x).hypot(b.y - a.y)
+}
+
+// Whether some rotation and translation puts every vertex of `a` within
+// `tolerance` of the matching vertex of `b`, trying each vertex of `b` as the
+// image of a's first vertex. Both rings must be counter-clockwise.
+pub(crate) fn rigid_match(a: &[Point], b: &[Point], tolerance: f64) -> bool {
+    let n = a.len();
+    if n != b.len() || n < 2 {
+        return false;
+    }
+    let first = distance(&a[0], &a[1]);
+    let a_angle = (a[1].y - a[0].y).atan2(a[1].x - a[0].x);
+    (0..n).any(|k| {
+        let (b0, b1) = (b[k], b[(k + 1) % n]);
+        if (distance(&b0, &b1) - first).abs() > 2.0 * tolerance {
+            return false;
+        }
+        let theta = (b1.y - b0.y).atan2(b1.x - b0.x) - a_angle;
+        let (c, s) = (theta.cos(), theta.sin());
+        (0..n).all(|i| {
+            let (x, y) = (a[i].x - a[0].x, a[i].y - a[0].y);
+            let p = Point::new(b0.x + c * x - s * y, b0.y + s * x + c * y);
+            distance(&p, &b[(k + i) % n]) <= tolerance
+        })
+    })
+}
+
+// Candidate key: vertex count and perimeter bucket. Congruent rings within
+// `tolerance` per vertex differ in perimeter by at most 2 * n * tolerance, so
+// they land in the same or an adjacent bucket of that width.
+fn bucket(vertices: &[Point], tolerance: f64) -> (usize, i64) {
+    let n = vertices.len();
+    let width = 2.0 * n as f64 * tolerance;
+    (n, (perimeter(vertices) / width).floor() as i64)
+}
+
+// Counts the document shapes congruent to each template. Each shape is
+// bucketed once and only compared with templates in neighbouring buckets,
+// so the cost grows with the shape count rather than shapes × templates. A
+// shape matching several templates counts towards the first. Congruent
+// shapes must have corresponding vertices: an extra collinear vertex makes
+// a different outline.
+pub fn census(doc: &Document, templates: &[Polygon], tolerance: f64, include_mirrored: bool) -> Census {
+    let tolerance = tolerance.max(EPSILON);
+    let rings: Vec<Vec<Point>> = templates.iter().map(|t| counter_clockwise(&t.vertices)).collect();
+    let mirrors: Vec<Vec<Point>> = rings.iter().map(|r| mirror(r)).collect();
+    let mut buckets: HashMap<(usize, i64), Vec<usize>> = HashMap::new();
+    for (i, ring) in rings.iter().enumerate() {
+        buckets.entry(bucket(ring, tolerance)).or_default().push(i);
+    }
+
+    let mut entries: Vec<CensusEntry> = (0..templates.len())
+        .map(|template| CensusEntry { template, count: 0, shapes: Vec::new(), mirrored: Vec::new() })
+        .collect();
+    let mut unmatched = Vec::new();
+    for shape in doc.shapes() {
+        let ring = counter_clockwise(&shape.world_geometry().vertices);
+        let (n, key) = bucket(&ring, tolerance);
+        let mut candidates: Vec<usize> = (key - 1..=key + 1)
+            .filter_map(|k| buckets.get(&(n, k)))
+            .flatten()
+            .copied()
+            .collect();
+        candidates.sort_unstable();
+
+        if let Some(&t) = candidates.iter().find(|&&t| rigid_match(&rings[t], &ring, tolerance)) {
+            entries[t].shapes.push(shape.id);
+        } else if let Some(&t) = candidates.iter().find(|&&t| include_mirrored && rigid_match(&mirrors[t], &ring, tolerance)) {
+            entries[t].mirrored.push(shape.id);
+        } else {
+            unmatched.push(shape.id);
+        }
+    }
+    for entry in &mut entries {
+        entry.count = entry.shapes.len();
+    }
+    Census { entries, unmatched }
+}
+
+// Shapes congruent to `template` by rotation and translation, in id order,
+// comparing against every shape; see `ShapeSignatureIndex` for many queries
+// over one document
+pub fn find_congruent(doc: &Document, template: &Polygon, tolerance: f64) -> Vec<ShapeId> {
+    let tolerance = tolerance.max(EPSILON);
+    let ring = counter_clockwise(&template.vertices);
+    let mut ids: Vec<ShapeId> = doc.shapes().filter(|s| rigid_match(&ring, &counter_clockwise(&s.world_geometry().vertices), tolerance)).map(|s| s.id).collect();
+    ids.sort_unstable();
+    ids
+}
+
+#[wasm_bindgen(js_name = census)]
+pub fn census_js(doc: &Document, templates: &JsValue, tolerance: f64, include_mirrored: bool) -> Result<JsValue, JsValue> {
+    let templates: Vec<Polygon> = crate::from_js(templates)?;
+    crate::to_js(&census(doc, &templates, tolerance, include_mirrored))
+}
+
+#[wasm_bindgen(js_name = find_congruent)]
+pub fn find_congruent_js(doc: &Document, template: &JsValue, tolerance: f64) -> Result<Vec<ShapeId>, JsValue> {
+    let template: Polygon = crate::polygon_from(template)?;
+    Ok(find_congruent(doc, &template, tolerance))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Matrix;
+
+    fn polygon(points: &[(f64, f64)]) -> Polygon {
+        Polygon::new(points.iter().map(|&(x, y)| Point::new(x, y)).collect())
+    }
+
+    fn templates() -> Vec<Polygon> {
+        vec![
+            polygon(&[(0.0, 0.0), (2.0, 0.0), (2.0, 1.0), (0.0, 1.0)]),
+            // Arms of different lengths, so the mirror image is a different part
+            polygon(&[(0.0, 0.0), (3.0, 0.0), (3.0, 1.0), (1.0, 1.0), (1.0, 2.0), (0.0, 2.0)]),
+            polygon(&[(0.0, 0.0), (4.0, 0.0), (0.0, 3.0)]),
+        ]
+    }
+
+    fn seeded_document() -> (Document, ShapeId) {
+        let mut doc = Document::new();
+        let templates = templates();
+        let place = |doc: &mut Document, shape: Polygon, i: usize| {
+            let id = doc.add_shape(shape);
+            let transform = Matrix::translate(10.0 * i as f64, -3.0 * i as f64).multiply(&Matrix::rotate(0.37 * i as f64));
+            doc.set_transform(id, transform).unwrap();
+            id
+        };
+        for i in 0..5 {
+            place(&mut doc, templates[0].clone(), i);
+        }
+        for i in 0..3 {
+            // Start the outline at a different vertex and run it clockwise
+            let mut vertices = templates[1].vertices.clone();
+            vertices.rotate_left(i + 1);
+            vertices.reverse();
+            place(&mut doc, Polygon::new(vertices), i);
+        }
+        for i in 0..2 {
+            place(&mut doc, templates[2].clone(), i + 7);
+        }
+        let mirrored = place(&mut doc, polygon(&[(0.0, 0.0), (-3.0, 0.0), (-3.0, 1.0), (-1.0, 1.0), (-1.0, 2.0), (0.0, 2.0)]), 4);
+        // Noise: near misses and unrelated outlines
+        place(&mut doc, polygon(&[(0.0, 0.0), (2.0, 0.0), (2.0, 1.1), (0.0, 1.1)]), 1);
+        place(&mut doc, polygon(&[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]), 2);
+        place(&mut doc, polygon(&[(0.0, 0.0), (4.0, 0.0), (0.0, 3.1)]), 3);
+        (doc, mirrored)
+    }
+
+    #[test]
+    fn test_counts_seeded_templates() {
+        let (doc, mirrored) = seeded_document();
+        let result = census(&doc, &templates(), 1e-6, false);
+        let counts: Vec<usize> = result.entries.iter().map(|e| e.count).collect();
+        assert_eq!(counts, vec![5, 3, 2]);
+        assert_eq!(result.entries[0].shapes, vec![0, 1, 2, 3, 4]);
+        assert_eq!(result.unmatched.len(), 4);
+        assert!(result.unmatched.contains(&mirrored));
+
+        let with_mirrors = census(&doc, &templates(), 1e-6, true);
+        assert_eq!(with_mirrors.entries[1].count, 3);
+        assert_eq!(with_mirrors.entries[1].mirrored, vec![mirrored]);
+        assert_eq!(with_mirrors.unmatched.len(), 3);
+    }
+
+    #[test]
+    fn test_tolerance_admits_small_deviations() {
+        let (doc, _) = seeded_document();
+        // The 2 x 1.1 rectangle is within 0.15 of the 2 x 1 template
+        assert_eq!(census(&doc, &templates(), 0.15, false).entries[0].count, 6);
+    }
+}
@@ -0,0 +1,155 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::predicates::{orient2d, point_in_ring, ring_signed_area};
+use crate::{Point, Polygon, Segment, EPSILON};
+
+// Where a piece of one polygon's boundary lies relative to another polygon.
+// Along the boundary, direction is compared with both rings running
+// counter-clockwise: `BoundaryOpposite` is an edge the two polygons share
+// from either side, as at a seam between abutting plates.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EdgeSide {
+    Inside,
+    Outside,
+    BoundarySame,
+    BoundaryOpposite,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct EdgeClass {
+    pub segment: Segment,
+    // Edge of the classified polygon the piece comes from, from vertex i to i + 1
+    pub edge: usize,
+    pub side: EdgeSide,
+}
+
+fn distance(a: &Point, b: &Point) -> f64 {
+    (b.x - a.x).hypot(b.y - a.y)
+}
+
+fn edges(ring: &[Point]) -> Vec<Segment> {
+    let n = ring.len();
+    (0..n).map(|i| Segment::new(ring[i], ring[(i + 1) % n])).collect()
+}
+
+// Parameters along `e` where it must be split so each piece relates to `b`
+// in one way: proper crossings with b's edges, and b's vertices lying on `e`
+fn cuts(e: &Segment, b: &[Segment], tolerance: f64) -> Vec<f64> {
+    let len = e.length();
+    let (dx, dy) = (e.b.x - e.a.x, e.b.y - e.a.y);
+    let at = |p: &Point| ((p.x - e.a.x) * dx + (p.y - e.a.y) * dy) / (len * len);
+    let mut cuts = vec![0.0, 1.0];
+    for f in b {
+        let (o1, o2) = (orient2d(&e.a, &e.b, &f.a), orient2d(&e.a, &e.b, &f.b));
+        let (o3, o4) = (orient2d(&f.a, &f.b, &e.a), orient2d(&f.a, &f.b, &e.b));
+        if o1 * o2 < 0.0 && o3 * o4 < 0.0 {
+            cuts.push(o3 / (o3 - o4));
+        }
+        if distance(&e.closest_point(&f.a), &f.a) <= tolerance {
+            cuts.push(at(&f.a));
+        }
+    }
+    cuts.retain(|t| (0.0..=1.0).contains(t));
+    cuts.sort_by(f64::total_cmp);
+    let step = tolerance.max(EPSILON) / len;
+    cuts.dedup_by(|t, kept| *t - *kept <= step);
+    // The end may have been merged into a cut just short of it
+    match cuts.len() {
+        0 | 1 => vec![0.0, 1.0],
+        n => {
+            cuts[n - 1] = 1.0;
+            cuts
+        }
+    }
+}
+
+fn side(piece: &Segment, b: &[Point], b_edges: &[Segment], flipped: bool, tolerance: f64) -> EdgeSide {
+    let mid = piece.midpoint();
+    let along = b_edges.iter().find(|f| {
+        [piece.a, mid, piece.b].iter().all(|p| distance(&f.closest_point(p), p) <= tolerance)
+    });
+    match along {
+        Some(f) => {
+            let dot = (piece.b.x - piece.a.x) * (f.b.x - f.a.x) + (piece.b.y - piece.a.y) * (f.b.y - f.a.y);
+            if (dot > 0.0) != flipped { EdgeSide::BoundarySame } else { EdgeSide::BoundaryOpposite }
+        }
+        None if point_in_ring(b, &mid) => EdgeSide::Inside,
+        None => EdgeSide::Outside,
+    }
+}
+
+// a's edges split wherever their relation to `b` changes, each piece
+// classified as inside b, outside it, or along its boundary within
+// `tolerance`. Pieces come in a's vertex order and together trace its
+// whole boundary.
+pub fn classify_edges(a: &Polygon, b: &Polygon, tolerance: f64) -> Vec<EdgeClass> {
+    let b_edges = edges(&b.vertices);
+    // Boundary directions compare as if both rings ran counter-clockwise
+    let flipped = (ring_signed_area(&a.vertices) < 0.0) != (ring_signed_area(&b.vertices) < 0.0);
+    let mut classes = Vec::new();
+    for (i, e) in edges(&a.vertices).iter().enumerate() {
+        if e.length() <= EPSILON {
+            continue;
+        }
+        let (dx, dy) = (e.b.x - e.a.x, e.b.y - e.a.y);
+        let at = |t: f64| if t >= 1.0 { e.b } else { Point::new(e.a.x + dx * t, e.a.y + dy * t) };
+        for w in cuts(e, &b_edges, tolerance.max(0.0)).windows(2) {
+            let segment = Segment::new(at(w[0]), at(w[1]));
+            classes.push(EdgeClass { segment, edge: i, side: side(&segment, &b.vertices, &b_edges, flipped, tolerance.max(EPSILON)) });
+        }
+    }
+    classes
+}
+
+#[wasm_bindgen(js_name = classify_edges)]
+pub fn classify_edges_js(a: &JsValue, b: &JsValue, tolerance: f64) -> Result<JsValue, JsValue> {
+    let a: Polygon = crate::polygon_from(a)?;
+    let b: Polygon = crate::polygon_from(b)?;
+    crate::to_js(&classify_edges(&a, &b, tolerance))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(x: f64, y: f64, size: f64) -> Polygon {
+        Polygon::new(vec![Point::new(x, y), Point::new(x + size, y), Point::new(x + size, y + size), Point::new(x, y + size)])
+    }
+
+    fn length(classes: &[EdgeClass], side: EdgeSide) -> f64 {
+        classes.iter().filter(|c| c.side == side).map(|c| c.segment.length()).sum()
+    }
+
+    #[test]
+    fn test_overlapping_squares_partition_the_perimeter() {
+        let (a, b) = (square(0.0, 0.0, 2.0), square(1.0, 1.0, 2.0));
+        let classes = classify_edges(&a, &b, 1e-9);
+        // Pieces run end to end around a
+        for (c, next) in classes.iter().zip(classes.iter().cycle().skip(1)) {
+            assert!(distance(&c.segment.b, &next.segment.a) < 1e-12);
+        }
+        let total: f64 = classes.iter().map(|c| c.segment.length()).sum();
+        assert!((total - a.perimeter()).abs() < 1e-12);
+        // The parts of a's right and top edges past (2, 1) and (1, 2)
+        assert!((length(&classes, EdgeSide::Inside) - 2.0).abs() < 1e-12);
+        assert!((length(&classes, EdgeSide::Outside) - 6.0).abs() < 1e-12);
+        assert_eq!(classes.iter().filter(|c| c.edge == 1).count(), 2);
+    }
+
+    #[test]
+    fn test_shared_edges_report_direction() {
+        let a = square(0.0, 0.0, 1.0);
+        // Abutting on part of a's right edge
+        let classes = classify_edges(&a, &square(1.0, 0.5, 1.0), 1e-9);
+        assert!((length(&classes, EdgeSide::BoundaryOpposite) - 0.5).abs() < 1e-12);
+        assert!(classes.iter().filter(|c| c.side == EdgeSide::BoundaryOpposite).all(|c| c.edge == 1));
+
+        // Against itself drawn the other way round, everything runs along
+        let mut reversed = a.clone();
+        reversed.vertices.reverse();
+        let classes = classify_edges(&a, &reversed, 1e-9);
+        assert!(classes.iter().all(|c| c.side == EdgeSide::BoundarySame));
+        assert!((length(&classes, EdgeSide::BoundarySame) - 4.0).abs() < 1e-12);
+    }
+}
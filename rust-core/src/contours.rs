@@ -0,0 +1,221 @@
+use wasm_bindgen::prelude::*;
+
+use crate::predicates::{point_in_ring, ring_signed_area};
+use crate::{MultiPolygon, Point, Polygon, EPSILON};
+
+// Lower bound on the samples used for a morphed ring
+const MIN_SAMPLES: usize = 64;
+
+fn distance_to_segment(p: &Point, a: &Point, b: &Point) -> f64 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let len2 = dx * dx + dy * dy;
+    let t = if len2 > 0.0 { (((p.x - a.x) * dx + (p.y - a.y) * dy) / len2).clamp(0.0, 1.0) } else { 0.0 };
+    let (cx, cy) = (a.x + t * dx, a.y + t * dy);
+    ((p.x - cx).powi(2) + (p.y - cy).powi(2)).sqrt()
+}
+
+fn distance_to_ring(p: &Point, ring: &[Point]) -> f64 {
+    let n = ring.len();
+    (0..n).map(|i| distance_to_segment(p, &ring[i], &ring[(i + 1) % n])).fold(f64::INFINITY, f64::min)
+}
+
+// Resamples a closed ring to `count` points evenly spaced by arc length,
+// starting at the ring's first vertex
+pub(crate) fn resample_ring(points: &[Point], count: usize) -> Vec<Point> {
+    let n = points.len();
+    if n == 0 || count == 0 {
+        return Vec::new();
+    }
+
+    let lengths: Vec<f64> = (0..n).map(|i| {
+        let a = points[i];
+        let b = points[(i + 1) % n];
+        ((b.x - a.x).powi(2) + (b.y - a.y).powi(2)).sqrt()
+    }).collect();
+    let total: f64 = lengths.iter().sum();
+    if total <= EPSILON {
+        return vec![points[0]; count];
+    }
+
+    let mut result = Vec::with_capacity(count);
+    let mut edge = 0;
+    let mut edge_start = 0.0;
+    for k in 0..count {
+        let target = total * k as f64 / count as f64;
+        while edge < n - 1 && edge_start + lengths[edge] < target {
+            edge_start += lengths[edge];
+            edge += 1;
+        }
+        let a = points[edge];
+        let b = points[(edge + 1) % n];
+        let t = if lengths[edge] > 0.0 { ((target - edge_start) / lengths[edge]).clamp(0.0, 1.0) } else { 0.0 };
+        result.push(Point::new(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t));
+    }
+
+    result
+}
+
+// Rotates `b` (already resampled to the length of `a`) so that corresponding
+// points are as close as possible, flipping it first if the windings differ
+fn align_ring(a: &[Point], mut b: Vec<Point>) -> Vec<Point> {
+    if ring_signed_area(a).signum() != ring_signed_area(&b).signum() {
+        b.reverse();
+    }
+
+    let n = a.len();
+    let cost = |shift: usize| -> f64 {
+        (0..n).map(|i| {
+            let q = b[(i + shift) % n];
+            (a[i].x - q.x).powi(2) + (a[i].y - q.y).powi(2)
+        }).sum()
+    };
+    let best = (0..n).min_by(|&x, &y| cost(x).total_cmp(&cost(y))).unwrap_or(0);
+    b.rotate_left(best);
+    b
+}
+
+// Approximate pole of inaccessibility: the interior point farthest from the
+// boundary, found by a coarse grid search refined around the best cell
+pub(crate) fn skeleton_point(polygon: &Polygon) -> Point {
+    let ring = &polygon.vertices;
+    let mut bbox = polygon.bounding_box();
+    let mut best = bbox.center();
+    let mut best_distance = if point_in_ring(ring, &best) { distance_to_ring(&best, ring) } else { -1.0 };
+
+    for _ in 0..4 {
+        let steps = 16;
+        let (w, h) = (bbox.width() / steps as f64, bbox.height() / steps as f64);
+        for i in 0..=steps {
+            for j in 0..=steps {
+                let p = Point::new(bbox.min.x + w * i as f64, bbox.min.y + h * j as f64);
+                if point_in_ring(ring, &p) {
+                    let d = distance_to_ring(&p, ring);
+                    if d > best_distance {
+                        best_distance = d;
+                        best = p;
+                    }
+                }
+            }
+        }
+        bbox.min = Point::new(best.x - w, best.y - h);
+        bbox.max = Point::new(best.x + w, best.y + h);
+    }
+
+    best
+}
+
+fn lerp_rings(a: &[Point], b: &[Point], t: f64) -> Polygon {
+    Polygon::new(a.iter().zip(b).map(|(p, q)| Point::new(p.x + (q.x - p.x) * t, p.y + (q.y - p.y) * t)).collect())
+}
+
+fn morph(a: &Polygon, b: &Polygon, t: f64) -> Polygon {
+    let count = a.vertices.len().max(b.vertices.len()).max(MIN_SAMPLES / 4) * 4;
+    let ra = resample_ring(&a.vertices, count);
+    let rb = align_ring(&ra, resample_ring(&b.vertices, count));
+    lerp_rings(&ra, &rb, t)
+}
+
+// Shrinks a ring towards a point; t = 0 is the ring itself
+fn collapse(ring: &Polygon, t: f64) -> Polygon {
+    let target = skeleton_point(ring);
+    Polygon::new(ring.vertices.iter().map(|p| Point::new(p.x + (target.x - p.x) * t, p.y + (target.y - p.y) * t)).collect())
+}
+
+// Blends between two offset levels. Each inner component is matched to the
+// outer component that contains its centroid (or, failing that, the one with
+// the nearest centroid); matched rings are resampled to a common count,
+// start-aligned and interpolated. Outer components with no match shrink
+// towards their skeleton point instead of disappearing abruptly, and inner
+// components with no match grow out of theirs. When an outer component splits
+// into several inner ones, each piece morphs from the shared parent outline.
+pub fn interpolate_contours(outer: &MultiPolygon, inner: &MultiPolygon, t: f64) -> MultiPolygon {
+    let t = t.clamp(0.0, 1.0);
+    let centroid = |p: &Polygon| p.bounding_box().center();
+
+    let parent: Vec<Option<usize>> = inner.iter().map(|ring| {
+        let c = centroid(ring);
+        outer.iter().position(|o| point_in_ring(&o.vertices, &c)).or_else(|| {
+            (0..outer.len()).min_by(|&i, &j| {
+                let (ci, cj) = (centroid(&outer[i]), centroid(&outer[j]));
+                let di = (ci.x - c.x).powi(2) + (ci.y - c.y).powi(2);
+                let dj = (cj.x - c.x).powi(2) + (cj.y - c.y).powi(2);
+                di.total_cmp(&dj)
+            })
+        })
+    }).collect();
+
+    let mut result = Vec::new();
+    for (i, ring) in outer.iter().enumerate() {
+        let children: Vec<usize> = (0..inner.len()).filter(|&j| parent[j] == Some(i)).collect();
+        if children.is_empty() {
+            result.push(collapse(ring, t));
+        }
+        for j in children {
+            result.push(morph(ring, &inner[j], t));
+        }
+    }
+    for (j, ring) in inner.iter().enumerate() {
+        if parent[j].is_none() {
+            result.push(collapse(ring, 1.0 - t));
+        }
+    }
+
+    result
+}
+
+#[wasm_bindgen(js_name = interpolate_contours)]
+pub fn interpolate_contours_js(outer: &JsValue, inner: &JsValue, t: f64) -> Result<JsValue, JsValue> {
+    let outer: MultiPolygon = crate::from_js(outer)?;
+    let inner: MultiPolygon = crate::from_js(inner)?;
+    crate::to_js(&interpolate_contours(&outer, &inner, t))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(min: f64, max: f64) -> Polygon {
+        Polygon::new(vec![Point::new(min, min), Point::new(max, min), Point::new(max, max), Point::new(min, max)])
+    }
+
+    fn hausdorff(a: &Polygon, b: &Polygon) -> f64 {
+        let one_way = |p: &Polygon, q: &Polygon| {
+            resample_ring(&p.vertices, 256).iter().map(|v| distance_to_ring(v, &q.vertices)).fold(0.0, f64::max)
+        };
+        one_way(a, b).max(one_way(b, a))
+    }
+
+    #[test]
+    fn test_halfway_between_square_and_inset() {
+        let result = interpolate_contours(&vec![square(0.0, 1.0)], &vec![square(0.4, 0.6)], 0.5);
+        assert_eq!(result.len(), 1);
+        assert!(hausdorff(&result[0], &square(0.2, 0.8)) < 1e-3);
+    }
+
+    #[test]
+    fn test_endpoints_reproduce_levels() {
+        // Start points and windings differ between the two levels
+        let outer = Polygon::new(vec![Point::new(1.0, 1.0), Point::new(0.0, 1.0), Point::new(0.0, 0.0), Point::new(1.0, 0.0)]);
+        let inner = square(0.3, 0.7);
+        let at_zero = interpolate_contours(&vec![outer.clone()], &vec![inner.clone()], 0.0);
+        let at_one = interpolate_contours(&vec![outer.clone()], &vec![inner.clone()], 1.0);
+        assert!(hausdorff(&at_zero[0], &outer) < 1e-9);
+        assert!(hausdorff(&at_one[0], &inner) < 1e-9);
+
+        let halfway = interpolate_contours(&vec![outer], &vec![inner], 0.5);
+        assert!(hausdorff(&halfway[0], &square(0.15, 0.85)) < 1e-3);
+    }
+
+    #[test]
+    fn test_vanishing_component_shrinks() {
+        let outer = vec![square(0.0, 1.0), square(5.0, 5.2)];
+        let inner = vec![square(0.4, 0.6)];
+        let result = interpolate_contours(&outer, &inner, 0.5);
+        assert_eq!(result.len(), 2);
+
+        let small = &result[1];
+        assert!((small.area() - 0.04 * 0.25).abs() < 1e-9);
+        let c = small.bounding_box().center();
+        assert!((c.x - 5.1).abs() < 0.01 && (c.y - 5.1).abs() < 0.01);
+    }
+}
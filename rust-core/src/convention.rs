@@ -0,0 +1,190 @@
+use std::cell::Cell;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::predicates::ring_signed_area;
+use crate::{Arc, Polygon};
+
+// Orientation of the caller's y axis. Internal math is always y-up; under
+// YDown, angles and winding are mirrored at the public API boundary so that
+// positive angles and "clockwise" keep their on-screen meaning.
+#[wasm_bindgen]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum CoordinateConvention {
+    #[default]
+    YUp,
+    YDown,
+}
+
+thread_local! {
+    static CONVENTION: Cell<CoordinateConvention> = const { Cell::new(CoordinateConvention::YUp) };
+}
+
+#[wasm_bindgen]
+pub fn set_convention(convention: CoordinateConvention) {
+    CONVENTION.with(|c| c.set(convention));
+}
+
+#[wasm_bindgen]
+pub fn convention() -> CoordinateConvention {
+    CONVENTION.with(|c| c.get())
+}
+
+// Converts an angle (any unit) between the caller's convention and the
+// internal y-up one; the mapping is its own inverse
+pub(crate) fn user_angle(angle: f64) -> f64 {
+    match convention() {
+        CoordinateConvention::YUp => angle,
+        CoordinateConvention::YDown => -angle,
+    }
+}
+
+impl Arc {
+    // The arc with its start angle and sweep converted as `user_angle`
+    // does, for arcs crossing the public API; its own inverse
+    pub(crate) fn user(&self) -> Arc {
+        Arc { start_angle: user_angle(self.start_angle), sweep: user_angle(self.sweep), ..*self }
+    }
+}
+
+impl Polygon {
+    // Area, positive when the vertices run counter-clockwise as the caller
+    // sees them on screen
+    pub fn signed_area(&self) -> f64 {
+        let area = ring_signed_area(&self.vertices);
+        match convention() {
+            CoordinateConvention::YUp => area,
+            CoordinateConvention::YDown => -area,
+        }
+    }
+
+    // Clockwise as the caller sees it on screen
+    pub fn is_clockwise(&self) -> bool {
+        self.signed_area() < 0.0
+    }
+
+    // The polygon wound counter-clockwise on screen when `ccw`, clockwise
+    // otherwise, reversing the vertices if needed. A polygon with no area
+    // is returned as it is.
+    pub fn ensure_winding(&self, ccw: bool) -> Polygon {
+        let area = self.signed_area();
+        let mut polygon = self.clone();
+        if (ccw && area < 0.0) || (!ccw && area > 0.0) {
+            polygon.vertices.reverse();
+        }
+        polygon
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse_coordinate_input, Document, MeasureSession, Point, Units};
+
+    use super::*;
+
+    fn mirror(p: &Point) -> Point {
+        Point::new(p.x, -p.y)
+    }
+
+    struct ScriptResult {
+        rotated: Vec<Point>,
+        clockwise: bool,
+        polar: Point,
+        heading: f64,
+    }
+
+    // The same user-level steps, fed with points drawn in the caller's own
+    // convention
+    fn script(convention: CoordinateConvention, to_user: fn(&Point) -> Point) -> ScriptResult {
+        set_convention(convention);
+        let square = [Point::new(0.0, 0.0), Point::new(2.0, 0.0), Point::new(2.0, 1.0), Point::new(0.0, 1.0)];
+        let polygon = Polygon::new(square.iter().map(to_user).collect());
+
+        let mut doc = Document::new();
+        let id = doc.add_shape(polygon.clone());
+        doc.set_pivot(id, to_user(&Point::new(2.0, 0.0))).unwrap();
+        doc.rotate_shape(id, std::f64::consts::FRAC_PI_6).unwrap();
+
+        let mut session = MeasureSession::new(Units::Millimeters, 3);
+        session.add_point(0.0, 0.0);
+        let target = to_user(&Point::new(1.0, 1.0));
+
+        let result = ScriptResult {
+            rotated: doc.shape(id).unwrap().world_geometry().vertices,
+            clockwise: polygon.is_clockwise(),
+            polar: parse_coordinate_input("@2<90", None, Some(&Point::new(0.0, 0.0)), Units::Millimeters).unwrap(),
+            heading: session.preview(target.x, target.y).angle_from_horizontal,
+        };
+        set_convention(CoordinateConvention::YUp);
+        result
+    }
+
+    #[test]
+    fn test_y_down_mirrors_y_up_consistently() {
+        let up = script(CoordinateConvention::YUp, |p| *p);
+        let down = script(CoordinateConvention::YDown, mirror);
+
+        for (a, b) in up.rotated.iter().zip(&down.rotated) {
+            let m = mirror(a);
+            assert!((m.x - b.x).abs() < 1e-12 && (m.y - b.y).abs() < 1e-12);
+        }
+        assert!(!up.clockwise && !down.clockwise);
+        assert_eq!(up.polar, Point::new(2.0 * 90f64.to_radians().cos(), 2.0));
+        assert_eq!(down.polar, mirror(&up.polar));
+        assert!((up.heading - 45.0).abs() < 1e-12 && (down.heading - 45.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_y_down_arcs_dimensions_and_offsets_mirror_y_up() {
+        use crate::{AngleQuadrant, AngularDimension, JoinStyle};
+        let arc = Arc { center: Point::new(1.0, 2.0), radius: 3.0, start_angle: 0.3, sweep: 2.0 };
+        let dimension = |to_user: fn(&Point) -> Point| AngularDimension {
+            edge_a: (to_user(&Point::new(0.0, 0.0)), to_user(&Point::new(10.0, 0.0))),
+            edge_b: (to_user(&Point::new(0.0, 0.0)), to_user(&Point::new(6.0, 8.0))),
+            offset: 5.0,
+            quadrant: AngleQuadrant::SupplementA,
+            arrow_size: 1.0,
+            precision: 1,
+            dms: false,
+        };
+        let square = |to_user: fn(&Point) -> Point| Polygon::new([(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0)].iter().map(|&(x, y)| to_user(&Point::new(x, y))).collect());
+
+        let up = (arc.user().to_polyline(16).points, dimension(|p| *p).render().unwrap(), square(|p| *p).offset(1.0, JoinStyle::Miter { limit: 4.0 }));
+        set_convention(CoordinateConvention::YDown);
+        let user_arc = Arc { center: mirror(&arc.center), ..arc };
+        let down = (user_arc.user().to_polyline(16).points, dimension(mirror).render().unwrap(), square(mirror).offset(1.0, JoinStyle::Miter { limit: 4.0 }));
+        set_convention(CoordinateConvention::YUp);
+
+        // The same arc drawn on a y-down screen, turning the same way there
+        assert_eq!(up.0.len(), down.0.len());
+        for (a, b) in up.0.iter().zip(&down.0) {
+            assert!((mirror(a).x - b.x).abs() < 1e-12 && (mirror(a).y - b.y).abs() < 1e-12);
+        }
+        assert!((up.1.value - down.1.value).abs() < 1e-12 && up.1.text == down.1.text && up.1.text == "126.9°");
+        for (a, b) in up.1.lines[0].points.iter().zip(&down.1.lines[0].points) {
+            assert!((mirror(a).x - b.x).abs() < 1e-12 && (mirror(a).y - b.y).abs() < 1e-12);
+        }
+        assert!((mirror(&up.1.text_anchor).x - down.1.text_anchor.x).abs() < 1e-12 && (mirror(&up.1.text_anchor).y - down.1.text_anchor.y).abs() < 1e-12);
+        // Outward stays outward whichever way the ring runs on screen
+        assert_eq!(up.2.len(), 1);
+        assert_eq!(down.2.len(), 1);
+        let (ub, db) = (up.2[0].bounding_box(), down.2[0].bounding_box());
+        assert!((ub.min.x + 1.0).abs() < 1e-12 && (db.min.y + 5.0).abs() < 1e-12 && (db.max.y - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_default_is_y_up() {
+        assert_eq!(convention(), CoordinateConvention::YUp);
+        let cw = Polygon::new(vec![Point::new(0.0, 0.0), Point::new(0.0, 1.0), Point::new(1.0, 1.0)]);
+        assert!(cw.is_clockwise());
+        assert!(cw.signed_area() < 0.0 && cw.ensure_winding(true).signed_area() > 0.0);
+        assert_eq!(cw.ensure_winding(false).vertices, cw.vertices);
+        set_convention(CoordinateConvention::YDown);
+        assert!(!cw.is_clockwise() && cw.signed_area() > 0.0);
+        assert_eq!(cw.ensure_winding(true).vertices, cw.vertices);
+        set_convention(CoordinateConvention::YUp);
+        let m = crate::Matrix::rotate(std::f64::consts::FRAC_PI_2);
+        assert!((m.m21 - 1.0).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,351 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::convention::user_angle;
+use crate::{CadError, Point};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Units {
+    #[serde(rename = "mm")]
+    Millimeters,
+    #[serde(rename = "in")]
+    Inches,
+}
+
+impl Units {
+    pub fn from_name(name: &str) -> Option<Units> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "mm" | "millimeters" => Some(Units::Millimeters),
+            "in" | "inches" | "\"" => Some(Units::Inches),
+            _ => None,
+        }
+    }
+
+    pub fn mm_per_unit(&self) -> f64 {
+        match self {
+            Units::Millimeters => 1.0,
+            Units::Inches => 25.4,
+        }
+    }
+
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            Units::Millimeters => "mm",
+            Units::Inches => "\"",
+        }
+    }
+
+    pub fn convert(&self, value: f64, to: Units) -> f64 {
+        value * self.mm_per_unit() / to.mm_per_unit()
+    }
+}
+
+struct Cursor {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Cursor {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn skip_ws(&mut self) {
+        while self.peek().is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn error(&self, message: &str) -> CadError {
+        CadError::Parse { position: self.pos, message: message.to_string() }
+    }
+
+    fn digits(&mut self) -> String {
+        let mut s = String::new();
+        while let Some(c) = self.peek().filter(|c| c.is_ascii_digit()) {
+            s.push(c);
+            self.pos += 1;
+        }
+        s
+    }
+
+    fn integer(&mut self) -> Result<f64, CadError> {
+        let digits = self.digits();
+        if digits.is_empty() {
+            return Err(self.error("expected a digit"));
+        }
+        Ok(digits.parse().unwrap())
+    }
+
+    fn decimal(&mut self) -> Result<f64, CadError> {
+        let start = self.pos;
+        let mut text = self.digits();
+        if self.peek() == Some('.') {
+            self.pos += 1;
+            text.push('.');
+            text.push_str(&self.digits());
+        }
+        if text.is_empty() || text == "." {
+            self.pos = start;
+            return Err(self.error("expected a number"));
+        }
+        Ok(text.parse().unwrap())
+    }
+
+    fn sign(&mut self) -> f64 {
+        if self.eat('-') {
+            -1.0
+        } else {
+            self.eat('+');
+            1.0
+        }
+    }
+
+    // A length: decimal, simple fraction (1/2) or mixed fraction (1-1/2),
+    // with an optional mm / in / " suffix converted into `units`
+    fn length(&mut self, units: Units) -> Result<f64, CadError> {
+        self.skip_ws();
+        let sign = self.sign();
+        let mut value = self.decimal()?;
+
+        if self.peek() == Some('-') && self.peek_at(1).is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+            value += self.fraction()?;
+        } else if self.peek() == Some('/') {
+            let slash = self.pos;
+            self.pos += 1;
+            let denominator = self.integer()?;
+            if denominator == 0.0 {
+                self.pos = slash;
+                return Err(self.error("fraction has a zero denominator"));
+            }
+            value /= denominator;
+        }
+
+        self.skip_ws();
+        let input_units = if self.eat('"') {
+            Units::Inches
+        } else if self.peek() == Some('m') && self.peek_at(1) == Some('m') {
+            self.pos += 2;
+            Units::Millimeters
+        } else if self.peek() == Some('i') && self.peek_at(1) == Some('n') {
+            self.pos += 2;
+            Units::Inches
+        } else {
+            units
+        };
+
+        Ok(sign * input_units.convert(value, units))
+    }
+
+    fn fraction(&mut self) -> Result<f64, CadError> {
+        let numerator = self.integer()?;
+        if !self.eat('/') {
+            return Err(self.error("expected '/' in fraction"));
+        }
+        let slash = self.pos - 1;
+        let denominator = self.integer()?;
+        if denominator == 0.0 {
+            self.pos = slash;
+            return Err(self.error("fraction has a zero denominator"));
+        }
+        Ok(numerator / denominator)
+    }
+
+    // Angle in degrees, optionally suffixed with a degree sign
+    fn angle(&mut self) -> Result<f64, CadError> {
+        self.skip_ws();
+        let sign = self.sign();
+        let value = self.decimal()?;
+        self.eat('°');
+        Ok(sign * value)
+    }
+}
+
+// Parses command-bar coordinate entry:
+//   10,20      absolute cartesian (offset by `base` when given)
+//   @5,0       relative to `last`
+//   10<45      polar, radius and angle in degrees counter-clockwise from +x
+//   @10<45     relative polar
+// Lengths accept mm / in / " suffixes and fractions like 1-1/2". Comma decimal
+// separators are rejected rather than silently read as an x,y separator.
+pub fn parse_coordinate_input(input: &str, base: Option<&Point>, last: Option<&Point>, units: Units) -> Result<Point, CadError> {
+    let mut cursor = Cursor { chars: input.chars().collect(), pos: 0 };
+    cursor.skip_ws();
+
+    let relative = cursor.eat('@');
+    let first = cursor.length(units)?;
+    cursor.skip_ws();
+
+    let separator = cursor.pos;
+    let offset = if cursor.eat(',') {
+        let second = cursor.length(units)?;
+        Point::new(first, second)
+    } else if cursor.eat('<') {
+        let degrees = cursor.angle()?;
+        let radians = user_angle(degrees).to_radians();
+        Point::new(first * radians.cos(), first * radians.sin())
+    } else if cursor.peek().is_none() {
+        return Err(cursor.error("expected ',' or '<' after the first value"));
+    } else {
+        return Err(cursor.error("unexpected character; expected ',' or '<'"));
+    };
+
+    cursor.skip_ws();
+    if let Some(c) = cursor.peek() {
+        if cursor.chars[separator] == ',' && (c == ',' || c == '<') {
+            return Err(CadError::Parse {
+                position: separator,
+                message: "comma decimal separators are not supported; use '.'".to_string(),
+            });
+        }
+        return Err(cursor.error("unexpected trailing input"));
+    }
+
+    let origin = if relative {
+        match last {
+            Some(p) => *p,
+            None => return Err(CadError::InvalidInput("relative coordinates need a previous point".to_string())),
+        }
+    } else {
+        base.copied().unwrap_or(Point::new(0.0, 0.0))
+    };
+
+    Ok(Point::new(origin.x + offset.x, origin.y + offset.y))
+}
+
+fn format_value(value: f64, precision: usize) -> String {
+    let text = format!("{:.*}", precision, value);
+    // Avoid showing "-0.000" for values that round to zero
+    if text.starts_with('-') && text[1..].chars().all(|c| c == '0' || c == '.') {
+        text[1..].to_string()
+    } else {
+        text
+    }
+}
+
+// Readout in the same syntax the parser accepts, e.g. `12.500mm, 3.000mm`
+pub fn format_point(p: &Point, units: Units, precision: usize) -> String {
+    format!(
+        "{}{}, {}{}",
+        format_value(p.x, precision), units.suffix(),
+        format_value(p.y, precision), units.suffix()
+    )
+}
+
+fn units_from_js(units: &str) -> Result<Units, JsValue> {
+    Units::from_name(units).ok_or_else(|| crate::js_error(CadError::InvalidInput(format!("unknown units '{}'", units))))
+}
+
+#[wasm_bindgen(js_name = parse_coordinate_input)]
+pub fn parse_coordinate_input_js(input: &str, base: &JsValue, last: &JsValue, units: &str) -> Result<JsValue, JsValue> {
+    let units = units_from_js(units)?;
+    let base: Option<Point> = crate::from_js(base)?;
+    let last: Option<Point> = crate::from_js(last)?;
+    let point = parse_coordinate_input(input, base.as_ref(), last.as_ref(), units)
+        .map_err(crate::js_error)?;
+    crate::to_js(&point)
+}
+
+#[wasm_bindgen(js_name = format_point)]
+pub fn format_point_js(x: f64, y: f64, units: &str, precision: usize) -> Result<String, JsValue> {
+    Ok(format_point(&Point::new(x, y), units_from_js(units)?, precision))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_point(p: Point, x: f64, y: f64) {
+        assert!((p.x - x).abs() < 1e-9 && (p.y - y).abs() < 1e-9, "got {:?}, expected ({}, {})", p, x, y);
+    }
+
+    #[test]
+    fn test_absolute_and_relative_cartesian() {
+        let mm = Units::Millimeters;
+        assert_point(parse_coordinate_input("10,20", None, None, mm).unwrap(), 10.0, 20.0);
+        assert_point(parse_coordinate_input(" -1.5 , 2 ", None, None, mm).unwrap(), -1.5, 2.0);
+
+        let base = Point::new(100.0, 100.0);
+        assert_point(parse_coordinate_input("10,20", Some(&base), None, mm).unwrap(), 110.0, 120.0);
+
+        let last = Point::new(3.0, 4.0);
+        assert_point(parse_coordinate_input("@5,0", None, Some(&last), mm).unwrap(), 8.0, 4.0);
+    }
+
+    #[test]
+    fn test_polar_and_relative_polar() {
+        let mm = Units::Millimeters;
+        let p = parse_coordinate_input("10<45", None, None, mm).unwrap();
+        let expected = 10.0 / 2f64.sqrt();
+        assert_point(p, expected, expected);
+
+        let last = Point::new(1.0, 1.0);
+        assert_point(parse_coordinate_input("@2<90", None, Some(&last), mm).unwrap(), 1.0, 3.0);
+        assert_point(parse_coordinate_input("@2<-90°", None, Some(&last), mm).unwrap(), 1.0, -1.0);
+    }
+
+    #[test]
+    fn test_relative_forms_require_last_point() {
+        let mm = Units::Millimeters;
+        assert!(matches!(parse_coordinate_input("@5,0", None, None, mm), Err(CadError::InvalidInput(_))));
+        assert!(matches!(parse_coordinate_input("@10<45", None, None, mm), Err(CadError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_unit_suffixes_and_fractions() {
+        assert_point(parse_coordinate_input("1in,25.4mm", None, None, Units::Millimeters).unwrap(), 25.4, 25.4);
+        assert_point(parse_coordinate_input("1-1/2\",1/4\"", None, None, Units::Inches).unwrap(), 1.5, 0.25);
+        assert_point(parse_coordinate_input("-1-1/2, 0", None, None, Units::Inches).unwrap(), -1.5, 0.0);
+        assert_point(parse_coordinate_input("12.7mm<0", None, None, Units::Inches).unwrap(), 0.5, 0.0);
+    }
+
+    #[test]
+    fn test_errors_pinpoint_position() {
+        let mm = Units::Millimeters;
+        assert_eq!(
+            parse_coordinate_input("10;20", None, None, mm),
+            Err(CadError::Parse { position: 2, message: "unexpected character; expected ',' or '<'".to_string() })
+        );
+        assert!(matches!(parse_coordinate_input("10,", None, None, mm), Err(CadError::Parse { position: 3, .. })));
+        assert!(matches!(parse_coordinate_input("1/0,2", None, None, mm), Err(CadError::Parse { position: 1, .. })));
+    }
+
+    #[test]
+    fn test_comma_decimal_separator_is_rejected() {
+        let mm = Units::Millimeters;
+        match parse_coordinate_input("1,5,2,5", None, None, mm) {
+            Err(CadError::Parse { position, message }) => {
+                assert_eq!(position, 1);
+                assert!(message.contains("decimal"));
+            }
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+        assert!(matches!(parse_coordinate_input("2,5<30", None, None, mm), Err(CadError::Parse { position: 1, .. })));
+    }
+
+    #[test]
+    fn test_format_point_round_trips() {
+        let p = Point::new(12.5, -0.0001);
+        let text = format_point(&p, Units::Millimeters, 3);
+        assert_eq!(text, "12.500mm, 0.000mm");
+        assert_point(parse_coordinate_input(&text, None, None, Units::Millimeters).unwrap(), 12.5, 0.0);
+
+        let text = format_point(&Point::new(1.5, 2.0), Units::Inches, 2);
+        assert_eq!(text, "1.50\", 2.00\"");
+        assert_point(parse_coordinate_input(&text, None, None, Units::Inches).unwrap(), 1.5, 2.0);
+    }
+}
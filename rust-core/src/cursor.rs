@@ -0,0 +1,261 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use wasm_bindgen::prelude::*;
+
+use crate::memory::{register_detached, Live, ObjectKind, Release};
+use crate::split::check_lines;
+use crate::{js_error, union_all_regions, BoundingBox, CadError, JoinStyle, Point, Polygon, PolygonWithHoles, Vector};
+
+// Multi-piece operation result that JS pulls from piece by piece, e.g. only
+// the pieces in view. Taking a piece frees it; boxes stay available, and
+// dropping the cursor frees whatever is left. A piece with holes keeps them
+// as rings of their own rather than keyholed.
+#[wasm_bindgen]
+#[derive(Debug, Default)]
+pub struct ResultCursor {
+    pieces: Rc<Pieces>,
+    bboxes: Vec<BoundingBox>,
+}
+
+// Untaken pieces, shared with the registry `free_all_detached` empties.
+// Empty once released, while the boxes stay behind in the cursor.
+#[derive(Debug, Default)]
+struct Pieces {
+    pieces: RefCell<Vec<Option<PolygonWithHoles>>>,
+    live: Live<{ ObjectKind::ResultCursor as usize }>,
+}
+
+fn piece_bytes(piece: &PolygonWithHoles) -> usize {
+    let rings = std::iter::once(&piece.outer).chain(&piece.holes);
+    std::mem::size_of::<Option<PolygonWithHoles>>() + rings.map(|r| std::mem::size_of::<Polygon>() + r.vertices.len() * std::mem::size_of::<Point>()).sum::<usize>()
+}
+
+impl Release for Pieces {
+    fn release(&self) -> usize {
+        self.pieces.borrow_mut().clear();
+        let freed = self.live.bytes();
+        self.live.set_bytes(0);
+        freed
+    }
+}
+
+impl Clone for ResultCursor {
+    fn clone(&self) -> ResultCursor {
+        ResultCursor::from_slots(self.pieces.pieces.borrow().clone(), self.bboxes.clone())
+    }
+}
+
+impl ResultCursor {
+    pub fn new(pieces: Vec<Polygon>) -> ResultCursor {
+        ResultCursor::with_holes(pieces.into_iter().map(|p| PolygonWithHoles::new(p, Vec::new())).collect())
+    }
+
+    pub fn with_holes(pieces: Vec<PolygonWithHoles>) -> ResultCursor {
+        let bboxes = pieces.iter().map(|p| p.outer.bounding_box()).collect();
+        ResultCursor::from_slots(pieces.into_iter().map(Some).collect(), bboxes)
+    }
+
+    fn from_slots(slots: Vec<Option<PolygonWithHoles>>, bboxes: Vec<BoundingBox>) -> ResultCursor {
+        let live = Live::with_bytes(slots.iter().flatten().map(piece_bytes).sum());
+        let pieces = Rc::new(Pieces { pieces: RefCell::new(slots), live });
+        register_detached(Rc::downgrade(&pieces) as std::rc::Weak<dyn Release>);
+        ResultCursor { pieces, bboxes }
+    }
+
+    pub fn piece_count(&self) -> usize {
+        self.bboxes.len()
+    }
+
+    pub fn piece_bbox(&self, index: usize) -> Result<BoundingBox, CadError> {
+        self.bboxes.get(index).copied().ok_or_else(|| out_of_range(index, self.bboxes.len()))
+    }
+
+    pub fn take_piece(&mut self, index: usize) -> Result<PolygonWithHoles, CadError> {
+        if index >= self.bboxes.len() {
+            return Err(out_of_range(index, self.bboxes.len()));
+        }
+        let taken = self.pieces.pieces.borrow_mut().get_mut(index).and_then(Option::take);
+        match taken {
+            Some(piece) => {
+                self.pieces.live.add_bytes(-(piece_bytes(&piece) as isize));
+                Ok(piece)
+            }
+            None => Err(CadError::InvalidInput(format!("piece {} was already taken or freed", index))),
+        }
+    }
+}
+
+fn out_of_range(index: usize, count: usize) -> CadError {
+    CadError::InvalidInput(format!("piece {} out of range for {} pieces", index, count))
+}
+
+#[wasm_bindgen]
+impl ResultCursor {
+    #[wasm_bindgen(js_name = piece_count)]
+    pub fn piece_count_js(&self) -> usize {
+        self.piece_count()
+    }
+
+    // [min_x, min_y, max_x, max_y]
+    #[wasm_bindgen(js_name = piece_bbox)]
+    pub fn piece_bbox_js(&self, index: usize) -> Result<Vec<f64>, JsValue> {
+        let b = self.piece_bbox(index).map_err(js_error)?;
+        Ok(vec![b.min.x, b.min.y, b.max.x, b.max.y])
+    }
+
+    // Vertex index each hole of a piece starts at in what `take_piece`
+    // gives, as earcut's holeIndices; empty for a piece without holes
+    #[wasm_bindgen(js_name = piece_hole_indices)]
+    pub fn piece_hole_indices_js(&self, index: usize) -> Result<Vec<usize>, JsValue> {
+        self.piece_bbox(index).map_err(js_error)?;
+        let pieces = self.pieces.pieces.borrow();
+        let Some(Some(piece)) = pieces.get(index) else {
+            return Err(js_error(CadError::InvalidInput(format!("piece {} was already taken or freed", index))));
+        };
+        let mut start = piece.outer.vertices.len();
+        Ok(piece.holes.iter().map(|h| {
+            let at = start;
+            start += h.vertices.len();
+            at
+        }).collect())
+    }
+
+    // Flat [x0, y0, x1, y1, ...] outline followed by each hole, handed to
+    // JS as a Float64Array
+    #[wasm_bindgen(js_name = take_piece)]
+    pub fn take_piece_js(&mut self, index: usize) -> Result<Vec<f64>, JsValue> {
+        let piece = self.take_piece(index).map_err(js_error)?;
+        Ok(std::iter::once(&piece.outer).chain(&piece.holes).flat_map(|r| &r.vertices).flat_map(|p| [p.x, p.y]).collect())
+    }
+}
+
+// Streamed `multi_split`; pieces come in the same order
+#[wasm_bindgen]
+pub fn multi_split_streamed(vertices: &JsValue, lines: &JsValue) -> Result<ResultCursor, JsValue> {
+    let polygon: Polygon = crate::polygon_from(vertices)?;
+    let lines: Vec<(Point, Vector)> = crate::from_js(lines)?;
+    check_lines(&lines).map_err(js_error)?;
+    Ok(ResultCursor::new(polygon.multi_split(&lines).into_iter().map(|p| p.polygon).collect()))
+}
+
+// One counter-clockwise triangle per piece
+#[wasm_bindgen]
+pub fn triangulate_streamed(vertices: &JsValue) -> Result<ResultCursor, JsValue> {
+    let polygon: Polygon = crate::polygon_from(vertices)?;
+    Ok(ResultCursor::new(triangles(&polygon)))
+}
+
+// Streamed `offset_polygon`, taking the same arguments
+#[wasm_bindgen]
+pub fn offset_streamed(vertices: &JsValue, distance: f64, join_style: &str, param: f64) -> Result<ResultCursor, JsValue> {
+    let polygon: Polygon = crate::polygon_from(vertices)?;
+    let join = JoinStyle::parse(join_style, param).map_err(js_error)?;
+    Ok(ResultCursor::new(offset_pieces(&polygon, distance, join).map_err(js_error)?))
+}
+
+// Streamed `union_all`; `polygons` is an array of vertex arrays
+#[wasm_bindgen]
+pub fn union_all_streamed(polygons: &JsValue) -> Result<ResultCursor, JsValue> {
+    let polygons: Vec<Polygon> = crate::from_js(polygons)?;
+    Ok(ResultCursor::with_holes(crate::strict::checked_regions(union_all_regions(&polygons), "union").map_err(js_error)?))
+}
+
+fn offset_pieces(polygon: &Polygon, distance: f64, join: JoinStyle) -> Result<Vec<Polygon>, CadError> {
+    crate::strict::checked(polygon.offset(distance, join), "offset")
+}
+
+fn triangles(polygon: &Polygon) -> Vec<Polygon> {
+    polygon.triangulate().into_iter()
+        .map(|t| Polygon::new(t.iter().map(|&i| polygon.vertices[i]).collect()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pieces_add_up_to_blocking_result() {
+        let outline = Polygon::new(vec![
+            Point::new(0.0, 0.0), Point::new(6.0, 0.0), Point::new(6.0, 2.0),
+            Point::new(2.0, 2.0), Point::new(2.0, 5.0), Point::new(0.0, 5.0),
+        ]);
+        let lines = [(Point::new(1.0, 0.0), Vector::new(0.0, 1.0)), (Point::new(0.0, 1.0), Vector::new(1.0, 0.0))];
+        let blocking = outline.multi_split(&lines);
+        let total: f64 = blocking.iter().map(|p| p.area).sum();
+
+        let mut cursor = ResultCursor::new(blocking.into_iter().map(|p| p.polygon).collect());
+        assert_eq!(cursor.piece_count(), 4);
+        let mut streamed = 0.0;
+        for i in (0..cursor.piece_count()).rev() {
+            let bbox = cursor.piece_bbox(i).unwrap();
+            let piece = cursor.take_piece(i).unwrap();
+            assert_eq!(piece.outer.bounding_box(), bbox);
+            streamed += piece.area();
+        }
+        assert!((streamed - total).abs() < 1e-12);
+        assert!((total - outline.area()).abs() < 1e-12);
+
+        assert!(cursor.take_piece(0).is_err());
+        assert!(cursor.piece_bbox(0).is_ok());
+        assert!(cursor.piece_bbox(4).is_err());
+    }
+
+    #[test]
+    fn test_triangle_pieces_cover_polygon() {
+        let outline = Polygon::new(vec![Point::new(0.0, 0.0), Point::new(4.0, 0.0), Point::new(4.0, 3.0), Point::new(2.0, 1.0), Point::new(0.0, 3.0)]);
+        let mut cursor = ResultCursor::new(triangles(&outline));
+        assert_eq!(cursor.piece_count(), 3);
+        let area: f64 = (0..3).map(|i| cursor.take_piece(i).unwrap().area()).sum();
+        assert!((area - outline.area()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_offset_and_union_pieces_match_blocking_results() {
+        // Two blocks joined by a bar, which an inset of 1.5 removes
+        let dumbbell = Polygon::new(vec![
+            Point::new(0.0, 0.0), Point::new(10.0, 0.0), Point::new(10.0, 4.0), Point::new(20.0, 4.0), Point::new(20.0, 0.0), Point::new(30.0, 0.0),
+            Point::new(30.0, 10.0), Point::new(20.0, 10.0), Point::new(20.0, 6.0), Point::new(10.0, 6.0), Point::new(10.0, 10.0), Point::new(0.0, 10.0),
+        ]);
+        let join = JoinStyle::Round { segments: 16 };
+        let blocking = dumbbell.offset(-1.5, join);
+        assert_eq!(blocking.len(), 2);
+        let mut cursor = ResultCursor::new(offset_pieces(&dumbbell, -1.5, join).unwrap());
+        assert_eq!(cursor.piece_count(), 2);
+        let mut area = 0.0;
+        for i in 0..2 {
+            let bbox = cursor.piece_bbox(i).unwrap();
+            let piece = cursor.take_piece(i).unwrap();
+            assert!(piece.outer.validate().is_ok() && piece.holes.is_empty());
+            assert_eq!(piece.outer.bounding_box(), bbox);
+            area += piece.area();
+        }
+        assert!((area - blocking.iter().map(Polygon::area).sum::<f64>()).abs() < 1e-9);
+
+        let square = |x: f64| Polygon::new(vec![Point::new(x, 0.0), Point::new(x + 2.0, 0.0), Point::new(x + 2.0, 2.0), Point::new(x, 2.0)]);
+        let mut cursor = ResultCursor::with_holes(union_all_regions(&[square(0.0), square(1.0), square(5.0)]));
+        assert_eq!(cursor.piece_count(), 2);
+        let area: f64 = (0..2).map(|i| cursor.take_piece(i).unwrap().area()).sum();
+        assert!((area - (6.0 + 4.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_holed_piece_comes_out_valid() {
+        // Four bars round a 2 x 2 opening
+        let bar = |x0: f64, y0: f64, x1: f64, y1: f64| Polygon::new(vec![Point::new(x0, y0), Point::new(x1, y0), Point::new(x1, y1), Point::new(x0, y1)]);
+        let frame = [bar(0.0, 0.0, 4.0, 1.0), bar(3.0, 0.0, 4.0, 4.0), bar(0.0, 3.0, 4.0, 4.0), bar(0.0, 0.0, 1.0, 4.0)];
+        crate::set_strict_output(true);
+        let regions = crate::strict::checked_regions(union_all_regions(&frame), "union");
+        crate::set_strict_output(false);
+        let mut cursor = ResultCursor::with_holes(regions.unwrap());
+        assert_eq!(cursor.piece_count(), 1);
+        assert_eq!(cursor.piece_hole_indices_js(0).unwrap(), vec![cursor.pieces.pieces.borrow()[0].as_ref().unwrap().outer.vertices.len()]);
+        let piece = cursor.take_piece(0).unwrap();
+        assert!(piece.outer.validate().is_ok());
+        assert_eq!(piece.holes.len(), 1);
+        assert!(piece.holes[0].validate().is_ok());
+        assert!((piece.area() - 12.0).abs() < 1e-9);
+        assert!(!piece.contains_point(&Point::new(2.0, 2.0)));
+    }
+}
@@ -0,0 +1,412 @@
+use std::f64::consts::TAU;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::{Arc, BoundingBox, CubicBezier, Point, Segment, Vector, EPSILON};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Curve {
+    Segment(Segment),
+    Arc(Arc),
+    Bezier(CubicBezier),
+}
+
+// Where two curves meet, with the parameter (0 at the start, 1 at the end)
+// on each. `tangent` marks curves touching without crossing, or running
+// along each other.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct CurveIntersection {
+    pub point: Point,
+    pub t_a: f64,
+    pub t_b: f64,
+    pub tangent: bool,
+}
+
+fn cross(a: &Vector, b: &Vector) -> f64 {
+    a.x * b.y - a.y * b.x
+}
+
+fn diff(a: &Point, b: &Point) -> Vector {
+    Vector::new(a.x - b.x, a.y - b.y)
+}
+
+impl Curve {
+    // Arcs are parametrised by angle
+    pub fn point_at(&self, t: f64) -> Point {
+        match self {
+            Curve::Segment(s) => Point::new(s.a.x + (s.b.x - s.a.x) * t, s.a.y + (s.b.y - s.a.y) * t),
+            Curve::Arc(arc) => arc.point_at(arc.start_angle + arc.sweep * t),
+            Curve::Bezier(bezier) => bezier.point_at(t),
+        }
+    }
+
+    pub fn derivative_at(&self, t: f64) -> Vector {
+        match self {
+            Curve::Segment(s) => diff(&s.b, &s.a),
+            Curve::Arc(arc) => {
+                let angle = arc.start_angle + arc.sweep * t;
+                Vector::new(-angle.sin() * arc.radius * arc.sweep, angle.cos() * arc.radius * arc.sweep)
+            }
+            Curve::Bezier(bezier) => bezier.derivative_at(t),
+        }
+    }
+
+    // Box holding the curve between `t0` and `t1`
+    fn range_box(&self, t0: f64, t1: f64) -> BoundingBox {
+        match self {
+            Curve::Segment(_) => BoundingBox::from_points(&[self.point_at(t0), self.point_at(t1)]),
+            Curve::Arc(arc) => {
+                let start_angle = arc.start_angle + arc.sweep * t0;
+                Arc { start_angle, sweep: arc.sweep * (t1 - t0), ..*arc }.bounding_box()
+            }
+            Curve::Bezier(bezier) => bezier.portion(t0, t1).control_box(),
+        }
+    }
+
+    fn degenerate(&self) -> bool {
+        match self {
+            Curve::Segment(s) => s.length() < EPSILON,
+            Curve::Arc(arc) => !(arc.radius > EPSILON && arc.sweep.abs() > EPSILON),
+            Curve::Bezier(_) => false,
+        }
+    }
+}
+
+// Parameter of `p` on the arc, if it lies within `tolerance` of the arc's
+// angular range; the circle is taken as given
+pub(crate) fn arc_parameter(arc: &Arc, p: &Point, tolerance: f64) -> Option<f64> {
+    let theta = (p.y - arc.center.y).atan2(p.x - arc.center.x);
+    let sweep = arc.sweep.abs().min(TAU);
+    let along = (arc.sweep.signum() * (theta - arc.start_angle)).rem_euclid(TAU);
+    let slack = tolerance / arc.radius;
+    if along <= sweep + slack {
+        Some((along / sweep).min(1.0))
+    } else if along >= TAU - slack {
+        Some(0.0)
+    } else {
+        None
+    }
+}
+
+fn segment_parameter(s: &Segment, p: &Point) -> f64 {
+    let d = diff(&s.b, &s.a);
+    let w = diff(p, &s.a);
+    ((w.x * d.x + w.y * d.y) / (d.x * d.x + d.y * d.y)).clamp(0.0, 1.0)
+}
+
+fn hit(point: Point, t_a: f64, t_b: f64, tangent: bool) -> CurveIntersection {
+    CurveIntersection { point, t_a, t_b, tangent }
+}
+
+fn segment_segment(s: &Segment, u: &Segment, tolerance: f64) -> Vec<CurveIntersection> {
+    let (d1, d2) = (diff(&s.b, &s.a), diff(&u.b, &u.a));
+    let (l1, l2) = (s.length(), u.length());
+    let w = diff(&u.a, &s.a);
+    let denom = cross(&d1, &d2);
+    if denom.abs() <= EPSILON * l1 * l2 {
+        // Parallel: only collinear overlaps meet, reported at their ends
+        if cross(&d1, &w).abs() / l1 > tolerance {
+            return vec![];
+        }
+        let ends = [u.a, u.b, s.a, s.b];
+        let mut found: Vec<CurveIntersection> = Vec::new();
+        for p in ends {
+            let (t_a, t_b) = (segment_parameter(s, &p), segment_parameter(u, &p));
+            let on = Curve::Segment(*s).point_at(t_a);
+            let other = Curve::Segment(*u).point_at(t_b);
+            if diff(&on, &p).magnitude() <= tolerance && diff(&other, &p).magnitude() <= tolerance {
+                found.push(hit(p, t_a, t_b, true));
+            }
+        }
+        return found;
+    }
+    let t = cross(&w, &d2) / denom;
+    let v = cross(&w, &d1) / denom;
+    let (slack1, slack2) = (tolerance / l1, tolerance / l2);
+    if t < -slack1 || t > 1.0 + slack1 || v < -slack2 || v > 1.0 + slack2 {
+        return vec![];
+    }
+    let t = t.clamp(0.0, 1.0);
+    vec![hit(Curve::Segment(*s).point_at(t), t, v.clamp(0.0, 1.0), false)]
+}
+
+fn segment_arc(s: &Segment, arc: &Arc, tolerance: f64) -> Vec<CurveIntersection> {
+    let d = diff(&s.b, &s.a);
+    let len2 = d.x * d.x + d.y * d.y;
+    let w = diff(&arc.center, &s.a);
+    let foot_t = (w.x * d.x + w.y * d.y) / len2;
+    let foot = Curve::Segment(*s).point_at(foot_t);
+    let distance = diff(&arc.center, &foot).magnitude();
+    let candidates = if (distance - arc.radius).abs() <= tolerance {
+        vec![(foot_t, true)]
+    } else if distance < arc.radius {
+        let half = (arc.radius * arc.radius - distance * distance).sqrt() / len2.sqrt();
+        vec![(foot_t - half, false), (foot_t + half, false)]
+    } else {
+        vec![]
+    };
+    let slack = tolerance / len2.sqrt();
+    candidates.into_iter().filter(|&(t, _)| t >= -slack && t <= 1.0 + slack).filter_map(|(t, tangent)| {
+        let t = t.clamp(0.0, 1.0);
+        let p = Curve::Segment(*s).point_at(t);
+        arc_parameter(arc, &p, tolerance).map(|u| hit(p, t, u, tangent))
+    }).collect()
+}
+
+fn arc_arc(a: &Arc, b: &Arc, tolerance: f64) -> Vec<CurveIntersection> {
+    let between = diff(&b.center, &a.center);
+    let d = between.magnitude();
+    let (r1, r2) = (a.radius, b.radius);
+    let on_both = |p: Point, tangent: bool| -> Option<CurveIntersection> {
+        Some(hit(p, arc_parameter(a, &p, tolerance)?, arc_parameter(b, &p, tolerance)?, tangent))
+    };
+    if d <= tolerance && (r1 - r2).abs() <= tolerance {
+        // Same circle: the arcs overlap, reported at the ends
+        let ends = [a.start_point(), a.end_point(), b.start_point(), b.end_point()];
+        return ends.into_iter().filter_map(|p| on_both(p, true)).collect();
+    }
+    if d > r1 + r2 + tolerance || d < (r1 - r2).abs() - tolerance || d <= EPSILON {
+        return vec![];
+    }
+    let u = Vector::new(between.x / d, between.y / d);
+    let along = |t: f64| Point::new(a.center.x + u.x * t, a.center.y + u.y * t);
+    if (d - (r1 + r2)).abs() <= tolerance {
+        return on_both(along(r1), true).into_iter().collect();
+    }
+    if (d - (r1 - r2).abs()).abs() <= tolerance {
+        return on_both(along(if r1 >= r2 { r1 } else { -r1 }), true).into_iter().collect();
+    }
+    let x = (r1 * r1 - r2 * r2 + d * d) / (2.0 * d);
+    let h = (r1 * r1 - x * x).max(0.0).sqrt();
+    let base = along(x);
+    [1.0, -1.0].into_iter()
+        .filter_map(|side| on_both(Point::new(base.x - u.y * h * side, base.y + u.x * h * side), false))
+        .collect()
+}
+
+// Newton steps on a(s) = b(t) from a starting guess, kept within both curves
+fn refine(a: &Curve, b: &Curve, mut s: f64, mut t: f64) -> (f64, f64) {
+    for _ in 0..64 {
+        let r = diff(&a.point_at(s), &b.point_at(t));
+        let (da, db) = (a.derivative_at(s), b.derivative_at(t));
+        // Least squares through the normal equations, slightly damped so
+        // tangent contacts, where the Jacobian is singular, still converge
+        let (m11, m12, m22) = (da.x * da.x + da.y * da.y, -(da.x * db.x + da.y * db.y), db.x * db.x + db.y * db.y);
+        let damping = (m11 + m22) * 1e-12 + f64::MIN_POSITIVE;
+        let (m11, m22) = (m11 + damping, m22 + damping);
+        let (g1, g2) = (da.x * r.x + da.y * r.y, -(db.x * r.x + db.y * r.y));
+        let det = m11 * m22 - m12 * m12;
+        let ds = -(m22 * g1 - m12 * g2) / det;
+        let dt = -(m11 * g2 - m12 * g1) / det;
+        if !(ds.is_finite() && dt.is_finite()) {
+            break;
+        }
+        let (next_s, next_t) = ((s + ds).clamp(0.0, 1.0), (t + dt).clamp(0.0, 1.0));
+        let moved = (next_s - s).abs() + (next_t - t).abs();
+        (s, t) = (next_s, next_t);
+        if moved < 1e-16 {
+            break;
+        }
+    }
+    (s, t)
+}
+
+// Pieces of the two curves whose boxes meet, halving the larger piece until
+// both are small, then each run of touching pieces refined to one point
+fn subdivide(a: &Curve, b: &Curve, tolerance: f64) -> Vec<CurveIntersection> {
+    let (whole_a, whole_b) = (a.range_box(0.0, 1.0), b.range_box(0.0, 1.0));
+    let extent = whole_a.union(&whole_b);
+    let leaf = tolerance.max((extent.width() + extent.height()) * 1e-3);
+    let grow = |b: BoundingBox| BoundingBox::new(Point::new(b.min.x - tolerance, b.min.y - tolerance), Point::new(b.max.x + tolerance, b.max.y + tolerance));
+    let size = |b: &BoundingBox| b.width().max(b.height());
+
+    let mut leaves: Vec<[f64; 4]> = Vec::new();
+    let mut stack = vec![([0.0, 1.0, 0.0, 1.0], 0)];
+    while let Some(([s0, s1, t0, t1], depth)) = stack.pop() {
+        let (box_a, box_b) = (a.range_box(s0, s1), b.range_box(t0, t1));
+        if !grow(box_a).intersects(&box_b) {
+            continue;
+        }
+        if (size(&box_a) <= leaf && size(&box_b) <= leaf) || depth >= 48 {
+            leaves.push([s0, s1, t0, t1]);
+            if leaves.len() >= 4096 {
+                break;
+            }
+        } else if size(&box_a) >= size(&box_b) {
+            let mid = (s0 + s1) / 2.0;
+            stack.push(([s0, mid, t0, t1], depth + 1));
+            stack.push(([mid, s1, t0, t1], depth + 1));
+        } else {
+            let mid = (t0 + t1) / 2.0;
+            stack.push(([s0, s1, t0, mid], depth + 1));
+            stack.push(([s0, s1, mid, t1], depth + 1));
+        }
+    }
+
+    // Runs of leaves touching in both parameters
+    let mut group: Vec<usize> = (0..leaves.len()).collect();
+    fn root(group: &mut [usize], mut i: usize) -> usize {
+        while group[i] != i {
+            group[i] = group[group[i]];
+            i = group[i];
+        }
+        i
+    }
+    for i in 0..leaves.len() {
+        for j in i + 1..leaves.len() {
+            let (p, q) = (leaves[i], leaves[j]);
+            if p[0] <= q[1] && q[0] <= p[1] && p[2] <= q[3] && q[2] <= p[3] {
+                let (ri, rj) = (root(&mut group, i), root(&mut group, j));
+                group[ri] = rj;
+            }
+        }
+    }
+
+    let mut best: Vec<Option<(f64, f64, f64)>> = vec![None; leaves.len()];
+    for (i, leaf) in leaves.iter().enumerate() {
+        let (s, t) = ((leaf[0] + leaf[1]) / 2.0, (leaf[2] + leaf[3]) / 2.0);
+        let gap = diff(&a.point_at(s), &b.point_at(t)).magnitude();
+        let g = root(&mut group, i);
+        if best[g].is_none_or(|(_, _, d)| gap < d) {
+            best[g] = Some((s, t, gap));
+        }
+    }
+
+    best.into_iter().flatten().filter_map(|(s, t, _)| {
+        let (s, t) = refine(a, b, s, t);
+        let (p, q) = (a.point_at(s), b.point_at(t));
+        if diff(&p, &q).magnitude() > tolerance {
+            return None;
+        }
+        let (da, db) = (a.derivative_at(s), b.derivative_at(t));
+        let sine = cross(&da, &db).abs() / (da.magnitude() * db.magnitude());
+        let tangent = sine.is_nan() || sine <= tolerance.sqrt().max(1e-6);
+        Some(hit(Point::new((p.x + q.x) / 2.0, (p.y + q.y) / 2.0), s, t, tangent))
+    }).collect()
+}
+
+fn swapped(found: Vec<CurveIntersection>) -> Vec<CurveIntersection> {
+    found.into_iter().map(|i| hit(i.point, i.t_b, i.t_a, i.tangent)).collect()
+}
+
+// Points where the curves meet to within `tolerance`, in order along `a`.
+// Segments and arcs meet in closed form; pairs with a Bézier go by
+// subdividing both curves where their boxes overlap and refining each
+// contact. Overlapping stretches are reported at their ends. Degenerate
+// curves (zero length, radius or sweep) meet nothing.
+pub fn intersect_curves(a: &Curve, b: &Curve, tolerance: f64) -> Vec<CurveIntersection> {
+    let tolerance = if tolerance.is_finite() { tolerance.max(EPSILON) } else { EPSILON };
+    if a.degenerate() || b.degenerate() {
+        return vec![];
+    }
+    let mut found = match (a, b) {
+        (Curve::Segment(s), Curve::Segment(u)) => segment_segment(s, u, tolerance),
+        (Curve::Segment(s), Curve::Arc(arc)) => segment_arc(s, arc, tolerance),
+        (Curve::Arc(arc), Curve::Segment(s)) => swapped(segment_arc(s, arc, tolerance)),
+        (Curve::Arc(p), Curve::Arc(q)) => arc_arc(p, q, tolerance),
+        _ => subdivide(a, b, tolerance),
+    };
+    found.sort_by(|p, q| p.t_a.total_cmp(&q.t_a));
+    // The same point reached twice, e.g. from both ends of a closed arc
+    let mut unique: Vec<CurveIntersection> = Vec::new();
+    for i in found {
+        if !unique.iter().any(|u| diff(&u.point, &i.point).magnitude() <= tolerance) {
+            unique.push(i);
+        }
+    }
+    unique
+}
+
+// Curves are { Segment: { a, b } }, { Arc: { center, radius, start_angle,
+// sweep } } or { Bezier: { p0, p1, p2, p3 } }; returns an array of
+// { point, t_a, t_b, tangent }
+#[wasm_bindgen(js_name = intersect_curves)]
+pub fn intersect_curves_js(a: &JsValue, b: &JsValue, tolerance: f64) -> Result<JsValue, JsValue> {
+    let curve = |v: &JsValue| -> Result<Curve, JsValue> {
+        crate::from_js(v)
+    };
+    crate::to_js(&intersect_curves(&curve(a)?, &curve(b)?, tolerance))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+
+    fn circle(x: f64, y: f64, radius: f64) -> Curve {
+        Curve::Arc(Arc { center: Point::new(x, y), radius, start_angle: 0.0, sweep: TAU })
+    }
+
+    fn close(p: &Point, x: f64, y: f64) -> bool {
+        (p.x - x).abs() < 1e-9 && (p.y - y).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_segments_and_arcs() {
+        let tangent_line = Curve::Segment(Segment::new(Point::new(-2.0, 1.0), Point::new(2.0, 1.0)));
+        let touch = intersect_curves(&tangent_line, &circle(0.0, 0.0, 1.0), 1e-9);
+        assert_eq!(touch.len(), 1);
+        assert!(touch[0].tangent && close(&touch[0].point, 0.0, 1.0));
+        assert!((touch[0].t_a - 0.5).abs() < 1e-12 && (touch[0].t_b - 0.25).abs() < 1e-12);
+
+        let crossing = intersect_curves(&circle(0.0, 0.0, 1.0), &circle(1.0, 0.0, 1.0), 1e-9);
+        assert_eq!(crossing.len(), 2);
+        let h = 3f64.sqrt() / 2.0;
+        assert!(close(&crossing[0].point, 0.5, h) && close(&crossing[1].point, 0.5, -h));
+        assert!(crossing.iter().all(|i| !i.tangent));
+        assert!((crossing[0].t_b - 1.0 / 3.0).abs() < 1e-12);
+        // The upper half of one circle only meets the other once
+        let upper = Curve::Arc(Arc { center: Point::new(1.0, 0.0), radius: 1.0, start_angle: 0.0, sweep: PI });
+        assert_eq!(intersect_curves(&circle(0.0, 0.0, 1.0), &upper, 1e-9).len(), 1);
+        // Clockwise arcs measure their parameter clockwise
+        let clockwise = Curve::Arc(Arc { center: Point::new(1.0, 0.0), radius: 1.0, start_angle: PI, sweep: -PI });
+        let found = intersect_curves(&circle(0.0, 0.0, 1.0), &clockwise, 1e-9);
+        assert!(found.len() == 1 && (found[0].t_b - 1.0 / 3.0).abs() < 1e-12);
+
+        let x = Curve::Segment(Segment::new(Point::new(0.0, 0.0), Point::new(2.0, 2.0)));
+        let y = Curve::Segment(Segment::new(Point::new(0.0, 2.0), Point::new(2.0, 0.0)));
+        let cross = intersect_curves(&x, &y, 1e-9);
+        assert!(cross.len() == 1 && close(&cross[0].point, 1.0, 1.0) && cross[0].t_b == 0.5);
+        let along = Curve::Segment(Segment::new(Point::new(1.0, 1.0), Point::new(3.0, 3.0)));
+        let overlap = intersect_curves(&x, &along, 1e-9);
+        assert!(overlap.len() == 2 && overlap.iter().all(|i| i.tangent));
+        assert!(intersect_curves(&x, &circle(10.0, 0.0, 1.0), 1e-9).is_empty());
+    }
+
+    #[test]
+    fn test_bezier_intersections_match_reference() {
+        // Both curves run x = 3t, with y = 9t(1 - t) and y = 17t(1 - t) - 1.5,
+        // which meet where t² - t + 3/16 = 0: t = 1/4 and t = 3/4
+        let a = Curve::Bezier(CubicBezier::new(Point::new(0.0, 0.0), Point::new(1.0, 3.0), Point::new(2.0, 3.0), Point::new(3.0, 0.0)));
+        let b = Curve::Bezier(CubicBezier::new(Point::new(0.0, -1.5), Point::new(1.0, 25.0 / 6.0), Point::new(2.0, 25.0 / 6.0), Point::new(3.0, -1.5)));
+        let found = intersect_curves(&a, &b, 1e-9);
+        assert_eq!(found.len(), 2);
+        for (i, t) in found.iter().zip([0.25, 0.75]) {
+            assert!((i.t_a - t).abs() < 1e-6 && (i.t_b - t).abs() < 1e-6);
+            assert!(close(&i.point, 3.0 * t, 27.0 / 16.0) && !i.tangent);
+        }
+
+        let level = Curve::Segment(Segment::new(Point::new(-1.0, 27.0 / 16.0), Point::new(4.0, 27.0 / 16.0)));
+        let found = intersect_curves(&level, &a, 1e-9);
+        assert_eq!(found.len(), 2);
+        assert!((found[0].t_b - 0.25).abs() < 1e-6 && (found[1].t_b - 0.75).abs() < 1e-6);
+        assert!((found[0].t_a - 1.75 / 5.0).abs() < 1e-6);
+
+        // Grazing the top of the curve at t = 1/2: once, as a tangent
+        let top = Curve::Segment(Segment::new(Point::new(0.0, 2.25), Point::new(3.0, 2.25)));
+        let graze = intersect_curves(&a, &top, 1e-9);
+        assert_eq!(graze.len(), 1);
+        assert!(graze[0].tangent && (graze[0].t_a - 0.5).abs() < 1e-6);
+        // The curve starts inside the arc's circle and peaks outside it
+        let arc = Curve::Arc(Arc { center: Point::new(1.5, 0.0), radius: 2.0, start_angle: 0.0, sweep: PI });
+        let found = intersect_curves(&arc, &a, 1e-9);
+        assert_eq!(found.len(), 2);
+        assert!((found[0].t_b + found[1].t_b - 1.0).abs() < 1e-9);
+        assert!(found.iter().all(|i| {
+            let p = a.point_at(i.t_b);
+            ((p.x - 1.5).hypot(p.y) - 2.0).abs() < 1e-9 && close(&arc.point_at(i.t_a), p.x, p.y)
+        }));
+    }
+}
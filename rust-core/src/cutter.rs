@@ -0,0 +1,244 @@
+use wasm_bindgen::prelude::*;
+
+use crate::arrangement::overlay;
+use crate::boolean::keyhole;
+use crate::memory::{Live, ObjectKind};
+use crate::{js_error, BooleanOp, BoundingBox, CadError, Document, Matrix, MultiPolygon, Point, Polygon, PolygonWithHoles, Shape, ShapeId, EPSILON};
+
+// Cookie-cutter tool: a cutter dragged over the document, previewing where
+// it overlaps the shapes under it, then cutting or merging exactly those
+// shapes on release. Every shape's world outline and box are taken once at
+// `begin`, so a move only tests boxes and then overlaps the few it hits.
+#[wasm_bindgen]
+pub struct CutterSession {
+    // About its own origin, which `move_to` puts at the cursor
+    cutter: Polygon,
+    extent: BoundingBox,
+    targets: Vec<(ShapeId, Polygon, BoundingBox)>,
+    at: Point,
+    affected: Vec<ShapeId>,
+    _live: Live<{ ObjectKind::CutterSession as usize }>,
+}
+
+impl CutterSession {
+    pub fn begin(doc: &Document, cutter: Polygon) -> Result<CutterSession, CadError> {
+        cutter.validate().map_err(CadError::Geometry)?;
+        let targets: Vec<(ShapeId, Polygon, BoundingBox)> = doc.shapes().map(|s| {
+            let world = s.world_geometry();
+            let b = world.bounding_box();
+            (s.id, world, b)
+        }).collect();
+        let bytes = targets.iter().map(|(_, p, _)| p.vertices.len()).sum::<usize>() * std::mem::size_of::<Point>() + targets.len() * std::mem::size_of::<(ShapeId, Polygon, BoundingBox)>();
+        let extent = cutter.bounding_box();
+        Ok(CutterSession { cutter, extent, targets, at: Point::new(0.0, 0.0), affected: Vec::new(), _live: Live::with_bytes(bytes) })
+    }
+
+    // The cutter where the cursor has it, in world space
+    pub fn placed(&self) -> Polygon {
+        self.cutter.transform(&Matrix::translate(self.at.x, self.at.y))
+    }
+
+    // Moves the cutter's origin to `to` and returns, per shape it now
+    // overlaps, the outline of the overlap, in id order
+    pub fn move_to(&mut self, to: Point) -> Vec<(ShapeId, MultiPolygon)> {
+        self.at = to;
+        let placed = self.placed();
+        let reach = BoundingBox::new(Point::new(self.extent.min.x + to.x, self.extent.min.y + to.y), Point::new(self.extent.max.x + to.x, self.extent.max.y + to.y));
+        let mut previews: Vec<(ShapeId, MultiPolygon)> = self.targets.iter().filter(|(_, _, b)| b.intersects(&reach)).filter_map(|(id, world, _)| {
+            let overlap = world.boolean(&placed, BooleanOp::Intersection);
+            (overlap.iter().map(Polygon::area).sum::<f64>() > EPSILON).then_some((*id, overlap))
+        }).collect();
+        previews.sort_by_key(|(id, _)| *id);
+        self.affected = previews.iter().map(|(id, _)| *id).collect();
+        previews
+    }
+
+    // Shapes the cutter overlaps where it last moved to
+    pub fn affected(&self) -> &[ShapeId] {
+        &self.affected
+    }
+
+    // Applies `op` with the cutter where it is to the shapes it overlaps,
+    // as one undoable step, and returns the shapes holding the results:
+    // those still there, then any added for extra pieces. A difference or
+    // intersection works on each shape alone, a shape cut into pieces
+    // keeping the largest and the rest becoming copies of it, and one cut
+    // away entirely being removed. A union merges the cutter and all of
+    // them into the first, the others being removed. Results keep each
+    // shape's transform.
+    pub fn commit(&self, doc: &mut Document, op: BooleanOp) -> Result<Vec<ShapeId>, CadError> {
+        let placed = self.placed();
+        let mut affected = self.affected.clone();
+        affected.sort_unstable();
+        affected.dedup();
+        let shapes: Vec<Shape> = affected.iter().map(|&id| doc.shape(id).cloned()).collect::<Result<_, CadError>>()?;
+        if shapes.is_empty() {
+            return Ok(Vec::new());
+        }
+        let worlds: Vec<Polygon> = shapes.iter().map(Shape::world_geometry).collect();
+        let results: Vec<Vec<PolygonWithHoles>> = match op {
+            BooleanOp::Union => {
+                let rings: Vec<Vec<Point>> = worlds.into_iter().map(|w| w.vertices).collect();
+                let merged = overlay(&rings, &[placed.vertices], |a, b| a || b);
+                std::iter::once(merged).chain((1..shapes.len()).map(|_| Vec::new())).collect()
+            }
+            _ => worlds.iter().map(|w| w.boolean_regions(&placed, op)).collect(),
+        };
+        let results: Vec<MultiPolygon> = results.into_iter()
+            .map(|r| crate::strict::checked_regions(r, "cutter").map(|r| r.into_iter().map(keyhole).collect()))
+            .collect::<Result<_, CadError>>()?;
+        // Everything that can fail is settled before the checkpoint
+        let mut placed_back = Vec::with_capacity(shapes.len());
+        for (shape, mut pieces) in shapes.into_iter().zip(results) {
+            if pieces.is_empty() {
+                placed_back.push((shape, Vec::new()));
+                continue;
+            }
+            let to_local = shape.world_transform().inverse().ok_or_else(|| CadError::Degenerate(format!("shape {} has a transform that can't be undone", shape.id)))?;
+            pieces.sort_by(|a, b| b.area().total_cmp(&a.area()));
+            let local: Vec<Polygon> = pieces.iter().map(|p| p.transform(&to_local)).collect();
+            placed_back.push((shape, local));
+        }
+
+        doc.checkpoint();
+        let applied = (|| {
+            let (mut kept, mut added) = (Vec::new(), Vec::new());
+            for (shape, pieces) in placed_back {
+                let mut pieces = pieces.into_iter();
+                let Some(first) = pieces.next() else {
+                    doc.remove_shape(shape.id)?;
+                    continue;
+                };
+                kept.push(shape.id);
+                doc.shape_mut(shape.id)?.geometry = first;
+                for piece in pieces {
+                    added.push(doc.insert(Shape { geometry: piece, ..shape.clone() }));
+                }
+            }
+            kept.extend(added);
+            Ok(kept)
+        })();
+        if applied.is_err() {
+            doc.roll_back();
+        }
+        applied
+    }
+}
+
+#[wasm_bindgen]
+impl CutterSession {
+    // `cutter` is an array of {x, y} about the cursor
+    #[wasm_bindgen(js_name = begin)]
+    pub fn begin_js(doc: &Document, cutter: &JsValue) -> Result<CutterSession, JsValue> {
+        CutterSession::begin(doc, crate::polygon_from(cutter)?).map_err(js_error)
+    }
+
+    // Array of [id, [overlap outlines]]
+    #[wasm_bindgen(js_name = move_to)]
+    pub fn move_to_js(&mut self, x: f64, y: f64) -> Result<JsValue, JsValue> {
+        crate::to_js(&self.move_to(Point::new(x, y)))
+    }
+
+    #[wasm_bindgen(js_name = affected)]
+    pub fn affected_js(&self) -> Vec<ShapeId> {
+        self.affected.clone()
+    }
+
+    // `op` is "union", "intersection" or "difference"
+    #[wasm_bindgen(js_name = commit)]
+    pub fn commit_js(&self, doc: &mut Document, op: &str) -> Result<Vec<ShapeId>, JsValue> {
+        let op = BooleanOp::parse(op).map_err(js_error)?;
+        self.commit(doc, op).map_err(js_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn circle(r: f64) -> Polygon {
+        Polygon::new((0..64).map(|i| {
+            let a = i as f64 * std::f64::consts::TAU / 64.0;
+            Point::new(r * a.cos(), r * a.sin())
+        }).collect())
+    }
+
+    fn square(x: f64) -> Polygon {
+        Polygon::new(vec![Point::new(x, 0.0), Point::new(x + 2.0, 0.0), Point::new(x + 2.0, 2.0), Point::new(x, 2.0)])
+    }
+
+    #[test]
+    fn test_circle_cuts_three_of_ten_squares() {
+        let mut doc = Document::new();
+        let ids: Vec<ShapeId> = (0..10).map(|k| doc.add_shape(square(3.0 * k as f64))).collect();
+        // One square placed by its transform rather than its vertices
+        doc.shape_mut(ids[4]).unwrap().geometry = square(0.0);
+        doc.set_transform(ids[4], Matrix::translate(12.0, 0.0)).unwrap();
+        let before: Vec<f64> = ids.iter().map(|&id| doc.shape(id).unwrap().world_geometry().area()).collect();
+
+        let mut session = CutterSession::begin(&doc, circle(2.5)).unwrap();
+        assert!(session.move_to(Point::new(-20.0, 1.0)).is_empty());
+        // Across the gaps either side of the fifth square, into its neighbours
+        let preview = session.move_to(Point::new(13.0, 1.0));
+        assert_eq!(session.affected(), &ids[3..6]);
+        let overlaps: Vec<f64> = preview.iter().map(|(_, p)| p.iter().map(Polygon::area).sum()).collect();
+        // The middle square lies inside the circle
+        assert!((overlaps[1] - 4.0).abs() < 1e-9);
+        assert!(overlaps[0] > 0.1 && (overlaps[0] - overlaps[2]).abs() < 1e-9);
+
+        let touched = session.commit(&mut doc, BooleanOp::Difference).unwrap();
+        assert_eq!(touched, vec![ids[3], ids[5]]);
+        assert!(doc.shape(ids[4]).is_err());
+        for (k, &id) in ids.iter().enumerate() {
+            let Ok(shape) = doc.shape(id) else { continue };
+            let expected = before[k] - if k == 3 { overlaps[0] } else if k == 5 { overlaps[2] } else { 0.0 };
+            assert!((shape.world_geometry().area() - expected).abs() < 1e-9, "square {}", k);
+        }
+        assert_eq!(doc.shapes().count(), 9);
+        // One step undoes the whole cut
+        assert!(doc.undo());
+        assert_eq!(doc.shapes().count(), 10);
+        assert!((doc.shape(ids[3]).unwrap().geometry.area() - 4.0).abs() < 1e-12);
+        assert_eq!(doc.shape(ids[4]).unwrap().transform, Matrix::translate(12.0, 0.0));
+    }
+
+    #[test]
+    fn test_union_merges_the_cutter_and_what_it_touches() {
+        let mut doc = Document::new();
+        let ids: Vec<ShapeId> = (0..3).map(|k| doc.add_shape(square(3.0 * k as f64))).collect();
+        let mut session = CutterSession::begin(&doc, Polygon::new(vec![Point::new(-2.0, -0.5), Point::new(2.0, -0.5), Point::new(2.0, 0.5), Point::new(-2.0, 0.5)])).unwrap();
+        session.move_to(Point::new(2.5, 1.0));
+        assert_eq!(session.affected().to_vec(), ids[..2].to_vec());
+        assert_eq!(session.commit(&mut doc, BooleanOp::Union).unwrap(), vec![ids[0]]);
+        assert_eq!(doc.shapes().count(), 2);
+        // Two squares joined by the part of the bar in the gap between them
+        assert!((doc.shape(ids[0]).unwrap().geometry.area() - (8.0 + 1.0)).abs() < 1e-9);
+        assert!(doc.shape(ids[1]).is_err() && doc.shape(ids[2]).is_ok());
+    }
+
+    #[test]
+    fn test_failed_commit_leaves_no_history() {
+        let mut doc = Document::new();
+        let ids: Vec<ShapeId> = (0..3).map(|k| doc.add_shape(square(3.0 * k as f64))).collect();
+        let mut session = CutterSession::begin(&doc, circle(2.5)).unwrap();
+        session.move_to(Point::new(2.5, 1.0));
+        assert_eq!(session.affected(), &ids[..2]);
+
+        // A shape gone since the move fails the commit before anything changes
+        doc.remove_shape(ids[1]).unwrap();
+        let before = doc.content_hash_all();
+        assert!(matches!(session.commit(&mut doc, BooleanOp::Difference), Err(CadError::UnknownShape(_))));
+        assert_eq!(doc.content_hash_all(), before);
+        assert!(!doc.undo() && !doc.redo());
+
+        // The same id twice is cut once, its two corners left as two shapes
+        session.affected = vec![ids[0], ids[0]];
+        let before = doc.shape(ids[0]).unwrap().geometry.area();
+        let touched = session.commit(&mut doc, BooleanOp::Difference).unwrap();
+        assert_eq!((touched.len(), touched[0]), (2, ids[0]));
+        assert!(doc.shape(ids[0]).unwrap().geometry.area() < before);
+        assert!(doc.undo());
+        assert_eq!(doc.shape(ids[0]).unwrap().geometry.area(), before);
+        assert!(doc.shape(touched[1]).is_err());
+    }
+}
@@ -0,0 +1,270 @@
+use wasm_bindgen::prelude::*;
+
+use crate::predicates::point_in_ring;
+use crate::{BoundingBox, Point, Polygon, EPSILON};
+
+impl Polygon {
+    pub fn is_rectilinear(&self) -> bool {
+        let n = self.vertices.len();
+        if n < 4 {
+            return false;
+        }
+
+        (0..n).all(|i| {
+            let a = self.vertices[i];
+            let b = self.vertices[(i + 1) % n];
+            (a.x - b.x).abs() < EPSILON || (a.y - b.y).abs() < EPSILON
+        })
+    }
+
+    // Exact decomposition of a rectilinear polygon into non-overlapping rectangles.
+    // The polygon is swept left to right across the x coordinates of its vertical
+    // edges (which is where every concave vertex lives); a rectangle keeps growing
+    // while its y-interval survives unchanged into the next strip.
+    // Non-rectilinear input returns an empty list.
+    pub fn decompose_rectangles(&self) -> Vec<BoundingBox> {
+        if !self.is_rectilinear() {
+            return Vec::new();
+        }
+
+        let n = self.vertices.len();
+        let mut xs: Vec<f64> = self.vertices.iter().map(|v| v.x).collect();
+        xs.sort_by(f64::total_cmp);
+        xs.dedup_by(|a, b| (*a - *b).abs() < EPSILON);
+
+        let mut result = Vec::new();
+        // Rectangles still open from the previous strip: (y0, y1, start x)
+        let mut open: Vec<(f64, f64, f64)> = Vec::new();
+
+        for w in xs.windows(2) {
+            let (x0, x1) = (w[0], w[1]);
+            let xm = (x0 + x1) / 2.0;
+
+            // Horizontal edges spanning the strip give its cross-section
+            let mut ys = Vec::new();
+            for i in 0..n {
+                let a = self.vertices[i];
+                let b = self.vertices[(i + 1) % n];
+                if (a.y - b.y).abs() < EPSILON && (a.x.min(b.x) < xm) && (a.x.max(b.x) > xm) {
+                    ys.push(a.y);
+                }
+            }
+            ys.sort_by(f64::total_cmp);
+
+            let intervals: Vec<(f64, f64)> = ys.chunks(2)
+                .filter(|c| c.len() == 2)
+                .map(|c| (c[0], c[1]))
+                .collect();
+
+            let mut next_open = Vec::new();
+            for &(y0, y1) in &intervals {
+                let continued = open.iter().position(|&(oy0, oy1, _)| {
+                    (oy0 - y0).abs() < EPSILON && (oy1 - y1).abs() < EPSILON
+                });
+                match continued {
+                    Some(idx) => next_open.push(open.remove(idx)),
+                    None => next_open.push((y0, y1, x0)),
+                }
+            }
+
+            for (y0, y1, start) in open {
+                result.push(BoundingBox::new(Point::new(start, y0), Point::new(x0, y1)));
+            }
+            open = next_open;
+        }
+
+        if let Some(&last_x) = xs.last() {
+            for (y0, y1, start) in open {
+                result.push(BoundingBox::new(Point::new(start, y0), Point::new(last_x, y1)));
+            }
+        }
+
+        result
+    }
+
+    // Approximate cover of an arbitrary polygon by grid-aligned rectangles.
+    // Every cell touched by the polygon is included, so the union covers the
+    // shape and overshoots it by at most one cell. Runs of cells within a row
+    // are merged, then identical runs in consecutive rows are merged.
+    pub fn cover_with_rectangles(&self, cell_size: f64) -> Vec<BoundingBox> {
+        if self.vertices.len() < 3 || cell_size <= 0.0 || !cell_size.is_finite() {
+            return Vec::new();
+        }
+
+        let bbox = self.bounding_box();
+        let cols = ((bbox.width() / cell_size).ceil() as usize).max(1);
+        let rows = ((bbox.height() / cell_size).ceil() as usize).max(1);
+        let n = self.vertices.len();
+
+        let mut result = Vec::new();
+        // Runs still open from the previous row: (first col, last col, start row)
+        let mut open: Vec<(usize, usize, usize)> = Vec::new();
+
+        for row in 0..=rows {
+            let mut runs = Vec::new();
+
+            if row < rows {
+                let y0 = bbox.min.y + row as f64 * cell_size;
+                let y1 = y0 + cell_size;
+
+                // Only edges reaching into this row can touch its cells
+                let edges: Vec<(Point, Point)> = (0..n)
+                    .map(|i| (self.vertices[i], self.vertices[(i + 1) % n]))
+                    .filter(|(a, b)| a.y.max(b.y) >= y0 && a.y.min(b.y) <= y1)
+                    .collect();
+
+                let mut run_start: Option<usize> = None;
+                for col in 0..=cols {
+                    let covered = col < cols && {
+                        let x0 = bbox.min.x + col as f64 * cell_size;
+                        let cell = BoundingBox::new(Point::new(x0, y0), Point::new(x0 + cell_size, y1));
+                        let center = Point::new(x0 + cell_size / 2.0, y0 + cell_size / 2.0);
+                        point_in_ring(&self.vertices, &center)
+                            || edges.iter().any(|(a, b)| segment_touches_box(a, b, &cell))
+                    };
+
+                    match (covered, run_start) {
+                        (true, None) => run_start = Some(col),
+                        (false, Some(start)) => {
+                            runs.push((start, col - 1));
+                            run_start = None;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
+            let mut next_open = Vec::new();
+            for &(c0, c1) in &runs {
+                match open.iter().position(|&(o0, o1, _)| o0 == c0 && o1 == c1) {
+                    Some(idx) => next_open.push(open.remove(idx)),
+                    None => next_open.push((c0, c1, row)),
+                }
+            }
+
+            for (c0, c1, start_row) in open {
+                result.push(BoundingBox::new(
+                    Point::new(bbox.min.x + c0 as f64 * cell_size, bbox.min.y + start_row as f64 * cell_size),
+                    Point::new(bbox.min.x + (c1 + 1) as f64 * cell_size, bbox.min.y + row as f64 * cell_size),
+                ));
+            }
+            open = next_open;
+        }
+
+        result
+    }
+}
+
+// Liang-Barsky clip of a segment against a box, inclusive of the box boundary
+fn segment_touches_box(a: &Point, b: &Point, bbox: &BoundingBox) -> bool {
+    let dx = b.x - a.x;
+    let dy = b.y - a.y;
+    let mut t0: f64 = 0.0;
+    let mut t1: f64 = 1.0;
+
+    let checks = [
+        (-dx, a.x - bbox.min.x),
+        (dx, bbox.max.x - a.x),
+        (-dy, a.y - bbox.min.y),
+        (dy, bbox.max.y - a.y),
+    ];
+
+    for (p, q) in checks {
+        if p.abs() < EPSILON {
+            if q < 0.0 {
+                return false;
+            }
+        } else {
+            let t = q / p;
+            if p < 0.0 {
+                t0 = t0.max(t);
+            } else {
+                t1 = t1.min(t);
+            }
+            if t0 > t1 {
+                return false;
+            }
+        }
+    }
+
+    true
+}
+
+#[wasm_bindgen]
+pub fn decompose_rectangles(vertices: &JsValue) -> Result<JsValue, JsValue> {
+    let polygon: Polygon = crate::polygon_from(vertices)?;
+    crate::to_js(&polygon.decompose_rectangles())
+}
+
+#[wasm_bindgen]
+pub fn cover_with_rectangles(vertices: &JsValue, cell_size: f64) -> Result<JsValue, JsValue> {
+    let polygon: Polygon = crate::polygon_from(vertices)?;
+    crate::to_js(&polygon.cover_with_rectangles(cell_size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn l_shape() -> Polygon {
+        Polygon::new(vec![
+            Point::new(0.0, 0.0),
+            Point::new(2.0, 0.0),
+            Point::new(2.0, 1.0),
+            Point::new(1.0, 1.0),
+            Point::new(1.0, 2.0),
+            Point::new(0.0, 2.0),
+        ])
+    }
+
+    fn boxes_overlap(a: &BoundingBox, b: &BoundingBox) -> bool {
+        a.min.x < b.max.x - EPSILON && b.min.x < a.max.x - EPSILON
+            && a.min.y < b.max.y - EPSILON && b.min.y < a.max.y - EPSILON
+    }
+
+    #[test]
+    fn test_l_shape_decomposes_into_two_rectangles() {
+        let polygon = l_shape();
+        let rects = polygon.decompose_rectangles();
+        assert_eq!(rects.len(), 2);
+
+        let total: f64 = rects.iter().map(|r| r.area()).sum();
+        assert!((total - polygon.area()).abs() < 1e-12);
+        assert!(!boxes_overlap(&rects[0], &rects[1]));
+    }
+
+    #[test]
+    fn test_non_rectilinear_decomposition_is_empty() {
+        let triangle = Polygon::new(vec![
+            Point::new(0.0, 0.0),
+            Point::new(4.0, 0.0),
+            Point::new(2.0, 3.0),
+        ]);
+        assert!(triangle.decompose_rectangles().is_empty());
+    }
+
+    #[test]
+    fn test_cover_with_rectangles() {
+        let triangle = Polygon::new(vec![
+            Point::new(0.0, 0.0),
+            Point::new(4.0, 0.0),
+            Point::new(0.0, 4.0),
+        ]);
+        let cell = 0.5;
+        let rects = triangle.cover_with_rectangles(cell);
+
+        for i in 0..rects.len() {
+            for j in (i + 1)..rects.len() {
+                assert!(!boxes_overlap(&rects[i], &rects[j]));
+            }
+        }
+
+        // Every vertex is covered and the overshoot stays within one cell per row
+        for v in &triangle.vertices {
+            assert!(rects.iter().any(|r| v.x >= r.min.x && v.x <= r.max.x && v.y >= r.min.y && v.y <= r.max.y));
+        }
+        let total: f64 = rects.iter().map(|r| r.area()).sum();
+        assert!(total >= triangle.area());
+        assert!(total <= triangle.area() + triangle.perimeter() * cell * 2.0);
+    }
+}
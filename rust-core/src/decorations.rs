@@ -0,0 +1,273 @@
+use std::f64::consts::TAU;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::memory::{Live, ObjectKind};
+use crate::{BoundingBox, CadError, Document, Point, ShapeId, EPSILON};
+
+// Sides of the polygon drawn for a round dot
+const DOT_SEGMENTS: usize = 8;
+
+// Visible world area and its zoom in screen pixels per world unit
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    pub bounds: BoundingBox,
+    pub scale: f64,
+}
+
+// Decoration sizes in screen pixels
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct DecorationStyle {
+    pub handle_size: f64,
+    pub vertex_dot_size: f64,
+    // Distance of the rotation handle above the selection box
+    pub rotation_offset: f64,
+    pub rotation_handle_size: f64,
+    pub dash_length: f64,
+    pub dash_gap: f64,
+    pub dash_width: f64,
+    pub handles: bool,
+    pub vertices: bool,
+    pub rotation: bool,
+    pub outline: bool,
+}
+
+impl Default for DecorationStyle {
+    fn default() -> DecorationStyle {
+        DecorationStyle {
+            handle_size: 8.0,
+            vertex_dot_size: 5.0,
+            rotation_offset: 24.0,
+            rotation_handle_size: 8.0,
+            dash_length: 6.0,
+            dash_gap: 4.0,
+            dash_width: 1.0,
+            handles: true,
+            vertices: true,
+            rotation: true,
+            outline: true,
+        }
+    }
+}
+
+// Flat [x0, y0, x1, y1, x2, y2, ...] triangle lists in world space, one per
+// kind of decoration, like `Document::tessellate`
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TessellationBuffers {
+    handles: Vec<f32>,
+    vertices: Vec<f32>,
+    rotation: Vec<f32>,
+    outline: Vec<f32>,
+    live: Live<{ ObjectKind::TessellationBuffers as usize }>,
+}
+
+#[wasm_bindgen]
+impl TessellationBuffers {
+    #[wasm_bindgen(getter)]
+    pub fn handles(&self) -> Vec<f32> {
+        self.handles.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn vertices(&self) -> Vec<f32> {
+        self.vertices.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn rotation(&self) -> Vec<f32> {
+        self.rotation.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn outline(&self) -> Vec<f32> {
+        self.outline.clone()
+    }
+}
+
+fn push_triangle(buffer: &mut Vec<f32>, a: Point, b: Point, c: Point) {
+    for p in [a, b, c] {
+        buffer.push(p.x as f32);
+        buffer.push(p.y as f32);
+    }
+}
+
+// Axis-aligned square of world side `size` about `at`, unless it's out of view
+fn push_square(buffer: &mut Vec<f32>, view: &BoundingBox, at: Point, size: f64) {
+    let h = size / 2.0;
+    let bounds = BoundingBox::new(Point::new(at.x - h, at.y - h), Point::new(at.x + h, at.y + h));
+    if !bounds.intersects(view) {
+        return;
+    }
+    let (a, b, c, d) = (bounds.min, Point::new(bounds.max.x, bounds.min.y), bounds.max, Point::new(bounds.min.x, bounds.max.y));
+    push_triangle(buffer, a, b, c);
+    push_triangle(buffer, a, c, d);
+}
+
+fn push_dot(buffer: &mut Vec<f32>, view: &BoundingBox, at: Point, size: f64) {
+    let r = size / 2.0;
+    if !BoundingBox::new(Point::new(at.x - r, at.y - r), Point::new(at.x + r, at.y + r)).intersects(view) {
+        return;
+    }
+    let rim = |k: usize| {
+        let a = TAU * k as f64 / DOT_SEGMENTS as f64;
+        Point::new(at.x + r * a.cos(), at.y + r * a.sin())
+    };
+    for k in 0..DOT_SEGMENTS {
+        push_triangle(buffer, at, rim(k), rim(k + 1));
+    }
+}
+
+// Dashes of `dash` on, `gap` off along the closed ring, with the pattern
+// carried on around corners
+fn push_dashes(buffer: &mut Vec<f32>, view: &BoundingBox, ring: &[Point], dash: f64, gap: f64, width: f64) {
+    let period = dash + gap;
+    if dash <= EPSILON || period <= EPSILON {
+        return;
+    }
+    let n = ring.len();
+    let mut phase = 0.0;
+    for i in 0..n {
+        let (a, b) = (ring[i], ring[(i + 1) % n]);
+        let len = (b.x - a.x).hypot(b.y - a.y);
+        if len < EPSILON {
+            continue;
+        }
+        let (ux, uy) = ((b.x - a.x) / len, (b.y - a.y) / len);
+        let (nx, ny) = (-uy * width / 2.0, ux * width / 2.0);
+        let at = |s: f64| Point::new(a.x + ux * s, a.y + uy * s);
+        // Start of the first dash touching this edge
+        let mut s = -phase;
+        while s < len {
+            let (from, to) = (s.max(0.0), (s + dash).min(len));
+            if to > from {
+                let (p, q) = (at(from), at(to));
+                if BoundingBox::from_points(&[p, q]).intersects(view) {
+                    let corners = [
+                        Point::new(p.x - nx, p.y - ny), Point::new(q.x - nx, q.y - ny),
+                        Point::new(q.x + nx, q.y + ny), Point::new(p.x + nx, p.y + ny),
+                    ];
+                    push_triangle(buffer, corners[0], corners[1], corners[2]);
+                    push_triangle(buffer, corners[0], corners[2], corners[3]);
+                }
+            }
+            s += period;
+        }
+        phase = (phase + len) % period;
+    }
+}
+
+// Screen-constant decorations for the selected shapes in one pass. Sizes
+// from `style` are converted to world units through the viewport scale, so
+// they keep their pixel size at any zoom. Shapes wholly out of view are
+// skipped, as is each decoration that falls outside it.
+pub fn decoration_geometry(doc: &Document, selection: &[ShapeId], viewport: &Viewport, style: &DecorationStyle) -> Result<TessellationBuffers, CadError> {
+    if !(viewport.scale.is_finite() && viewport.scale > 0.0) {
+        return Err(CadError::InvalidInput(format!("viewport scale must be positive, got {}", viewport.scale)));
+    }
+    let px = 1.0 / viewport.scale;
+    let view = &viewport.bounds;
+    // Furthest any decoration reaches outside a shape's box
+    let reach = px * (style.rotation_offset + style.rotation_handle_size).max(style.handle_size).max(style.vertex_dot_size);
+    let mut buffers = TessellationBuffers::default();
+    for &id in selection {
+        let world = doc.display_geometry(id)?;
+        let b = world.bounding_box();
+        let grown = BoundingBox::new(Point::new(b.min.x - reach, b.min.y - reach), Point::new(b.max.x + reach, b.max.y + reach));
+        if !grown.intersects(view) {
+            continue;
+        }
+
+        if style.outline {
+            push_dashes(&mut buffers.outline, view, &world.vertices, style.dash_length * px, style.dash_gap * px, style.dash_width * px);
+        }
+        if style.vertices {
+            for p in &world.vertices {
+                push_dot(&mut buffers.vertices, view, *p, style.vertex_dot_size * px);
+            }
+        }
+        let mid = Point::new((b.min.x + b.max.x) / 2.0, (b.min.y + b.max.y) / 2.0);
+        if style.handles {
+            // Corners and side midpoints of the selection box
+            for (x, y) in [(b.min.x, b.min.y), (mid.x, b.min.y), (b.max.x, b.min.y), (b.max.x, mid.y),
+                           (b.max.x, b.max.y), (mid.x, b.max.y), (b.min.x, b.max.y), (b.min.x, mid.y)] {
+                push_square(&mut buffers.handles, view, Point::new(x, y), style.handle_size * px);
+            }
+        }
+        if style.rotation {
+            push_dot(&mut buffers.rotation, view, Point::new(mid.x, b.max.y + style.rotation_offset * px), style.rotation_handle_size * px);
+        }
+    }
+    let floats = buffers.handles.len() + buffers.vertices.len() + buffers.rotation.len() + buffers.outline.len();
+    buffers.live.set_bytes(floats * std::mem::size_of::<f32>());
+    Ok(buffers)
+}
+
+#[wasm_bindgen(js_name = decoration_geometry)]
+pub fn decoration_geometry_js(doc: &Document, selection: Vec<ShapeId>, viewport: &JsValue, style: &JsValue) -> Result<TessellationBuffers, JsValue> {
+    let viewport: Viewport = crate::from_js(viewport)?;
+    let style: DecorationStyle = crate::options_from(style)?;
+    decoration_geometry(doc, &selection, &viewport, &style).map_err(crate::js_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Polygon;
+
+    fn viewport(scale: f64) -> Viewport {
+        Viewport { bounds: BoundingBox::new(Point::new(-100.0, -100.0), Point::new(100.0, 100.0)), scale }
+    }
+
+    // World extent of each quad (two triangles) in the buffer
+    fn quad_sizes(buffer: &[f32]) -> Vec<(f64, f64)> {
+        buffer.chunks(12).map(|q| {
+            let xs = q.iter().step_by(2).map(|&v| v as f64);
+            let ys = q.iter().skip(1).step_by(2).map(|&v| v as f64);
+            let (x0, x1) = xs.fold((f64::MAX, f64::MIN), |(lo, hi), v| (lo.min(v), hi.max(v)));
+            let (y0, y1) = ys.fold((f64::MAX, f64::MIN), |(lo, hi), v| (lo.min(v), hi.max(v)));
+            (x1 - x0, y1 - y0)
+        }).collect()
+    }
+
+    fn document() -> (Document, Vec<ShapeId>) {
+        let mut doc = Document::new();
+        let a = doc.add_shape(Polygon::new(vec![Point::new(0.0, 0.0), Point::new(10.0, 0.0), Point::new(10.0, 5.0), Point::new(0.0, 5.0)]));
+        let b = doc.add_shape(Polygon::new(vec![Point::new(20.0, 0.0), Point::new(30.0, 0.0), Point::new(25.0, 8.0)]));
+        doc.add_shape(Polygon::new(vec![Point::new(40.0, 0.0), Point::new(50.0, 0.0), Point::new(45.0, 8.0)]));
+        (doc, vec![a, b])
+    }
+
+    #[test]
+    fn test_handles_keep_pixel_size_across_zoom() {
+        let (doc, selection) = document();
+        let style = DecorationStyle::default();
+        for scale in [2.0, 16.0] {
+            let buffers = decoration_geometry(&doc, &selection, &viewport(scale), &style).unwrap();
+            let quads = quad_sizes(&buffers.handles);
+            assert_eq!(quads.len(), 16);
+            for (w, h) in quads {
+                assert!((w * scale - style.handle_size).abs() < 1e-4 && (h * scale - style.handle_size).abs() < 1e-4);
+            }
+            // One dot per vertex of the selected shapes, one rotation handle each
+            assert_eq!(buffers.vertices.len() / (6 * DOT_SEGMENTS), 7);
+            assert_eq!(buffers.rotation.len() / (6 * DOT_SEGMENTS), 2);
+            assert!(!buffers.outline.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_culls_to_viewport() {
+        let (doc, selection) = document();
+        let style = DecorationStyle { outline: false, ..DecorationStyle::default() };
+        // Only the lower-left corner of the rectangle is in view
+        let view = Viewport { bounds: BoundingBox::new(Point::new(-1.0, -1.0), Point::new(1.0, 1.0)), scale: 10.0 };
+        let buffers = decoration_geometry(&doc, &selection, &view, &style).unwrap();
+        assert_eq!(quad_sizes(&buffers.handles).len(), 1);
+        assert_eq!(buffers.vertices.len() / (6 * DOT_SEGMENTS), 1);
+        assert!(buffers.rotation.is_empty());
+        assert!(decoration_geometry(&doc, &[99], &view, &style).is_err());
+    }
+}
@@ -0,0 +1,249 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::census::counter_clockwise;
+use crate::{js_error, AttributeValue, CadError, Document, Point, ShapeId, ShapeSignatureIndex, EPSILON};
+
+// Which shapes may count as duplicates of each other
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum DedupScope {
+    All,
+    // Only shapes on the same layer
+    SameLayer,
+    // Only these shapes, amongst themselves
+    Shapes(Vec<ShapeId>),
+}
+
+// What happens to the copies that aren't kept
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum DuplicateAction {
+    Delete,
+    // Moved onto this layer, for views to hide; a transform on the layer
+    // moves them with it
+    MoveToLayer(String),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct DedupOptions {
+    // The copy kept is the one on the first of these layers; copies on
+    // other layers go by the order their layers first appear in the
+    // document, then by id
+    pub preferred_layers: Vec<String>,
+    pub action: DuplicateAction,
+    // Gives the kept copy attributes only the others have
+    pub merge_attributes: bool,
+}
+
+impl Default for DedupOptions {
+    fn default() -> DedupOptions {
+        DedupOptions { preferred_layers: Vec::new(), action: DuplicateAction::Delete, merge_attributes: true }
+    }
+}
+
+// An attribute the copies in a group don't agree on
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AttributeConflict {
+    pub key: String,
+    // The kept copy's value afterwards
+    pub kept: Option<AttributeValue>,
+    // Copies with some other value, and that value
+    pub others: Vec<(ShapeId, AttributeValue)>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DuplicateGroup {
+    pub kept: ShapeId,
+    pub duplicates: Vec<ShapeId>,
+    pub conflicts: Vec<AttributeConflict>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct DedupReport {
+    pub groups: Vec<DuplicateGroup>,
+}
+
+impl DedupReport {
+    pub fn duplicate_count(&self) -> usize {
+        self.groups.iter().map(|g| g.duplicates.len()).sum()
+    }
+}
+
+// Whether the rings trace the same outline in world space, every vertex of
+// one within `tolerance` of the matching vertex of the other, whichever
+// vertex each starts at and whichever way each winds
+fn coincident(a: &[Point], b: &[Point], tolerance: f64) -> bool {
+    let n = a.len();
+    if n != b.len() || n == 0 {
+        return false;
+    }
+    let (a, b) = (counter_clockwise(a), counter_clockwise(b));
+    (0..n).any(|k| (0..n).all(|i| (a[i].x - b[(k + i) % n].x).hypot(a[i].y - b[(k + i) % n].y) <= tolerance))
+}
+
+impl Document {
+    // Finds shapes lying exactly on top of one another, to within
+    // `tolerance` at every vertex, and keeps one of each set, deleting or
+    // moving aside the rest as one undoable step. Congruent shapes found
+    // through the signature index are confirmed vertex by vertex. Each set
+    // is gathered around its lowest id, so a chain of shapes each within
+    // tolerance of the next is not merged end to end.
+    pub fn deduplicate_overlapping(&mut self, tolerance: f64, scope: DedupScope, options: &DedupOptions) -> Result<DedupReport, CadError> {
+        let tolerance = tolerance.max(EPSILON);
+        let mut layer_order: HashMap<Option<&str>, usize> = HashMap::new();
+        for shape in self.shapes() {
+            let next = layer_order.len();
+            layer_order.entry(shape.layer.as_deref()).or_insert(next);
+        }
+        let rank = |layer: Option<&str>| match options.preferred_layers.iter().position(|l| Some(l.as_str()) == layer) {
+            Some(i) => i,
+            None => options.preferred_layers.len() + layer_order[&layer],
+        };
+
+        let allowed: Option<HashSet<ShapeId>> = match &scope {
+            DedupScope::Shapes(ids) => Some(ids.iter().copied().collect()),
+            _ => None,
+        };
+        let index = ShapeSignatureIndex::build(self, tolerance);
+        let mut grouped: HashSet<ShapeId> = HashSet::new();
+        let mut groups: Vec<DuplicateGroup> = Vec::new();
+        for shape in self.shapes() {
+            if grouped.contains(&shape.id) || allowed.as_ref().is_some_and(|a| !a.contains(&shape.id)) {
+                continue;
+            }
+            let world = shape.world_geometry();
+            let matches: Vec<ShapeId> = index.query(&world).into_iter().filter(|&j| j != shape.id && !grouped.contains(&j) && allowed.as_ref().is_none_or(|a| a.contains(&j))).filter(|&j| {
+                let other = self.shape(j).unwrap();
+                (scope != DedupScope::SameLayer || other.layer == shape.layer) && coincident(&world.vertices, &other.world_geometry().vertices, tolerance)
+            }).collect();
+            if matches.is_empty() {
+                continue;
+            }
+            let mut members = vec![shape.id];
+            members.extend(matches);
+            grouped.extend(&members);
+            let kept = *members.iter().min_by_key(|&&id| (rank(self.shape(id).unwrap().layer.as_deref()), id)).unwrap();
+            members.retain(|&id| id != kept);
+            groups.push(DuplicateGroup { kept, duplicates: members, conflicts: Vec::new() });
+        }
+        if groups.is_empty() {
+            return Ok(DedupReport::default());
+        }
+
+        self.checkpoint();
+        for group in &mut groups {
+            let kept = self.shape(group.kept)?.attributes.clone();
+            let theirs: Vec<(ShapeId, _)> = group.duplicates.iter().map(|&id| (id, self.shape(id).unwrap().attributes.clone())).collect();
+            let keys: BTreeSet<String> = kept.keys().chain(theirs.iter().flat_map(|(_, a)| a.keys())).cloned().collect();
+            for key in keys {
+                let mut value = kept.get(&key).cloned();
+                let others: Vec<(ShapeId, AttributeValue)> = theirs.iter().filter_map(|(id, a)| a.get(&key).map(|v| (*id, v.clone()))).collect();
+                if value.is_none() && options.merge_attributes {
+                    value = others.first().map(|(_, v)| v.clone());
+                    self.set_attribute(group.kept, &key, value.clone().unwrap())?;
+                }
+                let others: Vec<(ShapeId, AttributeValue)> = others.into_iter().filter(|(_, v)| Some(v) != value.as_ref()).collect();
+                if !others.is_empty() {
+                    group.conflicts.push(AttributeConflict { key, kept: value, others });
+                }
+            }
+            for &id in &group.duplicates {
+                match &options.action {
+                    DuplicateAction::Delete => {
+                        self.remove_shape(id)?;
+                    }
+                    DuplicateAction::MoveToLayer(layer) => self.set_layer(id, Some(layer.clone()))?,
+                }
+            }
+        }
+        Ok(DedupReport { groups })
+    }
+}
+
+#[wasm_bindgen]
+impl Document {
+    // `scope` is "All", "SameLayer" or { Shapes: [ids] }; `options` is
+    // { preferred_layers, action: "Delete" | { MoveToLayer: name },
+    // merge_attributes } or undefined for the defaults. Returns
+    // { groups: [{ kept, duplicates, conflicts: [{ key, kept, others }] }] }
+    #[wasm_bindgen(js_name = deduplicate_overlapping)]
+    pub fn deduplicate_overlapping_js(&mut self, tolerance: f64, scope: &JsValue, options: &JsValue) -> Result<JsValue, JsValue> {
+        let scope: DedupScope = crate::from_js(scope)?;
+        let options: DedupOptions = crate::options_from(options)?;
+        crate::to_js(&self.deduplicate_overlapping(tolerance, scope, &options).map_err(js_error)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Matrix, Polygon};
+
+    fn outline() -> Vec<Point> {
+        vec![Point::new(0.0, 0.0), Point::new(40.0, 0.0), Point::new(40.0, 10.0), Point::new(15.0, 25.0), Point::new(0.0, 10.0)]
+    }
+
+    fn add(doc: &mut Document, vertices: Vec<Point>, layer: &str) -> ShapeId {
+        let id = doc.add_shape(Polygon::new(vertices));
+        doc.set_layer(id, Some(layer.into())).unwrap();
+        id
+    }
+
+    #[test]
+    fn test_three_copies_merge_and_near_copy_stays() {
+        let mut doc = Document::new();
+        let print = add(&mut doc, outline(), "print");
+        // Same outline from another start vertex, wound the other way
+        let mut reversed = outline();
+        reversed.rotate_left(2);
+        reversed.reverse();
+        let backup = add(&mut doc, reversed, "backup");
+        // Placed by its transform rather than its vertices
+        let main = add(&mut doc, outline().iter().map(|p| Point::new(p.x - 5.0, p.y + 1.0)).collect(), "main");
+        doc.set_transform(main, Matrix::translate(5.0, -1.0)).unwrap();
+        let near = add(&mut doc, outline().iter().map(|p| Point::new(p.x + 0.02, p.y)).collect(), "main");
+        doc.set_attribute(print, "material", AttributeValue::Text("steel".into())).unwrap();
+        doc.set_attribute(backup, "material", AttributeValue::Text("oak".into())).unwrap();
+        doc.set_attribute(backup, "mass", AttributeValue::Number(5.0)).unwrap();
+
+        let options = DedupOptions { preferred_layers: vec!["main".into()], ..DedupOptions::default() };
+        let report = doc.deduplicate_overlapping(0.01, DedupScope::All, &options).unwrap();
+        assert_eq!(report.groups.len(), 1);
+        let group = &report.groups[0];
+        assert_eq!((group.kept, group.duplicates.clone()), (main, vec![print, backup]));
+        assert_eq!(group.conflicts, vec![AttributeConflict {
+            key: "material".into(),
+            kept: Some(AttributeValue::Text("steel".into())),
+            others: vec![(backup, AttributeValue::Text("oak".into()))],
+        }]);
+        let ids: Vec<ShapeId> = doc.shapes().map(|s| s.id).collect();
+        assert_eq!(ids, vec![main, near]);
+        assert_eq!(doc.shape(main).unwrap().attributes.get("mass"), Some(&AttributeValue::Number(5.0)));
+
+        assert!(doc.undo());
+        assert_eq!(doc.shapes().count(), 4);
+        assert!(doc.shape(main).unwrap().attributes.is_empty());
+        assert_eq!(doc.shape(backup).unwrap().attributes.len(), 2);
+        // Nothing left to merge means nothing to undo
+        assert_eq!(doc.deduplicate_overlapping(0.01, DedupScope::Shapes(vec![print, near]), &options).unwrap(), DedupReport::default());
+        assert!(!doc.undo());
+    }
+
+    #[test]
+    fn test_same_layer_scope_moves_copies_aside() {
+        let mut doc = Document::new();
+        let a = add(&mut doc, outline(), "walls");
+        let b = add(&mut doc, outline(), "walls");
+        let other = add(&mut doc, outline(), "print");
+        let options = DedupOptions { action: DuplicateAction::MoveToLayer("duplicates".into()), merge_attributes: false, ..DedupOptions::default() };
+        doc.set_attribute(b, "tag", AttributeValue::Number(1.0)).unwrap();
+        let report = doc.deduplicate_overlapping(1e-6, DedupScope::SameLayer, &options).unwrap();
+        assert_eq!(report.duplicate_count(), 1);
+        assert_eq!((report.groups[0].kept, report.groups[0].conflicts[0].kept.clone()), (a, None));
+        assert_eq!(doc.shape(b).unwrap().layer.as_deref(), Some("duplicates"));
+        assert_eq!(doc.shape(other).unwrap().layer.as_deref(), Some("print"));
+        assert_eq!(doc.shapes().count(), 3);
+    }
+}
@@ -0,0 +1,155 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::arrangement::overlay;
+use crate::{Document, Point, PolygonWithHoles, Shape};
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct LayerDiff {
+    pub layer: Option<String>,
+    pub added: Vec<PolygonWithHoles>,
+    pub removed: Vec<PolygonWithHoles>,
+    pub unchanged_area: f64,
+}
+
+// Regions carry holes: an enlarged shape adds a ring around its old outline
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct GeometricDiff {
+    pub added: Vec<PolygonWithHoles>,
+    pub removed: Vec<PolygonWithHoles>,
+    pub unchanged_area: f64,
+    // Unlayered first, then by layer name
+    pub layers: Vec<LayerDiff>,
+}
+
+fn area(regions: &[PolygonWithHoles]) -> f64 {
+    regions.iter().map(|r| r.area()).sum()
+}
+
+// Old and new rings of one layer's unmatched shapes
+type Unmatched = (Vec<Vec<Point>>, Vec<Vec<Point>>);
+
+fn world_ring(shape: &Shape) -> Vec<Point> {
+    shape.world_geometry().vertices
+}
+
+impl LayerDiff {
+    // Adds the difference between old and new coverage
+    fn compare(&mut self, old: &[Vec<Point>], new: &[Vec<Point>]) {
+        let removed = overlay(old, new, |a, b| a && !b);
+        let added = overlay(old, new, |a, b| b && !a);
+        let old_area = area(&overlay(old, &[], |a, _| a));
+        self.unchanged_area += old_area - area(&removed);
+        self.removed.extend(removed);
+        self.added.extend(added);
+    }
+}
+
+// Red/green overlay of what changed between two revisions. Shapes with the
+// same id on the same layer are compared one to one; the rest of each
+// layer (shapes added, deleted or moved between layers) is compared as a
+// whole. For every layer, old area - removed + added = new area, with area
+// where shapes of a layer overlap counted once.
+pub fn geometric_diff(old: &Document, new: &Document) -> GeometricDiff {
+    let mut layers: BTreeMap<Option<String>, LayerDiff> = BTreeMap::new();
+    let mut unmatched: BTreeMap<Option<String>, Unmatched> = BTreeMap::new();
+    let new_by_id: BTreeMap<u32, &Shape> = new.shapes().map(|s| (s.id, s)).collect();
+    let mut matched = Vec::new();
+
+    for before in old.shapes() {
+        match new_by_id.get(&before.id) {
+            Some(after) if after.layer == before.layer => {
+                matched.push(before.id);
+                let diff = layers.entry(before.layer.clone()).or_default();
+                let (old_ring, new_ring) = (world_ring(before), world_ring(after));
+                if old_ring == new_ring {
+                    diff.unchanged_area += before.world_geometry().area();
+                } else {
+                    diff.compare(&[old_ring], &[new_ring]);
+                }
+            }
+            _ => unmatched.entry(before.layer.clone()).or_default().0.push(world_ring(before)),
+        }
+    }
+    for after in new.shapes().filter(|s| !matched.contains(&s.id)) {
+        unmatched.entry(after.layer.clone()).or_default().1.push(world_ring(after));
+    }
+    for (layer, (old_rings, new_rings)) in unmatched {
+        layers.entry(layer).or_default().compare(&old_rings, &new_rings);
+    }
+
+    let mut result = GeometricDiff::default();
+    for (layer, mut diff) in layers {
+        diff.layer = layer;
+        result.added.extend(diff.added.iter().cloned());
+        result.removed.extend(diff.removed.iter().cloned());
+        result.unchanged_area += diff.unchanged_area;
+        result.layers.push(diff);
+    }
+    result
+}
+
+#[wasm_bindgen(js_name = geometric_diff)]
+pub fn geometric_diff_js(old: &Document, new: &Document) -> Result<JsValue, JsValue> {
+    crate::to_js(&geometric_diff(old, new))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Matrix, Polygon};
+
+    fn square(x: f64, y: f64, size: f64) -> Polygon {
+        Polygon::new(vec![Point::new(x, y), Point::new(x + size, y), Point::new(x + size, y + size), Point::new(x, y + size)])
+    }
+
+    fn total_area(doc: &Document) -> f64 {
+        doc.shapes().map(|s| s.world_geometry().area()).sum()
+    }
+
+    #[test]
+    fn test_enlarged_and_deleted_squares() {
+        let mut old = Document::new();
+        let grown = old.add_shape(square(0.0, 0.0, 2.0));
+        let deleted = old.add_shape(square(5.0, 0.0, 1.0));
+        old.add_shape(square(10.0, 0.0, 1.0));
+
+        let mut new = old.clone();
+        new.remove_shape(deleted).unwrap();
+        // Twice the size about its centre
+        new.set_transform(grown, Matrix::translate(-1.0, -1.0).multiply(&Matrix::scale(2.0, 2.0))).unwrap();
+
+        let diff = geometric_diff(&old, &new);
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].holes.len(), 1);
+        assert!((area(&diff.added) - 12.0).abs() < 1e-9);
+        assert_eq!(diff.removed.len(), 1);
+        assert!((area(&diff.removed) - 1.0).abs() < 1e-9);
+        assert!((diff.unchanged_area - 5.0).abs() < 1e-9);
+        assert!((total_area(&old) - area(&diff.removed) + area(&diff.added) - total_area(&new)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_redrawn_shapes_compare_by_layer() {
+        let mut old = Document::new();
+        let a = old.add_shape(square(0.0, 0.0, 1.0));
+        old.set_layer(a, Some("walls".to_string())).unwrap();
+
+        // Deleted and redrawn half a unit over, under a new id
+        let mut new = old.clone();
+        new.remove_shape(a).unwrap();
+        let b = new.add_shape(square(0.5, 0.0, 1.0));
+        new.set_layer(b, Some("walls".to_string())).unwrap();
+        new.add_shape(square(3.0, 0.0, 1.0));
+
+        let diff = geometric_diff(&old, &new);
+        assert_eq!(diff.layers.len(), 2);
+        assert_eq!(diff.layers[0].layer, None);
+        assert!((area(&diff.layers[0].added) - 1.0).abs() < 1e-9);
+        let walls = &diff.layers[1];
+        assert!((area(&walls.added) - 0.5).abs() < 1e-9 && (area(&walls.removed) - 0.5).abs() < 1e-9);
+        assert!((walls.unchanged_area - 0.5).abs() < 1e-9);
+    }
+}
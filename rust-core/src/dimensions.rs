@@ -0,0 +1,718 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::convention::user_angle;
+use crate::{js_error, Angle, CadError, DimensionId, Document, FeatureRef, Point, Polygon, PolygonWithHoles, Polyline, ShapeId, EPSILON};
+
+// Relative spread of vertex radii below which a hole is treated as round
+const ROUNDNESS: f64 = 0.01;
+// Segments used to draw a full turn of dimension arc
+const ARC_SEGMENTS: usize = 64;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DimensionKind {
+    OverallWidth,
+    OverallHeight,
+    HorizontalSpan,
+    VerticalSpan,
+    HoleOffsetX,
+    HoleOffsetY,
+    HoleDiameter,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DimensionSuggestion {
+    pub kind: DimensionKind,
+    pub value: f64,
+    // Measured points, suitable for placing the dimension's extension lines
+    pub from: Point,
+    pub to: Point,
+    // Features the dimension attaches to
+    pub references: Vec<FeatureRef>,
+    // Derivable from other suggestions, e.g. an edge spanning the full width
+    pub redundant: bool,
+    pub score: f64,
+}
+
+struct Span {
+    lo: f64,
+    hi: f64,
+    at: f64,
+    edge: usize,
+}
+
+fn extreme_vertex(polygon: &Polygon, key: impl Fn(&Point) -> f64) -> usize {
+    (0..polygon.vertices.len())
+        .min_by(|&i, &j| key(&polygon.vertices[i]).total_cmp(&key(&polygon.vertices[j])))
+        .unwrap_or(0)
+}
+
+// Axis-parallel outline edges, with edges covering the same interval merged
+// into one span (the top and bottom of a rectangle give a single width)
+fn axis_spans(outer: &Polygon, horizontal: bool, tolerance: f64) -> Vec<Span> {
+    let n = outer.vertices.len();
+    let mut spans: Vec<Span> = Vec::new();
+    for i in 0..n {
+        let a = outer.vertices[i];
+        let b = outer.vertices[(i + 1) % n];
+        let (along_a, along_b, across_a, across_b) = if horizontal { (a.x, b.x, a.y, b.y) } else { (a.y, b.y, a.x, b.x) };
+        if (across_a - across_b).abs() > tolerance || (along_a - along_b).abs() <= tolerance {
+            continue;
+        }
+
+        let (lo, hi) = (along_a.min(along_b), along_a.max(along_b));
+        if !spans.iter().any(|s| (s.lo - lo).abs() <= tolerance && (s.hi - hi).abs() <= tolerance) {
+            spans.push(Span { lo, hi, at: across_a, edge: i });
+        }
+    }
+    spans
+}
+
+// A span is redundant when it matches the overall extent or is exactly the
+// chain of two other spans
+fn span_is_redundant(spans: &[Span], k: usize, extent: (f64, f64), tolerance: f64) -> bool {
+    let s = &spans[k];
+    let close = |a: f64, b: f64| (a - b).abs() <= tolerance;
+    if close(s.lo, extent.0) && close(s.hi, extent.1) {
+        return true;
+    }
+    spans.iter().enumerate().any(|(i, a)| {
+        i != k && close(a.lo, s.lo) && spans.iter().enumerate().any(|(j, b)| j != k && j != i && close(a.hi, b.lo) && close(b.hi, s.hi))
+    })
+}
+
+// Proposes the dimensions a drafter would put on a finished outline: overall
+// extents, distinct axis-parallel edge spans, hole centers measured from the
+// bottom-left datum corner and diameters of round holes. Results are sorted
+// by score with redundant dimensions pushed down the list.
+pub fn suggest_dimensions(shape: &PolygonWithHoles, tolerance: f64) -> Vec<DimensionSuggestion> {
+    let outer = &shape.outer;
+    if outer.vertices.len() < 3 {
+        return Vec::new();
+    }
+
+    let bbox = outer.bounding_box();
+    let datum = bbox.min;
+    let datum_vertex = extreme_vertex(outer, |p| (p.x - datum.x).powi(2) + (p.y - datum.y).powi(2));
+    let left = extreme_vertex(outer, |p| p.x);
+    let right = extreme_vertex(outer, |p| -p.x);
+    let bottom = extreme_vertex(outer, |p| p.y);
+    let top = extreme_vertex(outer, |p| -p.y);
+
+    let suggestion = |kind, from: Point, to: Point, references: Vec<FeatureRef>, redundant: bool, score: f64| {
+        let value = match kind {
+            DimensionKind::OverallHeight | DimensionKind::VerticalSpan | DimensionKind::HoleOffsetY => to.y - from.y,
+            _ => to.x - from.x,
+        };
+        DimensionSuggestion { kind, value, from, to, references, redundant, score: if redundant { score * 0.25 } else { score } }
+    };
+
+    let mut result = vec![
+        suggestion(
+            DimensionKind::OverallWidth,
+            Point::new(bbox.min.x, bbox.min.y),
+            Point::new(bbox.max.x, bbox.min.y),
+            vec![FeatureRef::OuterVertex(left), FeatureRef::OuterVertex(right)],
+            false,
+            1.0,
+        ),
+        suggestion(
+            DimensionKind::OverallHeight,
+            Point::new(bbox.min.x, bbox.min.y),
+            Point::new(bbox.min.x, bbox.max.y),
+            vec![FeatureRef::OuterVertex(bottom), FeatureRef::OuterVertex(top)],
+            false,
+            1.0,
+        ),
+    ];
+
+    let n = outer.vertices.len();
+    for horizontal in [true, false] {
+        let spans = axis_spans(outer, horizontal, tolerance);
+        let extent = if horizontal { (bbox.min.x, bbox.max.x) } else { (bbox.min.y, bbox.max.y) };
+        let size = (extent.1 - extent.0).max(tolerance);
+        for (k, s) in spans.iter().enumerate() {
+            let (kind, from, to) = if horizontal {
+                (DimensionKind::HorizontalSpan, Point::new(s.lo, s.at), Point::new(s.hi, s.at))
+            } else {
+                (DimensionKind::VerticalSpan, Point::new(s.at, s.lo), Point::new(s.at, s.hi))
+            };
+            let references = vec![FeatureRef::OuterVertex(s.edge), FeatureRef::OuterVertex((s.edge + 1) % n)];
+            let redundant = span_is_redundant(&spans, k, extent, tolerance);
+            result.push(suggestion(kind, from, to, references, redundant, 0.4 + 0.2 * (s.hi - s.lo) / size));
+        }
+    }
+
+    for (i, hole) in shape.holes.iter().enumerate() {
+        if hole.vertices.len() < 3 {
+            continue;
+        }
+        let center = hole.bounding_box().center();
+        let references = vec![FeatureRef::OuterVertex(datum_vertex), FeatureRef::Hole(i)];
+        result.push(suggestion(DimensionKind::HoleOffsetX, datum, Point::new(center.x, datum.y), references.clone(), false, 0.9));
+        result.push(suggestion(DimensionKind::HoleOffsetY, datum, Point::new(datum.x, center.y), references, false, 0.9));
+
+        let radii: Vec<f64> = hole.vertices.iter().map(|p| ((p.x - center.x).powi(2) + (p.y - center.y).powi(2)).sqrt()).collect();
+        let max = radii.iter().cloned().fold(0.0, f64::max);
+        let min = radii.iter().cloned().fold(f64::INFINITY, f64::min);
+        if max - min <= tolerance.max(max * ROUNDNESS) {
+            let r = hole.bounding_box().width() / 2.0;
+            result.push(suggestion(
+                DimensionKind::HoleDiameter,
+                Point::new(center.x - r, center.y),
+                Point::new(center.x + r, center.y),
+                vec![FeatureRef::Hole(i)],
+                false,
+                0.8,
+            ));
+        }
+    }
+
+    result.sort_by(|a, b| b.score.total_cmp(&a.score));
+    result
+}
+
+// Which of the four angles formed by two crossing edge lines is dimensioned
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AngleQuadrant {
+    // Between the two edges themselves
+    Inside,
+    // Between edge b and the extension of edge a past the apex
+    SupplementA,
+    // Between edge a and the extension of edge b past the apex
+    SupplementB,
+    // Between both extensions
+    Opposite,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AngularDimension {
+    pub edge_a: (Point, Point),
+    pub edge_b: (Point, Point),
+    // Radius of the dimension arc around the apex
+    pub offset: f64,
+    pub quadrant: AngleQuadrant,
+    pub arrow_size: f64,
+    // Decimal places on the degrees, or on the seconds when `dms` is set
+    pub precision: usize,
+    // Write the value as degrees, minutes and seconds
+    #[serde(default)]
+    pub dms: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RadialDimension {
+    pub center: Point,
+    pub radius: f64,
+    // Direction of the leader from the center, in radians
+    pub leader_angle: f64,
+    // Dimension the diameter (Ø) rather than the radius (R)
+    pub diameter: bool,
+    // How far the leader runs past the arc to the text
+    pub leader_length: f64,
+    pub arrow_size: f64,
+    pub precision: usize,
+}
+
+// Annotation geometry ready to draw or export
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DimensionGraphic {
+    pub value: f64,
+    pub lines: Vec<Polyline>,
+    // Filled arrowhead triangles
+    pub arrows: Vec<Polygon>,
+    pub text_anchor: Point,
+    pub text: String,
+}
+
+fn unit_between(from: &Point, to: &Point) -> (f64, f64) {
+    let (dx, dy) = (to.x - from.x, to.y - from.y);
+    let len = dx.hypot(dy);
+    if len < EPSILON { (1.0, 0.0) } else { (dx / len, dy / len) }
+}
+
+fn offset_point(p: &Point, dir: (f64, f64), distance: f64) -> Point {
+    Point::new(p.x + dir.0 * distance, p.y + dir.1 * distance)
+}
+
+// Triangle with its tip at `tip`, pointing along `dir`
+fn arrowhead(tip: &Point, dir: (f64, f64), size: f64) -> Polygon {
+    let base = offset_point(tip, dir, -size);
+    let normal = (-dir.1, dir.0);
+    Polygon::new(vec![*tip, offset_point(&base, normal, size / 3.0), offset_point(&base, normal, -size / 3.0)])
+}
+
+impl AngularDimension {
+    // Apex where the two edge lines meet, or None for parallel edges
+    fn apex(&self) -> Option<Point> {
+        let ((a0, a1), (b0, b1)) = (self.edge_a, self.edge_b);
+        let (d1, d2) = ((a1.x - a0.x, a1.y - a0.y), (b1.x - b0.x, b1.y - b0.y));
+        let cross = d1.0 * d2.1 - d1.1 * d2.0;
+        if cross.abs() < EPSILON {
+            return None;
+        }
+        let t = ((b0.x - a0.x) * d2.1 - (b0.y - a0.y) * d2.0) / cross;
+        Some(Point::new(a0.x + t * d1.0, a0.y + t * d1.1))
+    }
+
+    // Arc between the rays of the chosen quadrant with arrows at both ends,
+    // extension lines from each edge out to the arc and the angle text just
+    // outside the arc's midpoint. Parallel edges render nothing.
+    pub fn render(&self) -> Option<DimensionGraphic> {
+        let apex = self.apex()?;
+        // Each edge's ray points from the apex towards its far end
+        let ray = |(p0, p1): (Point, Point)| {
+            let far = if (p0.x - apex.x).hypot(p0.y - apex.y) > (p1.x - apex.x).hypot(p1.y - apex.y) { p0 } else { p1 };
+            unit_between(&apex, &far)
+        };
+        let (mut u, mut v) = (ray(self.edge_a), ray(self.edge_b));
+        if matches!(self.quadrant, AngleQuadrant::SupplementA | AngleQuadrant::Opposite) {
+            u = (-u.0, -u.1);
+        }
+        if matches!(self.quadrant, AngleQuadrant::SupplementB | AngleQuadrant::Opposite) {
+            v = (-v.0, -v.1);
+        }
+
+        let start = u.1.atan2(u.0);
+        let mut sweep = v.1.atan2(v.0) - start;
+        while sweep > std::f64::consts::PI {
+            sweep -= std::f64::consts::TAU;
+        }
+        while sweep < -std::f64::consts::PI {
+            sweep += std::f64::consts::TAU;
+        }
+
+        let r = self.offset;
+        let at = |angle: f64| Point::new(apex.x + r * angle.cos(), apex.y + r * angle.sin());
+        let segments = ((ARC_SEGMENTS as f64 * sweep.abs() / std::f64::consts::TAU).ceil() as usize).max(2);
+        let arc: Vec<Point> = (0..=segments).map(|k| at(start + sweep * k as f64 / segments as f64)).collect();
+
+        // Arc tangents at the ends, pointing out along the arc
+        let turn = sweep.signum();
+        let start_tangent = (u.1 * turn, -u.0 * turn);
+        let end_tangent = (-v.1 * turn, v.0 * turn);
+        let arrows = vec![
+            arrowhead(&arc[0], start_tangent, self.arrow_size),
+            arrowhead(&arc[segments], end_tangent, self.arrow_size),
+        ];
+
+        // Extension lines run from the nearest point of each edge along its
+        // ray to a little past the arc
+        let extension = |(p0, p1): (Point, Point), dir: (f64, f64)| {
+            let along = |p: &Point| (p.x - apex.x) * dir.0 + (p.y - apex.y) * dir.1;
+            let (t0, t1) = (along(&p0), along(&p1));
+            let from = r.clamp(t0.min(t1), t0.max(t1));
+            Polyline::new(vec![offset_point(&apex, dir, from), offset_point(&apex, dir, r + self.arrow_size / 2.0)], false)
+        };
+        let mut lines = vec![Polyline::new(arc, false)];
+        for (edge, dir) in [(self.edge_a, u), (self.edge_b, v)] {
+            let line = extension(edge, dir);
+            let (a, b) = (line.points[0], line.points[1]);
+            if (b.x - a.x).hypot(b.y - a.y) > EPSILON {
+                lines.push(line);
+            }
+        }
+
+        let value = sweep.abs().to_degrees();
+        let middle = start + sweep / 2.0;
+        let text_anchor = Point::new(apex.x + (r + self.arrow_size) * middle.cos(), apex.y + (r + self.arrow_size) * middle.sin());
+        let text = if self.dms { Angle::from_radians(sweep.abs()).format_dms(self.precision) } else { format!("{:.p$}°", value, p = self.precision) };
+
+        Some(DimensionGraphic { value, lines, arrows, text_anchor, text })
+    }
+}
+
+impl RadialDimension {
+    // Radius: a line from the center to the arc with the arrow on the arc.
+    // Diameter: a line across through the center with arrows at both sides.
+    // Either way the leader continues past the arc to the R/Ø text.
+    pub fn render(&self) -> DimensionGraphic {
+        let angle = user_angle(self.leader_angle);
+        let dir = (angle.cos(), angle.sin());
+        let back = (-dir.0, -dir.1);
+        let on_arc = offset_point(&self.center, dir, self.radius);
+        let text_anchor = offset_point(&self.center, dir, self.radius + self.leader_length);
+
+        let (value, prefix, start) = if self.diameter {
+            (self.radius * 2.0, "Ø", offset_point(&self.center, back, self.radius))
+        } else {
+            (self.radius, "R", self.center)
+        };
+
+        let mut arrows = vec![arrowhead(&on_arc, dir, self.arrow_size)];
+        if self.diameter {
+            arrows.push(arrowhead(&start, back, self.arrow_size));
+        }
+
+        DimensionGraphic {
+            value,
+            lines: vec![Polyline::new(vec![start, text_anchor], false)],
+            arrows,
+            text_anchor,
+            text: format!("{}{:.p$}", prefix, value, p = self.precision),
+        }
+    }
+}
+
+// An edge of a document shape, the one from vertex `edge` to the next
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EdgeRef {
+    pub shape: ShapeId,
+    pub edge: usize,
+}
+
+// An AngularDimension between edges of document shapes. The edges are read
+// from the shapes' world outlines each time it is rendered, so it follows
+// edits to them; it goes when either shape does.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct EdgeAngleDimension {
+    // Given out by `add_angular_dimension`, from the ids linear dimensions use
+    #[serde(default)]
+    pub id: DimensionId,
+    pub edge_a: EdgeRef,
+    pub edge_b: EdgeRef,
+    pub offset: f64,
+    pub quadrant: AngleQuadrant,
+    pub arrow_size: f64,
+    pub precision: usize,
+    #[serde(default)]
+    pub dms: bool,
+}
+
+// A RadialDimension of a round document shape, with the centre and radius
+// its world outline has when rendered
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ShapeRadialDimension {
+    #[serde(default)]
+    pub id: DimensionId,
+    pub shape: ShapeId,
+    pub leader_angle: f64,
+    pub diameter: bool,
+    pub leader_length: f64,
+    pub arrow_size: f64,
+    pub precision: usize,
+}
+
+impl Document {
+    fn world_edge(&self, edge: &EdgeRef) -> Result<(Point, Point), CadError> {
+        let v = self.shape(edge.shape)?.world_geometry().vertices;
+        if edge.edge >= v.len() {
+            return Err(CadError::InvalidInput(format!("shape {} has no edge {}", edge.shape, edge.edge)));
+        }
+        Ok((v[edge.edge], v[(edge.edge + 1) % v.len()]))
+    }
+
+    // Centre and radius of a shape's world outline, which must be round to
+    // within ROUNDNESS at its vertices and the middles of its edges alike
+    fn round_outline(&self, id: ShapeId) -> Result<(Point, f64), CadError> {
+        let v = self.shape(id)?.world_geometry().vertices;
+        let n = v.len();
+        if n < 3 {
+            return Err(CadError::InvalidInput(format!("shape {} isn't round", id)));
+        }
+        let center = Point::new(v.iter().map(|p| p.x).sum::<f64>() / n as f64, v.iter().map(|p| p.y).sum::<f64>() / n as f64);
+        let radius = |p: Point| (p.x - center.x).hypot(p.y - center.y);
+        let radii: Vec<f64> = v.iter().map(|&p| radius(p)).collect();
+        let middles = (0..n).map(|i| radius(Point::new((v[i].x + v[(i + 1) % n].x) / 2.0, (v[i].y + v[(i + 1) % n].y) / 2.0)));
+        let (min, max) = radii.iter().copied().chain(middles).fold((f64::INFINITY, 0.0f64), |(lo, hi), r| (lo.min(r), hi.max(r)));
+        if max - min > max * ROUNDNESS {
+            return Err(CadError::InvalidInput(format!("shape {} isn't round", id)));
+        }
+        Ok((center, radii.iter().sum::<f64>() / n as f64))
+    }
+
+    fn angular_geometry(&self, d: &EdgeAngleDimension) -> Result<AngularDimension, CadError> {
+        Ok(AngularDimension {
+            edge_a: self.world_edge(&d.edge_a)?,
+            edge_b: self.world_edge(&d.edge_b)?,
+            offset: d.offset,
+            quadrant: d.quadrant,
+            arrow_size: d.arrow_size,
+            precision: d.precision,
+            dms: d.dms,
+        })
+    }
+
+    fn radial_geometry(&self, d: &ShapeRadialDimension) -> Result<RadialDimension, CadError> {
+        let (center, radius) = self.round_outline(d.shape)?;
+        Ok(RadialDimension { center, radius, leader_angle: d.leader_angle, diameter: d.diameter, leader_length: d.leader_length, arrow_size: d.arrow_size, precision: d.precision })
+    }
+
+    // Attaches `dimension` under a fresh id, which is returned; its edges
+    // must exist and not be parallel
+    pub fn add_angular_dimension(&mut self, mut dimension: EdgeAngleDimension) -> Result<DimensionId, CadError> {
+        if self.angular_geometry(&dimension)?.render().is_none() {
+            return Err(CadError::Degenerate("the edges are parallel".into()));
+        }
+        dimension.id = self.fresh_dimension_id();
+        self.angular_dimensions.push(dimension);
+        Ok(self.angular_dimensions.last().unwrap().id)
+    }
+
+    // Attaches `dimension` to its shape, which must be round, under a fresh id
+    pub fn add_radial_dimension(&mut self, mut dimension: ShapeRadialDimension) -> Result<DimensionId, CadError> {
+        self.round_outline(dimension.shape)?;
+        dimension.id = self.fresh_dimension_id();
+        self.radial_dimensions.push(dimension);
+        Ok(self.radial_dimensions.last().unwrap().id)
+    }
+
+    pub fn angular_dimensions(&self) -> impl Iterator<Item = &EdgeAngleDimension> {
+        self.angular_dimensions.iter()
+    }
+
+    pub fn radial_dimensions(&self) -> impl Iterator<Item = &ShapeRadialDimension> {
+        self.radial_dimensions.iter()
+    }
+
+    // Annotation geometry of an angular or radial dimension as its shapes
+    // are now
+    pub fn render_dimension(&self, id: DimensionId) -> Result<DimensionGraphic, CadError> {
+        if let Some(d) = self.angular_dimensions.iter().find(|d| d.id == id) {
+            return self.angular_geometry(d)?.render().ok_or_else(|| CadError::Degenerate(format!("dimension {} is between parallel edges", id)));
+        }
+        match self.radial_dimensions.iter().find(|d| d.id == id) {
+            Some(d) => Ok(self.radial_geometry(d)?.render()),
+            None => Err(CadError::UnknownDimension(id)),
+        }
+    }
+
+    // Angular and radial dimensions on shapes `includes` takes, in id order,
+    // with the shape each is filed under and its arrow size. Those that no
+    // longer render, e.g. with an edge since removed, are left out.
+    pub(crate) fn rendered_dimensions(&self, includes: impl Fn(ShapeId) -> bool) -> Vec<(DimensionId, ShapeId, f64, DimensionGraphic)> {
+        let angular = self.angular_dimensions.iter().filter(|d| includes(d.edge_a.shape) && includes(d.edge_b.shape)).map(|d| (d.id, d.edge_a.shape, d.arrow_size));
+        let radial = self.radial_dimensions.iter().filter(|d| includes(d.shape)).map(|d| (d.id, d.shape, d.arrow_size));
+        let mut rendered: Vec<_> = angular.chain(radial).filter_map(|(id, shape, arrow)| Some((id, shape, arrow, self.render_dimension(id).ok()?))).collect();
+        rendered.sort_by_key(|r| r.0);
+        rendered
+    }
+
+    // Removes the angular or radial dimension with this id
+    pub fn remove_attached_dimension(&mut self, id: DimensionId) -> Result<(), CadError> {
+        let count = self.angular_dimensions.len() + self.radial_dimensions.len();
+        self.angular_dimensions.retain(|d| d.id != id);
+        self.radial_dimensions.retain(|d| d.id != id);
+        if self.angular_dimensions.len() + self.radial_dimensions.len() == count {
+            return Err(CadError::UnknownDimension(id));
+        }
+        Ok(())
+    }
+}
+
+#[wasm_bindgen]
+impl Document {
+    // `dimension` is { edge_a: { shape, edge }, edge_b, offset, quadrant,
+    // arrow_size, precision, dms }
+    #[wasm_bindgen(js_name = add_angular_dimension)]
+    pub fn add_angular_dimension_js(&mut self, dimension: &JsValue) -> Result<DimensionId, JsValue> {
+        let dimension: EdgeAngleDimension = crate::from_js(dimension)?;
+        self.add_angular_dimension(dimension).map_err(js_error)
+    }
+
+    // `dimension` is { shape, leader_angle, diameter, leader_length,
+    // arrow_size, precision }
+    #[wasm_bindgen(js_name = add_radial_dimension)]
+    pub fn add_radial_dimension_js(&mut self, dimension: &JsValue) -> Result<DimensionId, JsValue> {
+        let dimension: ShapeRadialDimension = crate::from_js(dimension)?;
+        self.add_radial_dimension(dimension).map_err(js_error)
+    }
+
+    // { value, lines, arrows, text_anchor, text }
+    #[wasm_bindgen(js_name = render_dimension)]
+    pub fn render_dimension_js(&self, id: DimensionId) -> Result<JsValue, JsValue> {
+        crate::to_js(&self.render_dimension(id).map_err(js_error)?)
+    }
+}
+
+#[wasm_bindgen]
+pub fn render_angular_dimension(dimension: &JsValue) -> Result<JsValue, JsValue> {
+    let dimension: AngularDimension = crate::from_js(dimension)?;
+    crate::to_js(&dimension.render())
+}
+
+#[wasm_bindgen]
+pub fn render_radial_dimension(dimension: &JsValue) -> Result<JsValue, JsValue> {
+    let dimension: RadialDimension = crate::from_js(dimension)?;
+    crate::to_js(&dimension.render())
+}
+
+#[wasm_bindgen(js_name = suggest_dimensions)]
+pub fn suggest_dimensions_js(shape: &JsValue, tolerance: f64) -> Result<JsValue, JsValue> {
+    let shape: PolygonWithHoles = crate::from_js(shape)?;
+    crate::to_js(&suggest_dimensions(&shape, tolerance))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExportProfile, Matrix};
+
+    fn circle(cx: f64, cy: f64, r: f64, segments: usize) -> Polygon {
+        Polygon::new((0..segments).map(|i| {
+            let t = i as f64 / segments as f64 * std::f64::consts::TAU;
+            Point::new(cx + r * t.cos(), cy + r * t.sin())
+        }).collect())
+    }
+
+    #[test]
+    fn test_plate_with_two_holes() {
+        let outer = Polygon::new(vec![Point::new(0.0, 0.0), Point::new(10.0, 0.0), Point::new(10.0, 5.0), Point::new(0.0, 5.0)]);
+        let plate = PolygonWithHoles::new(outer, vec![circle(2.5, 2.5, 1.0, 32), circle(7.5, 2.0, 0.5, 32)]);
+        let suggestions = suggest_dimensions(&plate, 1e-6);
+        let top = &suggestions[..6];
+
+        let has = |kind: DimensionKind, value: f64, hole: Option<usize>| {
+            top.iter().any(|s| {
+                s.kind == kind
+                    && (s.value - value).abs() < 1e-9
+                    && hole.is_none_or(|h| s.references.contains(&FeatureRef::Hole(h)))
+            })
+        };
+        assert!(has(DimensionKind::OverallWidth, 10.0, None));
+        assert!(has(DimensionKind::OverallHeight, 5.0, None));
+        assert!(has(DimensionKind::HoleOffsetX, 2.5, Some(0)));
+        assert!(has(DimensionKind::HoleOffsetY, 2.5, Some(0)));
+        assert!(has(DimensionKind::HoleOffsetX, 7.5, Some(1)));
+        assert!(has(DimensionKind::HoleOffsetY, 2.0, Some(1)));
+
+        let diameter = suggestions.iter().find(|s| s.kind == DimensionKind::HoleDiameter && s.references == [FeatureRef::Hole(1)]).unwrap();
+        assert!((diameter.value - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_stepped_outline_ranks_redundant_spans_lower() {
+        let outer = Polygon::new(vec![
+            Point::new(0.0, 0.0),
+            Point::new(10.0, 0.0),
+            Point::new(10.0, 4.0),
+            Point::new(6.0, 4.0),
+            Point::new(6.0, 8.0),
+            Point::new(0.0, 8.0),
+        ]);
+        let suggestions = suggest_dimensions(&PolygonWithHoles::new(outer, vec![]), 1e-6);
+        let spans: Vec<&DimensionSuggestion> = suggestions.iter().filter(|s| s.kind == DimensionKind::HorizontalSpan).collect();
+        assert_eq!(spans.len(), 3);
+
+        let step = spans.iter().find(|s| (s.value - 4.0).abs() < 1e-9).unwrap();
+        let full = spans.iter().find(|s| (s.value - 10.0).abs() < 1e-9).unwrap();
+        assert!(!step.redundant);
+        assert!(full.redundant);
+        assert!(step.score > full.score);
+    }
+
+    fn angular(edge_a: (Point, Point), edge_b: (Point, Point), quadrant: AngleQuadrant) -> AngularDimension {
+        AngularDimension { edge_a, edge_b, offset: 5.0, quadrant, arrow_size: 0.5, precision: 1, dms: false }
+    }
+
+    #[test]
+    fn test_angular_dimension_between_perpendicular_edges() {
+        let origin = Point::new(2.0, 1.0);
+        let dimension = angular((origin, Point::new(12.0, 1.0)), (Point::new(2.0, 11.0), origin), AngleQuadrant::Inside);
+        let graphic = dimension.render().unwrap();
+
+        assert!((graphic.value - 90.0).abs() < 1e-12);
+        assert_eq!(graphic.text, "90.0°");
+        assert_eq!(graphic.arrows.len(), 2);
+        for p in &graphic.lines[0].points {
+            assert!(((p.x - origin.x).hypot(p.y - origin.y) - 5.0).abs() < 1e-12);
+        }
+
+        // Both edges already reach the arc, so the extensions only overshoot it
+        for line in &graphic.lines[1..] {
+            assert!((line.length() - 0.25).abs() < 1e-12);
+        }
+
+        let dms = AngularDimension { dms: true, precision: 0, ..dimension };
+        assert_eq!(dms.render().unwrap().text, "90°00′00″");
+    }
+
+    #[test]
+    fn test_angular_quadrants_and_parallel_edges() {
+        let origin = Point::new(0.0, 0.0);
+        let slanted = (origin, Point::new(60f64.to_radians().cos(), 60f64.to_radians().sin()));
+        let base = (origin, Point::new(1.0, 0.0));
+
+        let inside = angular(base, slanted, AngleQuadrant::Inside).render().unwrap();
+        let supplement = angular(base, slanted, AngleQuadrant::SupplementA).render().unwrap();
+        assert!((inside.value - 60.0).abs() < 1e-9);
+        assert!((supplement.value - 120.0).abs() < 1e-9);
+
+        let parallel = (Point::new(0.0, 1.0), Point::new(1.0, 1.0));
+        assert!(angular(base, parallel, AngleQuadrant::Inside).render().is_none());
+    }
+
+    #[test]
+    fn test_radial_dimension_text_and_leader() {
+        let mut dimension = RadialDimension {
+            center: Point::new(1.0, 1.0),
+            radius: 2.5,
+            leader_angle: 0.0,
+            diameter: false,
+            leader_length: 1.0,
+            arrow_size: 0.3,
+            precision: 2,
+        };
+        let radius = dimension.render();
+        assert_eq!(radius.text, "R2.50");
+        assert_eq!(radius.arrows.len(), 1);
+        assert_eq!(radius.arrows[0].vertices[0], Point::new(3.5, 1.0));
+        assert_eq!(radius.text_anchor, Point::new(4.5, 1.0));
+
+        dimension.diameter = true;
+        let diameter = dimension.render();
+        assert_eq!(diameter.text, "Ø5.00");
+        assert_eq!(diameter.arrows.len(), 2);
+        assert_eq!(diameter.lines[0].points[0], Point::new(-1.5, 1.0));
+    }
+
+    #[test]
+    fn test_attached_dimensions_follow_their_shapes_into_exports() {
+        let mut doc = Document::new();
+        let plate = doc.add_shape(Polygon::new(vec![Point::new(0.0, 0.0), Point::new(10.0, 0.0), Point::new(10.0, 10.0), Point::new(0.0, 10.0)]));
+        let hole = doc.add_shape(circle(5.0, 5.0, 2.0, 64));
+        doc.set_layer(hole, Some("holes".into())).unwrap();
+        let corner = EdgeAngleDimension {
+            id: 0,
+            edge_a: EdgeRef { shape: plate, edge: 0 },
+            edge_b: EdgeRef { shape: plate, edge: 3 },
+            offset: 3.0,
+            quadrant: AngleQuadrant::Inside,
+            arrow_size: 0.5,
+            precision: 1,
+            dms: false,
+        };
+        let angle = doc.add_angular_dimension(corner.clone()).unwrap();
+        let radius = doc.add_radial_dimension(ShapeRadialDimension { id: 0, shape: hole, leader_angle: 0.0, diameter: true, leader_length: 1.0, arrow_size: 0.5, precision: 2 }).unwrap();
+        assert!(doc.add_radial_dimension(ShapeRadialDimension { id: 0, shape: plate, leader_angle: 0.0, diameter: false, leader_length: 1.0, arrow_size: 0.5, precision: 2 }).is_err());
+        assert!(doc.add_angular_dimension(EdgeAngleDimension { edge_b: EdgeRef { shape: plate, edge: 2 }, ..corner.clone() }).is_err());
+
+        let graphic = doc.render_dimension(angle).unwrap();
+        assert_eq!(graphic.text, "90.0°");
+        assert!(graphic.lines[0].points.iter().all(|p| (p.x.hypot(p.y) - 3.0).abs() < 1e-12));
+        assert_eq!(doc.render_dimension(radius).unwrap().text, "Ø4.00");
+        // Moving the plate moves the arc with it
+        doc.set_transform(plate, Matrix::translate(5.0, 0.0)).unwrap();
+        let moved = doc.render_dimension(angle).unwrap();
+        assert!(moved.lines[0].points.iter().all(|p| ((p.x - 5.0).hypot(p.y) - 3.0).abs() < 1e-12));
+
+        let svg = doc.export("svg", Some(3)).unwrap();
+        assert!(svg.contains(&format!(r#"<g data-dimension="{}">"#, angle)) && svg.contains(">90.0°</text>") && svg.contains(">Ø4.00<"));
+        let dxf = doc.export("dxf", None).unwrap();
+        assert!(dxf.contains("  1\n90.0%%d\n") && dxf.contains("  1\n%%c4.00\n"));
+        assert_eq!(dxf.matches("\nSOLID\n").count(), 4);
+        // Dimension geometry doesn't come back as shapes
+        let mut reread = Document::new();
+        assert_eq!(reread.import_dxf(&dxf, false).unwrap().shapes.len(), 2);
+        let only_plate = ExportProfile { exclude_layers: ["holes".to_string()].into(), ..ExportProfile::default() };
+        assert_eq!(doc.export_with("svg", &only_plate).unwrap().matches("data-dimension").count(), 1);
+
+        doc.checkpoint();
+        doc.remove_shape(hole).unwrap();
+        assert!(matches!(doc.render_dimension(radius), Err(CadError::UnknownDimension(_))));
+        assert!(doc.undo());
+        assert!(doc.render_dimension(radius).is_ok());
+        doc.remove_attached_dimension(angle).unwrap();
+        assert_eq!(doc.angular_dimensions().count(), 0);
+    }
+}
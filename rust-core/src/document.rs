@@ -0,0 +1,2424 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::autosave::AutosaveLog;
+use crate::dxf::{read_dxf, DxfImport, RawDxfBlob};
+use crate::export::{write_export, ExportProfile, ExportResult, HashWriter};
+use crate::hash::{shape_hash, ContentHasher};
+use crate::import::{read_svg, SvgContents};
+use crate::library::LibraryInstance;
+use crate::memory::{tick, Live, ObjectKind};
+use crate::params::generate;
+use crate::provenance::ProvenanceLog;
+use crate::region::{make_region, Entity, RegionError};
+use crate::search::SearchIndex;
+use crate::sweep::sweep_translate;
+use crate::{js_error, AttributeValue, CurveId, BoundingBox, CadError, DimensionKind, EdgeAngleDimension, Generator, JoinStyle, Matrix, NamedView, Parameters, Point, Polygon, QuadTree, SearchQuery, Selection, ShapeRadialDimension, SplineEntity, SvgImport, TextFit, Vector, EPSILON};
+
+pub type ShapeId = u32;
+pub type DimensionId = u32;
+
+// Checkpoints kept for undo
+const UNDO_LIMIT: usize = 100;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Shape {
+    pub id: ShapeId,
+    // Geometry in the shape's local space
+    pub geometry: Polygon,
+    // Local to the space of the shape's group, or of its layer when it is
+    // in no group; world space unless either has a transform
+    pub transform: Matrix,
+    // Local-space pivot for rotate/scale; None means the centroid
+    pub pivot: Option<Point>,
+    #[serde(default)]
+    pub attributes: BTreeMap<String, AttributeValue>,
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default)]
+    pub layer: Option<String>,
+    // Innermost group the shape is in
+    #[serde(default)]
+    pub group: Option<ShapeId>,
+    // Layer and group transforms above the shape's own, kept in step by the
+    // document
+    #[serde(skip, default = "Matrix::identity")]
+    pub(crate) placement: Matrix,
+}
+
+// Shapes and groups gathered under one transform, nested in `parent`.
+// Groups draw ids from the same sequence as shapes, so an id names one or
+// the other and is never reused.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Group {
+    pub id: ShapeId,
+    // To the parent group's space, or the layer's for a top-level group
+    pub transform: Matrix,
+    pub parent: Option<ShapeId>,
+    // Pivot for rotate/scale in the group's own space; None means the
+    // centre of the world box round the shapes under it
+    #[serde(default)]
+    pub pivot: Option<Point>,
+}
+
+// Dimension attached to a shape, between two points in the shape's local
+// space. Its value follows the shape's transform: X and Y kinds measure along
+// the shape's own axes, HoleDiameter measures the distance between the points.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Dimension {
+    pub id: DimensionId,
+    pub shape: ShapeId,
+    pub kind: DimensionKind,
+    pub from: Point,
+    pub to: Point,
+    // e.g. the `tol_plus` / `tol_minus` read by inspection reports
+    #[serde(default)]
+    pub attributes: BTreeMap<String, AttributeValue>,
+}
+
+impl Dimension {
+    // Local-space direction the dimension measures along
+    pub fn direction(&self) -> Vector {
+        match self.kind {
+            DimensionKind::OverallWidth | DimensionKind::HorizontalSpan | DimensionKind::HoleOffsetX => Vector::new(1.0, 0.0),
+            DimensionKind::OverallHeight | DimensionKind::VerticalSpan | DimensionKind::HoleOffsetY => Vector::new(0.0, 1.0),
+            DimensionKind::HoleDiameter => Vector::new(self.to.x - self.from.x, self.to.y - self.from.y).normalize(),
+        }
+    }
+
+    // Value on `shape`, which must be the shape the dimension is attached to
+    pub fn value_on(&self, shape: &Shape) -> f64 {
+        let m = &shape.world_transform();
+        let linear = |v: Vector| Vector::new(m.m11 * v.x + m.m12 * v.y, m.m21 * v.x + m.m22 * v.y);
+        let d = linear(Vector::new(self.to.x - self.from.x, self.to.y - self.from.y));
+        match self.kind {
+            DimensionKind::HoleDiameter => d.magnitude(),
+            _ => {
+                let axis = linear(self.direction()).normalize();
+                (d.x * axis.x + d.y * axis.y).abs()
+            }
+        }
+    }
+}
+
+// Shapes are shared with any live snapshots: the list and each shape are
+// copied on first write after a snapshot, so a snapshot costs one pointer
+// and later edits only copy the shapes they touch.
+#[wasm_bindgen]
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Document {
+    shapes: Arc<Vec<Arc<Shape>>>,
+    next_id: ShapeId,
+    #[serde(skip)]
+    spatial: RefCell<LazyIndex<QuadTree>>,
+    #[serde(skip)]
+    search: RefCell<LazyIndex<SearchIndex>>,
+    #[serde(default)]
+    dimensions: Vec<Dimension>,
+    #[serde(default)]
+    next_dimension_id: DimensionId,
+    #[serde(default)]
+    parameters: Parameters,
+    #[serde(default)]
+    generators: Vec<Generator>,
+    // What the last DXF import didn't understand, for export to write back
+    #[serde(default)]
+    dxf_passthrough: Vec<RawDxfBlob>,
+    #[serde(default)]
+    views: BTreeMap<String, NamedView>,
+    // Layer to world, for layers that have one
+    #[serde(default)]
+    layer_transforms: BTreeMap<String, Matrix>,
+    #[serde(default)]
+    groups: Vec<Group>,
+    // How dimension text is fitted to the room it has
+    #[serde(default)]
+    pub(crate) text_fit: Option<TextFit>,
+    // Groups placed from a shape library
+    #[serde(default)]
+    pub(crate) instances: Vec<LibraryInstance>,
+    // Spline curves, by id, drawn alongside the shapes
+    #[serde(default)]
+    pub(crate) curves: BTreeMap<CurveId, SplineEntity>,
+    #[serde(default)]
+    pub(crate) next_curve_id: CurveId,
+    // Who made and edited each shape, when tracking is on
+    #[serde(default, serialize_with = "ProvenanceLog::serialize_saved")]
+    pub(crate) provenance: ProvenanceLog,
+    // Dimensions attached to shape edges and round shapes, sharing the
+    // linear dimensions' ids
+    #[serde(default)]
+    pub(crate) angular_dimensions: Vec<EdgeAngleDimension>,
+    #[serde(default)]
+    pub(crate) radial_dimensions: Vec<ShapeRadialDimension>,
+    #[serde(skip)]
+    changes: ChangeLog,
+    #[serde(skip)]
+    preview: Option<Preview>,
+    // Shape storage, as `shape_bytes` summed over the shapes
+    #[serde(skip)]
+    live: Live<{ ObjectKind::Document as usize }>,
+    // Shape handed out by `shape_mut` and its size then, settled on the next
+    // edit in case the caller resized its geometry
+    #[serde(skip)]
+    resized: Option<(ShapeId, usize)>,
+    #[serde(skip)]
+    pub(crate) autosave: AutosaveLog,
+    #[serde(skip)]
+    undo: UndoStack,
+    // Picked shapes, checkpointed with the rest so undo and redo bring back
+    // the selection each state had
+    #[serde(skip)]
+    pub(crate) selection: Selection,
+    // States `undo` went back from, latest last, until the next checkpoint
+    #[serde(skip)]
+    redo: UndoStack,
+    // Curve and control point being dragged, whose moves share one checkpoint
+    #[serde(skip)]
+    pub(crate) dragging: Option<(CurveId, usize)>,
+}
+
+// In-progress transform of a selection, e.g. during a drag. It is composed
+// on the fly wherever world positions are read, so stored shapes, indexes and
+// the change log are left alone until the preview is committed.
+#[derive(Clone, Debug)]
+struct Preview {
+    ids: BTreeSet<ShapeId>,
+    // World-space, applied on top of each shape's own transform
+    transform: Matrix,
+}
+
+// Outcome of `Document::regenerate`
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct RegenerateReport {
+    pub regenerated: Vec<ShapeId>,
+    // Generated shapes found edited by hand, left as drawn and no longer
+    // driven by their parameters
+    pub detached: Vec<ShapeId>,
+}
+
+// Content change of one shape; `hash` is None once the shape is removed
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ShapeChange {
+    pub id: ShapeId,
+    pub hash: Option<u64>,
+}
+
+// Shapes touched since the last `take_changes`, and the hash last reported
+// for each, so edits that end up back where they started are not reported
+#[derive(Clone, Debug)]
+struct ChangeLog {
+    pending: Vec<ShapeId>,
+    reported: HashMap<ShapeId, u64>,
+    // World box of each pending shape as of the last take; None for shapes
+    // added since
+    before: HashMap<ShapeId, Option<BoundingBox>>,
+    dirty_rect_limit: usize,
+}
+
+impl Default for ChangeLog {
+    fn default() -> ChangeLog {
+        ChangeLog { pending: Vec::new(), reported: HashMap::new(), before: HashMap::new(), dirty_rect_limit: 8 }
+    }
+}
+
+// Changes since the last take, and the world-space areas a renderer must
+// redraw for them: where changed shapes were and where they are now,
+// merged into non-overlapping rectangles
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ChangeSet {
+    pub changes: Vec<ShapeChange>,
+    pub dirty: Vec<BoundingBox>,
+}
+
+// Merges overlapping rectangles until none overlap, then collapses them to
+// their union if there are still more than `limit`
+fn merge_dirty(mut rects: Vec<BoundingBox>, limit: usize) -> Vec<BoundingBox> {
+    'merging: loop {
+        for i in 0..rects.len() {
+            for j in i + 1..rects.len() {
+                if rects[i].intersects(&rects[j]) {
+                    let other = rects.swap_remove(j);
+                    rects[i] = rects[i].union(&other);
+                    continue 'merging;
+                }
+            }
+        }
+        break;
+    }
+    if rects.len() > limit.max(1) {
+        return rects.into_iter().reduce(|a, b| a.union(&b)).into_iter().collect();
+    }
+    rects
+}
+
+// Query structure derived from the shapes
+pub(crate) trait ShapeIndex {
+    fn build(shapes: &[Arc<Shape>]) -> Self;
+    // Re-reads one shape; None when it has been removed
+    fn update(&mut self, id: ShapeId, shape: Option<&Shape>);
+    // Rough heap footprint, for memory reports; should be cheap to compute
+    fn approx_bytes(&self) -> usize;
+}
+
+// Built on first query, then refreshed for just the shapes touched since the
+// previous query. Clones start empty and rebuild on demand.
+struct LazyIndex<T> {
+    index: Option<T>,
+    stale: Vec<ShapeId>,
+    live: Option<Live<{ ObjectKind::Index as usize }>>,
+    // `memory::tick` as of the last query, for least-recently-used eviction
+    last_used: u64,
+}
+
+impl<T> Default for LazyIndex<T> {
+    fn default() -> LazyIndex<T> {
+        LazyIndex { index: None, stale: Vec::new(), live: None, last_used: 0 }
+    }
+}
+
+impl<T> Clone for LazyIndex<T> {
+    fn clone(&self) -> LazyIndex<T> {
+        LazyIndex::default()
+    }
+}
+
+// Earlier states of the document, latest last. Clones start empty, so a
+// checkpoint doesn't copy the checkpoints before it.
+#[derive(Debug, Default)]
+struct UndoStack(Vec<Document>);
+
+impl Clone for UndoStack {
+    fn clone(&self) -> UndoStack {
+        UndoStack::default()
+    }
+}
+
+impl<T> std::fmt::Debug for LazyIndex<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LazyIndex").field("built", &self.index.is_some()).field("stale", &self.stale.len()).finish()
+    }
+}
+
+impl<T: ShapeIndex> LazyIndex<T> {
+    fn touch(&mut self, id: ShapeId, shape_count: usize) {
+        if self.index.is_some() {
+            self.stale.push(id);
+            // Past this point a rebuild is cheaper than replaying the edits
+            if self.stale.len() > shape_count {
+                *self = LazyIndex::default();
+            }
+        }
+    }
+
+    fn refreshed(&mut self, shapes: &[Arc<Shape>]) -> &T {
+        self.last_used = tick();
+        let changed = self.index.is_none() || !self.stale.is_empty();
+        match &mut self.index {
+            Some(index) => {
+                self.stale.sort_unstable();
+                self.stale.dedup();
+                for id in self.stale.drain(..) {
+                    index.update(id, find(shapes, id).ok());
+                }
+            }
+            None => {
+                self.index = Some(T::build(shapes));
+                self.stale.clear();
+            }
+        }
+        let index = self.index.as_ref().unwrap();
+        if changed {
+            self.live.get_or_insert_with(Live::default).set_bytes(index.approx_bytes());
+        }
+        index
+    }
+
+    fn bytes(&self) -> usize {
+        self.live.as_ref().map_or(0, |l| l.bytes())
+    }
+}
+
+impl ShapeIndex for QuadTree {
+    fn build(shapes: &[Arc<Shape>]) -> QuadTree {
+        let boxes: Vec<(ShapeId, BoundingBox)> = shapes.iter().map(|s| (s.id, s.world_bounding_box())).collect();
+        QuadTree::bulk_build(&boxes)
+    }
+
+    fn update(&mut self, id: ShapeId, shape: Option<&Shape>) {
+        match shape {
+            Some(shape) => self.insert(id, shape.world_bounding_box()),
+            None => {
+                self.remove(id);
+            }
+        }
+    }
+
+    fn approx_bytes(&self) -> usize {
+        self.footprint()
+    }
+}
+
+// Immutable view of a document as of `Document::snapshot_view`
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct DocumentView {
+    shapes: Arc<Vec<Arc<Shape>>>,
+    // Just the list; the shapes are counted by the documents holding them
+    _live: Live<{ ObjectKind::DocumentView as usize }>,
+}
+
+// `a` after `b`, skipping the product when either is the identity so
+// shapes nothing is placing keep their transforms exactly
+fn compose(a: &Matrix, b: &Matrix) -> Matrix {
+    if *a == Matrix::identity() {
+        b.clone()
+    } else if *b == Matrix::identity() {
+        a.clone()
+    } else {
+        a.multiply(b)
+    }
+}
+
+pub(crate) fn apply(matrix: &Matrix, p: &Point) -> Point {
+    matrix.transform_point(p)
+}
+
+// `matrix` applied about `center` instead of the origin
+pub(crate) fn about(center: &Point, matrix: &Matrix) -> Matrix {
+    Matrix::translate(center.x, center.y)
+        .multiply(matrix)
+        .multiply(&Matrix::translate(-center.x, -center.y))
+}
+
+impl Shape {
+    pub fn pivot_local(&self) -> Point {
+        self.pivot.unwrap_or_else(|| self.geometry.centroid())
+    }
+
+    pub fn pivot_world(&self) -> Point {
+        apply(&self.world_transform(), &self.pivot_local())
+    }
+
+    // Local to world: the shape's transform, then its groups' from the
+    // innermost out, then its layer's
+    pub fn world_transform(&self) -> Matrix {
+        compose(&self.placement, &self.transform)
+    }
+
+    // Composes a world-space `matrix` on top of the shape's placement.
+    // Layer and group transforms are checked invertible when set.
+    pub(crate) fn transform_world(&mut self, matrix: &Matrix) {
+        if self.placement == Matrix::identity() {
+            self.transform = matrix.multiply(&self.transform);
+            return;
+        }
+        let inverse = self.placement.try_inverse().unwrap_or_else(|_| Matrix::identity());
+        self.transform = inverse.multiply(matrix).multiply(&self.placement).multiply(&self.transform);
+    }
+
+    pub fn world_geometry(&self) -> Polygon {
+        self.geometry.transform(&self.world_transform())
+    }
+
+    pub fn world_bounding_box(&self) -> BoundingBox {
+        self.world_geometry().bounding_box()
+    }
+}
+
+// World geometry as currently displayed, i.e. with any preview applied
+fn display_geometry(shape: &Shape, preview: Option<&Preview>) -> Polygon {
+    match preview {
+        Some(p) if p.ids.contains(&shape.id) => shape.geometry.transform(&p.transform.multiply(&shape.world_transform())),
+        _ => shape.world_geometry(),
+    }
+}
+
+fn shape_bytes(shape: &Shape) -> usize {
+    std::mem::size_of::<Arc<Shape>>() + std::mem::size_of::<Shape>()
+        + shape.geometry.vertices.len() * std::mem::size_of::<Point>()
+        + shape.label.as_ref().map_or(0, |l| l.len())
+        + shape.attributes.keys().map(|k| k.len() + std::mem::size_of::<AttributeValue>() + 32).sum::<usize>()
+}
+
+// `shapes` is kept in id order
+fn find(shapes: &[Arc<Shape>], id: ShapeId) -> Result<&Shape, CadError> {
+    shapes.binary_search_by_key(&id, |s| s.id).map(|i| shapes[i].as_ref()).map_err(|_| CadError::UnknownShape(id))
+}
+
+// Flat [x0, y0, x1, y1, x2, y2, ...] triangle list of the shapes' world geometry
+fn tessellate(shapes: &[Arc<Shape>], preview: Option<&Preview>) -> Vec<f32> {
+    let mut buffer = Vec::new();
+    for shape in shapes {
+        let world = display_geometry(shape, preview);
+        for triangle in world.triangulate() {
+            for i in triangle {
+                buffer.push(world.vertices[i].x as f32);
+                buffer.push(world.vertices[i].y as f32);
+            }
+        }
+    }
+    buffer
+}
+
+impl Document {
+    pub fn new() -> Document {
+        Document::default()
+    }
+
+    pub fn add_shape(&mut self, geometry: Polygon) -> ShapeId {
+        self.insert(Shape {
+            id: 0,
+            geometry,
+            transform: Matrix::identity(),
+            pivot: None,
+            attributes: BTreeMap::new(),
+            label: None,
+            layer: None,
+            group: None,
+            placement: Matrix::identity(),
+        })
+    }
+
+    // Adds `shape` under a fresh id
+    pub(crate) fn insert(&mut self, mut shape: Shape) -> ShapeId {
+        let id = self.next_id;
+        self.next_id += 1;
+        shape.id = id;
+        // e.g. a shape pasted from another document
+        if shape.group.is_some_and(|g| self.group(g).is_err()) {
+            shape.group = None;
+        }
+        shape.placement = self.placement(shape.layer.as_deref(), shape.group);
+        self.live.add_bytes(shape_bytes(&shape) as isize);
+        self.touch(id);
+        Arc::make_mut(&mut self.shapes).push(Arc::new(shape));
+        id
+    }
+
+    fn touch(&mut self, id: ShapeId) {
+        self.mark(id);
+        self.provenance.stamp(id);
+    }
+
+    // Everything `touch` does but stamp provenance, for changes that put
+    // back an earlier state rather than make a new one
+    fn mark(&mut self, id: ShapeId) {
+        if let Some((resized, bytes)) = self.resized.take() {
+            if let Ok(shape) = find(&self.shapes, resized) {
+                self.live.add_bytes(shape_bytes(shape) as isize - bytes as isize);
+            }
+        }
+        let count = self.shapes.len();
+        self.spatial.get_mut().touch(id, count);
+        self.search.get_mut().touch(id, count);
+        if !self.changes.before.contains_key(&id) {
+            let drawn = find(&self.shapes, id).ok().map(|s| s.world_bounding_box());
+            self.changes.before.insert(id, drawn);
+        }
+        self.autosave.touch(id, count);
+        self.changes.pending.push(id);
+        if self.changes.pending.len() > 2 * count + 16 {
+            self.changes.pending.sort_unstable();
+            self.changes.pending.dedup();
+        }
+    }
+
+    pub fn content_hash(&self, id: ShapeId) -> Result<u64, CadError> {
+        Ok(shape_hash(self.shape(id)?))
+    }
+
+    // Combined hash of every shape and its id, in id order
+    pub fn content_hash_all(&self) -> u64 {
+        let mut h = ContentHasher::new();
+        for shape in self.shapes() {
+            h.u64(shape.id as u64);
+            h.u64(shape_hash(shape));
+        }
+        h.finish()
+    }
+
+    // Text export in `format` ("svg" or "dxf"), with numbers as `format_number`
+    // writes them. Output depends only on the document's content, never on
+    // edit order, and carries no timestamps or generated ids. At a precision,
+    // outlines are rounded so none comes out self-intersecting.
+    pub fn export(&self, format: &str, precision: Option<usize>) -> Result<String, CadError> {
+        self.export_with(format, &ExportProfile::with_precision(precision))
+    }
+
+    // `export` of what `profile` picks out, with its layers renamed and
+    // coloured as it says. Shapes are filtered before any geometry is
+    // worked out for them.
+    pub fn export_with(&self, format: &str, profile: &ExportProfile) -> Result<String, CadError> {
+        Ok(self.export_report(format, profile)?.text)
+    }
+
+    // `export_with`, with how many vertices rounding had to adjust
+    pub fn export_report(&self, format: &str, profile: &ExportProfile) -> Result<ExportResult, CadError> {
+        let mut text = String::new();
+        let adjusted_vertices = write_export(self, format, profile, &mut text)?;
+        Ok(ExportResult { text, adjusted_vertices })
+    }
+
+    // FNV-1a hash of the bytes `export` would return, without building them
+    pub fn export_fingerprint(&self, format: &str, precision: Option<usize>) -> Result<u64, CadError> {
+        let mut out = HashWriter(ContentHasher::new());
+        write_export(self, format, &ExportProfile::with_precision(precision), &mut out)?;
+        Ok(out.0.finish())
+    }
+
+    // Shapes whose content hash differs from the one last reported, in id
+    // order. Shapes added and removed between two calls are not reported.
+    pub fn take_changes(&mut self) -> Vec<ShapeChange> {
+        self.take_change_set().changes
+    }
+
+    // `take_changes`, with the world areas the changes dirtied. A moved
+    // shape dirties both its old and new box; when the merged rectangles
+    // outnumber `set_dirty_rect_limit`, they collapse to their union.
+    // Boxes are of committed geometry, not transform previews.
+    pub fn take_change_set(&mut self) -> ChangeSet {
+        let mut pending = std::mem::take(&mut self.changes.pending);
+        let mut before = std::mem::take(&mut self.changes.before);
+        pending.sort_unstable();
+        pending.dedup();
+
+        let mut changes = Vec::new();
+        let mut dirty = Vec::new();
+        for id in pending {
+            let shape = find(&self.shapes, id).ok();
+            let hash = shape.map(shape_hash);
+            let previous = self.changes.reported.get(&id).copied();
+            if hash != previous {
+                match hash {
+                    Some(h) => self.changes.reported.insert(id, h),
+                    None => self.changes.reported.remove(&id),
+                };
+                changes.push(ShapeChange { id, hash });
+                dirty.extend(before.remove(&id).flatten());
+                dirty.extend(shape.map(|s| s.world_bounding_box()));
+            }
+        }
+        ChangeSet { changes, dirty: merge_dirty(dirty, self.changes.dirty_rect_limit) }
+    }
+
+    // Most dirty rectangles a change set carries; at least one
+    pub fn set_dirty_rect_limit(&mut self, limit: usize) {
+        self.changes.dirty_rect_limit = limit.max(1);
+    }
+
+    // Shapes touching any of the rectangles, e.g. a change set's dirty
+    // areas, in ascending id order
+    pub fn shapes_intersecting(&self, rects: &[BoundingBox]) -> Vec<ShapeId> {
+        let mut ids: Vec<ShapeId> = rects.iter().flat_map(|r| self.cull(r)).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+
+    pub fn shapes(&self) -> impl Iterator<Item = &Shape> {
+        self.shapes.iter().map(|s| s.as_ref())
+    }
+
+    pub fn shape(&self, id: ShapeId) -> Result<&Shape, CadError> {
+        find(&self.shapes, id)
+    }
+
+    // World geometry as drawn, with any preview transform applied
+    pub(crate) fn display_geometry(&self, id: ShapeId) -> Result<Polygon, CadError> {
+        Ok(display_geometry(self.shape(id)?, self.preview.as_ref()))
+    }
+
+    pub fn shape_mut(&mut self, id: ShapeId) -> Result<&mut Shape, CadError> {
+        let index = self.shapes.binary_search_by_key(&id, |s| s.id).map_err(|_| CadError::UnknownShape(id))?;
+        self.touch(id);
+        let shape = Arc::make_mut(&mut Arc::make_mut(&mut self.shapes)[index]);
+        self.resized = Some((id, shape_bytes(shape)));
+        Ok(shape)
+    }
+
+    // Packed world bounding boxes (as previewed), four f64 per shape laid out as
+    // [min_x, min_y, max_x, max_y]. With `ids` the boxes follow that list;
+    // without, they cover every shape in ascending id order.
+    pub fn bboxes(&self, ids: Option<&[ShapeId]>) -> Result<Vec<f64>, CadError> {
+        let mut packed = Vec::new();
+        let mut push = |shape: &Shape| {
+            let b = display_geometry(shape, self.preview.as_ref()).bounding_box();
+            packed.extend_from_slice(&[b.min.x, b.min.y, b.max.x, b.max.y]);
+        };
+        match ids {
+            Some(ids) => {
+                for &id in ids {
+                    push(self.shape(id)?);
+                }
+            }
+            None => self.shapes().for_each(push),
+        }
+        Ok(packed)
+    }
+
+    // Shapes whose world bounding box touches the viewport, in ascending id order
+    pub fn cull(&self, viewport: &BoundingBox) -> Vec<ShapeId> {
+        let hits = self.spatial.borrow_mut().refreshed(&self.shapes).query(viewport);
+        self.with_preview_hits(hits, viewport)
+    }
+
+    // The index holds stored positions, so previewed shapes are swapped out
+    // of its hits for a direct test of their previewed boxes
+    fn with_preview_hits(&self, hits: Vec<ShapeId>, area: &BoundingBox) -> Vec<ShapeId> {
+        let Some(preview) = &self.preview else {
+            return hits;
+        };
+        let mut hits: Vec<ShapeId> = hits.into_iter().filter(|id| !preview.ids.contains(id)).collect();
+        for &id in &preview.ids {
+            if let Ok(shape) = self.shape(id) {
+                if display_geometry(shape, Some(preview)).bounding_box().intersects(area) {
+                    hits.push(id);
+                }
+            }
+        }
+        hits.sort_unstable();
+        hits
+    }
+
+    // Starts previewing a transform of `ids`, replacing any preview already
+    // in progress. The preview transform starts as the identity.
+    pub fn begin_preview(&mut self, ids: &[ShapeId]) -> Result<(), CadError> {
+        for &id in ids {
+            self.shape(id)?;
+        }
+        self.preview = Some(Preview { ids: ids.iter().copied().collect(), transform: Matrix::identity() });
+        Ok(())
+    }
+
+    pub fn set_preview_transform(&mut self, transform: Matrix) -> Result<(), CadError> {
+        match self.preview.as_mut() {
+            Some(preview) => {
+                preview.transform = transform;
+                Ok(())
+            }
+            None => Err(CadError::InvalidInput("no transform preview in progress".to_string())),
+        }
+    }
+
+    pub fn is_previewing(&self) -> bool {
+        self.preview.is_some()
+    }
+
+    // Applies the previewed transform to the selection in one batch, as one
+    // undoable step, and returns the ids it was applied to. Shapes removed
+    // since the preview began are skipped.
+    pub fn commit_preview(&mut self) -> Result<Vec<ShapeId>, CadError> {
+        let preview = self.preview.take().ok_or_else(|| CadError::InvalidInput("no transform preview in progress".to_string()))?;
+        if preview.ids.iter().any(|&id| self.shape(id).is_ok()) {
+            self.checkpoint();
+        }
+        let mut committed = Vec::new();
+        for id in preview.ids {
+            if let Ok(shape) = self.shape_mut(id) {
+                shape.transform_world(&preview.transform);
+                committed.push(id);
+            }
+        }
+        Ok(committed)
+    }
+
+    pub fn cancel_preview(&mut self) {
+        self.preview = None;
+    }
+
+    pub fn set_attribute(&mut self, id: ShapeId, key: &str, value: AttributeValue) -> Result<(), CadError> {
+        self.shape_mut(id)?.attributes.insert(key.to_string(), value);
+        Ok(())
+    }
+
+    pub fn remove_attribute(&mut self, id: ShapeId, key: &str) -> Result<Option<AttributeValue>, CadError> {
+        Ok(self.shape_mut(id)?.attributes.remove(key))
+    }
+
+    pub fn set_label(&mut self, id: ShapeId, label: Option<String>) -> Result<(), CadError> {
+        self.shape_mut(id)?.label = label;
+        Ok(())
+    }
+
+    pub fn set_layer(&mut self, id: ShapeId, layer: Option<String>) -> Result<(), CadError> {
+        self.shape_mut(id)?.layer = layer;
+        self.update_placements(&[id])
+    }
+
+    // Identity for no layer, or a layer without a transform
+    pub fn layer_transform(&self, layer: Option<&str>) -> Matrix {
+        layer.and_then(|l| self.layer_transforms.get(l)).cloned().unwrap_or_else(Matrix::identity)
+    }
+
+    // Places every shape on `layer`, now and later, by `transform` on top
+    // of its own transform, leaving stored geometry and shape transforms
+    // as they are. None (or the identity) clears it.
+    pub fn set_layer_transform(&mut self, layer: &str, transform: Option<Matrix>) -> Result<(), CadError> {
+        let transform = transform.unwrap_or_else(Matrix::identity);
+        transform.try_inverse()?;
+        if transform == Matrix::identity() {
+            self.layer_transforms.remove(layer);
+        } else {
+            self.layer_transforms.insert(layer.to_string(), transform.clone());
+        }
+        self.update_placements(&self.layer_shapes(layer))
+    }
+
+    // Folds the layer's transform into the transforms of its shapes and
+    // clears it, as one undoable step, so world geometry stays where it is
+    pub fn bake_layer_transform(&mut self, layer: &str) -> Result<(), CadError> {
+        if !self.layer_transforms.contains_key(layer) {
+            return Ok(());
+        }
+        self.checkpoint();
+        self.layer_transforms.remove(layer);
+        for id in self.layer_shapes(layer) {
+            let placement = self.placement(Some(layer), self.shape(id)?.group);
+            let shape = self.shape_mut(id)?;
+            // What is left above the shape is its groups, checked invertible
+            let inverse = placement.try_inverse().unwrap_or_else(|_| Matrix::identity());
+            shape.transform = compose(&inverse, &shape.world_transform());
+            shape.placement = placement;
+        }
+        Ok(())
+    }
+
+    // Layer transform, then the group transforms from the outermost in
+    fn placement(&self, layer: Option<&str>, group: Option<ShapeId>) -> Matrix {
+        let mut m = Matrix::identity();
+        let mut next = group.and_then(|id| self.group(id).ok());
+        while let Some(g) = next {
+            m = compose(&g.transform, &m);
+            next = g.parent.and_then(|id| self.group(id).ok());
+        }
+        compose(&self.layer_transform(layer), &m)
+    }
+
+    fn update_placements(&mut self, ids: &[ShapeId]) -> Result<(), CadError> {
+        for &id in ids {
+            let shape = self.shape(id)?;
+            let placement = self.placement(shape.layer.as_deref(), shape.group);
+            if placement != shape.placement {
+                self.shape_mut(id)?.placement = placement;
+            }
+        }
+        Ok(())
+    }
+
+    fn layer_shapes(&self, layer: &str) -> Vec<ShapeId> {
+        self.shapes().filter(|s| s.layer.as_deref() == Some(layer)).map(|s| s.id).collect()
+    }
+
+    // Stored, local-space vertices, or with `world` as placed by the shape
+    // and layer transforms
+    pub fn vertices(&self, id: ShapeId, world: bool) -> Result<Vec<Point>, CadError> {
+        let shape = self.shape(id)?;
+        Ok(if world { shape.world_geometry().vertices } else { shape.geometry.vertices.clone() })
+    }
+
+    // Matching shape ids in ascending order
+    pub fn search(&self, query: &SearchQuery) -> Vec<ShapeId> {
+        self.search.borrow_mut().refreshed(&self.shapes).query(query).into_iter().collect()
+    }
+
+    pub fn snapshot_view(&self) -> DocumentView {
+        let live = Live::with_bytes(std::mem::size_of::<DocumentView>() + self.shapes.len() * std::mem::size_of::<Arc<Shape>>());
+        DocumentView { shapes: Arc::clone(&self.shapes), _live: live }
+    }
+
+    // Approximate bytes held: shapes plus built indexes
+    pub fn memory_usage(&self) -> usize {
+        self.live.bytes() + self.cache_bytes()
+    }
+
+    // Bytes held by the spatial and search indexes, which are rebuilt on
+    // the next query after being dropped
+    pub fn cache_bytes(&self) -> usize {
+        self.spatial.borrow().bytes() + self.search.borrow().bytes()
+    }
+
+    // Drops indexes, least recently used first, until they take at most
+    // `target_bytes`; returns the bytes freed
+    pub fn shrink_caches(&mut self, target_bytes: usize) -> usize {
+        let before = self.cache_bytes();
+        while self.cache_bytes() > target_bytes {
+            let (spatial, search) = (self.spatial.get_mut(), self.search.get_mut());
+            let spatial_first = match (spatial.index.is_some(), search.index.is_some()) {
+                (true, true) => spatial.last_used <= search.last_used,
+                (spatial_built, _) => spatial_built,
+            };
+            if spatial_first {
+                *spatial = LazyIndex::default();
+            } else {
+                *search = LazyIndex::default();
+            }
+        }
+        before - self.cache_bytes()
+    }
+
+    pub(crate) fn next_id(&self) -> ShapeId {
+        self.next_id
+    }
+
+    pub(crate) fn set_next_id(&mut self, next_id: ShapeId) {
+        self.next_id = next_id;
+    }
+
+    // Everything but the shapes and the id counter, for autosave deltas to
+    // carry when it changes
+    pub(crate) fn settings(&self) -> Document {
+        Document { shapes: Arc::new(Vec::new()), next_id: 0, ..self.clone() }
+    }
+
+    // Takes on everything `settings` covers from `settings`, keeping the
+    // shapes; call `recount` once done replaying
+    pub(crate) fn restore_settings(&mut self, settings: Document) {
+        let shapes = std::mem::take(&mut self.shapes);
+        *self = Document { shapes, next_id: self.next_id, ..settings };
+    }
+
+    // Stores `shape` under its own id, in id order, replacing any shape
+    // already there; call `recount` once done replaying
+    pub(crate) fn put_shape(&mut self, shape: Shape) {
+        self.mark(shape.id);
+        self.next_id = self.next_id.max(shape.id + 1);
+        let shapes = Arc::make_mut(&mut self.shapes);
+        match shapes.binary_search_by_key(&shape.id, |s| s.id) {
+            Ok(i) => shapes[i] = Arc::new(shape),
+            Err(i) => shapes.insert(i, Arc::new(shape)),
+        }
+    }
+
+    // Recounts shape storage and hands shapes their layer transforms, for
+    // documents built without going through `insert`, e.g. by deserializing
+    pub(crate) fn recount(&mut self) {
+        self.live.set_bytes(self.shapes.iter().map(|s| shape_bytes(s)).sum());
+        self.resized = None;
+        // Lookups binary-search by id, so a document saved elsewhere mustn't
+        // come in out of order
+        if !self.shapes.windows(2).all(|w| w[0].id < w[1].id) {
+            Arc::make_mut(&mut self.shapes).sort_by_key(|s| s.id);
+        }
+        for i in 0..self.shapes.len() {
+            let placement = self.placement(self.shapes[i].layer.as_deref(), self.shapes[i].group);
+            if placement != self.shapes[i].placement {
+                Arc::make_mut(&mut Arc::make_mut(&mut self.shapes)[i]).placement = placement;
+            }
+        }
+    }
+
+    pub fn groups(&self) -> impl Iterator<Item = &Group> {
+        self.groups.iter()
+    }
+
+    pub fn group(&self, id: ShapeId) -> Result<&Group, CadError> {
+        self.groups.iter().find(|g| g.id == id).ok_or(CadError::UnknownGroup(id))
+    }
+
+    fn is_group(&self, id: ShapeId) -> bool {
+        self.group(id).is_ok()
+    }
+
+    // Group a shape or group is directly in
+    fn parent_of(&self, id: ShapeId) -> Result<Option<ShapeId>, CadError> {
+        match self.group(id) {
+            Ok(g) => Ok(g.parent),
+            Err(_) => Ok(self.shape(id)?.group),
+        }
+    }
+
+    // Shapes and groups directly in the group, in ascending id order
+    pub fn group_members(&self, id: ShapeId) -> Result<Vec<ShapeId>, CadError> {
+        self.group(id)?;
+        let mut members: Vec<ShapeId> = self.shapes().filter(|s| s.group == Some(id)).map(|s| s.id)
+            .chain(self.groups.iter().filter(|g| g.parent == Some(id)).map(|g| g.id))
+            .collect();
+        members.sort_unstable();
+        Ok(members)
+    }
+
+    // Shapes in the group or any group inside it
+    fn shapes_under(&self, id: ShapeId) -> Vec<ShapeId> {
+        let inside = |mut group: Option<ShapeId>| {
+            while let Some(g) = group {
+                if g == id {
+                    return true;
+                }
+                group = self.group(g).ok().and_then(|g| g.parent);
+            }
+            false
+        };
+        self.shapes().filter(|s| inside(s.group)).map(|s| s.id).collect()
+    }
+
+    // Gathers shapes and groups, which must all be directly in the same
+    // group (or none), into a new group there with an identity transform,
+    // so nothing moves
+    pub fn create_group(&mut self, ids: &[ShapeId]) -> Result<ShapeId, CadError> {
+        let mut ids = ids.to_vec();
+        ids.sort_unstable();
+        ids.dedup();
+        let Some(&first) = ids.first() else {
+            return Err(CadError::InvalidInput("a group needs at least one member".to_string()));
+        };
+        let parent = self.parent_of(first)?;
+        for &id in &ids {
+            if self.parent_of(id)? != parent {
+                return Err(CadError::InvalidInput(format!("shape {} is not in the same group as shape {}", id, first)));
+            }
+        }
+        let group = self.next_id;
+        self.next_id += 1;
+        self.groups.push(Group { id: group, transform: Matrix::identity(), parent, pivot: None });
+        for id in ids {
+            match self.groups.iter_mut().find(|g| g.id == id) {
+                Some(g) => g.parent = Some(group),
+                None => self.shape_mut(id)?.group = Some(group),
+            }
+        }
+        Ok(group)
+    }
+
+    // Removes the group, releasing its members into its parent with its
+    // transform folded into theirs, so nothing moves. Members are never
+    // deleted with their group.
+    pub fn remove_group(&mut self, id: ShapeId) -> Result<Group, CadError> {
+        let index = self.groups.iter().position(|g| g.id == id).ok_or(CadError::UnknownGroup(id))?;
+        let under = self.shapes_under(id);
+        let group = self.groups.remove(index);
+        for g in self.groups.iter_mut().filter(|g| g.parent == Some(id)) {
+            g.transform = compose(&group.transform, &g.transform);
+            g.parent = group.parent;
+        }
+        let members: Vec<ShapeId> = self.shapes().filter(|s| s.group == Some(id)).map(|s| s.id).collect();
+        for member in members {
+            let shape = self.shape_mut(member)?;
+            shape.transform = compose(&group.transform, &shape.transform);
+            shape.group = group.parent;
+        }
+        self.update_placements(&under)?;
+        Ok(group)
+    }
+
+    // Geometry with every transform above it applied, ignoring previews
+    pub fn flattened(&self, id: ShapeId) -> Result<Polygon, CadError> {
+        Ok(self.shape(id)?.world_geometry())
+    }
+
+    // Shapes whose world geometry (as previewed) contains the point, on its
+    // boundary included, in ascending id order
+    pub fn hit_test_all(&self, p: &Point) -> Vec<ShapeId> {
+        let hits = self.spatial.borrow_mut().refreshed(&self.shapes).query_point(p);
+        self.with_preview_hits(hits, &BoundingBox::new(*p, *p))
+            .into_iter()
+            .filter(|&id| self.display_geometry(id).is_ok_and(|g| g.contains_point(p)))
+            .collect()
+    }
+
+    // World box of every shape as previewed; the empty box at the origin
+    // for an empty document
+    pub fn total_bounds(&self) -> BoundingBox {
+        self.shapes()
+            .map(|s| display_geometry(s, self.preview.as_ref()).bounding_box())
+            .reduce(|a, b| a.union(&b))
+            .unwrap_or_else(|| BoundingBox::from_points(&[]))
+    }
+
+    // Of a shape, or of a group, whose transform must be invertible
+    pub fn set_transform(&mut self, id: ShapeId, transform: Matrix) -> Result<(), CadError> {
+        if !self.is_group(id) {
+            self.shape_mut(id)?.transform = transform;
+            return Ok(());
+        }
+        transform.try_inverse()?;
+        if let Some(g) = self.groups.iter_mut().find(|g| g.id == id) {
+            g.transform = transform;
+        }
+        self.update_placements(&self.shapes_under(id))
+    }
+
+    // Of a shape, or of a group in the group's own space
+    pub fn set_pivot(&mut self, id: ShapeId, point_local: Point) -> Result<(), CadError> {
+        match self.groups.iter_mut().find(|g| g.id == id) {
+            Some(g) => g.pivot = Some(point_local),
+            None => self.shape_mut(id)?.pivot = Some(point_local),
+        }
+        Ok(())
+    }
+
+    pub fn get_pivot_world(&self, id: ShapeId) -> Result<Point, CadError> {
+        if !self.is_group(id) {
+            return Ok(self.shape(id)?.pivot_world());
+        }
+        let (outer, group) = (self.group_placement(id)?, self.group(id)?);
+        if let Some(pivot) = group.pivot {
+            return Ok(apply(&compose(&outer, &group.transform), &pivot));
+        }
+        let boxes = self.shapes_under(id).into_iter().map(|s| self.shape(s).map(|s| s.world_geometry().bounding_box()));
+        match boxes.reduce(|a, b| Ok(a?.union(&b?))) {
+            Some(bounds) => Ok(bounds?.center()),
+            None => Ok(apply(&compose(&outer, &group.transform), &Point::new(0.0, 0.0))),
+        }
+    }
+
+    // What places a group's transform in the world: its parents' and its
+    // layer's, a group taking the layer of the first shape under it
+    fn group_placement(&self, id: ShapeId) -> Result<Matrix, CadError> {
+        let parent = self.group(id)?.parent;
+        let layer = self.shapes_under(id).first().map(|&s| self.shape(s)).transpose()?.and_then(|s| s.layer.clone());
+        Ok(self.placement(layer.as_deref(), parent))
+    }
+
+    // Composes a world-space `matrix` about the pivot of the shape or group
+    // on top of its transform
+    fn transform_about_pivot(&mut self, id: ShapeId, matrix: &Matrix) -> Result<(), CadError> {
+        let pivoted = about(&self.get_pivot_world(id)?, matrix);
+        if !self.is_group(id) {
+            self.shape_mut(id)?.transform_world(&pivoted);
+            return Ok(());
+        }
+        let outer = self.group_placement(id)?;
+        let transform = outer.try_inverse()?.multiply(&pivoted).multiply(&outer).multiply(&self.group(id)?.transform);
+        self.set_transform(id, transform)
+    }
+
+    // Rotates a shape or group by `angle` radians about its pivot in world
+    // space, composed on top of its existing transform
+    pub fn rotate_shape(&mut self, id: ShapeId, angle: f64) -> Result<(), CadError> {
+        self.transform_about_pivot(id, &Matrix::rotate(angle))
+    }
+
+    // Scales a shape or group along the world axes about its pivot
+    pub fn scale_shape(&mut self, id: ShapeId, sx: f64, sy: f64) -> Result<(), CadError> {
+        self.transform_about_pivot(id, &Matrix::scale(sx, sy))
+    }
+
+    // Also removes the shape's dimensions
+    pub fn remove_shape(&mut self, id: ShapeId) -> Result<Shape, CadError> {
+        let index = self.shapes.binary_search_by_key(&id, |s| s.id).map_err(|_| CadError::UnknownShape(id))?;
+        self.touch(id);
+        self.dimensions.retain(|d| d.shape != id);
+        self.angular_dimensions.retain(|d| d.edge_a.shape != id && d.edge_b.shape != id);
+        self.radial_dimensions.retain(|d| d.shape != id);
+        self.selection.deselect(id);
+        let shape = Arc::unwrap_or_clone(Arc::make_mut(&mut self.shapes).remove(index));
+        self.live.add_bytes(-(shape_bytes(&shape) as isize));
+        self.provenance.forget(id);
+        Ok(shape)
+    }
+
+    // Saves the document as it is for `undo` to go back to, dropping the
+    // oldest checkpoint past UNDO_LIMIT
+    pub fn checkpoint(&mut self) {
+        self.dragging = None;
+        self.redo.0.clear();
+        self.undo.0.push(self.clone());
+        if self.undo.0.len() > UNDO_LIMIT {
+            self.undo.0.remove(0);
+        }
+    }
+
+    // Takes on `work`, a clone of this document edited in its place, as
+    // one undoable step; checkpoints `work` made along the way are dropped
+    pub(crate) fn replace_with(&mut self, work: Document) {
+        self.checkpoint();
+        let undo = std::mem::take(&mut self.undo);
+        *self = work;
+        self.undo = undo;
+        self.redo = UndoStack::default();
+    }
+
+    // Goes back to the latest checkpoint and forgets it, for an edit that
+    // failed part way; unlike `undo`, leaves nothing to redo
+    pub(crate) fn roll_back(&mut self) {
+        if let Some(point) = self.undo.0.pop() {
+            self.restore(point);
+        }
+    }
+
+    // Forgets the latest checkpoint, for an edit that turned out not to
+    // change anything
+    pub(crate) fn drop_checkpoint(&mut self) {
+        self.undo.0.pop();
+    }
+
+    // Goes back to the latest checkpoint, shapes and provenance records as
+    // they were then, and reports the shapes that changed as edits like any
+    // other, but without stamping them. Ids handed out since stay used.
+    // False when there is no checkpoint.
+    pub fn undo(&mut self) -> bool {
+        let Some(point) = self.undo.0.pop() else {
+            return false;
+        };
+        self.redo.0.push(self.clone());
+        self.restore(point);
+        true
+    }
+
+    // Goes forward again to the state the latest `undo` left, until an
+    // edit makes a new checkpoint. False when there is nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        let Some(point) = self.redo.0.pop() else {
+            return false;
+        };
+        self.undo.0.push(self.clone());
+        self.restore(point);
+        true
+    }
+
+    fn restore(&mut self, point: Document) {
+        self.preview = None;
+        self.dragging = None;
+        let now: HashMap<ShapeId, &Arc<Shape>> = self.shapes.iter().map(|s| (s.id, s)).collect();
+        let then: HashMap<ShapeId, &Arc<Shape>> = point.shapes.iter().map(|s| (s.id, s)).collect();
+        let mut changed: Vec<ShapeId> = now.iter().filter(|(id, s)| then.get(id).is_none_or(|t| !Arc::ptr_eq(s, t))).map(|(&id, _)| id)
+            .chain(then.keys().filter(|id| !now.contains_key(id)).copied())
+            .collect();
+        changed.sort_unstable();
+        for id in changed {
+            self.mark(id);
+        }
+        let Document { shapes, dimensions, next_dimension_id, parameters, generators, dxf_passthrough, views, layer_transforms, groups, text_fit, instances, curves, next_curve_id, provenance, angular_dimensions, radial_dimensions, selection, .. } = point;
+        self.shapes = shapes;
+        self.dimensions = dimensions;
+        self.next_dimension_id = self.next_dimension_id.max(next_dimension_id);
+        self.parameters = parameters;
+        self.generators = generators;
+        self.dxf_passthrough = dxf_passthrough;
+        self.views = views;
+        self.layer_transforms = layer_transforms;
+        self.groups = groups;
+        self.text_fit = text_fit;
+        self.instances = instances;
+        self.curves = curves;
+        self.next_curve_id = self.next_curve_id.max(next_curve_id);
+        self.provenance.restore(provenance);
+        self.angular_dimensions = angular_dimensions;
+        self.radial_dimensions = radial_dimensions;
+        self.recount();
+        // Only ids that name a shape in the state gone back or forward to
+        let mut kept = Selection::new();
+        kept.restore(self, selection.ids());
+        self.selection = kept;
+    }
+
+    // `from` and `to` are in the shape's local space
+    pub fn add_dimension(&mut self, shape: ShapeId, kind: DimensionKind, from: Point, to: Point) -> Result<DimensionId, CadError> {
+        self.shape(shape)?;
+        let id = self.fresh_dimension_id();
+        self.dimensions.push(Dimension { id, shape, kind, from, to, attributes: BTreeMap::new() });
+        Ok(id)
+    }
+
+    pub(crate) fn fresh_dimension_id(&mut self) -> DimensionId {
+        self.next_dimension_id += 1;
+        self.next_dimension_id - 1
+    }
+
+    pub fn dimensions(&self) -> impl Iterator<Item = &Dimension> {
+        self.dimensions.iter()
+    }
+
+    pub fn dimension(&self, id: DimensionId) -> Result<&Dimension, CadError> {
+        self.dimensions.iter().find(|d| d.id == id).ok_or(CadError::UnknownDimension(id))
+    }
+
+    pub fn dimension_mut(&mut self, id: DimensionId) -> Result<&mut Dimension, CadError> {
+        self.dimensions.iter_mut().find(|d| d.id == id).ok_or(CadError::UnknownDimension(id))
+    }
+
+    pub fn dimension_value(&self, id: DimensionId) -> Result<f64, CadError> {
+        let dimension = self.dimension(id)?;
+        Ok(dimension.value_on(self.shape(dimension.shape)?))
+    }
+
+    pub fn remove_dimension(&mut self, id: DimensionId) -> Result<Dimension, CadError> {
+        let index = self.dimensions.iter().position(|d| d.id == id).ok_or(CadError::UnknownDimension(id))?;
+        Ok(self.dimensions.remove(index))
+    }
+
+    // Saves `view` under `name`, replacing any view already called that
+    pub fn save_view(&mut self, name: &str, view: NamedView) {
+        self.views.insert(name.to_string(), view);
+    }
+
+    // Names of the saved views, sorted
+    pub fn list_views(&self) -> Vec<String> {
+        self.views.keys().cloned().collect()
+    }
+
+    pub fn get_view(&self, name: &str) -> Result<&NamedView, CadError> {
+        self.views.get(name).ok_or_else(|| CadError::UnknownView(name.to_string()))
+    }
+
+    pub fn delete_view(&mut self, name: &str) -> Result<NamedView, CadError> {
+        self.views.remove(name).ok_or_else(|| CadError::UnknownView(name.to_string()))
+    }
+
+    // Fails, changing nothing, if another view already has the new name
+    pub fn rename_view(&mut self, from: &str, to: &str) -> Result<(), CadError> {
+        if from != to && self.views.contains_key(to) {
+            return Err(CadError::InvalidInput(format!("a view named '{}' already exists", to)));
+        }
+        let view = self.delete_view(from)?;
+        self.views.insert(to.to_string(), view);
+        Ok(())
+    }
+
+    // Adds the region each shape covers while moving `distance` along
+    // `direction`, as new untransformed shapes in `ids` order
+    pub fn sweep_shapes(&mut self, ids: &[ShapeId], direction: &Vector, distance: f64) -> Result<Vec<ShapeId>, CadError> {
+        let envelopes: Vec<Polygon> = ids.iter()
+            .map(|&id| Ok(sweep_translate(&self.shape(id)?.world_geometry(), direction, distance)))
+            .collect::<Result<_, CadError>>()?;
+        Ok(envelopes.into_iter().map(|e| self.add_shape(e)).collect())
+    }
+
+    // Replaces the shape's local geometry by its offset, keeping the
+    // transform. When an inward offset splits, the largest piece is kept.
+    pub fn offset_shape(&mut self, id: ShapeId, distance: f64, quality: usize) -> Result<(), CadError> {
+        let pieces = crate::strict::checked(self.shape(id)?.geometry.offset(distance, JoinStyle::Round { segments: quality }), "offset")?;
+        let largest = pieces.into_iter().max_by(|a, b| a.area().abs().total_cmp(&b.area().abs()))
+            .ok_or_else(|| CadError::Degenerate(format!("offset by {} leaves nothing of shape {}", distance, id)))?;
+        self.shape_mut(id)?.geometry = largest;
+        Ok(())
+    }
+
+    // `count - 1` copies of the shape, copy k turned by `k * sweep / count`
+    // radians about the world point `center`; a full turn spaces all
+    // `count` instances evenly
+    pub fn polar_array(&mut self, id: ShapeId, center: Point, count: usize, sweep: f64) -> Result<Vec<ShapeId>, CadError> {
+        let shape = self.shape(id)?.clone();
+        if count == 0 {
+            return Err(CadError::InvalidInput("a polar array needs at least one instance".to_string()));
+        }
+        Ok((1..count).map(|k| {
+            let mut copy = shape.clone();
+            copy.transform_world(&about(&center, &Matrix::rotate(sweep * k as f64 / count as f64)));
+            self.insert(copy)
+        }).collect())
+    }
+
+    // A shape for the region the selected curves bound, any holes joined
+    // to the outline by keyhole cuts as boolean results are, so its area,
+    // fill and hit-testing leave them out
+    pub fn create_region(&mut self, entities: &[Entity], tolerance: f64) -> Result<ShapeId, RegionError> {
+        let region = make_region(entities, tolerance)?;
+        Ok(self.add_shape(crate::boolean::keyhole(region)))
+    }
+
+    // Clipboard contents for the given shapes, keeping transforms and pivots
+    pub fn copy_shapes(&self, ids: &[ShapeId]) -> Result<Vec<Shape>, CadError> {
+        ids.iter().map(|&id| self.shape(id).cloned()).collect()
+    }
+
+    // Inserts clipboard shapes under fresh ids, returned in clipboard order
+    pub fn paste_shapes(&mut self, shapes: &[Shape]) -> Vec<ShapeId> {
+        shapes.iter().map(|s| self.insert(s.clone())).collect()
+    }
+
+    pub fn parameters(&self) -> &Parameters {
+        &self.parameters
+    }
+
+    // Takes effect on shapes at the next `regenerate`
+    pub fn set_parameter(&mut self, name: &str, expression: &str) -> Result<(), CadError> {
+        self.parameters.set(name, expression)
+    }
+
+    // Adds a shape built by a factory call over the parameters, such as
+    // `create_rectangle(2 * flange + 10, height)`, kept in step with them
+    // by `regenerate`
+    pub fn add_generated(&mut self, call: &str) -> Result<ShapeId, CadError> {
+        let geometry = generate(call, &self.parameters.evaluate()?, self.parameters.units)?;
+        let generated = geometry.vertices.clone();
+        let shape = self.add_shape(geometry);
+        self.generators.push(Generator { shape, call: call.to_string(), generated });
+        Ok(shape)
+    }
+
+    // Re-evaluates the parameters and rebuilds every generated shape from
+    // them. Nothing changes unless every shape can be rebuilt. Dimension
+    // points on a generated vertex follow it to its new position. Shapes
+    // whose geometry was edited since they were generated are detached
+    // rather than overwritten; transforms may change freely. One undo
+    // reverts the whole rebuild.
+    pub fn regenerate(&mut self) -> Result<RegenerateReport, CadError> {
+        let values = self.parameters.evaluate()?;
+        self.generators.retain(|g| self.shapes.iter().any(|s| s.id == g.shape));
+        let mut report = RegenerateReport::default();
+        let mut rebuilt = Vec::new();
+        for (i, g) in self.generators.iter().enumerate() {
+            if self.shape(g.shape)?.geometry.vertices != g.generated {
+                report.detached.push(g.shape);
+            } else {
+                rebuilt.push((i, generate(&g.call, &values, self.parameters.units)?));
+            }
+        }
+
+        if !rebuilt.is_empty() || !report.detached.is_empty() {
+            self.checkpoint();
+        }
+        for (i, geometry) in rebuilt {
+            let id = self.generators[i].shape;
+            let old = std::mem::replace(&mut self.generators[i].generated, geometry.vertices.clone());
+            let follow = |p: &mut Point| {
+                if old.len() == geometry.vertices.len() {
+                    if let Some(k) = old.iter().position(|v| (v.x - p.x).hypot(v.y - p.y) <= EPSILON) {
+                        *p = geometry.vertices[k];
+                    }
+                }
+            };
+            for d in self.dimensions.iter_mut().filter(|d| d.shape == id) {
+                follow(&mut d.from);
+                follow(&mut d.to);
+            }
+            if old != geometry.vertices {
+                self.shape_mut(id)?.geometry = geometry;
+            }
+            report.regenerated.push(id);
+        }
+        self.generators.retain(|g| !report.detached.contains(&g.shape));
+        Ok(report)
+    }
+
+    // Adds the shapes drawn in SVG text with their group transforms baked
+    // into the geometry, each <g> becoming a group of what it holds with an
+    // identity transform, nested as in the file. Each shape records its
+    // element id as the `svg_id` attribute; a `data-layer` on the element
+    // or a group puts it on that layer. Nothing is added if the markup
+    // itself is broken.
+    pub fn import_svg(&mut self, text: &str) -> Result<SvgImport, CadError> {
+        let SvgContents { shapes: imported, groups: svg_groups, warnings } = read_svg(text)?;
+        let imported = crate::strict::checked_each(imported, |s| &mut s.geometry, "SVG import")?;
+        let mut shapes = Vec::new();
+        let mut placed = Vec::new();
+        for s in imported {
+            let id = self.add_shape(s.geometry);
+            if let Some(svg_id) = s.id {
+                self.set_attribute(id, "svg_id", AttributeValue::Text(svg_id))?;
+            }
+            if s.layer.is_some() {
+                self.set_layer(id, s.layer)?;
+            }
+            shapes.push(id);
+            placed.push((id, s.group));
+        }
+        // Innermost first, so each group's subgroups exist when it is made
+        let mut made: Vec<Option<ShapeId>> = vec![None; svg_groups.len()];
+        for k in (0..svg_groups.len()).rev() {
+            let mut members: Vec<ShapeId> = placed.iter().filter(|(_, g)| *g == Some(k)).map(|(id, _)| *id).collect();
+            members.extend((k + 1..svg_groups.len()).filter(|&j| svg_groups[j].parent == Some(k)).filter_map(|j| made[j]));
+            if !members.is_empty() {
+                made[k] = Some(self.create_group(&members)?);
+            }
+        }
+        let groups = made.into_iter().zip(svg_groups).filter_map(|(id, g)| id.map(|id| (id, g.id))).collect();
+        Ok(SvgImport { shapes, groups, warnings })
+    }
+
+    // Adds the closed polylines in DXF text as shapes on their layers, each
+    // recording its entity handle as the `dxf_handle` attribute. With
+    // `preserve`, every other entity, table and section replaces what an
+    // earlier import kept, and DXF export writes them back unchanged;
+    // without, they are dropped. Nothing is added if the file is malformed.
+    pub fn import_dxf(&mut self, text: &str, preserve: bool) -> Result<DxfImport, CadError> {
+        let (imported, blobs) = read_dxf(text)?;
+        let imported = crate::strict::checked_each(imported, |s| &mut s.geometry, "DXF import")?;
+        let mut shapes = Vec::new();
+        for s in imported {
+            let id = self.add_shape(s.geometry);
+            if let Some(handle) = s.handle {
+                self.set_attribute(id, "dxf_handle", AttributeValue::Text(handle))?;
+            }
+            if s.layer.is_some() {
+                self.set_layer(id, s.layer)?;
+            }
+            shapes.push(id);
+        }
+        let count = blobs.len();
+        if preserve {
+            self.dxf_passthrough = blobs;
+        }
+        let (preserved, dropped) = if preserve { (count, 0) } else { (0, count) };
+        Ok(DxfImport { shapes, preserved, dropped })
+    }
+
+    pub fn dxf_passthrough(&self) -> &[RawDxfBlob] {
+        &self.dxf_passthrough
+    }
+}
+
+impl DocumentView {
+    pub fn shapes(&self) -> impl Iterator<Item = &Shape> {
+        self.shapes.iter().map(|s| s.as_ref())
+    }
+
+    pub fn shape(&self, id: ShapeId) -> Result<&Shape, CadError> {
+        find(&self.shapes, id)
+    }
+}
+
+#[wasm_bindgen]
+impl Document {
+    #[wasm_bindgen(constructor)]
+    pub fn new_js() -> Document {
+        Document::new()
+    }
+
+    #[wasm_bindgen(js_name = add_shape)]
+    pub fn add_shape_js(&mut self, vertices: &JsValue) -> Result<ShapeId, JsValue> {
+        let polygon: Polygon = crate::polygon_from(vertices)?;
+        Ok(self.add_shape(polygon))
+    }
+
+    pub fn shape_count(&self) -> usize {
+        self.shapes.len()
+    }
+
+    #[wasm_bindgen(js_name = get_shape)]
+    pub fn get_shape_js(&self, id: ShapeId) -> Result<JsValue, JsValue> {
+        let shape = self.shape(id).map_err(js_error)?;
+        crate::to_js(shape)
+    }
+
+    // Geometry with the shape's transform, and any preview, applied
+    pub fn world_geometry(&self, id: ShapeId) -> Result<JsValue, JsValue> {
+        let shape = self.shape(id).map_err(js_error)?;
+        crate::to_js(&display_geometry(shape, self.preview.as_ref()))
+    }
+
+    #[wasm_bindgen(js_name = set_transform)]
+    pub fn set_transform_js(&mut self, id: ShapeId, matrix: &JsValue) -> Result<(), JsValue> {
+        let matrix: Matrix = crate::from_js(matrix)?;
+        self.set_transform(id, matrix).map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = set_pivot)]
+    pub fn set_pivot_js(&mut self, id: ShapeId, x: f64, y: f64) -> Result<(), JsValue> {
+        self.set_pivot(id, Point::new(x, y)).map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = get_pivot_world)]
+    pub fn get_pivot_world_js(&self, id: ShapeId) -> Result<JsValue, JsValue> {
+        let pivot = self.get_pivot_world(id).map_err(js_error)?;
+        crate::to_js(&pivot)
+    }
+
+    #[wasm_bindgen(js_name = rotate_shape)]
+    pub fn rotate_shape_js(&mut self, id: ShapeId, angle: f64) -> Result<(), JsValue> {
+        self.rotate_shape(id, angle).map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = scale_shape)]
+    pub fn scale_shape_js(&mut self, id: ShapeId, sx: f64, sy: f64) -> Result<(), JsValue> {
+        self.scale_shape(id, sx, sy).map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = remove_shape)]
+    pub fn remove_shape_js(&mut self, id: ShapeId) -> Result<(), JsValue> {
+        self.remove_shape(id).map(|_| ()).map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = checkpoint)]
+    pub fn checkpoint_js(&mut self) {
+        self.checkpoint()
+    }
+
+    #[wasm_bindgen(js_name = undo)]
+    pub fn undo_js(&mut self) -> bool {
+        self.undo()
+    }
+
+    #[wasm_bindgen(js_name = redo)]
+    pub fn redo_js(&mut self) -> bool {
+        self.redo()
+    }
+
+    #[wasm_bindgen(js_name = create_group)]
+    pub fn create_group_js(&mut self, ids: Vec<ShapeId>) -> Result<ShapeId, JsValue> {
+        self.create_group(&ids).map_err(js_error)
+    }
+
+    // { id, transform, parent }
+    #[wasm_bindgen(js_name = get_group)]
+    pub fn get_group_js(&self, id: ShapeId) -> Result<JsValue, JsValue> {
+        crate::to_js(self.group(id).map_err(js_error)?)
+    }
+
+    #[wasm_bindgen(js_name = group_members)]
+    pub fn group_members_js(&self, id: ShapeId) -> Result<Vec<ShapeId>, JsValue> {
+        self.group_members(id).map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = remove_group)]
+    pub fn remove_group_js(&mut self, id: ShapeId) -> Result<(), JsValue> {
+        self.remove_group(id).map(|_| ()).map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = flattened)]
+    pub fn flattened_js(&self, id: ShapeId) -> Result<JsValue, JsValue> {
+        crate::to_js(&self.flattened(id).map_err(js_error)?)
+    }
+
+    #[wasm_bindgen(js_name = hit_test_all)]
+    pub fn hit_test_all_js(&self, x: f64, y: f64) -> Vec<ShapeId> {
+        self.hit_test_all(&Point::new(x, y))
+    }
+
+    // { min, max }
+    #[wasm_bindgen(js_name = total_bounds)]
+    pub fn total_bounds_js(&self) -> Result<JsValue, JsValue> {
+        crate::to_js(&self.total_bounds())
+    }
+
+    // Rejects with the structured RegionError, like `make_region`
+    #[wasm_bindgen(js_name = create_region)]
+    pub fn create_region_js(&mut self, entities: &JsValue, tolerance: f64) -> Result<ShapeId, JsValue> {
+        let entities = crate::region::entities_from(entities)?;
+        self.create_region(&entities, tolerance).map_err(|e| crate::to_js(&e).unwrap_or_else(|err| err))
+    }
+
+    #[wasm_bindgen(js_name = copy_shapes)]
+    pub fn copy_shapes_js(&self, ids: Vec<ShapeId>) -> Result<JsValue, JsValue> {
+        let shapes = self.copy_shapes(&ids).map_err(js_error)?;
+        crate::to_js(&shapes)
+    }
+
+    #[wasm_bindgen(js_name = paste_shapes)]
+    pub fn paste_shapes_js(&mut self, clipboard: &JsValue) -> Result<Vec<ShapeId>, JsValue> {
+        let shapes: Vec<Shape> = crate::from_js(clipboard)?;
+        Ok(self.paste_shapes(&shapes))
+    }
+
+    #[wasm_bindgen(js_name = snapshot_view)]
+    pub fn snapshot_view_js(&self) -> DocumentView {
+        self.snapshot_view()
+    }
+
+    pub fn tessellate(&self) -> Vec<f32> {
+        tessellate(&self.shapes, self.preview.as_ref())
+    }
+
+    #[wasm_bindgen(js_name = bboxes)]
+    pub fn bboxes_js(&self, ids: Option<Vec<ShapeId>>) -> Result<Vec<f64>, JsValue> {
+        self.bboxes(ids.as_deref()).map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = cull)]
+    pub fn cull_js(&self, min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> Vec<ShapeId> {
+        self.cull(&BoundingBox::new(Point::new(min_x, min_y), Point::new(max_x, max_y)))
+    }
+
+    // Bounding-box pre-pick: shapes whose world box contains the point
+    pub fn bbox_hit(&self, x: f64, y: f64) -> Vec<ShapeId> {
+        let p = Point::new(x, y);
+        let hits = self.spatial.borrow_mut().refreshed(&self.shapes).query_point(&p);
+        self.with_preview_hits(hits, &BoundingBox::new(p, p))
+    }
+
+    #[wasm_bindgen(js_name = begin_preview)]
+    pub fn begin_preview_js(&mut self, ids: Vec<ShapeId>) -> Result<(), JsValue> {
+        self.begin_preview(&ids).map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = set_preview_transform)]
+    pub fn set_preview_transform_js(&mut self, matrix: &JsValue) -> Result<(), JsValue> {
+        let matrix: Matrix = crate::from_js(matrix)?;
+        self.set_preview_transform(matrix).map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = is_previewing)]
+    pub fn is_previewing_js(&self) -> bool {
+        self.is_previewing()
+    }
+
+    #[wasm_bindgen(js_name = commit_preview)]
+    pub fn commit_preview_js(&mut self) -> Result<Vec<ShapeId>, JsValue> {
+        self.commit_preview().map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = cancel_preview)]
+    pub fn cancel_preview_js(&mut self) {
+        self.cancel_preview()
+    }
+
+    #[wasm_bindgen(js_name = add_dimension)]
+    pub fn add_dimension_js(&mut self, shape: ShapeId, kind: &JsValue, from_x: f64, from_y: f64, to_x: f64, to_y: f64) -> Result<DimensionId, JsValue> {
+        let kind: DimensionKind = crate::from_js(kind)?;
+        self.add_dimension(shape, kind, Point::new(from_x, from_y), Point::new(to_x, to_y)).map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = get_dimension)]
+    pub fn get_dimension_js(&self, id: DimensionId) -> Result<JsValue, JsValue> {
+        let dimension = self.dimension(id).map_err(js_error)?;
+        crate::to_js(dimension)
+    }
+
+    #[wasm_bindgen(js_name = dimension_value)]
+    pub fn dimension_value_js(&self, id: DimensionId) -> Result<f64, JsValue> {
+        self.dimension_value(id).map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = set_dimension_attribute)]
+    pub fn set_dimension_attribute_js(&mut self, id: DimensionId, key: &str, value: &JsValue) -> Result<(), JsValue> {
+        let value: AttributeValue = crate::from_js(value)?;
+        self.dimension_mut(id).map_err(js_error)?.attributes.insert(key.to_string(), value);
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = remove_dimension)]
+    pub fn remove_dimension_js(&mut self, id: DimensionId) -> Result<(), JsValue> {
+        match self.remove_dimension(id) {
+            Err(CadError::UnknownDimension(_)) => self.remove_attached_dimension(id).map_err(js_error),
+            removed => removed.map(|_| ()).map_err(js_error),
+        }
+    }
+
+    // `view` is { state: { center, zoom }, rotation, layers: { name: visible } },
+    // rotation and layers optional
+    #[wasm_bindgen(js_name = save_view)]
+    pub fn save_view_js(&mut self, name: &str, view: &JsValue) -> Result<(), JsValue> {
+        let view: NamedView = crate::from_js(view)?;
+        self.save_view(name, view);
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = list_views)]
+    pub fn list_views_js(&self) -> Vec<String> {
+        self.list_views()
+    }
+
+    #[wasm_bindgen(js_name = get_view)]
+    pub fn get_view_js(&self, name: &str) -> Result<JsValue, JsValue> {
+        crate::to_js(self.get_view(name).map_err(js_error)?)
+    }
+
+    #[wasm_bindgen(js_name = delete_view)]
+    pub fn delete_view_js(&mut self, name: &str) -> Result<(), JsValue> {
+        self.delete_view(name).map(|_| ()).map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = rename_view)]
+    pub fn rename_view_js(&mut self, from: &str, to: &str) -> Result<(), JsValue> {
+        self.rename_view(from, to).map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = sweep_shapes)]
+    pub fn sweep_shapes_js(&mut self, ids: Vec<ShapeId>, dx: f64, dy: f64, distance: f64) -> Result<Vec<ShapeId>, JsValue> {
+        self.sweep_shapes(&ids, &Vector::new(dx, dy), distance).map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = offset_shape)]
+    pub fn offset_shape_js(&mut self, id: ShapeId, distance: f64, quality: usize) -> Result<(), JsValue> {
+        self.offset_shape(id, distance, quality).map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = polar_array)]
+    pub fn polar_array_js(&mut self, id: ShapeId, center_x: f64, center_y: f64, count: usize, sweep: f64) -> Result<Vec<ShapeId>, JsValue> {
+        self.polar_array(id, Point::new(center_x, center_y), count, sweep).map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = set_attribute)]
+    pub fn set_attribute_js(&mut self, id: ShapeId, key: &str, value: &JsValue) -> Result<(), JsValue> {
+        let value: AttributeValue = crate::from_js(value)?;
+        self.set_attribute(id, key, value).map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = remove_attribute)]
+    pub fn remove_attribute_js(&mut self, id: ShapeId, key: &str) -> Result<(), JsValue> {
+        self.remove_attribute(id, key).map(|_| ()).map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = set_label)]
+    pub fn set_label_js(&mut self, id: ShapeId, label: Option<String>) -> Result<(), JsValue> {
+        self.set_label(id, label).map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = set_layer)]
+    pub fn set_layer_js(&mut self, id: ShapeId, layer: Option<String>) -> Result<(), JsValue> {
+        self.set_layer(id, layer).map_err(js_error)
+    }
+
+    // `matrix` may be null to clear the layer's transform
+    #[wasm_bindgen(js_name = set_layer_transform)]
+    pub fn set_layer_transform_js(&mut self, layer: &str, matrix: &JsValue) -> Result<(), JsValue> {
+        let matrix: Option<Matrix> = crate::from_js(matrix)?;
+        self.set_layer_transform(layer, matrix).map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = get_layer_transform)]
+    pub fn get_layer_transform_js(&self, layer: &str) -> Result<JsValue, JsValue> {
+        crate::to_js(&self.layer_transform(Some(layer)))
+    }
+
+    #[wasm_bindgen(js_name = bake_layer_transform)]
+    pub fn bake_layer_transform_js(&mut self, layer: &str) -> Result<(), JsValue> {
+        self.bake_layer_transform(layer).map_err(js_error)
+    }
+
+    // Array of {x, y}, stored unless `world`
+    #[wasm_bindgen(js_name = get_vertices)]
+    pub fn get_vertices_js(&self, id: ShapeId, world: bool) -> Result<JsValue, JsValue> {
+        crate::to_js(&self.vertices(id, world).map_err(js_error)?)
+    }
+
+    #[wasm_bindgen(js_name = search)]
+    pub fn search_js(&self, query: &JsValue) -> Result<Vec<ShapeId>, JsValue> {
+        let query: SearchQuery = crate::from_js(query)?;
+        Ok(self.search(&query))
+    }
+
+    #[wasm_bindgen(js_name = content_hash)]
+    pub fn content_hash_js(&self, id: ShapeId) -> Result<u64, JsValue> {
+        self.content_hash(id).map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = content_hash_all)]
+    pub fn content_hash_all_js(&self) -> u64 {
+        self.content_hash_all()
+    }
+
+    #[wasm_bindgen(js_name = export)]
+    pub fn export_js(&self, format: &str, precision: Option<usize>) -> Result<String, JsValue> {
+        self.export(format, precision).map_err(js_error)
+    }
+
+    // `profile` is { include_layers, exclude_layers, layer_names: { layer:
+    // name }, layer_colors: { name: "#rrggbb" }, shapes: [ids], precision },
+    // any of them left out
+    #[wasm_bindgen(js_name = export_with)]
+    pub fn export_with_js(&self, format: &str, profile: &JsValue) -> Result<String, JsValue> {
+        let profile: ExportProfile = crate::options_from(profile)?;
+        self.export_with(format, &profile).map_err(js_error)
+    }
+
+    // `profile` as for `export_with`; returns { text, adjusted_vertices }
+    #[wasm_bindgen(js_name = export_report)]
+    pub fn export_report_js(&self, format: &str, profile: &JsValue) -> Result<JsValue, JsValue> {
+        let profile: ExportProfile = crate::options_from(profile)?;
+        crate::to_js(&self.export_report(format, &profile).map_err(js_error)?)
+    }
+
+    #[wasm_bindgen(js_name = export_fingerprint)]
+    pub fn export_fingerprint_js(&self, format: &str, precision: Option<usize>) -> Result<u64, JsValue> {
+        self.export_fingerprint(format, precision).map_err(js_error)
+    }
+
+    // Array of { id, hash } with hashes as BigInt (null for removed shapes)
+    #[wasm_bindgen(js_name = take_changes)]
+    pub fn take_changes_js(&mut self) -> Result<JsValue, JsValue> {
+        let serializer = serde_wasm_bindgen::Serializer::new().serialize_large_number_types_as_bigints(true);
+        self.take_changes().serialize(&serializer).map_err(|e| js_error(CadError::Serialization(e.to_string())))
+    }
+
+    // { changes, dirty: [{ min, max }] }, hashes as in `take_changes`
+    #[wasm_bindgen(js_name = take_change_set)]
+    pub fn take_change_set_js(&mut self) -> Result<JsValue, JsValue> {
+        let serializer = serde_wasm_bindgen::Serializer::new().serialize_large_number_types_as_bigints(true);
+        self.take_change_set().serialize(&serializer).map_err(|e| js_error(CadError::Serialization(e.to_string())))
+    }
+
+    #[wasm_bindgen(js_name = set_dirty_rect_limit)]
+    pub fn set_dirty_rect_limit_js(&mut self, limit: usize) {
+        self.set_dirty_rect_limit(limit)
+    }
+
+    // `rects` packed as [min_x, min_y, max_x, max_y] per rectangle
+    #[wasm_bindgen(js_name = shapes_intersecting)]
+    pub fn shapes_intersecting_js(&self, rects: Vec<f64>) -> Vec<ShapeId> {
+        let rects: Vec<BoundingBox> = rects.chunks_exact(4)
+            .map(|r| BoundingBox::new(Point::new(r[0], r[1]), Point::new(r[2], r[3])))
+            .collect();
+        self.shapes_intersecting(&rects)
+    }
+
+    #[wasm_bindgen(js_name = set_parameter)]
+    pub fn set_parameter_js(&mut self, name: &str, expression: &str) -> Result<(), JsValue> {
+        self.set_parameter(name, expression).map_err(js_error)
+    }
+
+    // Map of parameter name to value
+    pub fn parameter_values(&self) -> Result<JsValue, JsValue> {
+        crate::to_js(&self.parameters.evaluate().map_err(js_error)?)
+    }
+
+    #[wasm_bindgen(js_name = add_generated)]
+    pub fn add_generated_js(&mut self, call: &str) -> Result<ShapeId, JsValue> {
+        self.add_generated(call).map_err(js_error)
+    }
+
+    // { regenerated: [ids], detached: [ids] }
+    #[wasm_bindgen(js_name = regenerate)]
+    pub fn regenerate_js(&mut self) -> Result<JsValue, JsValue> {
+        crate::to_js(&self.regenerate().map_err(js_error)?)
+    }
+
+    // { shapes: [ids], groups: [[id, svg id or null]], warnings: [{ position, element, message }] }
+    #[wasm_bindgen(js_name = import_svg)]
+    pub fn import_svg_js(&mut self, text: &str) -> Result<JsValue, JsValue> {
+        crate::to_js(&self.import_svg(text).map_err(js_error)?)
+    }
+
+    // { shapes: [ids], preserved, dropped }
+    #[wasm_bindgen(js_name = import_dxf)]
+    pub fn import_dxf_js(&mut self, text: &str, preserve: bool) -> Result<JsValue, JsValue> {
+        crate::to_js(&self.import_dxf(text, preserve).map_err(js_error)?)
+    }
+
+    pub fn serialize(&self) -> Result<JsValue, JsValue> {
+        crate::to_js(self)
+    }
+
+    pub fn deserialize(value: &JsValue) -> Result<Document, JsValue> {
+        let mut doc: Document = crate::from_js(value)?;
+        doc.recount();
+        Ok(doc)
+    }
+
+    #[wasm_bindgen(js_name = shrink_caches)]
+    pub fn shrink_caches_js(&mut self, target_bytes: usize) -> usize {
+        self.shrink_caches(target_bytes)
+    }
+
+    #[wasm_bindgen(js_name = memory_usage)]
+    pub fn memory_usage_js(&self) -> usize {
+        self.memory_usage()
+    }
+}
+
+// Read-only counterpart of the Document bindings. Call free() from JS once a
+// frame's buffers are built to release the shapes it keeps alive.
+#[wasm_bindgen]
+impl DocumentView {
+    pub fn shape_count(&self) -> usize {
+        self.shapes.len()
+    }
+
+    #[wasm_bindgen(js_name = get_shape)]
+    pub fn get_shape_js(&self, id: ShapeId) -> Result<JsValue, JsValue> {
+        let shape = self.shape(id).map_err(js_error)?;
+        crate::to_js(shape)
+    }
+
+    pub fn world_geometry(&self, id: ShapeId) -> Result<JsValue, JsValue> {
+        let shape = self.shape(id).map_err(js_error)?;
+        crate::to_js(&shape.world_geometry())
+    }
+
+    pub fn area(&self, id: ShapeId) -> Result<f64, JsValue> {
+        self.shape(id).map_err(js_error)?.world_geometry().try_area().map_err(js_error)
+    }
+
+    pub fn tessellate(&self) -> Vec<f32> {
+        tessellate(&self.shapes, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AttributeValue;
+
+    fn unit_square() -> Polygon {
+        Polygon::new(vec![Point::new(0.0, 0.0), Point::new(1.0, 0.0), Point::new(1.0, 1.0), Point::new(0.0, 1.0)])
+    }
+
+    #[test]
+    fn test_rotation_about_corner_pivot() {
+        let mut doc = Document::new();
+        let id = doc.add_shape(unit_square());
+        doc.set_transform(id, Matrix::translate(5.0, 3.0)).unwrap();
+        doc.set_pivot(id, Point::new(1.0, 1.0)).unwrap();
+
+        let before = doc.shape(id).unwrap().world_geometry().vertices[2];
+        doc.rotate_shape(id, std::f64::consts::FRAC_PI_2).unwrap();
+        let after = doc.shape(id).unwrap().world_geometry().vertices[2];
+
+        assert!((after.x - before.x).abs() < 1e-12 && (after.y - before.y).abs() < 1e-12);
+        let pivot = doc.get_pivot_world(id).unwrap();
+        assert!((pivot.x - 6.0).abs() < 1e-12 && (pivot.y - 4.0).abs() < 1e-12);
+
+        // The opposite corner swings a quarter turn around the pivot
+        let origin = doc.shape(id).unwrap().world_geometry().vertices[0];
+        assert!((origin.x - 7.0).abs() < 1e-12 && (origin.y - 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_group_rotates_about_its_pivot() {
+        let mut doc = Document::new();
+        let a = doc.add_shape(unit_square());
+        let b = doc.add_shape(unit_square());
+        doc.set_transform(b, Matrix::translate(2.0, 0.0)).unwrap();
+        let pair = doc.create_group(&[a, b]).unwrap();
+        let outer = doc.create_group(&[pair]).unwrap();
+        doc.set_transform(outer, Matrix::translate(10.0, 0.0)).unwrap();
+
+        // By default about the centre of the box round both squares
+        let centre = doc.get_pivot_world(pair).unwrap();
+        assert!((centre.x - 11.5).abs() < 1e-12 && (centre.y - 0.5).abs() < 1e-12);
+        let before = doc.shape(a).unwrap().world_geometry().bounding_box().union(&doc.shape(b).unwrap().world_geometry().bounding_box());
+        doc.rotate_shape(pair, std::f64::consts::PI).unwrap();
+        let after = doc.shape(a).unwrap().world_geometry().bounding_box().union(&doc.shape(b).unwrap().world_geometry().bounding_box());
+        assert!((after.min.x - before.min.x).abs() < 1e-12 && (after.max.y - before.max.y).abs() < 1e-12);
+        assert!((doc.shape(a).unwrap().world_geometry().bounding_box().min.x - 12.0).abs() < 1e-12);
+        doc.rotate_shape(pair, std::f64::consts::PI).unwrap();
+
+        // About the group's own bottom-left corner, which stays put
+        doc.set_pivot(pair, Point::new(0.0, 0.0)).unwrap();
+        doc.rotate_shape(pair, std::f64::consts::FRAC_PI_2).unwrap();
+        let pivot = doc.get_pivot_world(pair).unwrap();
+        assert!((pivot.x - 10.0).abs() < 1e-12 && pivot.y.abs() < 1e-12);
+        let far = doc.shape(b).unwrap().world_geometry().bounding_box();
+        assert!((far.min.x - 9.0).abs() < 1e-12 && (far.max.x - 10.0).abs() < 1e-12);
+        assert!((far.min.y - 2.0).abs() < 1e-12 && (far.max.y - 3.0).abs() < 1e-12);
+        assert_eq!(doc.group(outer).unwrap().transform, Matrix::translate(10.0, 0.0));
+
+        doc.scale_shape(pair, 2.0, 2.0).unwrap();
+        let far = doc.shape(b).unwrap().world_geometry().bounding_box();
+        assert!((far.min.x - 8.0).abs() < 1e-12 && (far.max.y - 6.0).abs() < 1e-12);
+        assert!(matches!(doc.rotate_shape(999, 1.0), Err(CadError::UnknownShape(999))));
+    }
+
+    #[test]
+    fn test_default_pivot_is_centroid_and_survives_paste() {
+        let mut doc = Document::new();
+        let id = doc.add_shape(unit_square());
+        doc.scale_shape(id, 3.0, 3.0).unwrap();
+        let world = doc.shape(id).unwrap().world_geometry().bounding_box();
+        assert!((world.min.x + 1.0).abs() < 1e-12 && (world.max.x - 2.0).abs() < 1e-12);
+
+        doc.set_pivot(id, Point::new(0.0, 0.0)).unwrap();
+        let clipboard = doc.copy_shapes(&[id]).unwrap();
+        let pasted = doc.paste_shapes(&clipboard);
+        assert_eq!(pasted.len(), 1);
+        assert_ne!(pasted[0], id);
+        assert_eq!(doc.shape(pasted[0]).unwrap().pivot, Some(Point::new(0.0, 0.0)));
+
+        assert_eq!(doc.rotate_shape(99, 1.0), Err(CadError::UnknownShape(99)));
+    }
+
+    #[test]
+    fn test_snapshot_is_unaffected_by_later_edits() {
+        let mut doc = Document::new();
+        let ids: Vec<ShapeId> = (0..100).map(|_| doc.add_shape(unit_square())).collect();
+        let view = doc.snapshot_view();
+
+        for &id in &ids[..50] {
+            doc.scale_shape(id, 2.0, 2.0).unwrap();
+        }
+        doc.shape_mut(ids[50]).unwrap().geometry.vertices[2] = Point::new(5.0, 5.0);
+        doc.remove_shape(ids[99]).unwrap();
+        doc.add_shape(unit_square());
+
+        assert_eq!(view.shapes().count(), 100);
+        for &id in &ids {
+            let shape = view.shape(id).unwrap();
+            assert!((shape.world_geometry().area() - 1.0).abs() < 1e-12);
+            assert_eq!(shape.geometry.vertices[2], Point::new(1.0, 1.0));
+        }
+        assert!((doc.shape(ids[0]).unwrap().world_geometry().area() - 4.0).abs() < 1e-12);
+        assert_eq!(doc.shape(ids[50]).unwrap().geometry.vertices[2], Point::new(5.0, 5.0));
+        assert!(doc.shape(ids[99]).is_err());
+
+        // Untouched shapes are still shared rather than copied
+        assert!(Arc::ptr_eq(&doc.shapes[60], &view.shapes[60]));
+        assert_eq!(view.tessellate().len(), 100 * 2 * 6);
+    }
+
+    #[test]
+    fn test_content_hash_ignores_no_op_edits() {
+        let mut doc = Document::new();
+        let id = doc.add_shape(unit_square());
+        doc.set_attribute(id, "part", AttributeValue::Text("A".to_string())).unwrap();
+        doc.set_attribute(id, "qty", AttributeValue::Number(2.0)).unwrap();
+        let original = doc.content_hash(id).unwrap();
+        let all = doc.content_hash_all();
+        assert_eq!(doc.take_changes(), vec![ShapeChange { id, hash: Some(original) }]);
+
+        doc.shape_mut(id).unwrap().geometry.vertices[1] = Point::new(3.0, 0.0);
+        let moved = doc.content_hash(id).unwrap();
+        assert_ne!(moved, original);
+        doc.shape_mut(id).unwrap().geometry.vertices[1] = Point::new(1.0, 0.0);
+        doc.set_transform(id, Matrix::identity()).unwrap();
+        assert_eq!(doc.content_hash(id).unwrap(), original);
+        assert_eq!(doc.content_hash_all(), all);
+        assert_eq!(doc.take_changes(), vec![]);
+
+        // Attribute insertion order doesn't matter
+        let other = doc.add_shape(unit_square());
+        doc.set_attribute(other, "qty", AttributeValue::Number(2.0)).unwrap();
+        doc.set_attribute(other, "part", AttributeValue::Text("A".to_string())).unwrap();
+        assert_eq!(doc.content_hash(other).unwrap(), original);
+
+        doc.set_label(id, Some("x".to_string())).unwrap();
+        doc.remove_shape(other).unwrap();
+        let changes = doc.take_changes();
+        assert_eq!(changes, vec![ShapeChange { id, hash: Some(doc.content_hash(id).unwrap()) }]);
+        assert_ne!(changes[0].hash, Some(original));
+    }
+
+    #[test]
+    fn test_change_set_dirties_old_and_new_boxes() {
+        let mut doc = Document::new();
+        let id = doc.add_shape(unit_square());
+        let other = doc.add_shape(unit_square());
+        doc.set_transform(other, Matrix::translate(50.0, 0.0)).unwrap();
+        let added = doc.take_change_set();
+        assert_eq!(added.changes.len(), 2);
+        assert_eq!(added.dirty.len(), 2);
+
+        let at = |x: f64, y: f64| BoundingBox::new(Point::new(x, y), Point::new(x + 1.0, y + 1.0));
+        doc.checkpoint();
+        doc.set_transform(id, Matrix::translate(20.0, 0.0)).unwrap();
+        let far = doc.take_change_set();
+        assert_eq!(far.dirty, vec![at(0.0, 0.0), at(20.0, 0.0)]);
+        assert_eq!(doc.shapes_intersecting(&far.dirty), vec![id]);
+
+        // Undoing the move dirties the same two places
+        assert!(doc.undo());
+        let mut back = doc.take_change_set().dirty;
+        back.sort_by(|a, b| a.min.x.total_cmp(&b.min.x));
+        assert_eq!(back, far.dirty);
+        assert_eq!(doc.shape(id).unwrap().transform, Matrix::identity());
+
+        // A nudge overlaps where the shape was, so one box covers both
+        doc.set_transform(id, Matrix::translate(0.5, 0.0)).unwrap();
+        let nudge = doc.take_change_set();
+        assert_eq!(nudge.dirty, vec![BoundingBox::new(Point::new(0.0, 0.0), Point::new(1.5, 1.0))]);
+        assert_eq!(doc.take_change_set(), ChangeSet::default());
+    }
+
+    #[test]
+    fn test_dirty_rects_collapse_past_limit() {
+        let mut doc = Document::new();
+        let ids: Vec<ShapeId> = (0..5).map(|i| {
+            let id = doc.add_shape(unit_square());
+            doc.set_transform(id, Matrix::translate(i as f64 * 10.0, 0.0)).unwrap();
+            id
+        }).collect();
+        assert_eq!(doc.take_change_set().dirty.len(), 5);
+
+        doc.set_dirty_rect_limit(3);
+        for &id in &ids {
+            doc.set_label(id, Some("moved".to_string())).unwrap();
+        }
+        let set = doc.take_change_set();
+        assert_eq!(set.changes.len(), 5);
+        assert_eq!(set.dirty, vec![BoundingBox::new(Point::new(0.0, 0.0), Point::new(41.0, 1.0))]);
+        // Removed shapes dirty only where they were
+        doc.remove_shape(ids[4]).unwrap();
+        assert_eq!(doc.take_change_set().dirty, vec![BoundingBox::new(Point::new(40.0, 0.0), Point::new(41.0, 1.0))]);
+        assert_eq!(doc.shapes_intersecting(&set.dirty), ids[..4].to_vec());
+    }
+
+    #[test]
+    fn test_packed_bboxes_follow_id_list() {
+        let mut doc = Document::new();
+        let a = doc.add_shape(unit_square());
+        let b = doc.add_shape(unit_square());
+        doc.set_transform(b, Matrix::translate(10.0, 20.0)).unwrap();
+
+        assert_eq!(doc.bboxes(Some(&[b, a])).unwrap(), vec![10.0, 20.0, 11.0, 21.0, 0.0, 0.0, 1.0, 1.0]);
+        assert_eq!(doc.bboxes(None).unwrap(), vec![0.0, 0.0, 1.0, 1.0, 10.0, 20.0, 11.0, 21.0]);
+        assert_eq!(doc.bboxes(Some(&[a, 42])), Err(CadError::UnknownShape(42)));
+
+        // Lookups by id still land after a gap opens in the ids
+        let c = doc.add_shape(unit_square());
+        doc.remove_shape(b).unwrap();
+        doc.shape_mut(c).unwrap().transform = Matrix::translate(-5.0, 0.0);
+        assert_eq!(doc.bboxes(Some(&[c, a])).unwrap(), vec![-5.0, 0.0, -4.0, 1.0, 0.0, 0.0, 1.0, 1.0]);
+        assert_eq!(doc.bboxes(Some(&[b])), Err(CadError::UnknownShape(b)));
+    }
+
+    #[test]
+    fn test_cull_matches_brute_force_on_large_document() {
+        let mut doc = Document::new();
+        let mut seed: u64 = 11;
+        let mut next = || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 11) as f64 / (1u64 << 53) as f64
+        };
+        for _ in 0..5000 {
+            let id = doc.add_shape(unit_square());
+            let (x, y, size) = (next() * 1000.0, next() * 1000.0, 0.5 + next() * 10.0);
+            doc.set_transform(id, Matrix::translate(x, y).multiply(&Matrix::scale(size, size))).unwrap();
+        }
+
+        let brute = |doc: &Document, viewport: &BoundingBox| -> Vec<ShapeId> {
+            doc.shapes().filter(|s| s.world_bounding_box().intersects(viewport)).map(|s| s.id).collect()
+        };
+        let viewports = [
+            BoundingBox::new(Point::new(100.0, 100.0), Point::new(300.0, 250.0)),
+            BoundingBox::new(Point::new(-50.0, -50.0), Point::new(20.0, 20.0)),
+            BoundingBox::new(Point::new(0.0, 0.0), Point::new(1100.0, 1100.0)),
+        ];
+        for viewport in &viewports {
+            assert_eq!(doc.cull(viewport), brute(&doc, viewport));
+        }
+
+        // Edits after the first query are picked up incrementally
+        for id in (0..5000).step_by(3) {
+            doc.rotate_shape(id, 0.7).unwrap();
+        }
+        doc.remove_shape(4).unwrap();
+        let far = doc.add_shape(unit_square());
+        doc.set_transform(far, Matrix::translate(5000.0, 5000.0)).unwrap();
+        for viewport in &viewports {
+            assert_eq!(doc.cull(viewport), brute(&doc, viewport));
+        }
+        assert_eq!(doc.bbox_hit(5000.5, 5000.5), vec![far]);
+    }
+
+    #[test]
+    fn test_preview_leaves_stored_shapes_alone_until_commit() {
+        let mut doc = Document::new();
+        let ids: Vec<ShapeId> = (0..3).map(|_| doc.add_shape(unit_square())).collect();
+        doc.take_changes();
+        let hashes: Vec<u64> = ids.iter().map(|&id| doc.content_hash(id).unwrap()).collect();
+        assert_eq!(doc.bbox_hit(0.5, 0.5), ids);
+
+        doc.begin_preview(&ids[..2]).unwrap();
+        doc.set_preview_transform(Matrix::translate(10.0, 0.0)).unwrap();
+        assert_eq!(doc.bboxes(Some(&ids[..2])).unwrap(), vec![10.0, 0.0, 11.0, 1.0, 10.0, 0.0, 11.0, 1.0]);
+        assert_eq!(doc.bbox_hit(0.5, 0.5), vec![ids[2]]);
+        assert_eq!(doc.bbox_hit(10.5, 0.5), vec![ids[0], ids[1]]);
+        assert_eq!(doc.tessellate()[0], 10.0);
+        let stored: Vec<u64> = ids.iter().map(|&id| doc.content_hash(id).unwrap()).collect();
+        assert_eq!(stored, hashes);
+        assert_eq!(doc.take_changes(), vec![]);
+
+        // The last preview transform wins; nothing from earlier frames stacks up
+        doc.set_preview_transform(Matrix::translate(5.0, 5.0)).unwrap();
+        assert_eq!(doc.commit_preview().unwrap(), vec![ids[0], ids[1]]);
+        assert!(!doc.is_previewing());
+        let changes = doc.take_changes();
+        assert_eq!(changes.iter().map(|c| c.id).collect::<Vec<_>>(), vec![ids[0], ids[1]]);
+        assert_eq!(doc.bbox_hit(5.5, 5.5), vec![ids[0], ids[1]]);
+
+        doc.begin_preview(&[ids[2]]).unwrap();
+        doc.set_preview_transform(Matrix::scale(3.0, 3.0)).unwrap();
+        doc.cancel_preview();
+        assert_eq!(doc.bboxes(Some(&[ids[2]])).unwrap(), vec![0.0, 0.0, 1.0, 1.0]);
+        assert!(doc.set_preview_transform(Matrix::identity()).is_err());
+        assert!(doc.begin_preview(&[99]).is_err());
+    }
+
+    #[test]
+    fn test_one_undo_reverts_a_committed_preview() {
+        let mut doc = Document::new();
+        let ids: Vec<ShapeId> = (0..3).map(|_| doc.add_shape(unit_square())).collect();
+        doc.set_transform(ids[1], Matrix::rotate(0.5)).unwrap();
+        doc.begin_preview(&ids).unwrap();
+        doc.set_preview_transform(Matrix::translate(4.0, 0.0)).unwrap();
+        doc.set_preview_transform(Matrix::translate(7.0, 2.0)).unwrap();
+        doc.commit_preview().unwrap();
+        assert_eq!(doc.bbox_hit(7.5, 2.5), ids);
+
+        assert!(doc.undo());
+        assert_eq!(doc.shape(ids[0]).unwrap().transform, Matrix::identity());
+        assert_eq!(doc.shape(ids[1]).unwrap().transform, Matrix::rotate(0.5));
+        assert_eq!(doc.bbox_hit(0.5, 0.5), ids);
+        assert!(doc.bbox_hit(7.5, 2.5).is_empty());
+        assert!(!doc.undo());
+    }
+
+    #[test]
+    fn test_regenerate_follows_parameters() {
+        let mut doc = Document::new();
+        doc.set_parameter("flange", "5").unwrap();
+        doc.set_parameter("width", "2 * flange + 10").unwrap();
+        let id = doc.add_generated("create_rectangle(width, 15)").unwrap();
+        let dim = doc.add_dimension(id, DimensionKind::OverallWidth, Point::new(0.0, 0.0), Point::new(20.0, 0.0)).unwrap();
+        doc.set_transform(id, Matrix::translate(3.0, 0.0)).unwrap();
+
+        doc.set_parameter("flange", "8").unwrap();
+        let report = doc.regenerate().unwrap();
+        assert_eq!(report, RegenerateReport { regenerated: vec![id], detached: vec![] });
+        assert_eq!(doc.shape(id).unwrap().world_bounding_box().width(), 26.0);
+        assert_eq!(doc.dimension_value(dim).unwrap(), 26.0);
+
+        // A hand edit detaches the shape instead of being overwritten
+        doc.shape_mut(id).unwrap().geometry.vertices[2].y = 40.0;
+        doc.set_parameter("flange", "1").unwrap();
+        assert_eq!(doc.regenerate().unwrap().detached, vec![id]);
+        assert_eq!(doc.shape(id).unwrap().geometry.vertices[1].x, 26.0);
+        assert_eq!(doc.regenerate().unwrap(), RegenerateReport::default());
+
+        doc.set_parameter("flange", "width / 2").unwrap();
+        assert!(matches!(doc.regenerate(), Err(CadError::InvalidInput(m)) if m.ends_with("flange -> width -> flange")));
+    }
+
+    #[test]
+    fn test_regenerate_and_bake_undo_and_redo_in_one_step() {
+        let mut doc = Document::new();
+        doc.set_parameter("width", "10").unwrap();
+        let id = doc.add_generated("create_rectangle(width, 5)").unwrap();
+        let dim = doc.add_dimension(id, DimensionKind::OverallWidth, Point::new(0.0, 0.0), Point::new(10.0, 0.0)).unwrap();
+        doc.set_layer(id, Some("plan".to_string())).unwrap();
+        doc.set_layer_transform("plan", Some(Matrix::translate(0.0, 4.0))).unwrap();
+
+        doc.set_parameter("width", "30").unwrap();
+        doc.regenerate().unwrap();
+        doc.bake_layer_transform("plan").unwrap();
+        assert_eq!(doc.shape(id).unwrap().world_bounding_box().min.y, 4.0);
+
+        assert!(doc.undo());
+        assert_eq!(doc.layer_transform(Some("plan")), Matrix::translate(0.0, 4.0));
+        assert_eq!(doc.shape(id).unwrap().transform, Matrix::identity());
+        assert!(doc.undo());
+        assert_eq!(doc.shape(id).unwrap().geometry.vertices[1].x, 10.0);
+        assert_eq!(doc.dimension_value(dim).unwrap(), 10.0);
+        assert!(!doc.undo());
+
+        assert!(doc.redo() && doc.redo());
+        assert_eq!(doc.dimension_value(dim).unwrap(), 30.0);
+        assert_eq!(doc.layer_transform(Some("plan")), Matrix::identity());
+        assert!(!doc.redo());
+        // A new edit after an undo drops what was undone
+        assert!(doc.undo());
+        doc.rotate_shape(id, 1.0).unwrap();
+        doc.checkpoint();
+        assert!(!doc.redo());
+    }
+
+    #[test]
+    fn test_layer_transform_places_shapes_without_rewriting_them() {
+        let mut doc = Document::new();
+        let local = vec![Point::new(0.0, 0.0), Point::new(2.0, 0.0), Point::new(2.0, 1.0), Point::new(0.0, 1.0)];
+        let survey = doc.add_shape(Polygon::new(local.clone()));
+        doc.set_layer(survey, Some("survey".to_string())).unwrap();
+        doc.set_transform(survey, Matrix::translate(1.0, 0.0)).unwrap();
+        let base = doc.add_shape(Polygon::new(local.clone()));
+        doc.take_changes();
+
+        // A quarter turn about the origin carries the block from [1, 3] x [0, 1]
+        // to [-1, 0] x [1, 3]
+        doc.set_layer_transform("survey", Some(Matrix::rotate(std::f64::consts::FRAC_PI_2))).unwrap();
+        let b = doc.bboxes(Some(&[survey])).unwrap();
+        let expected = [-1.0, 1.0, 0.0, 3.0];
+        assert!(b.iter().zip(expected).all(|(a, e)| (a - e).abs() < 1e-12), "{:?}", b);
+        assert_eq!(doc.bbox_hit(-0.5, 2.0), vec![survey]);
+        assert_eq!(doc.bbox_hit(1.5, 0.5), vec![base]);
+        assert_eq!(doc.take_changes().iter().map(|c| c.id).collect::<Vec<_>>(), vec![survey]);
+        assert_eq!(doc.vertices(survey, false).unwrap(), local);
+        assert_eq!(doc.shape(survey).unwrap().transform, Matrix::translate(1.0, 0.0));
+
+        // Shapes moved onto the layer, or rotated on it, are placed in world space
+        let joined = doc.add_shape(Polygon::new(local.clone()));
+        doc.set_layer(joined, Some("survey".to_string())).unwrap();
+        let world = doc.vertices(joined, true).unwrap();
+        assert!((world[1].x - 0.0).abs() < 1e-12 && (world[1].y - 2.0).abs() < 1e-12);
+        let pivot = doc.get_pivot_world(survey).unwrap();
+        doc.rotate_shape(survey, 1.0).unwrap();
+        let turned = doc.get_pivot_world(survey).unwrap();
+        assert!((turned.x - pivot.x).abs() < 1e-12 && (turned.y - pivot.y).abs() < 1e-12);
+
+        let before: Vec<Vec<Point>> = [survey, joined].iter().map(|&id| doc.vertices(id, true).unwrap()).collect();
+        doc.bake_layer_transform("survey").unwrap();
+        assert_eq!(doc.layer_transform(Some("survey")), Matrix::identity());
+        doc.set_layer_transform("survey", None).unwrap();
+        for (id, before) in [survey, joined].into_iter().zip(before) {
+            let after = doc.vertices(id, true).unwrap();
+            assert!(after.iter().zip(&before).all(|(a, b)| (a.x - b.x).abs() < 1e-12 && (a.y - b.y).abs() < 1e-12));
+            assert_eq!(doc.vertices(id, false).unwrap(), local);
+        }
+        assert!(doc.set_layer_transform("survey", Some(Matrix::scale(0.0, 1.0))).is_err());
+    }
+
+    #[test]
+    fn test_svg_groups_import_as_nested_groups() {
+        let svg = r#"<svg>
+            <g id="plan" transform="translate(10 0)">
+              <g>
+                <rect id="a" width="1" height="1"/>
+                <rect id="b" x="2" width="1" height="1"/>
+              </g>
+              <rect id="c" x="5" width="1" height="1"/>
+              <g id="empty"></g>
+            </g>
+            <rect id="loose" width="1" height="1"/>
+          </svg>"#;
+        let mut doc = Document::new();
+        let import = doc.import_svg(svg).unwrap();
+        let [a, b, c, loose] = import.shapes[..] else { panic!("{:?}", import.shapes) };
+        assert_eq!(import.groups.len(), 2);
+        let (plan, inner) = (import.groups[0].0, import.groups[1].0);
+        assert_eq!((import.groups[0].1.as_deref(), import.groups[1].1.as_deref()), (Some("plan"), None));
+        assert_eq!(doc.group_members(plan).unwrap(), vec![c, inner]);
+        assert_eq!(doc.group_members(inner).unwrap(), vec![a, b]);
+        assert_eq!(doc.shape(loose).unwrap().group, None);
+        assert!(!doc.shape(a).unwrap().attributes.contains_key("svg_group"));
+
+        // Groups place nothing themselves; moving one moves what it holds
+        let before = doc.shape(b).unwrap().world_geometry().vertices;
+        assert_eq!(before[0], Point::new(12.0, 0.0));
+        doc.set_transform(plan, Matrix::translate(0.0, 3.0)).unwrap();
+        assert_eq!(doc.shape(b).unwrap().world_geometry().vertices[0], Point::new(12.0, 3.0));
+    }
+
+    #[test]
+    fn test_group_transforms_compose_and_removal_keeps_placement() {
+        let mut doc = Document::new();
+        let square = |x: f64| Polygon::new(vec![Point::new(x, 0.0), Point::new(x + 1.0, 0.0), Point::new(x + 1.0, 1.0), Point::new(x, 1.0)]);
+        let (a, b, c) = (doc.add_shape(square(0.0)), doc.add_shape(square(2.0)), doc.add_shape(square(10.0)));
+        let inner = doc.create_group(&[a, b]).unwrap();
+        let outer = doc.create_group(&[inner]).unwrap();
+        assert!(doc.create_group(&[inner, c]).is_err());
+        assert_eq!(doc.group_members(outer).unwrap(), vec![inner]);
+        assert_eq!(doc.shape(a).unwrap().group, Some(inner));
+
+        // Inner scales, then outer moves: a's corner (1, 1) goes to (2, 2) and
+        // then to (7, 2)
+        doc.set_transform(inner, Matrix::scale(2.0, 2.0)).unwrap();
+        doc.set_transform(outer, Matrix::translate(5.0, 0.0)).unwrap();
+        assert_eq!(doc.flattened(a).unwrap().vertices[2], Point::new(7.0, 2.0));
+        assert_eq!(doc.vertices(a, false).unwrap(), square(0.0).vertices);
+        assert_eq!(doc.hit_test_all(&Point::new(6.0, 1.0)), vec![a]);
+        assert!(doc.hit_test_all(&Point::new(1.0, 0.5)).is_empty());
+        let bounds = doc.total_bounds();
+        assert_eq!((bounds.min, bounds.max), (Point::new(5.0, 0.0), Point::new(11.0, 2.0)));
+        assert!(doc.set_transform(outer, Matrix::scale(0.0, 1.0)).is_err());
+
+        let before: Vec<Polygon> = [a, b].iter().map(|&id| doc.flattened(id).unwrap()).collect();
+        doc.remove_group(outer).unwrap();
+        assert_eq!(doc.group(inner).unwrap().parent, None);
+        doc.remove_group(inner).unwrap();
+        assert_eq!(doc.shape(b).unwrap().group, None);
+        for (id, before) in [a, b].into_iter().zip(before) {
+            assert_eq!(doc.flattened(id).unwrap().vertices, before.vertices);
+        }
+        // Ids keep counting past removed groups
+        assert!(doc.create_group(&[c]).unwrap() > outer);
+    }
+
+    #[test]
+    fn test_named_views_keep_their_layer_overrides() {
+        use crate::{Angle, ViewportState};
+        let mut doc = Document::new();
+        let mut detail = NamedView::new(ViewportState { center: Point::new(12.5, -3.0), zoom: 40.0 });
+        detail.rotation = Angle::from_degrees(30.0);
+        detail.layers.insert("furniture".to_string(), false);
+        let mut overall = NamedView::new(ViewportState { center: Point::new(0.0, 0.0), zoom: 0.5 });
+        overall.layers.insert("furniture".to_string(), true);
+        overall.layers.insert("grid".to_string(), false);
+        doc.save_view("detail A", detail.clone());
+        doc.save_view("overall plan", overall.clone());
+
+        let reloaded: Document = crate::binary::from_bytes(&crate::binary::to_bytes(&doc).unwrap()).unwrap();
+        assert_eq!(reloaded.list_views(), vec!["detail A", "overall plan"]);
+        assert_eq!(reloaded.get_view("detail A").unwrap(), &detail);
+        assert_eq!(reloaded.get_view("overall plan").unwrap(), &overall);
+
+        assert!(matches!(doc.rename_view("detail A", "overall plan"), Err(CadError::InvalidInput(_))));
+        doc.rename_view("detail A", "detail B").unwrap();
+        assert!(matches!(doc.get_view("detail A"), Err(CadError::UnknownView(_))));
+        assert_eq!(doc.delete_view("detail B").unwrap(), detail);
+        assert_eq!(doc.list_views(), vec!["overall plan"]);
+    }
+}
@@ -0,0 +1,216 @@
+use wasm_bindgen::prelude::*;
+
+use crate::memory::{Live, ObjectKind};
+use crate::segment::SegmentIntersection;
+use crate::{js_error, CadError, Point, Polygon, Segment};
+
+// Feedback for a cursor position that hasn't been clicked yet
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PreviewInfo {
+    // The edge from the last vertex to the cursor would touch the outline
+    // drawn so far somewhere other than at the last vertex
+    pub would_self_intersect: bool,
+    // The lowest such edge, by start vertex
+    pub crossing_edge: Option<usize>,
+    // Area the outline would close around with the cursor added
+    pub live_area: f64,
+    // The cursor is on the first vertex, from where the outline can close
+    // as a simple polygon
+    pub can_close: bool,
+}
+
+// Rubber-band polygon drawing: vertices are clicked one at a time, and each
+// cursor move checks just the edge it would add against the edges already
+// drawn, keeping the outline's shoelace sum as it goes so the live area
+// costs nothing extra
+#[wasm_bindgen]
+pub struct PolygonDrawSession {
+    points: Vec<Point>,
+    // Doubled signed area of the open chain, first vertex to last
+    twice_area: f64,
+    // Per drawn edge, the earlier edge it crossed if any
+    crossings: Vec<Option<usize>>,
+    // How near the first vertex the cursor snaps to it
+    snap_radius: f64,
+    live: Live<{ ObjectKind::DrawSession as usize }>,
+}
+
+fn cross(a: &Point, b: &Point) -> f64 {
+    a.x * b.y - a.y * b.x
+}
+
+impl PolygonDrawSession {
+    pub fn new(snap_radius: f64) -> PolygonDrawSession {
+        PolygonDrawSession { points: Vec::new(), twice_area: 0.0, crossings: Vec::new(), snap_radius: snap_radius.max(0.0), live: Live::with_bytes(std::mem::size_of::<PolygonDrawSession>()) }
+    }
+
+    pub fn points(&self) -> &[Point] {
+        &self.points
+    }
+
+    // No drawn edge crosses another
+    pub fn is_simple(&self) -> bool {
+        self.crossings.iter().all(Option::is_none)
+    }
+
+    // Lowest drawn edge that `edge`, starting at the last vertex, touches
+    // other than at its start; `skip_first` leaves out edge 0 for a closing
+    // edge, which meets it at the first vertex
+    fn first_crossing(&self, edge: &Segment, skip_first: bool) -> Option<usize> {
+        let n = self.points.len();
+        (0..n.saturating_sub(1)).filter(|&i| !(skip_first && i == 0)).find(|&i| {
+            let other = Segment::new(self.points[i], self.points[i + 1]);
+            let shares_end = i + 2 == n || (skip_first && i == 0);
+            match edge.intersect(&other) {
+                SegmentIntersection::None => false,
+                SegmentIntersection::Point(_) => !shares_end,
+                SegmentIntersection::Overlap(_) => true,
+            }
+        })
+    }
+
+    fn closes_at(&self, p: &Point) -> bool {
+        self.points.len() >= 3 && (p.x - self.points[0].x).hypot(p.y - self.points[0].y) <= self.snap_radius
+    }
+
+    pub fn add_vertex(&mut self, p: Point) {
+        let crossing = match self.points.last() {
+            Some(&last) => {
+                self.twice_area += cross(&last, &p);
+                self.first_crossing(&Segment::new(last, p), false)
+            }
+            None => None,
+        };
+        if !self.points.is_empty() {
+            self.crossings.push(crossing);
+        }
+        self.points.push(p);
+        self.live.add_bytes(std::mem::size_of::<Point>() as isize);
+    }
+
+    pub fn preview_vertex(&self, p: Point) -> PreviewInfo {
+        let (Some(&first), Some(&last)) = (self.points.first(), self.points.last()) else {
+            return PreviewInfo { would_self_intersect: false, crossing_edge: None, live_area: 0.0, can_close: false };
+        };
+        let closing = self.closes_at(&p);
+        let p = if closing { first } else { p };
+        let crossing_edge = self.first_crossing(&Segment::new(last, p), closing);
+        let live_area = (self.twice_area + cross(&last, &p) + cross(&p, &first)).abs() / 2.0;
+        PreviewInfo { would_self_intersect: crossing_edge.is_some(), crossing_edge, live_area, can_close: closing && crossing_edge.is_none() && self.is_simple() }
+    }
+
+    pub fn undo_vertex(&mut self) -> bool {
+        let Some(p) = self.points.pop() else {
+            return false;
+        };
+        self.crossings.pop();
+        if let Some(last) = self.points.last() {
+            self.twice_area -= cross(last, &p);
+        }
+        self.live.add_bytes(-(std::mem::size_of::<Point>() as isize));
+        true
+    }
+
+    // Closes the outline back to the first vertex and starts over. An
+    // outline that isn't a valid simple polygon is refused and kept for
+    // more editing.
+    pub fn finish(&mut self) -> Result<Polygon, CadError> {
+        if let Some(i) = self.crossings.iter().position(Option::is_some) {
+            return Err(CadError::InvalidInput(format!("edge {} crosses edge {}", i, self.crossings[i].unwrap())));
+        }
+        let polygon = Polygon::new(self.points.clone());
+        polygon.validate().map_err(CadError::Geometry)?;
+        while self.undo_vertex() {}
+        Ok(polygon)
+    }
+}
+
+#[wasm_bindgen]
+impl PolygonDrawSession {
+    #[wasm_bindgen(constructor)]
+    pub fn new_js(snap_radius: f64) -> PolygonDrawSession {
+        PolygonDrawSession::new(snap_radius)
+    }
+
+    #[wasm_bindgen(js_name = add_vertex)]
+    pub fn add_vertex_js(&mut self, x: f64, y: f64) {
+        self.add_vertex(Point::new(x, y))
+    }
+
+    #[wasm_bindgen(js_name = preview_vertex)]
+    pub fn preview_vertex_js(&self, x: f64, y: f64) -> PreviewInfo {
+        self.preview_vertex(Point::new(x, y))
+    }
+
+    #[wasm_bindgen(js_name = undo_vertex)]
+    pub fn undo_vertex_js(&mut self) -> bool {
+        self.undo_vertex()
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.points.len()
+    }
+
+    #[wasm_bindgen(js_name = is_simple)]
+    pub fn is_simple_js(&self) -> bool {
+        self.is_simple()
+    }
+
+    // Array of {x, y}
+    #[wasm_bindgen(js_name = finish)]
+    pub fn finish_js(&mut self) -> Result<JsValue, JsValue> {
+        crate::to_js(&self.finish().map_err(js_error)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crossing_edge_is_flagged_and_square_finishes() {
+        let mut session = PolygonDrawSession::new(0.01);
+        for (x, y) in [(0.0, 0.0), (4.0, 0.0), (4.0, 4.0)] {
+            session.add_vertex(Point::new(x, y));
+        }
+        // Back down through the bottom edge
+        let crossing = session.preview_vertex(Point::new(2.0, -2.0));
+        assert_eq!((crossing.would_self_intersect, crossing.crossing_edge, crossing.can_close), (true, Some(0), false));
+
+        let ok = session.preview_vertex(Point::new(0.0, 4.0));
+        assert_eq!(ok, PreviewInfo { would_self_intersect: false, crossing_edge: None, live_area: 16.0, can_close: false });
+        session.add_vertex(Point::new(0.0, 4.0));
+
+        // Clicking that crossing anyway is refused at the end, until undone
+        session.add_vertex(Point::new(2.0, -2.0));
+        assert!(!session.is_simple());
+        assert!(session.finish().is_err());
+        assert!(session.undo_vertex());
+        assert!(session.is_simple());
+
+        let close = session.preview_vertex(Point::new(0.004, -0.003));
+        assert!(close.can_close && !close.would_self_intersect);
+        assert_eq!(close.live_area, 16.0);
+        let square = session.finish().unwrap();
+        assert_eq!(square.area(), 16.0);
+        assert_eq!(square.vertices.len(), 4);
+        assert_eq!(session.vertex_count(), 0);
+    }
+
+    #[test]
+    fn test_backtracking_and_closing_through_a_vertex() {
+        let mut session = PolygonDrawSession::new(0.01);
+        for (x, y) in [(0.0, 0.0), (2.0, 2.0), (4.0, 0.0)] {
+            session.add_vertex(Point::new(x, y));
+        }
+        // Doubling back along the last edge overlaps it
+        assert_eq!(session.preview_vertex(Point::new(3.0, 1.0)).crossing_edge, Some(1));
+        session.add_vertex(Point::new(4.0, 4.0));
+        // Closing from (4, 4) would run through (2, 2)
+        let close = session.preview_vertex(Point::new(0.0, 0.0));
+        assert_eq!((close.crossing_edge, close.can_close), (Some(1), false));
+        assert!(session.finish().is_err());
+        assert_eq!(session.vertex_count(), 4);
+    }
+}
@@ -0,0 +1,328 @@
+use std::collections::BTreeSet;
+use std::fmt::{self, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::export::{export_outline, ordered_shapes, vertex_number, ExportProfile, DEFAULT_LAYER};
+use crate::search::AttributeValue;
+use crate::{CadError, Document, Point, Polygon, ShapeId};
+
+// Sections written ahead of ENTITIES, in DXF order; any other preserved
+// section follows it
+const LEADING_SECTIONS: [&str; 4] = ["HEADER", "CLASSES", "TABLES", "BLOCKS"];
+
+// Part of a DXF file the importer didn't turn into shapes, kept as its group
+// code pairs so export can write it back unchanged. An entity is kept on its
+// own with its layer (group 8); anything else is a whole section, without
+// the SECTION and ENDSEC around it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RawDxfBlob {
+    pub section: String,
+    pub layer: Option<String>,
+    pub codes: Vec<(i32, String)>,
+}
+
+// Outcome of `Document::import_dxf`: the shapes added, in file order, and
+// how many blobs were kept for export or dropped
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct DxfImport {
+    pub shapes: Vec<ShapeId>,
+    pub preserved: usize,
+    pub dropped: usize,
+}
+
+// Closed polyline read from a DXF file
+#[derive(Clone, Debug)]
+pub(crate) struct DxfShape {
+    pub(crate) geometry: Polygon,
+    pub(crate) layer: Option<String>,
+    pub(crate) handle: Option<String>,
+}
+
+// Group code and value lines, with the line number of each code. Values are
+// kept as written apart from the line ending.
+fn pairs(text: &str) -> Result<Vec<(usize, i32, String)>, CadError> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let code = lines[i].trim();
+        if code.is_empty() && lines[i..].iter().all(|l| l.trim().is_empty()) {
+            break;
+        }
+        let code = code.parse().map_err(|_| CadError::Parse { position: i + 1, message: format!("expected a group code, found '{}'", code) })?;
+        let Some(value) = lines.get(i + 1) else {
+            return Err(CadError::Parse { position: i + 1, message: "group code without a value".to_string() });
+        };
+        out.push((i + 1, code, value.to_string()));
+        i += 2;
+    }
+    Ok(out)
+}
+
+fn value(codes: &[(i32, String)], code: i32) -> Option<&str> {
+    codes.iter().find(|(c, _)| *c == code).map(|(_, v)| v.trim())
+}
+
+// A closed LWPOLYLINE of straight sides with at least three vertices, the
+// only entity read as a shape
+fn polyline(codes: &[(i32, String)]) -> Option<DxfShape> {
+    if value(codes, 0) != Some("LWPOLYLINE") {
+        return None;
+    }
+    let flags: i32 = value(codes, 70).unwrap_or("0").parse().ok()?;
+    let (mut xs, mut ys) = (Vec::new(), Vec::new());
+    for (code, v) in codes {
+        let v = v.trim();
+        match code {
+            10 => xs.push(v.parse::<f64>().ok()?),
+            20 => ys.push(v.parse::<f64>().ok()?),
+            42 if v.parse::<f64>().ok()? != 0.0 => return None,
+            _ => {}
+        }
+    }
+    if flags & 1 == 0 || xs.len() != ys.len() || xs.len() < 3 {
+        return None;
+    }
+    Some(DxfShape {
+        geometry: Polygon::new(xs.into_iter().zip(ys).map(|(x, y)| Point::new(x, y)).collect()),
+        layer: value(codes, 8).filter(|l| *l != "0").map(str::to_string),
+        handle: value(codes, 5).map(str::to_string),
+    })
+}
+
+// Shapes and everything else in DXF text, in file order
+pub(crate) fn read_dxf(text: &str) -> Result<(Vec<DxfShape>, Vec<RawDxfBlob>), CadError> {
+    let pairs = pairs(text)?;
+    let (mut shapes, mut blobs) = (Vec::new(), Vec::new());
+    let mut i = 0;
+    while i < pairs.len() {
+        let (line, code, ref v) = pairs[i];
+        match (code, v.trim()) {
+            (0, "EOF") => break,
+            (0, "SECTION") => {}
+            _ => return Err(CadError::Parse { position: line, message: format!("expected SECTION, found '{}'", v.trim()) }),
+        }
+        let section = match pairs.get(i + 1) {
+            Some((_, 2, name)) => name.trim().to_string(),
+            _ => return Err(CadError::Parse { position: line, message: "SECTION without a name".to_string() }),
+        };
+        let Some(end) = pairs[i + 2..].iter().position(|(_, c, v)| *c == 0 && v.trim() == "ENDSEC") else {
+            return Err(CadError::Parse { position: line, message: format!("{} section is not closed", section) });
+        };
+        let body: Vec<(i32, String)> = pairs[i + 2..i + 2 + end].iter().map(|(_, c, v)| (*c, v.clone())).collect();
+        i += end + 3;
+        if section != "ENTITIES" {
+            blobs.push(RawDxfBlob { section, layer: None, codes: body });
+            continue;
+        }
+        let starts: Vec<usize> = (0..body.len()).filter(|&k| body[k].0 == 0).collect();
+        for (k, &start) in starts.iter().enumerate() {
+            let entity = &body[start..starts.get(k + 1).copied().unwrap_or(body.len())];
+            match polyline(entity) {
+                Some(shape) => shapes.push(shape),
+                None => blobs.push(RawDxfBlob {
+                    section: section.clone(),
+                    layer: value(entity, 8).map(str::to_string),
+                    codes: entity.to_vec(),
+                }),
+            }
+        }
+    }
+    Ok((shapes, blobs))
+}
+
+fn handle(v: &str) -> Option<u64> {
+    u64::from_str_radix(v.trim(), 16).ok()
+}
+
+fn write_codes(out: &mut impl Write, codes: &[(i32, String)]) -> fmt::Result {
+    for (code, v) in codes {
+        writeln!(out, "{:>3}\n{}", code, v)?;
+    }
+    Ok(())
+}
+
+fn write_section(out: &mut impl Write, name: &str, codes: &[(i32, String)]) -> fmt::Result {
+    writeln!(out, "  0\nSECTION\n  2\n{}", name)?;
+    write_codes(out, codes)?;
+    writeln!(out, "  0\nENDSEC")
+}
+
+// Shapes as closed LWPOLYLINEs in world space, with preserved blobs written
+// back where they came from. A shape keeps the handle in its `dxf_handle`
+// attribute unless something else already has it, else takes the next one
+// above every handle in use and the header's $HANDSEED, which is moved past
+// the last handle given out. Preserved entities on layers the profile leaves
+// out are dropped and the rest renamed as it says. Angular and radial
+// dimensions follow the shapes, drawn out as plain entities.
+pub(crate) fn write_dxf(doc: &Document, profile: &ExportProfile, out: &mut impl Write) -> Result<usize, fmt::Error> {
+    let num = |v: f64| vertex_number(v, profile.precision);
+    let blobs = doc.dxf_passthrough();
+    let seed = blobs.iter().filter(|b| b.section == "HEADER").flat_map(|b| {
+        b.codes.windows(2).filter(|w| w[0] == (9, "$HANDSEED".to_string())).filter_map(|w| handle(&w[1].1))
+    }).max().unwrap_or(1);
+    // In the header, group 5 is the seed rather than a handle
+    let mut used: BTreeSet<u64> = blobs.iter().filter(|b| b.section != "HEADER").flat_map(|b| &b.codes).filter(|(c, _)| *c == 5 || *c == 105).filter_map(|(_, v)| handle(v)).collect();
+    let mut next = seed.max(used.last().map_or(1, |h| h + 1));
+    let groups = ordered_shapes(doc, profile);
+    let mut handles = Vec::new();
+    for shape in groups.iter().flat_map(|(_, shapes)| shapes) {
+        let kept = match shape.attributes.get("dxf_handle") {
+            Some(AttributeValue::Text(v)) => handle(v).filter(|h| *h != 0 && !used.contains(h)),
+            _ => None,
+        };
+        let h = kept.unwrap_or_else(|| {
+            while used.contains(&next) {
+                next += 1;
+            }
+            next
+        });
+        used.insert(h);
+        handles.push(h);
+    }
+    let dimensions = doc.rendered_dimensions(|id| doc.shape(id).is_ok_and(|s| profile.includes(s)));
+    let entities: usize = dimensions.iter().map(|(.., g)| g.lines.len() + g.arrows.len() + 1).sum();
+    let mut fresh = Vec::with_capacity(entities);
+    for _ in 0..entities {
+        while used.contains(&next) {
+            next += 1;
+        }
+        used.insert(next);
+        fresh.push(next);
+    }
+    let seed = next.max(used.last().map_or(1, |h| h + 1));
+
+    let leading = |b: &&RawDxfBlob| LEADING_SECTIONS.contains(&b.section.as_str());
+    for blob in blobs.iter().filter(leading) {
+        let mut codes = blob.codes.clone();
+        if blob.section == "HEADER" {
+            for k in 1..codes.len() {
+                if codes[k - 1] == (9, "$HANDSEED".to_string()) && codes[k].0 == 5 {
+                    codes[k].1 = format!("{:X}", seed);
+                }
+            }
+        }
+        write_section(out, &blob.section, &codes)?;
+    }
+    writeln!(out, "  0\nSECTION\n  2\nENTITIES")?;
+    let kept = |b: &&RawDxfBlob| b.section == "ENTITIES" && profile.shapes.is_none() && profile.includes_layer(b.layer.as_deref());
+    for blob in blobs.iter().filter(kept) {
+        let mut codes = blob.codes.clone();
+        if let Some(name) = profile.layer_name(blob.layer.as_deref()) {
+            codes.iter_mut().filter(|(c, _)| *c == 8).for_each(|(_, v)| *v = name.clone());
+        }
+        write_codes(out, &codes)?;
+    }
+    let shapes = groups.iter().flat_map(|(layer, shapes)| shapes.iter().map(move |s| (layer, s)));
+    let mut adjusted = 0;
+    for ((layer, shape), h) in shapes.zip(handles) {
+        let (vertices, n) = export_outline(shape, profile.precision);
+        adjusted += n;
+        writeln!(out, "  0\nLWPOLYLINE\n  5\n{:X}\n100\nAcDbEntity\n  8\n{}", h, layer.as_deref().unwrap_or(DEFAULT_LAYER))?;
+        if let Some(rgb) = profile.rgb(layer.as_deref()) {
+            writeln!(out, "420\n{}", rgb)?;
+        }
+        writeln!(out, "100\nAcDbPolyline\n 90\n{}\n 70\n1", vertices.len())?;
+        for p in &vertices {
+            writeln!(out, " 10\n{}\n 20\n{}", num(p.x), num(p.y))?;
+        }
+    }
+    // Dimensions as plain geometry on their shape's layer: lines as open
+    // LWPOLYLINEs, arrows as SOLIDs and the text as TEXT as high as the
+    // arrows are long, with DXF's %%c and %%d for Ø and °
+    let mut fresh = fresh.into_iter();
+    for (_, shape, arrow_size, graphic) in &dimensions {
+        let layer = profile.layer_name(doc.shape(*shape).ok().and_then(|s| s.layer.as_deref()));
+        let mut head = |out: &mut dyn Write, kind: &str| -> fmt::Result {
+            writeln!(out, "  0\n{}\n  5\n{:X}\n100\nAcDbEntity\n  8\n{}", kind, fresh.next().unwrap(), layer.as_deref().unwrap_or(DEFAULT_LAYER))?;
+            match profile.rgb(layer.as_deref()) {
+                Some(rgb) => writeln!(out, "420\n{}", rgb),
+                None => Ok(()),
+            }
+        };
+        for line in &graphic.lines {
+            head(out, "LWPOLYLINE")?;
+            writeln!(out, "100\nAcDbPolyline\n 90\n{}\n 70\n0", line.points.len())?;
+            for p in &line.points {
+                writeln!(out, " 10\n{}\n 20\n{}", num(p.x), num(p.y))?;
+            }
+        }
+        for arrow in &graphic.arrows {
+            head(out, "SOLID")?;
+            writeln!(out, "100\nAcDbTrace")?;
+            // A triangle repeats its last corner
+            for (k, p) in arrow.vertices.iter().chain(arrow.vertices.last()).enumerate() {
+                writeln!(out, " 1{}\n{}\n 2{}\n{}\n 3{}\n0", k, num(p.x), k, num(p.y), k)?;
+            }
+        }
+        head(out, "TEXT")?;
+        let at = graphic.text_anchor;
+        writeln!(out, "100\nAcDbText\n 10\n{}\n 20\n{}\n 30\n0\n 40\n{}\n  1\n{}\n100\nAcDbText", num(at.x), num(at.y), num(*arrow_size), graphic.text.replace('Ø', "%%c").replace('°', "%%d"))?;
+    }
+    writeln!(out, "  0\nENDSEC")?;
+    for blob in blobs.iter().filter(|b| b.section != "ENTITIES" && !leading(b)) {
+        write_section(out, &blob.section, &blob.codes)?;
+    }
+    writeln!(out, "  0\nEOF")?;
+    Ok(adjusted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../fixtures/dxf/unsupported.dxf");
+
+    fn text(codes: &[(i32, String)]) -> String {
+        let mut out = String::new();
+        write_codes(&mut out, codes).unwrap();
+        out
+    }
+
+    #[test]
+    fn test_unsupported_entities_survive_a_round_trip() {
+        let mut doc = Document::new();
+        let report = doc.import_dxf(FIXTURE, true).unwrap();
+        assert_eq!(report.shapes.len(), 1);
+        assert_eq!(report.preserved, 5);
+        assert_eq!(doc.shape(report.shapes[0]).unwrap().layer.as_deref(), Some("walls"));
+        let added = doc.add_shape(Polygon::new(vec![Point::new(20.0, 0.0), Point::new(25.0, 0.0), Point::new(25.0, 4.0)]));
+        doc.set_layer(added, Some("notes".to_string())).unwrap();
+
+        let dxf = doc.export("dxf", None).unwrap();
+        let dimension = doc.dxf_passthrough().iter().find(|b| value(&b.codes, 0) == Some("DIMENSION")).unwrap();
+        assert_eq!(dimension.layer.as_deref(), Some("notes"));
+        assert!(dxf.contains(&text(&dimension.codes)));
+        for blob in doc.dxf_passthrough().iter().filter(|b| b.section != "HEADER") {
+            assert!(dxf.contains(&text(&blob.codes)));
+        }
+        // The imported polyline keeps its handle, the new one goes past the
+        // header's seed, and the seed past both
+        assert!(dxf.contains("  0\nLWPOLYLINE\n  5\n2A\n"));
+        assert!(dxf.contains("  0\nLWPOLYLINE\n  5\n30\n"));
+        assert!(dxf.contains("  9\n$HANDSEED\n  5\n31\n"));
+        assert!(dxf.find("ENTITIES").unwrap() > dxf.find("TABLES").unwrap() && dxf.find("OBJECTS").unwrap() > dxf.find("ENTITIES").unwrap());
+
+        let mut again = Document::new();
+        let reread = again.import_dxf(&dxf, true).unwrap();
+        assert_eq!(reread.shapes.len(), 2);
+        assert_eq!(reread.preserved, 5);
+        assert_eq!(again.dxf_passthrough()[2..], doc.dxf_passthrough()[2..]);
+        assert_eq!(again.export("dxf", None).unwrap(), dxf);
+    }
+
+    #[test]
+    fn test_dropping_unsupported_entities() {
+        let mut doc = Document::new();
+        let report = doc.import_dxf(FIXTURE, false).unwrap();
+        assert_eq!((report.preserved, report.dropped), (0, 5));
+        let dxf = doc.export("dxf", Some(2)).unwrap();
+        assert_eq!(read_dxf(&dxf).unwrap().1.len(), 0);
+        assert!(dxf.contains(" 10\n10.00\n 20\n5.00\n"));
+        assert!(!dxf.contains("DIMENSION"));
+
+        assert!(matches!(read_dxf("  0\nSECTION\n  2\nENTITIES\n"), Err(CadError::Parse { position: 1, .. })));
+        assert!(matches!(read_dxf("  0\nSECTION\nx\n"), Err(CadError::Parse { position: 3, .. })));
+    }
+}
@@ -0,0 +1,136 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CadError {
+    // Malformed text input; `position` is the character offset of the problem
+    Parse { position: usize, message: String },
+    // Well-formed input that cannot be evaluated as given
+    InvalidInput(String),
+    // No shape with this id in the document
+    UnknownShape(u32),
+    // No dimension with this id in the document
+    UnknownDimension(u32),
+    // No saved view with this name in the document
+    UnknownView(String),
+    // No group with this id in the document
+    UnknownGroup(u32),
+    // No curve with this id in the document
+    UnknownCurve(u32),
+    // No item with this name in the shape library
+    UnknownLibraryItem(String),
+    // Geometry with no meaningful answer: a zero vector's direction, the
+    // centroid of no points, the inverse of a singular matrix
+    Degenerate(String),
+    // Neighbouring edges, by index, too close to parallel to be extended to
+    // meet
+    ParallelEdges(usize, usize),
+    // Rings, by index, whose outlines cross so neither contains the other
+    CrossingRings(usize, usize),
+    // A polygon that fails `Polygon::validate`
+    Geometry(GeometryError),
+    // Output that strict output mode couldn't repair into valid geometry;
+    // says which operation and piece, and what is wrong with it
+    InvalidResult(String),
+    // A result that could not be handed back to JS, or binary data that
+    // could not be written or read back
+    Serialization(String),
+    // An autosave snapshot that fails its integrity checks
+    CorruptSnapshot(String),
+    // The first autosave delta, by position in the list given, that fails
+    // its integrity checks
+    CorruptDelta { index: usize, message: String },
+}
+
+impl CadError {
+    // Stable identifier for each kind of error, e.g. for JS callers to
+    // switch on; it starts the Display text
+    pub fn code(&self) -> &'static str {
+        match self {
+            CadError::Parse { .. } => "parse error",
+            CadError::InvalidInput(_) => "invalid input",
+            CadError::UnknownShape(_) => "unknown shape",
+            CadError::UnknownDimension(_) => "unknown dimension",
+            CadError::UnknownView(_) => "unknown view",
+            CadError::UnknownGroup(_) => "unknown group",
+            CadError::UnknownCurve(_) => "unknown curve",
+            CadError::UnknownLibraryItem(_) => "unknown library item",
+            CadError::Degenerate(_) => "degenerate geometry",
+            CadError::ParallelEdges(..) => "parallel edges",
+            CadError::CrossingRings(..) => "crossing rings",
+            CadError::Geometry(e) => e.code(),
+            CadError::InvalidResult(_) => "invalid result",
+            CadError::Serialization(_) => "serialization error",
+            CadError::CorruptSnapshot(_) => "corrupt snapshot",
+            CadError::CorruptDelta { .. } => "corrupt delta",
+        }
+    }
+}
+
+impl fmt::Display for CadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CadError::Parse { position, message } => write!(f, "parse error at position {}: {}", position, message),
+            CadError::InvalidInput(message) => write!(f, "invalid input: {}", message),
+            CadError::UnknownShape(id) => write!(f, "unknown shape {}", id),
+            CadError::UnknownDimension(id) => write!(f, "unknown dimension {}", id),
+            CadError::UnknownView(name) => write!(f, "unknown view '{}'", name),
+            CadError::UnknownGroup(id) => write!(f, "unknown group {}", id),
+            CadError::UnknownCurve(id) => write!(f, "unknown curve {}", id),
+            CadError::UnknownLibraryItem(name) => write!(f, "unknown library item '{}'", name),
+            CadError::Degenerate(message) => write!(f, "degenerate geometry: {}", message),
+            CadError::ParallelEdges(i, j) => write!(f, "parallel edges: edges {} and {} don't meet", i, j),
+            CadError::CrossingRings(i, j) => write!(f, "crossing rings: rings {} and {} cross", i, j),
+            CadError::Geometry(e) => write!(f, "{}", e),
+            CadError::InvalidResult(message) => write!(f, "invalid result: {}", message),
+            CadError::Serialization(message) => write!(f, "serialization error: {}", message),
+            CadError::CorruptSnapshot(message) => write!(f, "corrupt snapshot: {}", message),
+            CadError::CorruptDelta { index, message } => write!(f, "corrupt delta {}: {}", index, message),
+        }
+    }
+}
+
+impl std::error::Error for CadError {}
+
+// Why a polygon's vertices don't make a valid polygon; indices are into the
+// vertex list, and an edge is numbered by the vertex it starts at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeometryError {
+    // Fewer than three vertices; holds the count
+    TooFewVertices(usize),
+    // Edges that cross or touch other than at a shared vertex
+    SelfIntersecting(usize, usize),
+    // A vertex within EPSILON of the one after it
+    DuplicateVertices(usize),
+    // A vertex with a NaN or infinite coordinate
+    NonFinite(usize),
+}
+
+impl GeometryError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            GeometryError::TooFewVertices(_) => "too few vertices",
+            GeometryError::SelfIntersecting(..) => "self-intersecting",
+            GeometryError::DuplicateVertices(_) => "duplicate vertices",
+            GeometryError::NonFinite(_) => "non-finite coordinate",
+        }
+    }
+}
+
+impl fmt::Display for GeometryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GeometryError::TooFewVertices(n) => write!(f, "too few vertices: {}, a polygon needs at least 3", n),
+            GeometryError::SelfIntersecting(i, j) => write!(f, "self-intersecting: edges {} and {} touch", i, j),
+            GeometryError::DuplicateVertices(i) => write!(f, "duplicate vertices: vertex {} and the next coincide", i),
+            GeometryError::NonFinite(i) => write!(f, "non-finite coordinate at vertex {}", i),
+        }
+    }
+}
+
+impl std::error::Error for GeometryError {}
+
+impl From<GeometryError> for CadError {
+    fn from(e: GeometryError) -> CadError {
+        CadError::Geometry(e)
+    }
+}
@@ -0,0 +1,354 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Write};
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::hash::ContentHasher;
+use crate::{round_coordinates_report, BoundingBox, CadError, DimensionGraphic, Document, Point, Polygon, Shape, ShapeId};
+
+// Number text for exported files. None gives the shortest decimal that
+// reads back as the same f64 (Rust's float Display, which doesn't depend on
+// locale or platform); Some(p) rounds to p decimals. -0 prints as 0 either
+// way, and non-finite values (which no format here can represent) as 0.
+pub fn format_number(v: f64, precision: Option<usize>) -> String {
+    let v = if v.is_finite() { v } else { 0.0 };
+    let text = match precision {
+        Some(p) => format!("{:.p$}", v, p = p),
+        None => format!("{}", v),
+    };
+    // Rounding can leave a negative zero, e.g. -0.0001 at 2 decimals
+    if text.starts_with('-') && text[1..].chars().all(|c| c == '0' || c == '.') {
+        text[1..].to_string()
+    } else {
+        text
+    }
+}
+
+// A shape's world outline as it is exported. At a precision its vertices
+// are rounded by `round_coordinates_report`, so the outline stays simple,
+// and the count is of the vertices that took refining or removing.
+pub(crate) fn export_outline(shape: &Shape, precision: Option<usize>) -> (Vec<Point>, usize) {
+    let world = shape.world_geometry();
+    match precision {
+        Some(p) => {
+            let rounded = round_coordinates_report(&world, p.min(u32::MAX as usize) as u32);
+            let adjusted = rounded.adjusted_vertices();
+            (rounded.polygon.vertices, adjusted)
+        }
+        None => (world.vertices, 0),
+    }
+}
+
+// A coordinate of an `export_outline` vertex: at the precision, unless
+// rounding kept it finer, when it is written exactly
+pub(crate) fn vertex_number(v: f64, precision: Option<usize>) -> String {
+    let text = format_number(v, precision);
+    if precision.is_some() && text.parse::<f64>().ok() != Some(v) {
+        format_number(v, None)
+    } else {
+        text
+    }
+}
+
+// Exported text, with how many vertices rounding to the profile's precision
+// had to refine or remove to keep outlines simple
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ExportResult {
+    pub text: String,
+    pub adjusted_vertices: usize,
+}
+
+impl Polygon {
+    // Closed SVG path data, "M x y L x y ... Z", in the polygon's own
+    // coordinates with numbers as `format_number` writes them. An empty
+    // polygon gives an empty string.
+    pub fn to_svg_path(&self) -> String {
+        let mut d = String::new();
+        for (i, p) in self.vertices.iter().enumerate() {
+            d.push_str(&format!("{}{} {} ", if i == 0 { "M" } else { "L" }, format_number(p.x, None), format_number(p.y, None)));
+        }
+        if !d.is_empty() {
+            d.push('Z');
+        }
+        d
+    }
+}
+
+#[wasm_bindgen]
+pub fn polygon_to_svg(vertices: &JsValue) -> Result<String, JsValue> {
+    let polygon: Polygon = crate::polygon_from(vertices)?;
+    Ok(polygon.to_svg_path())
+}
+
+// What an export takes from the document and how its layers come out, kept
+// as a preset. Layers are matched by name, with shapes on no layer going by
+// "0" as DXF files them; the default profile exports everything as it is.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct ExportProfile {
+    // Only these layers, unless empty
+    pub include_layers: BTreeSet<String>,
+    pub exclude_layers: BTreeSet<String>,
+    // Output name for a document layer; layers given the same name come out
+    // merged into one
+    pub layer_names: BTreeMap<String, String>,
+    // "#rrggbb" for an output layer, by its output name
+    pub layer_colors: BTreeMap<String, String>,
+    // Only these shapes, when given; preserved DXF entities, which aren't
+    // shapes, are then left out
+    pub shapes: Option<BTreeSet<ShapeId>>,
+    // Decimals for every number written, None for the shortest exact text
+    pub precision: Option<usize>,
+}
+
+// Layer name DXF gives shapes without one
+pub(crate) const DEFAULT_LAYER: &str = "0";
+
+impl ExportProfile {
+    pub fn with_precision(precision: Option<usize>) -> ExportProfile {
+        ExportProfile { precision, ..ExportProfile::default() }
+    }
+
+    pub fn includes_layer(&self, layer: Option<&str>) -> bool {
+        let name = layer.unwrap_or(DEFAULT_LAYER);
+        (self.include_layers.is_empty() || self.include_layers.contains(name)) && !self.exclude_layers.contains(name)
+    }
+
+    pub fn includes(&self, shape: &Shape) -> bool {
+        self.shapes.as_ref().is_none_or(|ids| ids.contains(&shape.id)) && self.includes_layer(shape.layer.as_deref())
+    }
+
+    // Output name of a document layer; None stays unnamed
+    pub fn layer_name(&self, layer: Option<&str>) -> Option<String> {
+        match self.layer_names.get(layer.unwrap_or(DEFAULT_LAYER)) {
+            Some(name) => Some(name.clone()),
+            None => layer.map(str::to_string),
+        }
+    }
+
+    // The colour as 0xRRGGBB, for an output layer
+    pub(crate) fn rgb(&self, layer: Option<&str>) -> Option<u32> {
+        let color = self.layer_colors.get(layer.unwrap_or(DEFAULT_LAYER))?;
+        u32::from_str_radix(color.strip_prefix('#')?, 16).ok().filter(|_| color.len() == 7)
+    }
+
+    fn check(&self) -> Result<(), CadError> {
+        match self.layer_colors.iter().find(|(layer, _)| self.rgb(Some(layer)).is_none()) {
+            Some((layer, color)) => Err(CadError::InvalidInput(format!("colour '{}' for layer '{}' is not #rrggbb", color, layer))),
+            None => Ok(()),
+        }
+    }
+}
+
+// Text formats `Document::export` can write; returns the vertices adjusted
+pub(crate) fn write_export(doc: &Document, format: &str, profile: &ExportProfile, out: &mut impl Write) -> Result<usize, CadError> {
+    profile.check()?;
+    let written = match format {
+        "svg" => write_svg(doc, profile, out),
+        "dxf" => crate::dxf::write_dxf(doc, profile, out),
+        _ => return Err(CadError::InvalidInput(format!("unknown export format '{}'", format))),
+    };
+    written.map_err(|_| CadError::InvalidInput("export output failed".to_string()))
+}
+
+// Hashes exported text as it is written, so the fingerprint needs no buffer
+pub(crate) struct HashWriter(pub(crate) ContentHasher);
+
+impl Write for HashWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.bytes(s.as_bytes());
+        Ok(())
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+// The shapes the profile takes, grouped by output layer, unlayered first and
+// the rest by name, each group in id order. Document order isn't used, since
+// it depends on edit history.
+pub(crate) fn ordered_shapes<'a>(doc: &'a Document, profile: &ExportProfile) -> Vec<(Option<String>, Vec<&'a Shape>)> {
+    let mut shapes: Vec<(Option<String>, &Shape)> = doc.shapes().filter(|s| profile.includes(s)).map(|s| (profile.layer_name(s.layer.as_deref()), s)).collect();
+    shapes.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.id.cmp(&b.1.id)));
+    let mut groups: Vec<(Option<String>, Vec<&Shape>)> = Vec::new();
+    for (layer, shape) in shapes {
+        match groups.last_mut() {
+            Some((last, group)) if *last == layer => group.push(shape),
+            _ => groups.push((layer, vec![shape])),
+        }
+    }
+    groups
+}
+
+// Every point of a dimension's lines and arrows
+pub(crate) fn dimension_points(graphic: &DimensionGraphic) -> impl Iterator<Item = &Point> {
+    graphic.lines.iter().flat_map(|l| &l.points).chain(graphic.arrows.iter().flat_map(|a| &a.vertices))
+}
+
+// World geometry as SVG paths, one group per layer, then a group per
+// angular or radial dimension on the shapes exported. SVG's y axis points
+// down, so y is negated.
+fn write_svg(doc: &Document, profile: &ExportProfile, out: &mut impl Write) -> Result<usize, fmt::Error> {
+    let num = |v: f64| format_number(v, profile.precision);
+    let groups = ordered_shapes(doc, profile);
+    let dimensions = doc.rendered_dimensions(|id| doc.shape(id).is_ok_and(|s| profile.includes(s)));
+    let mut corners: Vec<Point> = groups.iter().flat_map(|(_, shapes)| shapes).flat_map(|s| {
+        let b = s.world_bounding_box();
+        [b.min, b.max]
+    }).collect();
+    corners.extend(dimensions.iter().flat_map(|(.., g)| dimension_points(g)));
+    let b = BoundingBox::from_points(&corners);
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">"#,
+        num(b.min.x), num(-b.max.y), num(b.width()), num(b.height()),
+    )?;
+    let mut adjusted = 0;
+    for (layer, shapes) in groups {
+        write!(out, "<g")?;
+        if let Some(name) = &layer {
+            write!(out, r#" data-layer="{}""#, escape(name))?;
+        }
+        if let Some(rgb) = profile.rgb(layer.as_deref()) {
+            write!(out, r##" stroke="#{:06x}""##, rgb)?;
+        }
+        writeln!(out, ">")?;
+        for shape in shapes {
+            write!(out, r#"<path data-id="{}" d=""#, shape.id)?;
+            let (vertices, n) = export_outline(shape, profile.precision);
+            adjusted += n;
+            for (i, p) in vertices.iter().enumerate() {
+                write!(out, "{}{} {} ", if i == 0 { "M" } else { "L" }, vertex_number(p.x, profile.precision), vertex_number(-p.y, profile.precision))?;
+            }
+            writeln!(out, r#"Z"/>"#)?;
+        }
+        writeln!(out, "</g>")?;
+    }
+    let path = |points: &[Point], closed: bool| {
+        let mut d = String::new();
+        for (i, p) in points.iter().enumerate() {
+            d.push_str(&format!("{}{} {} ", if i == 0 { "M" } else { "L" }, num(p.x), num(-p.y)));
+        }
+        if closed { d + "Z" } else { d.trim_end().to_string() }
+    };
+    for (id, _, _, graphic) in &dimensions {
+        writeln!(out, r#"<g data-dimension="{}">"#, id)?;
+        for line in &graphic.lines {
+            writeln!(out, r#"<path fill="none" d="{}"/>"#, path(&line.points, false))?;
+        }
+        for arrow in &graphic.arrows {
+            writeln!(out, r#"<path d="{}"/>"#, path(&arrow.vertices, true))?;
+        }
+        writeln!(out, r#"<text x="{}" y="{}">{}</text>"#, num(graphic.text_anchor.x), num(-graphic.text_anchor.y), escape(&graphic.text))?;
+        writeln!(out, "</g>")?;
+    }
+    writeln!(out, "</svg>")?;
+    Ok(adjusted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Matrix, Polygon};
+
+    #[test]
+    fn test_number_formatting() {
+        assert_eq!(format_number(0.1 + 0.2, None), "0.30000000000000004");
+        assert_eq!(format_number(-0.0, None), "0");
+        assert_eq!(format_number(-0.0001, Some(2)), "0.00");
+        assert_eq!(format_number(2.5, Some(3)), "2.500");
+        assert_eq!(format_number(1e21, None), "1000000000000000000000");
+        assert_eq!(format_number(f64::NAN, None), "0");
+    }
+
+    // Same content reached through different edit histories
+    fn document(reverse: bool) -> Document {
+        let mut doc = Document::new();
+        let shapes = [
+            Polygon::new(vec![Point::new(0.0, 0.0), Point::new(1.0, 0.0), Point::new(0.5, 1.0 / 3.0)]),
+            Polygon::new(vec![Point::new(2.0, 0.0), Point::new(3.0, 0.0), Point::new(3.0, 1.0), Point::new(2.0, 1.0)]),
+            Polygon::new(vec![Point::new(-1.0, -1.0), Point::new(0.0, -1.0), Point::new(-0.5, -0.1)]),
+        ];
+        let ids: Vec<_> = shapes.into_iter().map(|s| doc.add_shape(s)).collect();
+        let mut edits: Vec<(usize, &str)> = vec![(0, "b<&>"), (1, "a"), (2, "b<&>")];
+        if reverse {
+            edits.reverse();
+        }
+        for (i, layer) in edits {
+            doc.set_layer(ids[i], Some(layer.to_string())).unwrap();
+        }
+        doc.set_layer(ids[2], None).unwrap();
+        doc.set_transform(ids[1], Matrix::rotate(0.1)).unwrap();
+        doc
+    }
+
+    #[test]
+    fn test_export_is_stable_and_fingerprint_matches() {
+        let (a, b) = (document(false), document(true));
+        let svg = a.export("svg", None).unwrap();
+        assert_eq!(svg, b.export("svg", None).unwrap());
+        assert!(svg.contains(r#"data-layer="b&lt;&amp;&gt;""#));
+        let unlayered = svg.find(r#"data-id="2""#).unwrap();
+        assert!(unlayered < svg.find(r#"data-id="1""#).unwrap() && svg.find(r#"data-id="1""#).unwrap() < svg.find(r#"data-id="0""#).unwrap());
+
+        let mut h = ContentHasher::new();
+        h.bytes(svg.as_bytes());
+        assert_eq!(a.export_fingerprint("svg", None).unwrap(), h.finish());
+        assert_eq!(b.export_fingerprint("svg", None).unwrap(), h.finish());
+        assert_ne!(a.export_fingerprint("svg", Some(2)).unwrap(), h.finish());
+        assert!(a.export_fingerprint("pdf", None).is_err());
+    }
+
+    #[test]
+    fn test_profile_excludes_renames_and_colours_layers() {
+        let mut doc = Document::new();
+        for (k, layer) in ["walls", "construction", "doors"].iter().enumerate() {
+            let x = 10.0 * k as f64;
+            let id = doc.add_shape(Polygon::new(vec![Point::new(x, 0.0), Point::new(x + 1.0, 0.0), Point::new(x + 1.0, 1.0)]));
+            doc.set_layer(id, Some(layer.to_string())).unwrap();
+        }
+        let profile = ExportProfile {
+            exclude_layers: ["construction".to_string()].into(),
+            layer_names: [("walls".to_string(), "A-WALL".to_string()), ("doors".to_string(), "A-DOOR".to_string())].into(),
+            layer_colors: [("A-WALL".to_string(), "#ff0000".to_string())].into(),
+            precision: Some(1),
+            ..ExportProfile::default()
+        };
+        let dxf = doc.export_with("dxf", &profile).unwrap();
+        let (shapes, _) = crate::dxf::read_dxf(&dxf).unwrap();
+        let mut layers: Vec<String> = shapes.iter().map(|s| s.layer.clone().unwrap()).collect();
+        layers.sort();
+        assert_eq!(layers, vec!["A-DOOR", "A-WALL"]);
+        assert!(!dxf.contains("construction") && !dxf.contains(" 10\n10.0\n"));
+        assert!(dxf.contains("  8\nA-WALL\n420\n16711680\n") && dxf.contains(" 10\n21.0\n"));
+
+        let svg = doc.export_with("svg", &profile).unwrap();
+        assert_eq!(svg.matches("<path").count(), 2);
+        assert!(svg.contains(r##"<g data-layer="A-WALL" stroke="#ff0000">"##));
+        let only = ExportProfile { include_layers: ["doors".to_string()].into(), ..profile.clone() };
+        assert_eq!(doc.export_with("svg", &only).unwrap().matches("<path").count(), 1);
+        let bad = ExportProfile { layer_colors: [("A-WALL".to_string(), "red".to_string())].into(), ..ExportProfile::default() };
+        assert!(matches!(doc.export_with("svg", &bad), Err(CadError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_rounded_export_keeps_sliver_simple() {
+        // A notch whose tip hovers 0.0004 above the bottom edge, breaking
+        // under plain rounding to 3 decimals
+        let mut doc = Document::new();
+        let notch = vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.6, 1.0), (0.5, 0.0004), (0.4, 1.0), (0.0, 1.0)];
+        doc.add_shape(Polygon::new(notch.into_iter().map(|(x, y)| Point::new(x, y)).collect()));
+        let profile = ExportProfile::with_precision(Some(3));
+        let dxf = doc.export_report("dxf", &profile).unwrap();
+        assert_eq!(dxf.adjusted_vertices, 1);
+        let (shapes, _) = crate::dxf::read_dxf(&dxf.text).unwrap();
+        assert!(shapes[0].geometry.is_simple());
+        assert!(dxf.text.contains(" 20\n0.0004\n") && dxf.text.contains(" 10\n0.600\n"));
+        let svg = doc.export_report("svg", &profile).unwrap();
+        assert_eq!(svg.adjusted_vertices, 1);
+        assert!(svg.text.contains("L0.500 -0.0004 "));
+        assert_eq!(doc.export_report("svg", &ExportProfile::default()).unwrap().adjusted_vertices, 0);
+    }
+}
@@ -0,0 +1,384 @@
+// C interface for native hosts, built with the `ffi` feature. Documents are
+// reached through numeric handles rather than pointers, so a freed or made
+// up handle is an error code instead of undefined behaviour; handles are
+// never reused and belong to the thread that made them. Every entry point
+// returns a CadStatus, writes results through out-pointers only on
+// success, and catches panics before they reach the caller. The header is
+// include/cad_engine.h, generated by cbindgen from this file.
+#![allow(clippy::missing_safety_doc)]
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use crate::binary::to_bytes;
+use crate::{BooleanOp, CadError, Document, Matrix, Point, Polygon, ShapeId};
+
+pub type CadDocumentHandle = u64;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CadStatus {
+    Ok = 0,
+    NullPointer = 1,
+    InvalidHandle = 2,
+    InvalidInput = 3,
+    UnknownShape = 4,
+    InvalidGeometry = 5,
+    // Output didn't fit; the needed size was still written
+    BufferTooSmall = 6,
+    Serialization = 7,
+    Panic = 8,
+    // Any other CadError; cad_last_error_message has the details
+    Failed = 9,
+}
+
+// Bytes handed to the caller, to be given back to cad_buffer_free
+#[repr(C)]
+#[derive(Debug)]
+pub struct CadBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+thread_local! {
+    static DOCUMENTS: RefCell<HashMap<CadDocumentHandle, Document>> = RefCell::new(HashMap::new());
+    static NEXT_HANDLE: Cell<CadDocumentHandle> = const { Cell::new(1) };
+    static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+}
+
+fn set_last_error(message: &str) {
+    LAST_ERROR.with(|e| *e.borrow_mut() = CString::new(message.replace('\0', " ")).unwrap_or_default());
+}
+
+impl From<CadError> for CadStatus {
+    fn from(e: CadError) -> CadStatus {
+        set_last_error(&e.to_string());
+        match e {
+            CadError::InvalidInput(_) | CadError::Parse { .. } => CadStatus::InvalidInput,
+            CadError::UnknownShape(_) => CadStatus::UnknownShape,
+            CadError::Geometry(_) | CadError::Degenerate(_) | CadError::InvalidResult(_) => CadStatus::InvalidGeometry,
+            CadError::Serialization(_) => CadStatus::Serialization,
+            _ => CadStatus::Failed,
+        }
+    }
+}
+
+fn fail(status: CadStatus, message: &str) -> CadStatus {
+    set_last_error(message);
+    status
+}
+
+// Runs an entry point's body, turning a panic into CadStatus::Panic
+fn guard(body: impl FnOnce() -> Result<(), CadStatus>) -> CadStatus {
+    match catch_unwind(AssertUnwindSafe(body)) {
+        Ok(Ok(())) => CadStatus::Ok,
+        Ok(Err(status)) => status,
+        Err(_) => fail(CadStatus::Panic, "internal panic"),
+    }
+}
+
+fn with_document<T>(handle: CadDocumentHandle, f: impl FnOnce(&mut Document) -> Result<T, CadStatus>) -> Result<T, CadStatus> {
+    DOCUMENTS.with(|docs| match docs.borrow_mut().get_mut(&handle) {
+        Some(doc) => f(doc),
+        None => Err(fail(CadStatus::InvalidHandle, &format!("no document with handle {}", handle))),
+    })
+}
+
+// Writes `value` through `out`, which must not be null
+unsafe fn write<T>(out: *mut T, value: T) -> Result<(), CadStatus> {
+    if out.is_null() {
+        return Err(fail(CadStatus::NullPointer, "null output pointer"));
+    }
+    out.write(value);
+    Ok(())
+}
+
+// `len` values from `data`; null is fine for none
+unsafe fn read<'a, T>(data: *const T, len: usize) -> Result<&'a [T], CadStatus> {
+    match (data.is_null(), len) {
+        (_, 0) => Ok(&[]),
+        (true, _) => Err(fail(CadStatus::NullPointer, "null input pointer")),
+        (false, _) => Ok(std::slice::from_raw_parts(data, len)),
+    }
+}
+
+fn buffer(bytes: Vec<u8>) -> CadBuffer {
+    let len = bytes.len();
+    CadBuffer { data: Box::into_raw(bytes.into_boxed_slice()) as *mut u8, len }
+}
+
+// Static text naming a status; never null
+#[no_mangle]
+pub extern "C" fn cad_status_name(status: CadStatus) -> *const c_char {
+    let name: &'static CStr = match status {
+        CadStatus::Ok => c"ok",
+        CadStatus::NullPointer => c"null pointer",
+        CadStatus::InvalidHandle => c"invalid handle",
+        CadStatus::InvalidInput => c"invalid input",
+        CadStatus::UnknownShape => c"unknown shape",
+        CadStatus::InvalidGeometry => c"invalid geometry",
+        CadStatus::BufferTooSmall => c"buffer too small",
+        CadStatus::Serialization => c"serialization error",
+        CadStatus::Panic => c"panic",
+        CadStatus::Failed => c"failed",
+    };
+    name.as_ptr()
+}
+
+// Message for the last failure on this thread, valid until the next call
+// that fails; empty before any
+#[no_mangle]
+pub extern "C" fn cad_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ptr())
+}
+
+// `out` must be null or point to writable memory for a handle.
+#[no_mangle]
+pub unsafe extern "C" fn cad_document_new(out: *mut CadDocumentHandle) -> CadStatus {
+    guard(|| {
+        let handle = NEXT_HANDLE.with(|next| next.replace(next.get() + 1));
+        write(out, handle)?;
+        DOCUMENTS.with(|docs| docs.borrow_mut().insert(handle, Document::new()));
+        Ok(())
+    })
+}
+
+// Freeing a handle twice, or one never made, is CadStatus::InvalidHandle
+#[no_mangle]
+pub extern "C" fn cad_document_free(handle: CadDocumentHandle) -> CadStatus {
+    guard(|| match DOCUMENTS.with(|docs| docs.borrow_mut().remove(&handle)) {
+        Some(_) => Ok(()),
+        None => Err(fail(CadStatus::InvalidHandle, &format!("no document with handle {}", handle))),
+    })
+}
+
+// `out` must be null or point to writable memory for a size_t.
+#[no_mangle]
+pub unsafe extern "C" fn cad_document_shape_count(handle: CadDocumentHandle, out: *mut usize) -> CadStatus {
+    guard(|| {
+        let count = with_document(handle, |doc| Ok(doc.shapes().count()))?;
+        write(out, count)
+    })
+}
+
+// Adds a polygon from `len` doubles, x then y for each vertex;
+// `coords` must be null or point to `len` readable doubles, and `out_id`
+// null or point to writable memory for a uint32_t.
+#[no_mangle]
+pub unsafe extern "C" fn cad_document_add_polygon(handle: CadDocumentHandle, coords: *const f64, len: usize, out_id: *mut ShapeId) -> CadStatus {
+    guard(|| {
+        let coords = read(coords, len)?;
+        if !len.is_multiple_of(2) {
+            return Err(fail(CadStatus::InvalidInput, &format!("{} coordinates don't make x, y pairs", len)));
+        }
+        if out_id.is_null() {
+            return Err(fail(CadStatus::NullPointer, "null output pointer"));
+        }
+        let polygon = Polygon::new(coords.chunks_exact(2).map(|c| Point::new(c[0], c[1])).collect());
+        let id = with_document(handle, |doc| Ok(doc.add_shape(polygon)))?;
+        write(out_id, id)
+    })
+}
+
+// World-space area of a shape;
+// `out` must be null or point to writable memory for a double.
+#[no_mangle]
+pub unsafe extern "C" fn cad_shape_area(handle: CadDocumentHandle, id: ShapeId, out: *mut f64) -> CadStatus {
+    guard(|| {
+        let area = with_document(handle, |doc| Ok(doc.shape(id)?.world_geometry().area()))?;
+        write(out, area)
+    })
+}
+
+// World-space bounding box as min x, min y, max x, max y;
+// `out` must be null or point to 4 writable doubles.
+#[no_mangle]
+pub unsafe extern "C" fn cad_shape_bbox(handle: CadDocumentHandle, id: ShapeId, out: *mut f64) -> CadStatus {
+    guard(|| {
+        let b = with_document(handle, |doc| Ok(doc.shape(id)?.world_geometry().bounding_box()))?;
+        if out.is_null() {
+            return Err(fail(CadStatus::NullPointer, "null output pointer"));
+        }
+        std::slice::from_raw_parts_mut(out, 4).copy_from_slice(&[b.min.x, b.min.y, b.max.x, b.max.y]);
+        Ok(())
+    })
+}
+
+// Sets a shape's transform from 6 doubles: m11, m12, m21, m22, dx, dy.
+// `matrix` must be null or point to 6 readable doubles.
+#[no_mangle]
+pub unsafe extern "C" fn cad_shape_set_transform(handle: CadDocumentHandle, id: ShapeId, matrix: *const f64) -> CadStatus {
+    guard(|| {
+        let m = read(matrix, 6)?;
+        let transform = Matrix { m11: m[0], m12: m[1], m21: m[2], m22: m[3], dx: m[4], dy: m[5] };
+        with_document(handle, |doc| Ok(doc.set_transform(id, transform)?))
+    })
+}
+
+// Adds the pieces of boolean `op` (0 union, 1 intersection, 2 difference)
+// of two shapes' world outlines as new shapes. Their ids go to `out_ids`,
+// up to `capacity` of them, and their number to `out_count`; with too
+// little room nothing is added and CadStatus::BufferTooSmall returned, so
+// the call can be repeated with a buffer of `out_count`.
+// `out_ids` must be null or point to `capacity` writable uint32_t, and
+// `out_count` null or point to writable memory for a size_t.
+#[no_mangle]
+pub unsafe extern "C" fn cad_shapes_boolean(handle: CadDocumentHandle, a: ShapeId, b: ShapeId, op: u32, out_ids: *mut ShapeId, capacity: usize, out_count: *mut usize) -> CadStatus {
+    guard(|| {
+        let op = match op {
+            0 => BooleanOp::Union,
+            1 => BooleanOp::Intersection,
+            2 => BooleanOp::Difference,
+            _ => return Err(fail(CadStatus::InvalidInput, &format!("unknown boolean op {}", op))),
+        };
+        if out_count.is_null() || (out_ids.is_null() && capacity > 0) {
+            return Err(fail(CadStatus::NullPointer, "null output pointer"));
+        }
+        with_document(handle, |doc| {
+            let pieces = crate::strict::checked_regions(doc.shape(a)?.world_geometry().boolean_regions(&doc.shape(b)?.world_geometry(), op), "boolean")?;
+            out_count.write(pieces.len());
+            if pieces.len() > capacity {
+                return Err(fail(CadStatus::BufferTooSmall, &format!("{} pieces for room for {}", pieces.len(), capacity)));
+            }
+            for (i, piece) in pieces.into_iter().enumerate() {
+                out_ids.add(i).write(doc.add_shape(crate::boolean::keyhole(piece)));
+            }
+            Ok(())
+        })
+    })
+}
+
+// Exports the document as "svg" or "dxf" text, UTF-8 without a
+// terminator; `precision` below 0 writes numbers in full.
+// `format` must be null or a NUL-terminated string, and `out` null or
+// point to a writable CadBuffer. Free the buffer with cad_buffer_free.
+#[no_mangle]
+pub unsafe extern "C" fn cad_document_export(handle: CadDocumentHandle, format: *const c_char, precision: i32, out: *mut CadBuffer) -> CadStatus {
+    guard(|| {
+        if format.is_null() {
+            return Err(fail(CadStatus::NullPointer, "null format"));
+        }
+        let format = CStr::from_ptr(format).to_str().map_err(|_| fail(CadStatus::InvalidInput, "format is not UTF-8"))?;
+        let precision = usize::try_from(precision).ok();
+        let text = with_document(handle, |doc| Ok(doc.export(format, precision)?))?;
+        if out.is_null() {
+            return Err(fail(CadStatus::NullPointer, "null output pointer"));
+        }
+        write(out, buffer(text.into_bytes()))
+    })
+}
+
+// The document in the engine's binary save format.
+// `out` must be null or point to a writable CadBuffer. Free the buffer
+// with cad_buffer_free.
+#[no_mangle]
+pub unsafe extern "C" fn cad_document_to_bytes(handle: CadDocumentHandle, out: *mut CadBuffer) -> CadStatus {
+    guard(|| {
+        let bytes = with_document(handle, |doc| Ok(to_bytes(&*doc)?))?;
+        if out.is_null() {
+            return Err(fail(CadStatus::NullPointer, "null output pointer"));
+        }
+        write(out, buffer(bytes))
+    })
+}
+
+// Frees a buffer from this library and clears it, so freeing it again is
+// a no-op.
+// `buffer` must be null or point to a CadBuffer this library filled in, or
+// one already cleared by this function.
+#[no_mangle]
+pub unsafe extern "C" fn cad_buffer_free(buffer: *mut CadBuffer) -> CadStatus {
+    guard(|| {
+        if buffer.is_null() {
+            return Err(fail(CadStatus::NullPointer, "null buffer"));
+        }
+        let b = &mut *buffer;
+        if !b.data.is_null() {
+            drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(b.data, b.len)));
+        }
+        *b = CadBuffer { data: std::ptr::null_mut(), len: 0 };
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr::{null, null_mut};
+
+    use super::*;
+
+    #[test]
+    fn test_document_round_trip_through_the_c_interface() {
+        unsafe {
+            let mut doc = 0;
+            assert_eq!(cad_document_new(&mut doc), CadStatus::Ok);
+            let (mut a, mut b) = (0, 0);
+            let square = [0.0, 0.0, 2.0, 0.0, 2.0, 2.0, 0.0, 2.0];
+            assert_eq!(cad_document_add_polygon(doc, square.as_ptr(), square.len(), &mut a), CadStatus::Ok);
+            assert_eq!(cad_document_add_polygon(doc, square.as_ptr(), square.len(), &mut b), CadStatus::Ok);
+            assert_eq!(cad_shape_set_transform(doc, b, [1.0, 0.0, 0.0, 1.0, 1.0, 1.0].as_ptr()), CadStatus::Ok);
+
+            let mut area = 0.0;
+            assert_eq!(cad_shape_area(doc, b, &mut area), CadStatus::Ok);
+            assert_eq!(area, 4.0);
+            let mut bbox = [0.0; 4];
+            assert_eq!(cad_shape_bbox(doc, b, bbox.as_mut_ptr()), CadStatus::Ok);
+            assert_eq!(bbox, [1.0, 1.0, 3.0, 3.0]);
+
+            // Too little room reports the size needed and adds nothing
+            let mut count = 0;
+            assert_eq!(cad_shapes_boolean(doc, a, b, 1, null_mut(), 0, &mut count), CadStatus::BufferTooSmall);
+            assert_eq!(count, 1);
+            let mut ids = [0; 1];
+            assert_eq!(cad_shapes_boolean(doc, a, b, 1, ids.as_mut_ptr(), 1, &mut count), CadStatus::Ok);
+            assert_eq!(cad_shape_area(doc, ids[0], &mut area), CadStatus::Ok);
+            assert!((area - 1.0).abs() < 1e-12);
+            let mut shapes = 0;
+            assert_eq!(cad_document_shape_count(doc, &mut shapes), CadStatus::Ok);
+            assert_eq!(shapes, 3);
+
+            let mut svg = CadBuffer { data: null_mut(), len: 0 };
+            assert_eq!(cad_document_export(doc, c"svg".as_ptr(), 3, &mut svg), CadStatus::Ok);
+            assert!(std::str::from_utf8(std::slice::from_raw_parts(svg.data, svg.len)).unwrap().starts_with("<svg"));
+            assert_eq!(cad_buffer_free(&mut svg), CadStatus::Ok);
+            assert_eq!(cad_buffer_free(&mut svg), CadStatus::Ok);
+            let mut bytes = CadBuffer { data: null_mut(), len: 0 };
+            assert_eq!(cad_document_to_bytes(doc, &mut bytes), CadStatus::Ok);
+            assert!(bytes.len > 0);
+            assert_eq!(cad_buffer_free(&mut bytes), CadStatus::Ok);
+
+            assert_eq!(cad_document_export(doc, c"pdf".as_ptr(), -1, &mut svg), CadStatus::InvalidInput);
+            assert!(CStr::from_ptr(cad_last_error_message()).to_str().unwrap().contains("pdf"));
+            assert_eq!(cad_document_free(doc), CadStatus::Ok);
+        }
+    }
+
+    #[test]
+    fn test_misuse_returns_error_codes() {
+        unsafe {
+            let mut doc = 0;
+            assert_eq!(cad_document_new(&mut doc), CadStatus::Ok);
+            let mut id = 0;
+            let mut area = 0.0;
+            assert_eq!(cad_document_add_polygon(doc, null(), 6, &mut id), CadStatus::NullPointer);
+            assert_eq!(cad_document_add_polygon(doc, [0.0, 0.0, 1.0].as_ptr(), 3, &mut id), CadStatus::InvalidInput);
+            assert_eq!(cad_shape_area(doc, 7, &mut area), CadStatus::UnknownShape);
+            assert_eq!(cad_shape_area(doc, 7, null_mut()), CadStatus::UnknownShape);
+            assert_eq!(cad_document_new(null_mut()), CadStatus::NullPointer);
+
+            assert_eq!(cad_document_free(doc), CadStatus::Ok);
+            assert_eq!(cad_document_free(doc), CadStatus::InvalidHandle);
+            assert_eq!(cad_shape_area(doc, 0, &mut area), CadStatus::InvalidHandle);
+            assert_eq!(cad_document_free(12345), CadStatus::InvalidHandle);
+            assert_eq!(cad_buffer_free(null_mut()), CadStatus::NullPointer);
+            assert_eq!(CStr::from_ptr(cad_status_name(CadStatus::InvalidHandle)).to_str().unwrap(), "invalid handle");
+            // A new document never gets the freed handle back
+            let mut next = 0;
+            assert_eq!(cad_document_new(&mut next), CadStatus::Ok);
+            assert_ne!(next, doc);
+            assert_eq!(cad_document_free(next), CadStatus::Ok);
+        }
+    }
+}
@@ -0,0 +1,268 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::{js_error, CadError, Point};
+
+// ISO 286-1 tables, in micrometres, for nominal sizes over each bound up to
+// and including the next, in millimetres
+const RANGES: [f64; 14] = [0.0, 3.0, 6.0, 10.0, 18.0, 30.0, 50.0, 80.0, 120.0, 180.0, 250.0, 315.0, 400.0, 500.0];
+
+// Standard tolerance grades IT4 to IT12; IT4 only for the holes' delta
+const IT: [[i32; 13]; 9] = [
+    [3, 4, 4, 5, 6, 7, 8, 10, 12, 14, 16, 18, 20],
+    [4, 5, 6, 8, 9, 11, 13, 15, 18, 20, 23, 25, 27],
+    [6, 8, 9, 11, 13, 16, 19, 22, 25, 29, 32, 36, 40],
+    [10, 12, 15, 18, 21, 25, 30, 35, 40, 46, 52, 57, 63],
+    [14, 18, 22, 27, 33, 39, 46, 54, 63, 72, 81, 89, 97],
+    [25, 30, 36, 43, 52, 62, 74, 87, 100, 115, 130, 140, 155],
+    [40, 48, 58, 70, 84, 100, 120, 140, 160, 185, 210, 230, 250],
+    [60, 75, 90, 110, 130, 160, 190, 220, 250, 290, 320, 360, 400],
+    [100, 120, 150, 180, 210, 250, 300, 350, 400, 460, 520, 570, 630],
+];
+
+// Shaft fundamental deviations: upper (es) for d to h, lower (ei) for k to p
+const D: [i32; 13] = [-20, -30, -40, -50, -65, -80, -100, -120, -145, -170, -190, -210, -230];
+const E: [i32; 13] = [-14, -20, -25, -32, -40, -50, -60, -72, -85, -100, -110, -125, -135];
+const F: [i32; 13] = [-6, -10, -13, -16, -20, -25, -30, -36, -43, -50, -56, -62, -68];
+const G: [i32; 13] = [-2, -4, -5, -6, -7, -9, -10, -12, -14, -15, -17, -18, -20];
+// k for IT4 to IT7; other grades of k sit on the nominal size
+const K: [i32; 13] = [0, 1, 1, 1, 2, 2, 2, 3, 3, 4, 4, 4, 5];
+const M: [i32; 13] = [2, 4, 6, 7, 8, 9, 11, 13, 15, 17, 20, 21, 23];
+const N: [i32; 13] = [4, 8, 10, 12, 15, 17, 20, 23, 27, 31, 34, 37, 40];
+const P: [i32; 13] = [6, 12, 15, 18, 22, 26, 32, 37, 43, 50, 56, 62, 68];
+
+const SUPPORTED: &str = "holes D, E, F, G, H, JS, K, M, N, P and shafts d, e, f, g, h, js, k, m, n, p, grades 5 to 12, sizes up to 500 mm";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FitFeature {
+    Hole,
+    Shaft,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FitKind {
+    Clearance,
+    Transition,
+    Interference,
+}
+
+// Maximum material (smallest hole, largest shaft) or least material
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MaterialLimit {
+    Maximum,
+    Least,
+}
+
+impl MaterialLimit {
+    pub fn parse(name: &str) -> Result<MaterialLimit, CadError> {
+        match name {
+            "mmc" => Ok(MaterialLimit::Maximum),
+            "lmc" => Ok(MaterialLimit::Least),
+            _ => Err(CadError::InvalidInput(format!("unknown material limit '{}', expected mmc or lmc", name))),
+        }
+    }
+}
+
+// Limits of one toleranced diameter; deviations and sizes in millimetres
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FeatureLimits {
+    pub feature: FitFeature,
+    pub designation: String,
+    pub upper_deviation: f64,
+    pub lower_deviation: f64,
+    pub max_size: f64,
+    pub min_size: f64,
+}
+
+impl FeatureLimits {
+    pub fn size_at(&self, limit: MaterialLimit) -> f64 {
+        match (self.feature, limit) {
+            (FitFeature::Hole, MaterialLimit::Maximum) | (FitFeature::Shaft, MaterialLimit::Least) => self.min_size,
+            _ => self.max_size,
+        }
+    }
+}
+
+// Clearance is the hole less the shaft, negative for interference
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FitResult {
+    pub nominal: f64,
+    pub hole: FeatureLimits,
+    pub shaft: FeatureLimits,
+    pub min_clearance: f64,
+    pub max_clearance: f64,
+    pub kind: FitKind,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Circle {
+    pub center: Point,
+    pub radius: f64,
+}
+
+impl Circle {
+    pub fn new(center: Point, radius: f64) -> Circle {
+        Circle { center, radius }
+    }
+
+    // This circle resized to `limits` at the given material condition, e.g.
+    // the smallest hole a toleranced bore can be, for overlay on the nominal
+    pub fn at_limit(&self, limits: &FeatureLimits, limit: MaterialLimit) -> Circle {
+        Circle::new(self.center, limits.size_at(limit) / 2.0)
+    }
+}
+
+fn unsupported(designation: &str) -> CadError {
+    CadError::InvalidInput(format!("unsupported tolerance '{}': supported are {}", designation, SUPPORTED))
+}
+
+// Upper and lower deviation in micrometres
+fn deviations(letters: &str, grade: usize, range: usize) -> Option<(f64, f64)> {
+    let it = |g: usize| IT[g - 4][range];
+    let tolerance = it(grade);
+    let upper = |es: i32| (es as f64, (es - tolerance) as f64);
+    let lower = |ei: i32| ((ei + tolerance) as f64, ei as f64);
+    // Holes K to P take the shaft's deviation mirrored, shifted by delta in
+    // the finer grades so that like fits match in either system
+    let delta = it(grade) - it(grade - 1);
+    // js7 to js11 round odd tolerances down to whole micrometres
+    let half = if (7..=11).contains(&grade) { (tolerance / 2) as f64 } else { tolerance as f64 / 2.0 };
+    Some(match letters {
+        "d" => upper(D[range]),
+        "e" => upper(E[range]),
+        "f" => upper(F[range]),
+        "g" => upper(G[range]),
+        "h" => upper(0),
+        "k" => lower(if grade <= 7 { K[range] } else { 0 }),
+        "m" => lower(M[range]),
+        "n" => lower(N[range]),
+        "p" => lower(P[range]),
+        "js" | "JS" => (half, -half),
+        "D" => lower(-D[range]),
+        "E" => lower(-E[range]),
+        "F" => lower(-F[range]),
+        "G" => lower(-G[range]),
+        "H" => lower(0),
+        "K" if grade <= 8 => upper(-K[range] + delta),
+        "K" => upper(0),
+        "M" if grade <= 8 => upper(-M[range] + delta),
+        "M" => upper(-M[range]),
+        "N" if grade <= 8 => upper(-N[range] + delta),
+        "N" => upper(0),
+        "P" if grade <= 7 => upper(-P[range] + delta),
+        "P" => upper(-P[range]),
+        _ => return None,
+    })
+}
+
+// Limits of a hole (capital letters, e.g. "H7") or shaft ("g6") of the
+// given nominal diameter in millimetres
+pub fn iso_tolerance(nominal: f64, designation: &str) -> Result<FeatureLimits, CadError> {
+    if !(nominal > 0.0 && nominal <= RANGES[RANGES.len() - 1]) {
+        return Err(CadError::InvalidInput(format!("nominal size {} is outside 0 to 500 mm", nominal)));
+    }
+    let split = designation.find(|c: char| c.is_ascii_digit()).ok_or_else(|| unsupported(designation))?;
+    let (letters, grade) = designation.split_at(split);
+    let grade: usize = grade.parse().ok().filter(|g| (5..=12).contains(g)).ok_or_else(|| unsupported(designation))?;
+    let range = RANGES.windows(2).position(|w| nominal > w[0] && nominal <= w[1]).unwrap();
+    let (upper, lower) = deviations(letters, grade, range).ok_or_else(|| unsupported(designation))?;
+    let feature = if letters.chars().all(|c| c.is_ascii_uppercase()) { FitFeature::Hole } else { FitFeature::Shaft };
+    let (upper_deviation, lower_deviation) = (upper / 1000.0, lower / 1000.0);
+    Ok(FeatureLimits {
+        feature,
+        designation: designation.to_string(),
+        upper_deviation,
+        lower_deviation,
+        max_size: nominal + upper_deviation,
+        min_size: nominal + lower_deviation,
+    })
+}
+
+pub fn iso_fit(nominal: f64, hole_grade: &str, shaft_grade: &str) -> Result<FitResult, CadError> {
+    let hole = iso_tolerance(nominal, hole_grade)?;
+    let shaft = iso_tolerance(nominal, shaft_grade)?;
+    if hole.feature != FitFeature::Hole || shaft.feature != FitFeature::Shaft {
+        return Err(CadError::InvalidInput(format!("expected a hole then a shaft (e.g. H7/g6), got {}/{}", hole_grade, shaft_grade)));
+    }
+    let min_clearance = hole.min_size - shaft.max_size;
+    let max_clearance = hole.max_size - shaft.min_size;
+    let kind = if min_clearance >= 0.0 {
+        FitKind::Clearance
+    } else if max_clearance <= 0.0 {
+        FitKind::Interference
+    } else {
+        FitKind::Transition
+    };
+    Ok(FitResult { nominal, hole, shaft, min_clearance, max_clearance, kind })
+}
+
+#[wasm_bindgen(js_name = iso_fit)]
+pub fn iso_fit_js(nominal: f64, hole_grade: &str, shaft_grade: &str) -> Result<JsValue, JsValue> {
+    crate::to_js(&iso_fit(nominal, hole_grade, shaft_grade).map_err(js_error)?)
+}
+
+#[wasm_bindgen(js_name = iso_tolerance)]
+pub fn iso_tolerance_js(nominal: f64, designation: &str) -> Result<JsValue, JsValue> {
+    crate::to_js(&iso_tolerance(nominal, designation).map_err(js_error)?)
+}
+
+// { center, radius } of the hole or shaft at "mmc" or "lmc"
+#[wasm_bindgen]
+pub fn material_limit_circle(nominal: f64, designation: &str, cx: f64, cy: f64, limit: &str) -> Result<JsValue, JsValue> {
+    let limits = iso_tolerance(nominal, designation).map_err(js_error)?;
+    let limit = MaterialLimit::parse(limit).map_err(js_error)?;
+    let circle = Circle::new(Point::new(cx, cy), nominal / 2.0).at_limit(&limits, limit);
+    crate::to_js(&circle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_h7_g6_at_25_mm() {
+        let fit = iso_fit(25.0, "H7", "g6").unwrap();
+        // Published limits: hole 25.000 / 25.021, shaft 24.980 / 24.993
+        assert!(close(fit.hole.min_size, 25.0) && close(fit.hole.max_size, 25.021));
+        assert!(close(fit.shaft.min_size, 24.980) && close(fit.shaft.max_size, 24.993));
+        assert!(close(fit.min_clearance, 0.007) && close(fit.max_clearance, 0.041));
+        assert_eq!(fit.kind, FitKind::Clearance);
+
+        let nominal = Circle::new(Point::new(3.0, 4.0), 12.5);
+        let smallest_hole = nominal.at_limit(&fit.hole, MaterialLimit::Maximum);
+        let largest_shaft = nominal.at_limit(&fit.shaft, MaterialLimit::Maximum);
+        assert!(close(smallest_hole.radius, 12.5) && close(largest_shaft.radius, 24.993 / 2.0));
+        assert!(close(nominal.at_limit(&fit.hole, MaterialLimit::Least).radius, 25.021 / 2.0));
+        assert!(close(nominal.at_limit(&fit.shaft, MaterialLimit::Least).radius, 12.49));
+        assert_eq!(largest_shaft.center, nominal.center);
+    }
+
+    #[test]
+    fn test_transition_and_interference_fits() {
+        // H7/k6 and H7/p6 at 25 mm: k6 +0.002 / +0.015, p6 +0.022 / +0.035
+        let k = iso_fit(25.0, "H7", "k6").unwrap();
+        assert!(close(k.shaft.lower_deviation, 0.002) && close(k.shaft.upper_deviation, 0.015));
+        assert_eq!(k.kind, FitKind::Transition);
+        let p = iso_fit(25.0, "H7", "p6").unwrap();
+        assert!(close(p.min_clearance, 0.0 - 0.035) && close(p.max_clearance, 0.021 - 0.022));
+        assert_eq!(p.kind, FitKind::Interference);
+        // Holes below H, with their delta: K7 +0.006 / -0.015, N7 -0.007 / -0.028
+        let k7 = iso_tolerance(25.0, "K7").unwrap();
+        assert!(close(k7.upper_deviation, 0.006) && close(k7.lower_deviation, -0.015));
+        let n7 = iso_tolerance(25.0, "N7").unwrap();
+        assert!(close(n7.upper_deviation, -0.007) && close(n7.lower_deviation, -0.028));
+        let f7 = iso_tolerance(50.0, "f7").unwrap();
+        assert!(close(f7.upper_deviation, -0.025) && close(f7.lower_deviation, -0.050));
+        assert!(close(iso_tolerance(25.0, "js6").unwrap().upper_deviation, 0.0065));
+
+        let error = iso_fit(25.0, "H7", "z6").unwrap_err().to_string();
+        assert!(error.contains("z6") && error.contains("supported are holes D"));
+        assert!(iso_fit(25.0, "H4", "g6").is_err());
+        assert!(iso_fit(25.0, "g6", "H7").is_err());
+        assert!(iso_fit(600.0, "H7", "g6").is_err());
+    }
+}
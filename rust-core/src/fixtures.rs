@@ -0,0 +1,365 @@
+use std::fmt::Write;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use crate::{BooleanOp, JoinStyle, MultiPolygon, Point, Polygon, PolygonWithHoles, Segment};
+
+// One ring of a fixture, as literal vertices or a regular polygon
+// approximating a circle
+#[derive(Clone, Copy, Debug)]
+pub enum FixtureRing {
+    Points(&'static [(f64, f64)]),
+    Circle { center: (f64, f64), radius: f64, segments: usize },
+}
+
+impl FixtureRing {
+    pub fn polygon(&self) -> Polygon {
+        match *self {
+            FixtureRing::Points(points) => Polygon::new(points.iter().map(|&(x, y)| Point::new(x, y)).collect()),
+            FixtureRing::Circle { center, radius, segments } => Polygon::new((0..segments).map(|i| {
+                let a = std::f64::consts::TAU * i as f64 / segments as f64;
+                Point::new(center.0 + radius * a.cos(), center.1 + radius * a.sin())
+            }).collect()),
+        }
+    }
+}
+
+// Geometry that once broke, or nearly broke, an algorithm. Every ring goes
+// through the single-shape algorithms and the first two through the
+// boolean ones. `valid` fixtures pass Polygon::validate and are held to
+// the stricter invariants; the rest only have to come out finite, stable
+// and without panicking.
+#[derive(Clone, Copy, Debug)]
+pub struct Fixture {
+    pub name: &'static str,
+    pub rings: &'static [FixtureRing],
+    pub valid: bool,
+}
+
+// Next float above 1
+const ONE_ULP_UP: f64 = 1.0000000000000002;
+
+pub const CORPUS: &[Fixture] = &[
+    Fixture { name: "nearly_collinear_triangle", rings: &[FixtureRing::Points(&[(0.0, 0.0), (10.0, 1e-12), (20.0, 0.0)])], valid: false },
+    Fixture { name: "thin_triangle", rings: &[FixtureRing::Points(&[(0.0, 0.0), (1000.0, 1e-6), (2000.0, 0.0)])], valid: true },
+    Fixture { name: "sliver_rectangle", rings: &[FixtureRing::Points(&[(0.0, 0.0), (1000.0, 0.0), (1000.0, 1e-6), (0.0, 1e-6)])], valid: true },
+    Fixture { name: "jittered_baseline", rings: &[FixtureRing::Points(&[(0.0, 0.0), (1.0, 1e-13), (2.0, -1e-13), (3.0, 2e-13), (4.0, 0.0), (4.0, 1.0), (0.0, 1.0)])], valid: true },
+    Fixture {
+        name: "offset_1e7_squares",
+        rings: &[
+            FixtureRing::Points(&[(1e7, 1e7), (1e7 + 1.0, 1e7), (1e7 + 1.0, 1e7 + 1.0), (1e7, 1e7 + 1.0)]),
+            FixtureRing::Points(&[(1e7 + 0.5, 1e7 + 0.25), (1e7 + 1.5, 1e7 + 0.25), (1e7 + 1.5, 1e7 + 1.25), (1e7 + 0.5, 1e7 + 1.25)]),
+        ],
+        valid: true,
+    },
+    Fixture {
+        name: "offset_1e7_concave",
+        rings: &[FixtureRing::Points(&[(1e7, -1e7), (1e7 + 3.0, -1e7), (1e7 + 3.0, -1e7 + 3.0), (1e7 + 1.5, -1e7 + 0.001), (1e7, -1e7 + 3.0)])],
+        valid: true,
+    },
+    Fixture {
+        name: "almost_tangent_circles",
+        rings: &[
+            FixtureRing::Circle { center: (0.0, 0.0), radius: 1.0, segments: 96 },
+            FixtureRing::Circle { center: (2.000000001, 0.0), radius: 1.0, segments: 96 },
+        ],
+        valid: true,
+    },
+    Fixture {
+        name: "barely_overlapping_circles",
+        rings: &[
+            FixtureRing::Circle { center: (0.0, 0.0), radius: 1.0, segments: 96 },
+            FixtureRing::Circle { center: (1.9999999, 0.0), radius: 1.0, segments: 96 },
+        ],
+        valid: true,
+    },
+    Fixture { name: "bowtie", rings: &[FixtureRing::Points(&[(0.0, 0.0), (2.0, 2.0), (2.0, 0.0), (0.0, 2.0)])], valid: false },
+    Fixture { name: "bowtie_through_vertex", rings: &[FixtureRing::Points(&[(0.0, 0.0), (1.0, 1.0), (2.0, 0.0), (2.0, 2.0), (1.0, 1.0), (0.0, 2.0)])], valid: false },
+    Fixture {
+        name: "rings_one_ulp_apart",
+        rings: &[
+            FixtureRing::Points(&[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]),
+            FixtureRing::Points(&[(0.0, 0.0), (ONE_ULP_UP, 0.0), (1.0, ONE_ULP_UP), (0.0, 1.0)]),
+        ],
+        valid: true,
+    },
+    Fixture { name: "duplicate_vertices", rings: &[FixtureRing::Points(&[(0.0, 0.0), (1.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)])], valid: false },
+];
+
+// Rust source for a fixture, ready to paste into CORPUS. When a bug report
+// comes with geometry, load the rings as Polygons and print this; floats
+// are written so they read back bit for bit.
+pub fn fixture_source(name: &str, rings: &[Polygon], valid: bool) -> String {
+    let mut out = format!("Fixture {{\n    name: {:?},\n    rings: &[\n", name);
+    for ring in rings {
+        let points: Vec<String> = ring.vertices.iter().map(|p| format!("({:?}, {:?})", p.x, p.y)).collect();
+        writeln!(out, "        FixtureRing::Points(&[{}]),", points.join(", ")).unwrap();
+    }
+    write!(out, "    ],\n    valid: {},\n}},\n", valid).unwrap();
+    out
+}
+
+// An invariant that didn't hold
+#[derive(Clone, Debug, PartialEq)]
+pub struct Violation {
+    pub fixture: &'static str,
+    pub algorithm: &'static str,
+    pub message: String,
+}
+
+// A fixture's rings, with the tolerances the invariants are checked to
+pub struct Prepared {
+    pub polygons: Vec<Polygon>,
+    pub valid: bool,
+    // Largest extent of any ring
+    pub scale: f64,
+    pub area_tolerance: f64,
+    pub length_tolerance: f64,
+}
+
+impl Prepared {
+    pub fn new(fixture: &Fixture) -> Prepared {
+        let polygons: Vec<Polygon> = fixture.rings.iter().map(FixtureRing::polygon).collect();
+        let scale = polygons.iter().map(|p| p.bounding_box()).map(|b| b.width().max(b.height())).fold(0.0, f64::max).max(1e-9);
+        Prepared { polygons, valid: fixture.valid, scale, area_tolerance: 1e-6 * scale * scale, length_tolerance: 1e-6 * scale }
+    }
+}
+
+// Each algorithm's run over a fixture: a digest of what it produced, for
+// the stability check, or the first invariant it broke
+type Adapter = fn(&Prepared) -> Result<String, String>;
+
+fn ensure(holds: bool, message: impl FnOnce() -> String) -> Result<(), String> {
+    if holds { Ok(()) } else { Err(message()) }
+}
+
+fn finite(points: &[Point], what: &str) -> Result<(), String> {
+    match points.iter().position(|p| !p.x.is_finite() || !p.y.is_finite()) {
+        Some(i) => Err(format!("{} has a non-finite point at {}: {:?}", what, i, points[i])),
+        None => Ok(()),
+    }
+}
+
+fn close(a: f64, b: f64, tolerance: f64, what: &str) -> Result<(), String> {
+    ensure((a - b).abs() <= tolerance, || format!("{}: {} and {} differ by more than {}", what, a, b, tolerance))
+}
+
+fn valid(polygon: &Polygon, what: &str) -> Result<(), String> {
+    polygon.validate().map_err(|e| format!("{} fails validation: {:?}", what, e))
+}
+
+fn area_check(p: &Prepared) -> Result<String, String> {
+    let areas: Vec<f64> = p.polygons.iter().map(Polygon::area).collect();
+    for (i, a) in areas.iter().enumerate() {
+        ensure(a.is_finite() && *a >= 0.0, || format!("ring {} has area {}", i, a))?;
+        // Area doesn't depend on where the ring starts
+        let mut rotated = p.polygons[i].vertices.clone();
+        rotated.rotate_left(1);
+        close(Polygon::new(rotated).area(), *a, p.area_tolerance, "area from the next vertex")?;
+    }
+    Ok(format!("{:?}", areas))
+}
+
+fn centroid_check(p: &Prepared) -> Result<String, String> {
+    let centroids: Vec<Point> = p.polygons.iter().map(Polygon::centroid).collect();
+    finite(&centroids, "centroids")?;
+    for (polygon, c) in p.polygons.iter().zip(&centroids) {
+        let b = polygon.bounding_box();
+        let t = p.length_tolerance;
+        ensure(c.x >= b.min.x - t && c.x <= b.max.x + t && c.y >= b.min.y - t && c.y <= b.max.y + t, || format!("centroid {:?} outside the bounding box {:?}", c, b))?;
+    }
+    Ok(format!("{:?}", centroids))
+}
+
+fn hull_check(p: &Prepared) -> Result<String, String> {
+    let hulls: Vec<Polygon> = p.polygons.iter().map(Polygon::convex_hull).collect();
+    for (polygon, hull) in p.polygons.iter().zip(&hulls) {
+        finite(&hull.vertices, "hull")?;
+        if p.valid {
+            valid(hull, "hull")?;
+            ensure(hull.is_convex(), || format!("hull {:?} is not convex", hull.vertices))?;
+            ensure(hull.area() >= polygon.area() - p.area_tolerance, || format!("hull area {} below the polygon's {}", hull.area(), polygon.area()))?;
+        }
+    }
+    Ok(format!("{:?}", hulls))
+}
+
+fn triangulate_check(p: &Prepared) -> Result<String, String> {
+    let mut digest = String::new();
+    for polygon in &p.polygons {
+        let triangles = polygon.triangulate();
+        let n = polygon.vertices.len();
+        ensure(triangles.iter().flatten().all(|&i| i < n), || format!("triangle index out of range for {} vertices", n))?;
+        if p.valid {
+            let covered: f64 = triangles.iter().map(|t| Polygon::new(t.iter().map(|&i| polygon.vertices[i]).collect()).area()).sum();
+            close(covered, polygon.area(), p.area_tolerance, "triangle areas against the polygon's")?;
+        }
+        write!(digest, "{:?};", triangles).unwrap();
+    }
+    Ok(digest)
+}
+
+fn regions_check(regions: &[PolygonWithHoles], strict: bool, what: &str) -> Result<f64, String> {
+    for r in regions {
+        finite(&r.outer.vertices, what)?;
+        for h in &r.holes {
+            finite(&h.vertices, what)?;
+        }
+        if strict {
+            valid(&r.outer, what)?;
+            for h in &r.holes {
+                valid(h, what)?;
+            }
+        }
+    }
+    let area: f64 = regions.iter().map(PolygonWithHoles::area).sum();
+    ensure(area.is_finite(), || format!("{} has area {}", what, area))?;
+    Ok(area)
+}
+
+fn boolean_check(p: &Prepared) -> Result<String, String> {
+    let (a, b) = match p.polygons.as_slice() {
+        [a, b, ..] => (a, b),
+        [a] => (a, a),
+        [] => return Ok(String::new()),
+    };
+    let run = |op| a.boolean_regions(b, op);
+    let (union, intersection, difference) = (run(BooleanOp::Union), run(BooleanOp::Intersection), run(BooleanOp::Difference));
+    let u = regions_check(&union, p.valid, "union")?;
+    let i = regions_check(&intersection, p.valid, "intersection")?;
+    let d = regions_check(&difference, p.valid, "difference")?;
+    if p.valid {
+        close(u + i, a.area() + b.area(), p.area_tolerance, "union plus intersection against both areas")?;
+        close(d + i, a.area(), p.area_tolerance, "difference plus intersection against the first area")?;
+    }
+    Ok(format!("{:?}{:?}{:?}", union, intersection, difference))
+}
+
+fn offset_check(p: &Prepared) -> Result<String, String> {
+    let distance = 0.05 * p.scale;
+    let mut digest = String::new();
+    for polygon in &p.polygons {
+        for join in [JoinStyle::Miter { limit: 4.0 }, JoinStyle::Round { segments: 32 }, JoinStyle::Bevel] {
+            let grown: MultiPolygon = polygon.offset(distance, join);
+            let shrunk: MultiPolygon = polygon.offset(-distance, join);
+            for piece in grown.iter().chain(&shrunk) {
+                finite(&piece.vertices, "offset")?;
+            }
+            if p.valid {
+                let area: f64 = grown.iter().map(Polygon::area).sum();
+                ensure(area >= polygon.area() - p.area_tolerance, || format!("outward {:?} offset has area {} below the polygon's {}", join, area, polygon.area()))?;
+                let inner: f64 = shrunk.iter().map(Polygon::area).sum();
+                ensure(inner <= polygon.area() + p.area_tolerance, || format!("inward {:?} offset has area {} above the polygon's {}", join, inner, polygon.area()))?;
+            }
+            write!(digest, "{:?}{:?}", grown, shrunk).unwrap();
+        }
+    }
+    Ok(digest)
+}
+
+fn simplify_check(p: &Prepared) -> Result<String, String> {
+    let simplified: Vec<Polygon> = p.polygons.iter().map(|polygon| polygon.simplify(1e-3 * p.scale)).collect();
+    for (polygon, s) in p.polygons.iter().zip(&simplified) {
+        finite(&s.vertices, "simplified")?;
+        ensure(s.vertices.len() <= polygon.vertices.len(), || format!("simplifying went from {} to {} vertices", polygon.vertices.len(), s.vertices.len()))?;
+    }
+    Ok(format!("{:?}", simplified))
+}
+
+fn intersections_check(p: &Prepared) -> Result<String, String> {
+    let mut digest = String::new();
+    for (k, polygon) in p.polygons.iter().enumerate() {
+        // Lines across the box, and each other ring's edges
+        let b = polygon.bounding_box();
+        let m = 0.1 * p.scale;
+        let mut probes = vec![
+            Segment::new(Point::new(b.min.x - m, b.center().y), Point::new(b.max.x + m, b.center().y)),
+            Segment::new(Point::new(b.min.x - m, b.min.y - m), Point::new(b.max.x + m, b.max.y + m)),
+        ];
+        for (_, other) in p.polygons.iter().enumerate().filter(|&(j, _)| j != k) {
+            let n = other.vertices.len();
+            probes.extend((0..n).map(|i| Segment::new(other.vertices[i], other.vertices[(i + 1) % n])));
+        }
+        let n = polygon.vertices.len();
+        for probe in &probes {
+            let points = polygon.intersect_segment(probe);
+            finite(&points, "intersections")?;
+            for q in &points {
+                ensure(probe.distance_to_point(q) <= p.length_tolerance, || format!("intersection {:?} is off the probe segment", q))?;
+                let to_boundary = (0..n).map(|i| Segment::new(polygon.vertices[i], polygon.vertices[(i + 1) % n]).distance_to_point(q)).fold(f64::INFINITY, f64::min);
+                ensure(to_boundary <= p.length_tolerance, || format!("intersection {:?} is {} off the boundary", q, to_boundary))?;
+            }
+            write!(digest, "{:?};", points).unwrap();
+        }
+    }
+    Ok(digest)
+}
+
+pub const ALGORITHMS: &[(&str, Adapter)] = &[
+    ("area", area_check),
+    ("centroid", centroid_check),
+    ("hull", hull_check),
+    ("triangulate", triangulate_check),
+    ("boolean", boolean_check),
+    ("offset", offset_check),
+    ("simplify", simplify_check),
+    ("intersections", intersections_check),
+];
+
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    payload.downcast_ref::<&str>().map(|s| s.to_string()).or_else(|| payload.downcast_ref::<String>().cloned()).unwrap_or_else(|| "unknown panic".into())
+}
+
+// Runs every algorithm over the fixture twice, reporting panics, broken
+// invariants and results that differ between the runs
+pub fn check_fixture(fixture: &Fixture) -> Vec<Violation> {
+    let prepared = Prepared::new(fixture);
+    if let Err(message) = valid_as_declared(fixture, &prepared) {
+        return vec![Violation { fixture: fixture.name, algorithm: "validate", message }];
+    }
+    let mut violations = Vec::new();
+    for &(algorithm, adapter) in ALGORITHMS {
+        let runs: Vec<Result<String, String>> = (0..2).map(|_| {
+            catch_unwind(AssertUnwindSafe(|| adapter(&prepared))).unwrap_or_else(|payload| Err(format!("panicked: {}", panic_message(&*payload))))
+        }).collect();
+        let message = match (&runs[0], &runs[1]) {
+            (Err(e), _) | (_, Err(e)) => Some(e.clone()),
+            (Ok(a), Ok(b)) if a != b => Some("results differ between two runs".into()),
+            _ => None,
+        };
+        if let Some(message) = message {
+            violations.push(Violation { fixture: fixture.name, algorithm, message });
+        }
+    }
+    violations
+}
+
+fn valid_as_declared(fixture: &Fixture, prepared: &Prepared) -> Result<(), String> {
+    let checks: Vec<bool> = prepared.polygons.iter().map(|p| p.validate().is_ok()).collect();
+    ensure(!fixture.valid || checks.iter().all(|&ok| ok), || format!("declared valid, but validation gives {:?}", checks))
+}
+
+pub fn check_corpus(corpus: &[Fixture]) -> Vec<Violation> {
+    corpus.iter().flat_map(check_fixture).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_corpus_holds_every_invariant() {
+        let violations = check_corpus(CORPUS);
+        let report: Vec<String> = violations.iter().map(|v| format!("{} / {}: {}", v.fixture, v.algorithm, v.message)).collect();
+        assert!(violations.is_empty(), "{} invariant violations:\n{}", violations.len(), report.join("\n"));
+    }
+
+    #[test]
+    fn test_harness_reports_breakage_and_source_round_trips() {
+        // A self-crossing ring declared valid is caught before anything runs
+        let wrong = Fixture { name: "mislabelled", rings: CORPUS.iter().find(|f| f.name == "bowtie").unwrap().rings, valid: true };
+        assert_eq!(check_fixture(&wrong)[0].algorithm, "validate");
+        let source = fixture_source("report", &[Polygon::new(vec![Point::new(0.1, 0.0), Point::new(ONE_ULP_UP, 1e7), Point::new(-3.0, 2.5e-12)])], false);
+        assert!(source.contains("FixtureRing::Points(&[(0.1, 0.0), (1.0000000000000002, 10000000.0), (-3.0, 2.5e-12)])"), "{}", source);
+        assert!(source.contains("valid: false"));
+    }
+}
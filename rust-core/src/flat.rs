@@ -0,0 +1,103 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{BoundingBox, CadError, Matrix, Point};
+
+// Flat batches of polygons: `coords` is [x0, y0, x1, y1, ...] for every
+// vertex of every polygon in turn, and `offsets` the index of each polygon's
+// first vertex (in vertices, not numbers), so polygon i runs up to the start
+// of polygon i + 1 or the end of `coords`
+
+fn ranges(coords: &[f64], offsets: &[u32]) -> Result<Vec<(usize, usize)>, CadError> {
+    if !coords.len().is_multiple_of(2) {
+        return Err(CadError::InvalidInput(format!("flat coordinates need x, y pairs, got {} numbers", coords.len())));
+    }
+    let count = coords.len() / 2;
+    let starts: Vec<usize> = offsets.iter().map(|&o| o as usize).collect();
+    let ends = starts.iter().skip(1).copied().chain(std::iter::once(count));
+    starts.iter().zip(ends).map(|(&start, end)| {
+        if start > end || end > count {
+            return Err(CadError::InvalidInput(format!("polygon offset {} is out of order or past the {} vertices", start, count)));
+        }
+        Ok((start, end))
+    }).collect()
+}
+
+// Every vertex transformed by `matrix`, in the same layout
+pub fn transform_polygons_flat(coords: &[f64], offsets: &[u32], matrix: &Matrix) -> Result<Vec<f64>, CadError> {
+    ranges(coords, offsets)?;
+    let mut out = Vec::with_capacity(coords.len());
+    for xy in coords.chunks_exact(2) {
+        let p = matrix.transform_point(&Point::new(xy[0], xy[1]));
+        out.extend([p.x, p.y]);
+    }
+    Ok(out)
+}
+
+// [min_x, min_y, max_x, max_y] per polygon, as `Polygon::bounding_box`
+pub fn bounding_boxes_flat(coords: &[f64], offsets: &[u32]) -> Result<Vec<f64>, CadError> {
+    let mut out = Vec::with_capacity(offsets.len() * 4);
+    for (start, end) in ranges(coords, offsets)? {
+        let b = if start == end {
+            BoundingBox::from_points(&[])
+        } else {
+            let mut b = BoundingBox::new(Point::new(f64::INFINITY, f64::INFINITY), Point::new(f64::NEG_INFINITY, f64::NEG_INFINITY));
+            for xy in coords[2 * start..2 * end].chunks_exact(2) {
+                b.min = Point::new(b.min.x.min(xy[0]), b.min.y.min(xy[1]));
+                b.max = Point::new(b.max.x.max(xy[0]), b.max.y.max(xy[1]));
+            }
+            b
+        };
+        out.extend([b.min.x, b.min.y, b.max.x, b.max.y]);
+    }
+    Ok(out)
+}
+
+#[wasm_bindgen(js_name = transform_polygons_flat)]
+pub fn transform_polygons_flat_js(coords: &[f64], offsets: &[u32], matrix_data: &JsValue) -> Result<Vec<f64>, JsValue> {
+    transform_polygons_flat(coords, offsets, &crate::matrix_from(matrix_data)?).map_err(crate::js_error)
+}
+
+#[wasm_bindgen(js_name = bounding_boxes_flat)]
+pub fn bounding_boxes_flat_js(coords: &[f64], offsets: &[u32]) -> Result<Vec<f64>, JsValue> {
+    bounding_boxes_flat(coords, offsets).map_err(crate::js_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Polygon;
+
+    #[test]
+    fn test_flat_batches_match_per_polygon_calls() {
+        let mut seed = 7u64;
+        let mut next = || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 11) as f64 / (1u64 << 53) as f64 * 200.0 - 100.0
+        };
+        let polygons: Vec<Polygon> = (0..50).map(|i| Polygon::new((0..3 + i % 7).map(|_| Point::new(next(), next())).collect())).collect();
+        let mut coords = Vec::new();
+        let mut offsets = Vec::new();
+        for p in &polygons {
+            offsets.push((coords.len() / 2) as u32);
+            coords.extend(p.vertices.iter().flat_map(|v| [v.x, v.y]));
+        }
+
+        let matrix = Matrix::translate(3.0, -2.0).multiply(&Matrix::rotate(0.7)).multiply(&Matrix::scale(2.0, 0.5));
+        let expected: Vec<f64> = polygons.iter().flat_map(|p| p.transform(&matrix).vertices).flat_map(|v| [v.x, v.y]).collect();
+        assert_eq!(transform_polygons_flat(&coords, &offsets, &matrix).unwrap(), expected);
+        let expected: Vec<f64> = polygons.iter().map(|p| p.bounding_box()).flat_map(|b| [b.min.x, b.min.y, b.max.x, b.max.y]).collect();
+        assert_eq!(bounding_boxes_flat(&coords, &offsets).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_malformed_batches() {
+        let coords = [0.0, 0.0, 2.0, 1.0, 5.0, 5.0];
+        assert_eq!(bounding_boxes_flat(&coords, &[0, 2]).unwrap(), vec![0.0, 0.0, 2.0, 1.0, 5.0, 5.0, 5.0, 5.0]);
+        // An empty polygon has the empty box at the origin
+        assert_eq!(bounding_boxes_flat(&coords, &[0, 0]).unwrap(), vec![0.0; 4].into_iter().chain([0.0, 0.0, 5.0, 5.0]).collect::<Vec<_>>());
+        assert!(bounding_boxes_flat(&coords, &[2, 1]).is_err());
+        assert!(bounding_boxes_flat(&coords, &[0, 4]).is_err());
+        assert!(transform_polygons_flat(&coords[..5], &[0], &Matrix::identity()).is_err());
+        assert!(bounding_boxes_flat(&[], &[]).unwrap().is_empty());
+    }
+}
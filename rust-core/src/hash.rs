@@ -0,0 +1,100 @@
+use crate::{AttributeValue, Shape};
+
+// 64-bit FNV-1a. Unlike std's default hasher this has no per-process keys,
+// so hashes are stable across sessions and platforms.
+pub(crate) struct ContentHasher(u64);
+
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+impl ContentHasher {
+    pub(crate) fn new() -> ContentHasher {
+        ContentHasher(FNV_OFFSET)
+    }
+
+    pub(crate) fn bytes(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= *b as u64;
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    pub(crate) fn u64(&mut self, v: u64) {
+        self.bytes(&v.to_le_bytes());
+    }
+
+    // -0.0 hashes as 0.0 and every NaN as the same NaN
+    pub(crate) fn f64(&mut self, v: f64) {
+        let v = if v.is_nan() { f64::NAN } else { v + 0.0 };
+        self.u64(v.to_bits());
+    }
+
+    // Length-prefixed so adjacent strings can't run into each other
+    pub(crate) fn str(&mut self, s: &str) {
+        self.u64(s.len() as u64);
+        self.bytes(s.as_bytes());
+    }
+
+    pub(crate) fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+// Hash of everything that affects a shape's appearance or meaning: local
+// vertices, world transform, attributes (in key order), label and layer.
+// The id and pivot are left out, so a pasted copy hashes like its original.
+pub(crate) fn shape_hash(shape: &Shape) -> u64 {
+    let mut h = ContentHasher::new();
+    h.u64(shape.geometry.vertices.len() as u64);
+    for p in &shape.geometry.vertices {
+        h.f64(p.x);
+        h.f64(p.y);
+    }
+    let m = &shape.world_transform();
+    for v in [m.m11, m.m12, m.m21, m.m22, m.dx, m.dy] {
+        h.f64(v);
+    }
+
+    h.u64(shape.attributes.len() as u64);
+    for (key, value) in &shape.attributes {
+        h.str(key);
+        match value {
+            AttributeValue::Number(n) => {
+                h.bytes(&[0]);
+                h.f64(*n);
+            }
+            AttributeValue::Text(t) => {
+                h.bytes(&[1]);
+                h.str(t);
+            }
+        }
+    }
+    for optional in [&shape.label, &shape.layer] {
+        match optional {
+            Some(s) => {
+                h.bytes(&[1]);
+                h.str(s);
+            }
+            None => h.bytes(&[0]),
+        }
+    }
+    h.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fnv_reference_values() {
+        // Published FNV-1a 64 test vectors
+        let hash = |s: &str| {
+            let mut h = ContentHasher::new();
+            h.bytes(s.as_bytes());
+            h.finish()
+        };
+        assert_eq!(hash(""), 0xcbf29ce484222325);
+        assert_eq!(hash("a"), 0xaf63dc4c8601ec8c);
+        assert_eq!(hash("foobar"), 0x85944171f73967e8);
+    }
+}
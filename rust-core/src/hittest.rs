@@ -0,0 +1,147 @@
+use wasm_bindgen::prelude::*;
+
+use crate::predicates::{orient2d_robust, point_in_ring};
+use crate::{Point, Polygon, Segment};
+
+// Exactly on the closed segment ab
+fn on_segment(a: &Point, b: &Point, p: &Point) -> bool {
+    orient2d_robust(a, b, p) == 0.0
+        && p.x >= a.x.min(b.x) && p.x <= a.x.max(b.x)
+        && p.y >= a.y.min(b.y) && p.y <= a.y.max(b.y)
+}
+
+pub(crate) fn edges(vertices: &[Point]) -> impl Iterator<Item = Segment> + '_ {
+    let n = vertices.len();
+    // A lone point is a zero-length edge, and two points make one edge, not
+    // the same edge twice
+    let count = match n {
+        0 => 0,
+        1 | 2 => 1,
+        _ => n,
+    };
+    (0..count).map(move |i| Segment::new(vertices[i], vertices[(i + 1) % n]))
+}
+
+impl Polygon {
+    // Inside or exactly on the boundary, by even-odd rule. A polygon with
+    // fewer than three vertices encloses nothing, so only points exactly on
+    // its vertex or segment count; an empty polygon contains nothing.
+    pub fn contains_point(&self, p: &Point) -> bool {
+        edges(&self.vertices).any(|e| on_segment(&e.a, &e.b, p)) || point_in_ring(&self.vertices, p)
+    }
+
+    // Distance from `p` to the nearest point of the boundary, zero on it and
+    // positive on either side. Fewer than three vertices measure to the
+    // point or segment; an empty polygon is infinitely far away.
+    pub fn distance_to_point(&self, p: &Point) -> f64 {
+        edges(&self.vertices).map(|e| {
+            let q = e.closest_point(p);
+            (q.x - p.x).hypot(q.y - p.y)
+        }).fold(f64::INFINITY, f64::min)
+    }
+
+    // Whether `p` picks the polygon: inside it, or within `tolerance` of its
+    // boundary from outside
+    pub fn hit(&self, p: &Point, tolerance: f64) -> bool {
+        self.contains_point(p) || self.distance_to_point(p) <= tolerance
+    }
+
+    // Index of the edge nearest `p`, if it is within `tolerance`; edge `i`
+    // runs from vertex `i` to the next. Of equally near edges, e.g. at a
+    // vertex, the lower-numbered one wins.
+    pub fn pick_edge(&self, p: &Point, tolerance: f64) -> Option<usize> {
+        edges(&self.vertices).map(|e| e.distance_to_point(p)).enumerate()
+            .filter(|&(_, d)| d <= tolerance)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i)
+    }
+}
+
+#[wasm_bindgen]
+pub fn polygon_contains_point(vertices: &JsValue, x: f64, y: f64) -> Result<bool, JsValue> {
+    let polygon: Polygon = crate::polygon_from(vertices)?;
+    Ok(polygon.contains_point(&Point::new(x, y)))
+}
+
+#[wasm_bindgen]
+pub fn polygon_distance_to_point(vertices: &JsValue, x: f64, y: f64) -> Result<f64, JsValue> {
+    let polygon: Polygon = crate::polygon_from(vertices)?;
+    Ok(polygon.distance_to_point(&Point::new(x, y)))
+}
+
+#[wasm_bindgen]
+pub fn polygon_pick_edge(vertices: &JsValue, x: f64, y: f64, tolerance: f64) -> Result<Option<usize>, JsValue> {
+    let polygon: Polygon = crate::polygon_from(vertices)?;
+    Ok(polygon.pick_edge(&Point::new(x, y), tolerance))
+}
+
+// `points` is an array of {x, y}; returns one boolean per point, as `hit`.
+// Points outside the bounding box grown by `tolerance` are rejected without
+// walking the edges.
+#[wasm_bindgen]
+pub fn hit_test(vertices: &JsValue, points: &JsValue, tolerance: f64) -> Result<JsValue, JsValue> {
+    let polygon: Polygon = crate::polygon_from(vertices)?;
+    let points: Vec<Point> = crate::from_js(points)?;
+    crate::to_js(&hit_points(&polygon, &points, tolerance))
+}
+
+fn hit_points(polygon: &Polygon, points: &[Point], tolerance: f64) -> Vec<bool> {
+    if polygon.vertices.is_empty() {
+        return vec![false; points.len()];
+    }
+    let b = polygon.bounding_box();
+    let reach = tolerance.max(0.0);
+    points.iter().map(|p| {
+        let near = p.x >= b.min.x - reach && p.x <= b.max.x + reach && p.y >= b.min.y - reach && p.y <= b.max.y + reach;
+        near && polygon.hit(p, tolerance)
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_boundary_counts_as_inside() {
+        let l = Polygon::new(vec![
+            Point::new(0.0, 0.0), Point::new(4.0, 0.0), Point::new(4.0, 1.0),
+            Point::new(1.0, 1.0), Point::new(1.0, 3.0), Point::new(0.0, 3.0),
+        ]);
+        assert!(l.contains_point(&Point::new(0.5, 2.0)));
+        assert!(!l.contains_point(&Point::new(2.0, 2.0)));
+        // Vertices, including the reflex one, and points along edges
+        for p in &l.vertices {
+            assert!(l.contains_point(p));
+            assert_eq!(l.distance_to_point(p), 0.0);
+        }
+        assert!(l.contains_point(&Point::new(2.5, 1.0)));
+        assert!(l.contains_point(&Point::new(0.0, 1.7)));
+        assert!((l.distance_to_point(&Point::new(2.0, 2.0)) - 1.0).abs() < 1e-12);
+        assert!((l.distance_to_point(&Point::new(0.5, 2.0)) - 0.5).abs() < 1e-12);
+        // On the sloped edge, wherever the ray crossing would round to
+        let t = Polygon::new(vec![Point::new(0.0, 0.0), Point::new(3.0, 0.0), Point::new(0.0, 3.0)]);
+        assert!(t.contains_point(&Point::new(1.5, 1.5)) && t.contains_point(&Point::new(2.25, 0.75)));
+        assert!(!t.contains_point(&Point::new(1.5, 1.5 + 1e-12)));
+    }
+
+    #[test]
+    fn test_degenerate_polygons_and_batches() {
+        let empty = Polygon::new(vec![]);
+        assert!(!empty.contains_point(&Point::new(0.0, 0.0)));
+        assert_eq!(empty.distance_to_point(&Point::new(0.0, 0.0)), f64::INFINITY);
+        let dot = Polygon::new(vec![Point::new(1.0, 1.0)]);
+        assert!(dot.contains_point(&Point::new(1.0, 1.0)));
+        assert!((dot.distance_to_point(&Point::new(4.0, 5.0)) - 5.0).abs() < 1e-12);
+        let stick = Polygon::new(vec![Point::new(0.0, 0.0), Point::new(2.0, 0.0)]);
+        assert!(stick.contains_point(&Point::new(1.0, 0.0)));
+        assert!(!stick.contains_point(&Point::new(1.0, 0.1)));
+        assert!((stick.distance_to_point(&Point::new(1.0, -0.5)) - 0.5).abs() < 1e-12);
+
+        // A thin sliver is picked from just outside within tolerance
+        let sliver = Polygon::new(vec![Point::new(0.0, 0.0), Point::new(10.0, 0.0), Point::new(10.0, 0.01), Point::new(0.0, 0.01)]);
+        let points = [Point::new(5.0, 0.005), Point::new(5.0, 0.3), Point::new(5.0, 1.0), Point::new(50.0, 0.0), Point::new(10.2, 0.0)];
+        assert_eq!(hit_points(&sliver, &points, 0.5), vec![true, true, false, false, true]);
+        assert_eq!(hit_points(&sliver, &points, 0.0), vec![true, false, false, false, false]);
+        assert_eq!(hit_points(&empty, &points, 1.0), vec![false; 5]);
+    }
+}
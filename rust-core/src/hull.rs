@@ -0,0 +1,81 @@
+use wasm_bindgen::prelude::*;
+
+use crate::predicates::orient2d;
+use crate::{Point, Polygon};
+
+// Andrew's monotone chain. Returns the hull counter-clockwise without
+// collinear points; duplicate input points are ignored.
+pub(crate) fn convex_hull_points(points: &[Point]) -> Vec<Point> {
+    let mut sorted: Vec<Point> = points.to_vec();
+    sorted.sort_by(|a, b| {
+        a.x.partial_cmp(&b.x).unwrap_or(std::cmp::Ordering::Equal)
+            .then(a.y.partial_cmp(&b.y).unwrap_or(std::cmp::Ordering::Equal))
+    });
+    sorted.dedup();
+
+    if sorted.len() < 3 {
+        return sorted;
+    }
+
+    let mut lower: Vec<Point> = Vec::new();
+    for p in &sorted {
+        while lower.len() >= 2 && orient2d(&lower[lower.len() - 2], &lower[lower.len() - 1], p) <= 0.0 {
+            lower.pop();
+        }
+        lower.push(*p);
+    }
+
+    let mut upper: Vec<Point> = Vec::new();
+    for p in sorted.iter().rev() {
+        while upper.len() >= 2 && orient2d(&upper[upper.len() - 2], &upper[upper.len() - 1], p) <= 0.0 {
+            upper.pop();
+        }
+        upper.push(*p);
+    }
+
+    lower.pop();
+    upper.pop();
+    lower.extend(upper);
+    lower
+}
+
+// Convex hull as a polygon, counter-clockwise. Fewer than three distinct
+// points, or all of them on one line, give a degenerate hull: the distinct
+// points, or the line's two ends.
+pub fn convex_hull(points: &[Point]) -> Polygon {
+    Polygon::new(convex_hull_points(points))
+}
+
+impl Polygon {
+    pub fn convex_hull(&self) -> Polygon {
+        convex_hull(&self.vertices)
+    }
+}
+
+// `points` is an array of {x, y}
+#[wasm_bindgen(js_name = convex_hull)]
+pub fn convex_hull_js(points: &JsValue) -> Result<JsValue, JsValue> {
+    let points: Vec<Point> = crate::from_js(points)?;
+    crate::to_js(&convex_hull(&points))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hull_of_awkward_point_sets() {
+        let p = |x: f64, y: f64| Point::new(x, y);
+        let hull = convex_hull(&[p(1.0, 1.0), p(0.0, 0.0), p(2.0, 0.0), p(2.0, 0.0), p(1.0, 0.0), p(2.0, 2.0), p(0.0, 2.0), p(1.0, 1.0)]);
+        assert_eq!(hull.vertices, vec![p(0.0, 0.0), p(2.0, 0.0), p(2.0, 2.0), p(0.0, 2.0)]);
+        assert_eq!(hull.area(), 4.0);
+        assert_eq!(convex_hull(&[p(3.0, 3.0), p(0.0, 0.0), p(1.0, 1.0), p(2.0, 2.0), p(1.0, 1.0)]).vertices, vec![p(0.0, 0.0), p(3.0, 3.0)]);
+        assert_eq!(convex_hull(&[p(1.0, 1.0), p(1.0, 1.0)]).vertices, vec![p(1.0, 1.0)]);
+        assert!(convex_hull(&[]).vertices.is_empty());
+        let star = Polygon::new((0..10).map(|i| {
+            let (r, a) = (if i % 2 == 0 { 2.0 } else { 1.0 }, i as f64 * std::f64::consts::PI / 5.0);
+            p(r * a.cos(), r * a.sin())
+        }).collect());
+        assert_eq!(star.convex_hull().vertices.len(), 5);
+    }
+}
@@ -0,0 +1,634 @@
+use std::collections::BTreeMap;
+use std::f64::consts::TAU;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::{CadError, CubicBezier, Matrix, Point, Polygon, ShapeId};
+
+// Sides of the polygon a circle or ellipse is imported as
+const ELLIPSE_SEGMENTS: usize = 64;
+
+// Problem with one element that was worked around rather than failing the
+// import; `position` is the character offset of the element's tag
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ImportWarning {
+    pub position: usize,
+    pub element: String,
+    pub message: String,
+}
+
+// Outcome of `Document::import_svg`: the shapes added, in file order, and
+// the group made for each <g> holding any of them, with the id it had
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct SvgImport {
+    pub shapes: Vec<ShapeId>,
+    pub groups: Vec<(ShapeId, Option<String>)>,
+    pub warnings: Vec<ImportWarning>,
+}
+
+// Shape read from an SVG file, in world space (y up)
+#[derive(Clone, Debug)]
+pub(crate) struct ImportedShape {
+    pub(crate) geometry: Polygon,
+    pub(crate) id: Option<String>,
+    // Innermost enclosing <g>, as an index into the groups read
+    pub(crate) group: Option<usize>,
+    // From a `data-layer` attribute, as `Document::export` writes
+    pub(crate) layer: Option<String>,
+}
+
+// <g> element read from an SVG file, in the order they open, so a group
+// comes after the one it is in
+#[derive(Clone, Debug)]
+pub(crate) struct SvgGroup {
+    pub(crate) id: Option<String>,
+    pub(crate) parent: Option<usize>,
+}
+
+// Everything `read_svg` takes from a file
+#[derive(Clone, Debug)]
+pub(crate) struct SvgContents {
+    pub(crate) shapes: Vec<ImportedShape>,
+    pub(crate) groups: Vec<SvgGroup>,
+    pub(crate) warnings: Vec<ImportWarning>,
+}
+
+fn svg_matrix(a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) -> Matrix {
+    Matrix { m11: a, m12: c, m21: b, m22: d, dx: e, dy: f }
+}
+
+struct Scanner<'a> {
+    chars: &'a [char],
+    pos: usize,
+}
+
+impl Scanner<'_> {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_separators(&mut self) {
+        while self.peek().is_some_and(|c| c.is_whitespace() || c == ',') {
+            self.pos += 1;
+        }
+    }
+
+    fn error(&self, message: &str) -> CadError {
+        CadError::Parse { position: self.pos, message: message.to_string() }
+    }
+
+    // SVG number: sign, digits with an optional fraction, optional
+    // exponent. Numbers may run together where unambiguous: `1-2`, `.5.5`.
+    fn number(&mut self) -> Option<f64> {
+        self.skip_separators();
+        let start = self.pos;
+        let digits = |s: &mut Scanner| {
+            let from = s.pos;
+            while s.peek().is_some_and(|c| c.is_ascii_digit()) {
+                s.pos += 1;
+            }
+            s.pos > from
+        };
+        if matches!(self.peek(), Some('+' | '-')) {
+            self.pos += 1;
+        }
+        let mut any = digits(self);
+        if self.peek() == Some('.') {
+            self.pos += 1;
+            any |= digits(self);
+        }
+        if !any {
+            self.pos = start;
+            return None;
+        }
+        if matches!(self.peek(), Some('e' | 'E')) {
+            let mark = self.pos;
+            self.pos += 1;
+            if matches!(self.peek(), Some('+' | '-')) {
+                self.pos += 1;
+            }
+            if !digits(self) {
+                self.pos = mark;
+            }
+        }
+        self.chars[start..self.pos].iter().collect::<String>().parse().ok()
+    }
+}
+
+// Parses an SVG transform attribute: a list of matrix, translate, scale,
+// rotate (optionally about a centre), skewX and skewY, separated by
+// whitespace and/or commas and applied right to left, as SVG specifies.
+// Angles are in degrees; the result maps SVG user space (y down).
+pub fn parse_svg_transform(text: &str) -> Result<Matrix, CadError> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut s = Scanner { chars: &chars, pos: 0 };
+    let mut result = Matrix::identity();
+    loop {
+        s.skip_separators();
+        if s.peek().is_none() {
+            return Ok(result);
+        }
+        let start = s.pos;
+        while s.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+            s.pos += 1;
+        }
+        let name: String = chars[start..s.pos].iter().collect();
+        while s.peek().is_some_and(|c| c.is_whitespace()) {
+            s.pos += 1;
+        }
+        if s.peek() != Some('(') {
+            return Err(s.error(if name.is_empty() { "expected a transform name" } else { "expected '('" }));
+        }
+        s.pos += 1;
+        let mut args = Vec::new();
+        while let Some(v) = s.number() {
+            args.push(v);
+        }
+        s.skip_separators();
+        if s.peek() != Some(')') {
+            return Err(s.error("expected a number or ')'"));
+        }
+        s.pos += 1;
+
+        let radians = |deg: f64| deg.to_radians();
+        let m = match (name.as_str(), &args[..]) {
+            ("matrix", &[a, b, c, d, e, f]) => svg_matrix(a, b, c, d, e, f),
+            ("translate", &[tx]) => svg_matrix(1.0, 0.0, 0.0, 1.0, tx, 0.0),
+            ("translate", &[tx, ty]) => svg_matrix(1.0, 0.0, 0.0, 1.0, tx, ty),
+            ("scale", &[k]) => svg_matrix(k, 0.0, 0.0, k, 0.0, 0.0),
+            ("scale", &[sx, sy]) => svg_matrix(sx, 0.0, 0.0, sy, 0.0, 0.0),
+            ("rotate", &[a]) | ("rotate", &[a, _, _]) => {
+                let (sin, cos) = radians(a).sin_cos();
+                let (cx, cy) = if args.len() == 3 { (args[1], args[2]) } else { (0.0, 0.0) };
+                // translate(cx, cy) rotate(a) translate(-cx, -cy)
+                svg_matrix(cos, sin, -sin, cos, cx - cos * cx + sin * cy, cy - sin * cx - cos * cy)
+            }
+            ("skewX", &[a]) => svg_matrix(1.0, 0.0, radians(a).tan(), 1.0, 0.0, 0.0),
+            ("skewY", &[a]) => svg_matrix(1.0, radians(a).tan(), 0.0, 1.0, 0.0, 0.0),
+            ("matrix" | "translate" | "scale" | "rotate" | "skewX" | "skewY", _) => {
+                return Err(CadError::Parse { position: start, message: format!("wrong number of arguments to {}", name) });
+            }
+            _ => return Err(CadError::Parse { position: start, message: format!("unknown transform '{}'", name) }),
+        };
+        result = result.multiply(&m);
+    }
+}
+
+// Start or self-closing tag, with its attributes
+struct Tag {
+    position: usize,
+    name: String,
+    attributes: BTreeMap<String, String>,
+    closed: bool,
+}
+
+enum Token {
+    Open(Tag),
+    Close(String),
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&apos;", "'").replace("&amp;", "&")
+}
+
+// Start and end tags in document order. Text, comments, processing
+// instructions and declarations are skipped.
+fn tokenize(text: &str) -> Result<Vec<Token>, CadError> {
+    let chars: Vec<char> = text.chars().collect();
+    let find = |from: usize, pattern: &str| -> Option<usize> {
+        let p: Vec<char> = pattern.chars().collect();
+        (from..chars.len()).find(|&i| chars[i..].starts_with(&p))
+    };
+    let unterminated = |position: usize| CadError::Parse { position, message: "unterminated markup".to_string() };
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while let Some(start) = find(i, "<") {
+        let rest = &chars[start..];
+        if rest.starts_with(&['<', '!', '-', '-']) {
+            i = find(start, "-->").ok_or_else(|| unterminated(start))? + 3;
+            continue;
+        }
+        if rest.starts_with(&['<', '?']) || rest.starts_with(&['<', '!']) {
+            i = find(start, ">").ok_or_else(|| unterminated(start))? + 1;
+            continue;
+        }
+        let mut pos = start + 1;
+        let closing = chars.get(pos) == Some(&'/');
+        if closing {
+            pos += 1;
+        }
+        let name_start = pos;
+        while chars.get(pos).is_some_and(|c| !c.is_whitespace() && *c != '>' && *c != '/') {
+            pos += 1;
+        }
+        let name: String = chars[name_start..pos].iter().collect();
+        let mut attributes = BTreeMap::new();
+        loop {
+            while chars.get(pos).is_some_and(|c| c.is_whitespace()) {
+                pos += 1;
+            }
+            match chars.get(pos) {
+                None => return Err(unterminated(start)),
+                Some('>') => break,
+                Some('/') if chars.get(pos + 1) == Some(&'>') => {
+                    pos += 1;
+                    break;
+                }
+                Some(_) => {}
+            }
+            let key_start = pos;
+            while chars.get(pos).is_some_and(|c| !c.is_whitespace() && *c != '=' && *c != '>' && *c != '/') {
+                pos += 1;
+            }
+            let key: String = chars[key_start..pos].iter().collect();
+            while chars.get(pos).is_some_and(|c| c.is_whitespace()) {
+                pos += 1;
+            }
+            if chars.get(pos) != Some(&'=') {
+                if key.is_empty() {
+                    return Err(CadError::Parse { position: pos, message: "unexpected character in tag".to_string() });
+                }
+                // Attribute without a value
+                attributes.insert(key, String::new());
+                continue;
+            }
+            pos += 1;
+            while chars.get(pos).is_some_and(|c| c.is_whitespace()) {
+                pos += 1;
+            }
+            let quote = match chars.get(pos) {
+                Some(&q) if q == '"' || q == '\'' => q,
+                _ => return Err(CadError::Parse { position: pos, message: "expected a quoted attribute value".to_string() }),
+            };
+            let end = (pos + 1..chars.len()).find(|&k| chars[k] == quote).ok_or_else(|| unterminated(start))?;
+            attributes.insert(key, unescape(&chars[pos + 1..end].iter().collect::<String>()));
+            pos = end + 1;
+        }
+        let closed = chars[pos] == '>' && chars[pos - 1] == '/';
+        tokens.push(if closing { Token::Close(name) } else { Token::Open(Tag { position: start, name, attributes, closed }) });
+        i = pos + 1;
+    }
+    Ok(tokens)
+}
+
+fn number_list(text: &str) -> Option<Vec<f64>> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut s = Scanner { chars: &chars, pos: 0 };
+    let mut values = Vec::new();
+    while let Some(v) = s.number() {
+        values.push(v);
+    }
+    s.skip_separators();
+    s.peek().is_none().then_some(values)
+}
+
+// Furthest a flattened path curve may stray from the curve, in user units
+const CURVE_TOLERANCE: f64 = 0.01;
+
+// Most segments one path curve is flattened into, however fine the tolerance
+const MAX_CURVE_SEGMENTS: usize = 1024;
+
+fn curve_segments(bend: f64, tolerance: f64) -> usize {
+    ((bend / tolerance).sqrt().ceil() as usize).clamp(1, MAX_CURVE_SEGMENTS)
+}
+
+// Polygons outlined by SVG path data, one per subpath with at least three
+// points, in the path's own coordinates (y down, nothing negated). Lines
+// (M, L, H, V, Z and their relative forms) are taken as given; cubic (C)
+// and quadratic (Q) curves are flattened into segments straying no more
+// than `tolerance` from them. Commands may repeat implicitly, numbers may
+// be separated by commas, whitespace or nothing where unambiguous, and a
+// subpath ends at Z or the next M whether or not it is closed. Arcs and
+// smooth curves fail with the position of the command.
+pub fn polygons_from_svg_path(d: &str, tolerance: f64) -> Result<Vec<Polygon>, CadError> {
+    if tolerance.is_nan() || tolerance <= 0.0 {
+        return Err(CadError::InvalidInput(format!("curve tolerance must be positive, got {}", tolerance)));
+    }
+    let chars: Vec<char> = d.chars().collect();
+    let mut s = Scanner { chars: &chars, pos: 0 };
+    let mut polygons = Vec::new();
+    let mut points: Vec<Point> = Vec::new();
+    let mut finish = |points: &mut Vec<Point>| {
+        if points.len() > 1 && points.first() == points.last() {
+            points.pop();
+        }
+        if points.len() >= 3 {
+            polygons.push(Polygon::new(std::mem::take(points)));
+        }
+        points.clear();
+    };
+    let (mut at, mut start) = (Point::new(0.0, 0.0), Point::new(0.0, 0.0));
+    let mut command: Option<(char, usize)> = None;
+    loop {
+        s.skip_separators();
+        let Some(c) = s.peek() else {
+            break;
+        };
+        if c.is_ascii_alphabetic() {
+            command = Some((c, s.pos));
+            s.pos += 1;
+            if c == 'Z' || c == 'z' {
+                finish(&mut points);
+                at = start;
+                command = None;
+            }
+            continue;
+        }
+        let Some((c, position)) = command else {
+            return Err(s.error("expected a path command"));
+        };
+        let relative = c.is_ascii_lowercase();
+        let base = if relative { at } else { Point::new(0.0, 0.0) };
+        let mut next = || s.number().ok_or_else(|| s.error("expected a number"));
+        let mut point = || -> Result<Point, CadError> { Ok(Point::new(base.x + next()?, base.y + next()?)) };
+        match c.to_ascii_uppercase() {
+            'M' => {
+                finish(&mut points);
+                at = point()?;
+                start = at;
+                points.push(at);
+                // Pairs after the first of a moveto are linetos
+                command = Some((if relative { 'l' } else { 'L' }, position));
+            }
+            'L' => {
+                at = point()?;
+                points.push(at);
+            }
+            'H' => {
+                at = Point::new(base.x + next()?, at.y);
+                points.push(at);
+            }
+            'V' => {
+                at = Point::new(at.x, base.y + next()?);
+                points.push(at);
+            }
+            'C' => {
+                let (p1, p2, p3) = (point()?, point()?, point()?);
+                let curve = CubicBezier::new(at, p1, p2, p3);
+                let bend = |a: &Point, b: &Point, c: &Point| (a.x - 2.0 * b.x + c.x).hypot(a.y - 2.0 * b.y + c.y);
+                let n = curve_segments(0.75 * bend(&at, &p1, &p2).max(bend(&p1, &p2, &p3)), tolerance);
+                points.extend((1..=n).map(|k| if k == n { p3 } else { curve.point_at(k as f64 / n as f64) }));
+                at = p3;
+            }
+            'Q' => {
+                let (p1, p2) = (point()?, point()?);
+                let n = curve_segments((at.x - 2.0 * p1.x + p2.x).hypot(at.y - 2.0 * p1.y + p2.y) / 4.0, tolerance);
+                points.extend((1..=n).map(|k| {
+                    let t = k as f64 / n as f64;
+                    let (a, b, c) = ((1.0 - t) * (1.0 - t), 2.0 * t * (1.0 - t), t * t);
+                    if k == n { p2 } else { Point::new(a * at.x + b * p1.x + c * p2.x, a * at.y + b * p1.y + c * p2.y) }
+                }));
+                at = p2;
+            }
+            _ => return Err(CadError::Parse { position, message: format!("path command '{}' is not supported", c) }),
+        }
+    }
+    finish(&mut points);
+    Ok(polygons)
+}
+
+// Outline of each of a path's subpaths
+fn path_outlines(d: &str) -> Result<Vec<Vec<Point>>, String> {
+    let polygons = polygons_from_svg_path(d, CURVE_TOLERANCE).map_err(|e| e.to_string())?;
+    Ok(polygons.into_iter().map(|p| p.vertices).collect())
+}
+
+fn ellipse(cx: f64, cy: f64, rx: f64, ry: f64) -> Vec<Point> {
+    (0..ELLIPSE_SEGMENTS).map(|k| {
+        let a = TAU * k as f64 / ELLIPSE_SEGMENTS as f64;
+        Point::new(cx + rx * a.cos(), cy + ry * a.sin())
+    }).collect()
+}
+
+// Local outlines of one drawing element, one per subpath of a path and
+// otherwise just the one, or why it can't be imported
+fn outlines(tag: &Tag) -> Result<Vec<Vec<Point>>, String> {
+    let num = |key: &str, default: Option<f64>| -> Result<f64, String> {
+        match tag.attributes.get(key) {
+            Some(v) => number_list(v).and_then(|l| (l.len() == 1).then(|| l[0])).ok_or_else(|| format!("bad {} value '{}'", key, v)),
+            None => default.ok_or_else(|| format!("missing {}", key)),
+        }
+    };
+    let points = match tag.name.as_str() {
+        "rect" => {
+            let (x, y, w, h) = (num("x", Some(0.0))?, num("y", Some(0.0))?, num("width", None)?, num("height", None)?);
+            vec![Point::new(x, y), Point::new(x + w, y), Point::new(x + w, y + h), Point::new(x, y + h)]
+        }
+        "circle" => {
+            let r = num("r", None)?;
+            ellipse(num("cx", Some(0.0))?, num("cy", Some(0.0))?, r, r)
+        }
+        "ellipse" => ellipse(num("cx", Some(0.0))?, num("cy", Some(0.0))?, num("rx", None)?, num("ry", None)?),
+        "polygon" | "polyline" => {
+            let list = tag.attributes.get("points").map(String::as_str).unwrap_or("");
+            let values = number_list(list).filter(|v| v.len() % 2 == 0).ok_or_else(|| "bad points list".to_string())?;
+            values.chunks(2).map(|c| Point::new(c[0], c[1])).collect()
+        }
+        "path" => {
+            let rings = path_outlines(tag.attributes.get("d").map(String::as_str).unwrap_or(""))?;
+            if rings.is_empty() {
+                return Err("no subpath with three points".to_string());
+            }
+            return Ok(rings);
+        }
+        _ => return Ok(vec![]),
+    };
+    if points.len() < 3 {
+        return Err("fewer than three points".to_string());
+    }
+    Ok(vec![points])
+}
+
+const DRAWN: [&str; 6] = ["rect", "circle", "ellipse", "polygon", "polyline", "path"];
+
+// Shapes from SVG text, each drawing element flattened to a polygon with
+// every enclosing group's transform applied, and a path to one polygon per
+// subpath. Circles and ellipses are
+// flattened before transforming, so a non-uniform scale gives the right
+// ellipse rather than a scaled circle's radius. y is negated into world
+// space, matching `Document::export`. An element that can't be read, or a
+// transform that can't be parsed (which SVG treats as no transform), gives
+// a warning; only broken markup fails the import.
+pub(crate) fn read_svg(text: &str) -> Result<SvgContents, CadError> {
+    struct Frame {
+        name: String,
+        transform: Matrix,
+        group: Option<usize>,
+        layer: Option<String>,
+    }
+    let flip = Matrix::scale(1.0, -1.0);
+    let root = Frame { name: String::new(), transform: flip, group: None, layer: None };
+    let mut stack = vec![root];
+    let mut shapes = Vec::new();
+    let mut groups = Vec::new();
+    let mut warnings = Vec::new();
+
+    for token in tokenize(text)? {
+        let tag = match token {
+            Token::Close(name) => {
+                match stack.last() {
+                    Some(frame) if stack.len() > 1 && frame.name == name => {
+                        stack.pop();
+                    }
+                    _ => return Err(CadError::Parse { position: 0, message: format!("unbalanced </{}>", name) }),
+                }
+                continue;
+            }
+            Token::Open(tag) => tag,
+        };
+        let parent = stack.last().unwrap();
+        let element = match tag.attributes.get("id") {
+            Some(id) => format!("{}#{}", tag.name, id),
+            None => tag.name.clone(),
+        };
+        let local = match tag.attributes.get("transform").map(|t| parse_svg_transform(t)) {
+            Some(Ok(m)) => m,
+            Some(Err(e)) => {
+                warnings.push(ImportWarning { position: tag.position, element: element.clone(), message: format!("transform ignored: {}", e) });
+                Matrix::identity()
+            }
+            None => Matrix::identity(),
+        };
+        let transform = parent.transform.multiply(&local);
+
+        if DRAWN.contains(&tag.name.as_str()) {
+            match outlines(&tag) {
+                Ok(rings) => shapes.extend(rings.into_iter().map(|points| ImportedShape {
+                    geometry: Polygon::new(points).transform(&transform),
+                    id: tag.attributes.get("id").cloned(),
+                    group: parent.group,
+                    layer: tag.attributes.get("data-layer").cloned().or_else(|| parent.layer.clone()),
+                })),
+                Err(message) => warnings.push(ImportWarning { position: tag.position, element, message }),
+            }
+        }
+        if !tag.closed {
+            let group = if tag.name == "g" {
+                groups.push(SvgGroup { id: tag.attributes.get("id").cloned(), parent: parent.group });
+                Some(groups.len() - 1)
+            } else {
+                parent.group
+            };
+            let layer = tag.attributes.get("data-layer").cloned().or_else(|| parent.layer.clone());
+            stack.push(Frame { name: tag.name, transform, group, layer });
+        }
+    }
+    if stack.len() > 1 {
+        return Err(CadError::Parse { position: text.chars().count(), message: format!("<{}> is never closed", stack.last().unwrap().name) });
+    }
+    Ok(SvgContents { shapes, groups, warnings })
+}
+
+// Array of vertex arrays, one per subpath
+#[wasm_bindgen]
+pub fn svg_to_polygons(d: &str, tolerance: f64) -> Result<JsValue, JsValue> {
+    let polygons = polygons_from_svg_path(d, tolerance).map_err(crate::js_error)?;
+    let rings: Vec<Vec<Point>> = polygons.into_iter().map(|p| p.vertices).collect();
+    crate::to_js(&rings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(m: &Matrix, x: f64, y: f64) -> (f64, f64) {
+        (m.m11 * x + m.m12 * y + m.dx, m.m21 * x + m.m22 * y + m.dy)
+    }
+
+    fn close(a: (f64, f64), b: (f64, f64)) -> bool {
+        (a.0 - b.0).abs() < 1e-9 && (a.1 - b.1).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_transform_grammar() {
+        let m = parse_svg_transform(" translate(10)rotate(90, 5 5) , scale( 2 ) ").unwrap();
+        // scale, then a quarter turn about (5, 5), then along x
+        assert!(close(apply(&m, 1.0, 0.0), (20.0, 2.0)));
+        let m = parse_svg_transform("matrix(1 0 0 1 -1.5e1 .5)skewX(45)").unwrap();
+        assert!(close(apply(&m, 0.0, 2.0), (-13.0, 2.5)));
+        assert!(close(apply(&parse_svg_transform("translate(1-2)").unwrap(), 0.0, 0.0), (1.0, -2.0)));
+        assert!(matches!(parse_svg_transform("scale(1, 2, 3)"), Err(CadError::Parse { position: 0, .. })));
+        assert!(parse_svg_transform("translate(1,").is_err());
+        assert!(parse_svg_transform("spin(4)").is_err());
+    }
+
+    #[test]
+    fn test_nested_groups_compose() {
+        let svg = r#"<?xml version="1.0"?>
+            <svg xmlns="http://www.w3.org/2000/svg">
+              <!-- three nested groups -->
+              <g id="outer" transform="translate(10,20)">
+                <g id="middle" transform="rotate(90 5 5)">
+                  <g id="inner" transform="scale(2, 3)">
+                    <rect id="known" x="1" y="1" width="2" height="1"/>
+                  </g>
+                  <rect id="bad" transform="translate(1 2" width="1" height="1"/>
+                </g>
+                <circle r="1"/>
+              </g>
+            </svg>"#;
+        let SvgContents { shapes, groups, warnings } = read_svg(svg).unwrap();
+        assert_eq!(shapes.len(), 3);
+        let known = &shapes[0];
+        let inner = &groups[known.group.unwrap()];
+        assert_eq!((known.id.as_deref(), inner.id.as_deref()), (Some("known"), Some("inner")));
+        assert_eq!(groups[inner.parent.unwrap()].parent, Some(0));
+        // Worked by hand: scale, a quarter turn about (5, 5) taking (x, y) to
+        // (10 - y, x), translate, then y negated
+        let expected = [(17.0, -22.0), (17.0, -26.0), (14.0, -26.0), (14.0, -22.0)];
+        for (p, e) in known.geometry.vertices.iter().zip(expected) {
+            assert!(close((p.x, p.y), e));
+        }
+        // The malformed transform is dropped, not the element
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].element, "rect#bad");
+        assert_eq!((shapes[1].group, shapes[2].group), (Some(1), Some(0)));
+        assert!(close((shapes[1].geometry.vertices[0].x, shapes[1].geometry.vertices[0].y), (20.0, -20.0)));
+        assert!(read_svg("<svg><g></svg>").is_err());
+    }
+
+    #[test]
+    fn test_path_data_to_polygons_and_back() {
+        let pt = |p: &Point| (p.x, p.y);
+        // Two subpaths, the second by implicit linetos after its moveto
+        let d = "M 0,0 H 10 V 10 H 0 Z M2,2 2,8 8,8 8,2z";
+        let polygons = polygons_from_svg_path(d, 0.01).unwrap();
+        assert_eq!(polygons.len(), 2);
+        assert_eq!(polygons[0].vertices.iter().map(pt).collect::<Vec<_>>(), vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)]);
+        assert_eq!(polygons[1].vertices.len(), 4);
+        for p in &polygons {
+            let again = polygons_from_svg_path(&p.to_svg_path(), 0.01).unwrap();
+            assert_eq!(again[0].vertices.iter().map(pt).collect::<Vec<_>>(), p.vertices.iter().map(pt).collect::<Vec<_>>());
+        }
+        // Scientific notation and numbers run together
+        let packed = polygons_from_svg_path("M1e1,0L2E+1 0 15-1e1l-5.5.5z", 0.01).unwrap();
+        assert_eq!(packed[0].vertices.iter().map(pt).collect::<Vec<_>>(), vec![(10.0, 0.0), (20.0, 0.0), (15.0, -10.0), (9.5, -9.5)]);
+
+        // A circle of radius 10 as Inkscape writes it, relative with the
+        // curve command repeated implicitly
+        let inkscape = "m 30,20 c 0,5.52285 -4.47715,10 -10,10 -5.52285,0 -10,-4.47715 -10,-10 0,-5.52285 4.47715,-10 10,-10 5.52285,0 10,4.47715 10,10 z";
+        let circle = &polygons_from_svg_path(inkscape, 0.001).unwrap()[0];
+        assert!(circle.vertices.len() > 16);
+        assert!(circle.vertices.iter().all(|p| ((p.x - 20.0).hypot(p.y - 20.0) - 10.0).abs() < 0.01));
+        assert!((circle.area() - std::f64::consts::PI * 100.0).abs() < 0.5);
+        let coarse = polygons_from_svg_path(inkscape, 0.5).unwrap();
+        assert!(coarse[0].vertices.len() < circle.vertices.len());
+        let quad = polygons_from_svg_path("M0 0Q5 10 10 0Z", 0.01).unwrap();
+        assert!(quad[0].vertices.iter().any(|p| (p.y - 5.0).abs() < 0.01));
+
+        assert!(matches!(polygons_from_svg_path("M0 0 A 5 5 0 0 1 10 0", 0.01), Err(CadError::Parse { position: 5, .. })));
+        assert!(matches!(polygons_from_svg_path("0 0 L 1 1", 0.01), Err(CadError::Parse { position: 0, .. })));
+        assert!(polygons_from_svg_path("M0 0 L1", 0.01).is_err());
+        assert!(polygons_from_svg_path("M0 0 L1 0 L1 1", 0.0).is_err());
+        assert_eq!(Polygon::new(vec![]).to_svg_path(), "");
+
+        // An imported path keeps every subpath, not just the first
+        let SvgContents { shapes, warnings, .. } = read_svg(&format!(r#"<svg><path id="frame" d="{}"/><path d="M0 0 L1 1"/></svg>"#, d)).unwrap();
+        assert_eq!(shapes.len(), 2);
+        assert!(shapes.iter().all(|s| s.id.as_deref() == Some("frame")));
+        assert_eq!(shapes[1].geometry.vertices.iter().map(pt).collect::<Vec<_>>(), vec![(2.0, -2.0), (2.0, -8.0), (8.0, -8.0), (8.0, -2.0)]);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].message, "no subpath with three points");
+    }
+}
@@ -0,0 +1,225 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::document::{about, apply};
+use crate::{format_number, AttributeValue, CadError, Dimension, DimensionId, DimensionKind, Document, Matrix, ShapeId, EPSILON};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Measurement {
+    pub dimension: DimensionId,
+    pub value: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct InspectionItem {
+    pub dimension: DimensionId,
+    pub shape: ShapeId,
+    pub kind: DimensionKind,
+    pub nominal: f64,
+    // Allowed deviation above and below nominal, both as magnitudes
+    pub tol_plus: f64,
+    pub tol_minus: f64,
+    // None when no measurement was supplied for this dimension
+    pub measured: Option<f64>,
+    pub deviation: Option<f64>,
+    pub pass: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct InspectionReport {
+    pub items: Vec<InspectionItem>,
+    // Measurements naming a dimension the document doesn't have
+    pub unmatched: Vec<Measurement>,
+}
+
+// Numeric tolerance attribute, looked up on the dimension and then on its
+// shape; missing tolerances are zero
+fn tolerance(doc: &Document, dimension: &Dimension, key: &str) -> f64 {
+    let number = |value: Option<&AttributeValue>| match value {
+        Some(AttributeValue::Number(n)) => Some(n.abs()),
+        _ => None,
+    };
+    number(dimension.attributes.get(key))
+        .or_else(|| doc.shape(dimension.shape).ok().and_then(|s| number(s.attributes.get(key))))
+        .unwrap_or(0.0)
+}
+
+// One item per document dimension, in id order. A later measurement of the
+// same dimension replaces an earlier one.
+pub fn inspection_report(doc: &Document, measurements: &[Measurement]) -> InspectionReport {
+    let mut items = Vec::new();
+    let mut dimensions: Vec<&Dimension> = doc.dimensions().filter(|d| doc.shape(d.shape).is_ok()).collect();
+    dimensions.sort_by_key(|d| d.id);
+    for dimension in dimensions {
+        let nominal = doc.dimension_value(dimension.id).unwrap();
+        let (tol_plus, tol_minus) = (tolerance(doc, dimension, "tol_plus"), tolerance(doc, dimension, "tol_minus"));
+        let measured = measurements.iter().rev().find(|m| m.dimension == dimension.id).map(|m| m.value);
+        let deviation = measured.map(|m| m - nominal);
+        items.push(InspectionItem {
+            dimension: dimension.id,
+            shape: dimension.shape,
+            kind: dimension.kind,
+            nominal,
+            tol_plus,
+            tol_minus,
+            measured,
+            deviation,
+            pass: deviation.map(|d| d <= tol_plus + EPSILON && d >= -tol_minus - EPSILON),
+        });
+    }
+    let unmatched = measurements.iter().filter(|m| doc.dimension(m.dimension).is_err()).copied().collect();
+    InspectionReport { items, unmatched }
+}
+
+impl InspectionReport {
+    pub fn failures(&self) -> usize {
+        self.items.iter().filter(|i| i.pass == Some(false)).count()
+    }
+
+    // Header row, one row per item, then one row per unmatched measurement
+    // with only the dimension id and measured value filled in. Numbers are
+    // written shortest-roundtrip, as `format_number` does.
+    pub fn to_csv(&self) -> String {
+        let num = |v: f64| format_number(v, None);
+        let optional = |v: Option<f64>| v.map(num).unwrap_or_default();
+        let mut csv = String::from("dimension,shape,kind,nominal,tol_plus,tol_minus,measured,deviation,result\n");
+        for item in &self.items {
+            let result = match item.pass {
+                Some(true) => "pass",
+                Some(false) => "fail",
+                None => "unmeasured",
+            };
+            csv.push_str(&format!(
+                "{},{},{:?},{},{},{},{},{},{}\n",
+                item.dimension, item.shape, item.kind, num(item.nominal), num(item.tol_plus), num(item.tol_minus),
+                optional(item.measured), optional(item.deviation), result,
+            ));
+        }
+        for m in &self.unmatched {
+            csv.push_str(&format!("{},,,,,,{},,unmatched\n", m.dimension, num(m.value)));
+        }
+        csv
+    }
+}
+
+// Copy of the document with each measured dimension stretched to
+// `nominal + exaggeration * deviation`. The stretch runs along the
+// dimension's direction about its `from` point, in the shape's local space,
+// and carries the shape's other dimensions along with its geometry.
+pub fn apply_measured_deviations(doc: &Document, report: &InspectionReport, exaggeration: f64) -> Document {
+    let mut deviated = doc.clone();
+    for item in &report.items {
+        let (Some(deviation), Ok(dimension)) = (item.deviation, doc.dimension(item.dimension)) else {
+            continue;
+        };
+        if item.nominal < EPSILON {
+            continue;
+        }
+        let f = (item.nominal + exaggeration * deviation) / item.nominal - 1.0;
+        let (from, u) = match deviated.dimension(dimension.id) {
+            Ok(d) => (d.from, d.direction()),
+            Err(_) => continue,
+        };
+        let stretch = Matrix {
+            m11: 1.0 + f * u.x * u.x,
+            m12: f * u.x * u.y,
+            m21: f * u.x * u.y,
+            m22: 1.0 + f * u.y * u.y,
+            dx: 0.0,
+            dy: 0.0,
+        };
+        let stretch = about(&from, &stretch);
+        let shape = deviated.shape_mut(item.shape).unwrap();
+        shape.geometry = shape.geometry.transform(&stretch);
+        if let Some(pivot) = shape.pivot {
+            shape.pivot = Some(apply(&stretch, &pivot));
+        }
+        let ids: Vec<DimensionId> = deviated.dimensions().filter(|d| d.shape == item.shape).map(|d| d.id).collect();
+        for id in ids {
+            let d = deviated.dimension_mut(id).unwrap();
+            d.from = apply(&stretch, &d.from);
+            d.to = apply(&stretch, &d.to);
+        }
+    }
+    deviated
+}
+
+// `measurements` is an array of { dimension, value }; `format` is "json" or "csv"
+#[wasm_bindgen]
+pub fn generate_inspection_report(doc: &Document, measurements: &JsValue, format: &str) -> Result<String, JsValue> {
+    let measurements: Vec<Measurement> = crate::from_js(measurements)?;
+    let report = inspection_report(doc, &measurements);
+    match format {
+        "csv" => Ok(report.to_csv()),
+        "json" => {
+            let value = crate::to_js(&report)?;
+            Ok(js_sys::JSON::stringify(&value)?.into())
+        }
+        _ => Err(crate::js_error(CadError::InvalidInput(format!("unknown report format {:?}", format)))),
+    }
+}
+
+// `report` is the parsed JSON form of `generate_inspection_report`
+#[wasm_bindgen(js_name = apply_measured_deviations)]
+pub fn apply_measured_deviations_js(doc: &Document, report: &JsValue, exaggeration: f64) -> Result<Document, JsValue> {
+    let report: InspectionReport = crate::from_js(report)?;
+    Ok(apply_measured_deviations(doc, &report, exaggeration))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Point, Polygon};
+
+    fn plate() -> (Document, DimensionId, DimensionId) {
+        let mut doc = Document::new();
+        let outline = Polygon::new(vec![Point::new(0.0, 0.0), Point::new(40.0, 0.0), Point::new(40.0, 20.0), Point::new(0.0, 20.0)]);
+        let shape = doc.add_shape(outline);
+        doc.set_transform(shape, Matrix::translate(100.0, 50.0).multiply(&Matrix::rotate(0.3))).unwrap();
+        doc.set_attribute(shape, "tol_plus", AttributeValue::Number(0.1)).unwrap();
+        doc.set_attribute(shape, "tol_minus", AttributeValue::Number(0.1)).unwrap();
+        let width = doc.add_dimension(shape, DimensionKind::OverallWidth, Point::new(0.0, 0.0), Point::new(40.0, 0.0)).unwrap();
+        let height = doc.add_dimension(shape, DimensionKind::OverallHeight, Point::new(40.0, 0.0), Point::new(40.0, 20.0)).unwrap();
+        // The dimension's own tolerance overrides the shape's
+        doc.dimension_mut(height).unwrap().attributes.insert("tol_minus".to_string(), AttributeValue::Number(0.5));
+        (doc, width, height)
+    }
+
+    #[test]
+    fn test_report_flags_out_of_tolerance_measurement() {
+        let (doc, width, height) = plate();
+        let measurements = [
+            Measurement { dimension: width, value: 40.3 },
+            Measurement { dimension: height, value: 19.6 },
+            Measurement { dimension: 42, value: 1.0 },
+        ];
+        let report = inspection_report(&doc, &measurements);
+        assert_eq!(report.items.len(), 2);
+        assert!((report.items[0].nominal - 40.0).abs() < 1e-9);
+        assert_eq!(report.items[0].pass, Some(false));
+        assert_eq!(report.items[1].pass, Some(true));
+        assert_eq!(report.failures(), 1);
+        assert_eq!(report.unmatched, vec![measurements[2]]);
+
+        let csv = report.to_csv();
+        assert_eq!(csv.lines().count(), 4);
+        assert!(csv.lines().nth(1).unwrap().ends_with(",fail"));
+        assert!(csv.lines().nth(3).unwrap().starts_with("42,"));
+    }
+
+    #[test]
+    fn test_deviated_copy_remeasures_to_measured_values() {
+        let (doc, width, height) = plate();
+        let report = inspection_report(&doc, &[Measurement { dimension: width, value: 40.3 }, Measurement { dimension: height, value: 19.6 }]);
+
+        let deviated = apply_measured_deviations(&doc, &report, 1.0);
+        assert!((deviated.dimension_value(width).unwrap() - 40.3).abs() < 1e-9);
+        assert!((deviated.dimension_value(height).unwrap() - 19.6).abs() < 1e-9);
+        assert!((deviated.shapes().next().unwrap().geometry.area() - 40.3 * 19.6).abs() < 1e-9);
+        // The original is untouched
+        assert!((doc.dimension_value(width).unwrap() - 40.0).abs() < 1e-9);
+
+        let exaggerated = apply_measured_deviations(&doc, &report, 10.0);
+        assert!((exaggerated.dimension_value(width).unwrap() - 43.0).abs() < 1e-9);
+    }
+}
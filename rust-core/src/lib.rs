@@ -1,6 +1,162 @@
 use wasm_bindgen::prelude::*;
 use serde::{Deserialize, Serialize};
-use serde_wasm_bindgen;
+
+mod adjacency;
+mod angle;
+mod arc;
+mod arrangement;
+mod autosave;
+mod beautify;
+mod bezier;
+mod binary;
+mod bend;
+mod boolean;
+mod census;
+mod classify;
+mod contours;
+mod convention;
+mod coordinates;
+mod cursor;
+mod curve;
+mod cutter;
+mod decompose;
+mod decorations;
+mod dedup;
+mod diff;
+mod dimensions;
+mod document;
+mod draw;
+mod dxf;
+mod error;
+mod export;
+#[cfg(all(feature = "ffi", not(target_arch = "wasm32")))]
+pub mod ffi;
+#[cfg(any(test, feature = "testing"))]
+pub mod fixtures;
+mod fits;
+mod flat;
+mod hash;
+mod hittest;
+mod hull;
+mod import;
+mod inspection;
+mod library;
+mod markers;
+mod macros;
+mod measure;
+mod memory;
+mod offset;
+mod outline;
+mod overlap;
+mod params;
+mod perimeter;
+mod precision;
+mod printability;
+mod profiles;
+mod predicates;
+mod projection;
+mod provenance;
+mod region;
+mod resize;
+mod rings;
+mod rooms;
+mod schedule;
+mod search;
+mod segment;
+mod selection;
+mod signature;
+mod simplify;
+mod snap;
+mod spatial;
+mod spline;
+mod split;
+mod stations;
+mod stencil;
+mod straightness;
+mod strict;
+mod sweep;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+mod textfit;
+mod thickness;
+mod tolerance;
+mod triangulate;
+mod zoom;
+
+pub use adjacency::*;
+pub use angle::*;
+pub use arc::*;
+pub use arrangement::region_at_point;
+pub use autosave::{AutosavePayload, AutosaveRestore};
+pub use beautify::*;
+pub use bezier::*;
+pub use bend::*;
+pub use boolean::*;
+pub use census::*;
+pub use classify::*;
+pub use contours::*;
+pub use convention::*;
+pub use coordinates::*;
+pub use cursor::*;
+pub use curve::*;
+pub use cutter::*;
+pub use decompose::*;
+pub use decorations::*;
+pub use dedup::*;
+pub use diff::*;
+pub use dimensions::*;
+pub use document::*;
+pub use draw::*;
+pub use dxf::*;
+pub use error::{CadError, GeometryError};
+pub use export::{format_number, ExportProfile, ExportResult};
+pub use fits::*;
+pub use flat::*;
+pub use hittest::*;
+pub use hull::*;
+pub use import::{parse_svg_transform, polygons_from_svg_path, ImportWarning, SvgImport};
+pub use inspection::*;
+pub use library::*;
+pub use markers::*;
+pub use macros::*;
+pub use measure::*;
+pub use memory::{free_all_detached, memory_report, KindUsage, MemoryReport, ObjectKind};
+pub use offset::*;
+pub use outline::*;
+pub use overlap::*;
+pub use params::{Generator, Parameters};
+pub use perimeter::*;
+pub use precision::*;
+pub use printability::*;
+pub use profiles::*;
+pub use projection::*;
+pub use provenance::*;
+pub use region::*;
+pub use resize::*;
+pub use rings::*;
+pub use rooms::*;
+pub use schedule::*;
+pub use search::{AttributeValue, SearchQuery};
+pub use segment::*;
+pub use selection::*;
+pub use signature::*;
+pub use simplify::*;
+pub use snap::*;
+pub use spatial::*;
+pub use spline::*;
+pub use split::*;
+pub use stations::*;
+pub use stencil::*;
+pub use straightness::*;
+pub use strict::*;
+pub use sweep::*;
+pub use textfit::*;
+pub use thickness::*;
+pub use tolerance::*;
+pub use zoom::*;
+
+// Tolerance used for geometric comparisons throughout the crate
+pub(crate) const EPSILON: f64 = 1e-9;
 
 // Enable console.log for debugging
 #[wasm_bindgen]
@@ -9,17 +165,18 @@ extern "C" {
     fn log(s: &str);
 }
 
+#[allow(unused_macros)]
 macro_rules! console_log {
     ($($t:tt)*) => (log(&format_args!($($t)*).to_string()))
 }
 
-#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
 pub struct Point {
     pub x: f64,
     pub y: f64,
 }
 
-#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
 pub struct Vector {
     pub x: f64,
     pub y: f64,
@@ -30,7 +187,46 @@ pub struct Polygon {
     pub vertices: Vec<Point>,
 }
 
+// Disjoint outer rings, e.g. the pieces of an offset or boolean result
+pub type MultiPolygon = Vec<Polygon>;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PolygonWithHoles {
+    pub outer: Polygon,
+    pub holes: Vec<Polygon>,
+}
+
+// Open or closed chain of points; a closed polyline does not repeat its first point
 #[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Polyline {
+    pub points: Vec<Point>,
+    pub closed: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Segment {
+    pub a: Point,
+    pub b: Point,
+}
+
+// Reference to a feature of a shape by index
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FeatureRef {
+    Hole(usize),
+    OuterVertex(usize),
+    // Outer edge from vertex i to vertex i + 1
+    Edge(usize),
+    // The whole outer boundary
+    Outline,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct BoundingBox {
+    pub min: Point,
+    pub max: Point,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct Matrix {
     pub m11: f64,
     pub m12: f64,
@@ -40,6 +236,32 @@ pub struct Matrix {
     pub dy: f64,
 }
 
+// A matrix as translate(translate) * rotate(rotation) * skew * scale(scale),
+// where skew shears x by tan(skew) per unit of y, as SVG's skewX does. A
+// reflection shows up as a negative scale.y.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Decomposition {
+    pub translation: Vector,
+    pub rotation: Angle,
+    pub scale: Vector,
+    pub skew: Angle,
+}
+
+// `Decomposition` with its angles as plain degrees, for JS
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct DecompositionDeg {
+    pub translation: Vector,
+    pub rotation_deg: f64,
+    pub scale: Vector,
+    pub skew_deg: f64,
+}
+
+impl Decomposition {
+    pub fn in_degrees(&self) -> DecompositionDeg {
+        DecompositionDeg { translation: self.translation, rotation_deg: self.rotation.to_degrees(), scale: self.scale, skew_deg: self.skew.to_degrees() }
+    }
+}
+
 impl Point {
     pub fn new(x: f64, y: f64) -> Point {
         Point { x, y }
@@ -63,28 +285,42 @@ impl Vector {
             Vector::new(self.x / mag, self.y / mag)
         }
     }
+
+    // None for a zero (or non-finite) vector, which has no direction
+    pub fn try_normalize(&self) -> Option<Vector> {
+        let mag = self.magnitude();
+        (mag.is_finite() && mag > EPSILON).then(|| Vector::new(self.x / mag, self.y / mag))
+    }
+}
+
+fn check_vertices(vertices: &[Point], at_least: usize, what: &str) -> Result<(), CadError> {
+    if vertices.len() < at_least {
+        return Err(CadError::Degenerate(format!("{} needs at least {} vertices, got {}", what, at_least, vertices.len())));
+    }
+    if let Some(p) = vertices.iter().find(|p| !(p.x.is_finite() && p.y.is_finite())) {
+        return Err(CadError::InvalidInput(format!("non-finite vertex ({}, {})", p.x, p.y)));
+    }
+    Ok(())
 }
 
 impl Polygon {
     pub fn new(vertices: Vec<Point>) -> Polygon {
         Polygon { vertices }
     }
+
+    // As `new`, for vertices that pass `validate`
+    pub fn try_new(vertices: Vec<Point>) -> Result<Polygon, GeometryError> {
+        let polygon = Polygon { vertices };
+        polygon.validate()?;
+        Ok(polygon)
+    }
     
     pub fn area(&self) -> f64 {
         if self.vertices.len() < 3 {
             return 0.0;
         }
         
-        let mut area = 0.0;
-        let n = self.vertices.len();
-        
-        for i in 0..n {
-            let j = (i + 1) % n;
-            area += self.vertices[i].x * self.vertices[j].y;
-            area -= self.vertices[j].x * self.vertices[i].y;
-        }
-        
-        area.abs() / 2.0
+        predicates::ring_signed_area(&self.vertices).abs()
     }
     
     pub fn perimeter(&self) -> f64 {
@@ -92,35 +328,75 @@ impl Polygon {
             return 0.0;
         }
         
-        let mut perimeter = 0.0;
         let n = self.vertices.len();
-        
-        for i in 0..n {
+        predicates::compensated_sum((0..n).map(|i| {
             let j = (i + 1) % n;
             let dx = self.vertices[j].x - self.vertices[i].x;
             let dy = self.vertices[j].y - self.vertices[i].y;
-            perimeter += (dx * dx + dy * dy).sqrt();
-        }
-        
-        perimeter
+            (dx * dx + dy * dy).sqrt()
+        }))
     }
     
+    // Centre of mass of the enclosed area. A polygon with no area (fewer
+    // than three vertices, or all of them on a line) falls back to the
+    // vertex average.
     pub fn centroid(&self) -> Point {
-        if self.vertices.is_empty() {
+        let Some(&o) = self.vertices.first() else {
             return Point::new(0.0, 0.0);
+        };
+        
+        // Shoelace terms about the first vertex, as in ring_signed_area
+        let n = self.vertices.len();
+        let terms: Vec<(f64, f64, f64)> = (1..n.saturating_sub(1)).map(|i| {
+            let (a, b) = (self.vertices[i], self.vertices[i + 1]);
+            let (ax, ay, bx, by) = (a.x - o.x, a.y - o.y, b.x - o.x, b.y - o.y);
+            let cross = ax * by - bx * ay;
+            (cross, (ax + bx) * cross, (ay + by) * cross)
+        }).collect();
+        let twice_area = predicates::compensated_sum(terms.iter().map(|t| t.0));
+        let b = self.bounding_box();
+        if twice_area.abs() <= EPSILON * b.width().max(b.height()).powi(2) {
+            return self.vertex_centroid();
         }
+        let cx = predicates::compensated_sum(terms.iter().map(|t| t.1)) / (3.0 * twice_area);
+        let cy = predicates::compensated_sum(terms.iter().map(|t| t.2)) / (3.0 * twice_area);
         
-        let mut cx = 0.0;
-        let mut cy = 0.0;
+        Point::new(o.x + cx, o.y + cy)
+    }
+    
+    // Plain average of the vertices, which drifts towards wherever the
+    // vertices are dense
+    pub fn vertex_centroid(&self) -> Point {
+        let Some(&o) = self.vertices.first() else {
+            return Point::new(0.0, 0.0);
+        };
         
-        for vertex in &self.vertices {
-            cx += vertex.x;
-            cy += vertex.y;
-        }
+        // Averaged as offsets from the first vertex, which stay small
+        // however far from the origin the polygon is
+        let n = self.vertices.len() as f64;
+        let cx = predicates::compensated_sum(self.vertices.iter().map(|v| v.x - o.x));
+        let cy = predicates::compensated_sum(self.vertices.iter().map(|v| v.y - o.y));
         
-        Point::new(cx / self.vertices.len() as f64, cy / self.vertices.len() as f64)
+        Point::new(o.x + cx / n, o.y + cy / n)
     }
     
+    // Checked versions of area, perimeter and centroid: errors where those
+    // quietly return 0 or the origin
+    pub fn try_area(&self) -> Result<f64, CadError> {
+        check_vertices(&self.vertices, 3, "area")?;
+        Ok(self.area())
+    }
+
+    pub fn try_perimeter(&self) -> Result<f64, CadError> {
+        check_vertices(&self.vertices, 2, "perimeter")?;
+        Ok(self.perimeter())
+    }
+
+    pub fn try_centroid(&self) -> Result<Point, CadError> {
+        check_vertices(&self.vertices, 1, "centroid")?;
+        Ok(self.centroid())
+    }
+
     pub fn transform(&self, matrix: &Matrix) -> Polygon {
         let mut new_vertices = Vec::new();
         
@@ -132,6 +408,155 @@ impl Polygon {
         
         Polygon::new(new_vertices)
     }
+    
+    pub fn bounding_box(&self) -> BoundingBox {
+        BoundingBox::from_points(&self.vertices)
+    }
+    
+    // Either winding; collinear runs of vertices are allowed
+    pub fn is_convex(&self) -> bool {
+        predicates::ring_is_convex(&self.vertices)
+    }
+}
+
+impl PolygonWithHoles {
+    pub fn new(outer: Polygon, holes: Vec<Polygon>) -> PolygonWithHoles {
+        PolygonWithHoles { outer, holes }
+    }
+
+    pub fn area(&self) -> f64 {
+        self.outer.area() - self.holes.iter().map(|h| h.area()).sum::<f64>()
+    }
+
+    pub fn contains_point(&self, p: &Point) -> bool {
+        predicates::point_in_ring(&self.outer.vertices, p) && !self.holes.iter().any(|h| predicates::point_in_ring(&h.vertices, p))
+    }
+}
+
+impl Polyline {
+    pub fn new(points: Vec<Point>, closed: bool) -> Polyline {
+        Polyline { points, closed }
+    }
+
+    pub fn length(&self) -> f64 {
+        let n = self.points.len();
+        let segments = if self.closed && n > 1 { n } else { n.saturating_sub(1) };
+        (0..segments).map(|i| {
+            let a = self.points[i];
+            let b = self.points[(i + 1) % n];
+            ((b.x - a.x).powi(2) + (b.y - a.y).powi(2)).sqrt()
+        }).sum()
+    }
+
+    pub fn transform(&self, matrix: &Matrix) -> Polyline {
+        Polyline::new(self.points.iter().map(|p| matrix.transform_point(p)).collect(), self.closed)
+    }
+
+    // Point `t` of the way along by length, 0 at the first point and 1 back
+    // at it for a closed polyline or at the last point otherwise. `t` is
+    // clamped to [0, 1]; an empty polyline has no points.
+    pub fn point_at(&self, t: f64) -> Option<Point> {
+        let n = self.points.len();
+        let first = *self.points.first()?;
+        let mut remaining = t.clamp(0.0, 1.0) * self.length();
+        let segments = if self.closed && n > 1 { n } else { n - 1 };
+        for i in 0..segments {
+            let (a, b) = (self.points[i], self.points[(i + 1) % n]);
+            let step = (b.x - a.x).hypot(b.y - a.y);
+            if remaining <= step && step > 0.0 {
+                let f = remaining / step;
+                return Some(Point::new(a.x + (b.x - a.x) * f, a.y + (b.y - a.y) * f));
+            }
+            remaining -= step;
+        }
+        Some(if self.closed { first } else { self.points[n - 1] })
+    }
+}
+
+impl Segment {
+    pub fn new(a: Point, b: Point) -> Segment {
+        Segment { a, b }
+    }
+
+    pub fn length(&self) -> f64 {
+        (self.b.x - self.a.x).hypot(self.b.y - self.a.y)
+    }
+
+    pub fn midpoint(&self) -> Point {
+        Point::new((self.a.x + self.b.x) / 2.0, (self.a.y + self.b.y) / 2.0)
+    }
+
+    // Closest point of the segment to `p`
+    pub fn closest_point(&self, p: &Point) -> Point {
+        let (dx, dy) = (self.b.x - self.a.x, self.b.y - self.a.y);
+        let len2 = dx * dx + dy * dy;
+        if len2 == 0.0 {
+            return self.a;
+        }
+        let t = (((p.x - self.a.x) * dx + (p.y - self.a.y) * dy) / len2).clamp(0.0, 1.0);
+        Point::new(self.a.x + dx * t, self.a.y + dy * t)
+    }
+}
+
+impl BoundingBox {
+    pub fn new(min: Point, max: Point) -> BoundingBox {
+        BoundingBox { min, max }
+    }
+    
+    // An empty point set yields a zero-sized box at the origin
+    pub fn from_points(points: &[Point]) -> BoundingBox {
+        if points.is_empty() {
+            return BoundingBox::new(Point::new(0.0, 0.0), Point::new(0.0, 0.0));
+        }
+        
+        let mut min = points[0];
+        let mut max = points[0];
+        
+        for p in points {
+            min.x = min.x.min(p.x);
+            min.y = min.y.min(p.y);
+            max.x = max.x.max(p.x);
+            max.y = max.y.max(p.y);
+        }
+        
+        BoundingBox::new(min, max)
+    }
+    
+    pub fn width(&self) -> f64 {
+        self.max.x - self.min.x
+    }
+    
+    pub fn height(&self) -> f64 {
+        self.max.y - self.min.y
+    }
+    
+    pub fn area(&self) -> f64 {
+        self.width() * self.height()
+    }
+
+    pub fn center(&self) -> Point {
+        Point::new((self.min.x + self.max.x) / 2.0, (self.min.y + self.max.y) / 2.0)
+    }
+
+    // Boundaries count as touching
+    pub fn intersects(&self, other: &BoundingBox) -> bool {
+        self.min.x <= other.max.x && other.min.x <= self.max.x && self.min.y <= other.max.y && other.min.y <= self.max.y
+    }
+
+    pub fn contains_point(&self, p: &Point) -> bool {
+        p.x >= self.min.x && p.x <= self.max.x && p.y >= self.min.y && p.y <= self.max.y
+    }
+
+    pub fn contains(&self, other: &BoundingBox) -> bool {
+        self.contains_point(&other.min) && self.contains_point(&other.max)
+    }
+
+    pub fn union(&self, other: &BoundingBox) -> BoundingBox {
+        BoundingBox::new(
+            Point::new(self.min.x.min(other.min.x), self.min.y.min(other.min.y)),
+            Point::new(self.max.x.max(other.max.x), self.max.y.max(other.max.y)),
+        )
+    }
 }
 
 impl Matrix {
@@ -159,7 +584,9 @@ impl Matrix {
         }
     }
     
+    // Positive angles turn counter-clockwise in the active coordinate convention
     pub fn rotate(angle: f64) -> Matrix {
+        let angle = convention::user_angle(angle);
         let cos_a = angle.cos();
         let sin_a = angle.sin();
         Matrix {
@@ -169,6 +596,33 @@ impl Matrix {
         }
     }
     
+    // `rotate` with the angle in degrees
+    pub fn rotate_deg(degrees: f64) -> Matrix {
+        Matrix::rotate(Angle::from_degrees(degrees).radians())
+    }
+
+    // Rotation by `angle` about `center` rather than the origin
+    pub fn rotate_about(angle: f64, center: &Point) -> Matrix {
+        Matrix::translate(center.x, center.y)
+            .multiply(&Matrix::rotate(angle))
+            .multiply(&Matrix::translate(-center.x, -center.y))
+    }
+
+    pub fn scale_about(sx: f64, sy: f64, center: &Point) -> Matrix {
+        Matrix::translate(center.x, center.y)
+            .multiply(&Matrix::scale(sx, sy))
+            .multiply(&Matrix::translate(-center.x, -center.y))
+    }
+
+    pub fn transform_point(&self, p: &Point) -> Point {
+        Point::new(self.m11 * p.x + self.m12 * p.y + self.dx, self.m21 * p.x + self.m22 * p.y + self.dy)
+    }
+
+    // Without the translation, as for directions and offsets
+    pub fn transform_vector(&self, v: &Vector) -> Vector {
+        Vector::new(self.m11 * v.x + self.m12 * v.y, self.m21 * v.x + self.m22 * v.y)
+    }
+
     pub fn multiply(&self, other: &Matrix) -> Matrix {
         Matrix {
             m11: self.m11 * other.m11 + self.m12 * other.m21,
@@ -179,11 +633,55 @@ impl Matrix {
             dy: self.m21 * other.dx + self.m22 * other.dy + self.dy,
         }
     }
+
+    pub fn determinant(&self) -> f64 {
+        self.m11 * self.m22 - self.m12 * self.m21
+    }
+
+    // Errors for a singular matrix (one that flattens the plane onto a line
+    // or point), judged relative to the size of its entries
+    pub fn try_inverse(&self) -> Result<Matrix, CadError> {
+        let det = self.determinant();
+        let size = self.m11.abs().max(self.m12.abs()).max(self.m21.abs()).max(self.m22.abs());
+        if !det.is_finite() || det.abs() <= EPSILON * size * size {
+            return Err(CadError::Degenerate(format!("matrix is singular (determinant {})", det)));
+        }
+        let (m11, m12, m21, m22) = (self.m22 / det, -self.m12 / det, -self.m21 / det, self.m11 / det);
+        Ok(Matrix {
+            m11, m12,
+            m21, m22,
+            dx: -(m11 * self.dx + m12 * self.dy),
+            dy: -(m21 * self.dx + m22 * self.dy),
+        })
+    }
+
+    // `try_inverse`, None for a singular matrix
+    pub fn inverse(&self) -> Option<Matrix> {
+        self.try_inverse().ok()
+    }
+
+    // Translation, rotation, skew and scale that compose back to this matrix.
+    // The rotation is that of the x axis, in the active convention; a matrix
+    // that collapses the x axis decomposes with no rotation or skew.
+    pub fn decompose(&self) -> Decomposition {
+        let sx = self.m11.hypot(self.m21);
+        let (cos, sin) = if sx > 0.0 { (self.m11 / sx, self.m21 / sx) } else { (1.0, 0.0) };
+        // Second column with the rotation taken back out: (sy tan(skew), sy)
+        let shear = cos * self.m12 + sin * self.m22;
+        let sy = cos * self.m22 - sin * self.m12;
+        let skew = if sy != 0.0 { (shear / sy).atan() } else { 0.0 };
+        Decomposition {
+            translation: Vector::new(self.dx, self.dy),
+            rotation: Angle::from_radians(convention::user_angle(sin.atan2(cos))),
+            scale: Vector::new(sx, sy),
+            skew: Angle::from_radians(skew),
+        }
+    }
 }
 
 // Simple WASM bindings - just functions, no complex types
 #[wasm_bindgen]
-pub fn create_square(size: f64) -> JsValue {
+pub fn create_square(size: f64) -> Result<JsValue, JsValue> {
     let vertices = vec![
         Point::new(0.0, 0.0),
         Point::new(size, 0.0),
@@ -191,49 +689,220 @@ pub fn create_square(size: f64) -> JsValue {
         Point::new(0.0, size),
     ];
     let polygon = Polygon::new(vertices);
-    serde_wasm_bindgen::to_value(&polygon).unwrap()
+    to_js(&polygon)
 }
 
 #[wasm_bindgen]
-pub fn create_triangle(base: f64, height: f64) -> JsValue {
+pub fn create_triangle(base: f64, height: f64) -> Result<JsValue, JsValue> {
     let vertices = vec![
         Point::new(0.0, 0.0),
         Point::new(base, 0.0),
         Point::new(base / 2.0, height),
     ];
     let polygon = Polygon::new(vertices);
-    serde_wasm_bindgen::to_value(&polygon).unwrap()
+    to_js(&polygon)
+}
+
+// `segments` points round the circle, counter-clockwise on screen from +x
+#[wasm_bindgen]
+pub fn create_circle(cx: f64, cy: f64, radius: f64, segments: usize) -> Result<JsValue, JsValue> {
+    let arc = Arc { center: Point::new(cx, cy), radius, start_angle: 0.0, sweep: std::f64::consts::TAU }.user();
+    let mut points = arc.to_polyline(segments.max(3)).points;
+    points.pop();
+    to_js(&Polygon::new(points))
+}
+
+// The arc over `segments` steps, closed by its chord; angles in radians
+#[wasm_bindgen]
+pub fn create_arc_polygon(cx: f64, cy: f64, radius: f64, start_angle: f64, sweep: f64, segments: usize) -> Result<JsValue, JsValue> {
+    let arc = Arc { center: Point::new(cx, cy), radius, start_angle, sweep }.user();
+    let mut points = arc.to_polyline(segments).points;
+    if sweep.abs() >= std::f64::consts::TAU {
+        points.pop();
+    }
+    to_js(&Polygon::new(points))
+}
+
+// Thrown to JS as an Error whose `code` is `CadError::code`, so the app can
+// switch on the kind and show the message
+fn js_error(e: CadError) -> JsValue {
+    let error = js_sys::Error::new(&e.to_string());
+    let _ = js_sys::Reflect::set(&error, &JsValue::from_str("code"), &JsValue::from_str(e.code()));
+    error.into()
+}
+
+// A value that won't serialize is thrown like any other error, rather than
+// aborting the module
+fn to_js<T: Serialize + ?Sized>(value: &T) -> Result<JsValue, JsValue> {
+    serde_wasm_bindgen::to_value(value).map_err(|e| js_error(CadError::Serialization(e.to_string())))
+}
+
+fn from_js<T: serde::de::DeserializeOwned>(value: &JsValue) -> Result<T, JsValue> {
+    serde_wasm_bindgen::from_value(value.clone()).map_err(|e| js_error(CadError::InvalidInput(e.to_string())))
+}
+
+// Options left undefined or null take their defaults; anything else has to
+// parse
+fn options_from<T: serde::de::DeserializeOwned + Default>(value: &JsValue) -> Result<T, JsValue> {
+    if value.is_undefined() || value.is_null() {
+        Ok(T::default())
+    } else {
+        from_js(value)
+    }
+}
+
+// Malformed input is an error rather than an empty polygon, so it can't
+// pass for degenerate geometry
+fn polygon_from(vertices: &JsValue) -> Result<Polygon, JsValue> {
+    from_js(vertices)
+}
+
+#[wasm_bindgen]
+pub fn calculate_area(vertices: &JsValue) -> Result<f64, JsValue> {
+    polygon_from(vertices)?.try_area().map_err(js_error)
+}
+
+#[wasm_bindgen]
+pub fn calculate_perimeter(vertices: &JsValue) -> Result<f64, JsValue> {
+    polygon_from(vertices)?.try_perimeter().map_err(js_error)
+}
+
+#[wasm_bindgen]
+pub fn calculate_centroid(vertices: &JsValue) -> Result<JsValue, JsValue> {
+    let centroid = polygon_from(vertices)?.try_centroid().map_err(js_error)?;
+    to_js(&centroid)
+}
+
+#[wasm_bindgen]
+pub fn calculate_vertex_centroid(vertices: &JsValue) -> Result<JsValue, JsValue> {
+    let polygon = polygon_from(vertices)?;
+    check_vertices(&polygon.vertices, 1, "centroid").map_err(js_error)?;
+    to_js(&polygon.vertex_centroid())
+}
+
+#[wasm_bindgen]
+pub fn calculate_signed_area(vertices: &JsValue) -> Result<f64, JsValue> {
+    Ok(polygon_from(vertices)?.signed_area())
+}
+
+#[wasm_bindgen]
+pub fn is_clockwise(vertices: &JsValue) -> Result<bool, JsValue> {
+    Ok(polygon_from(vertices)?.is_clockwise())
+}
+
+#[wasm_bindgen]
+pub fn ensure_winding(vertices: &JsValue, ccw: bool) -> Result<JsValue, JsValue> {
+    to_js(&polygon_from(vertices)?.ensure_winding(ccw))
+}
+
+#[wasm_bindgen]
+pub fn is_convex(vertices: &JsValue) -> Result<bool, JsValue> {
+    Ok(polygon_from(vertices)?.is_convex())
+}
+
+fn matrix_from(matrix_data: &JsValue) -> Result<Matrix, JsValue> {
+    from_js(matrix_data)
+}
+
+#[wasm_bindgen]
+pub fn transform_polygon(vertices: &JsValue, matrix_data: &JsValue) -> Result<JsValue, JsValue> {
+    let transformed = polygon_from(vertices)?.transform(&matrix_from(matrix_data)?);
+    to_js(&transformed)
+}
+
+fn polyline_from(polyline: &JsValue) -> Result<Polyline, JsValue> {
+    from_js(polyline)
+}
+
+#[wasm_bindgen]
+pub fn polyline_length(polyline: &JsValue) -> Result<f64, JsValue> {
+    Ok(polyline_from(polyline)?.length())
+}
+
+#[wasm_bindgen]
+pub fn transform_polyline(polyline: &JsValue, matrix_data: &JsValue) -> Result<JsValue, JsValue> {
+    let transformed = polyline_from(polyline)?.transform(&matrix_from(matrix_data)?);
+    to_js(&transformed)
 }
 
+// Null for an empty polyline
 #[wasm_bindgen]
-pub fn calculate_area(vertices: &JsValue) -> f64 {
-    let polygon: Polygon = serde_wasm_bindgen::from_value(vertices.clone()).unwrap_or_else(|_| Polygon::new(vec![]));
-    polygon.area()
+pub fn polyline_point_at(polyline: &JsValue, t: f64) -> Result<JsValue, JsValue> {
+    to_js(&polyline_from(polyline)?.point_at(t))
 }
 
 #[wasm_bindgen]
-pub fn calculate_perimeter(vertices: &JsValue) -> f64 {
-    let polygon: Polygon = serde_wasm_bindgen::from_value(vertices.clone()).unwrap_or_else(|_| Polygon::new(vec![]));
-    polygon.perimeter()
+pub fn invert_matrix(matrix_data: &JsValue) -> Result<JsValue, JsValue> {
+    let inverse = matrix_from(matrix_data)?.try_inverse().map_err(js_error)?;
+    to_js(&inverse)
 }
 
 #[wasm_bindgen]
-pub fn calculate_centroid(vertices: &JsValue) -> JsValue {
-    let polygon: Polygon = serde_wasm_bindgen::from_value(vertices.clone()).unwrap_or_else(|_| Polygon::new(vec![]));
-    let centroid = polygon.centroid();
-    serde_wasm_bindgen::to_value(&centroid).unwrap()
+pub fn matrix_determinant(matrix_data: &JsValue) -> Result<f64, JsValue> {
+    Ok(matrix_from(matrix_data)?.determinant())
 }
 
 #[wasm_bindgen]
-pub fn transform_polygon(vertices: &JsValue, matrix_data: &JsValue) -> JsValue {
-    let polygon: Polygon = serde_wasm_bindgen::from_value(vertices.clone()).unwrap_or_else(|_| Polygon::new(vec![]));
-    let matrix: Matrix = serde_wasm_bindgen::from_value(matrix_data.clone()).unwrap_or_else(|_| Matrix::identity());
-    let transformed = polygon.transform(&matrix);
-    serde_wasm_bindgen::to_value(&transformed).unwrap()
+pub fn transform_point(matrix_data: &JsValue, x: f64, y: f64) -> Result<JsValue, JsValue> {
+    let p = matrix_from(matrix_data)?.transform_point(&Point::new(x, y));
+    to_js(&p)
 }
 
 #[wasm_bindgen]
-pub fn create_matrix(translate_x: f64, translate_y: f64, rotate_angle: f64, scale_x: f64, scale_y: f64) -> JsValue {
+pub fn transform_vector(matrix_data: &JsValue, x: f64, y: f64) -> Result<JsValue, JsValue> {
+    let v = matrix_from(matrix_data)?.transform_vector(&Vector::new(x, y));
+    to_js(&v)
+}
+
+// { translation, rotation, scale, skew }, angles in radians
+#[wasm_bindgen]
+pub fn decompose_matrix(matrix_data: &JsValue) -> Result<JsValue, JsValue> {
+    to_js(&matrix_from(matrix_data)?.decompose())
+}
+
+// { translation, rotation_deg, scale, skew_deg }
+#[wasm_bindgen]
+pub fn decompose_matrix_deg(matrix_data: &JsValue) -> Result<JsValue, JsValue> {
+    to_js(&matrix_from(matrix_data)?.decompose().in_degrees())
+}
+
+#[wasm_bindgen]
+pub fn rotation_matrix_about(radians: f64, center_x: f64, center_y: f64) -> Result<JsValue, JsValue> {
+    to_js(&Matrix::rotate_about(radians, &Point::new(center_x, center_y)))
+}
+
+#[wasm_bindgen]
+pub fn scale_matrix_about(sx: f64, sy: f64, center_x: f64, center_y: f64) -> Result<JsValue, JsValue> {
+    to_js(&Matrix::scale_about(sx, sy, &Point::new(center_x, center_y)))
+}
+
+// Unit vector along (x, y); errors for a zero vector
+#[wasm_bindgen]
+pub fn normalize_vector(x: f64, y: f64) -> Result<JsValue, JsValue> {
+    let unit = Vector::new(x, y).try_normalize().ok_or_else(|| js_error(CadError::Degenerate("zero vector has no direction".to_string())))?;
+    to_js(&unit)
+}
+
+// Rotation about the origin, in radians or degrees
+#[wasm_bindgen]
+pub fn rotation_matrix(radians: f64) -> Result<JsValue, JsValue> {
+    to_js(&Matrix::rotate(radians))
+}
+
+#[wasm_bindgen]
+pub fn rotation_matrix_deg(degrees: f64) -> Result<JsValue, JsValue> {
+    to_js(&Matrix::rotate_deg(degrees))
+}
+
+// `create_matrix` with the rotation in degrees
+#[wasm_bindgen]
+pub fn create_matrix_deg(translate_x: f64, translate_y: f64, rotate_degrees: f64, scale_x: f64, scale_y: f64) -> Result<JsValue, JsValue> {
+    create_matrix(translate_x, translate_y, Angle::from_degrees(rotate_degrees).radians(), scale_x, scale_y)
+}
+
+// `rotate_angle` is in radians
+#[wasm_bindgen]
+pub fn create_matrix(translate_x: f64, translate_y: f64, rotate_angle: f64, scale_x: f64, scale_y: f64) -> Result<JsValue, JsValue> {
     let mut matrix = Matrix::identity();
     
     if translate_x != 0.0 || translate_y != 0.0 {
@@ -248,7 +917,7 @@ pub fn create_matrix(translate_x: f64, translate_y: f64, rotate_angle: f64, scal
         matrix = matrix.multiply(&Matrix::scale(scale_x, scale_y));
     }
     
-    serde_wasm_bindgen::to_value(&matrix).unwrap()
+    to_js(&matrix)
 }
 
 #[cfg(test)]
@@ -304,6 +973,81 @@ mod tests {
         assert_eq!(centroid.y, 1.0);
     }
 
+    #[test]
+    fn test_l_shape_centroid_weights_by_area() {
+        // A 4 x 1 foot and a 1 x 3 upright; the long foot pulls the true
+        // centroid right and down of the vertex average
+        let l = Polygon::new(vec![
+            Point::new(0.0, 0.0), Point::new(4.0, 0.0), Point::new(4.0, 1.0),
+            Point::new(1.0, 1.0), Point::new(1.0, 4.0), Point::new(0.0, 4.0),
+        ]);
+        let c = l.centroid();
+        let expected = (4.0 * 2.0 + 3.0 * 0.5) / 7.0;
+        assert!((c.x - expected).abs() < 1e-12 && (c.y - expected).abs() < 1e-12);
+        assert_eq!(l.vertex_centroid(), Point::new(10.0 / 6.0, 10.0 / 6.0));
+        // Extra vertices along an edge move the average but not the centroid
+        let mut dense = l.vertices.clone();
+        dense.splice(1..1, (1..40).map(|i| Point::new(i as f64 / 10.0, 0.0)));
+        let dense = Polygon::new(dense);
+        assert!((dense.centroid().x - c.x).abs() < 1e-12 && (dense.centroid().y - c.y).abs() < 1e-12);
+        assert!((dense.vertex_centroid().y - c.y).abs() > 0.5);
+        // Reversed winding, and no area at all
+        let mut reversed = l.vertices.clone();
+        reversed.reverse();
+        assert!((Polygon::new(reversed).centroid().x - c.x).abs() < 1e-12);
+        let flat = Polygon::new(vec![Point::new(0.0, 0.0), Point::new(1.0, 0.0), Point::new(3.0, 0.0)]);
+        assert_eq!(flat.centroid(), flat.vertex_centroid());
+    }
+
+    #[test]
+    fn test_convexity() {
+        let square = vec![Point::new(0.0, 0.0), Point::new(2.0, 0.0), Point::new(2.0, 2.0), Point::new(0.0, 2.0)];
+        assert!(Polygon::new(square.clone()).is_convex());
+        let mut reversed = square.clone();
+        reversed.reverse();
+        assert!(Polygon::new(reversed).is_convex());
+        // Collinear and repeated vertices along the edges
+        let padded = vec![
+            Point::new(0.0, 0.0), Point::new(1.0, 0.0), Point::new(2.0, 0.0), Point::new(2.0, 0.0),
+            Point::new(2.0, 2.0), Point::new(0.0, 2.0), Point::new(0.0, 1.0),
+        ];
+        assert!(Polygon::new(padded).is_convex());
+        let l = vec![Point::new(0.0, 0.0), Point::new(2.0, 0.0), Point::new(2.0, 1.0), Point::new(1.0, 1.0), Point::new(1.0, 2.0), Point::new(0.0, 2.0)];
+        assert!(!Polygon::new(l).is_convex());
+        // Every turn the same way, but twice round
+        let star: Vec<Point> = (0..5).map(|i| {
+            let a = std::f64::consts::TAU * (2 * i) as f64 / 5.0;
+            Point::new(a.cos(), a.sin())
+        }).collect();
+        assert!(!Polygon::new(star).is_convex());
+        assert!(!Polygon::new(vec![Point::new(0.0, 0.0), Point::new(1.0, 0.0), Point::new(2.0, 0.0)]).is_convex());
+        assert!(!Polygon::new(square[..2].to_vec()).is_convex());
+    }
+
+    #[test]
+    fn test_rotate_deg_matches_radians() {
+        assert_eq!(Matrix::rotate_deg(90.0), Matrix::rotate(std::f64::consts::FRAC_PI_2));
+        assert_eq!(Matrix::rotate_deg(-45.0), Matrix::rotate(-std::f64::consts::FRAC_PI_4));
+        assert_eq!(Matrix::rotate_deg(180.0), Matrix::rotate(std::f64::consts::PI));
+    }
+
+    #[test]
+    fn test_far_from_origin() {
+        // Unit squares at survey coordinates, where a plain shoelace sum is
+        // off by ~1e-2
+        let mut seed = 7u64;
+        for _ in 0..50 {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let x = 1e7 + (seed >> 11) as f64 / (1u64 << 53) as f64;
+            let y = 1e7 + (seed >> 40) as f64 / (1u64 << 24) as f64;
+            let polygon = Polygon::new(vec![Point::new(x, y), Point::new(x + 1.0, y), Point::new(x + 1.0, y + 1.0), Point::new(x, y + 1.0)]);
+            assert!((polygon.area() - 1.0).abs() < 1e-9);
+            assert!((polygon.perimeter() - 4.0).abs() < 1e-8);
+            let c = polygon.centroid();
+            assert!((c.x - (x + 0.5)).abs() < 1e-8 && (c.y - (y + 0.5)).abs() < 1e-8);
+        }
+    }
+
     #[test]
     fn test_transform() {
         let vertices = vec![
@@ -373,4 +1117,96 @@ mod tests {
         // Perimeter should be approximately 6.0 (for unit hexagon)
         assert!((polygon.perimeter() - 6.0).abs() < 0.1);
     }
+
+    #[test]
+    fn test_checked_variants_report_degenerate_input() {
+        assert!(Vector::new(0.0, 0.0).try_normalize().is_none());
+        assert!(Vector::new(f64::NAN, 1.0).try_normalize().is_none());
+        let unit = Vector::new(0.0, -2.0).try_normalize().unwrap();
+        assert_eq!((unit.x, unit.y), (0.0, -1.0));
+
+        let segment = Polygon::new(vec![Point::new(0.0, 0.0), Point::new(1.0, 0.0)]);
+        let error = segment.try_area().unwrap_err();
+        assert_eq!(error.code(), "degenerate geometry");
+        assert!(error.to_string().starts_with(error.code()));
+        assert_eq!(segment.try_perimeter().unwrap(), 2.0);
+        assert!(matches!(Polygon::new(vec![]).try_centroid(), Err(CadError::Degenerate(_))));
+        assert!(matches!(Polygon::new(vec![Point::new(f64::INFINITY, 0.0)]).try_centroid(), Err(CadError::InvalidInput(_))));
+        assert_eq!(Polygon::new(vec![Point::new(0.0, 0.0), Point::new(2.0, 0.0), Point::new(0.0, 2.0)]).try_area().unwrap(), 2.0);
+
+        assert!(split::check_lines(&[(Point::new(0.0, 0.0), Vector::new(1.0, 0.0)), (Point::new(0.0, 0.0), Vector::new(0.0, 0.0))]).is_err());
+    }
+
+    #[test]
+    fn test_matrix_inverse() {
+        let m = Matrix::translate(3.0, -2.0).multiply(&Matrix::rotate(0.7)).multiply(&Matrix::scale(2.0, 0.5));
+        let product = m.multiply(&m.try_inverse().unwrap());
+        for (value, expected) in [(product.m11, 1.0), (product.m12, 0.0), (product.m21, 0.0), (product.m22, 1.0), (product.dx, 0.0), (product.dy, 0.0)] {
+            assert!((value - expected).abs() < 1e-12);
+        }
+        let flat = Matrix::scale(1.0, 0.0);
+        assert_eq!(flat.try_inverse().unwrap_err().code(), "degenerate geometry");
+        assert!(Matrix::scale(1e-6, 1e-6).try_inverse().is_ok());
+        assert!(flat.inverse().is_none());
+
+        // Screen to model and back
+        let inverse = m.inverse().unwrap();
+        let p = Point::new(-4.0, 7.5);
+        let back = inverse.transform_point(&m.transform_point(&p));
+        assert!((back.x - p.x).abs() < 1e-12 && (back.y - p.y).abs() < 1e-12);
+        let v = m.transform_vector(&Vector::new(1.0, 0.0));
+        let moved = m.transform_point(&Point::new(1.0, 0.0));
+        assert!((v.x - (moved.x - m.dx)).abs() < 1e-12 && (v.y - (moved.y - m.dy)).abs() < 1e-12);
+        assert!((m.determinant() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_matrix_decomposition() {
+        let close = |a: &Matrix, b: &Matrix| [a.m11 - b.m11, a.m12 - b.m12, a.m21 - b.m21, a.m22 - b.m22, a.dx - b.dx, a.dy - b.dy].iter().all(|d| d.abs() < 1e-12);
+        let skew = |t: f64| Matrix { m11: 1.0, m12: t.tan(), m21: 0.0, m22: 1.0, dx: 0.0, dy: 0.0 };
+        for (rotation, sx, sy, shear) in [(0.7, 2.0, 0.5, 0.0), (-2.5, 1.0, -3.0, 0.3), (std::f64::consts::PI / 4.0, 1.0, 1.0, -0.4)] {
+            let m = Matrix::translate(3.0, -2.0).multiply(&Matrix::rotate(rotation)).multiply(&skew(shear)).multiply(&Matrix::scale(sx, sy));
+            let d = m.decompose();
+            assert!((d.rotation.radians() - rotation).abs() < 1e-12);
+            assert!((d.scale.x - sx).abs() < 1e-12 && (d.scale.y - sy).abs() < 1e-12);
+            assert!((d.skew.radians() - shear).abs() < 1e-12);
+            assert_eq!(d.translation, Vector::new(3.0, -2.0));
+            let rebuilt = Matrix::translate(d.translation.x, d.translation.y).multiply(&Matrix::rotate(d.rotation.radians()))
+                .multiply(&skew(d.skew.radians())).multiply(&Matrix::scale(d.scale.x, d.scale.y));
+            assert!(close(&rebuilt, &m));
+        }
+        assert!((Matrix::rotate_deg(45.0).decompose().rotation.to_degrees() - 45.0).abs() < 1e-12);
+        let degrees = Matrix::rotate_deg(30.0).multiply(&skew(-0.25f64.to_radians())).decompose().in_degrees();
+        assert!((degrees.rotation_deg - 30.0).abs() < 1e-12 && (degrees.skew_deg + 0.25).abs() < 1e-12);
+
+        // About a centre: the centre stays put
+        let center = Point::new(5.0, 1.0);
+        assert!(close(&Matrix::rotate_about(0.0, &center), &Matrix::identity()));
+        for m in [Matrix::rotate_about(1.1, &center), Matrix::scale_about(2.0, -3.0, &center)] {
+            let fixed = m.transform_point(&center);
+            assert!((fixed.x - center.x).abs() < 1e-12 && (fixed.y - center.y).abs() < 1e-12);
+        }
+        let quarter = Matrix::rotate_about(std::f64::consts::FRAC_PI_2, &center).transform_point(&Point::new(6.0, 1.0));
+        assert!((quarter.x - 5.0).abs() < 1e-12 && (quarter.y - 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_polyline_sampling() {
+        let open = Polyline::new(vec![Point::new(0.0, 0.0), Point::new(3.0, 0.0), Point::new(3.0, 1.0)], false);
+        assert_eq!(open.point_at(0.0), Some(Point::new(0.0, 0.0)));
+        assert_eq!(open.point_at(0.5), Some(Point::new(2.0, 0.0)));
+        assert_eq!(open.point_at(0.875), Some(Point::new(3.0, 0.5)));
+        assert_eq!(open.point_at(2.0), Some(Point::new(3.0, 1.0)));
+        // Closed, the way back along the diagonal counts too
+        let closed = Polyline::new(open.points.clone(), true);
+        let length = closed.length();
+        let back = closed.point_at((4.0 + 10f64.sqrt() / 2.0) / length).unwrap();
+        assert!((back.x - 1.5).abs() < 1e-12 && (back.y - 0.5).abs() < 1e-12);
+        assert_eq!(closed.point_at(1.0), Some(Point::new(0.0, 0.0)));
+        assert_eq!(Polyline::new(vec![], false).point_at(0.5), None);
+
+        let moved = open.transform(&Matrix::translate(1.0, 1.0).multiply(&Matrix::scale(2.0, 2.0)));
+        assert_eq!(moved.points[2], Point::new(7.0, 3.0));
+        assert!(!moved.closed && (moved.length() - 2.0 * open.length()).abs() < 1e-12);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,360 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::binary::{from_bytes, to_bytes};
+use crate::params::generate;
+use crate::{js_error, AttributeValue, CadError, Document, Matrix, Parameters, Shape, ShapeId};
+
+// What a library item places. From JS this is the externally tagged form,
+// e.g. { Generated: { parameters, calls } }.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum LibraryContent {
+    // Shapes as copied out of a document, in the item's own space; their
+    // transforms, attributes, labels and layers are kept, their groups not
+    Shapes(Vec<Shape>),
+    // One shape per factory call over the item's parameters, whose
+    // expressions are the defaults for each placement to override
+    Generated { parameters: Parameters, calls: Vec<String> },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LibraryItem {
+    pub name: String,
+    // 1 when first defined, one more each time it is redefined
+    pub version: u32,
+    pub content: LibraryContent,
+}
+
+impl LibraryItem {
+    // Names a placement may give values for
+    pub fn slots(&self) -> Vec<String> {
+        match &self.content {
+            LibraryContent::Shapes(_) => Vec::new(),
+            LibraryContent::Generated { parameters, .. } => parameters.names().map(|n| n.to_string()).collect(),
+        }
+    }
+
+    // Shapes for one placement, not yet in any document
+    fn instantiate(&self, values: &BTreeMap<String, f64>) -> Result<Vec<Shape>, CadError> {
+        if let Some(name) = values.keys().find(|&k| !self.slots().contains(k)) {
+            return Err(CadError::InvalidInput(format!("library item '{}' has no parameter '{}'", self.name, name)));
+        }
+        let mut shapes = match &self.content {
+            LibraryContent::Shapes(shapes) => shapes.clone(),
+            LibraryContent::Generated { parameters, calls } => {
+                let mut parameters = parameters.clone();
+                for (name, value) in values {
+                    parameters.set(name, &value.to_string())?;
+                }
+                let evaluated = parameters.evaluate()?;
+                calls.iter().map(|call| {
+                    let geometry = generate(call, &evaluated, parameters.units)?;
+                    Ok(Shape { id: 0, geometry, transform: Matrix::identity(), pivot: None, attributes: BTreeMap::new(), label: None, layer: None, group: None, placement: Matrix::identity() })
+                }).collect::<Result<Vec<Shape>, CadError>>()?
+            }
+        };
+        for shape in &mut shapes {
+            shape.group = None;
+            shape.attributes.insert("library_item".to_string(), AttributeValue::Text(self.name.clone()));
+            shape.attributes.insert("library_version".to_string(), AttributeValue::Number(self.version as f64));
+        }
+        Ok(shapes)
+    }
+}
+
+// Named, reusable components such as title blocks and standard brackets,
+// kept apart from any one document and placed into documents as groups
+#[wasm_bindgen]
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Library {
+    items: BTreeMap<String, LibraryItem>,
+}
+
+impl Library {
+    pub fn new() -> Library {
+        Library::default()
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.items.keys().cloned().collect()
+    }
+
+    pub fn item(&self, name: &str) -> Result<&LibraryItem, CadError> {
+        self.items.get(name).ok_or_else(|| CadError::UnknownLibraryItem(name.to_string()))
+    }
+
+    // Adds the item, or replaces the one of that name under the next
+    // version; returns the version. Generated content must evaluate with
+    // its defaults, and there must be something to place.
+    pub fn define(&mut self, name: &str, content: LibraryContent) -> Result<u32, CadError> {
+        let version = self.items.get(name).map_or(1, |item| item.version + 1);
+        let item = LibraryItem { name: name.to_string(), version, content };
+        if item.instantiate(&BTreeMap::new())?.is_empty() {
+            return Err(CadError::InvalidInput(format!("library item '{}' has no shapes", name)));
+        }
+        self.items.insert(name.to_string(), item);
+        Ok(version)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<LibraryItem, CadError> {
+        self.items.remove(name).ok_or_else(|| CadError::UnknownLibraryItem(name.to_string()))
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, CadError> {
+        to_bytes(self)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Library, CadError> {
+        from_bytes(bytes)
+    }
+}
+
+// Group placed from a library item, with the values it was placed with
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LibraryInstance {
+    pub group: ShapeId,
+    pub item: String,
+    pub version: u32,
+    pub parameters: BTreeMap<String, f64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct InstanceUpdate {
+    pub group: ShapeId,
+    pub from_version: u32,
+    pub to_version: u32,
+    pub removed: Vec<ShapeId>,
+    pub added: Vec<ShapeId>,
+    // Values the instance had for parameters the item no longer declares
+    pub dropped_parameters: Vec<String>,
+}
+
+// Outcome of `Document::update_instances`
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct InstanceUpdateReport {
+    pub updated: Vec<InstanceUpdate>,
+    // Instances already at the item's version
+    pub unchanged: Vec<ShapeId>,
+}
+
+impl Document {
+    pub fn library_instances(&self) -> &[LibraryInstance] {
+        &self.instances
+    }
+
+    // Places the item as a new group under `transform`, with `parameters`
+    // overriding its defaults; returns the group. Member shapes carry
+    // `library_item` and `library_version` attributes.
+    pub fn place_library_item(&mut self, library: &Library, name: &str, parameters: &BTreeMap<String, f64>, transform: Matrix) -> Result<ShapeId, CadError> {
+        let item = library.item(name)?;
+        transform.try_inverse()?;
+        let shapes = item.instantiate(parameters)?;
+        let ids = self.paste_shapes(&shapes);
+        let group = self.create_group(&ids)?;
+        self.set_transform(group, transform)?;
+        self.instances.push(LibraryInstance { group, item: name.to_string(), version: item.version, parameters: parameters.clone() });
+        Ok(group)
+    }
+
+    // Rebuilds every instance of the item placed from an older version,
+    // each with its own parameter values and group transform. Members are
+    // replaced, edits to them included; nothing changes unless every
+    // instance can be rebuilt. Instances whose group was removed are
+    // forgotten.
+    pub fn update_instances(&mut self, library: &Library, name: &str) -> Result<InstanceUpdateReport, CadError> {
+        let item = library.item(name)?;
+        let groups: Vec<ShapeId> = self.groups().map(|g| g.id).collect();
+        self.instances.retain(|i| groups.contains(&i.group));
+        let slots = item.slots();
+        let mut report = InstanceUpdateReport::default();
+        let mut rebuilt = Vec::new();
+        for (index, instance) in self.instances.iter().enumerate().filter(|(_, i)| i.item == name) {
+            if instance.version == item.version {
+                report.unchanged.push(instance.group);
+                continue;
+            }
+            let (kept, dropped): (BTreeMap<String, f64>, BTreeMap<String, f64>) = instance.parameters.iter().map(|(k, v)| (k.clone(), *v)).partition(|(k, _)| slots.contains(k));
+            rebuilt.push((index, item.instantiate(&kept)?, kept, dropped.into_keys().collect::<Vec<String>>()));
+        }
+
+        for (index, shapes, kept, dropped) in rebuilt {
+            let group = self.instances[index].group;
+            let removed: Vec<ShapeId> = self.shapes().filter(|s| s.group == Some(group)).map(|s| s.id).collect();
+            for &id in &removed {
+                self.remove_shape(id)?;
+            }
+            let shapes: Vec<Shape> = shapes.into_iter().map(|s| Shape { group: Some(group), ..s }).collect();
+            let added = self.paste_shapes(&shapes);
+            let instance = &mut self.instances[index];
+            report.updated.push(InstanceUpdate { group, from_version: instance.version, to_version: item.version, removed, added, dropped_parameters: dropped });
+            instance.version = item.version;
+            instance.parameters = kept;
+        }
+        Ok(report)
+    }
+}
+
+#[wasm_bindgen]
+impl Library {
+    #[wasm_bindgen(constructor)]
+    pub fn new_js() -> Library {
+        Library::new()
+    }
+
+    #[wasm_bindgen(js_name = names)]
+    pub fn names_js(&self) -> Vec<String> {
+        self.names()
+    }
+
+    // { name, version, content }
+    #[wasm_bindgen(js_name = item)]
+    pub fn item_js(&self, name: &str) -> Result<JsValue, JsValue> {
+        crate::to_js(self.item(name).map_err(js_error)?)
+    }
+
+    // `shapes` as from `copy_shapes`; returns the item's version
+    #[wasm_bindgen(js_name = define_shapes)]
+    pub fn define_shapes_js(&mut self, name: &str, shapes: &JsValue) -> Result<u32, JsValue> {
+        let shapes: Vec<Shape> = crate::from_js(shapes)?;
+        self.define(name, LibraryContent::Shapes(shapes)).map_err(js_error)
+    }
+
+    // `parameters` maps each parameter name to its default expression;
+    // `calls` are factory calls such as "create_rectangle(width, 20)"
+    #[wasm_bindgen(js_name = define_generated)]
+    pub fn define_generated_js(&mut self, name: &str, parameters: &JsValue, calls: Vec<String>) -> Result<u32, JsValue> {
+        let defaults: BTreeMap<String, String> = crate::from_js(parameters)?;
+        let mut parameters = Parameters::default();
+        for (slot, expression) in &defaults {
+            parameters.set(slot, expression).map_err(js_error)?;
+        }
+        self.define(name, LibraryContent::Generated { parameters, calls }).map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = remove)]
+    pub fn remove_js(&mut self, name: &str) -> Result<(), JsValue> {
+        self.remove(name).map(|_| ()).map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = to_json)]
+    pub fn to_json_js(&self) -> Result<String, JsValue> {
+        let value = self.serialize(&serde_wasm_bindgen::Serializer::json_compatible()).map_err(|e| js_error(CadError::Serialization(e.to_string())))?;
+        Ok(js_sys::JSON::stringify(&value)?.into())
+    }
+
+    #[wasm_bindgen(js_name = from_json)]
+    pub fn from_json_js(text: &str) -> Result<Library, JsValue> {
+        crate::from_js(&js_sys::JSON::parse(text)?)
+    }
+
+    #[wasm_bindgen(js_name = to_bytes)]
+    pub fn to_bytes_js(&self) -> Result<Vec<u8>, JsValue> {
+        self.to_bytes().map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = from_bytes)]
+    pub fn from_bytes_js(bytes: &[u8]) -> Result<Library, JsValue> {
+        Library::from_bytes(bytes).map_err(js_error)
+    }
+}
+
+#[wasm_bindgen]
+impl Document {
+    // `parameters` maps parameter names to numbers; returns the new group
+    #[wasm_bindgen(js_name = place_library_item)]
+    pub fn place_library_item_js(&mut self, library: &Library, name: &str, parameters: &JsValue, matrix: &JsValue) -> Result<ShapeId, JsValue> {
+        let parameters: BTreeMap<String, f64> = if parameters.is_undefined() || parameters.is_null() { BTreeMap::new() } else { crate::from_js(parameters)? };
+        let matrix: Matrix = crate::from_js(matrix)?;
+        self.place_library_item(library, name, &parameters, matrix).map_err(js_error)
+    }
+
+    // { updated: [{ group, from_version, to_version, removed, added, dropped_parameters }], unchanged }
+    #[wasm_bindgen(js_name = update_instances)]
+    pub fn update_instances_js(&mut self, library: &Library, name: &str) -> Result<JsValue, JsValue> {
+        crate::to_js(&self.update_instances(library, name).map_err(js_error)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Point, Polygon};
+
+    fn bracket(parameters: &[(&str, &str)], calls: &[&str]) -> LibraryContent {
+        let mut defaults = Parameters::default();
+        for (name, expression) in parameters {
+            defaults.set(name, expression).unwrap();
+        }
+        LibraryContent::Generated { parameters: defaults, calls: calls.iter().map(|c| c.to_string()).collect() }
+    }
+
+    fn world_bounds(doc: &Document, group: ShapeId) -> Vec<(Point, Point)> {
+        let mut shapes: Vec<&Shape> = doc.shapes().filter(|s| s.group == Some(group)).collect();
+        shapes.sort_by_key(|s| s.id);
+        shapes.iter().map(|s| {
+            let b = s.world_bounding_box();
+            (b.min, b.max)
+        }).collect()
+    }
+
+    #[test]
+    fn test_instances_regenerate_with_their_own_parameters() {
+        let mut library = Library::new();
+        assert_eq!(library.define("bracket", bracket(&[("width", "40"), ("height", "10")], &["create_rectangle(width, height)"])).unwrap(), 1);
+        // Bytes round trip before the library is used
+        let mut library = Library::from_bytes(&library.to_bytes().unwrap()).unwrap();
+
+        let mut doc = Document::new();
+        let wide = doc.place_library_item(&library, "bracket", &BTreeMap::from([("width".to_string(), 100.0)]), Matrix::identity()).unwrap();
+        let moved = doc.place_library_item(&library, "bracket", &BTreeMap::from([("height".to_string(), 5.0)]), Matrix::translate(0.0, 50.0)).unwrap();
+        assert_eq!(world_bounds(&doc, wide), vec![(Point::new(0.0, 0.0), Point::new(100.0, 10.0))]);
+        assert_eq!(world_bounds(&doc, moved), vec![(Point::new(0.0, 50.0), Point::new(40.0, 55.0))]);
+        let member = doc.group_members(wide).unwrap()[0];
+        assert_eq!(doc.shape(member).unwrap().attributes["library_item"], AttributeValue::Text("bracket".to_string()));
+        assert!(doc.place_library_item(&library, "bracket", &BTreeMap::from([("depth".to_string(), 1.0)]), Matrix::identity()).is_err());
+        assert!(matches!(doc.update_instances(&library, "hinge"), Err(CadError::UnknownLibraryItem(_))));
+
+        // Redefined with a flange and a thicker default; each instance keeps
+        // its own override and its placement
+        let flanged = bracket(&[("width", "40"), ("height", "12")], &["create_rectangle(width, height)", "create_square(height)"]);
+        assert_eq!(library.define("bracket", flanged).unwrap(), 2);
+        let report = doc.update_instances(&library, "bracket").unwrap();
+        assert_eq!(report.updated.len(), 2);
+        assert!(report.updated.iter().all(|u| u.from_version == 1 && u.to_version == 2 && u.removed.len() == 1 && u.added.len() == 2));
+        assert_eq!(world_bounds(&doc, wide), vec![(Point::new(0.0, 0.0), Point::new(100.0, 12.0)), (Point::new(0.0, 0.0), Point::new(12.0, 12.0))]);
+        assert_eq!(world_bounds(&doc, moved), vec![(Point::new(0.0, 50.0), Point::new(40.0, 55.0)), (Point::new(0.0, 50.0), Point::new(5.0, 55.0))]);
+        assert!(doc.library_instances().iter().all(|i| i.version == 2));
+
+        // Again, with nothing new, touches nothing
+        let report = doc.update_instances(&library, "bracket").unwrap();
+        assert_eq!((report.updated.len(), report.unchanged), (0, vec![wide, moved]));
+
+        // A renamed parameter drops the old value rather than failing
+        library.define("bracket", bracket(&[("span", "30"), ("height", "12")], &["create_rectangle(span, height)"])).unwrap();
+        let report = doc.update_instances(&library, "bracket").unwrap();
+        assert_eq!(report.updated[0].dropped_parameters, vec!["width".to_string()]);
+        assert_eq!(world_bounds(&doc, wide), vec![(Point::new(0.0, 0.0), Point::new(30.0, 12.0))]);
+    }
+
+    #[test]
+    fn test_static_items_and_bad_definitions() {
+        let mut source = Document::new();
+        let frame = source.add_shape(Polygon::new(vec![Point::new(0.0, 0.0), Point::new(4.0, 0.0), Point::new(4.0, 1.0), Point::new(0.0, 1.0)]));
+        source.set_label(frame, Some("title block".to_string())).unwrap();
+        let mut library = Library::new();
+        library.define("title", LibraryContent::Shapes(source.copy_shapes(&[frame]).unwrap())).unwrap();
+        assert!(library.define("empty", LibraryContent::Shapes(Vec::new())).is_err());
+        assert!(library.define("broken", bracket(&[("w", "1")], &["create_rectangle(w)"])).is_err());
+        assert_eq!(library.names(), vec!["title"]);
+
+        let mut doc = Document::new();
+        let group = doc.place_library_item(&library, "title", &BTreeMap::new(), Matrix::scale(2.0, 2.0)).unwrap();
+        assert_eq!(world_bounds(&doc, group), vec![(Point::new(0.0, 0.0), Point::new(8.0, 2.0))]);
+        let member = doc.group_members(group).unwrap()[0];
+        assert_eq!(doc.shape(member).unwrap().label.as_deref(), Some("title block"));
+        assert!(doc.place_library_item(&library, "title", &BTreeMap::new(), Matrix::scale(0.0, 1.0)).is_err());
+        assert_eq!(doc.shapes().count(), 1);
+    }
+}
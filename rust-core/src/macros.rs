@@ -0,0 +1,313 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::{js_error, CadError, Document, Matrix, Point, ShapeId, Vector};
+
+// Numeric argument of a recorded operation: a literal, or the name of one
+// of the macro's parameters, which `apply` can override
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum Arg {
+    Value(f64),
+    Parameter(String),
+}
+
+impl Arg {
+    fn value(&self, parameters: &BTreeMap<String, f64>) -> Result<f64, CadError> {
+        match self {
+            Arg::Value(v) => Ok(*v),
+            Arg::Parameter(name) => parameters.get(name).copied()
+                .ok_or_else(|| CadError::InvalidInput(format!("unknown macro parameter '{}'", name))),
+        }
+    }
+}
+
+// Document operation as a macro records it, applied to each shape it is given
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum Operation {
+    Offset { distance: Arg, quality: usize },
+    // Radians about each shape's pivot, as `Document::rotate_shape`
+    Rotate { angle: Arg },
+    Scale { sx: Arg, sy: Arg },
+    Translate { dx: Arg, dy: Arg },
+    PolarArray { center: Point, count: usize, sweep: Arg },
+    Sweep { direction: Vector, distance: Arg },
+    SetLayer { layer: Option<String> },
+}
+
+// Shape a step works on, relative to the run rather than by id: one of the
+// targets `apply` is given, or a shape an earlier step created
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    Target(usize),
+    Created { step: usize, index: usize },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MacroStep {
+    pub operation: Operation,
+    pub on: Vec<Role>,
+}
+
+// Recorded sequence of operations, replayable on other shapes. Serializes
+// as plain data, so JS can keep it as JSON.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct OperationMacro {
+    // Default value of each named parameter
+    pub parameters: BTreeMap<String, f64>,
+    pub steps: Vec<MacroStep>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MacroStepReport {
+    pub step: usize,
+    pub created: Vec<ShapeId>,
+    pub error: Option<String>,
+}
+
+// Outcome of `OperationMacro::apply`: the steps run, up to and including
+// the one that failed, if any
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MacroReport {
+    pub steps: Vec<MacroStepReport>,
+    // False when a step failed and the document was left as it was
+    pub applied: bool,
+}
+
+// Performs `operation` on each of `ids`, returning the shapes it created
+fn run(doc: &mut Document, operation: &Operation, ids: &[ShapeId], parameters: &BTreeMap<String, f64>) -> Result<Vec<ShapeId>, CadError> {
+    let arg = |a: &Arg| a.value(parameters);
+    let mut created = Vec::new();
+    for &id in ids {
+        match operation {
+            Operation::Offset { distance, quality } => doc.offset_shape(id, arg(distance)?, *quality)?,
+            Operation::Rotate { angle } => doc.rotate_shape(id, arg(angle)?)?,
+            Operation::Scale { sx, sy } => doc.scale_shape(id, arg(sx)?, arg(sy)?)?,
+            Operation::Translate { dx, dy } => {
+                let by = Matrix::translate(arg(dx)?, arg(dy)?);
+                doc.shape_mut(id)?.transform_world(&by);
+            }
+            Operation::PolarArray { center, count, sweep } => created.extend(doc.polar_array(id, *center, *count, arg(sweep)?)?),
+            Operation::Sweep { direction, distance } => created.extend(doc.sweep_shapes(&[id], direction, arg(distance)?)?),
+            Operation::SetLayer { layer } => doc.set_layer(id, layer.clone())?,
+        }
+    }
+    Ok(created)
+}
+
+// Builds a macro by performing operations on a document: each shape an
+// operation is given must be one of the recording's targets or a shape an
+// earlier step created, and is recorded by that role
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct MacroRecorder {
+    targets: Vec<ShapeId>,
+    recorded: OperationMacro,
+    created: Vec<Vec<ShapeId>>,
+}
+
+impl MacroRecorder {
+    pub fn new(targets: &[ShapeId]) -> MacroRecorder {
+        MacroRecorder { targets: targets.to_vec(), ..MacroRecorder::default() }
+    }
+
+    // Value an `Arg::Parameter` of this name takes while recording, and by
+    // default when the macro is applied
+    pub fn set_parameter(&mut self, name: &str, value: f64) {
+        self.recorded.parameters.insert(name.to_string(), value);
+    }
+
+    fn role(&self, id: ShapeId) -> Result<Role, CadError> {
+        if let Some(i) = self.targets.iter().position(|&t| t == id) {
+            return Ok(Role::Target(i));
+        }
+        self.created.iter().enumerate()
+            .find_map(|(step, ids)| ids.iter().position(|&c| c == id).map(|index| Role::Created { step, index }))
+            .ok_or_else(|| CadError::InvalidInput(format!("shape {} is neither a target nor made by an earlier step", id)))
+    }
+
+    // Performs the operation and records it; one that fails isn't recorded
+    pub fn perform(&mut self, doc: &mut Document, operation: Operation, on: &[ShapeId]) -> Result<Vec<ShapeId>, CadError> {
+        let roles = on.iter().map(|&id| self.role(id)).collect::<Result<Vec<_>, _>>()?;
+        let created = run(doc, &operation, on, &self.recorded.parameters)?;
+        self.recorded.steps.push(MacroStep { operation, on: roles });
+        self.created.push(created.clone());
+        Ok(created)
+    }
+
+    pub fn finish(&self) -> OperationMacro {
+        self.recorded.clone()
+    }
+}
+
+impl OperationMacro {
+    // Targets `apply` needs: one past the highest target role
+    pub fn target_count(&self) -> usize {
+        self.steps.iter().flat_map(|s| &s.on)
+            .filter_map(|r| match r {
+                Role::Target(i) => Some(i + 1),
+                Role::Created { .. } => None,
+            })
+            .max().unwrap_or(0)
+    }
+
+    // Replays the steps on `targets`, with `overrides` replacing parameter
+    // defaults. The steps apply as one batch: they all apply, or the first
+    // failure is reported and the document is left untouched.
+    pub fn apply(&self, doc: &mut Document, targets: &[ShapeId], overrides: &BTreeMap<String, f64>) -> Result<MacroReport, CadError> {
+        if targets.len() < self.target_count() {
+            return Err(CadError::InvalidInput(format!("macro needs {} targets, got {}", self.target_count(), targets.len())));
+        }
+        if let Some(name) = overrides.keys().find(|k| !self.parameters.contains_key(*k)) {
+            return Err(CadError::InvalidInput(format!("unknown macro parameter '{}'", name)));
+        }
+        let mut parameters = self.parameters.clone();
+        parameters.extend(overrides.iter().map(|(k, v)| (k.clone(), *v)));
+
+        let mut work = doc.clone();
+        let mut created: Vec<Vec<ShapeId>> = Vec::new();
+        let mut steps = Vec::new();
+        for (i, step) in self.steps.iter().enumerate() {
+            let ids = step.on.iter().map(|role| match *role {
+                Role::Target(t) => Ok(targets[t]),
+                Role::Created { step, index } => created.get(step).and_then(|c| c.get(index)).copied()
+                    .ok_or_else(|| CadError::InvalidInput(format!("step {} made no shape {} this time", step, index))),
+            }).collect::<Result<Vec<_>, _>>();
+            match ids.and_then(|ids| run(&mut work, &step.operation, &ids, &parameters)) {
+                Ok(made) => {
+                    steps.push(MacroStepReport { step: i, created: made.clone(), error: None });
+                    created.push(made);
+                }
+                Err(e) => {
+                    steps.push(MacroStepReport { step: i, created: vec![], error: Some(e.to_string()) });
+                    return Ok(MacroReport { steps, applied: false });
+                }
+            }
+        }
+        doc.replace_with(work);
+        Ok(MacroReport { steps, applied: true })
+    }
+}
+
+#[wasm_bindgen]
+impl MacroRecorder {
+    #[wasm_bindgen(constructor)]
+    pub fn new_js(targets: Vec<ShapeId>) -> MacroRecorder {
+        MacroRecorder::new(&targets)
+    }
+
+    #[wasm_bindgen(js_name = set_parameter)]
+    pub fn set_parameter_js(&mut self, name: &str, value: f64) {
+        self.set_parameter(name, value)
+    }
+
+    // `operation` as it serializes, e.g. { Rotate: { angle: "turn" } }
+    #[wasm_bindgen(js_name = perform)]
+    pub fn perform_js(&mut self, doc: &mut Document, operation: &JsValue, on: Vec<ShapeId>) -> Result<Vec<ShapeId>, JsValue> {
+        let operation: Operation = crate::from_js(operation)?;
+        self.perform(doc, operation, &on).map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = finish)]
+    pub fn finish_js(&self) -> Result<JsValue, JsValue> {
+        crate::to_js(&self.finish())
+    }
+}
+
+// `overrides` maps parameter names to values, and may be null
+#[wasm_bindgen]
+pub fn apply_macro(doc: &mut Document, operation_macro: &JsValue, targets: Vec<ShapeId>, overrides: &JsValue) -> Result<JsValue, JsValue> {
+    let operation_macro: OperationMacro = crate::from_js(operation_macro)?;
+    let overrides: BTreeMap<String, f64> = crate::options_from(overrides)?;
+    let report = operation_macro.apply(doc, &targets, &overrides).map_err(js_error)?;
+    crate::to_js(&report)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::TAU;
+
+    use super::*;
+    use crate::Polygon;
+
+    fn record(doc: &mut Document, target: ShapeId) -> OperationMacro {
+        let mut recorder = MacroRecorder::new(&[target]);
+        recorder.set_parameter("gap", 2.0);
+        recorder.perform(doc, Operation::Offset { distance: Arg::Parameter("gap".to_string()), quality: 16 }, &[target]).unwrap();
+        recorder.perform(doc, Operation::Rotate { angle: Arg::Value(0.3) }, &[target]).unwrap();
+        let copies = recorder.perform(doc, Operation::PolarArray { center: Point::new(30.0, 0.0), count: 4, sweep: Arg::Value(TAU) }, &[target]).unwrap();
+        assert_eq!(copies.len(), 3);
+        // Roles, not ids: a later step on a copy names it by the step that made it
+        recorder.perform(doc, Operation::SetLayer { layer: Some("array".to_string()) }, &copies[1..2]).unwrap();
+        assert!(recorder.perform(doc, Operation::Rotate { angle: Arg::Value(1.0) }, &[999]).is_err());
+        recorder.finish()
+    }
+
+    fn world(doc: &Document) -> Vec<Vec<Point>> {
+        doc.shapes().map(|s| s.world_geometry().vertices).collect()
+    }
+
+    #[test]
+    fn test_macro_replays_like_the_manual_steps() {
+        let mut source = Document::new();
+        let square = source.add_shape(Polygon::new(vec![Point::new(0.0, 0.0), Point::new(4.0, 0.0), Point::new(4.0, 4.0), Point::new(0.0, 4.0)]));
+        let recorded = record(&mut source, square);
+        assert_eq!(recorded.steps.len(), 4);
+        assert_eq!(recorded.steps[3].on, vec![Role::Created { step: 2, index: 1 }]);
+        assert_eq!(recorded.target_count(), 1);
+
+        let triangle = Polygon::new(vec![Point::new(10.0, 0.0), Point::new(16.0, 0.0), Point::new(12.0, 5.0)]);
+        let mut replayed = Document::new();
+        let t = replayed.add_shape(triangle.clone());
+        let report = recorded.apply(&mut replayed, &[t], &BTreeMap::from([("gap".to_string(), 1.5)])).unwrap();
+        assert!(report.applied);
+        assert_eq!(report.steps.len(), 4);
+        assert_eq!(report.steps[2].created.len(), 3);
+
+        let mut manual = Document::new();
+        let m = manual.add_shape(triangle.clone());
+        manual.offset_shape(m, 1.5, 16).unwrap();
+        manual.rotate_shape(m, 0.3).unwrap();
+        let copies = manual.polar_array(m, Point::new(30.0, 0.0), 4, TAU).unwrap();
+        manual.set_layer(copies[1], Some("array".to_string())).unwrap();
+
+        assert_eq!(world(&replayed), world(&manual));
+        let layers = |d: &Document| d.shapes().map(|s| s.layer.clone()).collect::<Vec<_>>();
+        assert_eq!(layers(&replayed), layers(&manual));
+
+        // One undo takes back the whole macro
+        assert!(replayed.undo());
+        assert_eq!(world(&replayed), vec![triangle.vertices.clone()]);
+        assert!(!replayed.undo());
+        assert!(replayed.redo());
+        assert_eq!(world(&replayed), world(&manual));
+    }
+
+    #[test]
+    fn test_failed_step_leaves_document_untouched() {
+        let mut doc = Document::new();
+        let square = doc.add_shape(Polygon::new(vec![Point::new(0.0, 0.0), Point::new(1.0, 0.0), Point::new(1.0, 1.0), Point::new(0.0, 1.0)]));
+        let mut recorder = MacroRecorder::new(&[square]);
+        recorder.set_parameter("inset", -0.1);
+        recorder.perform(&mut doc, Operation::Translate { dx: Arg::Value(5.0), dy: Arg::Value(0.0) }, &[square]).unwrap();
+        recorder.perform(&mut doc, Operation::Offset { distance: Arg::Parameter("inset".to_string()), quality: 8 }, &[square]).unwrap();
+        let recorded = recorder.finish();
+
+        let mut target = Document::new();
+        let id = target.add_shape(Polygon::new(vec![Point::new(0.0, 0.0), Point::new(2.0, 0.0), Point::new(2.0, 2.0), Point::new(0.0, 2.0)]));
+        let before = target.content_hash_all();
+        // An inset of the whole width leaves nothing, so the offset step fails
+        let report = recorded.apply(&mut target, &[id], &BTreeMap::from([("inset".to_string(), -5.0)])).unwrap();
+        assert!(!report.applied);
+        assert_eq!(report.steps.len(), 2);
+        assert!(report.steps[0].error.is_none() && report.steps[1].error.as_ref().unwrap().contains("offset"));
+        assert_eq!(target.content_hash_all(), before);
+
+        assert!(recorded.apply(&mut target, &[], &BTreeMap::new()).is_err());
+        assert!(recorded.apply(&mut target, &[id], &BTreeMap::from([("depth".to_string(), 1.0)])).is_err());
+        assert!(recorded.apply(&mut target, &[id], &BTreeMap::new()).unwrap().applied);
+        assert!((target.shape(id).unwrap().world_geometry().area() - 1.8 * 1.8).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,194 @@
+use std::f64::consts::TAU;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::{Point, Polygon, Polyline, EPSILON};
+
+const CIRCLE_SEGMENTS: usize = 12;
+
+#[wasm_bindgen]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MarkerStyle {
+    Arrow,
+    Circle,
+    Square,
+}
+
+// Marker of overall length `size` centred on `at`, with its local +x axis
+// along the unit tangent `t`. Arrows point along the tangent; all styles
+// wind counter-clockwise.
+fn marker(at: Point, t: (f64, f64), size: f64, style: MarkerStyle) -> Polygon {
+    let h = size / 2.0;
+    let local: Vec<(f64, f64)> = match style {
+        MarkerStyle::Arrow => vec![(h, 0.0), (-h, 0.6 * h), (-h, -0.6 * h)],
+        MarkerStyle::Square => vec![(-h, -h), (h, -h), (h, h), (-h, h)],
+        MarkerStyle::Circle => (0..CIRCLE_SEGMENTS).map(|i| {
+            let a = TAU * i as f64 / CIRCLE_SEGMENTS as f64;
+            (h * a.cos(), h * a.sin())
+        }).collect(),
+    };
+    let vertices = local.into_iter().map(|(u, v)| {
+        Point::new(at.x + t.0 * u - t.1 * v, at.y + t.1 * u + t.0 * v)
+    }).collect();
+    Polygon::new(vertices)
+}
+
+impl Polyline {
+    // (start, end, length) of each segment, including the closing one
+    pub(crate) fn segments(&self) -> Vec<(Point, Point, f64)> {
+        let n = self.points.len();
+        let count = if self.closed && n > 2 { n } else { n.saturating_sub(1) };
+        (0..count).map(|i| {
+            let (a, b) = (self.points[i], self.points[(i + 1) % n]);
+            (a, b, (b.x - a.x).hypot(b.y - a.y))
+        }).collect()
+    }
+
+    // One marker per `spacing` of arc length, centred along the path so the
+    // leftover is split between both ends. Positions on segments shorter than
+    // `size` are skipped, since the tangent there means little.
+    pub fn direction_markers(&self, spacing: f64, size: f64, style: MarkerStyle) -> Vec<Polygon> {
+        let segments = self.segments();
+        let total: f64 = segments.iter().map(|s| s.2).sum();
+        if spacing.is_nan() || spacing <= EPSILON || size.is_nan() || size <= 0.0 || total < EPSILON {
+            return vec![];
+        }
+
+        let count = (total / spacing + EPSILON).floor() as usize;
+        let mut s = (total - count as f64 * spacing) / 2.0 + spacing / 2.0;
+        let mut markers = Vec::new();
+        let mut walked = 0.0;
+        let mut segments = segments.into_iter().peekable();
+        for _ in 0..count {
+            while let Some(&(_, _, len)) = segments.peek() {
+                if walked + len >= s {
+                    break;
+                }
+                walked += len;
+                segments.next();
+            }
+            let Some(&(a, b, len)) = segments.peek() else {
+                break;
+            };
+            if len >= size && len > EPSILON {
+                let t = ((b.x - a.x) / len, (b.y - a.y) / len);
+                let f = (s - walked) / len;
+                let at = Point::new(a.x + (b.x - a.x) * f, a.y + (b.y - a.y) * f);
+                markers.push(marker(at, t, size, style));
+            }
+            s += spacing;
+        }
+        markers
+    }
+
+    // Marker at the first point, aligned with the first non-degenerate segment
+    pub fn start_marker(&self, size: f64, style: MarkerStyle) -> Option<Polygon> {
+        let (a, b, len) = self.segments().into_iter().find(|s| s.2 > EPSILON)?;
+        Some(marker(self.points[0], ((b.x - a.x) / len, (b.y - a.y) / len), size, style))
+    }
+
+    // Marker at the end of the path (the first point again when closed),
+    // aligned with the last non-degenerate segment
+    pub fn end_marker(&self, size: f64, style: MarkerStyle) -> Option<Polygon> {
+        let (a, b, len) = self.segments().into_iter().rev().find(|s| s.2 > EPSILON)?;
+        let end = if self.closed { self.points[0] } else { *self.points.last()? };
+        Some(marker(end, ((b.x - a.x) / len, (b.y - a.y) / len), size, style))
+    }
+}
+
+impl Polygon {
+    // Markers around the perimeter in vertex order, showing the winding
+    pub fn direction_markers(&self, spacing: f64, size: f64, style: MarkerStyle) -> Vec<Polygon> {
+        Polyline::new(self.vertices.clone(), true).direction_markers(spacing, size, style)
+    }
+}
+
+fn polyline_from(polyline: &JsValue) -> Result<Polyline, JsValue> {
+    crate::from_js(polyline)
+}
+
+#[wasm_bindgen]
+pub fn polyline_direction_markers(polyline: &JsValue, spacing: f64, size: f64, style: MarkerStyle) -> Result<JsValue, JsValue> {
+    crate::to_js(&polyline_from(polyline)?.direction_markers(spacing, size, style))
+}
+
+#[wasm_bindgen]
+pub fn polyline_start_marker(polyline: &JsValue, size: f64, style: MarkerStyle) -> Result<JsValue, JsValue> {
+    crate::to_js(&polyline_from(polyline)?.start_marker(size, style))
+}
+
+#[wasm_bindgen]
+pub fn polyline_end_marker(polyline: &JsValue, size: f64, style: MarkerStyle) -> Result<JsValue, JsValue> {
+    crate::to_js(&polyline_from(polyline)?.end_marker(size, style))
+}
+
+#[wasm_bindgen]
+pub fn polygon_direction_markers(polygon: &JsValue, spacing: f64, size: f64, style: MarkerStyle) -> Result<JsValue, JsValue> {
+    let polygon: Polygon = crate::polygon_from(polygon)?;
+    crate::to_js(&polygon.direction_markers(spacing, size, style))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::predicates::ring_signed_area;
+
+    // Unit direction of the marker's major principal axis, signed towards
+    // the vertex farthest from the centroid (the tip, for arrows)
+    fn principal_axis(marker: &Polygon) -> (f64, f64) {
+        let n = marker.vertices.len() as f64;
+        let cx = marker.vertices.iter().map(|p| p.x).sum::<f64>() / n;
+        let cy = marker.vertices.iter().map(|p| p.y).sum::<f64>() / n;
+        let (mut sxx, mut syy, mut sxy) = (0.0, 0.0, 0.0);
+        for p in &marker.vertices {
+            sxx += (p.x - cx).powi(2);
+            syy += (p.y - cy).powi(2);
+            sxy += (p.x - cx) * (p.y - cy);
+        }
+        let angle = 0.5 * (2.0 * sxy).atan2(sxx - syy);
+        let (ux, uy) = (angle.cos(), angle.sin());
+        let tip = marker.vertices.iter().max_by(|a, b| {
+            let da = (a.x - cx).hypot(a.y - cy);
+            let db = (b.x - cx).hypot(b.y - cy);
+            da.total_cmp(&db)
+        }).unwrap();
+        if (tip.x - cx) * ux + (tip.y - cy) * uy < 0.0 { (-ux, -uy) } else { (ux, uy) }
+    }
+
+    #[test]
+    fn test_straight_path_arrows_point_along_x() {
+        let path = Polyline::new(vec![Point::new(0.0, 0.0), Point::new(4.0, 0.0), Point::new(10.0, 0.0)], false);
+        let markers = path.direction_markers(2.5, 0.5, MarkerStyle::Arrow);
+        assert_eq!(markers.len(), 4);
+        for (i, m) in markers.iter().enumerate() {
+            let (ux, uy) = principal_axis(m);
+            assert!((ux - 1.0).abs() < 1e-9 && uy.abs() < 1e-9);
+            let cx = m.vertices.iter().map(|p| p.x).sum::<f64>() / 3.0;
+            assert!((cx - (1.25 + 2.5 * i as f64)).abs() < 0.2);
+            assert!(ring_signed_area(&m.vertices) > 0.0);
+        }
+
+        let end = path.end_marker(0.5, MarkerStyle::Arrow).unwrap();
+        assert!(principal_axis(&end).0 > 0.99);
+        assert_eq!(path.start_marker(0.5, MarkerStyle::Square).unwrap().vertices.len(), 4);
+    }
+
+    #[test]
+    fn test_short_segments_and_perimeter() {
+        // The middle position lands on a segment shorter than the marker
+        let path = Polyline::new(vec![Point::new(0.0, 0.0), Point::new(2.9, 0.0), Point::new(3.1, 0.0), Point::new(6.0, 0.0)], false);
+        assert_eq!(path.direction_markers(2.0, 0.5, MarkerStyle::Circle).len(), 2);
+        assert!(path.direction_markers(0.0, 0.5, MarkerStyle::Arrow).is_empty());
+
+        // Counter-clockwise square: the marker on the bottom edge points +x,
+        // the one on the top edge points -x
+        let square = Polygon::new(vec![Point::new(0.0, 0.0), Point::new(4.0, 0.0), Point::new(4.0, 4.0), Point::new(0.0, 4.0)]);
+        let markers = square.direction_markers(4.0, 0.5, MarkerStyle::Arrow);
+        assert_eq!(markers.len(), 4);
+        assert!(principal_axis(&markers[0]).0 > 0.99);
+        assert!(principal_axis(&markers[1]).1 > 0.99);
+        assert!(principal_axis(&markers[2]).0 < -0.99);
+        assert!(principal_axis(&markers[3]).1 < -0.99);
+    }
+}
@@ -0,0 +1,269 @@
+use wasm_bindgen::prelude::*;
+
+use crate::convention::user_angle;
+use crate::memory::{Live, ObjectKind};
+use crate::{format_point, CadError, Document, Point, Polygon, SnapResult, Snapper, Units};
+
+// Distance under which the cursor is treated as closing the traverse
+const CLOSE_TOLERANCE: f64 = 1e-9;
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, Debug)]
+pub struct Readout {
+    pub dx: f64,
+    pub dy: f64,
+    pub segment_length: f64,
+    pub cumulative_length: f64,
+    // Degrees counter-clockwise from +x
+    pub angle_from_horizontal: f64,
+    // Included angle in degrees at the previous point, once there are two segments
+    pub angle_from_previous: Option<f64>,
+    // Enclosed area once the chain closes on its first point
+    pub area: Option<f64>,
+    pub label: String,
+    // Where the cursor really was, which differs from the point measured to
+    // when it snapped
+    pub raw_x: f64,
+    pub raw_y: f64,
+    // Kind of snap the point took, e.g. "vertex"; None when unsnapped
+    pub snap: Option<String>,
+}
+
+#[wasm_bindgen]
+pub struct MeasureSession {
+    points: Vec<Point>,
+    units: Units,
+    precision: usize,
+    // Radius within which snapped points move onto a snap; 0 turns it off
+    snap_radius: f64,
+    live: Live<{ ObjectKind::MeasureSession as usize }>,
+}
+
+fn distance(a: &Point, b: &Point) -> f64 {
+    ((b.x - a.x).powi(2) + (b.y - a.y).powi(2)).sqrt()
+}
+
+impl MeasureSession {
+    pub fn new(units: Units, precision: usize) -> MeasureSession {
+        MeasureSession { points: Vec::new(), units, precision, snap_radius: 0.0, live: Live::with_bytes(std::mem::size_of::<MeasureSession>()) }
+    }
+
+    pub fn points(&self) -> &[Point] {
+        &self.points
+    }
+
+    pub fn set_snap_radius(&mut self, radius: f64) {
+        self.snap_radius = radius;
+    }
+
+    // The nearest snap to `raw` within the snap radius, which a snapped
+    // point moves onto
+    pub fn snap(&self, doc: &Document, snapper: &mut Snapper, raw: &Point) -> Option<SnapResult> {
+        snapper.snap(doc, raw, self.snap_radius)
+    }
+
+    // `add_point` at where `raw` snaps to, returning the readout for the
+    // segment it ends
+    pub fn add_point_snapped(&mut self, doc: &Document, snapper: &mut Snapper, raw: Point) -> Readout {
+        let (readout, cursor) = self.snapped_readout(doc, snapper, raw);
+        self.add_point(cursor.x, cursor.y);
+        readout
+    }
+
+    // `preview` at where `raw` snaps to
+    pub fn preview_snapped(&self, doc: &Document, snapper: &mut Snapper, raw: Point) -> Readout {
+        self.snapped_readout(doc, snapper, raw).0
+    }
+
+    fn snapped_readout(&self, doc: &Document, snapper: &mut Snapper, raw: Point) -> (Readout, Point) {
+        let snap = self.snap(doc, snapper, &raw);
+        let cursor = snap.map_or(raw, |s| s.point);
+        let mut readout = self.readout_to(&cursor);
+        (readout.raw_x, readout.raw_y) = (raw.x, raw.y);
+        if let Some(snap) = snap {
+            let kind = format!("{:?}", snap.kind).to_lowercase();
+            readout.label.push_str(&format!(" | snap {}", kind));
+            readout.snap = Some(kind);
+        }
+        (readout, cursor)
+    }
+
+    fn length(&self) -> f64 {
+        self.points.windows(2).map(|w| distance(&w[0], &w[1])).sum()
+    }
+
+    fn readout_to(&self, cursor: &Point) -> Readout {
+        let last = self.points.last().copied().unwrap_or(*cursor);
+        let (dx, dy) = (cursor.x - last.x, cursor.y - last.y);
+        let segment_length = (dx * dx + dy * dy).sqrt();
+        let cumulative_length = self.length() + segment_length;
+
+        let angle_from_previous = (self.points.len() >= 2 && segment_length > 0.0).then(|| {
+            let prev = self.points[self.points.len() - 2];
+            let (bx, by) = (prev.x - last.x, prev.y - last.y);
+            let cross = bx * dy - by * dx;
+            let dot = bx * dx + by * dy;
+            cross.atan2(dot).abs().to_degrees()
+        });
+
+        let closes = self.points.len() >= 3 && distance(cursor, &self.points[0]) <= CLOSE_TOLERANCE;
+        let area = closes.then(|| Polygon::new(self.points.clone()).area());
+
+        let angle_from_horizontal = user_angle(dy.atan2(dx).to_degrees());
+        let (p, u) = (self.precision, self.units.suffix());
+        let mut label = format!(
+            "{} | {:.p$}{u} (total {:.p$}{u}) | {:.2}°",
+            format_point(&Point::new(dx, dy), self.units, p),
+            segment_length, cumulative_length, angle_from_horizontal
+        );
+        if let Some(area) = area {
+            label.push_str(&format!(" | area {:.p$}{u}²", area));
+        }
+
+        Readout {
+            dx,
+            dy,
+            segment_length,
+            cumulative_length,
+            angle_from_horizontal,
+            angle_from_previous,
+            area,
+            label,
+            raw_x: cursor.x,
+            raw_y: cursor.y,
+            snap: None,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl MeasureSession {
+    #[wasm_bindgen(constructor)]
+    pub fn new_js(units: &str, precision: usize) -> Result<MeasureSession, JsValue> {
+        let units = Units::from_name(units).ok_or_else(|| crate::js_error(CadError::InvalidInput(format!("unknown units '{}'", units))))?;
+        Ok(MeasureSession::new(units, precision))
+    }
+
+    pub fn add_point(&mut self, x: f64, y: f64) {
+        self.points.push(Point::new(x, y));
+        self.live.add_bytes(std::mem::size_of::<Point>() as isize);
+    }
+
+    #[wasm_bindgen(js_name = set_snap_radius)]
+    pub fn set_snap_radius_js(&mut self, radius: f64) {
+        self.set_snap_radius(radius)
+    }
+
+    #[wasm_bindgen(js_name = add_point_snapped)]
+    pub fn add_point_snapped_js(&mut self, doc: &Document, snapper: &mut Snapper, x: f64, y: f64) -> Readout {
+        self.add_point_snapped(doc, snapper, Point::new(x, y))
+    }
+
+    #[wasm_bindgen(js_name = preview_snapped)]
+    pub fn preview_snapped_js(&self, doc: &Document, snapper: &mut Snapper, x: f64, y: f64) -> Readout {
+        self.preview_snapped(doc, snapper, Point::new(x, y))
+    }
+
+    pub fn point_count(&self) -> usize {
+        self.points.len()
+    }
+
+    // Live readout for a cursor position that hasn't been clicked yet
+    pub fn preview(&self, x: f64, y: f64) -> Readout {
+        self.readout_to(&Point::new(x, y))
+    }
+
+    pub fn undo_last_point(&mut self) -> bool {
+        let undone = self.points.pop().is_some();
+        if undone {
+            self.live.add_bytes(-(std::mem::size_of::<Point>() as isize));
+        }
+        undone
+    }
+
+    // Closes the traverse back to its first point; the readout carries the
+    // closing segment, the perimeter and the enclosed area
+    pub fn close(&self) -> Readout {
+        match self.points.first() {
+            Some(first) => {
+                let mut readout = self.readout_to(first);
+                if readout.area.is_none() && self.points.len() >= 3 {
+                    readout.area = Some(Polygon::new(self.points.clone()).area());
+                }
+                readout
+            }
+            None => self.readout_to(&Point::new(0.0, 0.0)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_traverse_around_3_4_5_triangle() {
+        let mut session = MeasureSession::new(Units::Millimeters, 3);
+        session.add_point(0.0, 0.0);
+
+        let first = session.preview(4.0, 0.0);
+        assert_eq!(first.segment_length, 4.0);
+        assert_eq!(first.angle_from_horizontal, 0.0);
+        assert!(first.angle_from_previous.is_none());
+        session.add_point(4.0, 0.0);
+
+        let second = session.preview(4.0, 3.0);
+        assert_eq!(second.segment_length, 3.0);
+        assert_eq!(second.cumulative_length, 7.0);
+        assert!((second.angle_from_horizontal - 90.0).abs() < 1e-12);
+        assert!((second.angle_from_previous.unwrap() - 90.0).abs() < 1e-12);
+        assert!(second.area.is_none());
+        session.add_point(4.0, 3.0);
+
+        let closing = session.close();
+        assert_eq!(closing.segment_length, 5.0);
+        assert_eq!(closing.cumulative_length, 12.0);
+        assert_eq!(closing.area, Some(6.0));
+        assert!(closing.label.contains("area 6.000mm²"));
+    }
+
+    #[test]
+    fn test_preview_on_first_point_reports_area_and_undo() {
+        let mut session = MeasureSession::new(Units::Inches, 2);
+        for (x, y) in [(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)] {
+            session.add_point(x, y);
+        }
+        assert_eq!(session.preview(0.0, 0.0).area, Some(4.0));
+        assert!(session.preview(0.1, 0.0).area.is_none());
+
+        assert!(session.undo_last_point());
+        assert_eq!(session.point_count(), 3);
+        assert_eq!(session.close().area, Some(2.0));
+    }
+
+    #[test]
+    fn test_points_near_a_vertex_snap_onto_it() {
+        let mut doc = Document::new();
+        doc.add_shape(Polygon::new(vec![Point::new(0.0, 0.0), Point::new(4.0, 0.0), Point::new(4.0, 3.0)]));
+        let mut snapper = Snapper::new();
+        let mut session = MeasureSession::new(Units::Millimeters, 3);
+
+        // Off until a radius is set
+        let raw = Point::new(0.05, -0.02);
+        assert!(session.preview_snapped(&doc, &mut snapper, raw).snap.is_none());
+        session.set_snap_radius(0.2);
+        session.add_point_snapped(&doc, &mut snapper, raw);
+        assert_eq!(session.points(), &[Point::new(0.0, 0.0)]);
+
+        let readout = session.add_point_snapped(&doc, &mut snapper, Point::new(3.9, 0.1));
+        assert_eq!(readout.snap.as_deref(), Some("vertex"));
+        assert_eq!((readout.raw_x, readout.raw_y), (3.9, 0.1));
+        assert_eq!(readout.segment_length, 4.0);
+        assert!(readout.label.ends_with("| snap vertex"));
+        assert_eq!(session.points()[1], Point::new(4.0, 0.0));
+
+        // Nothing within reach leaves the point where it was
+        let free = session.preview_snapped(&doc, &mut snapper, Point::new(10.0, 10.0));
+        assert!(free.snap.is_none() && free.dx == 6.0 && free.raw_x == 10.0);
+    }
+}
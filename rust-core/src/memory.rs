@@ -0,0 +1,255 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Weak;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+// Kinds of object whose memory is tracked. The JS-visible classes each
+// count once per handle, until JS frees it (or Rust drops it); `Index` counts
+// the spatial and search indexes documents build on demand.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ObjectKind {
+    Document,
+    DocumentView,
+    ResultCursor,
+    TessellationBuffers,
+    MeasureSession,
+    OffsetSession,
+    DrawSession,
+    Snapper,
+    CutterSession,
+    Index,
+}
+
+const KINDS: [ObjectKind; 10] = [
+    ObjectKind::Document,
+    ObjectKind::DocumentView,
+    ObjectKind::ResultCursor,
+    ObjectKind::TessellationBuffers,
+    ObjectKind::MeasureSession,
+    ObjectKind::OffsetSession,
+    ObjectKind::DrawSession,
+    ObjectKind::Snapper,
+    ObjectKind::CutterSession,
+    ObjectKind::Index,
+];
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KindUsage {
+    pub kind: ObjectKind,
+    pub live: usize,
+    // Approximate heap bytes: vertex and buffer storage plus fixed overheads,
+    // with data shared between snapshots counted once per holder
+    pub bytes: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MemoryReport {
+    pub kinds: Vec<KindUsage>,
+    pub total_bytes: usize,
+}
+
+// Held payload that `free_all_detached` may empty; returns the bytes freed
+pub(crate) trait Release {
+    fn release(&self) -> usize;
+}
+
+thread_local! {
+    static USAGE: RefCell<[(usize, usize); KINDS.len()]> = const { RefCell::new([(0, 0); KINDS.len()]) };
+    static DETACHED: RefCell<Vec<Weak<dyn Release>>> = const { RefCell::new(Vec::new()) };
+    static TICK: Cell<u64> = const { Cell::new(0) };
+}
+
+fn adjust(kind: usize, live: isize, bytes: isize) {
+    USAGE.with(|u| {
+        let entry = &mut u.borrow_mut()[kind];
+        entry.0 = entry.0.saturating_add_signed(live);
+        entry.1 = entry.1.saturating_add_signed(bytes);
+    });
+}
+
+// Registration of one tracked object, as a field of it: counted when made
+// (including by Clone, Default and deserializing), uncounted when dropped.
+// `K` is the `ObjectKind` as usize.
+pub(crate) struct Live<const K: usize> {
+    bytes: Cell<usize>,
+}
+
+impl<const K: usize> Live<K> {
+    pub(crate) fn with_bytes(bytes: usize) -> Live<K> {
+        adjust(K, 1, bytes as isize);
+        Live { bytes: Cell::new(bytes) }
+    }
+
+    pub(crate) fn bytes(&self) -> usize {
+        self.bytes.get()
+    }
+
+    pub(crate) fn set_bytes(&self, bytes: usize) {
+        adjust(K, 0, bytes as isize - self.bytes.get() as isize);
+        self.bytes.set(bytes);
+    }
+
+    pub(crate) fn add_bytes(&self, delta: isize) {
+        self.set_bytes(self.bytes.get().saturating_add_signed(delta));
+    }
+}
+
+impl<const K: usize> Default for Live<K> {
+    fn default() -> Live<K> {
+        Live::with_bytes(0)
+    }
+}
+
+impl<const K: usize> Clone for Live<K> {
+    fn clone(&self) -> Live<K> {
+        Live::with_bytes(self.bytes.get())
+    }
+}
+
+impl<const K: usize> Drop for Live<K> {
+    fn drop(&mut self) {
+        adjust(K, -1, -(self.bytes.get() as isize));
+    }
+}
+
+// Registration isn't part of an object's value
+impl<const K: usize> PartialEq for Live<K> {
+    fn eq(&self, _: &Live<K>) -> bool {
+        true
+    }
+}
+
+impl<const K: usize> std::fmt::Debug for Live<K> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Live({} bytes)", self.bytes.get())
+    }
+}
+
+pub(crate) fn register_detached(payload: Weak<dyn Release>) {
+    DETACHED.with(|d| {
+        let mut d = d.borrow_mut();
+        if d.len() >= 64 && d.len().is_power_of_two() {
+            d.retain(|w| w.strong_count() > 0);
+        }
+        d.push(payload);
+    });
+}
+
+// Increasing counter for least-recently-used bookkeeping
+pub(crate) fn tick() -> u64 {
+    TICK.with(|t| {
+        t.set(t.get() + 1);
+        t.get()
+    })
+}
+
+pub fn memory_report() -> MemoryReport {
+    let usage = USAGE.with(|u| *u.borrow());
+    let kinds: Vec<KindUsage> = KINDS.iter().zip(usage).map(|(&kind, (live, bytes))| KindUsage { kind, live, bytes }).collect();
+    MemoryReport { total_bytes: kinds.iter().map(|k| k.bytes).sum(), kinds }
+}
+
+// Empties every live result cursor, which nothing in a document refers to,
+// and returns the bytes freed. The JS handles stay valid, but any pieces not
+// yet taken are gone, so only call this when no cursor is still being read.
+pub fn free_all_detached() -> usize {
+    let payloads: Vec<Weak<dyn Release>> = DETACHED.with(|d| std::mem::take(&mut *d.borrow_mut()));
+    payloads.iter().filter_map(|w| w.upgrade()).map(|p| p.release()).sum()
+}
+
+#[wasm_bindgen(js_name = memory_report)]
+pub fn memory_report_js() -> Result<JsValue, JsValue> {
+    crate::to_js(&memory_report())
+}
+
+#[wasm_bindgen(js_name = free_all_detached)]
+pub fn free_all_detached_js() -> usize {
+    free_all_detached()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BoundingBox, Document, Point, Polygon, ResultCursor, SearchQuery};
+
+    fn square(x: f64, y: f64, size: f64) -> Polygon {
+        Polygon::new(vec![Point::new(x, y), Point::new(x + size, y), Point::new(x + size, y + size), Point::new(x, y + size)])
+    }
+
+    fn usage(kind: ObjectKind) -> KindUsage {
+        *memory_report().kinds.iter().find(|k| k.kind == kind).unwrap()
+    }
+
+    #[test]
+    fn test_counts_follow_object_lifetimes() {
+        let before = usage(ObjectKind::Document);
+        let mut docs: Vec<Document> = (0..5).map(|_| Document::new()).collect();
+        for doc in &mut docs {
+            doc.add_shape(square(0.0, 0.0, 1.0));
+        }
+        let copies = docs.clone();
+        let views: Vec<_> = docs.iter().map(|d| d.snapshot_view()).collect();
+        let during = usage(ObjectKind::Document);
+        assert_eq!(during.live, before.live + 10);
+        assert!(during.bytes > before.bytes);
+        assert_eq!(usage(ObjectKind::DocumentView).live, views.len());
+
+        // Shapes removed give their bytes back
+        let id = docs[0].shapes().next().unwrap().id;
+        let one = docs[0].memory_usage();
+        docs[0].remove_shape(id).unwrap();
+        assert!(docs[0].memory_usage() < one);
+
+        drop(copies);
+        drop(views);
+        drop(docs);
+        assert_eq!(usage(ObjectKind::Document), before);
+        assert_eq!(usage(ObjectKind::DocumentView).live, 0);
+    }
+
+    #[test]
+    fn test_free_detached_cursors() {
+        let pieces: Vec<Polygon> = (0..20).map(|i| square(i as f64, 0.0, 1.0)).collect();
+        let mut cursors: Vec<ResultCursor> = (0..3).map(|_| ResultCursor::new(pieces.clone())).collect();
+        assert_eq!(usage(ObjectKind::ResultCursor).live, 3);
+        let held = usage(ObjectKind::ResultCursor).bytes;
+        assert!(held > 0);
+        // Taking a piece hands its bytes over to the caller
+        cursors[0].take_piece(0).unwrap();
+        assert!(usage(ObjectKind::ResultCursor).bytes < held);
+
+        let freed = free_all_detached();
+        assert_eq!(usage(ObjectKind::ResultCursor).bytes, 0);
+        assert!(freed > 0 && freed < held);
+        assert!(cursors[1].take_piece(3).is_err());
+        assert_eq!(cursors[1].piece_count(), 20);
+        drop(cursors);
+        assert_eq!(usage(ObjectKind::ResultCursor).live, 0);
+    }
+
+    #[test]
+    fn test_shrink_caches_evicts_least_recently_used() {
+        let mut doc = Document::new();
+        for i in 0..500 {
+            let id = doc.add_shape(square(i as f64 * 2.0, 0.0, 1.0));
+            doc.set_label(id, Some(format!("part {}", i))).unwrap();
+        }
+        doc.cull(&BoundingBox::new(Point::new(0.0, 0.0), Point::new(10.0, 10.0)));
+        doc.search(&SearchQuery::LabelContains("part".to_string()));
+        let indexes = usage(ObjectKind::Index);
+        assert_eq!(indexes.live, 2);
+        let total = doc.cache_bytes();
+        assert_eq!(total, indexes.bytes);
+
+        // A budget just under the total drops only the older spatial index
+        assert!(doc.shrink_caches(total - 1) > 0);
+        assert!(doc.cache_bytes() < total);
+        assert_eq!(usage(ObjectKind::Index).live, 1);
+        doc.shrink_caches(0);
+        assert_eq!(doc.cache_bytes(), 0);
+        assert_eq!(usage(ObjectKind::Index), KindUsage { kind: ObjectKind::Index, live: 0, bytes: 0 });
+        // Caches come back on the next query
+        assert_eq!(doc.search(&SearchQuery::LabelContains("part 49".to_string())).len(), 11);
+    }
+}
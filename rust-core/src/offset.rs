@@ -0,0 +1,451 @@
+use std::f64::consts::TAU;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::arrangement::{drop_collinear, Arrangement};
+use crate::memory::{Live, ObjectKind};
+use crate::predicates::{point_in_ring, ring_signed_area};
+use crate::tolerance::edge_rect;
+use crate::{BoundingBox, CadError, GeometryError, MultiPolygon, Point, Polygon, Segment, EPSILON};
+
+// Longest a preview corner may reach, as a multiple of the distance; sharper
+// corners are clipped to this
+const MITER_LIMIT: f64 = 4.0;
+
+// How an offset fills the corners it opens up
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum JoinStyle {
+    // Edges extended to meet, unless that reaches further than `limit`
+    // times the distance from the corner, when the corner is bevelled
+    Miter { limit: f64 },
+    // Arc with `segments` segments per full turn
+    Round { segments: usize },
+    // Edge ends joined straight across
+    Bevel,
+}
+
+impl JoinStyle {
+    // "miter" with `param` as the limit, "round" with `param` segments per
+    // full turn, or "bevel", which ignores it
+    pub fn parse(name: &str, param: f64) -> Result<JoinStyle, CadError> {
+        match name {
+            "miter" => Ok(JoinStyle::Miter { limit: param }),
+            "round" => Ok(JoinStyle::Round { segments: param.max(0.0) as usize }),
+            "bevel" => Ok(JoinStyle::Bevel),
+            _ => Err(CadError::InvalidInput(format!("unknown join style '{}'", name))),
+        }
+    }
+}
+
+fn counter_clockwise(vertices: &[Point]) -> Vec<Point> {
+    let mut ring = vertices.to_vec();
+    if ring_signed_area(&ring) < 0.0 {
+        ring.reverse();
+    }
+    ring
+}
+
+fn edges(ring: &[Point]) -> Vec<Segment> {
+    let n = ring.len();
+    (0..n).map(|i| Segment::new(ring[i], ring[(i + 1) % n])).collect()
+}
+
+// Unit normal to the left of a segment, or None when it has no length
+fn left_normal(s: &Segment) -> Option<(f64, f64)> {
+    let len = s.length();
+    (len > EPSILON).then(|| (-(s.b.y - s.a.y) / len, (s.b.x - s.a.x) / len))
+}
+
+fn signed_distance_to(ring: &[Point], edges: &[Segment], p: &Point) -> f64 {
+    let d = edges.iter().map(|e| {
+        let q = e.closest_point(p);
+        (q.x - p.x).hypot(q.y - p.y)
+    }).fold(f64::INFINITY, f64::min);
+    if point_in_ring(ring, p) { -d } else { d }
+}
+
+// Arc about `center` from direction `from` turning by `sweep` (either way),
+// closed through the centre
+fn wedge(center: Point, from: (f64, f64), sweep: f64, radius: f64, quality: usize) -> Vec<Point> {
+    let steps = ((sweep.abs() / TAU * quality as f64).ceil() as usize).max(1);
+    let start = from.1.atan2(from.0);
+    let mut ring = vec![center];
+    ring.extend((0..=steps).map(|k| {
+        let a = start + sweep * k as f64 / steps as f64;
+        Point::new(center.x + radius * a.cos(), center.y + radius * a.sin())
+    }));
+    if ring_signed_area(&ring) < 0.0 {
+        ring.reverse();
+    }
+    ring
+}
+
+// Corner piece between the offset edge ends at `center + radius * u1` and
+// `center + radius * u2`
+fn corner(center: Point, u1: (f64, f64), u2: (f64, f64), sweep: f64, radius: f64, style: JoinStyle) -> Vec<Point> {
+    let at = |u: (f64, f64), r: f64| Point::new(center.x + u.0 * r, center.y + u.1 * r);
+    let mut ring = match style {
+        JoinStyle::Round { segments } => return wedge(center, u1, sweep, radius, segments.max(4)),
+        JoinStyle::Miter { limit } => {
+            // The edges meet 1 / cos(sweep / 2) out along the bisector
+            let cos = u1.0 * u2.0 + u1.1 * u2.1;
+            let reach = (2.0 / (1.0 + cos)).sqrt();
+            if reach <= limit {
+                let scale = reach / (2.0 * (1.0 + cos)).sqrt();
+                let tip = ((u1.0 + u2.0) * scale, (u1.1 + u2.1) * scale);
+                vec![center, at(u1, radius), at(tip, radius), at(u2, radius)]
+            } else {
+                vec![center, at(u1, radius), at(u2, radius)]
+            }
+        }
+        JoinStyle::Bevel => vec![center, at(u1, radius), at(u2, radius)],
+    };
+    if ring_signed_area(&ring) < 0.0 {
+        ring.reverse();
+    }
+    ring
+}
+
+impl Polygon {
+    // Distance from `p` to the boundary, negative inside
+    pub fn signed_distance(&self, p: &Point) -> f64 {
+        signed_distance_to(&self.vertices, &edges(&self.vertices), p)
+    }
+
+    // Everything within `distance` outside the polygon, or the polygon less
+    // everything within -distance inside it, with the corners the offset
+    // opens filled as `join` says. An inward offset can split into several
+    // pieces, each a simple ring; concave inputs are cut at the points
+    // where their offset edges cross. Holes an outward offset closes off
+    // are left out, since a MultiPolygon has no holes.
+    pub fn offset(&self, distance: f64, join: JoinStyle) -> MultiPolygon {
+        let ring = counter_clockwise(&self.vertices);
+        if ring.len() < 3 || !distance.is_finite() {
+            return vec![];
+        }
+        if distance.abs() < EPSILON {
+            return vec![Polygon::new(ring)];
+        }
+        let (h, outward) = (distance.abs(), distance > 0.0);
+        let edges = edges(&ring);
+        let n = ring.len();
+
+        // Band pieces on the offset side: a rectangle on every edge and a
+        // wedge at every corner that turns away from that side
+        let mut pieces: Vec<Vec<Point>> = edges.iter()
+            .filter_map(|e| if outward { edge_rect(e.a, e.b, 0.0, h) } else { edge_rect(e.a, e.b, h, 0.0) })
+            .collect();
+        let normals: Vec<Option<(f64, f64)>> = edges.iter().map(left_normal).collect();
+        for i in 0..n {
+            let (Some(n1), Some(n2)) = (normals[(i + n - 1) % n], normals[i]) else {
+                continue;
+            };
+            let turn = (n1.0 * n2.1 - n1.1 * n2.0).atan2(n1.0 * n2.0 + n1.1 * n2.1);
+            // Left turns are convex corners of a counter-clockwise ring
+            if (turn > EPSILON && outward) || (turn < -EPSILON && !outward) {
+                let (u1, u2) = if outward { ((-n1.0, -n1.1), (-n2.0, -n2.1)) } else { (n1, n2) };
+                pieces.push(corner(ring[i], u1, u2, turn, h, join));
+            }
+        }
+
+        let boxes: Vec<BoundingBox> = pieces.iter().map(|p| BoundingBox::from_points(p)).collect();
+        let in_band = |q: &Point| pieces.iter().zip(&boxes).any(|(p, b)| {
+            q.x >= b.min.x && q.x <= b.max.x && q.y >= b.min.y && q.y <= b.max.y && point_in_ring(p, q)
+        });
+        let mut segments = edges.clone();
+        for piece in &pieces {
+            segments.extend((0..piece.len()).map(|i| Segment::new(piece[i], piece[(i + 1) % piece.len()])));
+        }
+        let arrangement = Arrangement::build(&segments, EPSILON * h.max(1.0));
+        let rings = if outward {
+            arrangement.covered_boundary(|q| in_band(q) || point_in_ring(&ring, q))
+        } else {
+            arrangement.covered_boundary(|q| point_in_ring(&ring, q) && !in_band(q))
+        };
+        rings.into_iter()
+            .map(|r| drop_collinear(r, EPSILON))
+            .filter(|r| r.len() >= 3 && ring_signed_area(r) > 0.0)
+            .map(Polygon::new)
+            .collect()
+    }
+}
+
+// Sine of the angle under which neighbouring edges count as parallel, so
+// extending them to meet would throw the corner far away
+const PARALLEL_SINE: f64 = 1e-6;
+
+impl Polygon {
+    // The polygon with just the edges in `edge_indices` pushed `distance`
+    // outward (inward when negative), as an offset-edge tool does: each
+    // corner at a moved edge is the meeting of the two edge lines, so
+    // unmoved neighbours extend or trim to meet it and two moved edges meet
+    // in a mitre. Edge `i` runs from vertex `i` to the next; vertices keep
+    // their order. Neighbours that can't be re-met are an error naming both.
+    pub fn offset_edges(&self, edge_indices: &[usize], distance: f64) -> Result<Polygon, CadError> {
+        let v = &self.vertices;
+        let n = v.len();
+        if n < 3 {
+            return Err(GeometryError::TooFewVertices(n).into());
+        }
+        if let Some(&bad) = edge_indices.iter().find(|&&i| i >= n) {
+            return Err(CadError::InvalidInput(format!("edge {} is out of range for {} edges", bad, n)));
+        }
+        if !distance.is_finite() {
+            return Err(CadError::InvalidInput(format!("distance {} is not finite", distance)));
+        }
+        // Outward is to the right of a counter-clockwise ring
+        let outward = if ring_signed_area(v) >= 0.0 { -distance } else { distance };
+        let lines: Vec<(Segment, (f64, f64))> = edges(v).into_iter().enumerate().map(|(i, e)| {
+            let normal = left_normal(&e).ok_or_else(|| CadError::Degenerate(format!("edge {} has no length", i)))?;
+            let d = if edge_indices.contains(&i) { outward } else { 0.0 };
+            let shift = |p: Point| Point::new(p.x + normal.0 * d, p.y + normal.1 * d);
+            Ok((Segment::new(shift(e.a), shift(e.b)), normal))
+        }).collect::<Result<_, CadError>>()?;
+        let mut out = Vec::with_capacity(n);
+        for i in 0..n {
+            let prev = (i + n - 1) % n;
+            if !edge_indices.contains(&prev) && !edge_indices.contains(&i) {
+                out.push(v[i]);
+                continue;
+            }
+            let ((s, m), (u, k)) = (lines[prev], lines[i]);
+            let sine = m.0 * k.1 - m.1 * k.0;
+            if sine.abs() > PARALLEL_SINE {
+                // Where the line through `u` crosses the line through `s`
+                let (dx, dy) = (s.b.x - s.a.x, s.b.y - s.a.y);
+                let (ex, ey) = (u.b.x - u.a.x, u.b.y - u.a.y);
+                let t = ((u.a.x - s.a.x) * ey - (u.a.y - s.a.y) * ex) / (dx * ey - dy * ex);
+                out.push(Point::new(s.a.x + dx * t, s.a.y + dy * t));
+            } else if m.0 * k.0 + m.1 * k.1 > 0.0 && (s.b.x - u.a.x).hypot(s.b.y - u.a.y) <= EPSILON * distance.abs().max(1.0) {
+                // Straight on through a vertex both edges moved
+                out.push(u.a);
+            } else {
+                return Err(CadError::ParallelEdges(prev, i));
+            }
+        }
+        Ok(Polygon::new(out))
+    }
+}
+
+// Backend for dragging an offset. `begin` works out the edges and corner
+// directions once; each `preview` is then linear in the vertex count with
+// no allocation beyond its output, which keeps 5k-vertex outlines well
+// within a mousemove.
+#[wasm_bindgen]
+#[derive(Clone, Debug)]
+pub struct OffsetSession {
+    shape: Polygon,
+    ring: Vec<Point>,
+    edges: Vec<Segment>,
+    // Per vertex: the direction a preview moves it for a unit outward offset
+    miters: Vec<(f64, f64)>,
+    _live: Live<{ ObjectKind::OffsetSession as usize }>,
+}
+
+impl OffsetSession {
+    pub fn begin(shape: &Polygon) -> OffsetSession {
+        let ring = counter_clockwise(&shape.vertices);
+        let edges = edges(&ring);
+        let n = ring.len();
+        let normals: Vec<(f64, f64)> = edges.iter().map(|e| left_normal(e).unwrap_or((0.0, 0.0))).collect();
+        let miters = (0..n).map(|i| {
+            // Outward is the right-hand normal of a counter-clockwise ring
+            let (a, b) = (normals[(i + n - 1) % n], normals[i]);
+            let (x, y) = (-(a.0 + b.0), -(a.1 + b.1));
+            let len2 = x * x + y * y;
+            if len2 < EPSILON {
+                return (0.0, 0.0);
+            }
+            // Length 1 / cos(half the turn), clipped at the miter limit
+            let scale = (2.0 / len2).min(MITER_LIMIT / len2.sqrt());
+            (x * scale, y * scale)
+        }).collect();
+        let bytes = (shape.vertices.len() + ring.len()) * std::mem::size_of::<Point>() + edges.len() * std::mem::size_of::<Segment>() + n * std::mem::size_of::<(f64, f64)>();
+        OffsetSession { shape: shape.clone(), ring, edges, miters, _live: Live::with_bytes(bytes) }
+    }
+
+    // Offset distance the cursor implies (its signed distance from the
+    // boundary, negative inside) and a mitred preview at that distance.
+    // The preview is empty once an inward offset turns inside out.
+    pub fn preview(&self, cursor: &Point) -> (f64, MultiPolygon) {
+        let distance = signed_distance_to(&self.ring, &self.edges, cursor);
+        if self.ring.len() < 3 {
+            return (distance, vec![]);
+        }
+        let moved: Vec<Point> = self.ring.iter().zip(&self.miters)
+            .map(|(p, m)| Point::new(p.x + m.0 * distance, p.y + m.1 * distance))
+            .collect();
+        let preview = if ring_signed_area(&moved) > 0.0 { vec![Polygon::new(moved)] } else { vec![] };
+        (distance, preview)
+    }
+
+    pub fn commit(&self, distance: f64, quality: usize) -> MultiPolygon {
+        self.shape.offset(distance, JoinStyle::Round { segments: quality })
+    }
+}
+
+#[wasm_bindgen]
+impl OffsetSession {
+    #[wasm_bindgen(js_name = begin)]
+    pub fn begin_js(shape: &JsValue) -> Result<OffsetSession, JsValue> {
+        let shape: Polygon = crate::polygon_from(shape)?;
+        Ok(OffsetSession::begin(&shape))
+    }
+
+    // [distance, polygons]
+    #[wasm_bindgen(js_name = preview)]
+    pub fn preview_js(&self, x: f64, y: f64) -> Result<JsValue, JsValue> {
+        crate::to_js(&self.preview(&Point::new(x, y)))
+    }
+
+    #[wasm_bindgen(js_name = commit)]
+    pub fn commit_js(&self, distance: f64, quality: usize) -> Result<JsValue, JsValue> {
+        crate::to_js(&crate::strict::checked(self.commit(distance, quality), "offset").map_err(crate::js_error)?)
+    }
+}
+
+// `join_style` and `param` as for `JoinStyle::parse`, e.g. ("round", 64)
+#[wasm_bindgen]
+pub fn offset_polygon(vertices: &JsValue, distance: f64, join_style: &str, param: f64) -> Result<JsValue, JsValue> {
+    let polygon: Polygon = crate::polygon_from(vertices)?;
+    let join = JoinStyle::parse(join_style, param).map_err(crate::js_error)?;
+    crate::to_js(&crate::strict::checked(polygon.offset(distance, join), "offset").map_err(crate::js_error)?)
+}
+
+// `edge_indices` as `polygon_pick_edge` returns them; returns the vertices
+#[wasm_bindgen]
+pub fn polygon_offset_edges(vertices: &JsValue, edge_indices: Vec<usize>, distance: f64) -> Result<JsValue, JsValue> {
+    let polygon: Polygon = crate::polygon_from(vertices)?;
+    crate::to_js(&polygon.offset_edges(&edge_indices, distance).map_err(crate::js_error)?)
+}
+
+#[wasm_bindgen]
+pub fn polygon_signed_distance(vertices: &JsValue, x: f64, y: f64) -> Result<f64, JsValue> {
+    let polygon: Polygon = crate::polygon_from(vertices)?;
+    Ok(polygon.signed_distance(&Point::new(x, y)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+
+    fn l_shape() -> Polygon {
+        Polygon::new(vec![
+            Point::new(0.0, 0.0), Point::new(4.0, 0.0), Point::new(4.0, 1.0),
+            Point::new(1.0, 1.0), Point::new(1.0, 3.0), Point::new(0.0, 3.0),
+        ])
+    }
+
+    fn area(pieces: &MultiPolygon) -> f64 {
+        pieces.iter().map(|p| p.area()).sum()
+    }
+
+    #[test]
+    fn test_offset_areas() {
+        let square = Polygon::new(vec![Point::new(0.0, 0.0), Point::new(0.0, 1.0), Point::new(1.0, 1.0), Point::new(1.0, 0.0)]);
+        let grown = square.offset(0.5, JoinStyle::Round { segments: 256 });
+        assert_eq!(grown.len(), 1);
+        assert!((area(&grown) - (1.0 + 2.0 + PI * 0.25)).abs() < 1e-3);
+        let shrunk = square.offset(-0.25, JoinStyle::Round { segments: 64 });
+        assert!((area(&shrunk) - 0.25).abs() < 1e-9);
+        assert!(square.offset(-0.6, JoinStyle::Bevel).is_empty());
+
+        // The inside corner of the L rounds off when shrinking; the arms
+        // lose a quarter width each side
+        let thin = l_shape().offset(-0.25, JoinStyle::Round { segments: 256 });
+        assert_eq!(thin.len(), 1);
+        let expected = 3.5 * 0.5 + 2.0 * 0.5 + (1.0 - PI / 4.0) * 0.0625;
+        assert!((area(&thin) - expected).abs() < 1e-3, "{}", area(&thin));
+    }
+
+    #[test]
+    fn test_miter_and_bevel_joins() {
+        let square = Polygon::new(vec![Point::new(0.0, 0.0), Point::new(1.0, 0.0), Point::new(1.0, 1.0), Point::new(0.0, 1.0)]);
+        let mitred = square.offset(0.5, JoinStyle::Miter { limit: 2.0 });
+        assert_eq!(mitred.len(), 1);
+        assert!((area(&mitred) - 4.0).abs() < 1e-9);
+        assert_eq!(mitred[0].vertices.len(), 4);
+        // Square corners reach sqrt(2) times out, so a limit of 1.2 bevels them
+        let bevelled = square.offset(0.5, JoinStyle::Bevel);
+        assert!((area(&bevelled) - 3.5).abs() < 1e-9);
+        assert!((area(&square.offset(0.5, JoinStyle::Miter { limit: 1.2 })) - 3.5).abs() < 1e-9);
+
+        // Shrinking the L mitres its inside corner back to a square one
+        let thin = l_shape().offset(-0.25, JoinStyle::Miter { limit: 4.0 });
+        assert!((area(&thin) - (3.5 * 0.5 + 2.0 * 0.5)).abs() < 1e-9);
+        assert_eq!(thin[0].vertices.len(), 6);
+    }
+
+    #[test]
+    fn test_dumbbell_splits_cleanly() {
+        // Two 4 x 4 squares joined by a bar 1 wide
+        let dumbbell = Polygon::new(vec![
+            Point::new(0.0, 0.0), Point::new(4.0, 0.0), Point::new(4.0, 1.5), Point::new(6.0, 1.5),
+            Point::new(6.0, 0.0), Point::new(10.0, 0.0), Point::new(10.0, 4.0), Point::new(6.0, 4.0),
+            Point::new(6.0, 2.5), Point::new(4.0, 2.5), Point::new(4.0, 4.0), Point::new(0.0, 4.0),
+        ]);
+        assert_eq!(dumbbell.offset(-0.25, JoinStyle::Bevel).len(), 1);
+        for join in [JoinStyle::Round { segments: 64 }, JoinStyle::Miter { limit: 4.0 }, JoinStyle::Bevel] {
+            // The bar goes; mitred, each end is left exactly 2.5 square
+            let pieces = dumbbell.offset(-0.75, join);
+            assert_eq!(pieces.len(), 2);
+            assert!(pieces.iter().all(|p| p.is_simple() && p.area() > 6.25 - 1e-9 && p.area() < 7.0));
+            assert!((pieces[0].area() - pieces[1].area()).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_session_preview_and_commit() {
+        let shape = l_shape();
+        let session = OffsetSession::begin(&shape);
+        for (cursor, expected) in [(Point::new(2.0, 1.5), 0.5), (Point::new(0.4, 2.0), -0.4), (Point::new(5.0, 2.0), 2f64.sqrt())] {
+            let (distance, preview) = session.preview(&cursor);
+            assert!((distance - expected).abs() < 1e-12);
+            assert!((distance - shape.signed_distance(&cursor)).abs() < 1e-12);
+            assert_eq!(preview.len(), 1);
+        }
+
+        // Mitred preview of an outward offset: the square corners grow
+        // straight out
+        let (distance, preview) = session.preview(&Point::new(2.0, -0.2));
+        assert!((distance - 0.2).abs() < 1e-12);
+        let b = preview[0].bounding_box();
+        assert!((b.min.x + 0.2).abs() < 1e-12 && (b.max.x - 4.2).abs() < 1e-12);
+        assert!(session.preview(&Point::new(0.3, 0.5)).1[0].area() < shape.area());
+
+        let committed = session.commit(distance, 64);
+        let standalone = shape.offset(distance, JoinStyle::Round { segments: 64 });
+        assert_eq!(committed.len(), standalone.len());
+        assert!((area(&committed) - area(&standalone)).abs() < 1e-12);
+        // Round corners sit inside the preview's mitred ones
+        assert!(area(&committed) < area(&preview));
+    }
+
+    #[test]
+    fn test_offset_selected_edges() {
+        let square = Polygon::new(vec![Point::new(0.0, 0.0), Point::new(2.0, 0.0), Point::new(2.0, 2.0), Point::new(0.0, 2.0)]);
+        // Picking the top edge, then pushing it up: the sides stretch
+        let top = square.pick_edge(&Point::new(1.0, 2.05), 0.1).unwrap();
+        assert_eq!(top, 2);
+        let taller = square.offset_edges(&[top], 1.0).unwrap();
+        assert_eq!(taller.vertices, vec![Point::new(0.0, 0.0), Point::new(2.0, 0.0), Point::new(2.0, 3.0), Point::new(0.0, 3.0)]);
+        // Top and right together meet in a mitred corner, whichever way the
+        // ring runs
+        let both = square.offset_edges(&[1, 2], 1.0).unwrap();
+        assert_eq!(both.vertices, vec![Point::new(0.0, 0.0), Point::new(3.0, 0.0), Point::new(3.0, 3.0), Point::new(0.0, 3.0)]);
+        let mut clockwise = square.vertices.clone();
+        clockwise.reverse();
+        let reversed = Polygon::new(clockwise).offset_edges(&[0], 1.0).unwrap();
+        assert_eq!(reversed.vertices[0], Point::new(0.0, 3.0));
+        assert!((l_shape().offset_edges(&[3], -0.5).unwrap().area() - 5.0).abs() < 1e-12);
+
+        // A moved edge next to a collinear one has nothing to meet
+        let split_top = Polygon::new(vec![Point::new(0.0, 0.0), Point::new(2.0, 0.0), Point::new(2.0, 2.0), Point::new(1.0, 2.0), Point::new(0.0, 2.0)]);
+        assert_eq!(split_top.offset_edges(&[2], 1.0).err(), Some(CadError::ParallelEdges(2, 3)));
+        assert_eq!(split_top.offset_edges(&[2, 3], 1.0).unwrap().vertices[3], Point::new(1.0, 3.0));
+        assert!(square.offset_edges(&[4], 1.0).is_err());
+    }
+}
@@ -0,0 +1,408 @@
+use std::f64::consts::TAU;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::curve::arc_parameter;
+use crate::{intersect_curves, Arc, BooleanOp, Curve, Point, Polygon, Segment, Vector, EPSILON};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum OutlineEdge {
+    Line(Segment),
+    Arc(Arc),
+}
+
+// Closed boundary of line and arc edges, each starting where the last ends.
+// Counter-clockwise outlines bound material and clockwise ones holes.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Outline {
+    pub edges: Vec<OutlineEdge>,
+}
+
+fn distance(p: &Point, q: &Point) -> f64 {
+    (p.x - q.x).hypot(p.y - q.y)
+}
+
+fn full_circle(arc: &Arc) -> bool {
+    arc.sweep.abs() >= TAU - EPSILON
+}
+
+impl OutlineEdge {
+    fn curve(&self) -> Curve {
+        match self {
+            OutlineEdge::Line(s) => Curve::Segment(*s),
+            OutlineEdge::Arc(arc) => Curve::Arc(*arc),
+        }
+    }
+
+    pub fn start(&self) -> Point {
+        match self {
+            OutlineEdge::Line(s) => s.a,
+            OutlineEdge::Arc(arc) => arc.start_point(),
+        }
+    }
+
+    pub fn end(&self) -> Point {
+        match self {
+            OutlineEdge::Line(s) => s.b,
+            OutlineEdge::Arc(arc) => arc.end_point(),
+        }
+    }
+
+    pub fn length(&self) -> f64 {
+        match self {
+            OutlineEdge::Line(s) => s.length(),
+            OutlineEdge::Arc(arc) => arc.length(),
+        }
+    }
+
+    pub fn reversed(&self) -> OutlineEdge {
+        match self {
+            OutlineEdge::Line(s) => OutlineEdge::Line(Segment::new(s.b, s.a)),
+            OutlineEdge::Arc(arc) => OutlineEdge::Arc(Arc { start_angle: arc.start_angle + arc.sweep, sweep: -arc.sweep, ..*arc }),
+        }
+    }
+
+    // The stretch between parameters `t0` and `t1`, as in `Curve::point_at`
+    fn portion(&self, t0: f64, t1: f64) -> OutlineEdge {
+        match self {
+            OutlineEdge::Line(s) => {
+                let curve = Curve::Segment(*s);
+                OutlineEdge::Line(Segment::new(curve.point_at(t0), curve.point_at(t1)))
+            }
+            OutlineEdge::Arc(arc) => OutlineEdge::Arc(Arc { start_angle: arc.start_angle + arc.sweep * t0, sweep: arc.sweep * (t1 - t0), ..*arc }),
+        }
+    }
+
+    // The edge's part of ½∮(x dy − y dx), exact for arcs too
+    fn area_term(&self) -> f64 {
+        match self {
+            OutlineEdge::Line(s) => (s.a.x * s.b.y - s.b.x * s.a.y) / 2.0,
+            OutlineEdge::Arc(arc) => {
+                let (s, e) = (arc.start_angle, arc.start_angle + arc.sweep);
+                let (c, r) = (arc.center, arc.radius);
+                (r * (c.x * (e.sin() - s.sin()) - c.y * (e.cos() - s.cos())) + r * r * arc.sweep) / 2.0
+            }
+        }
+    }
+
+    fn distance_to(&self, p: &Point) -> f64 {
+        match self {
+            OutlineEdge::Line(s) => s.distance_to_point(p),
+            OutlineEdge::Arc(arc) => match arc_parameter(arc, p, 0.0) {
+                Some(_) => (distance(p, &arc.center) - arc.radius).abs(),
+                None => distance(p, &arc.start_point()).min(distance(p, &arc.end_point())),
+            },
+        }
+    }
+
+    // Unit direction of travel at the point of the edge nearest `p`
+    fn direction_near(&self, p: &Point) -> Vector {
+        let v = match self {
+            OutlineEdge::Line(s) => Vector::new(s.b.x - s.a.x, s.b.y - s.a.y),
+            OutlineEdge::Arc(arc) => {
+                let theta = (p.y - arc.center.y).atan2(p.x - arc.center.x);
+                Vector::new(-theta.sin() * arc.sweep.signum(), theta.cos() * arc.sweep.signum())
+            }
+        };
+        let m = v.magnitude();
+        Vector::new(v.x / m, v.y / m)
+    }
+
+    // Angle the edge turns through as seen from `p`, which is off it. An
+    // arc turns through its chord's angle, plus a full turn when `p` lies
+    // between the chord and the arc.
+    fn winding_angle(&self, p: &Point) -> f64 {
+        let subtended = |a: &Point, b: &Point| {
+            let (ux, uy, vx, vy) = (a.x - p.x, a.y - p.y, b.x - p.x, b.y - p.y);
+            (ux * vy - uy * vx).atan2(ux * vx + uy * vy)
+        };
+        match self {
+            OutlineEdge::Line(s) => subtended(&s.a, &s.b),
+            OutlineEdge::Arc(arc) if full_circle(arc) => {
+                if distance(p, &arc.center) < arc.radius { TAU * arc.sweep.signum() } else { 0.0 }
+            }
+            OutlineEdge::Arc(arc) => {
+                let (a, b) = (arc.start_point(), arc.end_point());
+                let mid = arc.point_at(arc.start_angle + arc.sweep / 2.0);
+                let side = |q: &Point| (b.x - a.x) * (q.y - a.y) - (b.y - a.y) * (q.x - a.x);
+                let between = distance(p, &arc.center) < arc.radius && side(p) * side(&mid) > 0.0;
+                subtended(&a, &b) + if between { TAU * arc.sweep.signum() } else { 0.0 }
+            }
+        }
+    }
+}
+
+impl Outline {
+    pub fn new(edges: Vec<OutlineEdge>) -> Outline {
+        Outline { edges }
+    }
+
+    pub fn circle(center: Point, radius: f64) -> Outline {
+        Outline::new(vec![OutlineEdge::Arc(Arc { center, radius, start_angle: 0.0, sweep: TAU })])
+    }
+
+    pub fn from_polygon(polygon: &Polygon) -> Outline {
+        let v = &polygon.vertices;
+        Outline::new((0..v.len()).map(|i| OutlineEdge::Line(Segment::new(v[i], v[(i + 1) % v.len()]))).collect())
+    }
+
+    // Signed, positive when counter-clockwise
+    pub fn area(&self) -> f64 {
+        self.edges.iter().map(|e| e.area_term()).sum()
+    }
+
+    pub fn reversed(&self) -> Outline {
+        Outline::new(self.edges.iter().rev().map(|e| e.reversed()).collect())
+    }
+
+    // By winding number, so a point on the boundary may go either way
+    pub fn contains_point(&self, p: &Point) -> bool {
+        let turn: f64 = self.edges.iter().map(|e| e.winding_angle(p)).sum();
+        (turn / TAU).round() != 0.0
+    }
+
+    fn distance_to(&self, p: &Point) -> f64 {
+        self.edges.iter().map(|e| e.distance_to(p)).fold(f64::INFINITY, f64::min)
+    }
+
+    fn counter_clockwise(&self) -> Outline {
+        if self.area() < 0.0 { self.reversed() } else { self.clone() }
+    }
+
+    // Union, intersection or difference of two simple outlines, as the
+    // loops of the result: counter-clockwise outlines and clockwise holes.
+    // Edges are split where the outlines meet and kept or dropped by which
+    // side of the other outline they run, so arcs come out as arcs of the
+    // same circle and only the meeting points are new corners.
+    pub fn boolean(&self, other: &Outline, op: BooleanOp) -> Vec<Outline> {
+        let (a, b) = (self.counter_clockwise(), other.counter_clockwise());
+        let scale = a.edges.iter().chain(&b.edges).flat_map(|e| [e.start(), e.end()])
+            .fold(1.0f64, |m, p| m.max(p.x.abs()).max(p.y.abs()));
+        let tolerance = EPSILON * scale;
+        let mut kept: Vec<OutlineEdge> = Vec::new();
+        for (first, this, that) in [(true, &a, &b), (false, &b, &a)] {
+            for piece in split(this, that, tolerance) {
+                let mid = piece.portion(0.5, 0.5).start();
+                // Which sides of the piece are in each outline, left then
+                // right; a piece on both boundaries is only taken from the
+                // first, and has the second on the side it runs along
+                let (left, right) = if that.distance_to(&mid) <= tolerance * 1e3 {
+                    if !first {
+                        continue;
+                    }
+                    let along = that.edges.iter().min_by(|p, q| p.distance_to(&mid).total_cmp(&q.distance_to(&mid))).unwrap();
+                    let (u, v) = (piece.direction_near(&mid), along.direction_near(&mid));
+                    if u.x * v.x + u.y * v.y > 0.0 { ((true, true), (false, false)) } else { ((true, false), (false, true)) }
+                } else {
+                    let inside = that.contains_point(&mid);
+                    if first { ((true, inside), (false, inside)) } else { ((inside, true), (inside, false)) }
+                };
+                let (l, r) = (op.keeps(left.0, left.1), op.keeps(right.0, right.1));
+                if l != r {
+                    kept.push(if l { piece } else { piece.reversed() });
+                }
+            }
+        }
+        stitch(kept).into_iter().map(merge).filter(|o| o.area().abs() > tolerance * tolerance).collect()
+    }
+
+    pub fn union(&self, other: &Outline) -> Vec<Outline> {
+        self.boolean(other, BooleanOp::Union)
+    }
+
+    pub fn intersection(&self, other: &Outline) -> Vec<Outline> {
+        self.boolean(other, BooleanOp::Intersection)
+    }
+
+    pub fn difference(&self, other: &Outline) -> Vec<Outline> {
+        self.boolean(other, BooleanOp::Difference)
+    }
+}
+
+// Edges of `this` cut wherever `that` meets them, cuts closer than
+// `tolerance` along the edge taken as one
+fn split(this: &Outline, that: &Outline, tolerance: f64) -> Vec<OutlineEdge> {
+    let mut pieces = Vec::new();
+    for edge in &this.edges {
+        let curve = edge.curve();
+        let mut cuts: Vec<f64> = that.edges.iter().flat_map(|other| intersect_curves(&curve, &other.curve(), tolerance)).map(|i| i.t_a).collect();
+        cuts.push(1.0);
+        cuts.sort_by(f64::total_cmp);
+        let min_step = tolerance / edge.length().max(tolerance);
+        let mut ts = vec![0.0];
+        for t in cuts {
+            if t - ts[ts.len() - 1] > min_step {
+                ts.push(t);
+            }
+        }
+        match ts.len() {
+            1 => ts.push(1.0),
+            n => ts[n - 1] = 1.0,
+        }
+        pieces.extend(ts.windows(2).map(|w| edge.portion(w[0], w[1])));
+    }
+    pieces
+}
+
+// Chains the pieces into loops, each following on from the nearest end
+fn stitch(mut pieces: Vec<OutlineEdge>) -> Vec<Vec<OutlineEdge>> {
+    let mut loops = Vec::new();
+    while !pieces.is_empty() {
+        let mut chain = vec![pieces.remove(0)];
+        let start = chain[0].start();
+        loop {
+            let end = chain.last().unwrap().end();
+            let next = pieces.iter().enumerate().map(|(i, p)| (i, distance(&p.start(), &end)))
+                .min_by(|p, q| p.1.total_cmp(&q.1));
+            match next {
+                Some((i, d)) if d < distance(&start, &end) => chain.push(pieces.remove(i)),
+                _ => break,
+            }
+        }
+        loops.push(chain);
+    }
+    loops
+}
+
+// Joins pieces of one line or circle that follow each other back into a
+// single edge, round the loop's end too
+fn merge(edges: Vec<OutlineEdge>) -> Outline {
+    let join = |p: &OutlineEdge, q: &OutlineEdge| -> Option<OutlineEdge> {
+        match (p, q) {
+            (OutlineEdge::Line(s), OutlineEdge::Line(u)) => {
+                let (dx, dy, ex, ey) = (s.b.x - s.a.x, s.b.y - s.a.y, u.b.x - u.a.x, u.b.y - u.a.y);
+                let cross = dx * ey - dy * ex;
+                (cross.abs() <= EPSILON * s.length() * u.length() && dx * ex + dy * ey > 0.0).then(|| OutlineEdge::Line(Segment::new(s.a, u.b)))
+            }
+            (OutlineEdge::Arc(x), OutlineEdge::Arc(y)) => {
+                let same = distance(&x.center, &y.center) <= EPSILON * x.radius.max(1.0) && (x.radius - y.radius).abs() <= EPSILON * x.radius.max(1.0);
+                let sweep = x.sweep + y.sweep;
+                (same && x.sweep.signum() == y.sweep.signum() && sweep.abs() <= TAU + EPSILON).then_some(OutlineEdge::Arc(Arc { sweep, ..*x }))
+            }
+            _ => None,
+        }
+    };
+    let mut merged: Vec<OutlineEdge> = Vec::new();
+    for edge in edges {
+        match merged.last().and_then(|last| join(last, &edge)) {
+            Some(joined) => *merged.last_mut().unwrap() = joined,
+            None => merged.push(edge),
+        }
+    }
+    while merged.len() > 1 {
+        match join(merged.last().unwrap(), &merged[0]) {
+            Some(joined) => {
+                merged.pop();
+                merged[0] = joined;
+            }
+            None => break,
+        }
+    }
+    if let [OutlineEdge::Arc(arc)] = merged.as_mut_slice() {
+        if (arc.sweep.abs() - TAU).abs() <= EPSILON {
+            arc.sweep = TAU * arc.sweep.signum();
+        }
+    }
+    Outline::new(merged)
+}
+
+// Outlines are { edges: [{ Line: { a, b } } or { Arc: { center, radius,
+// start_angle, sweep } }, ...] } and `op` as `boolean_op`; returns an array
+// of outlines
+#[wasm_bindgen]
+pub fn outline_boolean(a: &JsValue, b: &JsValue, op: &str) -> Result<JsValue, JsValue> {
+    let op = BooleanOp::parse(op).map_err(crate::js_error)?;
+    let (a, b) = (outline_from(a)?, outline_from(b)?);
+    let results: Vec<Outline> = a.boolean(&b, op).iter().map(Outline::user).collect();
+    crate::to_js(&results)
+}
+
+#[wasm_bindgen]
+pub fn outline_area(outline: &JsValue) -> Result<f64, JsValue> {
+    Ok(outline_from(outline)?.area())
+}
+
+fn outline_from(outline: &JsValue) -> Result<Outline, JsValue> {
+    Ok(crate::from_js::<Outline>(outline)?.user())
+}
+
+impl Outline {
+    // Arc angles converted between the caller's convention and the internal
+    // one, as `Arc::user` does
+    fn user(&self) -> Outline {
+        Outline::new(self.edges.iter().map(|e| match *e {
+            OutlineEdge::Arc(arc) => OutlineEdge::Arc(arc.user()),
+            line => line,
+        }).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+
+    fn rect(x0: f64, y0: f64, x1: f64, y1: f64) -> Outline {
+        let p = [Point::new(x0, y0), Point::new(x1, y0), Point::new(x1, y1), Point::new(x0, y1)];
+        Outline::from_polygon(&Polygon::new(p.to_vec()))
+    }
+
+    fn arcs(outline: &Outline) -> Vec<Arc> {
+        outline.edges.iter().filter_map(|e| match e {
+            OutlineEdge::Arc(arc) => Some(*arc),
+            OutlineEdge::Line(_) => None,
+        }).collect()
+    }
+
+    #[test]
+    fn test_circle_bite_out_of_rectangle_stays_an_arc() {
+        // The circle's seam is inside the rectangle, so the bite is two
+        // pieces of the circle that have to be joined again
+        let plate = rect(0.0, 0.0, 4.0, 2.0);
+        let hole = Outline::new(vec![OutlineEdge::Arc(Arc { center: Point::new(4.0, 1.0), radius: 1.0, start_angle: PI, sweep: TAU })]);
+        let result = plate.difference(&hole);
+        assert_eq!(result.len(), 1);
+        assert!((result[0].area() - (8.0 - PI / 2.0)).abs() < 1e-9);
+        let bite = arcs(&result[0]);
+        assert_eq!(bite.len(), 1);
+        assert_eq!((bite[0].center, bite[0].radius), (Point::new(4.0, 1.0), 1.0));
+        assert!((bite[0].sweep + PI).abs() < 1e-9);
+        // The circle spans the whole right edge, so the bite replaces it
+        assert_eq!(result[0].edges.len(), 4);
+
+        let union = plate.union(&hole);
+        assert_eq!(union.len(), 1);
+        assert!((union[0].area() - (8.0 + PI / 2.0)).abs() < 1e-9);
+        assert!((arcs(&union[0])[0].sweep - PI).abs() < 1e-9);
+        let overlap = plate.intersection(&hole);
+        assert_eq!(overlap.len(), 1);
+        assert!((overlap[0].area() - PI / 2.0).abs() < 1e-9);
+        assert_eq!(overlap[0].edges.len(), 2);
+    }
+
+    #[test]
+    fn test_lens_shared_edges_and_holes() {
+        // Two unit circles a radius apart overlap in a lens of two arcs
+        let (a, b) = (Outline::circle(Point::new(0.0, 0.0), 1.0), Outline::circle(Point::new(1.0, 0.0), 1.0));
+        let lens = a.intersection(&b);
+        assert_eq!(lens.len(), 1);
+        assert_eq!(arcs(&lens[0]).len(), 2);
+        assert!((lens[0].area() - (2.0 * PI / 3.0 - 3f64.sqrt() / 2.0)).abs() < 1e-9);
+
+        // Squares sharing an edge merge into one rectangle, the shared
+        // stretch dropped and the collinear sides joined
+        let joined = rect(0.0, 0.0, 1.0, 1.0).union(&rect(1.0, 0.0, 2.0, 1.0));
+        assert_eq!(joined.len(), 1);
+        assert_eq!(joined[0].edges.len(), 4);
+        assert!((joined[0].area() - 2.0).abs() < 1e-12);
+
+        // A circle wholly inside leaves a clockwise hole, still a circle
+        let ring = rect(-2.0, -2.0, 2.0, 2.0).difference(&a);
+        assert_eq!(ring.len(), 2);
+        assert!((ring.iter().map(|o| o.area()).sum::<f64>() - (16.0 - PI)).abs() < 1e-9);
+        assert!(ring.iter().any(|o| o.edges.len() == 1 && o.area() < 0.0));
+        assert!(rect(5.0, 5.0, 6.0, 6.0).intersection(&a).is_empty());
+    }
+}
@@ -0,0 +1,252 @@
+use wasm_bindgen::prelude::*;
+
+use crate::hull::convex_hull_points;
+use crate::{js_error, CadError, Document, Matrix, Point, Polygon, ShapeId, Vector, EPSILON};
+
+// Extra separation added to each push so touching shapes don't count as overlapping
+const SLOP: f64 = 1e-9;
+
+fn translated(points: &[Point], offset: &Vector) -> Vec<Point> {
+    points.iter().map(|p| Point::new(p.x + offset.x, p.y + offset.y)).collect()
+}
+
+fn project(points: &[Point], axis: &Vector) -> (f64, f64) {
+    points.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), p| {
+        let d = p.x * axis.x + p.y * axis.y;
+        (lo.min(d), hi.max(d))
+    })
+}
+
+fn edge_normals(points: &[Point]) -> Vec<Vector> {
+    let n = points.len();
+    (0..n)
+        .map(|i| {
+            let a = points[i];
+            let b = points[(i + 1) % n];
+            Vector::new(-(b.y - a.y), b.x - a.x)
+        })
+        .filter(|v| v.magnitude() > EPSILON)
+        .map(|v| v.normalize())
+        .collect()
+}
+
+// Minimum translation vector for two convex rings via the separating axis
+// theorem. Moving `b` by the result separates it from `a`; None when they
+// don't overlap.
+pub(crate) fn minimum_translation(a: &[Point], b: &[Point]) -> Option<Vector> {
+    if a.len() < 3 || b.len() < 3 {
+        return None;
+    }
+
+    let mut best: Option<(f64, Vector)> = None;
+    for axis in edge_normals(a).into_iter().chain(edge_normals(b)) {
+        let (a_lo, a_hi) = project(a, &axis);
+        let (b_lo, b_hi) = project(b, &axis);
+        let depth = a_hi.min(b_hi) - a_lo.max(b_lo);
+        if depth <= EPSILON {
+            return None;
+        }
+
+        // Push b towards whichever side of a it is already closer to
+        let forward = a_hi - b_lo;
+        let backward = b_hi - a_lo;
+        let (depth, axis) = if forward <= backward {
+            (forward, axis)
+        } else {
+            (backward, Vector::new(-axis.x, -axis.y))
+        };
+
+        if best.as_ref().is_none_or(|(d, _)| depth < *d) {
+            best = Some((depth, axis));
+        }
+    }
+
+    best.map(|(depth, axis)| Vector::new(axis.x * depth, axis.y * depth))
+}
+
+// Separates overlapping shapes by iteratively applying pairwise minimum
+// translation vectors, split evenly between each pair, until nothing overlaps
+// or `iterations` passes have run. Shapes are tested through their convex
+// hulls, which is exact for convex input and conservative (may leave a gap)
+// for concave input. With `bounds`, every shape is also pushed back inside
+// the half-planes of the bounds' convex hull after each pass. Returns the
+// final translation for each shape; pairs are visited in input order so the
+// result is deterministic.
+pub fn resolve_overlaps(shapes: &[Polygon], bounds: Option<&Polygon>, iterations: u32) -> Vec<Vector> {
+    let hulls: Vec<Vec<Point>> = shapes.iter().map(|s| convex_hull_points(&s.vertices)).collect();
+    let mut offsets = vec![Vector::new(0.0, 0.0); shapes.len()];
+    let walls = bounds.map(|b| {
+        let hull = convex_hull_points(&b.vertices);
+        let n = hull.len();
+        // Inward normal and offset for each bounds edge of the ccw hull
+        (0..n).filter_map(|i| {
+            let a = hull[i];
+            let b = hull[(i + 1) % n];
+            let normal = Vector::new(-(b.y - a.y), b.x - a.x);
+            (normal.magnitude() > EPSILON).then(|| {
+                let normal = normal.normalize();
+                (normal, normal.x * a.x + normal.y * a.y)
+            })
+        }).collect::<Vec<_>>()
+    });
+
+    for _ in 0..iterations {
+        let mut moved = false;
+
+        for i in 0..hulls.len() {
+            for j in (i + 1)..hulls.len() {
+                let a = translated(&hulls[i], &offsets[i]);
+                let b = translated(&hulls[j], &offsets[j]);
+                if let Some(mtv) = minimum_translation(&a, &b) {
+                    let len = mtv.magnitude();
+                    let scale = (len + SLOP) / len / 2.0;
+                    offsets[i].x -= mtv.x * scale;
+                    offsets[i].y -= mtv.y * scale;
+                    offsets[j].x += mtv.x * scale;
+                    offsets[j].y += mtv.y * scale;
+                    moved = true;
+                }
+            }
+        }
+
+        if let Some(walls) = &walls {
+            for (hull, offset) in hulls.iter().zip(offsets.iter_mut()) {
+                for (normal, d) in walls {
+                    let (lo, _) = project(&translated(hull, offset), normal);
+                    if lo < *d - EPSILON {
+                        offset.x += normal.x * (d - lo);
+                        offset.y += normal.y * (d - lo);
+                        moved = true;
+                    }
+                }
+            }
+        }
+
+        if !moved {
+            break;
+        }
+    }
+
+    offsets
+}
+
+impl Document {
+    // `resolve_overlaps` on the world outlines of `ids`, moving each shape
+    // by its translation as one undoable step. Returns the translations, in
+    // the order of `ids`.
+    pub fn resolve_overlaps(&mut self, ids: &[ShapeId], bounds: Option<&Polygon>, iterations: u32) -> Result<Vec<Vector>, CadError> {
+        let shapes: Vec<Polygon> = ids.iter().map(|&id| Ok(self.shape(id)?.world_geometry())).collect::<Result<_, CadError>>()?;
+        let offsets = resolve_overlaps(&shapes, bounds, iterations);
+        if offsets.iter().all(|v| v.x == 0.0 && v.y == 0.0) {
+            return Ok(offsets);
+        }
+        self.checkpoint();
+        for (&id, v) in ids.iter().zip(&offsets) {
+            self.shape_mut(id)?.transform_world(&Matrix::translate(v.x, v.y));
+        }
+        Ok(offsets)
+    }
+}
+
+#[wasm_bindgen]
+impl Document {
+    // `bounds` is an array of {x, y} or null; returns the translations as
+    // {x, y}, in the order of `ids`
+    #[wasm_bindgen(js_name = resolve_overlaps)]
+    pub fn resolve_overlaps_js(&mut self, ids: Vec<ShapeId>, bounds: &JsValue, iterations: u32) -> Result<JsValue, JsValue> {
+        let bounds: Option<Polygon> = crate::from_js(bounds)?;
+        crate::to_js(&self.resolve_overlaps(&ids, bounds.as_ref(), iterations).map_err(js_error)?)
+    }
+}
+
+#[wasm_bindgen(js_name = resolve_overlaps)]
+pub fn resolve_overlaps_js(shapes: &JsValue, bounds: &JsValue, iterations: u32) -> Result<JsValue, JsValue> {
+    let shapes: Vec<Polygon> = crate::from_js(shapes)?;
+    let bounds: Option<Polygon> = crate::from_js(bounds)?;
+    crate::to_js(&resolve_overlaps(&shapes, bounds.as_ref(), iterations))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(x: f64, y: f64, size: f64) -> Polygon {
+        Polygon::new(vec![
+            Point::new(x, y),
+            Point::new(x + size, y),
+            Point::new(x + size, y + size),
+            Point::new(x, y + size),
+        ])
+    }
+
+    fn moved(shape: &Polygon, offset: &Vector) -> Vec<Point> {
+        translated(&shape.vertices, offset)
+    }
+
+    #[test]
+    fn test_three_overlapping_squares_separate() {
+        let shapes = vec![square(0.0, 0.0, 1.0), square(0.5, 0.0, 1.0), square(0.25, 0.4, 1.0)];
+        let offsets = resolve_overlaps(&shapes, None, 100);
+
+        for i in 0..3 {
+            for j in (i + 1)..3 {
+                let a = moved(&shapes[i], &offsets[i]);
+                let b = moved(&shapes[j], &offsets[j]);
+                assert!(minimum_translation(&a, &b).is_none(), "shapes {} and {} still overlap", i, j);
+            }
+            assert!(offsets[i].magnitude() < 2f64.sqrt());
+        }
+    }
+
+    #[test]
+    fn test_resolve_overlaps_is_deterministic_and_noop_when_clear() {
+        let shapes = vec![square(0.0, 0.0, 1.0), square(0.5, 0.5, 1.0)];
+        let first = resolve_overlaps(&shapes, None, 50);
+        let second = resolve_overlaps(&shapes, None, 50);
+        for (a, b) in first.iter().zip(&second) {
+            assert_eq!((a.x, a.y), (b.x, b.y));
+        }
+
+        let clear = vec![square(0.0, 0.0, 1.0), square(2.0, 0.0, 1.0)];
+        for offset in resolve_overlaps(&clear, None, 50) {
+            assert_eq!((offset.x, offset.y), (0.0, 0.0));
+        }
+    }
+
+    #[test]
+    fn test_resolve_overlaps_stays_inside_bounds() {
+        let shapes = vec![square(0.0, 0.0, 1.0), square(0.2, 0.0, 1.0)];
+        let bounds = square(0.0, 0.0, 3.0);
+        let offsets = resolve_overlaps(&shapes, Some(&bounds), 100);
+
+        let a = moved(&shapes[0], &offsets[0]);
+        let b = moved(&shapes[1], &offsets[1]);
+        assert!(minimum_translation(&a, &b).is_none());
+        for p in a.iter().chain(&b) {
+            assert!(p.x >= -1e-9 && p.x <= 3.0 + 1e-9 && p.y >= -1e-9 && p.y <= 3.0 + 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_document_moves_shapes_apart_in_one_step() {
+        let mut doc = Document::new();
+        let a = doc.add_shape(square(0.0, 0.0, 1.0));
+        // Overlapping `a` through its transform
+        let b = doc.add_shape(square(0.0, 0.0, 1.0));
+        doc.set_transform(b, Matrix::translate(0.5, 0.0)).unwrap();
+        let clear = doc.add_shape(square(5.0, 0.0, 1.0));
+        let offsets = doc.resolve_overlaps(&[a, b, clear], None, 100).unwrap();
+        assert!(offsets[0].x < 0.0 && offsets[1].x > 0.0 && (offsets[2].x, offsets[2].y) == (0.0, 0.0));
+        let (wa, wb) = (doc.shape(a).unwrap().world_geometry(), doc.shape(b).unwrap().world_geometry());
+        assert!(minimum_translation(&wa.vertices, &wb.vertices).is_none());
+        assert_eq!(wb.vertices[0], Point::new(0.5 + offsets[1].x, offsets[1].y));
+
+        assert!(doc.undo());
+        assert_eq!(doc.shape(a).unwrap().transform, Matrix::identity());
+        assert_eq!(doc.shape(b).unwrap().transform, Matrix::translate(0.5, 0.0));
+        // Nothing overlapping, nothing to undo
+        doc.resolve_overlaps(&[a, clear], None, 100).unwrap();
+        assert!(!doc.undo());
+        assert!(doc.resolve_overlaps(&[a, 99], None, 100).is_err());
+    }
+}
@@ -0,0 +1,387 @@
+use std::collections::BTreeMap;
+use std::f64::consts::FRAC_PI_2;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{CadError, Point, Polygon, ShapeId, Units};
+
+// Segments per rounded corner of `create_rounded_rect`
+const CORNER_SEGMENTS: usize = 8;
+
+const FUNCTIONS: [&str; 7] = ["min", "max", "sin", "cos", "tan", "sqrt", "abs"];
+
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+    Number(f64),
+    Param(String),
+    Neg(Box<Expr>),
+    Binary(char, Box<Expr>, Box<Expr>),
+    // Function name, arguments, and the offset of the name for errors
+    Call(String, Vec<Expr>, usize),
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    units: Units,
+}
+
+impl Parser {
+    fn new(input: &str, units: Units) -> Parser {
+        Parser { chars: input.chars().collect(), pos: 0, units }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn error(&self, message: &str) -> CadError {
+        CadError::Parse { position: self.pos, message: message.to_string() }
+    }
+
+    fn finish(mut self, expr: Expr) -> Result<Expr, CadError> {
+        match self.peek() {
+            None => Ok(expr),
+            Some(_) => Err(self.error("unexpected trailing input")),
+        }
+    }
+
+    // sum := product (('+' | '-') product)*
+    fn sum(&mut self) -> Result<Expr, CadError> {
+        let mut left = self.product()?;
+        while let Some(op) = self.peek().filter(|c| *c == '+' || *c == '-') {
+            self.pos += 1;
+            left = Expr::Binary(op, Box::new(left), Box::new(self.product()?));
+        }
+        Ok(left)
+    }
+
+    // product := unary (('*' | '/') unary)*
+    fn product(&mut self) -> Result<Expr, CadError> {
+        let mut left = self.unary()?;
+        while let Some(op) = self.peek().filter(|c| *c == '*' || *c == '/') {
+            self.pos += 1;
+            left = Expr::Binary(op, Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, CadError> {
+        if self.eat('-') {
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        self.eat('+');
+        self.atom()
+    }
+
+    // atom := number [mm | in | "] | name | name '(' sum (',' sum)* ')' | '(' sum ')'
+    fn atom(&mut self) -> Result<Expr, CadError> {
+        match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let inner = self.sum()?;
+                if !self.eat(')') {
+                    return Err(self.error("expected ')'"));
+                }
+                Ok(inner)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => self.number(),
+            Some(c) if c.is_alphabetic() || c == '_' => {
+                let start = self.pos;
+                let name = self.name();
+                if !self.eat('(') {
+                    return Ok(Expr::Param(name));
+                }
+                let mut args = vec![self.sum()?];
+                while self.eat(',') {
+                    args.push(self.sum()?);
+                }
+                if !self.eat(')') {
+                    return Err(self.error("expected ',' or ')'"));
+                }
+                Ok(Expr::Call(name, args, start))
+            }
+            Some(_) => Err(self.error("expected a number, name or '('")),
+            None => Err(self.error("unexpected end of expression")),
+        }
+    }
+
+    fn name(&mut self) -> String {
+        let start = self.pos;
+        while self.chars.get(self.pos).is_some_and(|c| c.is_alphanumeric() || *c == '_') {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    fn number(&mut self) -> Result<Expr, CadError> {
+        let start = self.pos;
+        while self.chars.get(self.pos).is_some_and(|c| c.is_ascii_digit() || *c == '.') {
+            self.pos += 1;
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        let value: f64 = text.parse().map_err(|_| CadError::Parse { position: start, message: format!("invalid number '{}'", text) })?;
+        // A unit suffix must follow directly, so `2 in` isn't read as a unit
+        let rest: String = self.chars[self.pos..].iter().take(3).collect();
+        let suffix = if rest.starts_with('"') {
+            Some((Units::Inches, 1))
+        } else if rest.starts_with("mm") && !rest[2..].starts_with(|c: char| c.is_alphanumeric() || c == '_') {
+            Some((Units::Millimeters, 2))
+        } else if rest.starts_with("in") && !rest[2..].starts_with(|c: char| c.is_alphanumeric() || c == '_') {
+            Some((Units::Inches, 2))
+        } else {
+            None
+        };
+        Ok(Expr::Number(match suffix {
+            Some((units, len)) => {
+                self.pos += len;
+                units.convert(value, self.units)
+            }
+            None => value,
+        }))
+    }
+}
+
+fn parse(input: &str, units: Units) -> Result<Expr, CadError> {
+    let mut parser = Parser::new(input, units);
+    let expr = parser.sum()?;
+    parser.finish(expr)
+}
+
+fn dependencies(expr: &Expr, out: &mut Vec<String>) {
+    match expr {
+        Expr::Number(_) => {}
+        Expr::Param(name) => out.push(name.clone()),
+        Expr::Neg(e) => dependencies(e, out),
+        Expr::Binary(_, a, b) => {
+            dependencies(a, out);
+            dependencies(b, out);
+        }
+        Expr::Call(_, args, _) => args.iter().for_each(|a| dependencies(a, out)),
+    }
+}
+
+// Trig functions take degrees
+fn evaluate(expr: &Expr, values: &BTreeMap<String, f64>) -> Result<f64, CadError> {
+    let value = match expr {
+        Expr::Number(v) => *v,
+        Expr::Param(name) => *values.get(name).ok_or_else(|| CadError::InvalidInput(format!("unknown parameter '{}'", name)))?,
+        Expr::Neg(e) => -evaluate(e, values)?,
+        Expr::Binary(op, a, b) => {
+            let (a, b) = (evaluate(a, values)?, evaluate(b, values)?);
+            match op {
+                '+' => a + b,
+                '-' => a - b,
+                '*' => a * b,
+                _ if b == 0.0 => return Err(CadError::InvalidInput("division by zero".to_string())),
+                _ => a / b,
+            }
+        }
+        Expr::Call(name, args, position) => {
+            let args = args.iter().map(|a| evaluate(a, values)).collect::<Result<Vec<f64>, CadError>>()?;
+            let one = || match args[..] {
+                [x] => Ok(x),
+                _ => Err(CadError::Parse { position: *position, message: format!("{} takes one argument", name) }),
+            };
+            match name.as_str() {
+                "min" => args.iter().copied().fold(f64::INFINITY, f64::min),
+                "max" => args.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+                "sin" => one()?.to_radians().sin(),
+                "cos" => one()?.to_radians().cos(),
+                "tan" => one()?.to_radians().tan(),
+                "sqrt" => one()?.sqrt(),
+                "abs" => one()?.abs(),
+                _ => return Err(CadError::Parse { position: *position, message: format!("unknown function '{}'", name) }),
+            }
+        }
+    };
+    if value.is_finite() {
+        Ok(value)
+    } else {
+        Err(CadError::InvalidInput("result is not a finite number".to_string()))
+    }
+}
+
+// Named values for parametric shapes. Each parameter is an expression over
+// numbers and other parameters, with + - * /, parentheses, min, max, sqrt,
+// abs and trig in degrees. Numbers suffixed mm, in or " are converted into
+// `units`; bare numbers are taken to be in them already.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Parameters {
+    pub units: Units,
+    expressions: BTreeMap<String, String>,
+}
+
+impl Default for Parameters {
+    fn default() -> Parameters {
+        Parameters { units: Units::Millimeters, expressions: BTreeMap::new() }
+    }
+}
+
+impl Parameters {
+    // Checks the syntax only: references may name parameters set later
+    pub fn set(&mut self, name: &str, expression: &str) -> Result<(), CadError> {
+        let valid = name.chars().next().is_some_and(|c| c.is_alphabetic() || c == '_') && name.chars().all(|c| c.is_alphanumeric() || c == '_');
+        if !valid || FUNCTIONS.contains(&name) {
+            return Err(CadError::InvalidInput(format!("'{}' can't be used as a parameter name", name)));
+        }
+        parse(expression, self.units)?;
+        self.expressions.insert(name.to_string(), expression.to_string());
+        Ok(())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        self.expressions.remove(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.expressions.keys().map(|k| k.as_str())
+    }
+
+    pub fn expression(&self, name: &str) -> Option<&str> {
+        self.expressions.get(name).map(|e| e.as_str())
+    }
+
+    // Every parameter's value, each evaluated after those it references. A
+    // circular reference is an error naming the cycle, e.g. `a -> b -> a`.
+    pub fn evaluate(&self) -> Result<BTreeMap<String, f64>, CadError> {
+        let mut parsed = BTreeMap::new();
+        for (name, expression) in &self.expressions {
+            parsed.insert(name.as_str(), parse(expression, self.units)?);
+        }
+        let mut values = BTreeMap::new();
+        for name in self.expressions.keys() {
+            self.visit(name, &parsed, &mut values, &mut Vec::new())?;
+        }
+        Ok(values)
+    }
+
+    fn visit(&self, name: &str, parsed: &BTreeMap<&str, Expr>, values: &mut BTreeMap<String, f64>, path: &mut Vec<String>) -> Result<(), CadError> {
+        if values.contains_key(name) {
+            return Ok(());
+        }
+        if let Some(start) = path.iter().position(|p| p == name) {
+            let mut cycle = path[start..].to_vec();
+            cycle.push(name.to_string());
+            return Err(CadError::InvalidInput(format!("circular parameter reference: {}", cycle.join(" -> "))));
+        }
+        // Unknown names are reported when the expression is evaluated
+        let Some(expr) = parsed.get(name) else {
+            return Ok(());
+        };
+        path.push(name.to_string());
+        let mut deps = Vec::new();
+        dependencies(expr, &mut deps);
+        for dep in deps {
+            self.visit(&dep, parsed, values, path)?;
+        }
+        path.pop();
+        let value = evaluate(expr, values).map_err(|e| match e {
+            CadError::InvalidInput(message) => CadError::InvalidInput(format!("in parameter '{}': {}", name, message)),
+            e => e,
+        })?;
+        values.insert(name.to_string(), value);
+        Ok(())
+    }
+}
+
+// Shape whose geometry comes from a factory call over parameters, e.g.
+// `create_rounded_rect(width, height, r)`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Generator {
+    pub shape: ShapeId,
+    pub call: String,
+    // Local vertices as last generated, to tell when the shape was edited by hand
+    pub(crate) generated: Vec<Point>,
+}
+
+fn rounded_rect(width: f64, height: f64, radius: f64) -> Vec<Point> {
+    let r = radius.min(width / 2.0).min(height / 2.0);
+    if r <= 0.0 {
+        return vec![Point::new(0.0, 0.0), Point::new(width, 0.0), Point::new(width, height), Point::new(0.0, height)];
+    }
+    // Corner centres counter-clockwise from bottom right, each arc starting
+    // a quarter turn on from the last
+    let centers = [(width - r, r), (width - r, height - r), (r, height - r), (r, r)];
+    let mut ring = Vec::new();
+    for (k, (cx, cy)) in centers.iter().enumerate() {
+        let start = -FRAC_PI_2 + k as f64 * FRAC_PI_2;
+        ring.extend((0..=CORNER_SEGMENTS).map(|s| {
+            let a = start + FRAC_PI_2 * s as f64 / CORNER_SEGMENTS as f64;
+            Point::new(cx + r * a.cos(), cy + r * a.sin())
+        }));
+    }
+    ring
+}
+
+// Local geometry from a factory call: create_rectangle(width, height),
+// create_rounded_rect(width, height, radius), create_square(size) or
+// create_triangle(base, height)
+pub(crate) fn generate(call: &str, values: &BTreeMap<String, f64>, units: Units) -> Result<Polygon, CadError> {
+    let Expr::Call(name, args, position) = parse(call, units)? else {
+        return Err(CadError::Parse { position: 0, message: "expected a factory call like create_rectangle(w, h)".to_string() });
+    };
+    let args = args.iter().map(|a| evaluate(a, values)).collect::<Result<Vec<f64>, CadError>>()?;
+    if let Some(bad) = args.iter().find(|&&a| a < 0.0) {
+        return Err(CadError::InvalidInput(format!("{} got a negative size {}", name, bad)));
+    }
+    let vertices = match (name.as_str(), &args[..]) {
+        ("create_rectangle", &[w, h]) => rounded_rect(w, h, 0.0),
+        ("create_rounded_rect", &[w, h, r]) => rounded_rect(w, h, r),
+        ("create_square", &[s]) => rounded_rect(s, s, 0.0),
+        ("create_triangle", &[b, h]) => vec![Point::new(0.0, 0.0), Point::new(b, 0.0), Point::new(b / 2.0, h)],
+        ("create_rectangle" | "create_rounded_rect" | "create_square" | "create_triangle", _) => {
+            return Err(CadError::Parse { position, message: format!("wrong number of arguments to {}", name) });
+        }
+        _ => return Err(CadError::Parse { position, message: format!("unknown shape factory '{}'", name) }),
+    };
+    Ok(Polygon::new(vertices))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(expression: &str) -> Result<f64, CadError> {
+        let mut params = Parameters::default();
+        params.set("x", expression)?;
+        params.evaluate().map(|v| v["x"])
+    }
+
+    #[test]
+    fn test_expressions() {
+        assert_eq!(value("2 * (3 + 4) - 10 / 4").unwrap(), 11.5);
+        assert_eq!(value("max(1, min(7, 3), -2) + -1").unwrap(), 2.0);
+        assert!((value("1in + 5").unwrap() - 30.4).abs() < 1e-12);
+        assert!((value("cos(60)").unwrap() - 0.5).abs() < 1e-12);
+        assert!(matches!(value("2 * (3 + 4"), Err(CadError::Parse { position: 10, .. })));
+        assert!(matches!(value("sinh(1)"), Err(CadError::Parse { position: 0, .. })));
+        assert!(value("1 / (2 - 2)").is_err());
+    }
+
+    #[test]
+    fn test_cycle_reports_its_path() {
+        let mut params = Parameters::default();
+        params.set("total", "a + 1").unwrap();
+        params.set("a", "b * 2").unwrap();
+        params.set("b", "c - 1").unwrap();
+        params.set("c", "a").unwrap();
+        match params.evaluate() {
+            Err(CadError::InvalidInput(message)) => assert_eq!(message, "circular parameter reference: a -> b -> c -> a"),
+            other => panic!("expected a cycle, got {:?}", other),
+        }
+        assert!(params.set("max", "1").is_err());
+    }
+}
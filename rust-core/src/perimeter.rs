@@ -0,0 +1,137 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::hittest::edges;
+use crate::{js_error, CadError, Point, Polygon, Polyline, EPSILON};
+
+// Way round a boundary, as the caller sees it on screen
+#[wasm_bindgen]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TraversalDirection {
+    Clockwise,
+    CounterClockwise,
+}
+
+// Nearest place on the boundary: the edge from vertex `edge` and the
+// point on it
+struct BoundaryLocation {
+    edge: usize,
+    point: Point,
+}
+
+fn distance(a: &Point, b: &Point) -> f64 {
+    (a.x - b.x).hypot(a.y - b.y)
+}
+
+impl Polygon {
+    fn locate(&self, p: &Point, tolerance: f64) -> Result<BoundaryLocation, CadError> {
+        let mut best: Option<(f64, BoundaryLocation)> = None;
+        for (edge, segment) in edges(&self.vertices).enumerate() {
+            let point = segment.closest_point(p);
+            let d = distance(&point, p);
+            if best.as_ref().is_none_or(|(b, _)| d < *b) {
+                best = Some((d, BoundaryLocation { edge, point }));
+            }
+        }
+        match best {
+            Some((d, location)) if d <= tolerance.max(EPSILON) => Ok(location),
+            Some((d, _)) => Err(CadError::InvalidInput(format!("point ({}, {}) is {} from the boundary", p.x, p.y, d))),
+            None => Err(CadError::Degenerate("polygon has no edges".into())),
+        }
+    }
+
+    // Boundary in vertex order from `start` to `end`, going all the way
+    // round when `end` is behind `start` on the same edge
+    fn walk_forward(&self, start: &BoundaryLocation, end: &BoundaryLocation) -> Vec<Point> {
+        let n = self.vertices.len();
+        let mut steps = (end.edge + n - start.edge) % n;
+        let first = self.vertices[start.edge];
+        if steps == 0 && distance(&first, &end.point) < distance(&first, &start.point) - EPSILON {
+            steps = n;
+        }
+        let mut points = vec![start.point];
+        for k in 1..=steps {
+            points.push(self.vertices[(start.edge + k) % n]);
+        }
+        points.push(end.point);
+        points.dedup_by(|b, a| distance(a, b) <= EPSILON);
+        if points.len() == 1 {
+            points.push(end.point);
+        }
+        points
+    }
+
+    // The stretch of boundary from `start` to `end`, both of which are moved
+    // onto the nearest point of the boundary first, going round the way
+    // given. It runs from exactly those points through the vertices between
+    // them; a point further than `tolerance` from the boundary is an error,
+    // and the same point twice gives a path of no length.
+    pub fn boundary_between(&self, start: &Point, end: &Point, direction: TraversalDirection, tolerance: f64) -> Result<Polyline, CadError> {
+        let (from, to) = (self.locate(start, tolerance)?, self.locate(end, tolerance)?);
+        let forward = (direction == TraversalDirection::Clockwise) == self.is_clockwise();
+        let points = if forward {
+            self.walk_forward(&from, &to)
+        } else {
+            let mut points = self.walk_forward(&to, &from);
+            points.reverse();
+            points
+        };
+        Ok(Polyline::new(points, false))
+    }
+
+    pub fn boundary_length_between(&self, start: &Point, end: &Point, direction: TraversalDirection, tolerance: f64) -> Result<f64, CadError> {
+        Ok(self.boundary_between(start, end, direction, tolerance)?.length())
+    }
+}
+
+// { points, closed }
+#[wasm_bindgen]
+pub fn polygon_boundary_between(polygon: &JsValue, start_x: f64, start_y: f64, end_x: f64, end_y: f64, direction: TraversalDirection, tolerance: f64) -> Result<JsValue, JsValue> {
+    let path = crate::polygon_from(polygon)?.boundary_between(&Point::new(start_x, start_y), &Point::new(end_x, end_y), direction, tolerance).map_err(js_error)?;
+    crate::to_js(&path)
+}
+
+#[wasm_bindgen]
+pub fn polygon_boundary_length_between(polygon: &JsValue, start_x: f64, start_y: f64, end_x: f64, end_y: f64, direction: TraversalDirection, tolerance: f64) -> Result<f64, JsValue> {
+    crate::polygon_from(polygon)?.boundary_length_between(&Point::new(start_x, start_y), &Point::new(end_x, end_y), direction, tolerance).map_err(js_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit_square() -> Polygon {
+        Polygon::new(vec![Point::new(0.0, 0.0), Point::new(1.0, 0.0), Point::new(1.0, 1.0), Point::new(0.0, 1.0)])
+    }
+
+    #[test]
+    fn test_midpoints_of_adjacent_square_edges() {
+        let square = unit_square();
+        let (bottom, right) = (Point::new(0.5, 0.0), Point::new(1.0, 0.5));
+        let ccw = square.boundary_between(&bottom, &right, TraversalDirection::CounterClockwise, 1e-9).unwrap();
+        let cw = square.boundary_between(&bottom, &right, TraversalDirection::Clockwise, 1e-9).unwrap();
+        assert_eq!(ccw.length(), 1.0);
+        assert_eq!(cw.length(), 3.0);
+        assert_eq!(ccw.points, vec![bottom, Point::new(1.0, 0.0), right]);
+        assert_eq!(cw.points, vec![bottom, Point::new(0.0, 0.0), Point::new(0.0, 1.0), Point::new(1.0, 1.0), right]);
+        // Only the ends are shared
+        let inner = |p: &Polyline| p.points[1..p.points.len() - 1].to_vec();
+        assert!(inner(&ccw).iter().all(|p| !cw.points.contains(p)) && inner(&cw).iter().all(|p| !ccw.points.contains(p)));
+
+        // The same either way round a clockwise-wound copy
+        let mut reversed = square.clone();
+        reversed.vertices.reverse();
+        assert_eq!(reversed.boundary_length_between(&bottom, &right, TraversalDirection::CounterClockwise, 1e-9).unwrap(), 1.0);
+    }
+
+    #[test]
+    fn test_points_are_projected_onto_the_boundary() {
+        let square = unit_square();
+        // Slightly off the bottom edge, and behind the start on the same edge
+        let path = square.boundary_between(&Point::new(0.75, -0.01), &Point::new(0.25, 0.005), TraversalDirection::CounterClockwise, 0.02).unwrap();
+        assert_eq!((path.points[0], *path.points.last().unwrap()), (Point::new(0.75, 0.0), Point::new(0.25, 0.0)));
+        assert!((path.length() - 3.5).abs() < 1e-12);
+        assert_eq!(square.boundary_length_between(&Point::new(0.25, 0.0), &Point::new(0.25, 0.0), TraversalDirection::Clockwise, 1e-9).unwrap(), 0.0);
+        assert!(matches!(square.boundary_between(&Point::new(0.5, 0.5), &Point::new(1.0, 0.5), TraversalDirection::Clockwise, 0.02), Err(CadError::InvalidInput(_))));
+    }
+}
@@ -0,0 +1,405 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::predicates::{first_self_intersection, orient2d};
+use crate::{js_error, CadError, Document, Point, Polygon, ShapeId, EPSILON};
+
+// Beyond this many decimals rounding is a no-op for f64 input
+const MAX_DECIMALS: u32 = 15;
+
+// Coordinates closer than this fraction of the grid to a grid line, but not
+// on it, are taken for round numbers that picked up float noise
+const SUSPICIOUS_FRACTION: f64 = 1e-9;
+
+// Decimal digits beyond which the audit histogram lumps coordinates
+// together; an f64 never needs more than this to print exactly
+const MAX_DIGITS: usize = 17;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RoundedPolygon {
+    pub polygon: Polygon,
+    // Vertices kept at a finer precision than requested to avoid a self-intersection
+    pub refined_vertices: usize,
+    // Vertices dropped because rounding merged them or made their edges degenerate
+    pub removed_vertices: usize,
+}
+
+impl RoundedPolygon {
+    pub fn adjusted_vertices(&self) -> usize {
+        self.refined_vertices + self.removed_vertices
+    }
+}
+
+fn round_to(value: f64, decimals: u32) -> f64 {
+    if decimals >= MAX_DECIMALS {
+        return value;
+    }
+    let factor = 10f64.powi(decimals as i32);
+    (value * factor).round() / factor
+}
+
+fn moved(v: &Point, decimals: u32) -> bool {
+    round_to(v.x, decimals) != v.x || round_to(v.y, decimals) != v.y
+}
+
+// Rounds with the per-vertex precision, then drops duplicates and zero-width
+// spikes. Returns the cleaned ring and, for each kept vertex, its source index.
+fn round_and_clean(vertices: &[Point], precision: &[u32]) -> (Vec<Point>, Vec<usize>) {
+    let mut points: Vec<Point> = vertices.iter().zip(precision)
+        .map(|(v, &d)| Point::new(round_to(v.x, d), round_to(v.y, d)))
+        .collect();
+    let mut source: Vec<usize> = (0..vertices.len()).collect();
+
+    loop {
+        let n = points.len();
+        if n < 3 {
+            break;
+        }
+
+        let mut drop = None;
+        for i in 0..n {
+            let prev = points[(i + n - 1) % n];
+            let cur = points[i];
+            let next = points[(i + 1) % n];
+
+            let duplicate = (cur.x - prev.x).abs() < EPSILON && (cur.y - prev.y).abs() < EPSILON;
+            // A vertex whose neighbours lie on the same line through it, on the
+            // same side, makes a zero-width spike
+            let spike = orient2d(&prev, &cur, &next).abs() < EPSILON
+                && (prev.x - cur.x) * (next.x - cur.x) + (prev.y - cur.y) * (next.y - cur.y) > 0.0;
+
+            if duplicate || spike {
+                drop = Some(i);
+                break;
+            }
+        }
+
+        match drop {
+            Some(i) => {
+                points.remove(i);
+                source.remove(i);
+            }
+            None => break,
+        }
+    }
+
+    (points, source)
+}
+
+// Snaps every vertex to `decimals` places while keeping the outline valid.
+// Duplicate and newly-degenerate vertices are removed, and if rounding makes
+// the polygon self-intersect, the vertices of the offending edges are kept at
+// increasing precision until it no longer does.
+pub fn round_coordinates_report(polygon: &Polygon, decimals: u32) -> RoundedPolygon {
+    let n = polygon.vertices.len();
+    let decimals = decimals.min(MAX_DECIMALS);
+    let mut precision = vec![decimals; n];
+    let input_simple = polygon.is_simple();
+
+    loop {
+        let (points, source) = round_and_clean(&polygon.vertices, &precision);
+
+        // A polygon that was already invalid is rounded as-is; there's no valid
+        // result to protect
+        let conflict = if input_simple { first_self_intersection(&points) } else { None };
+
+        // Only vertices that rounding actually moved can be helped by more precision
+        let refine: Vec<usize> = match conflict {
+            Some((i, j)) => {
+                let m = points.len();
+                [i, (i + 1) % m, j, (j + 1) % m].iter()
+                    .map(|&k| source[k])
+                    .filter(|&k| precision[k] < MAX_DECIMALS && moved(&polygon.vertices[k], precision[k]))
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+
+        if refine.is_empty() {
+            let refined_vertices = source.iter()
+                .filter(|&&k| precision[k] > decimals && moved(&polygon.vertices[k], decimals))
+                .count();
+            return RoundedPolygon {
+                removed_vertices: n - points.len(),
+                refined_vertices,
+                polygon: Polygon::new(points),
+            };
+        }
+
+        for k in refine {
+            precision[k] += 1;
+        }
+    }
+}
+
+pub fn round_coordinates(polygon: &Polygon, decimals: u32) -> Polygon {
+    round_coordinates_report(polygon, decimals).polygon
+}
+
+#[wasm_bindgen(js_name = round_coordinates)]
+pub fn round_coordinates_js(vertices: &JsValue, decimals: u32) -> Result<JsValue, JsValue> {
+    let polygon: Polygon = crate::polygon_from(vertices)?;
+    crate::to_js(&round_coordinates_report(&polygon, decimals))
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SuspiciousShape {
+    pub id: ShapeId,
+    // Coordinates just off the grid
+    pub coordinates: usize,
+    // Largest distance of one of them from its grid line
+    pub max_deviation: f64,
+}
+
+// Vertices of two shapes that nearly but don't quite meet
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct NearCoincidence {
+    pub a: ShapeId,
+    pub b: ShapeId,
+    pub at: Point,
+    pub distance: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PrecisionReport {
+    pub grid: f64,
+    // Closeness to a grid line taken as float noise
+    pub epsilon: f64,
+    pub suspicious: Vec<SuspiciousShape>,
+    // Coordinates by how many decimal digits they print with, the last
+    // bucket holding MAX_DIGITS or more
+    pub decimal_digits: Vec<usize>,
+    pub near_coincident: Vec<NearCoincidence>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SnapReport {
+    pub shapes: Vec<ShapeId>,
+    pub coordinates: usize,
+    // Shapes left as they were because snapping would have made valid
+    // geometry invalid
+    pub skipped: Vec<ShapeId>,
+}
+
+// Distance off its grid line when that is above zero and below `epsilon`
+fn off_grid(v: f64, grid: f64, epsilon: f64) -> Option<f64> {
+    let d = (v - (v / grid).round() * grid).abs();
+    (d > 0.0 && d < epsilon).then_some(d)
+}
+
+fn snapped(v: f64, grid: f64, epsilon: f64) -> f64 {
+    match off_grid(v, grid, epsilon) {
+        Some(_) => (v / grid).round() * grid,
+        None => v,
+    }
+}
+
+// Digits after the point in the shortest text that reads back as `v`
+fn decimal_digits(v: f64) -> usize {
+    let text = v.to_string();
+    text.find('.').map_or(0, |dot| text.len() - dot - 1)
+}
+
+impl Document {
+    // Looks for coordinates that were meant to be round: shapes with
+    // geometry just off multiples of `grid`, the spread of decimal digits
+    // in use, and vertices of different shapes that are not quite
+    // together. Geometry is checked as stored, meeting vertices in world
+    // space.
+    pub fn precision_audit(&self, grid: f64) -> PrecisionReport {
+        let grid_ok = grid.is_finite() && grid > 0.0;
+        let epsilon = if grid_ok { grid * SUSPICIOUS_FRACTION } else { 0.0 };
+        let mut suspicious = Vec::new();
+        let mut decimal = vec![0; MAX_DIGITS + 1];
+        for shape in self.shapes() {
+            let mut flagged = SuspiciousShape { id: shape.id, coordinates: 0, max_deviation: 0.0 };
+            for v in shape.geometry.vertices.iter().flat_map(|p| [p.x, p.y]) {
+                decimal[decimal_digits(v).min(MAX_DIGITS)] += 1;
+                if let Some(d) = off_grid(v, grid, epsilon).filter(|_| grid_ok) {
+                    flagged.coordinates += 1;
+                    flagged.max_deviation = flagged.max_deviation.max(d);
+                }
+            }
+            if flagged.coordinates > 0 {
+                suspicious.push(flagged);
+            }
+        }
+        PrecisionReport { grid, epsilon, suspicious, decimal_digits: decimal, near_coincident: self.near_coincident(epsilon.max(EPSILON)) }
+    }
+
+    // Pairs of vertices from different shapes within `within` of each other
+    // but not equal, hashed into cells that size so only neighbouring cells
+    // are compared
+    fn near_coincident(&self, within: f64) -> Vec<NearCoincidence> {
+        let cell = |p: &Point| ((p.x / within).floor() as i64, (p.y / within).floor() as i64);
+        let mut cells: HashMap<(i64, i64), Vec<(ShapeId, Point)>> = HashMap::new();
+        for shape in self.shapes() {
+            for p in shape.world_geometry().vertices {
+                cells.entry(cell(&p)).or_default().push((shape.id, p));
+            }
+        }
+        let mut found = Vec::new();
+        for (&(cx, cy), here) in &cells {
+            for (dx, dy) in [(0, 0), (1, -1), (1, 0), (1, 1), (0, 1)] {
+                let Some(there) = cells.get(&(cx + dx, cy + dy)) else {
+                    continue;
+                };
+                for (i, &(a, p)) in here.iter().enumerate() {
+                    let others = if (dx, dy) == (0, 0) { &here[i + 1..] } else { &there[..] };
+                    for &(b, q) in others {
+                        let distance = (p.x - q.x).hypot(p.y - q.y);
+                        if a != b && distance > 0.0 && distance < within {
+                            found.push(NearCoincidence { a: a.min(b), b: a.max(b), at: if a < b { p } else { q }, distance });
+                        }
+                    }
+                }
+            }
+        }
+        found.sort_by(|x, y| (x.a, x.b, x.at.x, x.at.y).partial_cmp(&(y.a, y.b, y.at.x, y.at.y)).unwrap_or(std::cmp::Ordering::Equal));
+        found
+    }
+
+    // Moves the coordinates within `epsilon` of a multiple of `grid` onto
+    // it and leaves every other coordinate alone. A shape that was valid is
+    // only changed if it stays valid. Checkpoints first when anything
+    // moves, so one `undo` puts it all back.
+    pub fn snap_suspicious(&mut self, grid: f64, epsilon: f64) -> Result<SnapReport, CadError> {
+        if !(grid.is_finite() && grid > 0.0) {
+            return Err(CadError::InvalidInput(format!("grid must be positive, got {}", grid)));
+        }
+        let mut report = SnapReport { shapes: Vec::new(), coordinates: 0, skipped: Vec::new() };
+        let mut edits = Vec::new();
+        for shape in self.shapes() {
+            let before = &shape.geometry;
+            let moved = before.vertices.iter().flat_map(|p| [p.x, p.y]).filter(|&v| off_grid(v, grid, epsilon).is_some()).count();
+            if moved == 0 {
+                continue;
+            }
+            let after = Polygon::new(before.vertices.iter().map(|p| Point::new(snapped(p.x, grid, epsilon), snapped(p.y, grid, epsilon))).collect());
+            if before.validate().is_ok() && after.validate().is_err() {
+                report.skipped.push(shape.id);
+                continue;
+            }
+            report.shapes.push(shape.id);
+            report.coordinates += moved;
+            edits.push((shape.id, after));
+        }
+        if !edits.is_empty() {
+            self.checkpoint();
+        }
+        for (id, geometry) in edits {
+            self.shape_mut(id)?.geometry = geometry;
+        }
+        report.shapes.sort_unstable();
+        report.skipped.sort_unstable();
+        Ok(report)
+    }
+}
+
+#[wasm_bindgen]
+impl Document {
+    // { grid, epsilon, suspicious: [{ id, coordinates, max_deviation }],
+    // decimal_digits, near_coincident: [{ a, b, at, distance }] }
+    #[wasm_bindgen(js_name = precision_audit)]
+    pub fn precision_audit_js(&self, grid: f64) -> Result<JsValue, JsValue> {
+        crate::to_js(&self.precision_audit(grid))
+    }
+
+    // { shapes, coordinates, skipped }; undone by `undo`
+    #[wasm_bindgen(js_name = snap_suspicious)]
+    pub fn snap_suspicious_js(&mut self, grid: f64, epsilon: f64) -> Result<JsValue, JsValue> {
+        crate::to_js(&self.snap_suspicious(grid, epsilon).map_err(js_error)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A notch whose tip hovers 0.0004 above the bottom edge
+    fn sliver_notch() -> Polygon {
+        Polygon::new(vec![
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 0.0),
+            Point::new(1.0, 1.0),
+            Point::new(0.6, 1.0),
+            Point::new(0.5, 0.0004),
+            Point::new(0.4, 1.0),
+            Point::new(0.0, 1.0),
+        ])
+    }
+
+    #[test]
+    fn test_naive_rounding_breaks_sliver() {
+        let polygon = sliver_notch();
+        assert!(polygon.is_simple());
+
+        let naive: Vec<Point> = polygon.vertices.iter()
+            .map(|v| Point::new(round_to(v.x, 3), round_to(v.y, 3)))
+            .collect();
+        assert!(!Polygon::new(naive).is_simple());
+    }
+
+    #[test]
+    fn test_rounding_keeps_sliver_simple() {
+        let result = round_coordinates_report(&sliver_notch(), 3);
+
+        assert!(result.polygon.is_simple());
+        assert_eq!(result.polygon.vertices.len(), 7);
+        assert_eq!(result.refined_vertices, 1);
+        assert_eq!(result.polygon.vertices[4], Point::new(0.5, 0.0004));
+    }
+
+    #[test]
+    fn test_rounding_collapses_duplicates_and_spikes() {
+        let polygon = Polygon::new(vec![
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 0.0),
+            Point::new(1.0001, 0.0002),
+            Point::new(1.0, 1.0),
+            Point::new(0.5, 1.0),
+            Point::new(0.5, 1.2),
+            Point::new(0.5004, 1.0),
+            Point::new(0.0, 1.0),
+        ]);
+
+        // The merged corner, the spike and its collapsed base go; the original
+        // collinear vertex at (0.5, 1) stays
+        let result = round_coordinates_report(&polygon, 2);
+        assert_eq!(result.polygon.vertices.len(), 5);
+        assert_eq!(result.removed_vertices, 3);
+        assert!(result.polygon.is_simple());
+        assert!((result.polygon.area() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_snap_cleans_float_noise_but_not_off_grid_values() {
+        let mut doc = Document::new();
+        let e = 1e-13;
+        let a = doc.add_shape(Polygon::new(vec![Point::new(e, e), Point::new(1.5, 0.0), Point::new(1.5 - e, 1.0), Point::new(0.0, 1.0)]));
+        let b = doc.add_shape(Polygon::new(vec![Point::new(1.5, 0.0), Point::new(3.0 + e, 0.0), Point::new(3.0 - e, 1.0 + e), Point::new(1.5, 1.0)]));
+        let c = doc.add_shape(Polygon::new(vec![Point::new(5.0, 0.0), Point::new(6.0, 0.0), Point::new(6.0, 0.3), Point::new(5.0, 0.5)]));
+
+        let report = doc.precision_audit(0.5);
+        assert_eq!(report.suspicious.iter().map(|s| (s.id, s.coordinates)).collect::<Vec<_>>(), vec![(a, 3), (b, 3)]);
+        assert!(report.suspicious.iter().all(|s| s.max_deviation < 2e-13));
+        assert_eq!(report.decimal_digits.iter().sum::<usize>(), 24);
+        assert_eq!(report.decimal_digits[1], 5);
+        // a's noisy corner lands next to b's exact one
+        assert_eq!(report.near_coincident.len(), 1);
+        assert_eq!((report.near_coincident[0].a, report.near_coincident[0].b), (a, b));
+
+        let snap = doc.snap_suspicious(0.5, 1e-9).unwrap();
+        assert_eq!(snap, SnapReport { shapes: vec![a, b], coordinates: 6, skipped: vec![] });
+        let after = doc.precision_audit(0.5);
+        assert!(after.suspicious.is_empty() && after.near_coincident.is_empty());
+        assert!(after.decimal_digits[2..].iter().all(|&n| n == 0));
+        assert_eq!(doc.shape(c).unwrap().geometry.vertices[2], Point::new(6.0, 0.3));
+        assert!(doc.shapes().all(|s| s.geometry.validate().is_ok()));
+
+        assert!(doc.undo());
+        assert_eq!(doc.precision_audit(0.5), report);
+    }
+}
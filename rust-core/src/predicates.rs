@@ -0,0 +1,308 @@
+use crate::{GeometryError, Point, Polygon, Vector, EPSILON};
+
+// Twice the signed area of triangle (a, b, c); positive when counter-clockwise
+pub(crate) fn orient2d(a: &Point, b: &Point, c: &Point) -> f64 {
+    (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
+}
+
+// Bound on the rounding error of orient2d relative to the magnitude of its
+// two products (Shewchuk's ccwerrboundA)
+const ORIENT_BOUND: f64 = (3.0 + 8.0 * f64::EPSILON) * f64::EPSILON / 2.0;
+
+fn two_product(a: f64, b: f64) -> (f64, f64) {
+    let p = a * b;
+    (p, a.mul_add(b, -p))
+}
+
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let s = a + b;
+    let bv = s - a;
+    (s, (a - (s - bv)) + (b - bv))
+}
+
+// orient2d whose sign is always that of the exact determinant, so collinear
+// points give exactly zero. The plain result is kept whenever it is far enough
+// from zero to trust; otherwise the determinant is expanded into exact
+// products and summed without loss.
+pub(crate) fn orient2d_robust(a: &Point, b: &Point, c: &Point) -> f64 {
+    let left = (b.x - a.x) * (c.y - a.y);
+    let right = (b.y - a.y) * (c.x - a.x);
+    let det = left - right;
+    if det.abs() > ORIENT_BOUND * (left.abs() + right.abs()) {
+        return det;
+    }
+    // bx cy - bx ay - ax cy - by cx + by ax + ay cx, the ax ay terms cancelling
+    let products = [(b.x, c.y), (-b.x, a.y), (-a.x, c.y), (-b.y, c.x), (b.y, a.x), (a.y, c.x)];
+    let mut expansion: Vec<f64> = Vec::with_capacity(12);
+    for (x, y) in products {
+        let (p, e) = two_product(x, y);
+        for mut q in [e, p] {
+            // Grow-Expansion: components stay non-overlapping and increasing
+            for component in expansion.iter_mut() {
+                let (s, err) = two_sum(q, *component);
+                *component = err;
+                q = s;
+            }
+            expansion.push(q);
+        }
+    }
+    // The largest non-zero component carries the sign
+    expansion.iter().rev().find(|c| **c != 0.0).copied().unwrap_or(0.0)
+}
+
+// Neumaier's compensated sum, accurate to about one rounding of the total
+// however many terms cancel
+pub(crate) fn compensated_sum(terms: impl IntoIterator<Item = f64>) -> f64 {
+    let (mut sum, mut carry) = (0.0f64, 0.0);
+    for t in terms {
+        let next = sum + t;
+        carry += if sum.abs() >= t.abs() { (sum - next) + t } else { (t - next) + sum };
+        sum = next;
+    }
+    sum + carry
+}
+
+// Shoelace area, positive for counter-clockwise rings. Taken about the
+// first vertex: at survey coordinates the raw products are ~1e13 and
+// cancel away any digits a small ring's area had.
+pub(crate) fn ring_signed_area(points: &[Point]) -> f64 {
+    let n = points.len();
+    let Some(&o) = points.first() else {
+        return 0.0;
+    };
+    compensated_sum((1..n.saturating_sub(1)).map(|i| {
+        let (a, b) = (points[i], points[i + 1]);
+        (a.x - o.x) * (b.y - o.y) - (b.x - o.x) * (a.y - o.y)
+    })) / 2.0
+}
+
+// Convex in either winding: every turn goes the same way, and the turns add
+// up to one revolution, which rules out a star. Turns within EPSILON of
+// straight ahead (relative to the edge lengths) are collinear runs and don't
+// count; zero-length edges are skipped. A hairpin, however sharp, is still a
+// turn, so a needle is convex, but one that doubles straight back is not.
+// Fewer than three distinct directions is not convex.
+pub(crate) fn ring_is_convex(points: &[Point]) -> bool {
+    let n = points.len();
+    let edges: Vec<(f64, f64)> = (0..n)
+        .map(|i| (points[(i + 1) % n].x - points[i].x, points[(i + 1) % n].y - points[i].y))
+        .filter(|&(dx, dy)| dx != 0.0 || dy != 0.0)
+        .collect();
+    let m = edges.len();
+    let (mut sign, mut total, mut turns) = (0.0, 0.0, 0);
+    for i in 0..m {
+        let ((ax, ay), (bx, by)) = (edges[i], edges[(i + 1) % m]);
+        let (cross, dot) = (ax * by - ay * bx, ax * bx + ay * by);
+        total += cross.atan2(dot);
+        if dot > 0.0 && cross.abs() <= EPSILON * ax.hypot(ay) * bx.hypot(by) {
+            continue;
+        }
+        if cross == 0.0 {
+            return false;
+        }
+        if sign * cross < 0.0 {
+            return false;
+        }
+        sign = cross;
+        turns += 1;
+    }
+    turns >= 3 && (total.abs() - std::f64::consts::TAU).abs() < 1e-6
+}
+
+// Even-odd ray casting test
+pub(crate) fn point_in_ring(vertices: &[Point], p: &Point) -> bool {
+    let n = vertices.len();
+    if n < 3 {
+        return false;
+    }
+
+    let mut inside = false;
+    let mut j = n - 1;
+
+    for i in 0..n {
+        let a = vertices[i];
+        let b = vertices[j];
+        if (a.y > p.y) != (b.y > p.y) {
+            let x = (b.x - a.x) * (p.y - a.y) / (b.y - a.y) + a.x;
+            if p.x < x {
+                inside = !inside;
+            }
+        }
+        j = i;
+    }
+
+    inside
+}
+
+// Inclusive of the triangle boundary, independent of the triangle's winding
+pub(crate) fn point_in_triangle(p: &Point, a: &Point, b: &Point, c: &Point) -> bool {
+    if orient2d(a, b, c).abs() <= EPSILON {
+        // Degenerate triangle: only points on the a-c span are covered
+        return orient2d(a, c, p).abs() <= EPSILON && on_segment(a, c, p);
+    }
+
+    let d1 = orient2d(a, b, p);
+    let d2 = orient2d(b, c, p);
+    let d3 = orient2d(c, a, p);
+    let has_neg = d1 < -EPSILON || d2 < -EPSILON || d3 < -EPSILON;
+    let has_pos = d1 > EPSILON || d2 > EPSILON || d3 > EPSILON;
+    !(has_neg && has_pos)
+}
+
+fn on_segment(a: &Point, b: &Point, p: &Point) -> bool {
+    p.x >= a.x.min(b.x) - EPSILON && p.x <= a.x.max(b.x) + EPSILON
+        && p.y >= a.y.min(b.y) - EPSILON && p.y <= a.y.max(b.y) + EPSILON
+}
+
+// True when the closed segments ab and cd share at least one point
+pub(crate) fn segments_intersect(a: &Point, b: &Point, c: &Point, d: &Point) -> bool {
+    let d1 = orient2d(c, d, a);
+    let d2 = orient2d(c, d, b);
+    let d3 = orient2d(a, b, c);
+    let d4 = orient2d(a, b, d);
+
+    if ((d1 > EPSILON && d2 < -EPSILON) || (d1 < -EPSILON && d2 > EPSILON))
+        && ((d3 > EPSILON && d4 < -EPSILON) || (d3 < -EPSILON && d4 > EPSILON))
+    {
+        return true;
+    }
+
+    (d1.abs() <= EPSILON && on_segment(c, d, a))
+        || (d2.abs() <= EPSILON && on_segment(c, d, b))
+        || (d3.abs() <= EPSILON && on_segment(a, b, c))
+        || (d4.abs() <= EPSILON && on_segment(a, b, d))
+}
+
+impl Polygon {
+    // A polygon is simple when no two non-adjacent edges touch and adjacent
+    // edges meet only at their shared vertex.
+    pub fn is_simple(&self) -> bool {
+        self.vertices.len() >= 3 && first_self_intersection(&self.vertices).is_none()
+    }
+
+    // Checks, in turn, that there are at least three vertices, every
+    // coordinate is finite, no vertex repeats the next to within EPSILON
+    // (the last and first included) and the polygon is simple
+    pub fn validate(&self) -> Result<(), GeometryError> {
+        let v = &self.vertices;
+        if v.len() < 3 {
+            return Err(GeometryError::TooFewVertices(v.len()));
+        }
+        if let Some(i) = v.iter().position(|p| !p.x.is_finite() || !p.y.is_finite()) {
+            return Err(GeometryError::NonFinite(i));
+        }
+        let next = |i: usize| v[(i + 1) % v.len()];
+        if let Some(i) = (0..v.len()).find(|&i| (v[i].x - next(i).x).hypot(v[i].y - next(i).y) <= EPSILON) {
+            return Err(GeometryError::DuplicateVertices(i));
+        }
+        match first_self_intersection(v) {
+            Some((i, j)) => Err(GeometryError::SelfIntersecting(i.min(j), i.max(j))),
+            None => Ok(()),
+        }
+    }
+}
+
+// Returns the start indices of the first pair of edges found touching in a
+// way a simple polygon forbids. Edges are swept in x order so only pairs with
+// overlapping x-extents are compared.
+pub(crate) fn first_self_intersection(vertices: &[Point]) -> Option<(usize, usize)> {
+    let n = vertices.len();
+    if n < 3 {
+        return None;
+    }
+
+    let edge = |i: usize| (vertices[i], vertices[(i + 1) % n]);
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&i, &j| {
+        let (a, b) = edge(i);
+        let (c, d) = edge(j);
+        a.x.min(b.x).partial_cmp(&c.x.min(d.x)).unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    for (k, &i) in order.iter().enumerate() {
+        let (a, b) = edge(i);
+        let max_x = a.x.max(b.x);
+
+        for &j in &order[k + 1..] {
+            let (c, d) = edge(j);
+            if c.x.min(d.x) > max_x + EPSILON {
+                break;
+            }
+
+            let adjacent = (i + 1) % n == j || (j + 1) % n == i;
+            if adjacent {
+                // Adjacent edges may only overlap when they fold back onto each other
+                let (shared, other_a, other_b) = if (i + 1) % n == j { (b, a, d) } else { (a, b, c) };
+                // Folded to within rounding of the edges' lengths, so short
+                // edges or a needle's sharp tip don't count
+                let back = Vector::new(other_a.x - shared.x, other_a.y - shared.y);
+                let fwd = Vector::new(other_b.x - shared.x, other_b.y - shared.y);
+                if orient2d(&other_a, &shared, &other_b).abs() <= 64.0 * f64::EPSILON * back.magnitude() * fwd.magnitude() && back.x * fwd.x + back.y * fwd.y > 0.0 {
+                    return Some((i, j));
+                }
+                continue;
+            }
+
+            if segments_intersect(&a, &b, &c, &d) {
+                return Some((i, j));
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_simple() {
+        let square = Polygon::new(vec![
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 0.0),
+            Point::new(1.0, 1.0),
+            Point::new(0.0, 1.0),
+        ]);
+        assert!(square.is_simple());
+
+        let bowtie = Polygon::new(vec![
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 1.0),
+            Point::new(1.0, 0.0),
+            Point::new(0.0, 1.0),
+        ]);
+        assert!(!bowtie.is_simple());
+
+        // Two loops sharing a vertex
+        let pinched = Polygon::new(vec![
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 1.0),
+            Point::new(2.0, 0.0),
+            Point::new(2.0, 2.0),
+            Point::new(1.0, 1.0),
+            Point::new(0.0, 2.0),
+        ]);
+        assert!(!pinched.is_simple());
+    }
+
+    #[test]
+    fn test_validate() {
+        let points = |xy: &[(f64, f64)]| xy.iter().map(|&(x, y)| Point::new(x, y)).collect::<Vec<_>>();
+        let square = points(&[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]);
+        assert_eq!(Polygon::try_new(square.clone()).unwrap().vertices, square);
+
+        let bowtie = Polygon::new(points(&[(0.0, 0.0), (1.0, 1.0), (1.0, 0.0), (0.0, 1.0)]));
+        assert_eq!(bowtie.validate(), Err(GeometryError::SelfIntersecting(0, 2)));
+        let nan = points(&[(0.0, 0.0), (1.0, f64::NAN), (1.0, 1.0)]);
+        assert_eq!(Polygon::try_new(nan).unwrap_err(), GeometryError::NonFinite(1));
+        // Closing back onto the first vertex repeats it
+        let closed = Polygon::new(points(&[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (1e-10, 0.0)]));
+        assert_eq!(closed.validate(), Err(GeometryError::DuplicateVertices(3)));
+        assert_eq!(Polygon::new(points(&[(0.0, 0.0), (1.0, 0.0)])).validate(), Err(GeometryError::TooFewVertices(2)));
+
+        let e = crate::CadError::from(bowtie.validate().unwrap_err());
+        assert_eq!(e.code(), "self-intersecting");
+        assert!(e.to_string().starts_with(e.code()));
+    }
+}
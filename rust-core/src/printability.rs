@@ -0,0 +1,189 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::arrangement::{assemble, Arrangement};
+use crate::{Polygon, PolygonWithHoles, Polyline, Segment, EPSILON};
+
+// Process limits a profile is checked against, in drawing units
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct PrintOptions {
+    // Walls narrower than this can't be laid down
+    pub nozzle_width: f64,
+    // Holes narrower than this close up
+    pub min_hole_diameter: f64,
+    // Islands of less area than this are slivers
+    pub min_island_area: f64,
+    // Printed as the top of a vase-mode print, where a single outline is
+    // laid down and any island beyond the first is unsupported
+    pub vase_mode: bool,
+}
+
+impl Default for PrintOptions {
+    fn default() -> PrintOptions {
+        PrintOptions { nozzle_width: 0.4, min_hole_diameter: 1.0, min_island_area: 0.16, vase_mode: false }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    // Prints, but not as drawn
+    Warning,
+    // Won't print
+    Error,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PrintIssueKind {
+    ThinWall,
+    SmallHole,
+    Sliver,
+    UnsupportedIsland,
+}
+
+// One problem, with what to highlight: the narrowest section across a wall
+// as an open two-point polyline, or the outline of a hole or island
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PrintIssue {
+    pub kind: PrintIssueKind,
+    pub severity: Severity,
+    pub message: String,
+    pub highlight: Polyline,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PrintabilityReport {
+    pub issues: Vec<PrintIssue>,
+    // As `PolygonWithHoles::min_feature_size`
+    pub min_wall: f64,
+    pub islands: usize,
+}
+
+impl PrintabilityReport {
+    pub fn has_errors(&self) -> bool {
+        self.issues.iter().any(|i| i.severity == Severity::Error)
+    }
+}
+
+// Disjoint pieces of material, largest first
+fn islands(shape: &PolygonWithHoles) -> Vec<PolygonWithHoles> {
+    let rings = std::iter::once(&shape.outer).chain(&shape.holes);
+    let segments: Vec<Segment> = rings.flat_map(|r| {
+        let v = &r.vertices;
+        (0..v.len()).map(move |i| Segment::new(v[i], v[(i + 1) % v.len()]))
+    }).collect();
+    let arrangement = Arrangement::build(&segments, EPSILON);
+    let mut pieces = assemble(arrangement.covered_boundary(|q| shape.contains_point(q)), EPSILON);
+    pieces.sort_by(|a, b| b.area().total_cmp(&a.area()));
+    pieces
+}
+
+fn closed(polygon: &Polygon) -> Polyline {
+    Polyline::new(polygon.vertices.clone(), true)
+}
+
+// Checks a 2D profile before it is extruded for printing: the narrowest
+// wall against the nozzle, each hole's narrowest width against the
+// smallest hole that stays open, and the disjoint islands the profile falls
+// into, flagging tiny ones as slivers and, in vase mode, every island but
+// the largest as unsupported. Issues come walls first, then holes in order,
+// then islands.
+pub fn printability_report(shape: &PolygonWithHoles, opts: &PrintOptions) -> PrintabilityReport {
+    let mut issues = Vec::new();
+    let narrowest = shape.min_feature_size();
+    if let Some(section) = narrowest.section.filter(|_| narrowest.width < opts.nozzle_width) {
+        issues.push(PrintIssue {
+            kind: PrintIssueKind::ThinWall,
+            severity: Severity::Error,
+            message: format!("wall {} wide is narrower than the {} nozzle", narrowest.width, opts.nozzle_width),
+            highlight: Polyline::new(vec![section.a, section.b], false),
+        });
+    }
+    for (i, hole) in shape.holes.iter().enumerate() {
+        let width = hole.min_feature_size().width;
+        if width < opts.min_hole_diameter {
+            issues.push(PrintIssue {
+                kind: PrintIssueKind::SmallHole,
+                severity: Severity::Warning,
+                message: format!("hole {} is {} across, under the {} minimum", i, width, opts.min_hole_diameter),
+                highlight: closed(hole),
+            });
+        }
+    }
+    let pieces = islands(shape);
+    for (i, piece) in pieces.iter().enumerate() {
+        if piece.area() < opts.min_island_area {
+            issues.push(PrintIssue {
+                kind: PrintIssueKind::Sliver,
+                severity: Severity::Error,
+                message: format!("island of area {} is under the {} minimum", piece.area(), opts.min_island_area),
+                highlight: closed(&piece.outer),
+            });
+        } else if opts.vase_mode && i > 0 {
+            issues.push(PrintIssue {
+                kind: PrintIssueKind::UnsupportedIsland,
+                severity: Severity::Warning,
+                message: "vase mode prints one outline, so this island is unsupported".to_string(),
+                highlight: closed(&piece.outer),
+            });
+        }
+    }
+    PrintabilityReport { issues, min_wall: narrowest.width, islands: pieces.len() }
+}
+
+// `shape` is { outer: { vertices }, holes: [{ vertices }, ...] } and
+// `opts` any of the PrintOptions fields; returns { issues: [{ kind,
+// severity, message, highlight: { points, closed } }], min_wall, islands }
+#[wasm_bindgen(js_name = printability_report)]
+pub fn printability_report_js(shape: &JsValue, opts: &JsValue) -> Result<JsValue, JsValue> {
+    let shape: PolygonWithHoles = crate::from_js(shape)?;
+    let opts: PrintOptions = crate::options_from(opts)?;
+    crate::to_js(&printability_report(&shape, &opts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Point;
+
+    fn rect(x0: f64, y0: f64, x1: f64, y1: f64) -> Polygon {
+        Polygon::new(vec![Point::new(x0, y0), Point::new(x1, y0), Point::new(x1, y1), Point::new(x0, y1)])
+    }
+
+    #[test]
+    fn test_plate_with_thin_web_and_small_hole() {
+        // A slot leaving 0.3 of wall to the right edge, and a 0.5 hole
+        let pin = Polygon::new((0..32).rev().map(|k| {
+            let a = k as f64 * std::f64::consts::TAU / 32.0;
+            Point::new(5.0 + 0.25 * a.cos(), 5.0 + 0.25 * a.sin())
+        }).collect());
+        let plate = PolygonWithHoles::new(rect(0.0, 0.0, 20.0, 20.0), vec![rect(15.0, 5.0, 19.7, 15.0), pin]);
+        let report = printability_report(&plate, &PrintOptions::default());
+        assert_eq!(report.issues.len(), 2);
+        assert_eq!(report.islands, 1);
+        assert!(report.has_errors());
+
+        let wall = &report.issues[0];
+        assert_eq!((wall.kind, wall.severity), (PrintIssueKind::ThinWall, Severity::Error));
+        assert!((report.min_wall - 0.3).abs() < 1e-9);
+        assert!(wall.highlight.points.iter().all(|p| p.x >= 19.7 - 1e-9 && p.y >= 5.0 && p.y <= 15.0));
+        let hole = &report.issues[1];
+        assert_eq!((hole.kind, hole.severity), (PrintIssueKind::SmallHole, Severity::Warning));
+        assert!(hole.highlight.closed && hole.highlight.points.iter().all(|p| (p.x - 5.0).hypot(p.y - 5.0) < 0.26));
+
+        let relaxed = PrintOptions { nozzle_width: 0.25, min_hole_diameter: 0.4, ..PrintOptions::default() };
+        assert!(printability_report(&plate, &relaxed).issues.is_empty());
+    }
+
+    #[test]
+    fn test_islands_in_vase_mode() {
+        // A hole running right across the plate cuts it in two, and a
+        // second cuts a sliver off one end
+        let plate = PolygonWithHoles::new(rect(0.0, 0.0, 10.0, 4.0), vec![rect(4.0, -1.0, 5.0, 5.0), rect(9.9, -1.0, 9.97, 5.0)]);
+        let opts = PrintOptions { vase_mode: true, nozzle_width: 0.01, min_hole_diameter: 0.0, ..PrintOptions::default() };
+        let report = printability_report(&plate, &opts);
+        assert_eq!(report.islands, 3);
+        let kinds: Vec<PrintIssueKind> = report.issues.iter().map(|i| i.kind).collect();
+        assert_eq!(kinds, vec![PrintIssueKind::UnsupportedIsland, PrintIssueKind::Sliver]);
+    }
+}
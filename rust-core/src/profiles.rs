@@ -0,0 +1,228 @@
+use std::f64::consts::PI;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::{js_error, CadError, Point, Polygon, Polyline};
+
+// Tooth heights above and below the pitch line, in modules, as for a
+// standard full-depth gear
+const ADDENDUM: f64 = 1.0;
+const DEDENDUM: f64 = 1.25;
+// Solid bar below the tooth roots, in modules
+const RACK_BODY: f64 = 2.0;
+
+// Allowed riser height and tread going, in document units; None leaves
+// that side unchecked. The defaults are the residential code limits in
+// millimetres: risers from 4 in to 7 3/4 in, treads of at least 10 in.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct StepLimits {
+    pub min_riser: Option<f64>,
+    pub max_riser: Option<f64>,
+    pub min_tread: Option<f64>,
+    pub max_tread: Option<f64>,
+}
+
+impl Default for StepLimits {
+    fn default() -> StepLimits {
+        StepLimits { min_riser: Some(101.6), max_riser: Some(196.85), min_tread: Some(254.0), max_tread: None }
+    }
+}
+
+fn positive(value: f64, what: &str) -> Result<(), CadError> {
+    if value.is_finite() && value > 0.0 {
+        Ok(())
+    } else {
+        Err(CadError::InvalidInput(format!("{} must be positive, got {}", what, value)))
+    }
+}
+
+fn within(value: f64, min: Option<f64>, max: Option<f64>, what: &str) -> Result<(), CadError> {
+    if let Some(min) = min.filter(|&m| value < m) {
+        return Err(CadError::InvalidInput(format!("{} {} is below the minimum {} of {}", what, value, what, min)));
+    }
+    if let Some(max) = max.filter(|&m| value > m) {
+        return Err(CadError::InvalidInput(format!("{} {} exceeds the maximum {} of {}", what, value, what, max)));
+    }
+    Ok(())
+}
+
+// Staircase in section, from (0, 0) up to (total_run, total_rise): each of
+// the `step_count` steps is a riser then a tread, all the same size. A
+// nosing rakes each riser forward so its tread overhangs the one below
+// by `nosing`; the treads' going, nosing to nosing, stays total_run /
+// step_count.
+pub fn create_steps(total_run: f64, total_rise: f64, step_count: u32, nosing: f64, limits: &StepLimits) -> Result<Polyline, CadError> {
+    positive(total_run, "total run")?;
+    positive(total_rise, "total rise")?;
+    if step_count == 0 {
+        return Err(CadError::InvalidInput("step count must be at least 1".into()));
+    }
+    let (tread, riser) = (total_run / step_count as f64, total_rise / step_count as f64);
+    within(riser, limits.min_riser, limits.max_riser, "riser")?;
+    within(tread, limits.min_tread, limits.max_tread, "tread")?;
+    if !(nosing.is_finite() && nosing >= 0.0 && nosing < tread) {
+        return Err(CadError::InvalidInput(format!("nosing {} must be at least 0 and less than the tread {}", nosing, tread)));
+    }
+    let mut points = vec![Point::new(0.0, 0.0)];
+    for k in 0..step_count {
+        let (x, y) = (k as f64 * tread, (k + 1) as f64 * riser);
+        points.push(Point::new(x - nosing, y));
+        points.push(Point::new(x + tread, y));
+    }
+    // Land exactly on the requested totals
+    *points.last_mut().unwrap() = Point::new(total_run, total_rise);
+    Ok(Polyline::new(points, false))
+}
+
+// Comb: `teeth` rectangular teeth `depth` deep standing on a base strip
+// `base_height` high, `gap_width` apart. Its area is the strip's plus
+// teeth * tooth_width * depth.
+pub fn create_comb(teeth: u32, tooth_width: f64, gap_width: f64, depth: f64, base_height: f64) -> Result<Polygon, CadError> {
+    if teeth == 0 {
+        return Err(CadError::InvalidInput("a comb needs at least 1 tooth".into()));
+    }
+    positive(tooth_width, "tooth width")?;
+    positive(gap_width, "gap width")?;
+    positive(depth, "tooth depth")?;
+    positive(base_height, "base height")?;
+    let pitch = tooth_width + gap_width;
+    let width = teeth as f64 * pitch - gap_width;
+    let top = base_height + depth;
+    let mut vertices = vec![Point::new(0.0, 0.0), Point::new(width, 0.0)];
+    // Right to left across the teeth
+    for k in (0..teeth).rev() {
+        let left = k as f64 * pitch;
+        if k + 1 < teeth {
+            vertices.push(Point::new(left + tooth_width, base_height));
+        }
+        vertices.push(Point::new(left + tooth_width, top));
+        vertices.push(Point::new(left, top));
+        if k > 0 {
+            vertices.push(Point::new(left, base_height));
+        }
+    }
+    let comb = Polygon::new(vertices);
+    comb.validate().map_err(CadError::Geometry)?;
+    Ok(comb)
+}
+
+// Distance between teeth along the pitch line of a gear or rack of this
+// module, the same for every tooth count, which is why a rack meshes with
+// any gear of its module
+pub fn circular_pitch(module: f64) -> f64 {
+    PI * module
+}
+
+// Straight-flanked rack of `teeth` teeth for gears of the given module and
+// pressure angle (radians), centred in a bar `length` long. The pitch line
+// is y = 0, teeth reach ADDENDUM modules above it and roots DEDENDUM below,
+// and tooth and space are each half the circular pitch wide on it.
+pub fn create_rack_profile(module: f64, teeth: u32, pressure_angle: f64, length: f64) -> Result<Polygon, CadError> {
+    positive(module, "module")?;
+    if teeth == 0 {
+        return Err(CadError::InvalidInput("a rack needs at least 1 tooth".into()));
+    }
+    if !(pressure_angle > 0.0 && pressure_angle < PI / 4.0) {
+        return Err(CadError::InvalidInput(format!("pressure angle {} must be between 0 and π/4", pressure_angle)));
+    }
+    let pitch = circular_pitch(module);
+    let (tip, root) = (ADDENDUM * module, DEDENDUM * module);
+    let slope = pressure_angle.tan();
+    let tip_half = pitch / 4.0 - tip * slope;
+    let root_half = pitch / 4.0 + root * slope;
+    if tip_half <= 0.0 {
+        return Err(CadError::InvalidInput(format!("tooth tip width comes to {} at pressure angle {}", 2.0 * tip_half, pressure_angle)));
+    }
+    if pitch - 2.0 * root_half <= 0.0 {
+        return Err(CadError::InvalidInput(format!("root gap comes to {} at pressure angle {}", pitch - 2.0 * root_half, pressure_angle)));
+    }
+    let toothed = teeth as f64 * pitch;
+    if length.is_nan() || length < toothed {
+        return Err(CadError::InvalidInput(format!("rack length {} is shorter than the {} its teeth take", length, toothed)));
+    }
+    let start = (length - toothed) / 2.0;
+    let bottom = -(root + RACK_BODY * module);
+    let mut vertices = vec![Point::new(0.0, bottom), Point::new(length, bottom), Point::new(length, -root)];
+    for k in (0..teeth).rev() {
+        let centre = start + (k as f64 + 0.5) * pitch;
+        vertices.extend([
+            Point::new(centre + root_half, -root),
+            Point::new(centre + tip_half, tip),
+            Point::new(centre - tip_half, tip),
+            Point::new(centre - root_half, -root),
+        ]);
+    }
+    vertices.push(Point::new(0.0, -root));
+    let rack = Polygon::new(vertices);
+    rack.validate().map_err(CadError::Geometry)?;
+    Ok(rack)
+}
+
+// `limits` is { min_riser, max_riser, min_tread, max_tread }, any of them
+// null to leave unchecked, or undefined for the defaults; returns
+// { points, closed }
+#[wasm_bindgen(js_name = create_steps)]
+pub fn create_steps_js(total_run: f64, total_rise: f64, step_count: u32, nosing: f64, limits: &JsValue) -> Result<JsValue, JsValue> {
+    let limits: StepLimits = crate::options_from(limits)?;
+    crate::to_js(&create_steps(total_run, total_rise, step_count, nosing, &limits).map_err(js_error)?)
+}
+
+#[wasm_bindgen(js_name = create_comb)]
+pub fn create_comb_js(teeth: u32, tooth_width: f64, gap_width: f64, depth: f64, base_height: f64) -> Result<JsValue, JsValue> {
+    crate::to_js(&create_comb(teeth, tooth_width, gap_width, depth, base_height).map_err(js_error)?)
+}
+
+#[wasm_bindgen(js_name = create_rack_profile)]
+pub fn create_rack_profile_js(module: f64, teeth: u32, pressure_angle: f64, length: f64) -> Result<JsValue, JsValue> {
+    crate::to_js(&create_rack_profile(module, teeth, pressure_angle, length).map_err(js_error)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_steps_divide_run_and_rise() {
+        let steps = create_steps(2800.0, 1800.0, 10, 25.0, &StepLimits::default()).unwrap();
+        assert_eq!(steps.points.len(), 21);
+        assert_eq!(steps.points[0], Point::new(0.0, 0.0));
+        assert_eq!(*steps.points.last().unwrap(), Point::new(2800.0, 1800.0));
+        for k in 0..10 {
+            let (foot, nose, back) = (steps.points[2 * k], steps.points[2 * k + 1], steps.points[2 * k + 2]);
+            assert!((nose.y - foot.y - 180.0).abs() < 1e-9 && (foot.x - nose.x - 25.0).abs() < 1e-9);
+            assert!(nose.y == back.y && (back.x - foot.x - 280.0).abs() < 1e-9);
+        }
+        // Too few steps makes the risers too tall for the code
+        let err = create_steps(2800.0, 1800.0, 9, 25.0, &StepLimits::default()).unwrap_err();
+        assert!(err.to_string().contains("exceeds the maximum riser"), "{}", err);
+        assert!(create_steps(2800.0, 1800.0, 9, 25.0, &StepLimits { max_riser: None, ..StepLimits::default() }).is_ok());
+        assert!(create_steps(2800.0, 1800.0, 10, 280.0, &StepLimits::default()).unwrap_err().to_string().contains("nosing"));
+    }
+
+    #[test]
+    fn test_comb_area_and_rack_meshes_with_gear() {
+        let comb = create_comb(5, 3.0, 2.0, 10.0, 4.0).unwrap();
+        let width = 5.0 * 3.0 + 4.0 * 2.0;
+        assert!((comb.area() - (width * 4.0 + 5.0 * 3.0 * 10.0)).abs() < 1e-9);
+        assert_eq!(comb.vertices.len(), 2 + 4 * 5 - 2);
+        assert!(create_comb(5, 3.0, 0.0, 10.0, 4.0).unwrap_err().to_string().contains("gap width"));
+
+        let (module, pressure_angle) = (2.5, 20f64.to_radians());
+        let rack = create_rack_profile(module, 6, pressure_angle, 60.0).unwrap();
+        // Left tip corners of neighbouring teeth, one pitch apart
+        let tips: Vec<f64> = rack.vertices.iter().filter(|p| p.y == module).map(|p| p.x).collect();
+        assert_eq!(tips.len(), 12);
+        // Circular pitch of any gear of the same module: circumference of
+        // the pitch circle, diameter module * teeth, over the teeth
+        let gear_teeth = 17.0;
+        let gear_pitch = PI * module * gear_teeth / gear_teeth;
+        for pair in tips.windows(4).step_by(2) {
+            assert!(((pair[1] - pair[3]) - gear_pitch).abs() < 1e-9);
+        }
+        assert!((circular_pitch(module) - gear_pitch).abs() < 1e-9);
+        assert!(create_rack_profile(module, 6, pressure_angle, 40.0).unwrap_err().to_string().contains("rack length"));
+        assert!(create_rack_profile(module, 6, 0.7, 60.0).unwrap_err().to_string().contains("tooth tip width"));
+    }
+}
@@ -0,0 +1,148 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{Point, Polygon, Vector};
+
+fn dot(p: &Point, v: &Vector) -> f64 {
+    p.x * v.x + p.y * v.y
+}
+
+// Occupied stretches of the line `height` across `n` at (in `d` coordinates),
+// by even-odd rule, touching stretches joined
+fn scan(vertices: &[Point], d: &Vector, n: &Vector, height: f64) -> Vec<(f64, f64)> {
+    let mut crossings = Vec::new();
+    for i in 0..vertices.len() {
+        let (a, b) = (&vertices[i], &vertices[(i + 1) % vertices.len()]);
+        let (ha, hb) = (dot(a, n), dot(b, n));
+        if (ha > height) != (hb > height) {
+            let t = (height - ha) / (hb - ha);
+            crossings.push(dot(a, d) + t * (dot(b, d) - dot(a, d)));
+        }
+    }
+    crossings.sort_by(f64::total_cmp);
+    let mut intervals: Vec<(f64, f64)> = Vec::new();
+    for pair in crossings.chunks_exact(2) {
+        match intervals.last_mut() {
+            Some(last) if last.1 >= pair[0] => last.1 = pair[1],
+            _ => intervals.push((pair[0], pair[1])),
+        }
+    }
+    intervals
+}
+
+impl Polygon {
+    // The polygon's shadow along `direction`, sliced: `samples` lines
+    // running along the direction, spread evenly across the polygon's width
+    // at the middle of equal bands, each with the stretches it runs inside
+    // the polygon. Heights are measured along the direction turned a quarter
+    // turn counter-clockwise, positions along the unit direction, both from
+    // the origin. A zero direction or fewer than three vertices give nothing.
+    pub fn projection_profile(&self, direction: &Vector, samples: u32) -> Vec<(f64, Vec<(f64, f64)>)> {
+        let Some(d) = direction.try_normalize() else {
+            return vec![];
+        };
+        let n = Vector::new(-d.y, d.x);
+        if self.vertices.len() < 3 {
+            return vec![];
+        }
+        let heights = self.vertices.iter().map(|p| dot(p, &n));
+        let (low, high) = heights.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), h| (lo.min(h), hi.max(h)));
+        let band = (high - low) / samples as f64;
+        (0..samples).map(|i| {
+            let height = low + (i as f64 + 0.5) * band;
+            (height, scan(&self.vertices, &d, &n, height))
+        }).collect()
+    }
+
+    // Summary of `projection_profile`: the extent along the direction cut
+    // into `bins` equal bins, each with the share of sample lines occupying
+    // it, weighted by how much of the bin they cover, from 0 to 1
+    pub fn occupancy_histogram(&self, direction: &Vector, samples: u32, bins: usize) -> Vec<f64> {
+        let profile = self.projection_profile(direction, samples);
+        let Some(d) = direction.try_normalize() else {
+            return vec![0.0; bins];
+        };
+        if profile.is_empty() || bins == 0 {
+            return vec![0.0; bins];
+        }
+        let along = self.vertices.iter().map(|p| dot(p, &d));
+        let (start, end) = along.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), a| (lo.min(a), hi.max(a)));
+        let width = (end - start) / bins as f64;
+        let mut histogram = vec![0.0; bins];
+        if width <= 0.0 {
+            return histogram;
+        }
+        for (_, intervals) in &profile {
+            for (k, value) in histogram.iter_mut().enumerate() {
+                let (lo, hi) = (start + k as f64 * width, start + (k + 1) as f64 * width);
+                let covered: f64 = intervals.iter().map(|&(a, b)| (b.min(hi) - a.max(lo)).max(0.0)).sum();
+                *value += covered / width;
+            }
+        }
+        histogram.iter_mut().for_each(|v| *v /= profile.len() as f64);
+        histogram
+    }
+}
+
+// Array of [height, [[from, to], ...]] per sample line
+#[wasm_bindgen]
+pub fn projection_profile(vertices: &JsValue, direction_x: f64, direction_y: f64, samples: u32) -> Result<JsValue, JsValue> {
+    let polygon: Polygon = crate::polygon_from(vertices)?;
+    crate::to_js(&polygon.projection_profile(&Vector::new(direction_x, direction_y), samples))
+}
+
+#[wasm_bindgen]
+pub fn occupancy_histogram(vertices: &JsValue, direction_x: f64, direction_y: f64, samples: u32, bins: usize) -> Result<Vec<f64>, JsValue> {
+    let polygon: Polygon = crate::polygon_from(vertices)?;
+    Ok(polygon.occupancy_histogram(&Vector::new(direction_x, direction_y), samples, bins))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 6 wide, 4 tall, open at the top between x = 2 and x = 4 down to y = 1
+    fn u_shape() -> Polygon {
+        Polygon::new(vec![
+            Point::new(0.0, 0.0), Point::new(6.0, 0.0), Point::new(6.0, 4.0), Point::new(4.0, 4.0),
+            Point::new(4.0, 1.0), Point::new(2.0, 1.0), Point::new(2.0, 4.0), Point::new(0.0, 4.0),
+        ])
+    }
+
+    #[test]
+    fn test_u_shape_splits_across_its_opening() {
+        let profile = u_shape().projection_profile(&Vector::new(1.0, 0.0), 8);
+        assert_eq!(profile.len(), 8);
+        for (height, intervals) in &profile {
+            if *height < 1.0 {
+                assert_eq!(intervals, &vec![(0.0, 6.0)]);
+            } else {
+                assert_eq!(intervals, &vec![(0.0, 2.0), (4.0, 6.0)]);
+            }
+        }
+        assert_eq!(profile[0].0, 0.25);
+        assert_eq!(profile.iter().filter(|(_, i)| i.len() == 2).count(), 6);
+
+        // Along the arms the notch never shows as a gap
+        let down = u_shape().projection_profile(&Vector::new(0.0, -2.0), 6);
+        assert!(down.iter().all(|(_, i)| i.len() == 1));
+        // Heights across (0, -1) run along +x, positions along -y
+        assert_eq!(down[0].0, 0.5);
+        assert_eq!(down[0].1, vec![(-4.0, 0.0)]);
+        assert_eq!(down[2].1, vec![(-1.0, 0.0)]);
+        assert!(u_shape().projection_profile(&Vector::new(0.0, 0.0), 4).is_empty());
+    }
+
+    #[test]
+    fn test_occupancy_histogram() {
+        let histogram = u_shape().occupancy_histogram(&Vector::new(1.0, 0.0), 8, 3);
+        assert_eq!(histogram.len(), 3);
+        assert_eq!(histogram[0], 1.0);
+        assert_eq!(histogram[2], 1.0);
+        assert_eq!(histogram[1], 0.25);
+        // A rotated copy gives the same summary along the rotated direction
+        let turned = u_shape().transform(&crate::Matrix::rotate(0.6));
+        let rotated = turned.occupancy_histogram(&Vector::new(0.6f64.cos(), 0.6f64.sin()), 8, 3);
+        assert!(rotated.iter().zip(&histogram).all(|(a, b)| (a - b).abs() < 1e-9));
+        assert_eq!(Polygon::new(vec![]).occupancy_histogram(&Vector::new(1.0, 0.0), 8, 2), vec![0.0, 0.0]);
+    }
+}
@@ -0,0 +1,232 @@
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize, Serializer};
+use wasm_bindgen::prelude::*;
+
+use crate::{js_error, CadError, Document, ShapeId};
+
+// One edit to a shape
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ProvenanceStamp {
+    // The document's edit count when the stamp was made: each stamp takes
+    // the next number
+    pub revision: u64,
+    pub author: Option<String>,
+    // What the document's clock read, e.g. milliseconds from JS; 0 without
+    // a clock
+    pub time: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Provenance {
+    pub created: ProvenanceStamp,
+    pub modified: ProvenanceStamp,
+}
+
+// Time source, since WASM has no clock of its own
+#[derive(Clone, Default)]
+struct Clock(Option<Rc<dyn Fn() -> f64>>);
+
+impl std::fmt::Debug for Clock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(if self.0.is_some() { "Clock(set)" } else { "Clock(none)" })
+    }
+}
+
+// Who made and last changed each shape, while tracking is on. Records are
+// kept outside the shapes, so content hashes don't see them, and are saved
+// with the document only when asked.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub(crate) struct ProvenanceLog {
+    tracking: bool,
+    saved: bool,
+    revision: u64,
+    records: BTreeMap<ShapeId, Provenance>,
+    #[serde(skip)]
+    author: Option<String>,
+    #[serde(skip)]
+    clock: Clock,
+}
+
+impl ProvenanceLog {
+    // Written out as an empty log unless saving was asked for, keeping the
+    // field count fixed for the binary format
+    pub(crate) fn serialize_saved<S: Serializer>(log: &ProvenanceLog, serializer: S) -> Result<S::Ok, S::Error> {
+        if log.saved {
+            log.serialize(serializer)
+        } else {
+            ProvenanceLog::default().serialize(serializer)
+        }
+    }
+
+    // Records an edit to `id`, its creation if it has no record yet
+    pub(crate) fn stamp(&mut self, id: ShapeId) {
+        if !self.tracking {
+            return;
+        }
+        self.revision += 1;
+        let stamp = ProvenanceStamp { revision: self.revision, author: self.author.clone(), time: self.clock.0.as_ref().map_or(0.0, |now| now()) };
+        match self.records.get_mut(&id) {
+            Some(record) => record.modified = stamp,
+            None => {
+                self.records.insert(id, Provenance { created: stamp.clone(), modified: stamp });
+            }
+        }
+    }
+
+    pub(crate) fn forget(&mut self, id: ShapeId) {
+        self.records.remove(&id);
+    }
+
+    // Takes on `earlier`'s records, as after an undo back to it, keeping the
+    // session's author and clock and never counting revisions backwards
+    pub(crate) fn restore(&mut self, earlier: ProvenanceLog) {
+        let revision = self.revision.max(earlier.revision);
+        *self = ProvenanceLog { revision, author: self.author.take(), clock: std::mem::take(&mut self.clock), ..earlier };
+    }
+}
+
+impl Document {
+    // Starts stamping shapes as they are added and edited; with `save` the
+    // records are written out with the document. Turning tracking off keeps
+    // the records made so far.
+    pub fn set_provenance_tracking(&mut self, enabled: bool, save: bool) {
+        self.provenance.tracking = enabled;
+        self.provenance.saved = save;
+    }
+
+    // Author of edits from now on, for this session; not saved
+    pub fn set_author(&mut self, name: Option<String>) {
+        self.provenance.author = name;
+    }
+
+    pub fn set_clock(&mut self, clock: impl Fn() -> f64 + 'static) {
+        self.provenance.clock = Clock(Some(Rc::new(clock)));
+    }
+
+    // Revision of the latest stamp
+    pub fn revision(&self) -> u64 {
+        self.provenance.revision
+    }
+
+    // None for shapes not stamped since tracking began
+    pub fn provenance(&self, id: ShapeId) -> Result<Option<&Provenance>, CadError> {
+        self.shape(id)?;
+        Ok(self.provenance.records.get(&id))
+    }
+
+    // Shapes added or edited after `revision`, in ascending id order
+    pub fn shapes_modified_since(&self, revision: u64) -> Vec<ShapeId> {
+        self.provenance.records.iter().filter(|(_, p)| p.modified.revision > revision).map(|(&id, _)| id).collect()
+    }
+}
+
+#[wasm_bindgen]
+impl Document {
+    #[wasm_bindgen(js_name = set_provenance_tracking)]
+    pub fn set_provenance_tracking_js(&mut self, enabled: bool, save: bool) {
+        self.set_provenance_tracking(enabled, save)
+    }
+
+    #[wasm_bindgen(js_name = set_author)]
+    pub fn set_author_js(&mut self, name: Option<String>) {
+        self.set_author(name)
+    }
+
+    // `clock` is called with no arguments for each stamp and should return
+    // a number that never goes down, e.g. () => Date.now()
+    #[wasm_bindgen(js_name = set_clock)]
+    pub fn set_clock_js(&mut self, clock: js_sys::Function) {
+        self.set_clock(move || clock.call0(&JsValue::NULL).ok().and_then(|v| v.as_f64()).unwrap_or(0.0))
+    }
+
+    #[wasm_bindgen(js_name = revision)]
+    pub fn revision_js(&self) -> u64 {
+        self.revision()
+    }
+
+    // { created: { revision, author, time }, modified } or null
+    #[wasm_bindgen(js_name = provenance)]
+    pub fn provenance_js(&self, id: ShapeId) -> Result<JsValue, JsValue> {
+        crate::to_js(&self.provenance(id).map_err(js_error)?)
+    }
+
+    #[wasm_bindgen(js_name = shapes_modified_since)]
+    pub fn shapes_modified_since_js(&self, revision: u64) -> Vec<ShapeId> {
+        self.shapes_modified_since(revision)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::binary::{from_bytes, to_bytes};
+    use crate::{Matrix, Point, Polygon};
+
+    fn stamp(doc: &Document, id: ShapeId) -> (u64, u64, Option<String>, Option<String>) {
+        let p = doc.provenance(id).unwrap().unwrap();
+        (p.created.revision, p.modified.revision, p.created.author.clone(), p.modified.author.clone())
+    }
+
+    #[test]
+    fn test_two_authors_save_load_and_undo() {
+        let mut doc = Document::new();
+        let square = Polygon::new(vec![Point::new(0.0, 0.0), Point::new(1.0, 0.0), Point::new(1.0, 1.0), Point::new(0.0, 1.0)]);
+        let untracked = doc.add_shape(square.clone());
+        doc.set_provenance_tracking(true, true);
+        let ticks = Rc::new(Cell::new(0.0));
+        let clock = Rc::clone(&ticks);
+        doc.set_clock(move || {
+            clock.set(clock.get() + 10.0);
+            clock.get()
+        });
+        let hash = doc.content_hash_all();
+
+        doc.set_author(Some("ana".to_string()));
+        let a = doc.add_shape(square.clone());
+        doc.set_author(Some("ben".to_string()));
+        let b = doc.add_shape(square.clone());
+        doc.set_transform(a, Matrix::translate(2.0, 0.0)).unwrap();
+        doc.set_author(Some("ana".to_string()));
+        doc.set_transform(b, Matrix::translate(0.0, 2.0)).unwrap();
+
+        let some = |s: &str| Some(s.to_string());
+        assert_eq!(stamp(&doc, a), (1, 3, some("ana"), some("ben")));
+        assert_eq!(stamp(&doc, b), (2, 4, some("ben"), some("ana")));
+        assert_eq!(doc.provenance(b).unwrap().unwrap().modified.time, 40.0);
+        assert_eq!(doc.provenance(untracked).unwrap(), None);
+        assert_eq!(doc.shapes_modified_since(3), vec![b]);
+        assert_eq!(doc.shapes_modified_since(0), vec![a, b]);
+        // Records live outside the shapes' content
+        let mut plain = doc.clone();
+        plain.provenance = ProvenanceLog::default();
+        assert_eq!(plain.content_hash_all(), doc.content_hash_all());
+        assert_ne!(hash, doc.content_hash_all());
+
+        let loaded: Document = from_bytes(&to_bytes(&doc).unwrap()).unwrap();
+        assert_eq!(stamp(&loaded, a), stamp(&doc, a));
+        assert_eq!(stamp(&loaded, b), stamp(&doc, b));
+        assert_eq!(loaded.revision(), 4);
+        doc.set_provenance_tracking(true, false);
+        let loaded: Document = from_bytes(&to_bytes(&doc).unwrap()).unwrap();
+        assert_eq!(loaded.provenance(a).unwrap(), None);
+        assert_eq!(loaded.revision(), 0);
+
+        // Undo brings back the earlier stamp rather than making a new one
+        doc.checkpoint();
+        doc.set_author(Some("ben".to_string()));
+        doc.set_transform(a, Matrix::translate(5.0, 5.0)).unwrap();
+        let c = doc.add_shape(square);
+        assert_eq!(stamp(&doc, a), (1, 5, some("ana"), some("ben")));
+        assert!(doc.undo());
+        assert_eq!(stamp(&doc, a), (1, 3, some("ana"), some("ben")));
+        assert!(doc.shape(c).is_err());
+        assert_eq!(doc.shapes_modified_since(4), Vec::<ShapeId>::new());
+        assert_eq!(doc.revision(), 6);
+        assert_eq!(ticks.get(), 60.0);
+        assert!(!doc.undo());
+    }
+}
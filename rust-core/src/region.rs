@@ -0,0 +1,335 @@
+use std::f64::consts::TAU;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::predicates::{orient2d, point_in_ring, ring_signed_area};
+use crate::{Arc, Point, Polygon, PolygonWithHoles, Polyline, Segment, EPSILON};
+
+// Segments per full turn when flattening arcs
+const ARC_SEGMENTS: usize = 64;
+
+// A selectable curve. From JS this is the externally tagged form, e.g.
+// { Arc: { center, radius, start_angle, sweep } }.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum Entity {
+    Segment(Segment),
+    Arc(Arc),
+    Polyline(Polyline),
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct RegionGap {
+    // Midpoint between the two loose ends
+    pub at: Point,
+    pub size: f64,
+    pub from: Point,
+    pub to: Point,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum RegionError {
+    // Nothing in the selection bounds an area
+    Empty,
+    // Curve ends with nothing to join, paired with the nearest other loose end
+    Gaps(Vec<RegionGap>),
+    // More than two curve ends meet here
+    Branch { at: Point, ends: usize },
+    // Curves cross or run over each other here
+    Overlap { at: Point },
+    // The loops bound this many separate areas rather than one
+    Disjoint(usize),
+}
+
+impl fmt::Display for RegionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegionError::Empty => write!(f, "selection bounds no area"),
+            RegionError::Gaps(gaps) => {
+                write!(f, "{} gap(s) in selection", gaps.len())?;
+                for g in gaps {
+                    write!(f, "; {} at ({}, {})", g.size, g.at.x, g.at.y)?;
+                }
+                Ok(())
+            }
+            RegionError::Branch { at, ends } => write!(f, "{} curve ends meet at ({}, {})", ends, at.x, at.y),
+            RegionError::Overlap { at } => write!(f, "curves overlap at ({}, {})", at.x, at.y),
+            RegionError::Disjoint(count) => write!(f, "selection bounds {} separate areas", count),
+        }
+    }
+}
+
+impl std::error::Error for RegionError {}
+
+fn distance(a: &Point, b: &Point) -> f64 {
+    (b.x - a.x).hypot(b.y - a.y)
+}
+
+// Points along the entity, with the ends first and last; a closed polyline
+// repeats its first point at the end
+fn flatten(entity: &Entity) -> Vec<Point> {
+    match entity {
+        Entity::Segment(s) => vec![s.a, s.b],
+        Entity::Arc(arc) => {
+            let steps = ((arc.sweep.abs() / TAU * ARC_SEGMENTS as f64).ceil() as usize).max(1);
+            (0..=steps).map(|k| arc.point_at(arc.start_angle + arc.sweep * k as f64 / steps as f64)).collect()
+        }
+        Entity::Polyline(p) => {
+            let mut points = p.points.clone();
+            if p.closed && !points.is_empty() {
+                points.push(points[0]);
+            }
+            points
+        }
+    }
+}
+
+// Joins chains end to end into closed loops. Ends within `tolerance` of each
+// other meet; every meeting point must join exactly two ends.
+fn chain_loops(chains: &[Vec<Point>], tolerance: f64) -> Result<Vec<Vec<Point>>, RegionError> {
+    // End 2c is the start of chain c and 2c + 1 its end
+    let ends: Vec<Point> = chains.iter().flat_map(|c| [c[0], c[c.len() - 1]]).collect();
+    let mut node: Vec<usize> = (0..ends.len()).collect();
+    for i in 0..ends.len() {
+        for j in 0..i {
+            if distance(&ends[i], &ends[j]) <= tolerance {
+                node[i] = node[j];
+                break;
+            }
+        }
+    }
+    let mut at_node: Vec<Vec<usize>> = vec![Vec::new(); ends.len()];
+    for (end, &n) in node.iter().enumerate() {
+        at_node[n].push(end);
+    }
+
+    let loose: Vec<usize> = at_node.iter().filter(|e| e.len() == 1).map(|e| e[0]).collect();
+    if !loose.is_empty() {
+        return Err(RegionError::Gaps(pair_gaps(&loose, &ends)));
+    }
+    if let Some(e) = at_node.iter().find(|e| e.len() > 2) {
+        return Err(RegionError::Branch { at: ends[e[0]], ends: e.len() });
+    }
+
+    let mut used = vec![false; chains.len()];
+    let mut loops = Vec::new();
+    for first in 0..chains.len() {
+        if used[first] {
+            continue;
+        }
+        let mut ring: Vec<Point> = Vec::new();
+        let mut end = 2 * first;
+        loop {
+            let c = end / 2;
+            used[c] = true;
+            let mut points = chains[c].clone();
+            if end % 2 == 1 {
+                points.reverse();
+            }
+            // The joining point comes from the previous chain
+            ring.extend(points.iter().skip(if ring.is_empty() { 0 } else { 1 }));
+            let exit = end ^ 1;
+            let next = at_node[node[exit]].iter().copied().find(|&e| e != exit).unwrap();
+            if next / 2 == first {
+                break;
+            }
+            end = next;
+        }
+        ring.pop();
+        loops.push(ring);
+    }
+    Ok(loops)
+}
+
+// Each loose end with its nearest unpaired loose end, closest pairs first
+fn pair_gaps(loose: &[usize], ends: &[Point]) -> Vec<RegionGap> {
+    let mut pairs: Vec<(f64, usize, usize)> = Vec::new();
+    for (i, &a) in loose.iter().enumerate() {
+        for &b in &loose[i + 1..] {
+            pairs.push((distance(&ends[a], &ends[b]), a, b));
+        }
+    }
+    pairs.sort_by(|x, y| x.0.total_cmp(&y.0));
+    let mut paired = vec![false; ends.len()];
+    let mut gaps = Vec::new();
+    for (size, a, b) in pairs {
+        if paired[a] || paired[b] {
+            continue;
+        }
+        paired[a] = true;
+        paired[b] = true;
+        let (from, to) = (ends[a], ends[b]);
+        gaps.push(RegionGap { at: Point::new((from.x + to.x) / 2.0, (from.y + to.y) / 2.0), size, from, to });
+    }
+    gaps
+}
+
+// Where segment ab properly crosses cd, if it does
+pub(crate) fn crossing(a: &Point, b: &Point, c: &Point, d: &Point) -> Option<Point> {
+    let (o1, o2) = (orient2d(a, b, c), orient2d(a, b, d));
+    let (o3, o4) = (orient2d(c, d, a), orient2d(c, d, b));
+    if o1 * o2 < 0.0 && o3 * o4 < 0.0 {
+        let t = o1 / (o1 - o2);
+        Some(Point::new(c.x + (d.x - c.x) * t, c.y + (d.y - c.y) * t))
+    } else {
+        None
+    }
+}
+
+// First crossing between edges of the loops, skipping an edge against itself
+// and its neighbours
+fn first_crossing(loops: &[Vec<Point>]) -> Option<Point> {
+    let edges: Vec<(usize, usize, Point, Point)> = loops.iter().enumerate()
+        .flat_map(|(l, r)| (0..r.len()).map(move |i| (l, i, r[i], r[(i + 1) % r.len()])))
+        .collect();
+    for (i, e) in edges.iter().enumerate() {
+        for f in &edges[i + 1..] {
+            let n = loops[e.0].len();
+            if e.0 == f.0 && (f.1 == e.1 + 1 || (e.1 == 0 && f.1 == n - 1)) {
+                continue;
+            }
+            if let Some(p) = crossing(&e.2, &e.3, &f.2, &f.3) {
+                return Some(p);
+            }
+        }
+    }
+    None
+}
+
+// Region bounded by the selected curves: one outer loop and the loops it
+// encloses as holes. Outer comes out counter-clockwise, holes clockwise.
+pub fn make_region(entities: &[Entity], tolerance: f64) -> Result<PolygonWithHoles, RegionError> {
+    let tolerance = tolerance.max(EPSILON);
+    let chains: Vec<Vec<Point>> = entities.iter().map(flatten).filter(|c| c.len() >= 2).collect();
+    if chains.is_empty() {
+        return Err(RegionError::Empty);
+    }
+    let loops = chain_loops(&chains, tolerance)?;
+    for ring in &loops {
+        // A loop of curves doubling back over each other encloses nothing
+        if ring.len() < 3 || ring_signed_area(ring).abs() <= tolerance * tolerance {
+            return Err(RegionError::Overlap { at: ring[0] });
+        }
+    }
+    if let Some(at) = first_crossing(&loops) {
+        return Err(RegionError::Overlap { at });
+    }
+
+    // Loops don't cross, so one vertex tells whether a loop is inside another
+    let inside = |inner: &Vec<Point>, outer: &Vec<Point>| !std::ptr::eq(inner, outer) && point_in_ring(outer, &inner[0]);
+    let outers: Vec<&Vec<Point>> = loops.iter().filter(|l| !loops.iter().any(|o| inside(l, o))).collect();
+    if outers.len() > 1 {
+        return Err(RegionError::Disjoint(outers.len()));
+    }
+    let depth = |l: &Vec<Point>| loops.iter().filter(|o| inside(l, o)).count();
+    // An island inside a hole is a second area
+    if loops.iter().any(|l| depth(l) > 1) {
+        return Err(RegionError::Disjoint(1 + loops.iter().filter(|l| depth(l) == 2).count()));
+    }
+
+    let oriented = |ring: &Vec<Point>, ccw: bool| {
+        let mut ring = ring.clone();
+        if (ring_signed_area(&ring) > 0.0) != ccw {
+            ring.reverse();
+        }
+        Polygon::new(ring)
+    };
+    let holes = loops.iter().filter(|l| depth(l) == 1).map(|l| oriented(l, false)).collect();
+    Ok(PolygonWithHoles::new(oriented(outers[0], true), holes))
+}
+
+// Entities from JS, arc angles in the caller's convention
+pub(crate) fn entities_from(entities: &JsValue) -> Result<Vec<Entity>, JsValue> {
+    let entities: Vec<Entity> = crate::from_js(entities)?;
+    Ok(entities.into_iter().map(|e| match e {
+        Entity::Arc(arc) => Entity::Arc(arc.user()),
+        other => other,
+    }).collect())
+}
+
+#[wasm_bindgen(js_name = make_region)]
+pub fn make_region_js(entities: &JsValue, tolerance: f64) -> Result<JsValue, JsValue> {
+    let entities = entities_from(entities)?;
+    match make_region(&entities, tolerance) {
+        Ok(region) => crate::to_js(&region),
+        // Structured, so the UI can highlight gaps and junctions
+        Err(e) => Err(crate::to_js(&e)?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::{FRAC_PI_2, PI};
+
+    use super::*;
+    use crate::Document;
+
+    fn stadium(bottom_end: f64) -> Vec<Entity> {
+        vec![
+            Entity::Segment(Segment::new(Point::new(0.0, -1.0), Point::new(bottom_end, -1.0))),
+            Entity::Arc(Arc { center: Point::new(4.0, 0.0), radius: 1.0, start_angle: -FRAC_PI_2, sweep: PI }),
+            // Drawn against the direction of travel
+            Entity::Segment(Segment::new(Point::new(0.0, 1.0), Point::new(4.0, 1.0))),
+            Entity::Arc(Arc { center: Point::new(0.0, 0.0), radius: 1.0, start_angle: FRAC_PI_2, sweep: PI }),
+        ]
+    }
+
+    // A full turn flattened at ARC_SEGMENTS
+    fn flattened_circle_area(radius: f64) -> f64 {
+        ARC_SEGMENTS as f64 / 2.0 * radius * radius * (TAU / ARC_SEGMENTS as f64).sin()
+    }
+
+    #[test]
+    fn test_stadium_region_and_hole() {
+        let region = make_region(&stadium(4.0), 1e-6).unwrap();
+        assert!((region.area() - (8.0 + flattened_circle_area(1.0))).abs() < 1e-9);
+        assert!(ring_signed_area(&region.outer.vertices) > 0.0);
+
+        let mut selection = stadium(4.0);
+        selection.push(Entity::Arc(Arc { center: Point::new(2.0, 0.0), radius: 0.5, start_angle: 0.0, sweep: -TAU }));
+        let region = make_region(&selection, 1e-6).unwrap();
+        assert_eq!(region.holes.len(), 1);
+        assert!(ring_signed_area(&region.holes[0].vertices) < 0.0);
+        assert!((region.area() - (8.0 + flattened_circle_area(1.0) - flattened_circle_area(0.5))).abs() < 1e-9);
+
+        let mut doc = Document::new();
+        let id = doc.create_region(&selection, 1e-6).unwrap();
+        assert_eq!(doc.shape_count(), 1);
+        let shape = doc.shape(id).unwrap();
+        assert!((shape.geometry.area() - region.area()).abs() < 1e-9);
+        assert!(doc.hit_test_all(&Point::new(2.0, 0.0)).is_empty() && doc.hit_test_all(&Point::new(2.0, 0.75)) == vec![id]);
+        let filled: f64 = doc.tessellate().chunks(6).map(|t| ((t[2] - t[0]) as f64 * (t[5] - t[1]) as f64 - (t[4] - t[0]) as f64 * (t[3] - t[1]) as f64).abs() / 2.0).sum();
+        assert!((filled - region.area()).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_gap_and_junction_errors() {
+        match make_region(&stadium(3.9), 1e-6) {
+            Err(RegionError::Gaps(gaps)) => {
+                assert_eq!(gaps.len(), 1);
+                assert!((gaps[0].size - 0.1).abs() < 1e-9);
+                assert!(distance(&gaps[0].at, &Point::new(3.95, -1.0)) < 1e-9);
+            }
+            other => panic!("expected a gap, got {:?}", other),
+        }
+        // Within tolerance the gap closes
+        assert!(make_region(&stadium(3.9), 0.11).is_ok());
+
+        let mut branching = stadium(4.0);
+        branching.push(Entity::Segment(Segment::new(Point::new(4.0, 1.0), Point::new(2.0, 0.0))));
+        branching.push(Entity::Segment(Segment::new(Point::new(2.0, 0.0), Point::new(4.0, -1.0))));
+        match make_region(&branching, 1e-6) {
+            Err(RegionError::Branch { at, ends }) => {
+                assert_eq!(ends, 3);
+                assert!(distance(&at, &Point::new(4.0, -1.0)) < 1e-6 || distance(&at, &Point::new(4.0, 1.0)) < 1e-6);
+            }
+            other => panic!("expected a branch, got {:?}", other),
+        }
+
+        let mut doubled = stadium(4.0);
+        doubled.push(Entity::Segment(Segment::new(Point::new(5.0, -2.0), Point::new(6.0, 3.0))));
+        doubled.push(Entity::Segment(Segment::new(Point::new(6.0, 3.0), Point::new(5.0, -2.0))));
+        assert!(matches!(make_region(&doubled, 1e-6), Err(RegionError::Overlap { .. })));
+    }
+}
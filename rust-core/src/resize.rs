@@ -0,0 +1,177 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{FeatureRef, Point, Polygon, PolygonWithHoles, Vector, EPSILON};
+
+// One-axis coordinate mapping used by the resize
+enum AxisMap {
+    // Coordinates up to `start` stay put, those past `end` shift by `delta`,
+    // and the vertex-free band in between stretches to absorb the change
+    Slice { start: f64, end: f64, delta: f64 },
+    // Plain scaling about `origin`
+    Proportional { origin: f64, scale: f64 },
+}
+
+impl AxisMap {
+    fn apply(&self, v: f64) -> f64 {
+        match *self {
+            AxisMap::Slice { start, end, delta } => {
+                if v <= start {
+                    v
+                } else if v >= end {
+                    v + delta
+                } else {
+                    start + (v - start) * (end - start + delta) / (end - start)
+                }
+            }
+            AxisMap::Proportional { origin, scale } => origin + (v - origin) * scale,
+        }
+    }
+}
+
+// Picks the widest band along one axis that contains no outline vertex and is
+// only crossed by edges parallel to the axis. Stretching inside that band just
+// lengthens straight edges, leaving every corner region rigid.
+fn slice_axis(outer: &Polygon, horizontal: bool, old_size: f64, new_size: f64) -> Option<AxisMap> {
+    let coord = |p: &Point| if horizontal { p.x } else { p.y };
+    let across = |p: &Point| if horizontal { p.y } else { p.x };
+    let delta = new_size - old_size;
+
+    let mut values: Vec<f64> = outer.vertices.iter().map(coord).collect();
+    values.sort_by(f64::total_cmp);
+    values.dedup_by(|a, b| (*a - *b).abs() < EPSILON);
+
+    let n = outer.vertices.len();
+    let mut best: Option<(f64, f64)> = None;
+    for w in values.windows(2) {
+        let (start, end) = (w[0], w[1]);
+        if end - start + delta <= EPSILON {
+            continue;
+        }
+
+        let mid = (start + end) / 2.0;
+        let straight = (0..n).all(|i| {
+            let a = outer.vertices[i];
+            let b = outer.vertices[(i + 1) % n];
+            let crosses = coord(&a).min(coord(&b)) < mid && coord(&a).max(coord(&b)) > mid;
+            !crosses || (across(&a) - across(&b)).abs() < EPSILON
+        });
+
+        if straight && best.is_none_or(|(s, e)| end - start > e - s) {
+            best = Some((start, end));
+        }
+    }
+
+    best.map(|(start, end)| AxisMap::Slice { start, end, delta })
+}
+
+fn map_polygon(polygon: &Polygon, mx: &AxisMap, my: &AxisMap) -> Polygon {
+    Polygon::new(polygon.vertices.iter().map(|p| Point::new(mx.apply(p.x), my.apply(p.y))).collect())
+}
+
+// 9-slice style resize: for rectilinear-ish outlines the corner regions move
+// rigidly and only straight edge runs stretch, so fillets and notches keep
+// their size. Outlines without a clean stretch band on an axis fall back to
+// proportional scaling on that axis. Holes listed in `fixed_features` keep
+// their exact shape and are translated so their bbox center follows the
+// mapping; all other holes are mapped like the outline.
+pub fn parametric_resize(shape: &PolygonWithHoles, old_bbox_size: &Vector, new_bbox_size: &Vector, fixed_features: &[FeatureRef]) -> PolygonWithHoles {
+    let origin = shape.outer.bounding_box().min;
+    let axis = |horizontal: bool, old: f64, new: f64, origin: f64| {
+        slice_axis(&shape.outer, horizontal, old, new).unwrap_or(AxisMap::Proportional {
+            origin,
+            scale: if old.abs() > EPSILON { new / old } else { 1.0 },
+        })
+    };
+    let mx = axis(true, old_bbox_size.x, new_bbox_size.x, origin.x);
+    let my = axis(false, old_bbox_size.y, new_bbox_size.y, origin.y);
+
+    let holes = shape.holes.iter().enumerate().map(|(i, hole)| {
+        if fixed_features.contains(&FeatureRef::Hole(i)) {
+            let center = hole.bounding_box().center();
+            let dx = mx.apply(center.x) - center.x;
+            let dy = my.apply(center.y) - center.y;
+            Polygon::new(hole.vertices.iter().map(|p| Point::new(p.x + dx, p.y + dy)).collect())
+        } else {
+            map_polygon(hole, &mx, &my)
+        }
+    }).collect();
+
+    PolygonWithHoles::new(map_polygon(&shape.outer, &mx, &my), holes)
+}
+
+#[wasm_bindgen(js_name = parametric_resize)]
+pub fn parametric_resize_js(shape: &JsValue, old_width: f64, old_height: f64, new_width: f64, new_height: f64, fixed_features: &JsValue) -> Result<JsValue, JsValue> {
+    let shape: PolygonWithHoles = crate::from_js(shape)?;
+    let fixed: Vec<FeatureRef> = crate::from_js(fixed_features)?;
+    let resized = parametric_resize(&shape, &Vector::new(old_width, old_height), &Vector::new(new_width, new_height), &fixed);
+    crate::to_js(&resized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn circle(cx: f64, cy: f64, r: f64, segments: usize) -> Polygon {
+        Polygon::new((0..segments).map(|i| {
+            let t = i as f64 / segments as f64 * std::f64::consts::TAU;
+            Point::new(cx + r * t.cos(), cy + r * t.sin())
+        }).collect())
+    }
+
+    fn rect(x0: f64, y0: f64, x1: f64, y1: f64) -> Polygon {
+        Polygon::new(vec![Point::new(x0, y0), Point::new(x1, y0), Point::new(x1, y1), Point::new(x0, y1)])
+    }
+
+    #[test]
+    fn test_doubling_width_keeps_fixed_holes() {
+        let plate = PolygonWithHoles::new(
+            rect(0.0, 0.0, 10.0, 5.0),
+            vec![circle(2.5, 2.5, 1.0, 32), circle(7.5, 2.5, 1.0, 32)],
+        );
+        let fixed = [FeatureRef::Hole(0), FeatureRef::Hole(1)];
+        let resized = parametric_resize(&plate, &Vector::new(10.0, 5.0), &Vector::new(20.0, 5.0), &fixed);
+
+        let bbox = resized.outer.bounding_box();
+        assert!((bbox.width() - 20.0).abs() < 1e-12);
+        assert!((bbox.height() - 5.0).abs() < 1e-12);
+
+        for (before, after) in plate.holes.iter().zip(&resized.holes) {
+            assert!((before.area() - after.area()).abs() < 1e-12);
+            let c0 = before.bounding_box().center();
+            let c1 = after.bounding_box().center();
+            assert!((c1.x - c0.x * 2.0).abs() < 1e-12);
+            assert!((c1.y - c0.y).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_corner_notch_keeps_its_size() {
+        // 10x5 plate with a 1x1 notch in the top-right corner
+        let outer = Polygon::new(vec![
+            Point::new(0.0, 0.0),
+            Point::new(10.0, 0.0),
+            Point::new(10.0, 4.0),
+            Point::new(9.0, 4.0),
+            Point::new(9.0, 5.0),
+            Point::new(0.0, 5.0),
+        ]);
+        let shape = PolygonWithHoles::new(outer, vec![]);
+        let resized = parametric_resize(&shape, &Vector::new(10.0, 5.0), &Vector::new(16.0, 8.0), &[]);
+
+        let v = &resized.outer.vertices;
+        assert_eq!(v[2], Point::new(16.0, 7.0));
+        assert_eq!(v[3], Point::new(15.0, 7.0));
+        assert_eq!(v[4], Point::new(15.0, 8.0));
+    }
+
+    #[test]
+    fn test_non_rectilinear_falls_back_to_proportional() {
+        let shape = PolygonWithHoles::new(circle(0.0, 0.0, 2.0, 64), vec![circle(0.0, 0.0, 0.5, 16)]);
+        let resized = parametric_resize(&shape, &Vector::new(4.0, 4.0), &Vector::new(8.0, 4.0), &[FeatureRef::Hole(0)]);
+
+        let bbox = resized.outer.bounding_box();
+        assert!((bbox.width() - 8.0).abs() < 1e-9);
+        assert!((resized.holes[0].area() - shape.holes[0].area()).abs() < 1e-12);
+        assert!((resized.holes[0].bounding_box().center().x - 2.0).abs() < 1e-9);
+    }
+}
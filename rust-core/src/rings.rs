@@ -0,0 +1,172 @@
+use wasm_bindgen::prelude::*;
+
+use crate::arrangement::interior_point;
+use crate::hittest::edges;
+use crate::predicates::{point_in_ring, ring_signed_area};
+use crate::segment::SegmentIntersection;
+use crate::{js_error, BoundingBox, CadError, Point, Polygon, PolygonWithHoles, QuadTree};
+
+// A ring wound counter-clockwise, with what containment tests need
+struct Ring {
+    ccw: Vec<Point>,
+    area: f64,
+    bounds: BoundingBox,
+    probe: Point,
+}
+
+fn distance(a: &Point, b: &Point) -> f64 {
+    (a.x - b.x).hypot(a.y - b.y)
+}
+
+// Whether the two outlines cross rather than one lying inside or outside the
+// other. Meeting at points within `tolerance` of a vertex, or running along
+// each other, is touching; an edge crossing another away from both their
+// ends, or vertices of one on both sides of the other, is crossing. Two
+// rings tracing the same outline count as crossing too.
+fn crosses(a: &Ring, b: &Ring, tolerance: f64) -> bool {
+    for e in edges(&a.ccw) {
+        for f in edges(&b.ccw) {
+            if let SegmentIntersection::Point(p) = e.intersect(&f) {
+                if [e.a, e.b, f.a, f.b].iter().all(|q| distance(&p, q) > tolerance) {
+                    return true;
+                }
+            }
+        }
+    }
+    let sides = |ring: &Ring, other: &Ring| {
+        let (mut inside, mut outside) = (false, false);
+        for v in &ring.ccw {
+            if edges(&other.ccw).all(|f| f.distance_to_point(v) > tolerance) {
+                if point_in_ring(&other.ccw, v) { inside = true } else { outside = true }
+            }
+        }
+        (inside, outside)
+    };
+    let (ab, ba) = (sides(a, b), sides(b, a));
+    ab == (true, true) || ba == (true, true) || (ab == (false, false) && ba == (false, false))
+}
+
+// Builds polygons with holes from closed rings given in any order and either
+// winding: a ring inside an even number of others is an outer boundary, one
+// inside an odd number is a hole in the smallest ring around it, so islands
+// in holes become polygons of their own. Outer boundaries come back
+// counter-clockwise and holes clockwise, in the order their outer rings were
+// given. Rings may touch at points; rings that cross are an error naming the
+// first such pair by index, and a ring without area is an error too.
+pub fn assemble_rings(rings: &[Polygon], tolerance: f64) -> Result<Vec<PolygonWithHoles>, CadError> {
+    let tolerance = tolerance.max(0.0);
+    let mut prepared = Vec::with_capacity(rings.len());
+    for (i, ring) in rings.iter().enumerate() {
+        let mut ccw = ring.vertices.clone();
+        if ring_signed_area(&ccw) < 0.0 {
+            ccw.reverse();
+        }
+        let area = ring_signed_area(&ccw);
+        let probe = (ccw.len() >= 3 && area > tolerance * tolerance).then(|| interior_point(&ccw)).flatten()
+            .ok_or_else(|| CadError::Degenerate(format!("ring {} encloses no area", i)))?;
+        prepared.push(Ring { bounds: ring.bounding_box(), ccw, area, probe });
+    }
+    let boxes: Vec<(u32, BoundingBox)> = prepared.iter().enumerate().map(|(i, r)| (i as u32, r.bounds)).collect();
+    let tree = QuadTree::bulk_build(&boxes);
+
+    // Each ring's smallest enclosing ring, from rings whose box holds its box
+    let mut parent: Vec<Option<usize>> = vec![None; prepared.len()];
+    let mut pairs: Vec<(usize, usize)> = Vec::new();
+    for (i, ring) in prepared.iter().enumerate() {
+        let mut near: Vec<usize> = tree.query(&ring.bounds).into_iter().map(|j| j as usize).filter(|&j| j != i).collect();
+        near.sort_unstable();
+        for j in near {
+            if j > i {
+                pairs.push((i, j));
+            }
+            let other = &prepared[j];
+            if other.area > ring.area && other.bounds.contains(&ring.bounds) && point_in_ring(&other.ccw, &ring.probe)
+                && parent[i].is_none_or(|p| other.area < prepared[p].area) {
+                parent[i] = Some(j);
+            }
+        }
+    }
+    pairs.sort_unstable();
+    if let Some(&(i, j)) = pairs.iter().find(|&&(i, j)| crosses(&prepared[i], &prepared[j], tolerance)) {
+        return Err(CadError::CrossingRings(i, j));
+    }
+
+    let mut depth: Vec<usize> = vec![0; prepared.len()];
+    for i in 0..prepared.len() {
+        let mut at = parent[i];
+        while let Some(p) = at {
+            depth[i] += 1;
+            at = parent[p];
+        }
+    }
+    let mut region: Vec<Option<usize>> = vec![None; prepared.len()];
+    let mut regions: Vec<PolygonWithHoles> = Vec::new();
+    for i in (0..prepared.len()).filter(|&i| depth[i].is_multiple_of(2)) {
+        region[i] = Some(regions.len());
+        regions.push(PolygonWithHoles::new(Polygon::new(prepared[i].ccw.clone()), vec![]));
+    }
+    for i in (0..prepared.len()).filter(|&i| !depth[i].is_multiple_of(2)) {
+        let mut cw = prepared[i].ccw.clone();
+        cw.reverse();
+        if let Some(r) = parent[i].and_then(|p| region[p]) {
+            regions[r].holes.push(Polygon::new(cw));
+        }
+    }
+    Ok(regions)
+}
+
+// Array of rings, each an array of {x, y}; returns [{ outer, holes }]
+#[wasm_bindgen(js_name = assemble_rings)]
+pub fn assemble_rings_js(rings: &JsValue, tolerance: f64) -> Result<JsValue, JsValue> {
+    let rings: Vec<Polygon> = crate::from_js(rings)?;
+    crate::to_js(&assemble_rings(&rings, tolerance).map_err(js_error)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(c: f64, r: f64, clockwise: bool) -> Polygon {
+        let mut v = vec![Point::new(c - r, c - r), Point::new(c + r, c - r), Point::new(c + r, c + r), Point::new(c - r, c + r)];
+        if clockwise {
+            v.reverse();
+        }
+        Polygon::new(v)
+    }
+
+    #[test]
+    fn test_target_nests_islands_in_holes() {
+        // Hole-in-island, outer ring, island, hole, in no particular order or
+        // winding
+        let rings = vec![square(0.0, 1.0, false), square(0.0, 4.0, true), square(0.0, 2.0, true), square(0.0, 3.0, false)];
+        let regions = assemble_rings(&rings, 1e-9).unwrap();
+        assert_eq!(regions.len(), 2);
+        let (outer, island) = (&regions[0], &regions[1]);
+        assert_eq!(outer.outer.area(), 64.0);
+        assert_eq!(outer.holes.len(), 1);
+        assert_eq!(outer.holes[0].area(), 36.0);
+        assert_eq!(island.outer.area(), 16.0);
+        assert_eq!(island.holes.len(), 1);
+        assert_eq!(island.holes[0].area(), 4.0);
+        assert!(regions.iter().all(|r| ring_signed_area(&r.outer.vertices) > 0.0 && r.holes.iter().all(|h| ring_signed_area(&h.vertices) < 0.0)));
+        assert!(outer.contains_point(&Point::new(3.5, 0.0)) && !outer.contains_point(&Point::new(2.5, 0.0)));
+        assert!(island.contains_point(&Point::new(1.5, 0.0)) && !island.contains_point(&Point::new(0.5, 0.0)));
+
+        // A diamond in the hole, touching it and the island at a corner each, is
+        // a third polygon
+        let mut touching = rings.clone();
+        touching.push(Polygon::new(vec![Point::new(2.5, 2.5), Point::new(2.0, 2.0), Point::new(2.5, 1.5), Point::new(3.0, 2.0)]));
+        let regions = assemble_rings(&touching, 1e-9).unwrap();
+        assert_eq!(regions.len(), 3);
+        assert_eq!(regions[2].holes.len(), 0);
+        assert_eq!(regions[0].holes.len(), 1);
+    }
+
+    #[test]
+    fn test_crossing_rings_are_rejected() {
+        let rings = vec![square(0.0, 1.0, false), square(10.0, 1.0, false), square(1.0, 1.0, true)];
+        assert_eq!(assemble_rings(&rings, 1e-9).err(), Some(CadError::CrossingRings(0, 2)));
+        assert!(assemble_rings(&[square(0.0, 1.0, false), square(0.0, 1.0, true)], 1e-9).is_err());
+        assert!(matches!(assemble_rings(&[Polygon::new(vec![Point::new(0.0, 0.0), Point::new(1.0, 1.0)])], 1e-9), Err(CadError::Degenerate(_))));
+    }
+}
@@ -0,0 +1,138 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::arrangement::{drop_collinear, heal_gaps, islands, merge_epsilon, scan_point, Arrangement};
+use crate::predicates::point_in_ring;
+use crate::tolerance::edge_rect;
+use crate::{Point, Polygon, PolygonWithHoles, Polyline, Segment};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RoomResult {
+    pub polygon: PolygonWithHoles,
+    pub area: f64,
+    // Around the outline and every hole
+    pub perimeter: f64,
+    // Where to place the room's label: the centroid, or a point inside the
+    // room when the centroid falls outside it
+    pub anchor: Point,
+}
+
+fn centerline_segments(centerlines: &[Polyline]) -> Vec<Segment> {
+    centerlines.iter().flat_map(|line| {
+        let n = line.points.len();
+        let count = if line.closed && n > 2 { n } else { n.saturating_sub(1) };
+        (0..count).map(move |i| Segment::new(line.points[i], line.points[(i + 1) % n]))
+    }).collect()
+}
+
+// Wall of the given thickness along a centerline segment, carried half the
+// thickness past each end so walls meeting at a corner leave no notch
+fn stroke(s: &Segment, thickness: f64) -> Option<Vec<Point>> {
+    let len = s.length();
+    let (ux, uy) = ((s.b.x - s.a.x) / len, (s.b.y - s.a.y) / len);
+    let h = thickness / 2.0;
+    edge_rect(Point::new(s.a.x - ux * h, s.a.y - uy * h), Point::new(s.b.x + ux * h, s.b.y + uy * h), h, h)
+}
+
+fn anchor(room: &PolygonWithHoles, fallback: Option<Point>) -> Point {
+    let weighted = |p: &Polygon, sign: f64| {
+        let (c, a) = (p.centroid(), p.area());
+        (c.x * a * sign, c.y * a * sign, a * sign)
+    };
+    let (x, y, a) = std::iter::once(weighted(&room.outer, 1.0))
+        .chain(room.holes.iter().map(|h| weighted(h, -1.0)))
+        .fold((0.0, 0.0, 0.0), |acc, w| (acc.0 + w.0, acc.1 + w.1, acc.2 + w.2));
+    let centroid = Point::new(x / a, y / a);
+    match fallback {
+        Some(p) if !room.contains_point(&centroid) => p,
+        _ => centroid,
+    }
+}
+
+// Rooms enclosed by walls drawn as centerlines: the bounded areas clear of
+// the stroked, unioned walls. Free wall ends within `tolerance` of another
+// wall are joined, and with `doorway` set, so are gaps up to that width,
+// splitting the rooms either side across the opening; the walls themselves
+// are not extended, so the opening's area goes to the rooms.
+pub fn rooms_from_walls(centerlines: &[Polyline], wall_thickness: f64, tolerance: f64, doorway: Option<f64>) -> Vec<RoomResult> {
+    let lines: Vec<Segment> = centerline_segments(centerlines).into_iter().filter(|s| s.length() > 0.0).collect();
+    let walls: Vec<Vec<Point>> = lines.iter().filter_map(|s| stroke(s, wall_thickness.max(0.0))).collect();
+    let mut segments: Vec<Segment> = walls.iter()
+        .flat_map(|w| (0..w.len()).map(move |i| Segment::new(w[i], w[(i + 1) % w.len()])))
+        .collect();
+    let eps = merge_epsilon(&segments);
+    let bridge = tolerance.max(doorway.unwrap_or(0.0));
+    segments.extend(heal_gaps(&lines, bridge, eps).into_iter().skip(lines.len()));
+
+    let in_wall = |q: &Point| walls.iter().any(|w| point_in_ring(w, q));
+    let arrangement = Arrangement::build(&segments, eps);
+    let faces = arrangement.faces();
+    let islands = islands(&faces);
+    let mut rooms = Vec::new();
+    for (face, islands) in faces.iter().zip(&islands) {
+        if face.area <= eps * eps {
+            continue;
+        }
+        let mut rings = vec![face.ring.as_slice()];
+        rings.extend(islands.iter().map(|f| f.ring.as_slice()));
+        let inside = scan_point(&rings);
+        if inside.is_none_or(|q| in_wall(&q)) {
+            continue;
+        }
+        let outer = Polygon::new(drop_collinear(face.ring.clone(), eps));
+        let holes: Vec<Polygon> = islands.iter().map(|f| Polygon::new(drop_collinear(f.ring.clone(), eps))).collect();
+        let perimeter = outer.perimeter() + holes.iter().map(|h| h.perimeter()).sum::<f64>();
+        let polygon = PolygonWithHoles::new(outer, holes);
+        rooms.push(RoomResult { area: polygon.area(), perimeter, anchor: anchor(&polygon, inside), polygon });
+    }
+    rooms
+}
+
+// `centerlines` is an array of { points: [{x, y}], closed }; `doorway` null
+// leaves openings unbridged
+#[wasm_bindgen(js_name = rooms_from_walls)]
+pub fn rooms_from_walls_js(centerlines: &JsValue, wall_thickness: f64, tolerance: f64, doorway: Option<f64>) -> Result<JsValue, JsValue> {
+    let centerlines: Vec<Polyline> = crate::from_js(centerlines)?;
+    crate::to_js(&rooms_from_walls(&centerlines, wall_thickness, tolerance, doorway))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outline() -> Polyline {
+        Polyline::new(vec![Point::new(0.0, 0.0), Point::new(4.0, 0.0), Point::new(4.0, 3.0), Point::new(0.0, 3.0)], true)
+    }
+
+    fn total_area(rooms: &[RoomResult]) -> f64 {
+        rooms.iter().map(|r| r.area).sum()
+    }
+
+    #[test]
+    fn test_dividing_wall_makes_two_rooms() {
+        let divider = Polyline::new(vec![Point::new(2.0, 0.0), Point::new(2.0, 3.0)], false);
+        let rooms = rooms_from_walls(&[outline(), divider], 0.2, 1e-6, None);
+        assert_eq!(rooms.len(), 2);
+        // Inside the outer walls, less the divider's footprint
+        let clear = 3.8 * 2.8 - 0.2 * 2.8;
+        assert!((total_area(&rooms) - clear).abs() < 1e-9);
+        for room in &rooms {
+            assert!((room.area - 5.04).abs() < 1e-9);
+            assert!((room.perimeter - 2.0 * (1.8 + 2.8)).abs() < 1e-9);
+            assert!(room.polygon.contains_point(&room.anchor));
+        }
+    }
+
+    #[test]
+    fn test_doorway_is_bridged_on_request() {
+        // Stops a metre short of the far wall
+        let divider = Polyline::new(vec![Point::new(2.0, 0.0), Point::new(2.0, 2.0)], false);
+        let lines = [outline(), divider];
+        assert_eq!(rooms_from_walls(&lines, 0.2, 1e-6, None).len(), 1);
+        assert_eq!(rooms_from_walls(&lines, 0.2, 1e-6, Some(0.9)).len(), 1);
+
+        let rooms = rooms_from_walls(&lines, 0.2, 1e-6, Some(1.2));
+        assert_eq!(rooms.len(), 2);
+        assert!((total_area(&rooms) - (3.8 * 2.8 - 0.2 * 2.0)).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,276 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::predicates::compensated_sum;
+use crate::{format_number, js_error, AttributeValue, CadError, Document, Shape, Units};
+
+// What the rows of a quantity schedule are split by. From JS this is the
+// externally tagged form, e.g. "Layer" or { Attribute: "material" }.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum GroupKey {
+    Layer,
+    Attribute(String),
+    // Shapes carry no style of their own, so this is their "style"
+    // attribute
+    Style,
+}
+
+impl GroupKey {
+    fn name(&self) -> String {
+        match self {
+            GroupKey::Layer => "layer".to_string(),
+            GroupKey::Attribute(key) => key.clone(),
+            GroupKey::Style => "style".to_string(),
+        }
+    }
+
+    fn of(&self, shape: &Shape) -> Option<String> {
+        let attribute = |key: &str| match shape.attributes.get(key) {
+            Some(AttributeValue::Text(text)) => Some(text.clone()),
+            Some(AttributeValue::Number(n)) => Some(format_number(*n, None)),
+            None => None,
+        };
+        match self {
+            GroupKey::Layer => shape.layer.clone(),
+            GroupKey::Attribute(key) => attribute(key),
+            GroupKey::Style => attribute("style"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ScheduleOptions {
+    pub units: Units,
+    // Decimals written out
+    pub precision: usize,
+    // Leave out the layers this saved view hides
+    #[serde(default)]
+    pub visible_in: Option<String>,
+}
+
+impl Default for ScheduleOptions {
+    fn default() -> ScheduleOptions {
+        ScheduleOptions { units: Units::Millimeters, precision: 3, visible_in: None }
+    }
+}
+
+// Total and spread of one quantity over a group's shapes; all 0 for none
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct Quantity {
+    pub total: f64,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+}
+
+impl Quantity {
+    fn of(values: &[f64]) -> Quantity {
+        if values.is_empty() {
+            return Quantity::default();
+        }
+        let total = compensated_sum(values.iter().copied());
+        Quantity { total, min: values.iter().copied().fold(f64::INFINITY, f64::min), max: values.iter().copied().fold(f64::NEG_INFINITY, f64::max), mean: total / values.len() as f64 }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ScheduleRow {
+    // None for shapes without the grouping layer or attribute
+    pub group: Option<String>,
+    pub count: usize,
+    pub area: Quantity,
+    pub perimeter: Quantity,
+}
+
+impl ScheduleRow {
+    fn of(group: Option<String>, shapes: &[&Shape]) -> ScheduleRow {
+        let world: Vec<_> = shapes.iter().map(|s| s.world_geometry()).collect();
+        let areas: Vec<f64> = world.iter().map(|p| p.area()).collect();
+        let perimeters: Vec<f64> = world.iter().map(|p| p.perimeter()).collect();
+        ScheduleRow { group, count: shapes.len(), area: Quantity::of(&areas), perimeter: Quantity::of(&perimeters) }
+    }
+}
+
+// Quantity takeoff: shape counts, areas and perimeters per group, in world
+// space. Every document shape is a closed outline, so its length is its
+// perimeter.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Schedule {
+    pub group_by: GroupKey,
+    pub options: ScheduleOptions,
+    // Ungrouped shapes first, then by group name
+    pub rows: Vec<ScheduleRow>,
+    // Over every scheduled shape
+    pub total: ScheduleRow,
+}
+
+// JSON string literal
+fn quoted(text: &str) -> String {
+    let mut out = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+impl Schedule {
+    fn units(&self) -> (String, String) {
+        let name = match self.options.units {
+            Units::Millimeters => "mm",
+            Units::Inches => "in",
+        };
+        (name.to_string(), format!("{}²", name))
+    }
+
+    fn number(&self, v: f64) -> String {
+        format_number(v, Some(self.options.precision))
+    }
+
+    // Header row naming the units, one row per group and a closing total
+    // row; an ungrouped row has an empty group cell
+    pub fn to_csv(&self) -> String {
+        let (length, area) = self.units();
+        let cell = |text: &str| if text.contains([',', '"', '\n']) { format!("\"{}\"", text.replace('"', "\"\"")) } else { text.to_string() };
+        let mut csv = format!(
+            "{},count,area ({a}),area min ({a}),area max ({a}),area mean ({a}),perimeter ({l}),perimeter min ({l}),perimeter max ({l}),perimeter mean ({l})\n",
+            cell(&self.group_by.name()), a = area, l = length,
+        );
+        let total = ScheduleRow { group: Some("total".to_string()), ..self.total.clone() };
+        for row in self.rows.iter().chain([&total]) {
+            let (a, p) = (&row.area, &row.perimeter);
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{},{}\n",
+                cell(row.group.as_deref().unwrap_or("")), row.count,
+                self.number(a.total), self.number(a.min), self.number(a.max), self.number(a.mean),
+                self.number(p.total), self.number(p.min), self.number(p.max), self.number(p.mean),
+            ));
+        }
+        csv
+    }
+
+    // { group_by, units, area_units, rows: [{ group, count, area: { total,
+    // min, max, mean }, perimeter }], total }, numbers at the schedule's
+    // precision
+    pub fn to_json(&self) -> String {
+        let (length, area) = self.units();
+        let quantity = |q: &Quantity| format!("{{\"total\":{},\"min\":{},\"max\":{},\"mean\":{}}}", self.number(q.total), self.number(q.min), self.number(q.max), self.number(q.mean));
+        let row = |r: &ScheduleRow| format!(
+            "{{\"group\":{},\"count\":{},\"area\":{},\"perimeter\":{}}}",
+            r.group.as_deref().map_or("null".to_string(), quoted), r.count, quantity(&r.area), quantity(&r.perimeter),
+        );
+        format!(
+            "{{\"group_by\":{},\"units\":{},\"area_units\":{},\"rows\":[{}],\"total\":{}}}",
+            quoted(&self.group_by.name()), quoted(&length), quoted(&area),
+            self.rows.iter().map(row).collect::<Vec<_>>().join(","), row(&self.total),
+        )
+    }
+}
+
+impl Document {
+    pub fn quantity_schedule(&self, group_by: GroupKey, options: ScheduleOptions) -> Result<Schedule, CadError> {
+        let hidden: Vec<&String> = match &options.visible_in {
+            Some(view) => self.get_view(view)?.layers.iter().filter(|(_, &visible)| !visible).map(|(layer, _)| layer).collect(),
+            None => Vec::new(),
+        };
+        let mut shapes: Vec<&Shape> = self.shapes().filter(|s| s.layer.as_ref().is_none_or(|l| !hidden.contains(&l))).collect();
+        shapes.sort_by_key(|s| s.id);
+        let mut groups: BTreeMap<Option<String>, Vec<&Shape>> = BTreeMap::new();
+        for &shape in &shapes {
+            groups.entry(group_by.of(shape)).or_default().push(shape);
+        }
+        let rows = groups.into_iter().map(|(group, shapes)| ScheduleRow::of(group, &shapes)).collect();
+        Ok(Schedule { total: ScheduleRow::of(None, &shapes), group_by, options, rows })
+    }
+}
+
+#[wasm_bindgen]
+impl Document {
+    // `group_by` is "Layer", "Style" or { Attribute: key }; `options` is
+    // { units: "mm" | "in", precision, visible_in? }; `format` is "json" or
+    // "csv"
+    #[wasm_bindgen(js_name = quantity_schedule)]
+    pub fn quantity_schedule_js(&self, group_by: &JsValue, options: &JsValue, format: &str) -> Result<String, JsValue> {
+        let group_by: GroupKey = crate::from_js(group_by)?;
+        let options: ScheduleOptions = crate::options_from(options)?;
+        let schedule = self.quantity_schedule(group_by, options).map_err(js_error)?;
+        match format {
+            "csv" => Ok(schedule.to_csv()),
+            "json" => Ok(schedule.to_json()),
+            _ => Err(js_error(CadError::InvalidInput(format!("unknown schedule format {:?}", format)))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{NamedView, Point, Polygon, ViewportState};
+
+    fn rect(w: f64, h: f64) -> Polygon {
+        Polygon::new(vec![Point::new(0.0, 0.0), Point::new(w, 0.0), Point::new(w, h), Point::new(0.0, h)])
+    }
+
+    fn takeoff() -> Document {
+        let mut doc = Document::new();
+        for (w, h, layer, material) in [(2.0, 3.0, "walls", "brick"), (4.0, 1.0, "walls", "timber"), (1.0, 1.0, "floor", "timber"), (5.0, 2.0, "floor", "tile"), (3.0, 3.0, "floor", "brick")] {
+            let id = doc.add_shape(rect(w, h));
+            let shape = doc.shape_mut(id).unwrap();
+            shape.layer = Some(layer.to_string());
+            shape.attributes.insert("material".to_string(), AttributeValue::Text(material.to_string()));
+        }
+        doc
+    }
+
+    #[test]
+    fn test_layer_totals() {
+        let doc = takeoff();
+        let schedule = doc.quantity_schedule(GroupKey::Layer, ScheduleOptions::default()).unwrap();
+        let summary: Vec<_> = schedule.rows.iter().map(|r| (r.group.clone().unwrap(), r.count, r.area.total, r.perimeter.total)).collect();
+        assert_eq!(summary, vec![("floor".to_string(), 3, 20.0, 30.0), ("walls".to_string(), 2, 10.0, 20.0)]);
+        assert_eq!(schedule.rows[0].area, Quantity { total: 20.0, min: 1.0, max: 10.0, mean: 20.0 / 3.0 });
+        assert_eq!(schedule.total.area.total, 30.0);
+
+        let csv = schedule.to_csv();
+        assert!(csv.starts_with("layer,count,area (mm²),"));
+        assert_eq!(csv.lines().nth(1).unwrap(), "floor,3,20.000,1.000,10.000,6.667,30.000,4.000,14.000,10.000");
+        assert_eq!(csv.lines().last().unwrap(), "total,5,30.000,1.000,10.000,6.000,50.000,4.000,14.000,10.000");
+        let json = schedule.to_json();
+        assert!(json.starts_with("{\"group_by\":\"layer\",\"units\":\"mm\",\"area_units\":\"mm²\",\"rows\":[{\"group\":\"floor\",\"count\":3,\"area\":{\"total\":20.000,"));
+
+        // Hidden layers stay out when asked
+        let mut doc = doc;
+        let mut view = NamedView::new(ViewportState { center: Point::new(0.0, 0.0), zoom: 1.0 });
+        view.layers.insert("walls".to_string(), false);
+        doc.save_view("plan", view);
+        let visible = doc.quantity_schedule(GroupKey::Layer, ScheduleOptions { visible_in: Some("plan".to_string()), ..ScheduleOptions::default() }).unwrap();
+        assert_eq!(visible.rows.len(), 1);
+        assert_eq!(visible.total.count, 3);
+        assert!(doc.quantity_schedule(GroupKey::Layer, ScheduleOptions { visible_in: Some("none".to_string()), ..ScheduleOptions::default() }).is_err());
+    }
+
+    #[test]
+    fn test_material_grouping_keeps_grand_totals() {
+        let mut doc = takeoff();
+        doc.add_shape(rect(1.0, 2.0));
+        let by_layer = doc.quantity_schedule(GroupKey::Layer, ScheduleOptions::default()).unwrap();
+        let by_material = doc.quantity_schedule(GroupKey::Attribute("material".to_string()), ScheduleOptions::default()).unwrap();
+        let groups: Vec<_> = by_material.rows.iter().map(|r| (r.group.as_deref(), r.count, r.area.total)).collect();
+        assert_eq!(groups, vec![(None, 1, 2.0), (Some("brick"), 2, 15.0), (Some("tile"), 1, 10.0), (Some("timber"), 2, 5.0)]);
+        assert_eq!(by_material.total, by_layer.total);
+        for schedule in [&by_layer, &by_material] {
+            assert_eq!(schedule.rows.iter().map(|r| r.count).sum::<usize>(), 6);
+            assert_eq!(schedule.rows.iter().map(|r| r.area.total).sum::<f64>(), 32.0);
+            assert_eq!(schedule.rows.iter().map(|r| r.perimeter.total).sum::<f64>(), 56.0);
+        }
+        assert!(by_material.to_csv().lines().nth(1).unwrap().starts_with(",1,2.000,"));
+        assert!(by_material.to_json().contains("{\"group\":null,\"count\":1,"));
+    }
+}
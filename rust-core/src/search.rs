@@ -0,0 +1,274 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::document::ShapeIndex;
+use crate::{Shape, ShapeId};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum AttributeValue {
+    Number(f64),
+    Text(String),
+}
+
+// Structured search over shape attributes, labels and layers. From JS this is
+// the externally tagged form, e.g. { And: [{ Prefix: { key, prefix } }, ...] }.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum SearchQuery {
+    Equals { key: String, value: AttributeValue },
+    // Text attributes starting with `prefix`
+    Prefix { key: String, prefix: String },
+    // Numeric attributes within the inclusive range; a missing bound is open
+    Range { key: String, min: Option<f64>, max: Option<f64> },
+    // Case-insensitive substring of the shape's label
+    LabelContains(String),
+    Layer(String),
+    And(Vec<SearchQuery>),
+    Or(Vec<SearchQuery>),
+    Not(Box<SearchQuery>),
+}
+
+// Total order over attribute numbers so they can key a BTreeMap
+#[derive(Clone, Copy, Debug)]
+struct NumberKey(f64);
+
+impl PartialEq for NumberKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for NumberKey {}
+
+impl PartialOrd for NumberKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for NumberKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Adding 0.0 folds -0.0 into 0.0
+        (self.0 + 0.0).total_cmp(&(other.0 + 0.0))
+    }
+}
+
+struct Indexed {
+    attributes: BTreeMap<String, AttributeValue>,
+    layer: Option<String>,
+}
+
+// Per-key value indexes over the document's shapes
+#[derive(Default)]
+pub(crate) struct SearchIndex {
+    text: HashMap<String, BTreeMap<String, BTreeSet<ShapeId>>>,
+    numbers: HashMap<String, BTreeMap<NumberKey, BTreeSet<ShapeId>>>,
+    layers: HashMap<String, BTreeSet<ShapeId>>,
+    // Lowercased labels, scanned for substring queries
+    labels: BTreeMap<ShapeId, String>,
+    indexed: HashMap<ShapeId, Indexed>,
+}
+
+impl SearchIndex {
+    fn insert(&mut self, shape: &Shape) {
+        let id = shape.id;
+        for (key, value) in &shape.attributes {
+            match value {
+                AttributeValue::Text(text) => {
+                    self.text.entry(key.clone()).or_default().entry(text.clone()).or_default().insert(id);
+                }
+                AttributeValue::Number(n) => {
+                    self.numbers.entry(key.clone()).or_default().entry(NumberKey(*n)).or_default().insert(id);
+                }
+            }
+        }
+        if let Some(layer) = &shape.layer {
+            self.layers.entry(layer.clone()).or_default().insert(id);
+        }
+        if let Some(label) = &shape.label {
+            self.labels.insert(id, label.to_lowercase());
+        }
+        self.indexed.insert(id, Indexed { attributes: shape.attributes.clone(), layer: shape.layer.clone() });
+    }
+
+    fn remove(&mut self, id: ShapeId) {
+        let Some(old) = self.indexed.remove(&id) else {
+            return;
+        };
+        for (key, value) in &old.attributes {
+            match value {
+                AttributeValue::Text(text) => {
+                    if let Some(ids) = self.text.get_mut(key).and_then(|m| m.get_mut(text)) {
+                        ids.remove(&id);
+                    }
+                }
+                AttributeValue::Number(n) => {
+                    if let Some(ids) = self.numbers.get_mut(key).and_then(|m| m.get_mut(&NumberKey(*n))) {
+                        ids.remove(&id);
+                    }
+                }
+            }
+        }
+        if let Some(ids) = old.layer.and_then(|layer| self.layers.get_mut(&layer)) {
+            ids.remove(&id);
+        }
+        self.labels.remove(&id);
+    }
+
+    fn all(&self) -> BTreeSet<ShapeId> {
+        self.indexed.keys().copied().collect()
+    }
+
+    pub(crate) fn query(&self, query: &SearchQuery) -> BTreeSet<ShapeId> {
+        let union = |sets: &mut dyn Iterator<Item = &BTreeSet<ShapeId>>| sets.flatten().copied().collect::<BTreeSet<_>>();
+        match query {
+            SearchQuery::Equals { key, value } => match value {
+                AttributeValue::Text(text) => self.text.get(key).and_then(|m| m.get(text)).cloned().unwrap_or_default(),
+                AttributeValue::Number(n) => self.numbers.get(key).and_then(|m| m.get(&NumberKey(*n))).cloned().unwrap_or_default(),
+            },
+            SearchQuery::Prefix { key, prefix } => match self.text.get(key) {
+                Some(values) => union(&mut values.range(prefix.clone()..).take_while(|(v, _)| v.starts_with(prefix.as_str())).map(|(_, ids)| ids)),
+                None => BTreeSet::new(),
+            },
+            SearchQuery::Range { key, min, max } => match self.numbers.get(key) {
+                Some(values) => {
+                    let lo = NumberKey(min.unwrap_or(f64::NEG_INFINITY));
+                    let hi = NumberKey(max.unwrap_or(f64::INFINITY));
+                    if lo > hi {
+                        return BTreeSet::new();
+                    }
+                    union(&mut values.range(lo..=hi).map(|(_, ids)| ids))
+                }
+                None => BTreeSet::new(),
+            },
+            SearchQuery::LabelContains(needle) => {
+                let needle = needle.to_lowercase();
+                self.labels.iter().filter(|(_, label)| label.contains(&needle)).map(|(id, _)| *id).collect()
+            }
+            SearchQuery::Layer(layer) => self.layers.get(layer).cloned().unwrap_or_default(),
+            SearchQuery::And(parts) => {
+                let mut parts = parts.iter();
+                let mut result = match parts.next() {
+                    Some(first) => self.query(first),
+                    None => return self.all(),
+                };
+                for part in parts {
+                    if result.is_empty() {
+                        break;
+                    }
+                    let next = self.query(part);
+                    result.retain(|id| next.contains(id));
+                }
+                result
+            }
+            SearchQuery::Or(parts) => parts.iter().flat_map(|p| self.query(p)).collect(),
+            SearchQuery::Not(inner) => {
+                let excluded = self.query(inner);
+                self.all().into_iter().filter(|id| !excluded.contains(id)).collect()
+            }
+        }
+    }
+}
+
+impl ShapeIndex for SearchIndex {
+    fn build(shapes: &[Arc<Shape>]) -> SearchIndex {
+        let mut index = SearchIndex::default();
+        for shape in shapes {
+            index.insert(shape);
+        }
+        index
+    }
+
+    fn update(&mut self, id: ShapeId, shape: Option<&Shape>) {
+        self.remove(id);
+        if let Some(shape) = shape {
+            self.insert(shape);
+        }
+    }
+
+    // Per shape: its entry in `indexed`, plus an id in each value set it's
+    // filed under, taken as a handful of attributes on average
+    fn approx_bytes(&self) -> usize {
+        let per_shape = std::mem::size_of::<(ShapeId, Indexed)>() + 4 * (std::mem::size_of::<ShapeId>() + 32);
+        self.indexed.len() * per_shape + self.labels.len() * (std::mem::size_of::<String>() + 16)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Document, Point, Polygon};
+
+    use super::*;
+
+    fn text(s: &str) -> AttributeValue {
+        AttributeValue::Text(s.to_string())
+    }
+
+    fn parts_document() -> (Document, Vec<ShapeId>) {
+        let mut doc = Document::new();
+        let square = Polygon::new(vec![Point::new(0.0, 0.0), Point::new(1.0, 0.0), Point::new(1.0, 1.0)]);
+        let specs = [
+            ("BRK-100", 2.0, "Bracket rev A", "cut"),
+            ("BRK-200", 5.5, "Bracket REV B", "engrave"),
+            ("PLT-100", 3.0, "Plate", "cut"),
+            ("BRKT-9", 8.0, "Spacer", "cut"),
+        ];
+        let ids = specs.iter().map(|(part, thickness, label, layer)| {
+            let id = doc.add_shape(square.clone());
+            doc.set_attribute(id, "part_number", text(part)).unwrap();
+            doc.set_attribute(id, "thickness", AttributeValue::Number(*thickness)).unwrap();
+            doc.set_label(id, Some(label.to_string())).unwrap();
+            doc.set_layer(id, Some(layer.to_string())).unwrap();
+            id
+        }).collect();
+        (doc, ids)
+    }
+
+    #[test]
+    fn test_compound_queries() {
+        let (doc, ids) = parts_document();
+        let brk = SearchQuery::Prefix { key: "part_number".to_string(), prefix: "BRK-".to_string() };
+        assert_eq!(doc.search(&brk), vec![ids[0], ids[1]]);
+        assert_eq!(doc.search(&SearchQuery::LabelContains("rev".to_string())), vec![ids[0], ids[1]]);
+
+        let query = SearchQuery::And(vec![
+            SearchQuery::Or(vec![brk.clone(), SearchQuery::Equals { key: "part_number".to_string(), value: text("PLT-100") }]),
+            SearchQuery::Layer("cut".to_string()),
+        ]);
+        assert_eq!(doc.search(&query), vec![ids[0], ids[2]]);
+        assert_eq!(doc.search(&SearchQuery::Not(Box::new(brk))), vec![ids[2], ids[3]]);
+        assert_eq!(doc.search(&SearchQuery::And(vec![])).len(), 4);
+    }
+
+    #[test]
+    fn test_numeric_ranges() {
+        let (doc, ids) = parts_document();
+        let range = |min, max| SearchQuery::Range { key: "thickness".to_string(), min, max };
+        assert_eq!(doc.search(&range(Some(2.0), Some(5.5))), vec![ids[0], ids[1], ids[2]]);
+        assert_eq!(doc.search(&range(Some(5.6), None)), vec![ids[3]]);
+        assert_eq!(doc.search(&range(None, Some(1.0))), vec![]);
+        assert_eq!(doc.search(&range(Some(9.0), Some(1.0))), vec![]);
+        assert_eq!(doc.search(&SearchQuery::Equals { key: "thickness".to_string(), value: AttributeValue::Number(3.0) }), vec![ids[2]]);
+    }
+
+    #[test]
+    fn test_index_follows_edits() {
+        let (mut doc, ids) = parts_document();
+        let brk = SearchQuery::Prefix { key: "part_number".to_string(), prefix: "BRK-".to_string() };
+        assert_eq!(doc.search(&brk), vec![ids[0], ids[1]]);
+
+        doc.set_attribute(ids[2], "part_number", text("BRK-300")).unwrap();
+        doc.remove_attribute(ids[0], "part_number").unwrap();
+        doc.shape_mut(ids[1]).unwrap().attributes.insert("thickness".to_string(), AttributeValue::Number(20.0));
+        doc.set_layer(ids[3], None).unwrap();
+        doc.remove_shape(ids[1]).unwrap();
+        let pasted = doc.paste_shapes(&doc.copy_shapes(&[ids[2]]).unwrap());
+
+        assert_eq!(doc.search(&brk), vec![ids[2], pasted[0]]);
+        assert_eq!(doc.search(&SearchQuery::Range { key: "thickness".to_string(), min: Some(10.0), max: None }), vec![]);
+        assert_eq!(doc.search(&SearchQuery::Layer("cut".to_string())), vec![ids[0], ids[2], pasted[0]]);
+    }
+}
@@ -0,0 +1,192 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::hittest::edges;
+use crate::{Point, Polygon, Segment, EPSILON};
+
+// Where two closed segments meet
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum SegmentIntersection {
+    None,
+    Point(Point),
+    // Shared stretch of collinear segments, running the way the first does
+    Overlap(Segment),
+}
+
+fn cross(ux: f64, uy: f64, vx: f64, vy: f64) -> f64 {
+    ux * vy - uy * vx
+}
+
+impl Segment {
+    pub fn distance_to_point(&self, p: &Point) -> f64 {
+        let q = self.closest_point(p);
+        (q.x - p.x).hypot(q.y - p.y)
+    }
+
+    // Parameter of `p` projected onto the segment's line, 0 at `a` and 1 at
+    // `b`; 0 for a zero-length segment
+    pub(crate) fn parameter(&self, p: &Point) -> f64 {
+        let (dx, dy) = (self.b.x - self.a.x, self.b.y - self.a.y);
+        let len2 = dx * dx + dy * dy;
+        if len2 == 0.0 {
+            return 0.0;
+        }
+        ((p.x - self.a.x) * dx + (p.y - self.a.y) * dy) / len2
+    }
+
+    // Meeting of the closed segments, with anything within EPSILON of the
+    // other counted as meeting it. Segments that are parallel to within
+    // EPSILON either miss, touch end to end or overlap; a crossing within
+    // EPSILON of an endpoint reports that endpoint exactly, so segments
+    // sharing an endpoint meet there and nowhere else.
+    pub fn intersect(&self, other: &Segment) -> SegmentIntersection {
+        let (l1, l2) = (self.length(), other.length());
+        if l1 <= EPSILON || l2 <= EPSILON {
+            let (point, line) = if l1 <= EPSILON { (self.a, other) } else { (other.a, self) };
+            return if line.distance_to_point(&point) <= EPSILON { SegmentIntersection::Point(point) } else { SegmentIntersection::None };
+        }
+        let (d1x, d1y) = (self.b.x - self.a.x, self.b.y - self.a.y);
+        let (d2x, d2y) = (other.b.x - other.a.x, other.b.y - other.a.y);
+        let (wx, wy) = (other.a.x - self.a.x, other.a.y - self.a.y);
+        let denom = cross(d1x, d1y, d2x, d2y);
+        if denom.abs() <= EPSILON * l1 * l2 {
+            if cross(d1x, d1y, wx, wy).abs() / l1 > EPSILON {
+                return SegmentIntersection::None;
+            }
+            // Collinear: the other's ends along this one, clipped to it
+            let (t0, t1) = (self.parameter(&other.a), self.parameter(&other.b));
+            let (near, far) = if t0 <= t1 { ((t0, other.a), (t1, other.b)) } else { ((t1, other.b), (t0, other.a)) };
+            let start = if near.0 > 0.0 { near } else { (0.0, self.a) };
+            let end = if far.0 < 1.0 { far } else { (1.0, self.b) };
+            let slack = EPSILON / l1;
+            return if end.0 - start.0 < -slack {
+                SegmentIntersection::None
+            } else if end.0 - start.0 <= slack {
+                SegmentIntersection::Point(start.1)
+            } else {
+                SegmentIntersection::Overlap(Segment::new(start.1, end.1))
+            };
+        }
+        let t = cross(wx, wy, d2x, d2y) / denom;
+        let v = cross(wx, wy, d1x, d1y) / denom;
+        let (slack1, slack2) = (EPSILON / l1, EPSILON / l2);
+        if t < -slack1 || t > 1.0 + slack1 || v < -slack2 || v > 1.0 + slack2 {
+            return SegmentIntersection::None;
+        }
+        let point = if t.abs() <= slack1 {
+            self.a
+        } else if (t - 1.0).abs() <= slack1 {
+            self.b
+        } else if v.abs() <= slack2 {
+            other.a
+        } else if (v - 1.0).abs() <= slack2 {
+            other.b
+        } else {
+            Point::new(self.a.x + d1x * t, self.a.y + d1y * t)
+        };
+        SegmentIntersection::Point(point)
+    }
+}
+
+impl Polygon {
+    // Points where `seg` meets the boundary, ordered from `seg.a` to
+    // `seg.b`. An edge the segment runs along gives the two ends of the
+    // shared stretch, and a crossing at a vertex is reported once.
+    pub fn intersect_segment(&self, seg: &Segment) -> Vec<Point> {
+        let mut found: Vec<(f64, Point)> = Vec::new();
+        for edge in edges(&self.vertices) {
+            match seg.intersect(&edge) {
+                SegmentIntersection::None => {}
+                SegmentIntersection::Point(p) => found.push((seg.parameter(&p), p)),
+                SegmentIntersection::Overlap(s) => found.extend([(seg.parameter(&s.a), s.a), (seg.parameter(&s.b), s.b)]),
+            }
+        }
+        found.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut points: Vec<Point> = Vec::new();
+        for (_, p) in found {
+            if points.last().is_none_or(|q| (q.x - p.x).hypot(q.y - p.y) > EPSILON) {
+                points.push(p);
+            }
+        }
+        points
+    }
+}
+
+fn segment_from(value: &JsValue) -> Result<Segment, JsValue> {
+    crate::from_js(value)
+}
+
+// Segments are { a, b }; returns "None", { Point: { x, y } } or
+// { Overlap: { a, b } }
+#[wasm_bindgen]
+pub fn segment_intersection(a: &JsValue, b: &JsValue) -> Result<JsValue, JsValue> {
+    crate::to_js(&segment_from(a)?.intersect(&segment_from(b)?))
+}
+
+// { x, y }
+#[wasm_bindgen]
+pub fn segment_closest_point(segment: &JsValue, x: f64, y: f64) -> Result<JsValue, JsValue> {
+    crate::to_js(&segment_from(segment)?.closest_point(&Point::new(x, y)))
+}
+
+#[wasm_bindgen]
+pub fn segment_distance_to_point(segment: &JsValue, x: f64, y: f64) -> Result<f64, JsValue> {
+    Ok(segment_from(segment)?.distance_to_point(&Point::new(x, y)))
+}
+
+// Array of {x, y}, in order along the segment
+#[wasm_bindgen]
+pub fn polygon_intersect_segment(vertices: &JsValue, segment: &JsValue) -> Result<JsValue, JsValue> {
+    let polygon: Polygon = crate::polygon_from(vertices)?;
+    crate::to_js(&polygon.intersect_segment(&segment_from(segment)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seg(ax: f64, ay: f64, bx: f64, by: f64) -> Segment {
+        Segment::new(Point::new(ax, ay), Point::new(bx, by))
+    }
+
+    #[test]
+    fn test_segment_intersection_cases() {
+        use SegmentIntersection::*;
+        assert_eq!(seg(0.0, 0.0, 2.0, 2.0).intersect(&seg(0.0, 2.0, 2.0, 0.0)), Point(crate::Point::new(1.0, 1.0)));
+        // Parallel, and collinear but apart
+        assert_eq!(seg(0.0, 0.0, 2.0, 0.0).intersect(&seg(0.0, 1.0, 2.0, 1.0)), None);
+        assert_eq!(seg(0.0, 0.0, 1.0, 0.0).intersect(&seg(2.0, 0.0, 3.0, 0.0)), None);
+        // Collinear overlap, in the first segment's direction whichever way
+        // the second runs
+        assert_eq!(seg(0.0, 0.0, 3.0, 0.0).intersect(&seg(4.0, 0.0, 1.0, 0.0)), Overlap(seg(1.0, 0.0, 3.0, 0.0)));
+        assert_eq!(seg(0.0, 0.0, 3.0, 0.0).intersect(&seg(-1.0, 0.0, 5.0, 0.0)), Overlap(seg(0.0, 0.0, 3.0, 0.0)));
+        // Collinear end to end, and crossing at shared endpoints, give the
+        // endpoint exactly
+        assert_eq!(seg(0.0, 0.0, 1.0, 0.0).intersect(&seg(1.0, 0.0, 2.0, 0.0)), Point(crate::Point::new(1.0, 0.0)));
+        let (a, b) = (seg(0.1, 0.7, 0.3, 0.9), seg(0.3, 0.9, 0.8, 0.1));
+        assert_eq!(a.intersect(&b), Point(crate::Point::new(0.3, 0.9)));
+        assert_eq!(b.intersect(&a), Point(crate::Point::new(0.3, 0.9)));
+        // T junction within EPSILON of the other segment
+        assert_eq!(seg(0.0, 0.0, 2.0, 0.0).intersect(&seg(1.0, 1e-10, 1.0, 1.0)), Point(crate::Point::new(1.0, 1e-10)));
+        assert_eq!(seg(0.0, 0.0, 2.0, 0.0).intersect(&seg(1.0, 1e-6, 1.0, 1.0)), None);
+        // Zero-length segments meet only where they lie on the other
+        assert_eq!(seg(1.0, 0.0, 1.0, 0.0).intersect(&seg(0.0, 0.0, 2.0, 0.0)), Point(crate::Point::new(1.0, 0.0)));
+        assert_eq!(seg(1.0, 1.0, 1.0, 1.0).intersect(&seg(0.0, 0.0, 2.0, 0.0)), None);
+
+        let s = seg(0.0, 0.0, 4.0, 0.0);
+        assert_eq!(s.closest_point(&crate::Point::new(5.0, 3.0)), crate::Point::new(4.0, 0.0));
+        assert_eq!(s.distance_to_point(&crate::Point::new(2.0, -1.5)), 1.5);
+    }
+
+    #[test]
+    fn test_polygon_crossings_in_order() {
+        let square = Polygon::new(vec![Point::new(0.0, 0.0), Point::new(4.0, 0.0), Point::new(4.0, 4.0), Point::new(0.0, 4.0)]);
+        let across = square.intersect_segment(&seg(6.0, 1.0, -2.0, 1.0));
+        assert_eq!(across, vec![Point::new(4.0, 1.0), Point::new(0.0, 1.0)]);
+        // Through two corners, each reported once
+        assert_eq!(square.intersect_segment(&seg(-1.0, -1.0, 5.0, 5.0)), vec![Point::new(0.0, 0.0), Point::new(4.0, 4.0)]);
+        // Along an edge, the shared stretch's ends
+        assert_eq!(square.intersect_segment(&seg(1.0, 0.0, 6.0, 0.0)), vec![Point::new(1.0, 0.0), Point::new(4.0, 0.0)]);
+        assert!(square.intersect_segment(&seg(1.0, 1.0, 2.0, 2.0)).is_empty());
+    }
+}
@@ -0,0 +1,213 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::{Document, ShapeId};
+
+// Shapes the user has picked, in the order they were picked. It refers to
+// shapes by id, which stay the same across edits and save/load, so a saved
+// selection can be restored against a reloaded document.
+#[wasm_bindgen]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Selection {
+    ids: Vec<ShapeId>,
+}
+
+impl Selection {
+    pub fn new() -> Selection {
+        Selection::default()
+    }
+
+    pub fn ids(&self) -> &[ShapeId] {
+        &self.ids
+    }
+
+    pub fn contains(&self, id: ShapeId) -> bool {
+        self.ids.contains(&id)
+    }
+
+    // Adds `id` at the end unless it is already selected
+    pub fn select(&mut self, id: ShapeId) {
+        if !self.contains(id) {
+            self.ids.push(id);
+        }
+    }
+
+    pub fn deselect(&mut self, id: ShapeId) -> bool {
+        let before = self.ids.len();
+        self.ids.retain(|&i| i != id);
+        self.ids.len() != before
+    }
+
+    pub fn clear(&mut self) {
+        self.ids.clear();
+    }
+
+    // The ids to save alongside the document
+    pub fn serialize(&self) -> Vec<ShapeId> {
+        self.ids.clone()
+    }
+
+    // Replaces the selection with saved ids that still name a shape in
+    // `doc`, keeping their order; returns how many were dropped
+    pub fn restore(&mut self, doc: &Document, data: &[ShapeId]) -> usize {
+        self.clear();
+        let mut dropped = 0;
+        for &id in data {
+            if doc.shape(id).is_ok() {
+                self.select(id);
+            } else {
+                dropped += 1;
+            }
+        }
+        dropped
+    }
+}
+
+impl Document {
+    // The document's own selection, which `checkpoint` saves and `undo` and
+    // `redo` restore along with the shapes; a shape removed leaves it
+    pub fn selection(&self) -> &Selection {
+        &self.selection
+    }
+
+    pub fn selection_mut(&mut self) -> &mut Selection {
+        &mut self.selection
+    }
+
+    // Replaces the selection, keeping only ids that name a shape
+    pub fn set_selection(&mut self, selection: &Selection) -> usize {
+        let mut kept = Selection::new();
+        let dropped = kept.restore(self, selection.ids());
+        self.selection = kept;
+        dropped
+    }
+}
+
+#[wasm_bindgen]
+impl Selection {
+    #[wasm_bindgen(constructor)]
+    pub fn new_js() -> Selection {
+        Selection::new()
+    }
+
+    #[wasm_bindgen(js_name = ids)]
+    pub fn ids_js(&self) -> Vec<ShapeId> {
+        self.ids.clone()
+    }
+
+    #[wasm_bindgen(js_name = contains)]
+    pub fn contains_js(&self, id: ShapeId) -> bool {
+        self.contains(id)
+    }
+
+    #[wasm_bindgen(js_name = select)]
+    pub fn select_js(&mut self, id: ShapeId) {
+        self.select(id)
+    }
+
+    #[wasm_bindgen(js_name = deselect)]
+    pub fn deselect_js(&mut self, id: ShapeId) -> bool {
+        self.deselect(id)
+    }
+
+    #[wasm_bindgen(js_name = clear)]
+    pub fn clear_js(&mut self) {
+        self.clear()
+    }
+
+    #[wasm_bindgen(js_name = serialize)]
+    pub fn serialize_js(&self) -> Vec<ShapeId> {
+        self.serialize()
+    }
+
+    #[wasm_bindgen(js_name = restore)]
+    pub fn restore_js(&mut self, doc: &Document, data: Vec<ShapeId>) -> usize {
+        self.restore(doc, &data)
+    }
+}
+
+#[wasm_bindgen]
+impl Document {
+    #[wasm_bindgen(js_name = get_selection)]
+    pub fn get_selection_js(&self) -> Selection {
+        self.selection.clone()
+    }
+
+    // Returns how many ids were dropped for naming no shape
+    #[wasm_bindgen(js_name = set_selection)]
+    pub fn set_selection_js(&mut self, selection: &Selection) -> usize {
+        self.set_selection(selection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Point, Polygon};
+
+    #[test]
+    fn test_restore_after_reload_drops_missing_shapes() {
+        let mut doc = Document::new();
+        let ids: Vec<ShapeId> = (0..4).map(|i| {
+            let x = i as f64 * 2.0;
+            doc.add_shape(Polygon::new(vec![Point::new(x, 0.0), Point::new(x + 1.0, 0.0), Point::new(x + 1.0, 1.0)]))
+        }).collect();
+        let mut selection = Selection::new();
+        for &id in &[ids[2], ids[0], ids[3], ids[0]] {
+            selection.select(id);
+        }
+        assert_eq!(selection.ids(), &[ids[2], ids[0], ids[3]]);
+        let saved = selection.serialize();
+
+        // Reloaded unchanged, everything comes back in order
+        let mut restored = Selection::new();
+        assert_eq!(restored.restore(&doc.clone(), &saved), 0);
+        assert_eq!(restored, selection);
+
+        // Reloaded after a shape was deleted elsewhere
+        doc.remove_shape(ids[0]).unwrap();
+        assert_eq!(restored.restore(&doc, &saved), 1);
+        assert_eq!(restored.ids(), &[ids[2], ids[3]]);
+        assert!(!restored.contains(ids[0]));
+        assert!(restored.deselect(ids[2]) && !restored.deselect(ids[2]));
+    }
+
+    #[test]
+    fn test_undo_and_redo_bring_back_the_selection() {
+        let square = |x: f64| Polygon::new(vec![Point::new(x, 0.0), Point::new(x + 1.0, 0.0), Point::new(x + 1.0, 1.0), Point::new(x, 1.0)]);
+        let mut doc = Document::new();
+        let a = doc.add_shape(square(0.0));
+        doc.selection_mut().select(a);
+        doc.checkpoint();
+        let b = doc.add_shape(square(2.0));
+        doc.selection_mut().select(b);
+
+        // Deleting a selected shape deselects it, and undo reselects it
+        doc.checkpoint();
+        doc.remove_shape(b).unwrap();
+        assert_eq!(doc.selection().ids(), &[a]);
+        assert!(doc.undo());
+        assert_eq!(doc.selection().ids(), &[a, b]);
+        assert!(doc.redo());
+        assert_eq!(doc.selection().ids(), &[a]);
+
+        // Undoing past b's creation leaves it out, and redo brings it back
+        assert!(doc.undo() && doc.undo());
+        assert!(doc.shape(b).is_err());
+        assert_eq!(doc.selection().ids(), &[a]);
+        doc.selection_mut().clear();
+        assert!(doc.redo());
+        assert_eq!(doc.selection().ids(), &[a, b]);
+
+        // Saved with the document and restored after a reload
+        let saved = doc.selection().serialize();
+        let mut reloaded: Document = crate::binary::from_bytes(&crate::binary::to_bytes(&doc).unwrap()).unwrap();
+        assert!(reloaded.selection().ids().is_empty());
+        assert_eq!(reloaded.set_selection(&Selection { ids: saved.clone() }), 0);
+        assert_eq!(reloaded.selection().ids(), &[a, b]);
+        reloaded.remove_shape(a).unwrap();
+        let mut restored = Selection::new();
+        assert_eq!(restored.restore(&reloaded, &saved), 1);
+        assert_eq!(restored.ids(), &[b]);
+    }
+}
@@ -0,0 +1,244 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+
+use wasm_bindgen::prelude::*;
+
+use crate::census::{counter_clockwise, perimeter, rigid_match};
+use crate::predicates::ring_signed_area;
+use crate::{Document, Point, Polygon, ShapeChange, ShapeId, EPSILON};
+
+// Vertex count, then perimeter, longest edge and shortest edge buckets
+type Key = (usize, i64, i64, i64);
+
+// What a shape is compared by, worked out once when it is indexed
+#[derive(Clone, Debug)]
+struct Signature {
+    ring: Vec<Point>,
+    perimeter: f64,
+    area: f64,
+    // Edge lengths, shortest first
+    edges: Vec<f64>,
+}
+
+impl Signature {
+    fn new(vertices: &[Point]) -> Signature {
+        let ring = counter_clockwise(vertices);
+        let n = ring.len();
+        let mut edges: Vec<f64> = (0..n).map(|i| (ring[(i + 1) % n].x - ring[i].x).hypot(ring[(i + 1) % n].y - ring[i].y)).collect();
+        edges.sort_by(f64::total_cmp);
+        Signature { perimeter: perimeter(&ring), area: ring_signed_area(&ring), ring, edges }
+    }
+
+    fn longest(&self) -> f64 {
+        self.edges.last().copied().unwrap_or(0.0)
+    }
+
+    fn shortest(&self) -> f64 {
+        self.edges.first().copied().unwrap_or(0.0)
+    }
+}
+
+// Slack for rounding when comparing against an exact bound
+fn slack(v: f64) -> f64 {
+    EPSILON * (1.0 + v.abs())
+}
+
+// Finds shapes congruent to a template by rotation and translation without
+// comparing against every shape. Moving each vertex by at most the
+// tolerance changes each edge, and so each edge in sorted order, by at most
+// twice the tolerance, and the perimeter by at most 2n times it. Shapes are
+// hashed on vertex count and buckets of perimeter, longest and shortest edge
+// at least that wide, so a match is always in a neighbouring bucket; the
+// sorted edges and area screen candidates before the exact comparison `census`
+// uses. Follows the document through `apply_changes`, fed what
+// `Document::take_changes` returns.
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct ShapeSignatureIndex {
+    tolerance: f64,
+    buckets: HashMap<Key, Vec<ShapeId>>,
+    shapes: HashMap<ShapeId, (Key, Signature)>,
+    // Exact comparisons made so far
+    verifications: Cell<usize>,
+}
+
+impl ShapeSignatureIndex {
+    pub fn build(doc: &Document, tolerance: f64) -> ShapeSignatureIndex {
+        let mut index = ShapeSignatureIndex { tolerance: tolerance.max(EPSILON), buckets: HashMap::new(), shapes: HashMap::new(), verifications: Cell::new(0) };
+        for shape in doc.shapes() {
+            index.insert(shape.id, Signature::new(&shape.world_geometry().vertices));
+        }
+        index
+    }
+
+    fn key(&self, s: &Signature) -> Key {
+        let n = s.ring.len();
+        let (along, edge) = (4.0 * n.max(1) as f64 * self.tolerance, 4.0 * self.tolerance);
+        (n, (s.perimeter / along).floor() as i64, (s.longest() / edge).floor() as i64, (s.shortest() / edge).floor() as i64)
+    }
+
+    fn insert(&mut self, id: ShapeId, signature: Signature) {
+        let key = self.key(&signature);
+        self.buckets.entry(key).or_default().push(id);
+        self.shapes.insert(id, (key, signature));
+    }
+
+    fn remove(&mut self, id: ShapeId) {
+        if let Some((key, _)) = self.shapes.remove(&id) {
+            let bucket = self.buckets.get_mut(&key).unwrap();
+            bucket.retain(|&i| i != id);
+            if bucket.is_empty() {
+                self.buckets.remove(&key);
+            }
+        }
+    }
+
+    // Re-reads just the shapes in `changes`
+    pub fn apply_changes(&mut self, doc: &Document, changes: &[ShapeChange]) {
+        for change in changes {
+            self.remove(change.id);
+            if let Ok(shape) = doc.shape(change.id) {
+                self.insert(change.id, Signature::new(&shape.world_geometry().vertices));
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.shapes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shapes.is_empty()
+    }
+
+    pub fn verifications(&self) -> usize {
+        self.verifications.get()
+    }
+
+    // Same answer as `find_congruent` at the index's tolerance
+    pub fn query(&self, template: &Polygon) -> Vec<ShapeId> {
+        let t = self.tolerance;
+        let wanted = Signature::new(&template.vertices);
+        let n = wanted.ring.len();
+        let (n0, p, l, s) = self.key(&wanted);
+        // Shoelace terms each move by at most the tolerance times the two
+        // edges at the vertex, plus the square of the tolerance
+        let area_bound = t * wanted.perimeter + 0.5 * n as f64 * t * t;
+        let mut ids = Vec::new();
+        for key in (p - 1..=p + 1).flat_map(|p| (l - 1..=l + 1).flat_map(move |l| (s - 1..=s + 1).map(move |s| (n0, p, l, s)))) {
+            for &id in self.buckets.get(&key).into_iter().flatten() {
+                let candidate = &self.shapes[&id].1;
+                if (candidate.area - wanted.area).abs() > area_bound + slack(wanted.area) {
+                    continue;
+                }
+                if candidate.edges.iter().zip(&wanted.edges).any(|(a, b)| (a - b).abs() > 2.0 * t + slack(*b)) {
+                    continue;
+                }
+                self.verifications.set(self.verifications.get() + 1);
+                if rigid_match(&wanted.ring, &candidate.ring, t) {
+                    ids.push(id);
+                }
+            }
+        }
+        ids.sort_unstable();
+        ids
+    }
+}
+
+#[wasm_bindgen]
+impl ShapeSignatureIndex {
+    #[wasm_bindgen(js_name = build)]
+    pub fn build_js(doc: &Document, tolerance: f64) -> ShapeSignatureIndex {
+        ShapeSignatureIndex::build(doc, tolerance)
+    }
+
+    // `changes` as returned by `Document.take_changes`
+    #[wasm_bindgen(js_name = apply_changes)]
+    pub fn apply_changes_js(&mut self, doc: &Document, changes: &JsValue) -> Result<(), JsValue> {
+        let changes: Vec<ShapeChange> = crate::from_js(changes)?;
+        self.apply_changes(doc, &changes);
+        Ok(())
+    }
+
+    // `template` is an array of {x, y}
+    #[wasm_bindgen(js_name = query)]
+    pub fn query_js(&self, template: &JsValue) -> Result<Vec<ShapeId>, JsValue> {
+        let template: Polygon = crate::polygon_from(template)?;
+        Ok(self.query(&template))
+    }
+
+    #[wasm_bindgen(js_name = verifications)]
+    pub fn verifications_js(&self) -> usize {
+        self.verifications()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{find_congruent, Matrix};
+
+    #[test]
+    fn test_index_agrees_with_brute_force() {
+        let mut state = 11u64;
+        let mut next = move || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (state >> 11) as f64 / (1u64 << 53) as f64
+        };
+        // Star-shaped outlines with 3 to 8 vertices
+        let outline = |next: &mut dyn FnMut() -> f64| {
+            let n = 3 + (next() * 6.0) as usize;
+            Polygon::new((0..n).map(|i| {
+                let (a, r) = ((i as f64 + 0.8 * next()) / n as f64 * std::f64::consts::TAU, 1.0 + 3.0 * next());
+                Point::new(r * a.cos(), r * a.sin())
+            }).collect())
+        };
+        let templates: Vec<Polygon> = (0..5).map(|_| outline(&mut next)).collect();
+        let tolerance = 1e-3;
+
+        let mut doc = Document::new();
+        let mut planted = Vec::new();
+        for i in 0..20_000 {
+            let mut shape = if i % 100 < 2 { templates[(i / 100) % 5].clone() } else { outline(&mut next) };
+            if i % 100 < 2 {
+                // Jittered within the tolerance and started at another vertex
+                for v in &mut shape.vertices {
+                    *v = Point::new(v.x + (next() - 0.5) * 0.2 * tolerance, v.y + (next() - 0.5) * 0.2 * tolerance);
+                }
+                let k = (next() * shape.vertices.len() as f64) as usize;
+                shape.vertices.rotate_left(k);
+                planted.push(i as ShapeId);
+            }
+            let id = doc.add_shape(shape);
+            doc.set_transform(id, Matrix::translate(next() * 1000.0, next() * 1000.0).multiply(&Matrix::rotate(next() * 6.3))).unwrap();
+        }
+        doc.take_changes();
+
+        let index = ShapeSignatureIndex::build(&doc, tolerance);
+        let mut found = 0;
+        for template in &templates {
+            let expected = find_congruent(&doc, template, tolerance);
+            found += expected.len();
+            assert_eq!(index.query(template), expected);
+        }
+        assert_eq!(found, planted.len());
+        // Brute force makes one comparison per shape per template
+        assert!(index.verifications() * 100 < 5 * doc.shapes().count(), "{} verifications", index.verifications());
+
+        // Some planted copies move, some are redrawn, and one is deleted
+        let mut index = index;
+        for (k, &id) in planted.iter().enumerate().take(30) {
+            match k % 3 {
+                0 => doc.set_transform(id, Matrix::rotate(1.0)).unwrap(),
+                1 => doc.shape_mut(id).unwrap().geometry = outline(&mut next),
+                _ => doc.shape_mut(id).unwrap().geometry = templates[(k + 1) % 5].clone(),
+            }
+        }
+        doc.remove_shape(planted[40]).unwrap();
+        let changes = doc.take_changes();
+        index.apply_changes(&doc, &changes);
+        for template in &templates {
+            assert_eq!(index.query(template), find_congruent(&doc, template, tolerance));
+        }
+    }
+}
@@ -0,0 +1,302 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+use wasm_bindgen::prelude::*;
+
+use crate::predicates::{orient2d, point_in_triangle};
+use crate::{Point, Polygon, Segment};
+
+// Heap entry ordered so the smallest triangle pops first
+struct Candidate {
+    area: f64,
+    index: usize,
+    version: u32,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.area.partial_cmp(&self.area).unwrap_or(Ordering::Equal)
+            .then_with(|| other.index.cmp(&self.index))
+    }
+}
+
+// Uniform grid over the vertices so the "no vertex inside the removed
+// triangle" check only looks at nearby points
+struct VertexGrid {
+    cell: f64,
+    cells: HashMap<(i64, i64), Vec<usize>>,
+}
+
+impl VertexGrid {
+    fn new(points: &[Point], cell: f64) -> VertexGrid {
+        let mut grid = VertexGrid { cell, cells: HashMap::new() };
+        for (i, p) in points.iter().enumerate() {
+            grid.cells.entry(grid.key(p)).or_default().push(i);
+        }
+        grid
+    }
+
+    fn key(&self, p: &Point) -> (i64, i64) {
+        ((p.x / self.cell).floor() as i64, (p.y / self.cell).floor() as i64)
+    }
+
+    fn remove(&mut self, p: &Point, index: usize) {
+        let key = self.key(p);
+        if let Some(bucket) = self.cells.get_mut(&key) {
+            bucket.retain(|&i| i != index);
+        }
+    }
+
+    fn any_in_triangle(&self, points: &[Point], tri: [usize; 3]) -> bool {
+        let [a, b, c] = tri.map(|i| points[i]);
+        let lo = self.key(&Point::new(a.x.min(b.x).min(c.x), a.y.min(b.y).min(c.y)));
+        let hi = self.key(&Point::new(a.x.max(b.x).max(c.x), a.y.max(b.y).max(c.y)));
+
+        for gx in lo.0..=hi.0 {
+            for gy in lo.1..=hi.1 {
+                if let Some(bucket) = self.cells.get(&(gx, gy)) {
+                    for &i in bucket {
+                        if !tri.contains(&i) && point_in_triangle(&points[i], &a, &b, &c) {
+                            return true;
+                        }
+                    }
+                }
+            }
+        }
+
+        false
+    }
+}
+
+impl Polygon {
+    // Visvalingam-Whyatt simplification with a hard bound on the area change.
+    // Vertices are removed smallest-triangle first while the summed area of the
+    // removed triangles stays within `max_area_error_fraction` of the polygon
+    // area, so the result's area differs from the original by at most that
+    // fraction. A vertex is only removed when no other vertex lies inside its
+    // triangle, which keeps a simple input simple and preserves winding.
+    pub fn simplify_area_bounded(&self, max_area_error_fraction: f64) -> Polygon {
+        let n = self.vertices.len();
+        if n <= 3 || max_area_error_fraction <= 0.0 || !max_area_error_fraction.is_finite() {
+            return self.clone();
+        }
+
+        let points = &self.vertices;
+        let budget = self.area() * max_area_error_fraction;
+        let mut prev: Vec<usize> = (0..n).map(|i| (i + n - 1) % n).collect();
+        let mut next: Vec<usize> = (0..n).map(|i| (i + 1) % n).collect();
+        let mut removed = vec![false; n];
+        let mut version = vec![0u32; n];
+        let mut remaining = n;
+        let mut spent = 0.0;
+
+        let triangle_area = |prev: &[usize], next: &[usize], i: usize| {
+            orient2d(&points[prev[i]], &points[i], &points[next[i]]).abs() / 2.0
+        };
+
+        let cell = (self.perimeter() / n as f64 * 4.0).max(f64::MIN_POSITIVE);
+        let mut grid = VertexGrid::new(points, cell);
+
+        let mut heap = BinaryHeap::new();
+        for i in 0..n {
+            heap.push(Candidate { area: triangle_area(&prev, &next, i), index: i, version: 0 });
+        }
+
+        while let Some(Candidate { area, index, version: v }) = heap.pop() {
+            if remaining <= 3 || spent + area > budget {
+                break;
+            }
+            if removed[index] || v != version[index] {
+                continue;
+            }
+
+            let (p, q) = (prev[index], next[index]);
+            if grid.any_in_triangle(points, [p, index, q]) {
+                // Blocked for now; it gets another chance if a neighbour changes
+                continue;
+            }
+
+            removed[index] = true;
+            remaining -= 1;
+            spent += area;
+            grid.remove(&points[index], index);
+            next[p] = q;
+            prev[q] = p;
+
+            for &j in &[p, q] {
+                version[j] += 1;
+                heap.push(Candidate { area: triangle_area(&prev, &next, j), index: j, version: version[j] });
+            }
+        }
+
+        let vertices = (0..n).filter(|&i| !removed[i]).map(|i| points[i]).collect();
+        Polygon::new(vertices)
+    }
+}
+
+// Indices of the chain from `from` to `to` (going forward round the ring)
+// that Ramer-Douglas-Peucker keeps at `tolerance`, ends excluded
+fn douglas_peucker(points: &[Point], from: usize, to: usize, tolerance: f64, keep: &mut [bool]) {
+    let n = points.len();
+    let mut stack = vec![(from, to)];
+    while let Some((a, b)) = stack.pop() {
+        let chord = Segment::new(points[a], points[b]);
+        let span = (b + n - a) % n;
+        let farthest = (1..span).map(|k| (a + k) % n).map(|i| (i, chord.distance_to_point(&points[i])))
+            .fold(None, |best: Option<(usize, f64)>, (i, d)| if best.is_none_or(|(_, bd)| d > bd) { Some((i, d)) } else { best });
+        if let Some((i, d)) = farthest {
+            if d > tolerance {
+                keep[i] = true;
+                stack.push((a, i));
+                stack.push((i, b));
+            }
+        }
+    }
+}
+
+impl Polygon {
+    // Ramer-Douglas-Peucker on the closed ring: every dropped vertex lies
+    // within `tolerance` of the simplified outline. The ring is split at two
+    // vertices far apart, the one furthest from the first vertex and the one
+    // furthest from that, rather than at the first vertex, so where the ring
+    // starts doesn't decide what survives. At least three vertices are kept
+    // of any ring that has them.
+    pub fn simplify(&self, tolerance: f64) -> Polygon {
+        let points = &self.vertices;
+        let n = points.len();
+        if n <= 3 {
+            return self.clone();
+        }
+        let distance = |i: usize, j: usize| (points[i].x - points[j].x).hypot(points[i].y - points[j].y);
+        let farthest_from = |j: usize| (0..n).fold(j, |best, i| if distance(i, j) > distance(best, j) { i } else { best });
+        let a = farthest_from(0);
+        let b = farthest_from(a);
+        let mut keep = vec![false; n];
+        keep[a] = true;
+        keep[b] = true;
+        let tolerance = tolerance.max(0.0);
+        douglas_peucker(points, a, b, tolerance, &mut keep);
+        douglas_peucker(points, b, a, tolerance, &mut keep);
+        if keep.iter().filter(|&&k| k).count() < 3 {
+            let chord = Segment::new(points[a], points[b]);
+            let third = (0..n).filter(|&i| i != a && i != b)
+                .max_by(|&i, &j| chord.distance_to_point(&points[i]).total_cmp(&chord.distance_to_point(&points[j])));
+            if let Some(i) = third {
+                keep[i] = true;
+            }
+        }
+        Polygon::new((0..n).filter(|&i| keep[i]).map(|i| points[i]).collect())
+    }
+}
+
+// Ramer-Douglas-Peucker, as `Polygon::simplify`
+#[wasm_bindgen]
+pub fn simplify_polygon(vertices: &JsValue, tolerance: f64) -> Result<JsValue, JsValue> {
+    let polygon: Polygon = crate::polygon_from(vertices)?;
+    crate::to_js(&crate::strict::checked_polygon(polygon.simplify(tolerance), "simplify").map_err(crate::js_error)?)
+}
+
+#[wasm_bindgen]
+pub fn simplify_area_bounded(vertices: &JsValue, max_area_error_fraction: f64) -> Result<JsValue, JsValue> {
+    let polygon: Polygon = crate::polygon_from(vertices)?;
+    crate::to_js(&crate::strict::checked_polygon(polygon.simplify_area_bounded(max_area_error_fraction), "simplify").map_err(crate::js_error)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Deterministic pseudo-random noise in [-1, 1)
+    fn noise(seed: &mut u64) -> f64 {
+        *seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        ((*seed >> 11) as f64 / (1u64 << 53) as f64) * 2.0 - 1.0
+    }
+
+    fn noisy_circle(n: usize, radius: f64, amplitude: f64) -> Polygon {
+        let mut seed = 42;
+        let vertices = (0..n)
+            .map(|i| {
+                let t = i as f64 / n as f64 * std::f64::consts::TAU;
+                let r = radius + amplitude * noise(&mut seed);
+                Point::new(r * t.cos(), r * t.sin())
+            })
+            .collect();
+        Polygon::new(vertices)
+    }
+
+    #[test]
+    fn test_simplify_area_bounded_noisy_outline() {
+        let polygon = noisy_circle(10_000, 100.0, 0.05);
+        assert!(polygon.is_simple());
+
+        let simplified = polygon.simplify_area_bounded(0.001);
+        let error = (simplified.area() - polygon.area()).abs() / polygon.area();
+
+        assert!(error <= 0.001);
+        assert!(simplified.is_simple());
+        assert!(simplified.vertices.len() < polygon.vertices.len() / 2);
+    }
+
+    #[test]
+    fn test_simplify_area_bounded_keeps_corners() {
+        // Collinear edge vertices cost nothing to remove; the corners do not
+        let mut vertices = Vec::new();
+        for i in 0..10 {
+            vertices.push(Point::new(i as f64 / 10.0, 0.0));
+        }
+        vertices.push(Point::new(1.0, 0.0));
+        vertices.push(Point::new(1.0, 1.0));
+        vertices.push(Point::new(0.0, 1.0));
+        let polygon = Polygon::new(vertices);
+
+        let simplified = polygon.simplify_area_bounded(0.001);
+        assert_eq!(simplified.vertices.len(), 4);
+        assert!((simplified.area() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_simplify_collapses_redundant_edge_vertices() {
+        // 2,500 points on each side of a 10 x 10 square, starting mid-edge
+        // and a little noisy
+        let mut seed = 7;
+        let mut vertices = Vec::new();
+        let corners = [Point::new(0.0, 0.0), Point::new(10.0, 0.0), Point::new(10.0, 10.0), Point::new(0.0, 10.0)];
+        for side in 0..4 {
+            let (a, b) = (corners[side], corners[(side + 1) % 4]);
+            for k in 0..2500 {
+                let t = k as f64 / 2500.0;
+                let wobble = if k == 0 { 0.0 } else { 1e-4 * noise(&mut seed) };
+                vertices.push(Point::new(a.x + (b.x - a.x) * t + wobble, a.y + (b.y - a.y) * t + wobble));
+            }
+        }
+        vertices.rotate_left(1250);
+        let polygon = Polygon::new(vertices);
+        let simplified = polygon.simplify(0.01);
+        assert_eq!(simplified.vertices.len(), 4);
+        for c in &corners {
+            assert!(simplified.vertices.contains(c));
+        }
+        assert!((simplified.area() - polygon.area()).abs() < 0.01 * 40.0);
+
+        let circle = noisy_circle(10_000, 100.0, 0.05);
+        let simplified = circle.simplify(0.5);
+        assert!(simplified.vertices.len() < 100);
+        assert!((simplified.area() - circle.area()).abs() <= 0.5 * 2.0 * std::f64::consts::PI * 100.0);
+        // Everything within tolerance still leaves a triangle
+        assert_eq!(circle.simplify(1000.0).vertices.len(), 3);
+    }
+}
@@ -0,0 +1,451 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::memory::{Live, ObjectKind};
+use crate::region::crossing;
+use crate::{BoundingBox, Document, Point, QuadTree, Segment, Shape, ShapeChange, ShapeId};
+
+// Declared in priority order: at equal distance a vertex wins over a
+// midpoint, and so on
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SnapKind {
+    Vertex,
+    Midpoint,
+    Center,
+    Intersection,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct SnapResult {
+    pub point: Point,
+    pub kind: SnapKind,
+    pub shape: ShapeId,
+    // The second shape of an intersection, when it isn't a self-crossing
+    pub other: Option<ShapeId>,
+    pub distance: f64,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Candidate {
+    point: Point,
+    kind: SnapKind,
+    shape: ShapeId,
+}
+
+// Edge `index` of a shape with `count` edges, in world space
+#[derive(Clone, Copy, Debug)]
+struct EdgeRef {
+    shape: ShapeId,
+    index: usize,
+    count: usize,
+    segment: Segment,
+}
+
+fn distance2(a: &Point, b: &Point) -> f64 {
+    (a.x - b.x).powi(2) + (a.y - b.y).powi(2)
+}
+
+// Total order on results: distance, then kind, then shapes and position, so
+// the indexed and brute-force searches agree on ties
+fn rank(a: &SnapResult, b: &SnapResult) -> Ordering {
+    a.distance.total_cmp(&b.distance)
+        .then(a.kind.cmp(&b.kind))
+        .then(a.shape.cmp(&b.shape))
+        .then(a.other.cmp(&b.other))
+        .then(a.point.x.total_cmp(&b.point.x))
+        .then(a.point.y.total_cmp(&b.point.y))
+}
+
+fn offer(best: &mut Option<SnapResult>, result: SnapResult) {
+    if best.as_ref().is_none_or(|b| rank(&result, b) == Ordering::Less) {
+        *best = Some(result);
+    }
+}
+
+fn result(c: &Candidate, cursor: &Point) -> SnapResult {
+    SnapResult { point: c.point, kind: c.kind, shape: c.shape, other: None, distance: distance2(&c.point, cursor).sqrt() }
+}
+
+fn extract(shape: &Shape) -> (Vec<Candidate>, Vec<EdgeRef>) {
+    let id = shape.id;
+    let world = shape.world_geometry();
+    let ring = &world.vertices;
+    let n = ring.len();
+    let mut candidates: Vec<Candidate> = ring.iter().map(|&point| Candidate { point, kind: SnapKind::Vertex, shape: id }).collect();
+    let edges: Vec<EdgeRef> = (0..if n > 1 { n } else { 0 })
+        .map(|i| EdgeRef { shape: id, index: i, count: n, segment: Segment::new(ring[i], ring[(i + 1) % n]) })
+        .collect();
+    candidates.extend(edges.iter().map(|e| Candidate { point: e.segment.midpoint(), kind: SnapKind::Midpoint, shape: id }));
+    if let Ok(point) = world.try_centroid() {
+        candidates.push(Candidate { point, kind: SnapKind::Center, shape: id });
+    }
+    candidates.retain(|c| c.point.x.is_finite() && c.point.y.is_finite());
+    (candidates, edges)
+}
+
+// Nearest crossing within `bound` of the cursor between the given edges,
+// skipping each edge against its neighbours. Edges must come sorted by shape
+// and index so both searches compute every crossing the same way round.
+fn nearest_crossing(edges: &[EdgeRef], cursor: &Point, bound: f64, best: &mut Option<SnapResult>) {
+    for (i, e) in edges.iter().enumerate() {
+        for f in &edges[i + 1..] {
+            if e.shape == f.shape && (f.index == e.index + 1 || (e.index == 0 && f.index == e.count - 1)) {
+                continue;
+            }
+            if let Some(point) = crossing(&e.segment.a, &e.segment.b, &f.segment.a, &f.segment.b) {
+                let distance = distance2(&point, cursor).sqrt();
+                if distance <= bound {
+                    let other = (f.shape != e.shape).then_some(f.shape);
+                    offer(best, SnapResult { point, kind: SnapKind::Intersection, shape: e.shape, other, distance });
+                }
+            }
+        }
+    }
+}
+
+fn near(cursor: &Point, r: f64) -> BoundingBox {
+    BoundingBox::new(Point::new(cursor.x - r, cursor.y - r), Point::new(cursor.x + r, cursor.y + r))
+}
+
+fn sort_edges(edges: &mut [EdgeRef]) {
+    edges.sort_by(|a, b| a.shape.cmp(&b.shape).then(a.index.cmp(&b.index)));
+}
+
+// Edges of every shape in a quadtree, built the first time an intersection
+// is looked for
+struct EdgeIndex {
+    tree: QuadTree,
+    edges: HashMap<u32, EdgeRef>,
+    by_shape: HashMap<ShapeId, Vec<u32>>,
+    next: u32,
+}
+
+impl EdgeIndex {
+    fn build(doc: &Document) -> EdgeIndex {
+        let all: Vec<EdgeRef> = doc.shapes().flat_map(|s| extract(s).1).collect();
+        let boxes: Vec<BoundingBox> = all.iter().map(|e| BoundingBox::from_points(&[e.segment.a, e.segment.b])).collect();
+        let bounds = boxes.iter().copied().reduce(|a, b| a.union(&b)).unwrap_or_else(|| BoundingBox::from_points(&[]));
+        let mut index = EdgeIndex { tree: QuadTree::new(bounds), edges: HashMap::new(), by_shape: HashMap::new(), next: 0 };
+        for e in all {
+            index.insert(e);
+        }
+        index
+    }
+
+    fn insert(&mut self, e: EdgeRef) {
+        let id = self.next;
+        self.next += 1;
+        self.tree.insert(id, BoundingBox::from_points(&[e.segment.a, e.segment.b]));
+        self.edges.insert(id, e);
+        self.by_shape.entry(e.shape).or_default().push(id);
+    }
+
+    fn replace(&mut self, shape: ShapeId, edges: Vec<EdgeRef>) {
+        for id in self.by_shape.remove(&shape).unwrap_or_default() {
+            self.tree.remove(id);
+            self.edges.remove(&id);
+        }
+        for e in edges {
+            self.insert(e);
+        }
+    }
+
+    fn query(&self, area: &BoundingBox) -> Vec<EdgeRef> {
+        let mut edges: Vec<EdgeRef> = self.tree.query(area).iter().map(|id| self.edges[id]).collect();
+        sort_edges(&mut edges);
+        edges
+    }
+}
+
+// Every precomputable candidate in a packed 2-d tree: each range of `tree`
+// holds its median at the middle, split on x at even depths and y at odd
+// ones. Candidates of edited shapes are marked dead and their replacements
+// kept loose, searched linearly, until repacking pays off.
+struct SnapIndex {
+    candidates: Vec<Candidate>,
+    live: Vec<bool>,
+    tree: Vec<usize>,
+    loose: Vec<usize>,
+    dead: usize,
+    by_shape: HashMap<ShapeId, Vec<usize>>,
+    edges: Option<EdgeIndex>,
+}
+
+fn axis(p: &Point, depth: usize) -> f64 {
+    if depth & 1 == 0 { p.x } else { p.y }
+}
+
+fn pack(slots: &mut [usize], candidates: &[Candidate], depth: usize) {
+    if slots.len() <= 1 {
+        return;
+    }
+    let mid = slots.len() / 2;
+    slots.select_nth_unstable_by(mid, |&a, &b| axis(&candidates[a].point, depth).total_cmp(&axis(&candidates[b].point, depth)));
+    let (left, right) = slots.split_at_mut(mid);
+    pack(left, candidates, depth + 1);
+    pack(&mut right[1..], candidates, depth + 1);
+}
+
+impl SnapIndex {
+    // Estimated heap bytes, edge index included
+    fn footprint(&self) -> usize {
+        let slots = self.candidates.len() * (std::mem::size_of::<Candidate>() + std::mem::size_of::<bool>() + 2 * std::mem::size_of::<usize>());
+        let edges = self.edges.as_ref().map_or(0, |e| e.tree.footprint() + e.edges.len() * (std::mem::size_of::<(u32, EdgeRef)>() + 2 * std::mem::size_of::<u32>()));
+        slots + self.loose.len() * std::mem::size_of::<usize>() + edges
+    }
+
+    fn new(candidates: Vec<Candidate>, edges: Option<EdgeIndex>) -> SnapIndex {
+        let mut by_shape: HashMap<ShapeId, Vec<usize>> = HashMap::new();
+        for (i, c) in candidates.iter().enumerate() {
+            by_shape.entry(c.shape).or_default().push(i);
+        }
+        let mut tree: Vec<usize> = (0..candidates.len()).collect();
+        pack(&mut tree, &candidates, 0);
+        SnapIndex { live: vec![true; candidates.len()], candidates, tree, loose: Vec::new(), dead: 0, by_shape, edges }
+    }
+
+    fn replace(&mut self, shape: ShapeId, candidates: Vec<Candidate>) {
+        for slot in self.by_shape.remove(&shape).unwrap_or_default() {
+            self.live[slot] = false;
+            self.dead += 1;
+        }
+        for c in candidates {
+            let slot = self.candidates.len();
+            self.candidates.push(c);
+            self.live.push(true);
+            self.loose.push(slot);
+            self.by_shape.entry(shape).or_default().push(slot);
+        }
+    }
+
+    // Past this point a query spends more time on loose and dead slots than
+    // a repack costs
+    fn needs_repack(&self) -> bool {
+        self.loose.len() > 64 + self.tree.len() / 8 || self.dead > self.tree.len() / 2 + 64
+    }
+
+    fn repack(self) -> SnapIndex {
+        let live: Vec<Candidate> = self.candidates.iter().zip(&self.live).filter(|(_, &l)| l).map(|(c, _)| *c).collect();
+        SnapIndex::new(live, self.edges)
+    }
+
+    fn search(&self, range: &[usize], depth: usize, cursor: &Point, bound: f64, best: &mut Option<SnapResult>) {
+        if range.is_empty() {
+            return;
+        }
+        let mid = range.len() / 2;
+        let slot = range[mid];
+        let c = &self.candidates[slot];
+        if self.live[slot] && distance2(&c.point, cursor) <= bound * bound {
+            offer(best, result(c, cursor));
+        }
+        let diff = axis(cursor, depth) - axis(&c.point, depth);
+        let (first, second) = if diff < 0.0 { (&range[..mid], &range[mid + 1..]) } else { (&range[mid + 1..], &range[..mid]) };
+        self.search(first, depth + 1, cursor, bound, best);
+        let reach = best.as_ref().map_or(bound, |b| b.distance);
+        if diff.abs() <= reach {
+            self.search(second, depth + 1, cursor, bound, best);
+        }
+    }
+}
+
+// Finds the snap point nearest the cursor among every shape's vertices, edge
+// midpoints, centroid and edge crossings. Without an index each snap scans
+// the whole document; `rebuild_index` precomputes everything but the
+// crossings, which are looked for through an edge quadtree only within the
+// distance of the best candidate found. The index then follows the document
+// through `apply_changes`, fed what `Document::take_changes` returns.
+// Snapping sees committed geometry, not transform previews.
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct Snapper {
+    index: Option<SnapIndex>,
+    // Candidates extracted from shapes, for checking incremental updates
+    pub(crate) extracted: usize,
+    live: Live<{ ObjectKind::Snapper as usize }>,
+}
+
+impl Snapper {
+    pub fn new() -> Snapper {
+        Snapper::default()
+    }
+
+    pub fn rebuild_index(&mut self, doc: &Document) {
+        let mut candidates = Vec::new();
+        for shape in doc.shapes() {
+            candidates.extend(extract(shape).0);
+        }
+        self.extracted += candidates.len();
+        self.index = Some(SnapIndex::new(candidates, None));
+        self.recount();
+    }
+
+    // Re-extracts just the shapes in `changes`; does nothing before the
+    // first `rebuild_index`
+    pub fn apply_changes(&mut self, doc: &Document, changes: &[ShapeChange]) {
+        let Some(mut index) = self.index.take() else {
+            return;
+        };
+        for change in changes {
+            let (candidates, edges) = doc.shape(change.id).map(extract).unwrap_or_default();
+            self.extracted += candidates.len();
+            index.replace(change.id, candidates);
+            if let Some(edge_index) = index.edges.as_mut() {
+                edge_index.replace(change.id, edges);
+            }
+        }
+        self.index = Some(if index.needs_repack() { index.repack() } else { index });
+        self.recount();
+    }
+
+    fn recount(&self) {
+        self.live.set_bytes(self.index.as_ref().map_or(0, SnapIndex::footprint));
+    }
+
+    pub fn snap(&mut self, doc: &Document, cursor: &Point, radius: f64) -> Option<SnapResult> {
+        if !(radius.is_finite() && radius > 0.0) {
+            return None;
+        }
+        let mut best = None;
+        match self.index.as_mut() {
+            Some(index) => {
+                index.search(&index.tree, 0, cursor, radius, &mut best);
+                for &slot in &index.loose {
+                    if index.live[slot] && distance2(&index.candidates[slot].point, cursor) <= radius * radius {
+                        offer(&mut best, result(&index.candidates[slot], cursor));
+                    }
+                }
+                let bound = best.as_ref().map_or(radius, |b| b.distance);
+                if bound > 0.0 {
+                    let edges = index.edges.get_or_insert_with(|| EdgeIndex::build(doc)).query(&near(cursor, bound));
+                    nearest_crossing(&edges, cursor, bound, &mut best);
+                }
+            }
+            None => {
+                let mut edges = Vec::new();
+                for shape in doc.shapes() {
+                    let (candidates, shape_edges) = extract(shape);
+                    for c in candidates.iter().filter(|c| distance2(&c.point, cursor) <= radius * radius) {
+                        offer(&mut best, result(c, cursor));
+                    }
+                    edges.extend(shape_edges);
+                }
+                let bound = best.as_ref().map_or(radius, |b| b.distance);
+                let area = near(cursor, bound);
+                edges.retain(|e| BoundingBox::from_points(&[e.segment.a, e.segment.b]).intersects(&area));
+                sort_edges(&mut edges);
+                nearest_crossing(&edges, cursor, bound, &mut best);
+            }
+        }
+        // The edge index may have just been built
+        self.recount();
+        best
+    }
+}
+
+#[wasm_bindgen]
+impl Snapper {
+    #[wasm_bindgen(constructor)]
+    pub fn new_js() -> Snapper {
+        Snapper::new()
+    }
+
+    #[wasm_bindgen(js_name = rebuild_index)]
+    pub fn rebuild_index_js(&mut self, doc: &Document) {
+        self.rebuild_index(doc)
+    }
+
+    // `changes` as returned by `Document.take_changes`
+    #[wasm_bindgen(js_name = apply_changes)]
+    pub fn apply_changes_js(&mut self, doc: &Document, changes: &JsValue) -> Result<(), JsValue> {
+        let changes: Vec<ShapeChange> = crate::from_js(changes)?;
+        self.apply_changes(doc, &changes);
+        Ok(())
+    }
+
+    // null when nothing is within `radius`
+    #[wasm_bindgen(js_name = snap)]
+    pub fn snap_js(&mut self, doc: &Document, x: f64, y: f64, radius: f64) -> Result<JsValue, JsValue> {
+        crate::to_js(&self.snap(doc, &Point::new(x, y), radius))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Matrix, Polygon};
+
+    fn random_document(count: usize) -> Document {
+        let mut seed: u64 = 7;
+        let mut next = || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (seed >> 11) as f64 / (1u64 << 53) as f64
+        };
+        let mut doc = Document::new();
+        for _ in 0..count {
+            let (x, y) = (next() * 1000.0, next() * 1000.0);
+            let sides = 3 + (next() * 3.0) as usize;
+            let vertices = (0..sides).map(|k| {
+                let a = std::f64::consts::TAU * (k as f64 + next() * 0.5) / sides as f64;
+                let r = 2.0 + next() * 6.0;
+                Point::new(x + r * a.cos(), y + r * a.sin())
+            }).collect();
+            doc.add_shape(Polygon::new(vertices));
+        }
+        doc
+    }
+
+    #[test]
+    fn test_index_matches_brute_force() {
+        let doc = random_document(10_000);
+        let mut brute = Snapper::new();
+        let mut indexed = Snapper::new();
+        indexed.rebuild_index(&doc);
+        let mut seed: u64 = 3;
+        let mut found = 0;
+        for _ in 0..100 {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let x = (seed >> 11) as f64 / (1u64 << 53) as f64 * 1000.0;
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let y = (seed >> 11) as f64 / (1u64 << 53) as f64 * 1000.0;
+            let (cursor, radius) = (Point::new(x, y), 1.0 + (x as usize % 4) as f64);
+            let expected = brute.snap(&doc, &cursor, radius);
+            found += expected.is_some() as usize;
+            assert_eq!(indexed.snap(&doc, &cursor, radius), expected);
+        }
+        assert!(found > 15);
+    }
+
+    #[test]
+    fn test_edit_updates_only_its_candidates() {
+        let mut doc = Document::new();
+        for i in 0..100 {
+            let x = (i % 10) as f64 * 10.0;
+            let y = (i / 10) as f64 * 10.0;
+            doc.add_shape(Polygon::new(vec![Point::new(x, y), Point::new(x + 4.0, y), Point::new(x + 4.0, y + 4.0), Point::new(x, y + 4.0)]));
+        }
+        doc.take_changes();
+        let mut snapper = Snapper::new();
+        snapper.rebuild_index(&doc);
+        // Four vertices, four midpoints and a centre per square
+        assert_eq!(snapper.extracted, 900);
+
+        doc.set_transform(0, Matrix::translate(500.0, 500.0)).unwrap();
+        let changes = doc.take_changes();
+        snapper.apply_changes(&doc, &changes);
+        assert_eq!(snapper.extracted, 909);
+
+        let hit = snapper.snap(&doc, &Point::new(502.1, 502.0), 1.0).unwrap();
+        assert_eq!((hit.kind, hit.shape), (SnapKind::Center, 0));
+        assert!(snapper.snap(&doc, &Point::new(0.1, 0.1), 0.5).is_none_or(|h| h.shape != 0));
+        // Crossings still come from the moved geometry
+        let other = doc.add_shape(Polygon::new(vec![Point::new(502.0, 498.0), Point::new(503.0, 498.0), Point::new(503.0, 510.0), Point::new(502.0, 510.0)]));
+        let changes = doc.take_changes();
+        snapper.apply_changes(&doc, &changes);
+        let hit = snapper.snap(&doc, &Point::new(503.0, 500.2), 0.5).unwrap();
+        assert_eq!((hit.kind, hit.shape, hit.other), (SnapKind::Intersection, 0, Some(other)));
+    }
+}
@@ -0,0 +1,440 @@
+use std::collections::{HashMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{BoundingBox, Point};
+
+// Items a leaf holds before it splits
+const NODE_CAPACITY: usize = 8;
+const MAX_DEPTH: usize = 12;
+// Items a rebalance packs per insert or remove, so it is spread over many
+// edits instead of stalling one
+const REBALANCE_STEP: usize = 4096;
+
+type Item = (u32, BoundingBox);
+
+#[derive(Clone, Debug)]
+struct Node {
+    bounds: BoundingBox,
+    // Where the node divides into quadrants: the centre, unless the node
+    // was packed around its items' medians
+    split: Point,
+    // Items that don't fit entirely inside one child stay here
+    items: Vec<Item>,
+    children: Option<Box<[Node; 4]>>,
+}
+
+fn quadrants(bounds: &BoundingBox, c: &Point) -> [BoundingBox; 4] {
+    [
+        BoundingBox::new(bounds.min, *c),
+        BoundingBox::new(Point::new(c.x, bounds.min.y), Point::new(bounds.max.x, c.y)),
+        BoundingBox::new(Point::new(bounds.min.x, c.y), Point::new(c.x, bounds.max.y)),
+        BoundingBox::new(*c, bounds.max),
+    ]
+}
+
+fn child_for(node: &Node, bbox: &BoundingBox) -> Option<usize> {
+    quadrants(&node.bounds, &node.split).iter().position(|q| q.contains(bbox))
+}
+
+impl Node {
+    fn new(bounds: BoundingBox) -> Node {
+        Node { split: bounds.center(), bounds, items: Vec::new(), children: None }
+    }
+
+    // Returns the depth the item ended up at and how many items that node
+    // then holds
+    fn insert(&mut self, id: u32, bbox: BoundingBox, depth: usize) -> (usize, usize) {
+        if let Some(i) = self.children.as_ref().and(child_for(self, &bbox)) {
+            return self.children.as_mut().unwrap()[i].insert(id, bbox, depth + 1);
+        }
+
+        self.items.push((id, bbox));
+        if self.children.is_none() && self.items.len() > NODE_CAPACITY && depth < MAX_DEPTH {
+            let mut children = Box::new(quadrants(&self.bounds, &self.split).map(Node::new));
+            for (id, bbox) in std::mem::take(&mut self.items) {
+                match child_for(self, &bbox) {
+                    Some(i) => {
+                        children[i].insert(id, bbox, depth + 1);
+                    }
+                    None => self.items.push((id, bbox)),
+                }
+            }
+            self.children = Some(children);
+            return match child_for(self, &bbox) {
+                Some(i) => (depth + 1, self.children.as_ref().unwrap()[i].items.len()),
+                None => (depth, self.items.len()),
+            };
+        }
+        (depth, self.items.len())
+    }
+
+    // `bbox` must be the box the item was inserted with
+    fn remove(&mut self, id: u32, bbox: &BoundingBox) -> bool {
+        if let Some(i) = self.children.as_ref().and(child_for(self, bbox)) {
+            return self.children.as_mut().unwrap()[i].remove(id, bbox);
+        }
+        match self.items.iter().position(|(item, _)| *item == id) {
+            Some(i) => {
+                self.items.swap_remove(i);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn query(&self, area: &BoundingBox, out: &mut Vec<u32>) {
+        out.extend(self.items.iter().filter(|(_, b)| b.intersects(area)).map(|(id, _)| *id));
+        if let Some(children) = &self.children {
+            for child in children.iter().filter(|c| c.bounds.intersects(area)) {
+                child.query(area, out);
+            }
+        }
+    }
+
+    fn tally(&self, depth: usize, stats: &mut QuadTreeStats) {
+        stats.nodes += 1;
+        stats.max_depth = stats.max_depth.max(depth);
+        match &self.children {
+            Some(children) => children.iter().for_each(|c| c.tally(depth + 1, stats)),
+            None => {
+                if stats.depth_histogram.len() <= depth {
+                    stats.depth_histogram.resize(depth + 1, 0);
+                }
+                stats.depth_histogram[depth] += 1;
+                stats.avg_items_per_leaf += self.items.len() as f64;
+            }
+        }
+    }
+}
+
+// A node for `items`, divided at the median of their centres across x and
+// then y, the sort-tile step of STR packing with one tile boundary per
+// axis, so each quadrant gets about a quarter of them. Returns what each
+// child is to hold as well, unless the node stays a leaf.
+fn pack(bounds: BoundingBox, mut items: Vec<Item>, depth: usize) -> (Node, Option<[Vec<Item>; 4]>) {
+    let mut node = Node::new(bounds);
+    if items.len() <= NODE_CAPACITY || depth >= MAX_DEPTH {
+        node.items = items;
+        return (node, None);
+    }
+    let (mid, count) = (items.len() / 2, items.len());
+    items.select_nth_unstable_by(mid, |a, b| a.1.center().x.total_cmp(&b.1.center().x));
+    let x = items[mid].1.center().x;
+    items.select_nth_unstable_by(mid, |a, b| a.1.center().y.total_cmp(&b.1.center().y));
+    let y = items[mid].1.center().y;
+    node.split = Point::new(x.clamp(bounds.min.x, bounds.max.x), y.clamp(bounds.min.y, bounds.max.y));
+    let mut children: [Vec<Item>; 4] = Default::default();
+    for item in items {
+        match child_for(&node, &item.1) {
+            Some(i) => children[i].push(item),
+            None => node.items.push(item),
+        }
+    }
+    // Items piled on one point can't be told apart by splitting
+    if let Some(i) = children.iter().position(|c| c.len() == count) {
+        node.items = std::mem::take(&mut children[i]);
+        node.split = bounds.center();
+        return (node, None);
+    }
+    node.children = Some(Box::new(quadrants(&bounds, &node.split).map(Node::new)));
+    (node, Some(children))
+}
+
+// Bulk load under way: nodes still to pack, each with its items and the
+// child indices down to it from the root
+#[derive(Clone, Debug)]
+struct Packing {
+    root: Node,
+    queue: VecDeque<(Vec<usize>, Vec<Item>)>,
+    // Boxes as of the start for ids edited since, None for ids added since
+    touched: HashMap<u32, Option<BoundingBox>>,
+}
+
+impl Packing {
+    fn new(items: Vec<Item>) -> Packing {
+        let bounds = items.iter().map(|(_, b)| *b).reduce(|a, b| a.union(&b)).unwrap_or_else(|| BoundingBox::from_points(&[]));
+        Packing { root: Node::new(bounds), queue: VecDeque::from([(Vec::new(), items)]), touched: HashMap::new() }
+    }
+
+    // Packs nodes until about `budget` items have been placed; true once
+    // the tree is complete
+    fn step(&mut self, budget: usize) -> bool {
+        let mut placed = 0;
+        while placed < budget {
+            let Some((path, items)) = self.queue.pop_front() else {
+                break;
+            };
+            placed += items.len();
+            let mut slot = &mut self.root;
+            for &i in &path {
+                slot = &mut slot.children.as_mut().unwrap()[i];
+            }
+            let (node, children) = pack(slot.bounds, items, path.len());
+            *slot = node;
+            for (i, items) in children.into_iter().flatten().enumerate().filter(|(_, c)| !c.is_empty()) {
+                let mut to = path.clone();
+                to.push(i);
+                self.queue.push_back((to, items));
+            }
+        }
+        self.queue.is_empty()
+    }
+}
+
+// Shape of the tree, for seeing how balanced it is
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct QuadTreeStats {
+    pub items: usize,
+    pub nodes: usize,
+    pub max_depth: usize,
+    // Leaves at each depth, the root's first
+    pub depth_histogram: Vec<usize>,
+    pub avg_items_per_leaf: f64,
+    // Rebalances finished so far, and whether one is under way
+    pub rebalances: usize,
+    pub rebalancing: bool,
+}
+
+// Region quadtree over item bounding boxes, keyed by id. Items outside the
+// root bounds are kept at the root, so the tree stays correct (if slower)
+// when geometry grows past the extent it was built for.
+//
+// Inserts that land badly, outside the root or in a full leaf too deep to
+// split, are counted; once they make up a sixteenth of the tree it is
+// repacked as by `bulk_build`, a step at a time on later inserts and
+// removes. Edits meanwhile go to the old tree, which answers queries until
+// the new one is done and has caught up with them.
+#[derive(Clone, Debug)]
+pub struct QuadTree {
+    root: Node,
+    entries: HashMap<u32, BoundingBox>,
+    strain: usize,
+    packing: Option<Box<Packing>>,
+    rebalances: usize,
+}
+
+impl QuadTree {
+    pub fn new(bounds: BoundingBox) -> QuadTree {
+        QuadTree { root: Node::new(bounds), entries: HashMap::new(), strain: 0, packing: None, rebalances: 0 }
+    }
+
+    // Tree packed around `items` in one go, much shallower than inserting
+    // them one by one when they bunch up. The root covers the items; a
+    // repeated id keeps its last box.
+    pub fn bulk_build(items: &[(u32, BoundingBox)]) -> QuadTree {
+        let entries: HashMap<u32, BoundingBox> = items.iter().copied().collect();
+        let mut packing = Packing::new(entries.iter().map(|(&id, &b)| (id, b)).collect());
+        packing.step(usize::MAX);
+        QuadTree { root: packing.root, entries, strain: 0, packing: None, rebalances: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // Estimated heap bytes: each item sits in one node and in the id map,
+    // and leaves run about half full
+    pub(crate) fn footprint(&self) -> usize {
+        let item = std::mem::size_of::<Item>();
+        let nodes = 1 + 2 * self.entries.len() / NODE_CAPACITY;
+        self.entries.len() * 2 * item + nodes * std::mem::size_of::<Node>()
+    }
+
+    // Inserting an id that is already present replaces its box
+    pub fn insert(&mut self, id: u32, bbox: BoundingBox) {
+        self.remove(id);
+        self.note(id);
+        let (depth, count) = self.root.insert(id, bbox, 0);
+        if !self.root.bounds.contains(&bbox) || (depth >= MAX_DEPTH && count > 2 * NODE_CAPACITY) {
+            self.strain += 1;
+        }
+        self.entries.insert(id, bbox);
+        self.advance();
+    }
+
+    pub fn remove(&mut self, id: u32) -> bool {
+        self.note(id);
+        let removed = match self.entries.remove(&id) {
+            Some(bbox) => self.root.remove(id, &bbox),
+            None => false,
+        };
+        self.advance();
+        removed
+    }
+
+    // Keeps the box an id had when a rebalance started, before the first
+    // edit to it since
+    fn note(&mut self, id: u32) {
+        if let Some(packing) = self.packing.as_mut() {
+            packing.touched.entry(id).or_insert_with(|| self.entries.get(&id).copied());
+        }
+    }
+
+    fn advance(&mut self) {
+        if self.packing.is_none() && self.strain > 8 * NODE_CAPACITY + self.entries.len() / 16 {
+            let items = self.entries.iter().map(|(&id, &b)| (id, b)).collect();
+            self.packing = Some(Box::new(Packing::new(items)));
+        }
+        let Some(packing) = self.packing.as_mut() else {
+            return;
+        };
+        if packing.step(REBALANCE_STEP) {
+            let packing = self.packing.take().unwrap();
+            let mut root = packing.root;
+            for (id, before) in packing.touched {
+                if let Some(b) = before {
+                    root.remove(id, &b);
+                }
+                if let Some(now) = self.entries.get(&id) {
+                    root.insert(id, *now, 0);
+                }
+            }
+            self.root = root;
+            self.strain = 0;
+            self.rebalances += 1;
+        }
+    }
+
+    // Ids whose boxes touch `area`, in ascending order
+    pub fn query(&self, area: &BoundingBox) -> Vec<u32> {
+        let mut out = Vec::new();
+        self.root.query(area, &mut out);
+        out.sort_unstable();
+        out
+    }
+
+    pub fn query_point(&self, p: &Point) -> Vec<u32> {
+        self.query(&BoundingBox::new(*p, *p))
+    }
+
+    pub fn stats(&self) -> QuadTreeStats {
+        let mut stats = QuadTreeStats {
+            items: self.entries.len(),
+            nodes: 0,
+            max_depth: 0,
+            depth_histogram: Vec::new(),
+            avg_items_per_leaf: 0.0,
+            rebalances: self.rebalances,
+            rebalancing: self.packing.is_some(),
+        };
+        self.root.tally(0, &mut stats);
+        stats.avg_items_per_leaf /= stats.depth_histogram.iter().sum::<usize>() as f64;
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square_at(x: f64, y: f64, size: f64) -> BoundingBox {
+        BoundingBox::new(Point::new(x, y), Point::new(x + size, y + size))
+    }
+
+    #[test]
+    fn test_query_matches_brute_force_after_updates() {
+        let mut tree = QuadTree::new(square_at(0.0, 0.0, 100.0));
+        let mut boxes: Vec<BoundingBox> = (0..400).map(|i| {
+            let (x, y) = ((i * 37 % 97) as f64, (i * 53 % 89) as f64);
+            square_at(x, y, 1.0 + (i % 5) as f64)
+        }).collect();
+        for (i, b) in boxes.iter().enumerate() {
+            tree.insert(i as u32, *b);
+        }
+
+        // Move some, including one outside the root bounds, and drop others
+        for i in (0..400).step_by(7) {
+            boxes[i] = square_at(boxes[i].min.y, boxes[i].min.x, 2.0);
+            tree.insert(i as u32, boxes[i]);
+        }
+        boxes[1] = square_at(150.0, 150.0, 1.0);
+        tree.insert(1, boxes[1]);
+        for i in (0..400).step_by(11) {
+            assert!(tree.remove(i as u32));
+        }
+        assert!(!tree.remove(0));
+
+        for area in [square_at(10.0, 10.0, 20.0), square_at(0.0, 0.0, 200.0), square_at(149.0, 149.0, 1.5)] {
+            let expected: Vec<u32> = (0..400u32).filter(|&i| i % 11 != 0 && boxes[i as usize].intersects(&area)).collect();
+            assert_eq!(tree.query(&area), expected);
+        }
+        assert_eq!(tree.query_point(&Point::new(150.5, 150.5)), vec![1]);
+    }
+
+    fn clustered(count: usize, clusters: usize, radius: f64, seed: u64) -> Vec<(u32, BoundingBox)> {
+        let mut state = seed;
+        let mut next = move || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (state >> 11) as f64 / (1u64 << 53) as f64
+        };
+        let centres: Vec<(f64, f64)> = (0..clusters).map(|_| (radius + next() * (1000.0 - 2.0 * radius), radius + next() * (1000.0 - 2.0 * radius))).collect();
+        (0..count).map(|i| {
+            let (cx, cy) = centres[i % clusters];
+            let (r, a) = (radius * next().sqrt(), next() * std::f64::consts::TAU);
+            (i as u32, square_at(cx + r * a.cos(), cy + r * a.sin(), 0.001 + next() * 0.01))
+        }).collect()
+    }
+
+    fn brute_force(items: &[(u32, BoundingBox)], area: &BoundingBox) -> Vec<u32> {
+        let mut ids: Vec<u32> = items.iter().filter(|(_, b)| b.intersects(area)).map(|(id, _)| *id).collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    #[test]
+    fn test_bulk_build_is_shallower_with_the_same_answers() {
+        let items = clustered(100_000, 20, 5.0, 7);
+        let bulk = QuadTree::bulk_build(&items);
+        let mut incremental = QuadTree::new(square_at(0.0, 0.0, 1000.0));
+        for (id, b) in &items {
+            incremental.insert(*id, *b);
+        }
+        let (packed, grown) = (bulk.stats(), incremental.stats());
+        assert_eq!((packed.items, grown.items, grown.rebalances), (100_000, 100_000, 0));
+        assert!(packed.max_depth + 3 <= grown.max_depth, "{packed:?} {grown:?}");
+
+        let (cx, cy) = (items[3].1.min.x, items[3].1.min.y);
+        for area in [square_at(cx - 2.0, cy - 2.0, 4.0), square_at(cx, cy, 0.05), square_at(0.0, 0.0, 500.0), square_at(0.0, 0.0, 1000.0)] {
+            let expected = brute_force(&items, &area);
+            assert_eq!(bulk.query(&area), expected);
+            assert_eq!(incremental.query(&area), expected);
+        }
+    }
+
+    #[test]
+    fn test_pathological_inserts_rebalance_in_steps() {
+        // Spread-out shapes, then a pile of tiny ones in one spot
+        let mut items = clustered(20_000, 50, 10.0, 5);
+        items.extend(clustered(30_000, 1, 0.05, 3).into_iter().map(|(id, b)| (id + 20_000, b)));
+        let mut tree = QuadTree::new(square_at(0.0, 0.0, 1000.0));
+        let area = square_at(items[20_000].1.min.x - 0.02, items[20_000].1.min.y - 0.02, 0.04);
+        let mean_depth = |s: &QuadTreeStats| s.depth_histogram.iter().enumerate().map(|(d, n)| d * n).sum::<usize>() as f64 / s.depth_histogram.iter().sum::<usize>() as f64;
+        let mut before = None;
+        for (n, (id, b)) in items.iter().enumerate() {
+            tree.insert(*id, *b);
+            let stats = tree.stats();
+            if stats.rebalancing && before.is_none() {
+                // Still answered by the old tree while the new one is packed
+                assert_eq!(tree.query(&area), brute_force(&items[..=n], &area));
+                before = Some(stats);
+            }
+        }
+        let (before, after) = (before.unwrap(), tree.stats());
+        assert!(after.rebalances >= 1 && !after.rebalancing, "{after:?}");
+        assert!(mean_depth(&after) + 1.0 < mean_depth(&before), "{before:?} {after:?}");
+        assert_eq!(tree.query(&area), brute_force(&items, &area));
+
+        // Edits after the swap keep working on the packed tree
+        for i in (0..items.len()).step_by(3) {
+            items[i].1 = square_at(items[i].1.min.x + 0.01, items[i].1.min.y, 0.002);
+            tree.insert(items[i].0, items[i].1);
+        }
+        assert_eq!(tree.query(&area), brute_force(&items, &area));
+        assert_eq!(tree.len(), items.len());
+    }
+}
@@ -0,0 +1,493 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::{js_error, CadError, CubicBezier, Document, Point};
+
+pub type CurveId = u32;
+
+// Render points per knot span
+const SPAN_SAMPLES: usize = 16;
+
+// (1 - t)·a + t·b
+fn mix(a: &Point, b: &Point, t: f64) -> Point {
+    Point::new(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t)
+}
+
+fn distance(a: &Point, b: &Point) -> f64 {
+    (a.x - b.x).hypot(a.y - b.y)
+}
+
+// B-spline with a clamped knot vector, so it starts at the first control
+// point and ends at the last. A cubic Bézier is the degree 3 spline on
+// knots [0, 0, 0, 0, 1, 1, 1, 1].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BSpline {
+    pub degree: usize,
+    pub control_points: Vec<Point>,
+    // Non-decreasing, control_points.len() + degree + 1 of them
+    pub knots: Vec<f64>,
+}
+
+impl BSpline {
+    // Uniform clamped knots over [0, 1]
+    pub fn clamped(degree: usize, control_points: Vec<Point>) -> Result<BSpline, CadError> {
+        let n = control_points.len();
+        if degree == 0 || n <= degree {
+            return Err(CadError::InvalidInput(format!("a degree {} spline needs more than {} control points, got {}", degree, degree, n)));
+        }
+        let spans = n - degree;
+        let knots = (0..n + degree + 1).map(|i| (i.saturating_sub(degree).min(spans)) as f64 / spans as f64).collect();
+        Ok(BSpline { degree, control_points, knots })
+    }
+
+    pub fn from_bezier(bezier: &CubicBezier) -> BSpline {
+        BSpline { degree: 3, control_points: vec![bezier.p0, bezier.p1, bezier.p2, bezier.p3], knots: vec![0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0] }
+    }
+
+    pub fn domain(&self) -> (f64, f64) {
+        (self.knots[self.degree], self.knots[self.control_points.len()])
+    }
+
+    // Index k of the non-empty span [knots[k], knots[k+1]) holding `u`, the
+    // last one for the end of the domain
+    fn span(&self, u: f64) -> usize {
+        let (p, n) = (self.degree, self.control_points.len());
+        let mut k = (p..n).rev().find(|&k| self.knots[k] <= u).unwrap_or(p);
+        while k > p && self.knots[k] == self.knots[k + 1] {
+            k -= 1;
+        }
+        k
+    }
+
+    // By de Boor, `u` clamped to the domain
+    pub fn point_at(&self, u: f64) -> Point {
+        let (lo, hi) = self.domain();
+        let u = u.clamp(lo, hi);
+        let (p, k) = (self.degree, self.span(u));
+        let mut d: Vec<Point> = self.control_points[k - p..=k].to_vec();
+        for r in 1..=p {
+            for j in (r..=p).rev() {
+                let i = j + k - p;
+                let alpha = (u - self.knots[i]) / (self.knots[i + p + 1 - r] - self.knots[i]);
+                d[j] = mix(&d[j - 1], &d[j], alpha);
+            }
+        }
+        d[p]
+    }
+
+    // Parameter interval over which control point `i` moves the curve
+    fn support(&self, i: usize) -> (f64, f64) {
+        (self.knots[i], self.knots[i + self.degree + 1])
+    }
+
+    // Adds knot `u` once, by Boehm's algorithm: one more control point, the
+    // same degree, the same curve
+    pub fn insert_knot(&mut self, u: f64) -> Result<(), CadError> {
+        let (lo, hi) = self.domain();
+        let p = self.degree;
+        if !(u > lo && u < hi) {
+            return Err(CadError::InvalidInput(format!("knot {} is outside the open domain ({}, {})", u, lo, hi)));
+        }
+        if self.knots.iter().filter(|&&k| k == u).count() >= p {
+            return Err(CadError::InvalidInput(format!("knot {} already has multiplicity {}", u, p)));
+        }
+        let k = self.span(u);
+        let old = &self.control_points;
+        let points: Vec<Point> = (0..=old.len()).map(|i| {
+            if i + p <= k {
+                old[i]
+            } else if i > k {
+                old[i - 1]
+            } else {
+                mix(&old[i - 1], &old[i], (u - self.knots[i]) / (self.knots[i + p] - self.knots[i]))
+            }
+        }).collect();
+        self.control_points = points;
+        self.knots.insert(k + 1, u);
+        Ok(())
+    }
+
+    // The spline with knot index `r` taken out once (Tiller's removal),
+    // and the old control points it had to move: first..=last
+    fn without_knot(&self, r: usize) -> (BSpline, usize, usize) {
+        let (p, u) = (self.degree, self.knots[r]);
+        let s = self.knots.iter().filter(|&&k| k == u).count();
+        let (first, last) = (r - p, r - s);
+        let off = first - 1;
+        let pts = &self.control_points;
+        let mut temp = vec![Point::new(0.0, 0.0); last + 2 - off];
+        temp[0] = pts[off];
+        temp[last + 1 - off] = pts[last + 1];
+        let (mut i, mut j, mut ii, mut jj) = (first, last, 1, last - off);
+        while j > i {
+            let alfi = (u - self.knots[i]) / (self.knots[i + p + 1] - self.knots[i]);
+            let alfj = (u - self.knots[j]) / (self.knots[j + p + 1] - self.knots[j]);
+            temp[ii] = Point::new((pts[i].x - (1.0 - alfi) * temp[ii - 1].x) / alfi, (pts[i].y - (1.0 - alfi) * temp[ii - 1].y) / alfi);
+            temp[jj] = Point::new((pts[j].x - alfj * temp[jj + 1].x) / (1.0 - alfj), (pts[j].y - alfj * temp[jj + 1].y) / (1.0 - alfj));
+            i += 1;
+            ii += 1;
+            j -= 1;
+            jj -= 1;
+        }
+        let mut points = pts.clone();
+        let (mut i, mut j) = (first, last);
+        while j > i {
+            points[i] = temp[i - off];
+            points[j] = temp[j - off];
+            i += 1;
+            j -= 1;
+        }
+        points.remove((2 * r - s - p) / 2);
+        let mut knots = self.knots.clone();
+        knots.remove(r);
+        (BSpline { degree: p, control_points: points, knots }, first, last)
+    }
+
+    // Drops a control point by removing the interior knot, among those whose
+    // removal rewrites it, that moves the curve least. The move is measured
+    // by putting the knot back and comparing control points, which bounds
+    // how far any point of the curve went; over `tolerance` is an error.
+    pub fn remove_control_point(&mut self, index: usize, tolerance: f64) -> Result<(), CadError> {
+        let (p, n) = (self.degree, self.control_points.len());
+        if index >= n {
+            return Err(CadError::InvalidInput(format!("control point {} of {}", index, n)));
+        }
+        let (lo, hi) = self.domain();
+        let mut best: Option<(f64, BSpline)> = None;
+        for r in p + 1..n {
+            let u = self.knots[r];
+            if u <= lo || u >= hi || self.knots[r + 1] == u || self.knots[r - p] == u {
+                continue;
+            }
+            let (candidate, first, last) = self.without_knot(r);
+            if index < first || index > last.max(first) {
+                continue;
+            }
+            let mut back = candidate.clone();
+            if back.insert_knot(u).is_err() {
+                continue;
+            }
+            let moved = back.control_points.iter().zip(&self.control_points).map(|(a, b)| distance(a, b)).fold(0.0, f64::max);
+            if best.as_ref().is_none_or(|(d, _)| moved < *d) {
+                best = Some((moved, candidate));
+            }
+        }
+        match best {
+            Some((moved, candidate)) if moved <= tolerance => {
+                *self = candidate;
+                Ok(())
+            }
+            Some((moved, _)) => Err(CadError::InvalidInput(format!("removing control point {} moves the curve by up to {}", index, moved))),
+            None => Err(CadError::InvalidInput(format!("control point {} has no interior knot to remove", index))),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Span {
+    from: f64,
+    to: f64,
+    // Samples from `from` up to but not including `to`
+    points: Vec<Point>,
+}
+
+// A spline in the document with its render polyline, kept per knot span so
+// an edit re-flattens only the spans it changes
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(from = "BSpline", into = "BSpline")]
+pub struct SplineEntity {
+    spline: BSpline,
+    spans: Vec<Span>,
+    // Spans flattened by the last edit
+    reflattened: usize,
+}
+
+impl From<BSpline> for SplineEntity {
+    fn from(spline: BSpline) -> SplineEntity {
+        let mut entity = SplineEntity { spline, spans: Vec::new(), reflattened: 0 };
+        entity.refresh(f64::NEG_INFINITY, f64::INFINITY);
+        entity
+    }
+}
+
+impl From<SplineEntity> for BSpline {
+    fn from(entity: SplineEntity) -> BSpline {
+        entity.spline
+    }
+}
+
+impl SplineEntity {
+    pub fn spline(&self) -> &BSpline {
+        &self.spline
+    }
+
+    pub fn reflattened(&self) -> usize {
+        self.reflattened
+    }
+
+    // The curve's render points, start to end
+    pub fn polyline(&self) -> Vec<Point> {
+        let mut points: Vec<Point> = self.spans.iter().flat_map(|s| s.points.iter().copied()).collect();
+        points.push(self.spline.point_at(self.spline.domain().1));
+        points
+    }
+
+    // Rebuilds the spans list from the knots, flattening the spans that
+    // overlap the changed interval (lo, hi) or didn't exist before
+    fn refresh(&mut self, lo: f64, hi: f64) {
+        let spline = &self.spline;
+        let mut old = std::mem::take(&mut self.spans).into_iter().peekable();
+        self.reflattened = 0;
+        for k in spline.degree..spline.control_points.len() {
+            let (from, to) = (spline.knots[k], spline.knots[k + 1]);
+            if from == to {
+                continue;
+            }
+            while old.next_if(|s| s.from < from).is_some() {}
+            let kept = old.next_if(|s| s.from == from && s.to == to).filter(|_| to <= lo || from >= hi);
+            let span = kept.unwrap_or_else(|| {
+                self.reflattened += 1;
+                Span { from, to, points: (0..SPAN_SAMPLES).map(|j| spline.point_at(from + (to - from) * j as f64 / SPAN_SAMPLES as f64)).collect() }
+            });
+            self.spans.push(span);
+        }
+    }
+
+    pub fn set_control_point(&mut self, index: usize, point: Point) -> Result<(), CadError> {
+        let n = self.spline.control_points.len();
+        if index >= n {
+            return Err(CadError::InvalidInput(format!("control point {} of {}", index, n)));
+        }
+        self.spline.control_points[index] = point;
+        let (lo, hi) = self.spline.support(index);
+        self.refresh(lo, hi);
+        Ok(())
+    }
+
+    // The curve is unchanged, so only the span the knot splits is flattened
+    pub fn insert_knot(&mut self, u: f64) -> Result<(), CadError> {
+        self.spline.insert_knot(u)?;
+        self.refresh(u, u);
+        Ok(())
+    }
+
+    // Adds a control point between `index` and the next, by inserting a
+    // knot in the middle of the widest span both of them shape
+    pub fn insert_control_point(&mut self, index: usize) -> Result<(), CadError> {
+        let (p, n) = (self.spline.degree, self.spline.control_points.len());
+        if index + 1 >= n {
+            return Err(CadError::InvalidInput(format!("no control point after {} of {}", index, n)));
+        }
+        let knots = &self.spline.knots;
+        let (from, to) = (index + 1..=index + p).map(|k| (knots[k], knots[k + 1])).max_by(|a, b| (a.1 - a.0).total_cmp(&(b.1 - b.0))).unwrap();
+        self.insert_knot((from + to) / 2.0)
+    }
+
+    pub fn remove_control_point(&mut self, index: usize, tolerance: f64) -> Result<(), CadError> {
+        let before = self.spline.control_points.clone();
+        let (old_knots, p) = (self.spline.knots.clone(), self.spline.degree);
+        self.spline.remove_control_point(index, tolerance)?;
+        // Old points that moved or went, found from each end, and what they
+        // shaped
+        let after = &self.spline.control_points;
+        let first = before.iter().zip(after).position(|(a, b)| a != b).unwrap_or(after.len());
+        let last = before.len() - 1 - before.iter().rev().zip(after.iter().rev()).position(|(a, b)| a != b).unwrap_or(after.len());
+        let (lo, hi) = (old_knots[first.min(last)], old_knots[last.max(first) + p + 1]);
+        self.refresh(lo, hi);
+        Ok(())
+    }
+}
+
+impl Document {
+    pub fn add_spline(&mut self, spline: BSpline) -> CurveId {
+        let id = self.next_curve_id;
+        self.next_curve_id += 1;
+        self.curves.insert(id, SplineEntity::from(spline));
+        id
+    }
+
+    pub fn curve(&self, id: CurveId) -> Result<&SplineEntity, CadError> {
+        self.curves.get(&id).ok_or(CadError::UnknownCurve(id))
+    }
+
+    pub fn curve_ids(&self) -> Vec<CurveId> {
+        self.curves.keys().copied().collect()
+    }
+
+    pub fn remove_curve(&mut self, id: CurveId) -> Result<BSpline, CadError> {
+        self.curves.remove(&id).map(|c| c.spline).ok_or(CadError::UnknownCurve(id))
+    }
+
+    // Checkpoints, then edits the curve; the checkpoint is dropped again if
+    // the edit fails
+    fn edit_curve(&mut self, id: CurveId, edit: impl FnOnce(&mut SplineEntity) -> Result<(), CadError>) -> Result<(), CadError> {
+        self.curve(id)?;
+        self.checkpoint();
+        let result = edit(self.curves.get_mut(&id).unwrap());
+        if result.is_err() {
+            self.drop_checkpoint();
+        }
+        result
+    }
+
+    // Moves one control point. Repeated moves of the same point, as during
+    // a pointer drag, share the checkpoint taken by the first, so one undo
+    // takes back the whole drag.
+    pub fn set_control_point(&mut self, id: CurveId, index: usize, point: Point) -> Result<(), CadError> {
+        let entity = self.curve(id)?;
+        if index >= entity.spline.control_points.len() {
+            return Err(CadError::InvalidInput(format!("control point {} of {}", index, entity.spline.control_points.len())));
+        }
+        if self.dragging != Some((id, index)) {
+            self.checkpoint();
+            self.dragging = Some((id, index));
+        }
+        self.curves.get_mut(&id).unwrap().set_control_point(index, point)
+    }
+
+    pub fn insert_knot(&mut self, id: CurveId, u: f64) -> Result<(), CadError> {
+        self.edit_curve(id, |c| c.insert_knot(u))
+    }
+
+    pub fn insert_control_point(&mut self, id: CurveId, index: usize) -> Result<(), CadError> {
+        self.edit_curve(id, |c| c.insert_control_point(index))
+    }
+
+    pub fn remove_control_point(&mut self, id: CurveId, index: usize, tolerance: f64) -> Result<(), CadError> {
+        self.edit_curve(id, |c| c.remove_control_point(index, tolerance))
+    }
+}
+
+#[wasm_bindgen]
+impl Document {
+    // `control_points` is an array of {x, y}, with uniform clamped knots
+    #[wasm_bindgen(js_name = add_spline)]
+    pub fn add_spline_js(&mut self, degree: usize, control_points: &JsValue) -> Result<CurveId, JsValue> {
+        let points: Vec<Point> = crate::from_js(control_points)?;
+        Ok(self.add_spline(BSpline::clamped(degree, points).map_err(js_error)?))
+    }
+
+    // `points` is the four control points as an array of {x, y}
+    #[wasm_bindgen(js_name = add_bezier)]
+    pub fn add_bezier_js(&mut self, points: &JsValue) -> Result<CurveId, JsValue> {
+        let points: Vec<Point> = crate::from_js(points)?;
+        match points[..] {
+            [p0, p1, p2, p3] => Ok(self.add_spline(BSpline::from_bezier(&CubicBezier::new(p0, p1, p2, p3)))),
+            _ => Err(js_error(CadError::InvalidInput(format!("a cubic Bézier has 4 control points, got {}", points.len())))),
+        }
+    }
+
+    // { degree, control_points, knots }
+    #[wasm_bindgen(js_name = curve)]
+    pub fn curve_js(&self, id: CurveId) -> Result<JsValue, JsValue> {
+        crate::to_js(self.curve(id).map_err(js_error)?.spline())
+    }
+
+    #[wasm_bindgen(js_name = curve_ids)]
+    pub fn curve_ids_js(&self) -> Vec<CurveId> {
+        self.curve_ids()
+    }
+
+    // Array of {x, y} to draw the curve with
+    #[wasm_bindgen(js_name = curve_polyline)]
+    pub fn curve_polyline_js(&self, id: CurveId) -> Result<JsValue, JsValue> {
+        crate::to_js(&self.curve(id).map_err(js_error)?.polyline())
+    }
+
+    #[wasm_bindgen(js_name = remove_curve)]
+    pub fn remove_curve_js(&mut self, id: CurveId) -> Result<(), JsValue> {
+        self.remove_curve(id).map(|_| ()).map_err(js_error)
+    }
+
+    // For pointer moves while dragging
+    #[wasm_bindgen(js_name = set_control_point)]
+    pub fn set_control_point_js(&mut self, id: CurveId, index: usize, x: f64, y: f64) -> Result<(), JsValue> {
+        self.set_control_point(id, index, Point::new(x, y)).map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = insert_knot)]
+    pub fn insert_knot_js(&mut self, id: CurveId, u: f64) -> Result<(), JsValue> {
+        self.insert_knot(id, u).map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = insert_control_point)]
+    pub fn insert_control_point_js(&mut self, id: CurveId, index: usize) -> Result<(), JsValue> {
+        self.insert_control_point(id, index).map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = remove_control_point)]
+    pub fn remove_control_point_js(&mut self, id: CurveId, index: usize, tolerance: f64) -> Result<(), JsValue> {
+        self.remove_control_point(id, index, tolerance).map_err(js_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wave() -> BSpline {
+        BSpline::clamped(3, (0..10).map(|i| Point::new(i as f64, if i % 2 == 0 { 0.0 } else { 2.0 + i as f64 * 0.1 })).collect()).unwrap()
+    }
+
+    fn samples(spline: &BSpline) -> Vec<Point> {
+        (0..=1000).map(|i| spline.point_at(i as f64 / 1000.0)).collect()
+    }
+
+    #[test]
+    fn test_knot_insertion_keeps_the_curve() {
+        let mut doc = Document::new();
+        let id = doc.add_spline(wave());
+        let before = samples(doc.curve(id).unwrap().spline());
+        let polyline = doc.curve(id).unwrap().polyline();
+
+        doc.insert_knot(id, 0.37).unwrap();
+        doc.insert_knot(id, 0.37).unwrap();
+        doc.insert_control_point(id, 0).unwrap();
+        let entity = doc.curve(id).unwrap();
+        assert_eq!(entity.spline().control_points.len(), 13);
+        assert_eq!(entity.reflattened(), 2);
+        for (a, b) in samples(entity.spline()).iter().zip(&before) {
+            assert!(distance(a, b) < 1e-12);
+        }
+        assert!(doc.insert_knot(id, 0.37).is_ok());
+        assert!(doc.insert_knot(id, 0.37).is_err());
+        assert!(doc.insert_knot(id, 1.0).is_err());
+
+        // Inserted knots come back out for free; original ones don't
+        let mut spline = doc.curve(id).unwrap().spline().clone();
+        let n = spline.control_points.len();
+        spline.remove_control_point(3, 1e-9).unwrap();
+        assert_eq!(spline.control_points.len(), n - 1);
+        assert!(samples(&spline).iter().zip(&before).all(|(a, b)| distance(a, b) < 1e-9));
+        assert!(wave().remove_control_point(5, 1e-3).is_err());
+        assert!(wave().remove_control_point(5, 10.0).is_ok());
+
+        while doc.undo() {}
+        assert_eq!(doc.curve(id).unwrap().polyline(), polyline);
+    }
+
+    #[test]
+    fn test_drag_reflattens_only_affected_spans() {
+        let mut doc = Document::new();
+        let id = doc.add_spline(wave());
+        let before = doc.curve(id).unwrap().spans.clone();
+        assert_eq!(before.len(), 7);
+
+        for step in 1..=5 {
+            doc.set_control_point(id, 1, Point::new(1.0, 2.0 + step as f64)).unwrap();
+        }
+        let entity = doc.curve(id).unwrap();
+        // Point 1 shapes knots [0, 2/7), the first two spans
+        assert_eq!(entity.reflattened(), 2);
+        for (old, new) in before.iter().zip(&entity.spans) {
+            let inside = old.from < entity.spline().support(1).1;
+            assert_eq!(old.points == new.points, !inside, "span from {}", old.from);
+        }
+        assert_eq!(entity.polyline().len(), 7 * SPAN_SAMPLES + 1);
+
+        // One undo takes back the whole drag
+        assert!(doc.undo());
+        assert_eq!(doc.curve(id).unwrap().spans, before);
+        assert!(!doc.undo());
+    }
+}
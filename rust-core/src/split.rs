@@ -0,0 +1,278 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::{CadError, Point, Polygon, Vector, EPSILON};
+
+// Default sliver threshold, relative to the area of the polygon being split
+const SLIVER_FRACTION: f64 = 1e-9;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RegionPiece {
+    pub polygon: Polygon,
+    pub area: f64,
+    pub centroid: Point,
+    // Bit k is set when the piece lies on the left of line k
+    pub side_signature: u64,
+}
+
+struct Chain {
+    start: usize,
+    end: usize,
+    points: Vec<Point>,
+}
+
+// Signed distance-like value; positive on the left of the directed line.
+// Values within EPSILON of the line are snapped to zero and counted as left,
+// which acts as a consistent tie-break for vertices lying on the line.
+fn side_values(ring: &[Point], point: &Point, direction: &Vector) -> Vec<f64> {
+    let dir = direction.normalize();
+    ring.iter().map(|v| {
+        let s = dir.x * (v.y - point.y) - dir.y * (v.x - point.x);
+        if s.abs() < EPSILON { 0.0 } else { s }
+    }).collect()
+}
+
+// Pieces of `ring` on one side of the line. The boundary is cut into chains
+// lying on that side; crossings sorted along the line pair up into the cut
+// segments inside the polygon, and following chain -> cut -> chain closes
+// each piece. This handles concave rings that split into several pieces.
+fn pieces_on_side(ring: &[Point], s: &[f64], point: &Point, direction: &Vector, left: bool) -> Vec<Polygon> {
+    let n = ring.len();
+    let inside = |i: usize| if left { s[i] >= 0.0 } else { s[i] < 0.0 };
+
+    let Some(outside_start) = (0..n).find(|&i| !inside(i)) else {
+        return vec![Polygon::new(ring.to_vec())];
+    };
+    if (0..n).all(|i| !inside(i)) {
+        return Vec::new();
+    }
+
+    let dir = direction.normalize();
+    let mut crossings: Vec<(f64, Point)> = Vec::new();
+    let mut chains: Vec<Chain> = Vec::new();
+    let mut current: Option<Chain> = None;
+
+    let crossing = |a: usize, b: usize, crossings: &mut Vec<(f64, Point)>| {
+        let (sa, sb) = (s[a], s[b]);
+        let c = if sa == 0.0 {
+            ring[a]
+        } else if sb == 0.0 {
+            ring[b]
+        } else {
+            let t = sa / (sa - sb);
+            Point::new(ring[a].x + (ring[b].x - ring[a].x) * t, ring[a].y + (ring[b].y - ring[a].y) * t)
+        };
+        let param = (c.x - point.x) * dir.x + (c.y - point.y) * dir.y;
+        crossings.push((param, c));
+        crossings.len() - 1
+    };
+
+    for k in 0..n {
+        let a = (outside_start + k) % n;
+        let b = (a + 1) % n;
+        match (inside(a), inside(b)) {
+            (false, true) => {
+                let id = crossing(a, b, &mut crossings);
+                current = Some(Chain { start: id, end: id, points: vec![crossings[id].1] });
+            }
+            (true, false) => {
+                let id = crossing(a, b, &mut crossings);
+                if let Some(mut chain) = current.take() {
+                    chain.points.push(ring[a]);
+                    chain.points.push(crossings[id].1);
+                    chain.end = id;
+                    chains.push(chain);
+                }
+            }
+            (true, true) => {
+                if let Some(chain) = current.as_mut() {
+                    chain.points.push(ring[a]);
+                }
+            }
+            (false, false) => {}
+        }
+    }
+
+    let mut order: Vec<usize> = (0..crossings.len()).collect();
+    order.sort_by(|&i, &j| crossings[i].0.total_cmp(&crossings[j].0));
+    let mut partner = vec![usize::MAX; crossings.len()];
+    for pair in order.chunks(2) {
+        if let [i, j] = *pair {
+            partner[i] = j;
+            partner[j] = i;
+        }
+    }
+
+    let mut used = vec![false; chains.len()];
+    let mut pieces = Vec::new();
+    for first in 0..chains.len() {
+        if used[first] {
+            continue;
+        }
+
+        let mut points: Vec<Point> = Vec::new();
+        let mut k = first;
+        loop {
+            used[k] = true;
+            points.extend(chains[k].points.iter().copied());
+            let next_start = partner[chains[k].end];
+            match chains.iter().position(|c| c.start == next_start) {
+                Some(next) if !used[next] => k = next,
+                _ => break,
+            }
+        }
+
+        points.dedup();
+        if points.len() > 1 && points.first() == points.last() {
+            points.pop();
+        }
+        if points.len() >= 3 {
+            pieces.push(Polygon::new(points));
+        }
+    }
+
+    pieces
+}
+
+impl Polygon {
+    // Splits a simple polygon by the infinite line through `point` along
+    // `direction`, returning the pieces on its left and on its right
+    pub fn split_by_line(&self, point: &Point, direction: &Vector) -> (Vec<Polygon>, Vec<Polygon>) {
+        if self.vertices.len() < 3 || direction.magnitude() < EPSILON {
+            return (vec![self.clone()], Vec::new());
+        }
+
+        let s = side_values(&self.vertices, point, direction);
+        (
+            pieces_on_side(&self.vertices, &s, point, direction, true),
+            pieces_on_side(&self.vertices, &s, point, direction, false),
+        )
+    }
+
+    pub fn multi_split(&self, lines: &[(Point, Vector)]) -> Vec<RegionPiece> {
+        self.multi_split_with_epsilon(lines, self.area() * SLIVER_FRACTION)
+    }
+
+    // Cuts by every line in turn (at most 64). A cut that would leave a piece
+    // smaller than `area_epsilon` is not applied to that piece, so the sliver
+    // stays merged with its neighbour across the line; the piece's signature
+    // bit then follows the side holding most of its area.
+    pub fn multi_split_with_epsilon(&self, lines: &[(Point, Vector)], area_epsilon: f64) -> Vec<RegionPiece> {
+        let mut pieces: Vec<(Polygon, u64)> = vec![(self.clone(), 0)];
+
+        for (k, (point, direction)) in lines.iter().enumerate().take(64) {
+            let bit = 1u64 << k;
+            let mut next = Vec::new();
+
+            for (piece, mask) in pieces {
+                let (left, right) = piece.split_by_line(point, direction);
+                let left_area: f64 = left.iter().map(|p| p.area()).sum();
+                let right_area: f64 = right.iter().map(|p| p.area()).sum();
+                let sliver = left.iter().chain(&right).any(|p| p.area() < area_epsilon);
+
+                if sliver || left.is_empty() || right.is_empty() {
+                    let side = if left_area >= right_area { bit } else { 0 };
+                    next.push((piece, mask | side));
+                } else {
+                    next.extend(left.into_iter().map(|p| (p, mask | bit)));
+                    next.extend(right.into_iter().map(|p| (p, mask)));
+                }
+            }
+
+            pieces = next;
+        }
+
+        pieces.into_iter().map(|(polygon, side_signature)| RegionPiece {
+            area: polygon.area(),
+            centroid: polygon.centroid(),
+            side_signature,
+            polygon,
+        }).collect()
+    }
+}
+
+// A line with no direction splits nothing; from JS that is an error rather
+// than a quietly unsplit polygon
+pub(crate) fn check_lines(lines: &[(Point, Vector)]) -> Result<(), CadError> {
+    match lines.iter().position(|(_, d)| d.try_normalize().is_none()) {
+        Some(i) => Err(CadError::Degenerate(format!("split line {} has no direction", i))),
+        None => Ok(()),
+    }
+}
+
+#[wasm_bindgen]
+pub fn multi_split(vertices: &JsValue, lines: &JsValue) -> Result<JsValue, JsValue> {
+    let polygon: Polygon = crate::polygon_from(vertices)?;
+    let lines: Vec<(Point, Vector)> = crate::from_js(lines)?;
+    check_lines(&lines).map_err(crate::js_error)?;
+    crate::to_js(&polygon.multi_split(&lines))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(size: f64) -> Polygon {
+        Polygon::new(vec![Point::new(0.0, 0.0), Point::new(size, 0.0), Point::new(size, size), Point::new(0.0, size)])
+    }
+
+    #[test]
+    fn test_square_by_center_lines() {
+        let lines = [
+            (Point::new(1.0, 0.0), Vector::new(0.0, 1.0)),
+            (Point::new(0.0, 1.0), Vector::new(1.0, 0.0)),
+        ];
+        let pieces = square(2.0).multi_split(&lines);
+        assert_eq!(pieces.len(), 4);
+
+        let mut signatures: Vec<u64> = pieces.iter().map(|p| p.side_signature).collect();
+        signatures.sort();
+        assert_eq!(signatures, vec![0, 1, 2, 3]);
+        for piece in &pieces {
+            assert!((piece.area - 1.0).abs() < 1e-12);
+        }
+
+        // Left of the upward line x = 1 is x < 1; left of the rightward line y = 1 is y > 1
+        let top_left = pieces.iter().find(|p| p.side_signature == 3).unwrap();
+        assert!((top_left.centroid.x - 0.5).abs() < 1e-12 && (top_left.centroid.y - 1.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_concave_split_yields_separate_pieces() {
+        let u_shape = Polygon::new(vec![
+            Point::new(0.0, 0.0),
+            Point::new(3.0, 0.0),
+            Point::new(3.0, 3.0),
+            Point::new(2.0, 3.0),
+            Point::new(2.0, 1.0),
+            Point::new(1.0, 1.0),
+            Point::new(1.0, 3.0),
+            Point::new(0.0, 3.0),
+        ]);
+        let (above, below) = u_shape.split_by_line(&Point::new(0.0, 2.0), &Vector::new(1.0, 0.0));
+        assert_eq!(above.len(), 2);
+        assert_eq!(below.len(), 1);
+        for arm in &above {
+            assert!((arm.area() - 1.0).abs() < 1e-12);
+        }
+        assert!((below[0].area() - 5.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_slivers_are_merged() {
+        let lines = [
+            (Point::new(1.0, 0.0), Vector::new(0.0, 1.0)),
+            (Point::new(1e-7, 0.0), Vector::new(0.0, 1.0)),
+        ];
+        let pieces = square(2.0).multi_split_with_epsilon(&lines, 1e-3);
+        assert_eq!(pieces.len(), 2);
+        let total: f64 = pieces.iter().map(|p| p.area).sum();
+        assert!((total - 4.0).abs() < 1e-12);
+
+        // A line that misses the polygon just records the side
+        let outside = [(Point::new(5.0, 0.0), Vector::new(0.0, 1.0))];
+        let pieces = square(2.0).multi_split(&outside);
+        assert_eq!(pieces.len(), 1);
+        assert_eq!(pieces[0].side_signature, 1);
+    }
+}
@@ -0,0 +1,175 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::{Point, Polygon, Polyline, Vector, EPSILON};
+
+// How station labels are written. `Plus` is the civil "12+34.5" form, where
+// the part before the plus counts whole multiples of `group`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum StationFormat {
+    Plus { group: f64, precision: usize },
+    Decimal { precision: usize },
+}
+
+impl Default for StationFormat {
+    fn default() -> StationFormat {
+        StationFormat::Plus { group: 100.0, precision: 0 }
+    }
+}
+
+impl StationFormat {
+    pub fn label(&self, chainage: f64) -> String {
+        match *self {
+            StationFormat::Decimal { precision } => format!("{:.*}", precision, chainage),
+            StationFormat::Plus { group, precision } => {
+                // Round first so e.g. 99.996 can't print as "0+100.00"
+                let scale = 10f64.powi(precision as i32);
+                let rounded = (chainage.abs() * scale).round() / scale;
+                let whole = (rounded / group).floor();
+                let rest = (rounded - whole * group).max(0.0);
+                let digits = group.log10().ceil().max(1.0) as usize;
+                let width = if precision > 0 { digits + precision + 1 } else { digits };
+                let sign = if chainage < 0.0 && rounded > 0.0 { "-" } else { "" };
+                format!("{}{}+{:0w$.p$}", sign, whole, rest, w = width, p = precision)
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Station {
+    pub point: Point,
+    // Unit direction of travel
+    pub tangent: Vector,
+    // Unit left normal of the tangent
+    pub normal: Vector,
+    pub chainage: f64,
+    pub label: String,
+}
+
+impl Station {
+    // Tick of the given length across the path, centred on the station
+    pub fn tick(&self, length: f64) -> Polyline {
+        let (h, n) = (length / 2.0, self.normal);
+        Polyline::new(vec![
+            Point::new(self.point.x - n.x * h, self.point.y - n.y * h),
+            Point::new(self.point.x + n.x * h, self.point.y + n.y * h),
+        ], false)
+    }
+}
+
+impl Polyline {
+    pub fn stations(&self, interval: f64, start_chainage: f64) -> Vec<Station> {
+        self.stations_with_format(interval, start_chainage, StationFormat::default())
+    }
+
+    // Stations at the start of the path and every `interval` of arc length
+    // after it. On closed paths chainage runs on through the closing segment,
+    // and a station landing back on the start is not repeated.
+    pub fn stations_with_format(&self, interval: f64, start_chainage: f64, format: StationFormat) -> Vec<Station> {
+        let segments: Vec<_> = self.segments().into_iter().filter(|s| s.2 > EPSILON).collect();
+        if interval.is_nan() || interval <= EPSILON || segments.is_empty() {
+            return vec![];
+        }
+        let total: f64 = segments.iter().map(|s| s.2).sum();
+        let mut last = (total / interval + EPSILON).floor() as usize;
+        if self.closed && (last as f64 * interval - total).abs() < EPSILON * total.max(1.0) {
+            last = last.saturating_sub(1);
+        }
+
+        let mut stations = Vec::with_capacity(last + 1);
+        let (mut index, mut walked) = (0, 0.0);
+        for k in 0..=last {
+            let s = (k as f64 * interval).min(total);
+            // Stay on the last segment for a station at the very end
+            while index + 1 < segments.len() && walked + segments[index].2 <= s {
+                walked += segments[index].2;
+                index += 1;
+            }
+            let (a, b, len) = segments[index];
+            let tangent = Vector::new((b.x - a.x) / len, (b.y - a.y) / len);
+            let f = ((s - walked) / len).clamp(0.0, 1.0);
+            let chainage = start_chainage + s;
+            stations.push(Station {
+                point: Point::new(a.x + (b.x - a.x) * f, a.y + (b.y - a.y) * f),
+                tangent,
+                normal: Vector::new(-tangent.y, tangent.x),
+                chainage,
+                label: format.label(chainage),
+            });
+        }
+        stations
+    }
+}
+
+impl Polygon {
+    // Stations around the perimeter, starting at the first vertex
+    pub fn stations(&self, interval: f64, start_chainage: f64, format: StationFormat) -> Vec<Station> {
+        Polyline::new(self.vertices.clone(), true).stations_with_format(interval, start_chainage, format)
+    }
+}
+
+fn polyline_from(polyline: &JsValue) -> Result<Polyline, JsValue> {
+    crate::from_js(polyline)
+}
+
+// `format` may be undefined for the default 0+00 labels
+#[wasm_bindgen]
+pub fn polyline_stations(polyline: &JsValue, interval: f64, start_chainage: f64, format: &JsValue) -> Result<JsValue, JsValue> {
+    let format: StationFormat = crate::options_from(format)?;
+    let stations = polyline_from(polyline)?.stations_with_format(interval, start_chainage, format);
+    crate::to_js(&stations)
+}
+
+#[wasm_bindgen]
+pub fn polygon_stations(polygon: &JsValue, interval: f64, start_chainage: f64, format: &JsValue) -> Result<JsValue, JsValue> {
+    let polygon: Polygon = crate::polygon_from(polygon)?;
+    let format: StationFormat = crate::options_from(format)?;
+    crate::to_js(&polygon.stations(interval, start_chainage, format))
+}
+
+#[wasm_bindgen]
+pub fn polyline_station_ticks(polyline: &JsValue, interval: f64, start_chainage: f64, length: f64) -> Result<JsValue, JsValue> {
+    let ticks: Vec<Polyline> = polyline_from(polyline)?.stations(interval, start_chainage).iter().map(|s| s.tick(length)).collect();
+    crate::to_js(&ticks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stations_every_25_units() {
+        let path = Polyline::new(vec![Point::new(0.0, 0.0), Point::new(60.0, 0.0), Point::new(60.0, 40.0)], false);
+        let stations = path.stations(25.0, 0.0);
+        let chainages: Vec<f64> = stations.iter().map(|s| s.chainage).collect();
+        assert_eq!(chainages, vec![0.0, 25.0, 50.0, 75.0, 100.0]);
+        let labels: Vec<&str> = stations.iter().map(|s| s.label.as_str()).collect();
+        assert_eq!(labels, vec!["0+00", "0+25", "0+50", "0+75", "1+00"]);
+
+        assert!((stations[3].point.x - 60.0).abs() < 1e-12 && (stations[3].point.y - 15.0).abs() < 1e-12);
+        assert!((stations[4].point.y - 40.0).abs() < 1e-12 && (stations[4].tangent.y - 1.0).abs() < 1e-12);
+        for s in &stations {
+            let tick = s.tick(2.0);
+            let (dx, dy) = (tick.points[1].x - tick.points[0].x, tick.points[1].y - tick.points[0].y);
+            assert!((dx * s.tangent.x + dy * s.tangent.y).abs() < 1e-12);
+            assert!((dx.hypot(dy) - 2.0).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_closed_perimeter_and_labels() {
+        let square = Polygon::new(vec![Point::new(0.0, 0.0), Point::new(10.0, 0.0), Point::new(10.0, 10.0), Point::new(0.0, 10.0)]);
+        let stations = square.stations(15.0, 1000.0, StationFormat::Decimal { precision: 1 });
+        let labels: Vec<&str> = stations.iter().map(|s| s.label.as_str()).collect();
+        assert_eq!(labels, vec!["1000.0", "1015.0", "1030.0"]);
+        assert!((stations[2].point.x - 0.0).abs() < 1e-12 && (stations[2].point.y - 10.0).abs() < 1e-12);
+        // The start is not repeated when the perimeter is a multiple of the interval
+        assert_eq!(square.stations(10.0, 0.0, StationFormat::default()).len(), 4);
+
+        let plus = StationFormat::Plus { group: 100.0, precision: 2 };
+        assert_eq!(plus.label(1234.5), "12+34.50");
+        assert_eq!(plus.label(99.996), "1+00.00");
+        assert_eq!(StationFormat::Plus { group: 1000.0, precision: 0 }.label(1005.0), "1+005");
+    }
+}
@@ -0,0 +1,187 @@
+use wasm_bindgen::prelude::*;
+
+use crate::arrangement::overlay;
+use crate::hittest::edges;
+use crate::{js_error, CadError, MultiPolygon, Point, PolygonWithHoles, Polyline, Vector, EPSILON};
+
+// Places tried for a bridge around each hole
+const STATIONS: usize = 64;
+// A vertex turning more than this is a corner bridges keep clear of
+const CORNER_ANGLE: f64 = std::f64::consts::PI / 6.0;
+// Connections within this fraction of the bridge width of the shortest are
+// as good as it, and the one nearest where the bridge is wanted wins
+const LENGTH_SLACK: f64 = 0.01;
+
+// A possible bridge from the hole at `at` of the way round it
+#[derive(Clone, Copy)]
+struct Connection {
+    at: f64,
+    from: Point,
+    to: Point,
+    length: f64,
+}
+
+fn distance(a: &Point, b: &Point) -> f64 {
+    (a.x - b.x).hypot(a.y - b.y)
+}
+
+fn corners(ring: &[Point]) -> Vec<Point> {
+    let n = ring.len();
+    (0..n).filter(|&i| {
+        let (p, v, q) = (ring[(i + n - 1) % n], ring[i], ring[(i + 1) % n]);
+        let (ux, uy, wx, wy) = (v.x - p.x, v.y - p.y, q.x - v.x, q.y - v.y);
+        (ux * wy - uy * wx).atan2(ux * wx + uy * wy).abs() > CORNER_ANGLE
+    }).map(|i| ring[i]).collect()
+}
+
+// Shortest connection from each station round `hole` to any of `targets`,
+// leaving out those ending within `clearance` of a corner unless that
+// leaves none
+fn connections(hole: &[Point], targets: &[&Vec<Point>], clearance: f64) -> Vec<Connection> {
+    let outline = Polyline::new(hole.to_vec(), true);
+    let mut near_corner: Vec<Point> = corners(hole);
+    near_corner.extend(targets.iter().flat_map(|t| corners(t)));
+    let all: Vec<(Connection, bool)> = (0..STATIONS).filter_map(|k| {
+        let at = (k as f64 + 0.5) / STATIONS as f64;
+        let from = outline.point_at(at)?;
+        let to = targets.iter().flat_map(|t| edges(t)).map(|e| e.closest_point(&from))
+            .min_by(|a, b| distance(a, &from).total_cmp(&distance(b, &from)))?;
+        let clear = near_corner.iter().all(|c| distance(c, &from) > clearance && distance(c, &to) > clearance);
+        Some((Connection { at, from, to, length: distance(&from, &to) }, clear))
+    }).collect();
+    let any_clear = all.iter().any(|(_, clear)| *clear);
+    all.into_iter().filter(|(_, clear)| *clear || !any_clear).map(|(c, _)| c).collect()
+}
+
+// `count` connections spread round the hole: the shortest, then the
+// shortest near each of the points evenly spaced round from it
+fn spread(candidates: &[Connection], count: usize, width: f64) -> Vec<Connection> {
+    let Some(first) = candidates.iter().copied().min_by(|a, b| a.length.total_cmp(&b.length)) else {
+        return vec![];
+    };
+    let mut chosen = vec![first];
+    for m in 1..count {
+        let wanted = first.at + m as f64 / count as f64;
+        let off = |c: &Connection| {
+            let d = (c.at - wanted).rem_euclid(1.0);
+            d.min(1.0 - d)
+        };
+        let window: Vec<&Connection> = candidates.iter().filter(|c| off(c) <= 0.5 / count as f64).collect();
+        let Some(shortest) = window.iter().map(|c| c.length).min_by(f64::total_cmp) else {
+            continue;
+        };
+        if let Some(c) = window.into_iter().filter(|c| c.length <= shortest + LENGTH_SLACK * width).min_by(|a, b| off(a).total_cmp(&off(b))) {
+            chosen.push(*c);
+        }
+    }
+    chosen
+}
+
+// Bar of the given width along a connection, run on past both ends so it
+// cuts cleanly through boundaries meeting it at an angle
+fn bridge(c: &Connection, width: f64) -> Option<Vec<Point>> {
+    let d = Vector::new(c.to.x - c.from.x, c.to.y - c.from.y).try_normalize()?;
+    let (h, n) = (width / 2.0, Vector::new(-d.y, d.x));
+    let (a, b) = (Point::new(c.from.x - d.x * width, c.from.y - d.y * width), Point::new(c.to.x + d.x * width, c.to.y + d.y * width));
+    Some(vec![
+        Point::new(a.x - n.x * h, a.y - n.y * h),
+        Point::new(b.x - n.x * h, b.y - n.y * h),
+        Point::new(b.x + n.x * h, b.y + n.y * h),
+        Point::new(a.x + n.x * h, a.y + n.y * h),
+    ])
+}
+
+// The shape to cut with bridges of material left across it, so nothing
+// inside a hole falls out. Holes are bridged one at a time, nearest first,
+// each to the outline or to a hole already bridged by the shortest ways
+// across that keep clear of corners, `bridges_per_island` of them spread
+// evenly round it. The pieces that are left have no holes.
+pub fn stencilify(shape: &PolygonWithHoles, bridge_width: f64, bridges_per_island: u32) -> Result<MultiPolygon, CadError> {
+    if !(bridge_width.is_finite() && bridge_width > EPSILON) {
+        return Err(CadError::InvalidInput(format!("bridge width must be positive, got {}", bridge_width)));
+    }
+    if bridges_per_island == 0 {
+        return Err(CadError::InvalidInput("at least one bridge per island is needed".into()));
+    }
+    let outer = shape.outer.vertices.clone();
+    let mut pending: Vec<Vec<Point>> = shape.holes.iter().map(|h| h.vertices.clone()).filter(|h| h.len() >= 3).collect();
+    let mut connected: Vec<Vec<Point>> = vec![outer.clone()];
+    let mut bridges: Vec<Vec<Point>> = Vec::new();
+    while !pending.is_empty() {
+        let targets: Vec<&Vec<Point>> = connected.iter().collect();
+        let options: Vec<Vec<Connection>> = pending.iter().map(|h| connections(h, &targets, bridge_width)).collect();
+        let nearest = (0..pending.len())
+            .min_by(|&i, &j| {
+                let best = |k: usize| options[k].iter().map(|c| c.length).fold(f64::INFINITY, f64::min);
+                best(i).total_cmp(&best(j))
+            })
+            .unwrap();
+        bridges.extend(spread(&options[nearest], bridges_per_island as usize, bridge_width).iter().filter_map(|c| bridge(c, bridge_width)));
+        connected.push(pending.remove(nearest));
+    }
+
+    let mut cut: Vec<Vec<Point>> = connected.split_off(1);
+    cut.extend(bridges);
+    let regions = overlay(&[outer], &cut, |in_shape, in_cut| in_shape && !in_cut);
+    if regions.iter().any(|r| !r.holes.is_empty()) {
+        return Err(CadError::InvalidInput(format!("bridges {} wide leave an island enclosed", bridge_width)));
+    }
+    Ok(regions.into_iter().map(|r| r.outer).collect())
+}
+
+// `shape` is { outer, holes }; returns the pieces as arrays of {x, y}
+#[wasm_bindgen(js_name = stencilify)]
+pub fn stencilify_js(shape: &JsValue, bridge_width: f64, bridges_per_island: u32) -> Result<JsValue, JsValue> {
+    let shape: PolygonWithHoles = crate::from_js(shape)?;
+    let pieces: Vec<Vec<Point>> = stencilify(&shape, bridge_width, bridges_per_island).map_err(js_error)?.into_iter().map(|p| p.vertices).collect();
+    crate::to_js(&pieces)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assemble_rings, Polygon};
+
+    fn circle(r: f64, clockwise: bool) -> Polygon {
+        let mut v: Vec<Point> = (0..64).map(|i| {
+            let a = i as f64 * std::f64::consts::TAU / 64.0;
+            Point::new(r * a.cos(), r * a.sin())
+        }).collect();
+        if clockwise {
+            v.reverse();
+        }
+        Polygon::new(v)
+    }
+
+    #[test]
+    fn test_washer_takes_one_or_two_bridges() {
+        let washer = PolygonWithHoles::new(circle(10.0, false), vec![circle(5.0, true)]);
+        let ring_area = washer.outer.area() - washer.holes[0].area();
+        for bridges in [1, 2] {
+            let pieces = stencilify(&washer, 1.0, bridges).unwrap();
+            assert_eq!(pieces.len(), bridges as usize);
+            let regions = assemble_rings(&pieces, 1e-9).unwrap();
+            assert!(regions.iter().all(|r| r.holes.is_empty()));
+            let removed = ring_area - pieces.iter().map(|p| p.area()).sum::<f64>();
+            let expected = bridges as f64 * 1.0 * 5.0;
+            assert!((removed - expected).abs() < 0.02 * expected, "{} bridges removed {}", bridges, removed);
+        }
+        // Deterministic
+        let again = stencilify(&washer, 1.0, 2).unwrap();
+        assert_eq!(again.iter().map(|p| p.vertices.len()).collect::<Vec<_>>(), stencilify(&washer, 1.0, 2).unwrap().iter().map(|p| p.vertices.len()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_bridges_keep_clear_of_corners() {
+        // Square frame: the shortest ways across are all mid-side, away from the corners
+        let square = |r: f64| Polygon::new(vec![Point::new(-r, -r), Point::new(r, -r), Point::new(r, r), Point::new(-r, r)]);
+        let mut hole = square(4.0);
+        hole.vertices.reverse();
+        let frame = PolygonWithHoles::new(square(5.0), vec![hole]);
+        let pieces = stencilify(&frame, 0.5, 1).unwrap();
+        assert_eq!(pieces.len(), 1);
+        assert!((frame.outer.area() - frame.holes[0].area() - pieces[0].area() - 0.5).abs() < 1e-9);
+        assert!(pieces[0].vertices.iter().all(|v| v.x.abs() == 4.0 || v.x.abs() == 5.0 || v.y.abs() == 4.0 || v.y.abs() == 5.0));
+        assert!(stencilify(&frame, 0.0, 1).is_err() && stencilify(&frame, 0.5, 0).is_err());
+    }
+}
@@ -0,0 +1,164 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::predicates::ring_signed_area;
+use crate::{Point, Polygon, Segment};
+
+// Which way a chain's vertices lie off its chord, relative to the polygon
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bow {
+    Straight,
+    Outward,
+    Inward,
+}
+
+// Run of consecutive edges reading as one straight edge, from vertex
+// `start` to vertex `end` (indices wrap past the last vertex)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct EdgeChainReport {
+    pub start: usize,
+    pub end: usize,
+    pub edge_count: usize,
+    pub chord: Segment,
+    pub max_deviation: f64,
+    // Intermediate vertex furthest from the chord; None for a single edge
+    pub peak: Option<usize>,
+    pub bow: Bow,
+}
+
+fn turn(prev: &Point, p: &Point, next: &Point) -> f64 {
+    let (ax, ay, bx, by) = (p.x - prev.x, p.y - prev.y, next.x - p.x, next.y - p.y);
+    (ax * by - ay * bx).atan2(ax * bx + ay * by).abs()
+}
+
+// Signed distance of `p` from the line ab, positive on the left; None when
+// `p` projects outside the chord, so a chain can't fold back on itself
+fn offset(a: &Point, b: &Point, p: &Point) -> Option<f64> {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let len2 = dx * dx + dy * dy;
+    let t = ((p.x - a.x) * dx + (p.y - a.y) * dy) / len2;
+    (len2 > 0.0 && (0.0..=1.0).contains(&t)).then(|| (dx * (p.y - a.y) - dy * (p.x - a.x)) / len2.sqrt())
+}
+
+impl Polygon {
+    // Groups the edges into chains whose intermediate vertices all lie
+    // within `chain_tolerance` of the chord between the chain's ends, and
+    // reports each chain's chord, largest deviation and bow. Chains grow
+    // greedily round the ring from its sharpest corner, so true corners end
+    // chains. Fewer than three vertices report nothing.
+    pub fn edge_straightness(&self, chain_tolerance: f64) -> Vec<EdgeChainReport> {
+        let v = &self.vertices;
+        let n = v.len();
+        if n < 3 || chain_tolerance.is_nan() || chain_tolerance < 0.0 {
+            return vec![];
+        }
+        let at = |i: usize| &v[i % n];
+        let start = (0..n).max_by(|&i, &j| turn(at(i + n - 1), at(i), at(i + 1)).total_cmp(&turn(at(j + n - 1), at(j), at(j + 1)))).unwrap();
+        // Left of a counter-clockwise ring is inside
+        let inward = if ring_signed_area(v) >= 0.0 { 1.0 } else { -1.0 };
+        let offsets = |i: usize, j: usize| -> Option<Vec<f64>> { (i + 1..j).map(|k| offset(at(i), at(j), at(k))).collect() };
+
+        let mut chains = Vec::new();
+        let mut i = start;
+        while i < start + n {
+            let mut j = i + 1;
+            while j < start + n && offsets(i, j + 1).is_some_and(|d| d.iter().all(|d| d.abs() <= chain_tolerance)) {
+                j += 1;
+            }
+            let d = offsets(i, j).unwrap_or_default();
+            let peak = (0..d.len()).max_by(|&a, &b| d[a].abs().total_cmp(&d[b].abs()));
+            let max_deviation = peak.map_or(0.0, |k| d[k].abs());
+            let bow = match peak.map(|k| d[k] * inward) {
+                Some(s) if s > 0.0 => Bow::Inward,
+                Some(s) if s < 0.0 => Bow::Outward,
+                _ => Bow::Straight,
+            };
+            chains.push(EdgeChainReport {
+                start: i % n,
+                end: j % n,
+                edge_count: j - i,
+                chord: Segment::new(*at(i), *at(j)),
+                max_deviation,
+                peak: peak.map(|k| (i + 1 + k) % n),
+                bow,
+            });
+            i = j;
+        }
+        chains
+    }
+
+    // Replaces every chain `edge_straightness(tolerance)` finds by its
+    // chord. Corners sharper than the tolerance allows are kept as they are.
+    pub fn straighten_chains(&self, tolerance: f64) -> Polygon {
+        let chains = self.edge_straightness(tolerance);
+        if chains.len() < 3 {
+            return self.clone();
+        }
+        Polygon::new(chains.iter().map(|c| self.vertices[c.start]).collect())
+    }
+}
+
+#[wasm_bindgen]
+pub fn edge_straightness(vertices: &JsValue, chain_tolerance: f64) -> Result<JsValue, JsValue> {
+    let polygon: Polygon = crate::polygon_from(vertices)?;
+    crate::to_js(&polygon.edge_straightness(chain_tolerance))
+}
+
+#[wasm_bindgen]
+pub fn straighten_chains(vertices: &JsValue, tolerance: f64) -> Result<JsValue, JsValue> {
+    let polygon: Polygon = crate::polygon_from(vertices)?;
+    crate::to_js(&polygon.straighten_chains(tolerance))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+
+    // 10 x 10 square whose right edge bows out by `bow` over 16 segments
+    fn bowed_square(bow: f64) -> Polygon {
+        let mut vertices = vec![Point::new(0.0, 0.0), Point::new(10.0, 0.0)];
+        vertices.extend((1..16).map(|i| {
+            let t = i as f64 / 16.0;
+            Point::new(10.0 + bow * (PI * t).sin(), 10.0 * t)
+        }));
+        vertices.extend([Point::new(10.0, 10.0), Point::new(0.0, 10.0)]);
+        Polygon::new(vertices)
+    }
+
+    #[test]
+    fn test_bowed_edge_is_one_chain() {
+        let square = bowed_square(0.05);
+        let chains = square.edge_straightness(0.1);
+        assert_eq!(chains.len(), 4);
+        let bowed: Vec<&EdgeChainReport> = chains.iter().filter(|c| c.edge_count > 1).collect();
+        assert_eq!(bowed.len(), 1);
+        let chain = bowed[0];
+        assert!((chain.max_deviation - 0.05).abs() < 1e-12);
+        assert_eq!(chain.edge_count, 16);
+        assert_eq!(chain.bow, Bow::Outward);
+        assert_eq!(chain.peak, Some(9));
+        assert_eq!((chain.chord.a, chain.chord.b), (Point::new(10.0, 0.0), Point::new(10.0, 10.0)));
+        assert!(chains.iter().filter(|c| c.edge_count == 1).all(|c| c.max_deviation == 0.0 && c.bow == Bow::Straight));
+
+        let straight = square.straighten_chains(0.1);
+        assert_eq!(straight.vertices.len(), 4);
+        assert!((straight.area() - 100.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_tight_tolerance_splits_the_bow() {
+        let square = bowed_square(0.05);
+        let chains = square.edge_straightness(0.005);
+        assert!(chains.len() > 5);
+        assert!(chains.iter().all(|c| c.max_deviation <= 0.005));
+        // Reversed winding swaps the sides, not the bow
+        let mut reversed = square.clone();
+        reversed.vertices.reverse();
+        assert!(reversed.edge_straightness(0.1).iter().any(|c| c.bow == Bow::Outward));
+        let dented = bowed_square(-0.05).edge_straightness(0.1);
+        assert!(dented.iter().any(|c| c.bow == Bow::Inward && (c.max_deviation - 0.05).abs() < 1e-12));
+        assert!(Polygon::new(vec![Point::new(0.0, 0.0), Point::new(1.0, 0.0)]).edge_straightness(0.1).is_empty());
+    }
+}
@@ -0,0 +1,229 @@
+use std::cell::Cell;
+
+use wasm_bindgen::prelude::*;
+
+use crate::predicates::{orient2d, ring_signed_area};
+use crate::{CadError, MultiPolygon, Point, Polygon, PolygonWithHoles, EPSILON};
+
+// A piece with less area than this fraction of its squared extent is a
+// sliver, narrower than a billionth of its length
+const SLIVER_FRACTION: f64 = 1e-9;
+
+thread_local! {
+    static STRICT: Cell<bool> = const { Cell::new(false) };
+}
+
+// With strict output on, booleans, offsets, simplification and imports
+// repair what they produce and fail with CadError::InvalidResult rather
+// than return a polygon `Polygon::validate` rejects. Off by default, when
+// nothing is checked.
+#[wasm_bindgen]
+pub fn set_strict_output(strict: bool) {
+    STRICT.with(|s| s.set(strict));
+}
+
+#[wasm_bindgen]
+pub fn strict_output() -> bool {
+    STRICT.with(|s| s.get())
+}
+
+// The repairs that can't change what a polygon means: vertices within
+// EPSILON of the one before and zero-width spikes dropped, and the ring
+// wound counter-clockwise
+pub fn repair_polygon(polygon: &Polygon) -> Polygon {
+    let mut points = polygon.vertices.clone();
+    loop {
+        let n = points.len();
+        if n < 3 {
+            break;
+        }
+        let drop = (0..n).find(|&i| {
+            let (prev, cur, next) = (points[(i + n - 1) % n], points[i], points[(i + 1) % n]);
+            let (back, fwd) = ((prev.x - cur.x).hypot(prev.y - cur.y), (next.x - cur.x).hypot(next.y - cur.y));
+            let dot = (prev.x - cur.x) * (next.x - cur.x) + (prev.y - cur.y) * (next.y - cur.y);
+            back <= EPSILON || (dot > 0.0 && orient2d(&prev, &cur, &next).abs() <= EPSILON * back * fwd)
+        });
+        match drop {
+            Some(i) => {
+                points.remove(i);
+            }
+            None => break,
+        }
+    }
+    if ring_signed_area(&points) < 0.0 {
+        points.reverse();
+    }
+    Polygon::new(points)
+}
+
+// Area of the triangles fanning out from the first vertex, all counted
+// positive
+fn fan_area(points: &[Point]) -> f64 {
+    points.windows(2).skip(1).map(|w| orient2d(&points[0], &w[0], &w[1]).abs() / 2.0).sum()
+}
+
+// `repair_polygon` on one ring, or None for a sliver; `label` names it in
+// the error when it's still invalid
+fn repair_ring(ring: &Polygon, label: &str) -> Result<Option<Polygon>, CadError> {
+    let ring = repair_polygon(ring);
+    let b = ring.bounding_box();
+    let sliver = SLIVER_FRACTION * b.width().max(b.height()).powi(2);
+    match ring.validate() {
+        Ok(()) if ring.area().abs() <= sliver => Ok(None),
+        Ok(()) => Ok(Some(ring)),
+        // A ring folded flat, whose area doesn't cancel out between
+        // lobes wound opposite ways as a bowtie's does
+        Err(_) if fan_area(&ring.vertices) <= sliver => Ok(None),
+        Err(e) => Err(CadError::InvalidResult(format!("{}: {}", label, e))),
+    }
+}
+
+// `repair_polygon` on each piece `operation` produced, leaving out slivers;
+// any piece still invalid fails the whole result
+pub fn repair_output(pieces: MultiPolygon, operation: &str) -> Result<MultiPolygon, CadError> {
+    let mut repaired = Vec::with_capacity(pieces.len());
+    for (k, piece) in pieces.iter().enumerate() {
+        repaired.extend(repair_ring(piece, &format!("{} piece {}", operation, k))?);
+    }
+    Ok(repaired)
+}
+
+// `repair_output` for regions with holes, the outline and each hole checked
+// as a ring of its own: keyholed, the cut there and back is a touch
+// `validate` rejects. Holes stay clockwise; a region whose outline is a
+// sliver is left out, as is a sliver hole.
+pub fn repair_regions(regions: Vec<PolygonWithHoles>, operation: &str) -> Result<Vec<PolygonWithHoles>, CadError> {
+    let mut repaired = Vec::with_capacity(regions.len());
+    for (k, region) in regions.iter().enumerate() {
+        let Some(outer) = repair_ring(&region.outer, &format!("{} piece {}", operation, k))? else {
+            continue;
+        };
+        let mut holes = Vec::with_capacity(region.holes.len());
+        for (h, hole) in region.holes.iter().enumerate() {
+            if let Some(mut hole) = repair_ring(hole, &format!("{} piece {} hole {}", operation, k, h))? {
+                hole.vertices.reverse();
+                holes.push(hole);
+            }
+        }
+        repaired.push(PolygonWithHoles::new(outer, holes));
+    }
+    Ok(repaired)
+}
+
+// `repair_output` when strict output is on; otherwise the pieces as they are
+pub(crate) fn checked(pieces: MultiPolygon, operation: &str) -> Result<MultiPolygon, CadError> {
+    if strict_output() {
+        repair_output(pieces, operation)
+    } else {
+        Ok(pieces)
+    }
+}
+
+// `repair_regions` when strict output is on; otherwise the regions as they are
+pub(crate) fn checked_regions(regions: Vec<PolygonWithHoles>, operation: &str) -> Result<Vec<PolygonWithHoles>, CadError> {
+    if strict_output() {
+        repair_regions(regions, operation)
+    } else {
+        Ok(regions)
+    }
+}
+
+// `checked` for an operation giving one polygon, which mustn't be left out
+pub(crate) fn checked_polygon(polygon: Polygon, operation: &str) -> Result<Polygon, CadError> {
+    if !strict_output() {
+        return Ok(polygon);
+    }
+    match repair_output(vec![polygon], operation)?.pop() {
+        Some(p) => Ok(p),
+        None => Err(CadError::InvalidResult(format!("{} leaves only a sliver", operation))),
+    }
+}
+
+// `checked` on the outline of each of `items`, leaving out those that are
+// slivers
+pub(crate) fn checked_each<T>(items: Vec<T>, outline: impl Fn(&mut T) -> &mut Polygon, operation: &str) -> Result<Vec<T>, CadError> {
+    if !strict_output() {
+        return Ok(items);
+    }
+    let mut kept = Vec::with_capacity(items.len());
+    for mut item in items {
+        let polygon = std::mem::replace(outline(&mut item), Polygon::new(Vec::new()));
+        if let Some(repaired) = repair_output(vec![polygon], operation)?.pop() {
+            *outline(&mut item) = repaired;
+            kept.push(item);
+        }
+    }
+    Ok(kept)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Document, JoinStyle};
+
+    fn polygon(points: &[(f64, f64)]) -> Polygon {
+        Polygon::new(points.iter().map(|&(x, y)| Point::new(x, y)).collect())
+    }
+
+    #[test]
+    fn test_repair_dedupes_rewinds_and_drops_slivers() {
+        // Clockwise, with a repeated corner and a spike
+        let square = polygon(&[(0.0, 0.0), (0.0, 1.0), (0.0, 1.0), (0.5, 1.0), (0.5, 3.0), (0.5, 1.0), (1.0, 1.0), (1.0, 0.0)]);
+        let needle = polygon(&[(5.0, 0.0), (15.0, 0.0), (15.0, 1e-9)]);
+        let pieces = repair_output(vec![square, needle], "test").unwrap();
+        assert_eq!(pieces.len(), 1);
+        assert!(pieces[0].validate().is_ok() && ring_signed_area(&pieces[0].vertices) > 0.0);
+        assert!((pieces[0].area() - 1.0).abs() < 1e-12);
+
+        let bowtie = polygon(&[(0.0, 0.0), (1.0, 1.0), (1.0, 0.0), (0.0, 1.0)]);
+        let err = repair_output(vec![bowtie], "test").unwrap_err();
+        assert_eq!(err.code(), "invalid result");
+        assert!(err.to_string().contains("test piece 0: self-intersecting"), "{}", err);
+    }
+
+    #[test]
+    fn test_holed_result_checks_ring_by_ring() {
+        let outer = polygon(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)]);
+        let inner = polygon(&[(4.0, 4.0), (6.0, 4.0), (6.0, 6.0), (4.0, 6.0)]);
+        let regions = outer.boolean_regions(&inner, crate::BooleanOp::Difference);
+        // Keyholed, the cut there and back touches itself
+        assert!(repair_output(outer.difference(&inner), "difference").is_err());
+
+        set_strict_output(true);
+        let checked = checked_regions(regions, "difference");
+        set_strict_output(false);
+        let checked = checked.unwrap();
+        assert_eq!(checked.len(), 1);
+        assert_eq!(checked[0].holes.len(), 1);
+        assert!(checked[0].outer.validate().is_ok() && checked[0].holes[0].validate().is_ok());
+        assert!(ring_signed_area(&checked[0].holes[0].vertices) < 0.0);
+        assert!((checked[0].area() - 96.0).abs() < 1e-9);
+        assert!((crate::boolean::keyhole(checked[0].clone()).area() - 96.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_strict_offset_errors_where_lax_returns_bad_geometry() {
+        // A hairline spike out of a square, offset in by less than its width
+        let spiked = polygon(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (5.00000001, 10.0), (5.0, 20.0), (5.0, 10.0), (0.0, 10.0)]);
+        let mut doc = Document::new();
+        let id = doc.add_shape(spiked.clone());
+        let clean = doc.add_shape(polygon(&[(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0)]));
+        let lax = spiked.offset(-1.5e-9, JoinStyle::Round { segments: 64 });
+        assert!(lax.iter().any(|p| p.validate().is_err()));
+
+        set_strict_output(true);
+        let err = doc.offset_shape(id, -1.5e-9, 64).unwrap_err();
+        assert!(matches!(err, CadError::InvalidResult(_)));
+        assert_eq!(doc.shape(id).unwrap().geometry.vertices, spiked.vertices);
+        doc.offset_shape(clean, 0.5, 64).unwrap();
+        let strict = doc.shape(clean).unwrap().geometry.clone();
+        set_strict_output(false);
+
+        // Clean results come out the same either way
+        let mut other = Document::new();
+        let again = other.add_shape(polygon(&[(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0)]));
+        other.offset_shape(again, 0.5, 64).unwrap();
+        assert_eq!(other.shape(again).unwrap().geometry.vertices, strict.vertices);
+        assert!(doc.offset_shape(id, -1.5e-9, 64).is_ok());
+    }
+}
@@ -0,0 +1,91 @@
+use wasm_bindgen::prelude::*;
+
+use crate::arrangement::{drop_collinear, merge_epsilon, Arrangement};
+use crate::hull::convex_hull_points;
+use crate::predicates::{point_in_ring, ring_is_convex, ring_signed_area, segments_intersect};
+use crate::{Point, Polygon, Segment, Vector, EPSILON};
+
+// Whether `q` is covered by the sweep, i.e. the segment from `q - d` to `q`
+// meets the polygon
+fn swept_over(vertices: &[Point], q: &Point, d: &Vector) -> bool {
+    let back = Point::new(q.x - d.x, q.y - d.y);
+    if point_in_ring(vertices, q) || point_in_ring(vertices, &back) {
+        return true;
+    }
+    let n = vertices.len();
+    (0..n).any(|i| segments_intersect(&back, q, &vertices[i], &vertices[(i + 1) % n]))
+}
+
+// Region covered when `shape` moves `distance` along `direction`. Convex
+// shapes take the hull of both end positions (their Minkowski sum with the
+// travel segment). Otherwise the outlines at both ends and the vertex travel
+// paths are overlaid, and the covered faces are merged. Enclosed pockets
+// are filled, which errs on the safe side for clearance checks.
+pub fn sweep_translate(shape: &Polygon, direction: &Vector, distance: f64) -> Polygon {
+    let unit = direction.normalize();
+    let d = Vector::new(unit.x * distance, unit.y * distance);
+    let vertices = &shape.vertices;
+    if vertices.len() < 3 || d.magnitude() < EPSILON {
+        return shape.clone();
+    }
+    let moved: Vec<Point> = vertices.iter().map(|p| Point::new(p.x + d.x, p.y + d.y)).collect();
+    if ring_is_convex(vertices) {
+        let mut both = vertices.clone();
+        both.extend_from_slice(&moved);
+        return Polygon::new(convex_hull_points(&both));
+    }
+
+    let n = vertices.len();
+    let mut segments = Vec::with_capacity(3 * n);
+    for i in 0..n {
+        let j = (i + 1) % n;
+        segments.push(Segment::new(vertices[i], vertices[j]));
+        segments.push(Segment::new(moved[i], moved[j]));
+        segments.push(Segment::new(vertices[i], moved[i]));
+    }
+    let eps = merge_epsilon(&segments);
+    let rings = Arrangement::build(&segments, eps).covered_boundary(|q| swept_over(vertices, q, &d));
+    let outer = rings.into_iter().filter(|r| ring_signed_area(r) > 0.0).max_by(|a, b| ring_signed_area(a).total_cmp(&ring_signed_area(b)));
+    Polygon::new(drop_collinear(outer.unwrap_or_default(), eps))
+}
+
+#[wasm_bindgen(js_name = sweep_translate)]
+pub fn sweep_translate_js(vertices: &JsValue, dx: f64, dy: f64, distance: f64) -> Result<JsValue, JsValue> {
+    let polygon: Polygon = crate::polygon_from(vertices)?;
+    crate::to_js(&sweep_translate(&polygon, &Vector::new(dx, dy), distance))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn l_shape() -> Polygon {
+        Polygon::new(vec![
+            Point::new(0.0, 0.0), Point::new(2.0, 0.0), Point::new(2.0, 1.0),
+            Point::new(1.0, 1.0), Point::new(1.0, 2.0), Point::new(0.0, 2.0),
+        ])
+    }
+
+    #[test]
+    fn test_square_sweeps_to_rectangle() {
+        let square = Polygon::new(vec![Point::new(0.0, 0.0), Point::new(1.0, 0.0), Point::new(1.0, 1.0), Point::new(0.0, 1.0)]);
+        let swept = sweep_translate(&square, &Vector::new(1.0, 0.0), 3.0);
+        assert_eq!(swept.vertices.len(), 4);
+        let bbox = swept.bounding_box();
+        assert_eq!((bbox.width(), bbox.height()), (4.0, 1.0));
+        assert!((swept.area() - 4.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_l_shape_swept_area() {
+        // Every line along the travel meets the L in one interval, so the
+        // swept area is the L's area plus travel times its perpendicular extent
+        let swept = sweep_translate(&l_shape(), &Vector::new(1.0, 0.0), 1.0);
+        assert!((swept.area() - 5.0).abs() < 1e-9);
+        assert_eq!(swept.vertices.len(), 6);
+
+        let diagonal = sweep_translate(&l_shape(), &Vector::new(1.0, 1.0), 2f64.sqrt());
+        assert!((diagonal.area() - (3.0 + 2f64.sqrt() * 2.0 * 2f64.sqrt())).abs() < 1e-9);
+        assert!(ring_signed_area(&diagonal.vertices) > 0.0);
+    }
+}
@@ -0,0 +1,248 @@
+use std::collections::BTreeMap;
+use std::f64::consts::TAU;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use crate::hash::ContentHasher;
+use crate::{
+    format_number, Angle, AngleQuadrant, Arc, AttributeValue, BSpline, DimensionKind, Document, EdgeAngleDimension, EdgeRef, Entity, Library, LibraryContent, Matrix, NamedView,
+    Outline, OutlineEdge, Parameters, Point, Polygon, Polyline, Segment, ShapeRadialDimension, Vector, ViewportState,
+};
+
+// Digits kept in golden output: enough to catch real changes, few enough
+// that last-bit differences in sin and cos between platforms don't show
+const GOLDEN_PRECISION: Option<usize> = Some(6);
+
+const SCENE_SVG: &str = r#"<svg xmlns="http://www.w3.org/2000/svg">
+  <g id="plan" data-layer="imported" transform="translate(200 -40) scale(2)">
+    <g id="desk" transform="rotate(30)">
+      <rect id="top" x="0" y="0" width="8" height="4"/>
+      <ellipse id="lamp" cx="6" cy="1" rx="1" ry="0.5"/>
+    </g>
+    <polygon id="rug" points="0,10 6,10 6,14 0,14"/>
+  </g>
+</svg>"#;
+
+// A document touching every kind of content the engine models: parameters
+// and generated shapes, linear, angular and radial dimensions, layers,
+// labels and attributes, local transforms and pivots, nested groups, a
+// region built from segments, an arc and a polyline, another from an
+// outline boolean, a spline, library instances, a swept shape, an imported
+// SVG and named views. The same seed always builds the same document.
+pub fn build_reference_scene(seed: u64) -> Document {
+    let mut state = seed;
+    let mut next = move || {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (state >> 11) as f64 / (1u64 << 53) as f64
+    };
+    let mut doc = Document::new();
+
+    let width = 40.0 + (next() * 20.0).round();
+    doc.set_parameter("width", &width.to_string()).unwrap();
+    doc.set_parameter("height", "width / 2").unwrap();
+    doc.set_parameter("corner", "min(3mm, height / 4)").unwrap();
+    let plate = doc.add_generated("create_rectangle(width, height)").unwrap();
+    doc.add_generated("create_rounded_rect(width, height, corner)").unwrap();
+    doc.add_generated("create_triangle(width / 2, height)").unwrap();
+    doc.add_dimension(plate, DimensionKind::OverallWidth, Point::new(0.0, 0.0), Point::new(width, 0.0)).unwrap();
+    doc.add_dimension(plate, DimensionKind::OverallHeight, Point::new(0.0, 0.0), Point::new(0.0, width / 2.0)).unwrap();
+    doc.set_parameter("width", &(width + 10.0).to_string()).unwrap();
+    doc.regenerate().unwrap();
+
+    // Jittered regular polygons across the layers
+    let layers = [Some("walls"), Some("fixtures"), None];
+    let mut loose = Vec::new();
+    for k in 0..9 {
+        let sides = 3 + (next() * 6.0) as usize;
+        let radius = 2.0 + next() * 8.0;
+        let vertices = (0..sides).map(|i| {
+            let a = std::f64::consts::TAU * (i as f64 + next() * 0.3) / sides as f64;
+            Point::new(radius * a.cos(), radius * a.sin())
+        }).collect();
+        let id = doc.add_shape(Polygon::new(vertices));
+        doc.set_layer(id, layers[k % 3].map(String::from)).unwrap();
+        doc.set_label(id, Some(format!("part-{}", k))).unwrap();
+        doc.set_attribute(id, "mass", AttributeValue::Number((next() * 100.0).round())).unwrap();
+        doc.set_attribute(id, "material", AttributeValue::Text(["steel", "oak", "glass"][k % 3].to_string())).unwrap();
+        doc.set_transform(id, Matrix::translate(-80.0 + 20.0 * k as f64, 60.0 + next() * 10.0)).unwrap();
+        if k % 2 == 1 {
+            doc.set_pivot(id, Point::new(radius / 2.0, 0.0)).unwrap();
+            doc.rotate_shape(id, next() * 3.0).unwrap();
+        }
+        if k % 3 == 2 {
+            doc.scale_shape(id, 1.5, 0.75).unwrap();
+        }
+        loose.push(id);
+    }
+
+    // A D-shaped region: straight base, arc, and an open polyline back
+    let entities = [
+        Entity::Segment(Segment::new(Point::new(0.0, -40.0), Point::new(10.0, -40.0))),
+        Entity::Arc(Arc { center: Point::new(10.0, -35.0), radius: 5.0, start_angle: -std::f64::consts::FRAC_PI_2, sweep: std::f64::consts::PI }),
+        Entity::Polyline(Polyline::new(vec![Point::new(10.0, -30.0), Point::new(0.0, -30.0), Point::new(0.0, -40.0)], false)),
+    ];
+    doc.create_region(&entities, 1e-6).unwrap();
+
+    doc.sweep_shapes(&loose[..1], &Vector::new(1.0, -1.0), 15.0).unwrap();
+    doc.import_svg(SCENE_SVG).unwrap();
+
+    // Two of the loose parts turned together, in a group moved up
+    let pair = doc.create_group(&loose[3..5]).unwrap();
+    doc.set_transform(pair, Matrix::rotate_about(0.4, &Point::new(0.0, 60.0))).unwrap();
+    let outer = doc.create_group(&[pair, loose[5]]).unwrap();
+    doc.set_transform(outer, Matrix::translate(0.0, 25.0)).unwrap();
+
+    let control = (0..6).map(|i| Point::new(-60.0 + 12.0 * i as f64, -60.0 + next() * 10.0)).collect();
+    doc.add_spline(BSpline::clamped(3, control).unwrap());
+
+    let mut library = Library::new();
+    let mut slots = Parameters::default();
+    slots.set("size", "8").unwrap();
+    library.define("bracket", LibraryContent::Generated { parameters: slots, calls: vec!["create_rectangle(size, size / 4)".into(), "create_triangle(size / 2, size)".into()] }).unwrap();
+    for (k, size) in [6.0, 12.0].into_iter().enumerate() {
+        let placed = Matrix::translate(120.0 + 30.0 * k as f64, -20.0).multiply(&Matrix::rotate(next()));
+        doc.place_library_item(&library, "bracket", &BTreeMap::from([("size".to_string(), size)]), placed).unwrap();
+    }
+
+    // A slab with a round notch, from an outline difference
+    let slab = Outline::from_polygon(&Polygon::new(vec![Point::new(60.0, -60.0), Point::new(90.0, -60.0), Point::new(90.0, -40.0), Point::new(60.0, -40.0)]));
+    let notched = slab.difference(&Outline::circle(Point::new(90.0, -50.0), 6.0));
+    let edges: Vec<Entity> = notched[0].edges.iter().map(|e| match *e {
+        OutlineEdge::Line(s) => Entity::Segment(s),
+        OutlineEdge::Arc(a) => Entity::Arc(a),
+    }).collect();
+    doc.create_region(&edges, 1e-6).unwrap();
+
+    let hole = doc.add_shape(Polygon::new((0..48).map(|i| {
+        let a = TAU * i as f64 / 48.0;
+        Point::new(75.0 + 4.0 * a.cos(), 20.0 + 4.0 * a.sin())
+    }).collect()));
+    doc.add_radial_dimension(ShapeRadialDimension { id: 0, shape: hole, leader_angle: 0.8, diameter: true, leader_length: 3.0, arrow_size: 1.0, precision: 2 }).unwrap();
+    for (k, dms) in [false, true].into_iter().enumerate() {
+        let edges = (EdgeRef { shape: loose[k], edge: 0 }, EdgeRef { shape: loose[k], edge: 1 });
+        doc.add_angular_dimension(EdgeAngleDimension { id: 0, edge_a: edges.0, edge_b: edges.1, offset: 4.0, quadrant: AngleQuadrant::Inside, arrow_size: 1.0, precision: 1, dms }).unwrap();
+    }
+
+    let mut detail = NamedView::new(ViewportState { center: Point::new(75.0, 20.0), zoom: 8.0 });
+    detail.rotation = Angle::from_degrees(15.0);
+    detail.layers.insert("fixtures".to_string(), false);
+    doc.save_view("detail", detail);
+    doc.save_view("overall", NamedView::new(ViewportState { center: Point::new(0.0, 0.0), zoom: 1.0 }));
+    doc
+}
+
+// Digest of the render buffer: the triangle count, and a hash of the
+// coordinates to three decimals, well above f32 rounding at scene scale
+pub fn tessellation_digest(doc: &Document) -> String {
+    let buffer = doc.tessellate();
+    let mut h = ContentHasher::new();
+    for v in &buffer {
+        h.str(&format_number(*v as f64, Some(3)));
+    }
+    format!("triangles {}\nhash {:016x}\n", buffer.len() / 6, h.finish())
+}
+
+// Counts and measures of a document, one per line, for golden comparison
+pub fn scene_statistics(doc: &Document) -> String {
+    let num = |v: f64| format_number(v, GOLDEN_PRECISION);
+    let mut out = String::new();
+    let shapes: Vec<_> = doc.shapes().collect();
+    writeln!(out, "shapes {}", shapes.len()).unwrap();
+    let mut layers: Vec<Option<&str>> = shapes.iter().map(|s| s.layer.as_deref()).collect();
+    layers.sort();
+    layers.dedup();
+    for layer in layers {
+        let count = shapes.iter().filter(|s| s.layer.as_deref() == layer).count();
+        writeln!(out, "layer {} {}", layer.unwrap_or("-"), count).unwrap();
+    }
+    for s in &shapes {
+        let world = s.world_geometry();
+        writeln!(out, "shape {} vertices {} area {} perimeter {}", s.id, world.vertices.len(), num(world.area()), num(world.perimeter())).unwrap();
+    }
+    for d in doc.dimensions() {
+        writeln!(out, "dimension {} {:?} {}", d.id, d.kind, num(doc.dimension_value(d.id).unwrap())).unwrap();
+    }
+    for id in doc.angular_dimensions().map(|d| d.id).chain(doc.radial_dimensions().map(|d| d.id)) {
+        let graphic = doc.render_dimension(id).unwrap();
+        writeln!(out, "dimension {} {} {}", id, num(graphic.value), graphic.text).unwrap();
+    }
+    for g in doc.groups() {
+        writeln!(out, "group {} members {}", g.id, doc.group_members(g.id).unwrap().len()).unwrap();
+    }
+    for instance in doc.library_instances() {
+        writeln!(out, "instance {} {} version {}", instance.group, instance.item, instance.version).unwrap();
+    }
+    for id in doc.curve_ids() {
+        let curve = doc.curve(id).unwrap();
+        writeln!(out, "curve {} control points {} polyline {}", id, curve.spline().control_points.len(), curve.polyline().len()).unwrap();
+    }
+    for name in doc.list_views() {
+        let view = doc.get_view(&name).unwrap();
+        writeln!(out, "view {} zoom {} layers {}", name, num(view.state.zoom), view.layers.len()).unwrap();
+    }
+    let values = doc.parameters().evaluate().unwrap();
+    for (name, value) in &values {
+        writeln!(out, "parameter {} {}", name, num(*value)).unwrap();
+    }
+    out
+}
+
+fn golden_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("goldens").join(name)
+}
+
+// Compares `actual` against the committed fixture `goldens/<name>`. Run
+// with UPDATE_GOLDENS=1 to rewrite the fixtures instead, then review the
+// diff before committing it.
+pub fn check_golden(name: &str, actual: &str) {
+    let path = golden_path(name);
+    if std::env::var("UPDATE_GOLDENS").is_ok_and(|v| v == "1") {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("can't read golden {}: {} (run with UPDATE_GOLDENS=1 to create it)", path.display(), e));
+    if expected != actual {
+        let line = expected.lines().zip(actual.lines()).position(|(a, b)| a != b).unwrap_or(expected.lines().count().min(actual.lines().count()));
+        panic!(
+            "{} differs from its golden at line {}:\n  expected: {}\n  actual:   {}\nrerun with UPDATE_GOLDENS=1 if the change is intended",
+            name, line + 1, expected.lines().nth(line).unwrap_or("<end>"), actual.lines().nth(line).unwrap_or("<end>"),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scene_is_deterministic() {
+        let (a, b) = (build_reference_scene(1), build_reference_scene(1));
+        assert_eq!(a.content_hash_all(), b.content_hash_all());
+        assert_ne!(a.content_hash_all(), build_reference_scene(2).content_hash_all());
+        assert!(a.shapes().any(|s| s.layer.as_deref() == Some("imported")));
+        assert_eq!(a.dimensions().count(), 2);
+        assert_eq!((a.angular_dimensions().count(), a.radial_dimensions().count()), (2, 1));
+        assert_eq!((a.library_instances().len(), a.curve_ids().len(), a.list_views().len()), (2, 1, 2));
+    }
+
+    #[test]
+    fn test_svg_export_golden() {
+        check_golden("reference_scene.svg", &build_reference_scene(1).export("svg", GOLDEN_PRECISION).unwrap());
+    }
+
+    #[test]
+    fn test_dxf_export_golden() {
+        check_golden("reference_scene.dxf", &build_reference_scene(1).export("dxf", GOLDEN_PRECISION).unwrap());
+    }
+
+    #[test]
+    fn test_tessellation_golden() {
+        check_golden("reference_scene.tess", &tessellation_digest(&build_reference_scene(1)));
+    }
+
+    #[test]
+    fn test_statistics_golden() {
+        check_golden("reference_scene.stats", &scene_statistics(&build_reference_scene(1)));
+    }
+}
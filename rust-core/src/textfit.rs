@@ -0,0 +1,290 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::document::apply;
+use crate::export::format_number;
+use crate::{js_error, AttributeValue, CadError, DimensionGraphic, DimensionId, DimensionKind, Document, Polyline, EPSILON};
+
+// Monospace model: every character, spaces included, advances this many
+// character heights
+pub const CHAR_ADVANCE: f64 = 0.6;
+// Sharpest turn, in radians, a label may follow along a path
+const MAX_LABEL_TURN: f64 = std::f64::consts::PI / 6.0;
+const ELLIPSIS: char = '…';
+
+// What to do with text that is too long for the space it has. From JS this
+// is the externally tagged form, e.g. { ShrinkToFit: { min_height: 1.5 } }.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum FitStrategy {
+    // Cut the text short and end it with an ellipsis
+    Truncate,
+    // Make the characters smaller, but no smaller than `min_height`
+    ShrinkToFit { min_height: f64 },
+    // Shorten words as `abbreviate` describes
+    AbbreviateWords,
+}
+
+// Text as it will be drawn
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TextFitResult {
+    pub text: String,
+    pub char_height: f64,
+    pub width: f64,
+    // The strategy that produced `text`: Truncate when the one asked for
+    // could not make the text fit
+    pub strategy: FitStrategy,
+    // Whether the text is drawn as given, at the height asked for
+    pub unchanged: bool,
+}
+
+// Strategy and height for one kind of annotation text
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct TextFit {
+    pub strategy: FitStrategy,
+    pub char_height: f64,
+}
+
+pub fn text_width(text: &str, char_height: f64) -> f64 {
+    text.chars().count() as f64 * CHAR_ADVANCE * char_height
+}
+
+fn fits(text: &str, char_height: f64, available: f64) -> bool {
+    text_width(text, char_height) <= available + EPSILON
+}
+
+fn result(text: String, char_height: f64, strategy: FitStrategy, unchanged: bool) -> TextFitResult {
+    TextFitResult { width: text_width(&text, char_height), text, char_height, strategy, unchanged }
+}
+
+// As many leading characters as fit with an ellipsis after them, trailing
+// spaces dropped; empty when not even the ellipsis fits
+fn truncate(text: &str, char_height: f64, available: f64) -> String {
+    let room = ((available + EPSILON) / (CHAR_ADVANCE * char_height)).floor() as usize;
+    if room == 0 {
+        return String::new();
+    }
+    let kept: String = text.chars().take(room - 1).collect();
+    let mut kept = kept.trim_end().to_string();
+    kept.push(ELLIPSIS);
+    kept
+}
+
+fn is_vowel(c: char) -> bool {
+    matches!(c.to_ascii_lowercase(), 'a' | 'e' | 'i' | 'o' | 'u')
+}
+
+// Shortens `text` a step at a time until it fits, returning None if it
+// still doesn't once the steps run out. Words are split on spaces, and a
+// word with a digit in it is never touched, so values survive.
+//   1. Drop the vowels after the first letter of each word of four or more
+//      letters, longest words first and leftmost among equals.
+//   2. Drop whole words other than the first and last, from the middle out
+//      and the left of the middle first, leaving one ellipsis where they were.
+fn abbreviate(text: &str, char_height: f64, available: f64) -> Option<String> {
+    let mut words: Vec<String> = text.split(' ').map(|w| w.to_string()).collect();
+    let join = |words: &[String]| words.join(" ");
+    let keeps = |w: &str| w.chars().any(|c| c.is_ascii_digit());
+    let mut order: Vec<usize> = (0..words.len()).filter(|&i| words[i].chars().count() >= 4 && !keeps(&words[i])).collect();
+    order.sort_by_key(|&i| (std::cmp::Reverse(words[i].chars().count()), i));
+    for i in order {
+        let mut chars = words[i].chars();
+        let first = chars.next().unwrap();
+        words[i] = std::iter::once(first).chain(chars.filter(|&c| !is_vowel(c))).collect();
+        if fits(&join(&words), char_height, available) {
+            return Some(join(&words));
+        }
+    }
+
+    if words.len() < 3 {
+        return None;
+    }
+    let middle: Vec<usize> = (1..words.len() - 1).collect();
+    let centre = (words.len() - 1) / 2;
+    let mut dropping: Vec<usize> = middle.iter().copied().filter(|&i| !keeps(&words[i])).collect();
+    dropping.sort_by_key(|&i| (i.abs_diff(centre), std::cmp::Reverse(centre.saturating_sub(i))));
+    let mut dropped = vec![false; words.len()];
+    for i in dropping {
+        dropped[i] = true;
+        let mut shown: Vec<String> = Vec::new();
+        for (k, w) in words.iter().enumerate() {
+            if !dropped[k] {
+                shown.push(w.clone());
+            } else if !dropped[k - 1] {
+                shown.push(ELLIPSIS.to_string());
+            }
+        }
+        if fits(&join(&shown), char_height, available) {
+            return Some(join(&shown));
+        }
+    }
+    None
+}
+
+// How to draw `text` at `char_height` within `available_length`. The same
+// input always gives the same output.
+pub fn fit_text_to_length(text: &str, char_height: f64, available_length: f64, strategy: FitStrategy) -> TextFitResult {
+    let available = available_length.max(0.0);
+    if fits(text, char_height, available) {
+        return result(text.to_string(), char_height, strategy, true);
+    }
+    let fallback = |height: f64| result(truncate(text, height, available), height, FitStrategy::Truncate, false);
+    match strategy {
+        FitStrategy::Truncate => fallback(char_height),
+        FitStrategy::ShrinkToFit { min_height } => {
+            let needed = available / (text.chars().count() as f64 * CHAR_ADVANCE);
+            if needed + EPSILON >= min_height {
+                result(text.to_string(), needed.min(char_height), strategy, false)
+            } else {
+                fallback(min_height.min(char_height))
+            }
+        }
+        FitStrategy::AbbreviateWords => match abbreviate(text, char_height, available) {
+            Some(short) => result(short, char_height, strategy, false),
+            None => fallback(char_height),
+        },
+    }
+}
+
+impl Polyline {
+    // Longest stretch of the path that no turn sharper than 30° breaks,
+    // less one character height of clearance split between its ends
+    pub fn max_label_length(&self, char_height: f64) -> f64 {
+        let segments: Vec<_> = self.segments().into_iter().filter(|s| s.2 > EPSILON).collect();
+        let (mut best, mut run) = (0.0f64, 0.0);
+        for (i, (a, b, length)) in segments.iter().enumerate() {
+            let gentle = i > 0 && {
+                let (pa, pb, _) = segments[i - 1];
+                let turn = (b.y - a.y).atan2(b.x - a.x) - (pb.y - pa.y).atan2(pb.x - pa.x);
+                turn.sin().atan2(turn.cos()).abs() <= MAX_LABEL_TURN
+            };
+            run = if gentle { run + length } else { *length };
+            best = best.max(run);
+        }
+        (best - char_height).max(0.0)
+    }
+}
+
+impl DimensionGraphic {
+    // The graphic's text as `fit` draws it in `available_length`, e.g. the
+    // length of a leader's landing
+    pub fn fit_text(&self, available_length: f64, fit: &TextFit) -> TextFitResult {
+        fit_text_to_length(&self.text, fit.char_height, available_length, fit.strategy)
+    }
+}
+
+impl Document {
+    pub fn text_fit(&self) -> Option<TextFit> {
+        self.text_fit
+    }
+
+    // Strategy and height dimension text is drawn with; None leaves text
+    // as it is
+    pub fn set_text_fit(&mut self, fit: Option<TextFit>) {
+        self.text_fit = fit;
+    }
+
+    // Dimension text fitted between the dimension's points as placed: the
+    // dimension's `text` attribute if it has one, otherwise its value to two
+    // decimals, after Ø for a hole diameter
+    pub fn dimension_text(&self, id: DimensionId) -> Result<TextFitResult, CadError> {
+        let dimension = self.dimension(id)?;
+        let text = match dimension.attributes.get("text") {
+            Some(AttributeValue::Text(text)) => text.clone(),
+            _ => {
+                let prefix = if dimension.kind == DimensionKind::HoleDiameter { "Ø" } else { "" };
+                format!("{}{}", prefix, format_number(self.dimension_value(id)?, Some(2)))
+            }
+        };
+        let m = self.shape(dimension.shape)?.world_transform();
+        let (from, to) = (apply(&m, &dimension.from), apply(&m, &dimension.to));
+        Ok(match self.text_fit {
+            Some(fit) => fit_text_to_length(&text, fit.char_height, (to.x - from.x).hypot(to.y - from.y), fit.strategy),
+            None => result(text, 1.0, FitStrategy::Truncate, true),
+        })
+    }
+}
+
+// `strategy` is "Truncate", "AbbreviateWords" or { ShrinkToFit: { min_height } };
+// returns { text, char_height, width, strategy, unchanged }
+#[wasm_bindgen(js_name = fit_text_to_length)]
+pub fn fit_text_to_length_js(text: &str, char_height: f64, available_length: f64, strategy: &JsValue) -> Result<JsValue, JsValue> {
+    let strategy: FitStrategy = crate::from_js(strategy)?;
+    crate::to_js(&fit_text_to_length(text, char_height, available_length, strategy))
+}
+
+#[wasm_bindgen]
+pub fn polyline_max_label_length(polyline: &JsValue, char_height: f64) -> Result<f64, JsValue> {
+    let polyline: Polyline = crate::from_js(polyline)?;
+    Ok(polyline.max_label_length(char_height))
+}
+
+#[wasm_bindgen]
+impl Document {
+    // { strategy, char_height }, or null to leave text as it is
+    #[wasm_bindgen(js_name = set_text_fit)]
+    pub fn set_text_fit_js(&mut self, fit: &JsValue) -> Result<(), JsValue> {
+        let fit: Option<TextFit> = if fit.is_null() || fit.is_undefined() { None } else { Some(crate::from_js(fit)?) };
+        self.set_text_fit(fit);
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = dimension_text)]
+    pub fn dimension_text_js(&self, id: DimensionId) -> Result<JsValue, JsValue> {
+        crate::to_js(&self.dimension_text(id).map_err(js_error)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Matrix, Point, Polygon};
+
+    const LABEL: &str = "Northern Mechanical Equipment Room 204";
+
+    #[test]
+    fn test_each_strategy_on_one_label() {
+        // 38 characters at height 1 take 22.8; 12 leaves room for 20
+        let truncated = fit_text_to_length(LABEL, 1.0, 12.0, FitStrategy::Truncate);
+        assert_eq!(truncated.text, "Northern Mechanical…");
+        assert!(truncated.width <= 12.0 && !truncated.unchanged);
+
+        let shrunk = fit_text_to_length(LABEL, 1.0, 12.0, FitStrategy::ShrinkToFit { min_height: 0.5 });
+        assert_eq!(shrunk.text, LABEL);
+        assert!((shrunk.char_height - 12.0 / 22.8).abs() < 1e-12 && (shrunk.width - 12.0).abs() < 1e-9);
+        // Even the smallest height is too big: truncated at that height
+        let fallback = fit_text_to_length(LABEL, 1.0, 12.0, FitStrategy::ShrinkToFit { min_height: 0.8 });
+        assert_eq!((fallback.strategy, fallback.char_height), (FitStrategy::Truncate, 0.8));
+        assert_eq!(fallback.text, "Northern Mechanical Equi…");
+
+        // Vowels go from the longest words first; the room number never
+        // changes
+        let abbreviated = fit_text_to_length(LABEL, 1.0, 17.0, FitStrategy::AbbreviateWords);
+        assert_eq!(abbreviated.text, "Nrthrn Mchncl Eqpmnt Rm 204");
+        let shorter = fit_text_to_length(LABEL, 1.0, 10.0, FitStrategy::AbbreviateWords);
+        assert_eq!(shorter.text, "Nrthrn … Rm 204");
+        assert_eq!(fit_text_to_length(LABEL, 1.0, 10.0, FitStrategy::AbbreviateWords), shorter);
+        let hopeless = fit_text_to_length(LABEL, 1.0, 3.0, FitStrategy::AbbreviateWords);
+        assert_eq!((hopeless.text.as_str(), hopeless.strategy), ("Nort…", FitStrategy::Truncate));
+        assert!(fit_text_to_length(LABEL, 1.0, 30.0, FitStrategy::AbbreviateWords).unchanged);
+    }
+
+    #[test]
+    fn test_label_room_and_dimension_text() {
+        // A gentle bend keeps the run going, the right angle breaks it
+        let path = Polyline::new(vec![Point::new(0.0, 0.0), Point::new(4.0, 0.0), Point::new(8.0, 1.0), Point::new(8.0, 4.0)], false);
+        let bent = 4.0 + 17f64.sqrt();
+        assert!((path.max_label_length(0.5) - (bent - 0.5)).abs() < 1e-12);
+        assert_eq!(Polyline::new(vec![Point::new(0.0, 0.0), Point::new(0.2, 0.0)], false).max_label_length(1.0), 0.0);
+
+        let mut doc = Document::new();
+        let plate = doc.add_shape(Polygon::new(vec![Point::new(0.0, 0.0), Point::new(3.0, 0.0), Point::new(3.0, 1.0), Point::new(0.0, 1.0)]));
+        let width = doc.add_dimension(plate, DimensionKind::OverallWidth, Point::new(0.0, 0.0), Point::new(3.0, 0.0)).unwrap();
+        assert_eq!(doc.dimension_text(width).unwrap().text, "3.00");
+        doc.dimension_mut(width).unwrap().attributes.insert("text".to_string(), AttributeValue::Text("Overall plate width".to_string()));
+        doc.set_text_fit(Some(TextFit { strategy: FitStrategy::Truncate, char_height: 1.0 }));
+        assert_eq!(doc.dimension_text(width).unwrap().text, "Over…");
+        // Room follows the shape's transform
+        doc.set_transform(plate, Matrix::scale(4.0, 1.0)).unwrap();
+        assert_eq!(doc.dimension_text(width).unwrap().text, "Overall plate width");
+    }
+}
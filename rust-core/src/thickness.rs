@@ -0,0 +1,224 @@
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::arrangement::{drop_collinear, Arrangement};
+use crate::predicates::{point_in_ring, ring_signed_area};
+use crate::{JoinStyle, MultiPolygon, Point, Polygon, PolygonWithHoles, Segment, SegmentIntersection, EPSILON};
+
+// Narrowest place of a shape: `width` across it along `section`, the
+// shortest chord through the inside that meets the boundary square-on at
+// both ends, or at a reflex corner. A shape with no area has width 0 and no
+// section.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ThinRegionReport {
+    pub width: f64,
+    pub section: Option<Segment>,
+}
+
+fn near(p: &Point, q: &Point) -> bool {
+    (p.x - q.x).hypot(p.y - q.y) <= EPSILON
+}
+
+// Whether `p` is closer to the corner `v` than to either edge at it, so a
+// chord from `v` towards `p` meets both edges at no more than a right angle
+fn in_corner_cone(prev: &Point, v: &Point, next: &Point, p: &Point) -> bool {
+    let (wx, wy) = (p.x - v.x, p.y - v.y);
+    wx * (v.x - prev.x) + wy * (v.y - prev.y) >= 0.0 && wx * (next.x - v.x) + wy * (next.y - v.y) <= 0.0
+}
+
+// Chords between every pair of edges that don't share a corner, each a
+// local minimum of the distance across: edge end to the foot of its
+// perpendicular on the other edge, the middle of the stretch where parallel
+// edges face each other, and corner to corner where each lies in the
+// other's cone
+fn chords(rings: &[Vec<Point>]) -> Vec<Segment> {
+    let mut edges: Vec<(usize, usize, Segment)> = Vec::new();
+    for (r, ring) in rings.iter().enumerate() {
+        let n = ring.len();
+        edges.extend((0..n).map(|i| (r, i, Segment::new(ring[i], ring[(i + 1) % n]))));
+    }
+    let adjacent = |a: &(usize, usize, Segment), b: &(usize, usize, Segment)| {
+        let n = rings[a.0].len();
+        a.0 == b.0 && (a.1 == b.1 || (a.1 + 1) % n == b.1 || (b.1 + 1) % n == a.1)
+    };
+    let mut found = Vec::new();
+    for (i, e) in edges.iter().enumerate() {
+        for f in &edges[i + 1..] {
+            if adjacent(e, f) {
+                continue;
+            }
+            let (e, f) = (&e.2, &f.2);
+            for (from, onto) in [(e.a, f), (e.b, f), (f.a, e), (f.b, e)] {
+                let t = onto.parameter(&from);
+                if (0.0..=1.0).contains(&t) {
+                    found.push(Segment::new(from, onto.closest_point(&from)));
+                }
+            }
+            let (dx, dy) = (e.b.x - e.a.x, e.b.y - e.a.y);
+            let cross = dx * (f.b.y - f.a.y) - dy * (f.b.x - f.a.x);
+            if cross.abs() <= EPSILON * e.length() * f.length() {
+                let (t0, t1) = (e.parameter(&f.a), e.parameter(&f.b));
+                let (lo, hi) = (t0.min(t1).max(0.0), t0.max(t1).min(1.0));
+                if hi > lo {
+                    let t = (lo + hi) / 2.0;
+                    let p = Point::new(e.a.x + dx * t, e.a.y + dy * t);
+                    found.push(Segment::new(p, f.closest_point(&p)));
+                }
+            }
+        }
+    }
+    let corners: Vec<(Point, Point, Point)> = rings.iter().flat_map(|ring| {
+        let n = ring.len();
+        (0..n).map(move |i| (ring[(i + n - 1) % n], ring[i], ring[(i + 1) % n]))
+    }).collect();
+    for (i, a) in corners.iter().enumerate() {
+        for b in &corners[i + 1..] {
+            if in_corner_cone(&a.0, &a.1, &a.2, &b.1) && in_corner_cone(&b.0, &b.1, &b.2, &a.1) {
+                found.push(Segment::new(a.1, b.1));
+            }
+        }
+    }
+    found.retain(|s| s.length() > EPSILON);
+    found
+}
+
+// Whether `chord` runs through the inside, touching the boundary only at
+// its ends
+fn clear(chord: &Segment, edges: &[Segment], inside: &impl Fn(&Point) -> bool) -> bool {
+    let mid = chord.midpoint();
+    inside(&mid) && edges.iter().all(|e| e.distance_to_point(&mid) > EPSILON && match chord.intersect(e) {
+        SegmentIntersection::None => true,
+        SegmentIntersection::Point(p) => near(&p, &chord.a) || near(&p, &chord.b),
+        SegmentIntersection::Overlap(_) => false,
+    })
+}
+
+fn narrowest(rings: Vec<Vec<Point>>, inside: impl Fn(&Point) -> bool) -> ThinRegionReport {
+    let rings: Vec<Vec<Point>> = rings.into_iter().filter(|r| r.len() >= 3).collect();
+    let edges: Vec<Segment> = rings.iter().flat_map(|r| (0..r.len()).map(|i| Segment::new(r[i], r[(i + 1) % r.len()]))).collect();
+    let mut candidates = chords(&rings);
+    candidates.sort_by(|a, b| a.length().total_cmp(&b.length()));
+    match candidates.into_iter().find(|c| clear(c, &edges, &inside)) {
+        Some(section) => ThinRegionReport { width: section.length(), section: Some(section) },
+        None => ThinRegionReport { width: 0.0, section: None },
+    }
+}
+
+impl Polygon {
+    pub fn min_feature_size(&self) -> ThinRegionReport {
+        narrowest(vec![self.vertices.clone()], |p| point_in_ring(&self.vertices, p))
+    }
+
+    // Parts of the polygon narrower than `threshold`: what is left after
+    // taking away its opening, the polygon inset by half the threshold and
+    // outset back with mitred corners, so square corners aren't counted as
+    // thin. Corners sharper than a right angle are thin near their tips and
+    // show up as slivers.
+    pub fn thin_regions(&self, threshold: f64) -> MultiPolygon {
+        let mut ring = self.vertices.clone();
+        if ring.len() < 3 || threshold.is_nan() || threshold <= 0.0 {
+            return vec![];
+        }
+        if ring_signed_area(&ring) < 0.0 {
+            ring.reverse();
+        }
+        let miter = JoinStyle::Miter { limit: f64::INFINITY };
+        let opened: MultiPolygon = self.offset(-threshold / 2.0, miter).iter().flat_map(|p| p.offset(threshold / 2.0, miter)).collect();
+        let mut segments: Vec<Segment> = (0..ring.len()).map(|i| Segment::new(ring[i], ring[(i + 1) % ring.len()])).collect();
+        for p in &opened {
+            segments.extend((0..p.vertices.len()).map(|i| Segment::new(p.vertices[i], p.vertices[(i + 1) % p.vertices.len()])));
+        }
+        let arrangement = Arrangement::build(&segments, EPSILON * threshold.max(1.0));
+        arrangement.covered_boundary(|q| point_in_ring(&ring, q) && !opened.iter().any(|p| point_in_ring(&p.vertices, q)))
+            .into_iter()
+            .map(|r| drop_collinear(r, EPSILON))
+            .filter(|r| r.len() >= 3 && ring_signed_area(r) > EPSILON * threshold * threshold)
+            .map(Polygon::new)
+            .collect()
+    }
+}
+
+impl PolygonWithHoles {
+    // As `Polygon::min_feature_size`, with the webs between holes and the
+    // outline, or between two holes, measured too
+    pub fn min_feature_size(&self) -> ThinRegionReport {
+        let mut rings = vec![self.outer.vertices.clone()];
+        rings.extend(self.holes.iter().map(|h| h.vertices.clone()));
+        narrowest(rings, |p| point_in_ring(&self.outer.vertices, p) && !self.holes.iter().any(|h| point_in_ring(&h.vertices, p)))
+    }
+}
+
+// { width, section: { a, b } or null }
+#[wasm_bindgen]
+pub fn polygon_min_feature_size(vertices: &JsValue) -> Result<JsValue, JsValue> {
+    let polygon: Polygon = crate::polygon_from(vertices)?;
+    crate::to_js(&polygon.min_feature_size())
+}
+
+// Array of vertex arrays
+#[wasm_bindgen]
+pub fn polygon_thin_regions(vertices: &JsValue, threshold: f64) -> Result<JsValue, JsValue> {
+    let polygon: Polygon = crate::polygon_from(vertices)?;
+    let rings: Vec<Vec<Point>> = polygon.thin_regions(threshold).into_iter().map(|p| p.vertices).collect();
+    crate::to_js(&rings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn poly(points: &[(f64, f64)]) -> Polygon {
+        Polygon::new(points.iter().map(|&(x, y)| Point::new(x, y)).collect())
+    }
+
+    // Two 1 x 2 bells joined by a neck 0.4 wide and 1 long
+    fn dumbbell() -> Polygon {
+        poly(&[
+            (0.0, 0.0), (1.0, 0.0), (1.0, 0.8), (2.0, 0.8), (2.0, 0.0), (3.0, 0.0),
+            (3.0, 2.0), (2.0, 2.0), (2.0, 1.2), (1.0, 1.2), (1.0, 2.0), (0.0, 2.0),
+        ])
+    }
+
+    #[test]
+    fn test_dumbbell_neck_is_the_thin_region() {
+        let report = dumbbell().min_feature_size();
+        assert!((report.width - 0.4).abs() < 1e-9);
+        let section = report.section.unwrap();
+        assert!(section.midpoint().x >= 1.0 && section.midpoint().x <= 2.0);
+
+        let thin = dumbbell().thin_regions(0.5);
+        assert_eq!(thin.len(), 1);
+        let b = thin[0].bounding_box();
+        assert!((b.min.x - 1.0).abs() < 1e-9 && (b.max.x - 2.0).abs() < 1e-9);
+        assert!((b.min.y - 0.8).abs() < 1e-9 && (b.max.y - 1.2).abs() < 1e-9);
+        assert!((thin[0].area() - 0.4).abs() < 1e-9);
+        assert!(dumbbell().thin_regions(0.3).is_empty());
+    }
+
+    #[test]
+    fn test_widths_of_simple_shapes() {
+        let rect = poly(&[(0.0, 0.0), (3.0, 0.0), (3.0, 1.0), (0.0, 1.0)]);
+        assert_eq!(rect.min_feature_size().width, 1.0);
+        // A fine circle is as wide as it is across, not a chord long
+        let circle = Polygon::new((0..64).map(|i| {
+            let a = i as f64 * std::f64::consts::TAU / 64.0;
+            Point::new(a.cos(), a.sin())
+        }).collect());
+        assert!(circle.min_feature_size().width > 1.99);
+        // Two notches pinching to 0.2 between their reflex corners
+        let pinched = poly(&[
+            (0.0, 0.0), (2.0, 0.0), (2.0, 1.0), (1.1, 1.5), (2.0, 2.0),
+            (2.0, 3.0), (0.0, 3.0), (0.0, 2.0), (0.9, 1.5), (0.0, 1.0),
+        ]).min_feature_size();
+        assert!((pinched.width - 0.2).abs() < 1e-9);
+        assert_eq!(pinched.section.unwrap().midpoint(), Point::new(1.0, 1.5));
+        assert_eq!(Polygon::new(vec![]).min_feature_size(), ThinRegionReport { width: 0.0, section: None });
+
+        // A hole 0.1 from the outline leaves a thin web
+        let plate = PolygonWithHoles {
+            outer: poly(&[(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0)]),
+            holes: vec![poly(&[(1.0, 0.1), (1.0, 3.0), (3.0, 3.0), (3.0, 0.1)])],
+        };
+        assert!((plate.min_feature_size().width - 0.1).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,265 @@
+use std::collections::BTreeMap;
+use std::f64::consts::TAU;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::arrangement::{drop_collinear, Arrangement};
+use crate::predicates::{point_in_ring, ring_signed_area};
+use crate::search::AttributeValue;
+use crate::{CadError, Document, FeatureRef, Point, Polygon, PolygonWithHoles, Segment, ShapeId, EPSILON};
+
+// Segments used for a full circle of zone boundary
+const ZONE_SEGMENTS: usize = 48;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ToleranceSpec {
+    // Band along an edge, `plus` outside the shape and `minus` inside, cut
+    // square at the edge's ends
+    Linear { plus: f64, minus: f64 },
+    // Circle of this diameter about the nominal point
+    Positional { diameter: f64 },
+    PositionalBox { width: f64, height: f64 },
+    // Band of total width `width` centred on the boundary
+    Profile { width: f64 },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ToleranceZone {
+    pub shape: ShapeId,
+    pub feature: FeatureRef,
+    pub spec: ToleranceSpec,
+    pub zone: PolygonWithHoles,
+}
+
+fn invalid(message: String) -> CadError {
+    CadError::InvalidInput(message)
+}
+
+fn circle(center: Point, radius: f64) -> Vec<Point> {
+    (0..ZONE_SEGMENTS).map(|i| {
+        let a = TAU * i as f64 / ZONE_SEGMENTS as f64;
+        Point::new(center.x + radius * a.cos(), center.y + radius * a.sin())
+    }).collect()
+}
+
+// Counter-clockwise rectangle from a + n * left to b - n * right, where n is
+// the unit normal to the left of a -> b
+pub(crate) fn edge_rect(a: Point, b: Point, left: f64, right: f64) -> Option<Vec<Point>> {
+    let len = (b.x - a.x).hypot(b.y - a.y);
+    if len < EPSILON {
+        return None;
+    }
+    let (nx, ny) = (-(b.y - a.y) / len, (b.x - a.x) / len);
+    let at = |p: Point, f: f64| Point::new(p.x + nx * f, p.y + ny * f);
+    Some(vec![at(a, -right), at(b, -right), at(b, left), at(a, left)])
+}
+
+// Union of the pieces as one polygon: the largest outer ring, with every
+// hole of the union
+fn union(pieces: &[Vec<Point>]) -> PolygonWithHoles {
+    let segments: Vec<Segment> = pieces.iter()
+        .flat_map(|p| (0..p.len()).map(move |i| Segment::new(p[i], p[(i + 1) % p.len()])))
+        .collect();
+    let rings = Arrangement::build(&segments, EPSILON).covered_boundary(|q| pieces.iter().any(|p| point_in_ring(p, q)));
+    let mut outer: Option<Vec<Point>> = None;
+    let mut holes = Vec::new();
+    for ring in rings.into_iter().map(|r| drop_collinear(r, EPSILON)) {
+        if ring_signed_area(&ring) < 0.0 {
+            holes.push(Polygon::new(ring));
+        } else if outer.as_ref().is_none_or(|o| ring_signed_area(o) < ring_signed_area(&ring)) {
+            outer = Some(ring);
+        }
+    }
+    PolygonWithHoles::new(Polygon::new(outer.unwrap_or_default()), holes)
+}
+
+// Everything within `half` of the closed ring: a rectangle on each edge and
+// a disk on each vertex
+fn band(ring: &[Point], half: f64) -> PolygonWithHoles {
+    let n = ring.len();
+    let mut pieces: Vec<Vec<Point>> = (0..n).filter_map(|i| edge_rect(ring[i], ring[(i + 1) % n], half, half)).collect();
+    pieces.extend(ring.iter().map(|p| circle(*p, half)));
+    union(&pieces)
+}
+
+fn nominal_point(shape: &PolygonWithHoles, feature: FeatureRef) -> Result<Point, CadError> {
+    let outer = &shape.outer.vertices;
+    match feature {
+        FeatureRef::Hole(i) => shape.holes.get(i).ok_or_else(|| invalid(format!("no hole {}", i)))?.try_centroid(),
+        FeatureRef::OuterVertex(i) => outer.get(i).copied().ok_or_else(|| invalid(format!("no vertex {}", i))),
+        FeatureRef::Edge(i) => {
+            let (a, b) = edge(shape, i)?;
+            Ok(Point::new((a.x + b.x) / 2.0, (a.y + b.y) / 2.0))
+        }
+        FeatureRef::Outline => shape.outer.try_centroid(),
+    }
+}
+
+fn edge(shape: &PolygonWithHoles, i: usize) -> Result<(Point, Point), CadError> {
+    let outer = &shape.outer.vertices;
+    if i >= outer.len() || outer.len() < 2 {
+        return Err(invalid(format!("no edge {}", i)));
+    }
+    Ok((outer[i], outer[(i + 1) % outer.len()]))
+}
+
+// Region a feature of `shape` may deviate into. Linear bands apply to outer
+// edges, profile bands to an edge, a hole or the whole outline, and
+// positional zones are centred on the feature's nominal point: a hole's
+// centroid, a vertex, an edge's midpoint or the outline's centroid.
+pub fn tolerance_zone(shape: &PolygonWithHoles, feature: FeatureRef, tol: &ToleranceSpec) -> Result<PolygonWithHoles, CadError> {
+    let positive = |v: f64| v.is_finite() && v > 0.0;
+    let zone = match *tol {
+        ToleranceSpec::Linear { plus, minus } => {
+            let FeatureRef::Edge(i) = feature else {
+                return Err(invalid("linear tolerances apply to edges".to_string()));
+            };
+            if !(plus.is_finite() && minus.is_finite() && plus + minus > 0.0) {
+                return Err(invalid(format!("invalid linear tolerance +{} -{}", plus, minus)));
+            }
+            let (a, b) = edge(shape, i)?;
+            // Outside is to the right of a counter-clockwise outline
+            let (left, right) = if ring_signed_area(&shape.outer.vertices) < 0.0 { (plus, minus) } else { (minus, plus) };
+            let rect = edge_rect(a, b, left, right).ok_or_else(|| invalid(format!("edge {} is degenerate", i)))?;
+            PolygonWithHoles::new(Polygon::new(rect), vec![])
+        }
+        ToleranceSpec::Positional { diameter } if positive(diameter) => {
+            PolygonWithHoles::new(Polygon::new(circle(nominal_point(shape, feature)?, diameter / 2.0)), vec![])
+        }
+        ToleranceSpec::PositionalBox { width, height } if positive(width) && positive(height) => {
+            let c = nominal_point(shape, feature)?;
+            let (w, h) = (width / 2.0, height / 2.0);
+            let corners = vec![Point::new(c.x - w, c.y - h), Point::new(c.x + w, c.y - h), Point::new(c.x + w, c.y + h), Point::new(c.x - w, c.y + h)];
+            PolygonWithHoles::new(Polygon::new(corners), vec![])
+        }
+        ToleranceSpec::Profile { width } if positive(width) => match feature {
+            FeatureRef::Outline => band(&shape.outer.vertices, width / 2.0),
+            FeatureRef::Hole(i) => band(&shape.holes.get(i).ok_or_else(|| invalid(format!("no hole {}", i)))?.vertices, width / 2.0),
+            FeatureRef::Edge(i) => {
+                let (a, b) = edge(shape, i)?;
+                let mut pieces: Vec<Vec<Point>> = edge_rect(a, b, width / 2.0, width / 2.0).into_iter().collect();
+                pieces.extend([circle(a, width / 2.0), circle(b, width / 2.0)]);
+                union(&pieces)
+            }
+            FeatureRef::OuterVertex(_) => return Err(invalid("profile tolerances apply to edges and boundaries".to_string())),
+        },
+        _ => return Err(invalid(format!("tolerance sizes must be positive, got {:?}", tol))),
+    };
+    Ok(zone)
+}
+
+// Tolerances a shape carries as Number attributes: `profile_tol` (band width
+// around the outline), `position_tol` (diameter about the centroid) and
+// `edge_tol.<i>` (± band on outer edge i)
+fn attribute_tolerances(attributes: &BTreeMap<String, AttributeValue>) -> Vec<(FeatureRef, ToleranceSpec)> {
+    let mut specs = Vec::new();
+    for (key, value) in attributes {
+        let &AttributeValue::Number(v) = value else {
+            continue;
+        };
+        let spec = match key.as_str() {
+            "profile_tol" => (FeatureRef::Outline, ToleranceSpec::Profile { width: v }),
+            "position_tol" => (FeatureRef::Outline, ToleranceSpec::Positional { diameter: v }),
+            _ => match key.strip_prefix("edge_tol.").and_then(|i| i.parse().ok()) {
+                Some(i) => (FeatureRef::Edge(i), ToleranceSpec::Linear { plus: v, minus: v }),
+                None => continue,
+            },
+        };
+        specs.push(spec);
+    }
+    specs
+}
+
+// Every zone from the tolerance attributes of the shapes on `layer`, in
+// world space. Attributes that don't make a valid zone are skipped.
+pub fn tolerance_zones(doc: &Document, layer: &str) -> Vec<ToleranceZone> {
+    let mut zones = Vec::new();
+    for shape in doc.shapes().filter(|s| s.layer.as_deref() == Some(layer)) {
+        let world = PolygonWithHoles::new(shape.world_geometry(), vec![]);
+        for (feature, spec) in attribute_tolerances(&shape.attributes) {
+            if let Ok(zone) = tolerance_zone(&world, feature, &spec) {
+                zones.push(ToleranceZone { shape: shape.id, feature, spec, zone });
+            }
+        }
+    }
+    zones
+}
+
+#[wasm_bindgen(js_name = tolerance_zone)]
+pub fn tolerance_zone_js(shape: &JsValue, feature: &JsValue, tol: &JsValue) -> Result<JsValue, JsValue> {
+    let shape: PolygonWithHoles = crate::from_js(shape)?;
+    let feature: FeatureRef = crate::from_js(feature)?;
+    let tol: ToleranceSpec = crate::from_js(tol)?;
+    let zone = tolerance_zone(&shape, feature, &tol).map_err(crate::js_error)?;
+    crate::to_js(&zone)
+}
+
+#[wasm_bindgen(js_name = tolerance_zones)]
+pub fn tolerance_zones_js(doc: &Document, layer: &str) -> Result<JsValue, JsValue> {
+    crate::to_js(&tolerance_zones(doc, layer))
+}
+
+// Whether a measured point lies within a zone
+#[wasm_bindgen]
+pub fn zone_contains(zone: &JsValue, x: f64, y: f64) -> Result<bool, JsValue> {
+    Ok(crate::from_js::<PolygonWithHoles>(zone)?.contains_point(&Point::new(x, y)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(x: f64, y: f64, size: f64) -> Polygon {
+        Polygon::new(vec![Point::new(x, y), Point::new(x + size, y), Point::new(x + size, y + size), Point::new(x, y + size)])
+    }
+
+    #[test]
+    fn test_profile_band_of_unit_square() {
+        let shape = PolygonWithHoles::new(square(0.0, 0.0, 1.0), vec![]);
+        let zone = tolerance_zone(&shape, FeatureRef::Outline, &ToleranceSpec::Profile { width: 0.2 }).unwrap();
+        assert_eq!(zone.holes.len(), 1);
+        // Perimeter times width, plus the rounded outside corners minus the
+        // inside corners counted twice
+        let expected = 4.0 * 0.2 + (std::f64::consts::PI - 4.0) * 0.01;
+        assert!((zone.area() - expected).abs() < 1e-3, "{}", zone.area());
+        assert!(zone.contains_point(&Point::new(1.05, 0.5)));
+        assert!(zone.contains_point(&Point::new(0.5, 0.95)));
+        assert!(!zone.contains_point(&Point::new(0.5, 0.5)));
+        assert!(!zone.contains_point(&Point::new(1.08, 1.08)));
+    }
+
+    #[test]
+    fn test_positional_zone_classifies_measured_points() {
+        let plate = PolygonWithHoles::new(square(0.0, 0.0, 4.0), vec![square(1.5, 1.5, 1.0)]);
+        let zone = tolerance_zone(&plate, FeatureRef::Hole(0), &ToleranceSpec::Positional { diameter: 0.5 }).unwrap();
+        assert!(zone.contains_point(&Point::new(2.2, 2.1)));
+        assert!(!zone.contains_point(&Point::new(2.2, 2.2)));
+        assert!(tolerance_zone(&plate, FeatureRef::Hole(1), &ToleranceSpec::Positional { diameter: 0.5 }).is_err());
+
+        // Bottom edge: +0.2 below the shape, -0.1 into it
+        let band = tolerance_zone(&plate, FeatureRef::Edge(0), &ToleranceSpec::Linear { plus: 0.2, minus: 0.1 }).unwrap();
+        assert!((band.area() - 4.0 * 0.3).abs() < 1e-9);
+        assert!(band.contains_point(&Point::new(2.0, -0.15)) && !band.contains_point(&Point::new(2.0, 0.15)));
+    }
+
+    #[test]
+    fn test_document_zones_for_layer() {
+        let mut doc = Document::new();
+        let a = doc.add_shape(square(0.0, 0.0, 1.0));
+        let b = doc.add_shape(square(5.0, 0.0, 1.0));
+        doc.set_layer(a, Some("inspect".to_string())).unwrap();
+        doc.set_layer(b, Some("other".to_string())).unwrap();
+        for id in [a, b] {
+            doc.set_attribute(id, "profile_tol", AttributeValue::Number(0.2)).unwrap();
+        }
+        doc.set_attribute(a, "edge_tol.1", AttributeValue::Number(0.1)).unwrap();
+        doc.set_attribute(a, "note", AttributeValue::Text("x".to_string())).unwrap();
+
+        let zones = tolerance_zones(&doc, "inspect");
+        assert_eq!(zones.len(), 2);
+        assert!(zones.iter().all(|z| z.shape == a));
+        let edge = zones.iter().find(|z| z.feature == FeatureRef::Edge(1)).unwrap();
+        assert!((edge.zone.area() - 0.2).abs() < 1e-9);
+    }
+}
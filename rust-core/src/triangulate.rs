@@ -0,0 +1,428 @@
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::predicates::{orient2d, orient2d_robust, ring_signed_area};
+use crate::{Point, Polygon, EPSILON};
+
+// What a triangulation needed beyond plain ear clipping
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TriangulationDiagnostics {
+    // Times ear clipping ran out of ears and the rest of the ring went to
+    // the monotone triangulator instead
+    pub ear_fallbacks: usize,
+    // Triangles with next to no area for their size, as runs of collinear
+    // vertices can force
+    pub slivers: usize,
+}
+
+// Twice the area over the summed squared sides: largest for an equilateral
+// triangle, near zero for a sliver
+fn quality(a: &Point, b: &Point, c: &Point) -> f64 {
+    let sq = |p: &Point, q: &Point| (q.x - p.x).powi(2) + (q.y - p.y).powi(2);
+    let size = sq(a, b) + sq(b, c) + sq(c, a);
+    if size > 0.0 { orient2d(a, b, c) / size } else { 0.0 }
+}
+
+// Clips ears from the counter-clockwise `ring` of indices into `v`, always
+// the best-shaped ear first so long near-collinear runs aren't whittled
+// into slivers. Returns what is left: three vertices, or more if no ear
+// remains.
+fn clip_ears(v: &[Point], ring: &[usize], triangles: &mut Vec<[usize; 3]>) -> Vec<usize> {
+    let m = ring.len();
+    let mut prev: Vec<usize> = (0..m).map(|i| (i + m - 1) % m).collect();
+    let mut next: Vec<usize> = (0..m).map(|i| (i + 1) % m).collect();
+    let mut alive = vec![true; m];
+
+    let ear = |i: usize, prev: &[usize], next: &[usize], alive: &[bool]| -> Option<f64> {
+        let (a, b, c) = (&v[ring[prev[i]]], &v[ring[i]], &v[ring[next[i]]]);
+        if orient2d_robust(a, b, c) <= 0.0 {
+            return None;
+        }
+        // Another vertex inside or on the triangle blocks it, unless it only
+        // touches at a corner, as where a ring passes through a point twice
+        let blocked = (0..m).any(|k| {
+            let p = &v[ring[k]];
+            alive[k] && k != i && k != prev[i] && k != next[i] && p != a && p != b && p != c
+                && orient2d_robust(a, b, p) >= 0.0 && orient2d_robust(b, c, p) >= 0.0 && orient2d_robust(c, a, p) >= 0.0
+        });
+        (!blocked).then(|| quality(a, b, c))
+    };
+
+    let mut ears: Vec<Option<f64>> = (0..m).map(|i| ear(i, &prev, &next, &alive)).collect();
+    let mut left = m;
+    while left > 3 {
+        let best = (0..m).filter(|&i| alive[i]).filter_map(|i| ears[i].map(|q| (i, q))).max_by(|x, y| x.1.total_cmp(&y.1).then(y.0.cmp(&x.0)));
+        let Some((i, _)) = best else {
+            break;
+        };
+        triangles.push([ring[prev[i]], ring[i], ring[next[i]]]);
+        alive[i] = false;
+        left -= 1;
+        let (p, n) = (prev[i], next[i]);
+        next[p] = n;
+        prev[n] = p;
+        // Clipping only removes a vertex, so only the two corners it
+        // changed can gain or lose their ear
+        ears[p] = ear(p, &prev, &next, &alive);
+        ears[n] = ear(n, &prev, &next, &alive);
+    }
+
+    let start = (0..m).find(|&i| alive[i]).unwrap();
+    let mut rest = vec![ring[start]];
+    let mut i = next[start];
+    while i != start {
+        rest.push(ring[i]);
+        i = next[i];
+    }
+    rest
+}
+
+// Sweep order: higher first, then leftmost
+fn above(a: &Point, b: &Point) -> Ordering {
+    b.y.total_cmp(&a.y).then(a.x.total_cmp(&b.x))
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Turn {
+    Start,
+    End,
+    Split,
+    Merge,
+    Regular,
+}
+
+// Diagonals splitting the counter-clockwise `ring` into y-monotone pieces,
+// as positions in `ring`: a downward sweep joining each split and merge
+// vertex to the helper of the edge on its left
+fn monotone_diagonals(v: &[Point], ring: &[usize]) -> Vec<(usize, usize)> {
+    let m = ring.len();
+    let p = |i: usize| &v[ring[i]];
+    let (prev, next) = (|i: usize| (i + m - 1) % m, |i: usize| (i + 1) % m);
+    let higher = |i: usize, j: usize| above(p(i), p(j)).then(i.cmp(&j)) == Ordering::Less;
+    let turn: Vec<Turn> = (0..m).map(|i| {
+        let convex = orient2d_robust(p(prev(i)), p(i), p(next(i))) > 0.0;
+        match (higher(i, prev(i)), higher(i, next(i)), convex) {
+            (true, true, true) => Turn::Start,
+            (true, true, false) => Turn::Split,
+            (false, false, true) => Turn::End,
+            (false, false, false) => Turn::Merge,
+            _ => Turn::Regular,
+        }
+    }).collect();
+    let mut order: Vec<usize> = (0..m).collect();
+    order.sort_by(|&i, &j| above(p(i), p(j)).then(i.cmp(&j)));
+
+    // Edges from i to next(i) with the interior on their right, each with
+    // its helper
+    let mut status: Vec<(usize, usize)> = Vec::new();
+    let x_at = |e: usize, y: f64| {
+        let (a, b) = (p(e), p(next(e)));
+        if a.y == b.y { a.x.max(b.x) } else { a.x + (y - a.y) * (b.x - a.x) / (b.y - a.y) }
+    };
+    let left_of = |status: &[(usize, usize)], i: usize| -> Option<usize> {
+        let at = p(i);
+        (0..status.len()).filter(|&k| x_at(status[k].0, at.y) <= at.x)
+            .max_by(|&k, &l| x_at(status[k].0, at.y).total_cmp(&x_at(status[l].0, at.y)))
+    };
+    let mut diagonals = Vec::new();
+    let finish = |status: &mut Vec<(usize, usize)>, diagonals: &mut Vec<(usize, usize)>, e: usize, i: usize| {
+        if let Some(k) = status.iter().position(|s| s.0 == e) {
+            if turn[status[k].1] == Turn::Merge {
+                diagonals.push((i, status[k].1));
+            }
+            status.remove(k);
+        }
+    };
+    for i in order {
+        let reassign = |status: &mut Vec<(usize, usize)>, diagonals: &mut Vec<(usize, usize)>, always: bool| {
+            if let Some(k) = left_of(status, i) {
+                if always || turn[status[k].1] == Turn::Merge {
+                    diagonals.push((i, status[k].1));
+                }
+                status[k].1 = i;
+            }
+        };
+        match turn[i] {
+            Turn::Start => status.push((i, i)),
+            Turn::End => finish(&mut status, &mut diagonals, prev(i), i),
+            Turn::Split => {
+                reassign(&mut status, &mut diagonals, true);
+                status.push((i, i));
+            }
+            Turn::Merge => {
+                finish(&mut status, &mut diagonals, prev(i), i);
+                reassign(&mut status, &mut diagonals, false);
+            }
+            // On a left-hand chain the boundary runs downwards
+            Turn::Regular if higher(prev(i), i) => {
+                finish(&mut status, &mut diagonals, prev(i), i);
+                status.push((i, i));
+            }
+            Turn::Regular => reassign(&mut status, &mut diagonals, false),
+        }
+    }
+    diagonals
+}
+
+// The faces `diagonals` cut `ring` into, each counter-clockwise
+fn pieces(v: &[Point], ring: &[usize], diagonals: &[(usize, usize)]) -> Vec<Vec<usize>> {
+    let m = ring.len();
+    let mut out: Vec<Vec<usize>> = (0..m).map(|i| vec![(i + 1) % m]).collect();
+    for &(a, b) in diagonals {
+        out[a].push(b);
+        out[b].push(a);
+    }
+    let angle = |from: usize, to: usize| {
+        let (a, b) = (&v[ring[from]], &v[ring[to]]);
+        (b.y - a.y).atan2(b.x - a.x)
+    };
+    let mut seen = HashSet::new();
+    let mut faces = Vec::new();
+    for start in 0..m {
+        for &to in &out[start] {
+            if !seen.insert((start, to)) {
+                continue;
+            }
+            let mut face = vec![start];
+            let (mut u, mut w) = (start, to);
+            // Walk keeping the face on the left: at each vertex take the
+            // first edge clockwise from the one arrived along
+            while w != start && face.len() <= m {
+                face.push(w);
+                let back = angle(w, u);
+                let turn = |x: usize| {
+                    let d = (back - angle(w, x)).rem_euclid(std::f64::consts::TAU);
+                    if x == u || d == 0.0 { std::f64::consts::TAU } else { d }
+                };
+                let x = *out[w].iter().min_by(|&&x, &&y| turn(x).total_cmp(&turn(y))).unwrap();
+                (u, w) = (w, x);
+                seen.insert((u, w));
+            }
+            faces.push(face.into_iter().map(|i| ring[i]).collect());
+        }
+    }
+    faces
+}
+
+// Stack triangulation of a y-monotone counter-clockwise piece
+fn triangulate_piece(v: &[Point], face: &[usize], triangles: &mut Vec<[usize; 3]>) {
+    let k = face.len();
+    if k < 3 {
+        return;
+    }
+    let mut sorted: Vec<usize> = (0..k).collect();
+    sorted.sort_by(|&i, &j| above(&v[face[i]], &v[face[j]]).then(i.cmp(&j)));
+    // Walking forwards from the top runs down the left-hand chain
+    let mut on_left = vec![false; k];
+    let (top, bottom) = (sorted[0], sorted[k - 1]);
+    let mut i = top;
+    while i != bottom {
+        on_left[i] = true;
+        i = (i + 1) % k;
+    }
+    let mut emit = |a: usize, b: usize, c: usize| {
+        let (a, b, c) = (face[a], face[b], face[c]);
+        triangles.push(if orient2d(&v[a], &v[b], &v[c]) < 0.0 { [a, c, b] } else { [a, b, c] });
+    };
+
+    let mut stack = vec![sorted[0], sorted[1]];
+    for j in 2..k - 1 {
+        let u = sorted[j];
+        if on_left[u] != on_left[*stack.last().unwrap()] {
+            while stack.len() > 1 {
+                let t = stack.pop().unwrap();
+                emit(u, t, *stack.last().unwrap());
+            }
+            stack = vec![sorted[j - 1], u];
+        } else {
+            let mut last = stack.pop().unwrap();
+            while let Some(&top) = stack.last() {
+                let (pu, pl, pt) = (&v[face[u]], &v[face[last]], &v[face[top]]);
+                let inside = if on_left[u] { orient2d_robust(pt, pl, pu) > 0.0 } else { orient2d_robust(pu, pl, pt) > 0.0 };
+                if !inside {
+                    break;
+                }
+                emit(u, last, top);
+                last = stack.pop().unwrap();
+            }
+            stack.push(last);
+            stack.push(u);
+        }
+    }
+    let u = sorted[k - 1];
+    while stack.len() > 1 {
+        let t = stack.pop().unwrap();
+        emit(u, t, *stack.last().unwrap());
+    }
+}
+
+impl Polygon {
+    // Ear-clipping triangulation of a simple polygon. Triangles index into
+    // `vertices` and are counter-clockwise whatever the input winding.
+    pub fn triangulate(&self) -> Vec<[usize; 3]> {
+        self.triangulate_with_diagnostics().0
+    }
+
+    // As `triangulate`, reporting whether ear clipping stalled (only
+    // degenerate or self-touching input does) and how many slivers came out.
+    // A stalled ring is split into monotone pieces rather than clipped at a
+    // corner that isn't a true ear.
+    pub fn triangulate_with_diagnostics(&self) -> (Vec<[usize; 3]>, TriangulationDiagnostics) {
+        let v = &self.vertices;
+        let n = v.len();
+        let mut diagnostics = TriangulationDiagnostics::default();
+        if n < 3 {
+            return (Vec::new(), diagnostics);
+        }
+
+        let mut ring: Vec<usize> = (0..n).collect();
+        if ring_signed_area(v) < 0.0 {
+            ring.reverse();
+        }
+
+        let mut triangles = Vec::with_capacity(n - 2);
+        let rest = clip_ears(v, &ring, &mut triangles);
+        if rest.len() == 3 {
+            triangles.push([rest[0], rest[1], rest[2]]);
+        } else {
+            diagnostics.ear_fallbacks += 1;
+            for face in pieces(v, &rest, &monotone_diagonals(v, &rest)) {
+                triangulate_piece(v, &face, &mut triangles);
+            }
+        }
+
+        diagnostics.slivers = triangles.iter().filter(|t| quality(&v[t[0]], &v[t[1]], &v[t[2]]).abs() <= EPSILON).count();
+        (triangles, diagnostics)
+    }
+}
+
+#[wasm_bindgen]
+pub fn triangulation_diagnostics(vertices: &JsValue) -> Result<JsValue, JsValue> {
+    let polygon: Polygon = crate::polygon_from(vertices)?;
+    crate::to_js(&polygon.triangulate_with_diagnostics().1)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Point;
+
+    use super::*;
+
+    fn triangle_area(p: &Polygon, t: &[usize; 3]) -> f64 {
+        orient2d(&p.vertices[t[0]], &p.vertices[t[1]], &p.vertices[t[2]]) / 2.0
+    }
+
+    // Vertices of a fixture file, `{ "name": ..., "vertices": [[x, y], ...] }`
+    fn fixture(json: &str) -> Polygon {
+        let list = &json[json.find("\"vertices\"").unwrap() + 10..];
+        let numbers: Vec<f64> = list.split(|c: char| !(c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E')))
+            .filter(|s| !s.is_empty())
+            .map(|s| s.parse().unwrap())
+            .collect();
+        Polygon::new(numbers.chunks(2).map(|c| Point::new(c[0], c[1])).collect())
+    }
+
+    // Whether two triangles share interior, by separating axes; touching
+    // along an edge or at a corner doesn't count
+    fn overlap(a: [Point; 3], b: [Point; 3], tolerance: f64) -> bool {
+        for tri in [&a, &b] {
+            for i in 0..3 {
+                let (p, q) = (tri[i], tri[(i + 1) % 3]);
+                let axis = (q.y - p.y, p.x - q.x);
+                let project = |t: &[Point; 3]| {
+                    let d: Vec<f64> = t.iter().map(|r| r.x * axis.0 + r.y * axis.1).collect();
+                    (d.iter().cloned().fold(f64::INFINITY, f64::min), d.iter().cloned().fold(f64::NEG_INFINITY, f64::max))
+                };
+                let ((a0, a1), (b0, b1)) = (project(&a), project(&b));
+                let slack = tolerance * axis.0.hypot(axis.1);
+                if a1 <= b0 + slack || b1 <= a0 + slack {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    #[test]
+    fn test_concave_polygon_area_is_preserved() {
+        let u_shape = Polygon::new(vec![
+            Point::new(0.0, 0.0),
+            Point::new(3.0, 0.0),
+            Point::new(3.0, 3.0),
+            Point::new(2.0, 3.0),
+            Point::new(2.0, 1.0),
+            Point::new(1.0, 1.0),
+            Point::new(1.0, 3.0),
+            Point::new(0.0, 3.0),
+        ]);
+        let triangles = u_shape.triangulate();
+        assert_eq!(triangles.len(), 6);
+        for t in &triangles {
+            assert!(triangle_area(&u_shape, t) > 0.0);
+        }
+        let total: f64 = triangles.iter().map(|t| triangle_area(&u_shape, t)).sum();
+        assert!((total - u_shape.area()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_clockwise_input_gives_counter_clockwise_triangles() {
+        let square = Polygon::new(vec![Point::new(0.0, 0.0), Point::new(0.0, 1.0), Point::new(1.0, 1.0), Point::new(1.0, 0.0)]);
+        let triangles = square.triangulate();
+        assert_eq!(triangles.len(), 2);
+        assert!(triangles.iter().all(|t| triangle_area(&square, t) > 0.0));
+    }
+
+    #[test]
+    fn test_problem_polygons_conserve_area() {
+        let fixtures = [
+            include_str!("../fixtures/triangulation/long_wall.json"),
+            include_str!("../fixtures/triangulation/flat_arc.json"),
+            include_str!("../fixtures/triangulation/survey_parcel.json"),
+            include_str!("../fixtures/triangulation/comb.json"),
+        ];
+        for json in fixtures {
+            let polygon = fixture(json);
+            let (triangles, diagnostics) = polygon.triangulate_with_diagnostics();
+            assert_eq!(diagnostics.ear_fallbacks, 0);
+            let total: f64 = triangles.iter().map(|t| triangle_area(&polygon, t)).sum();
+            assert!((total - polygon.area()).abs() <= 1e-9 * polygon.area());
+            assert!(triangles.iter().all(|t| triangle_area(&polygon, t) >= 0.0));
+
+            let b = polygon.bounding_box();
+            let tolerance = 1e-9 * b.width().max(b.height());
+            let corners: Vec<[Point; 3]> = triangles.iter().map(|t| t.map(|i| polygon.vertices[i])).collect();
+            for i in 0..corners.len() {
+                for j in i + 1..corners.len() {
+                    assert!(!overlap(corners[i], corners[j], tolerance), "triangles {} and {} overlap", i, j);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_monotone_pieces_cover_the_ring() {
+        // Plain orient2d rounds this to zero; it is just left of the line
+        let (a, c) = (Point::new(0.5, 0.5), Point::new(12.0, 12.0));
+        let b = Point::new(0.03091471123878753, 0.030914711238787466);
+        assert_eq!(orient2d(&a, &b, &c), 0.0);
+        assert!(orient2d_robust(&a, &b, &c) > 0.0);
+        assert_eq!(orient2d_robust(&a, &Point::new(3.0, 3.0), &c), 0.0);
+
+        let comb = Polygon::new(vec![
+            Point::new(0.0, 0.0), Point::new(6.0, 0.0), Point::new(6.0, 3.0), Point::new(5.0, 3.0), Point::new(5.0, 1.0),
+            Point::new(4.0, 1.0), Point::new(4.0, 3.0), Point::new(3.0, 3.0), Point::new(3.0, 1.0), Point::new(2.0, 1.0),
+            Point::new(2.0, 3.0), Point::new(1.0, 3.0), Point::new(1.0, 1.0), Point::new(0.0, 3.0),
+        ]);
+        let ring: Vec<usize> = (0..comb.vertices.len()).collect();
+        let mut triangles = Vec::new();
+        for face in pieces(&comb.vertices, &ring, &monotone_diagonals(&comb.vertices, &ring)) {
+            triangulate_piece(&comb.vertices, &face, &mut triangles);
+        }
+        assert_eq!(triangles.len(), comb.vertices.len() - 2);
+        let total: f64 = triangles.iter().map(|t| triangle_area(&comb, t)).sum();
+        assert!((total - comb.area()).abs() < 1e-12);
+    }
+}
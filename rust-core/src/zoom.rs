@@ -0,0 +1,182 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::{Angle, BoundingBox, CadError, Document, Point, ShapeId, Viewport};
+
+// What "zoom to" fits in view
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum FitTarget {
+    Document,
+    // Shapes on the named layer, or the unlayered ones for None
+    Layer(Option<String>),
+    Shapes(Vec<ShapeId>),
+    Bounds(BoundingBox),
+}
+
+// Where a viewport looks: the world point at the canvas centre, and the
+// zoom in screen pixels per world unit
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ViewportState {
+    pub center: Point,
+    pub zoom: f64,
+}
+
+// View saved with a document under a name: where it looks, which
+// `Viewport::animate_to` can fly to, how far the canvas is turned, and
+// layers shown (true) or hidden (false) while it is recalled, overriding
+// their usual visibility. Layers it doesn't list are left as they are.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct NamedView {
+    pub state: ViewportState,
+    #[serde(default)]
+    pub rotation: Angle,
+    #[serde(default)]
+    pub layers: BTreeMap<String, bool>,
+}
+
+impl NamedView {
+    pub fn new(state: ViewportState) -> NamedView {
+        NamedView { state, rotation: Angle::default(), layers: BTreeMap::new() }
+    }
+}
+
+fn target_bounds(doc: &Document, target: &FitTarget) -> Result<Option<BoundingBox>, CadError> {
+    let ids: Vec<ShapeId> = match target {
+        FitTarget::Bounds(b) => return Ok(Some(*b)),
+        FitTarget::Document => doc.shapes().map(|s| s.id).collect(),
+        FitTarget::Layer(layer) => doc.shapes().filter(|s| &s.layer == layer).map(|s| s.id).collect(),
+        FitTarget::Shapes(ids) => ids.clone(),
+    };
+    let mut bounds: Option<BoundingBox> = None;
+    for id in ids {
+        let b = doc.display_geometry(id)?.bounding_box();
+        bounds = Some(bounds.map_or(b, |u| u.union(&b)));
+    }
+    Ok(bounds)
+}
+
+impl Viewport {
+    pub fn width_px(&self) -> f64 {
+        self.bounds.width() * self.scale
+    }
+
+    pub fn height_px(&self) -> f64 {
+        self.bounds.height() * self.scale
+    }
+
+    pub fn state(&self) -> ViewportState {
+        ViewportState { center: self.bounds.center(), zoom: self.scale }
+    }
+
+    // The same canvas looking at `state`
+    pub fn with_state(&self, state: &ViewportState) -> Viewport {
+        let (hw, hh) = (self.width_px() / state.zoom / 2.0, self.height_px() / state.zoom / 2.0);
+        let c = state.center;
+        Viewport { bounds: BoundingBox::new(Point::new(c.x - hw, c.y - hh), Point::new(c.x + hw, c.y + hh)), scale: state.zoom }
+    }
+
+    // Centre and zoom that show all of `target` with `padding_px` clear on
+    // every side, the tighter axis deciding the zoom so the other is
+    // letterboxed. Targets with no area zoom to `point_zoom` about their
+    // centre, and a line fits on its length alone. The zoom never exceeds
+    // `max_zoom`. An empty target leaves the view where it is.
+    pub fn fit_target(&self, doc: &Document, target: &FitTarget, padding_px: f64, max_zoom: f64, point_zoom: f64) -> Result<ViewportState, CadError> {
+        if !(max_zoom > 0.0 && point_zoom > 0.0) {
+            return Err(CadError::InvalidInput(format!("zoom limits must be positive, got {} and {}", max_zoom, point_zoom)));
+        }
+        let Some(bounds) = target_bounds(doc, target)? else {
+            return Ok(self.state());
+        };
+        let pad = padding_px.max(0.0);
+        // At least a pixel across, however much padding is asked for
+        let (room_w, room_h) = ((self.width_px() - 2.0 * pad).max(1.0), (self.height_px() - 2.0 * pad).max(1.0));
+        let fit = |room: f64, size: f64| if size > 0.0 { room / size } else { f64::INFINITY };
+        let zoom = fit(room_w, bounds.width()).min(fit(room_h, bounds.height()));
+        let zoom = if zoom.is_finite() { zoom } else { point_zoom };
+        Ok(ViewportState { center: bounds.center(), zoom: zoom.min(max_zoom) })
+    }
+
+    // The view `t` of the way from here to `state`, eased in and out. Zoom
+    // moves at a steady rate in log space, so each frame scales the picture
+    // by the same factor. From t = 1 on this is exactly `state`.
+    pub fn animate_to(&self, state: &ViewportState, t: f64) -> Viewport {
+        if t >= 1.0 {
+            return self.with_state(state);
+        }
+        let s = t.max(0.0);
+        let s = s * s * (3.0 - 2.0 * s);
+        let from = self.state();
+        let lerp = |a: f64, b: f64| a + (b - a) * s;
+        self.with_state(&ViewportState {
+            center: Point::new(lerp(from.center.x, state.center.x), lerp(from.center.y, state.center.y)),
+            zoom: lerp(from.zoom.ln(), state.zoom.ln()).exp(),
+        })
+    }
+}
+
+#[wasm_bindgen]
+pub fn fit_viewport(doc: &Document, viewport: &JsValue, target: &JsValue, padding_px: f64, max_zoom: f64, point_zoom: f64) -> Result<JsValue, JsValue> {
+    let viewport: Viewport = crate::from_js(viewport)?;
+    let target: FitTarget = crate::from_js(target)?;
+    let state = viewport.fit_target(doc, &target, padding_px, max_zoom, point_zoom).map_err(crate::js_error)?;
+    crate::to_js(&state)
+}
+
+#[wasm_bindgen]
+pub fn animate_viewport(viewport: &JsValue, state: &JsValue, t: f64) -> Result<JsValue, JsValue> {
+    let viewport: Viewport = crate::from_js(viewport)?;
+    let state: ViewportState = crate::from_js(state)?;
+    crate::to_js(&viewport.animate_to(&state, t))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Polygon;
+
+    // A canvas of `w` by `h` pixels at unit zoom
+    fn canvas(w: f64, h: f64) -> Viewport {
+        Viewport { bounds: BoundingBox::new(Point::new(0.0, 0.0), Point::new(w, h)), scale: 1.0 }
+    }
+
+    #[test]
+    fn test_point_respects_max_zoom() {
+        let doc = Document::new();
+        let point = FitTarget::Bounds(BoundingBox::new(Point::new(3.0, 4.0), Point::new(3.0, 4.0)));
+        let state = canvas(800.0, 600.0).fit_target(&doc, &point, 20.0, 50.0, 10.0).unwrap();
+        assert_eq!(state, ViewportState { center: Point::new(3.0, 4.0), zoom: 10.0 });
+        let state = canvas(800.0, 600.0).fit_target(&doc, &point, 20.0, 5.0, 10.0).unwrap();
+        assert_eq!(state.zoom, 5.0);
+    }
+
+    #[test]
+    fn test_wide_shape_letterboxes() {
+        let mut doc = Document::new();
+        let id = doc.add_shape(Polygon::new(vec![Point::new(0.0, 0.0), Point::new(100.0, 0.0), Point::new(100.0, 2.0), Point::new(0.0, 2.0)]));
+        let target = FitTarget::Shapes(vec![id]);
+        for view in [canvas(800.0, 600.0), canvas(600.0, 800.0)] {
+            let state = view.fit_target(&doc, &target, 10.0, 1000.0, 1.0).unwrap();
+            // Width decides: the shape spans the canvas less padding
+            assert!((state.zoom - (view.width_px() - 20.0) / 100.0).abs() < 1e-12);
+            let fitted = view.with_state(&state);
+            assert!((fitted.bounds.min.x - (0.0 - 10.0 / state.zoom)).abs() < 1e-9);
+            assert!(fitted.bounds.height() > 2.0);
+            let c = fitted.bounds.center();
+            assert!((c.x - 50.0).abs() < 1e-9 && (c.y - 1.0).abs() < 1e-9);
+        }
+        assert!(canvas(800.0, 600.0).fit_target(&doc, &FitTarget::Shapes(vec![9]), 10.0, 1000.0, 1.0).is_err());
+    }
+
+    #[test]
+    fn test_animation_ends_on_destination() {
+        let view = canvas(800.0, 600.0);
+        let dest = ViewportState { center: Point::new(-250.0, 75.5), zoom: 37.0 };
+        assert_eq!(view.animate_to(&dest, 1.0), view.with_state(&dest));
+        assert_eq!(view.animate_to(&dest, 0.0), view.with_state(&view.state()));
+        // Halfway in log space is the geometric mean
+        let half = view.animate_to(&dest, 0.5);
+        assert!((half.scale - 37.0f64.sqrt()).abs() < 1e-9);
+    }
+}